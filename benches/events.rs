@@ -0,0 +1,89 @@
+//! Benchmarks of emitting and processing events without optional features enabled.
+//!
+//! These benchmarks track the cost of the default path, which should not be affected by the optional subsystems
+//! (routing, tracing, contracts, logical clocks, etc.) while they are disabled.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde::Serialize;
+
+use simcore::{Event, EventHandler, Id, Simulation, SimulationContext};
+
+const CLIENTS_COUNT: usize = 100;
+const EVENTS_COUNT: usize = 100_000;
+
+#[derive(Clone, Serialize)]
+struct Message {}
+
+struct Client {
+    messages_received: u64,
+}
+
+impl EventHandler for Client {
+    fn on(&mut self, _event: Event) {
+        self.messages_received += 1;
+    }
+}
+
+fn setup() -> (Simulation, SimulationContext, Vec<Id>) {
+    let mut sim = Simulation::new(123);
+    let clients = (0..CLIENTS_COUNT)
+        .map(|i| {
+            let client = Rc::new(RefCell::new(Client { messages_received: 0 }));
+            sim.add_handler(format!("client_{}", i), client)
+        })
+        .collect();
+    let server = sim.create_context("server");
+    (sim, server, clients)
+}
+
+fn emit_events(server: &SimulationContext, clients: &[Id], ordered: bool) {
+    for i in 0..EVENTS_COUNT {
+        let dst = clients[i % clients.len()];
+        let delay = 1. + i as f64;
+        if ordered {
+            server.emit_ordered(Message {}, dst, delay);
+        } else {
+            server.emit(Message {}, dst, delay);
+        }
+    }
+}
+
+fn bench_emit(c: &mut Criterion) {
+    for (name, ordered) in [("emit", false), ("emit_ordered", true)] {
+        c.bench_function(name, |b| {
+            b.iter_batched(
+                setup,
+                |(sim, server, clients)| {
+                    emit_events(&server, &clients, ordered);
+                    sim
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn bench_step(c: &mut Criterion) {
+    for (name, ordered) in [("step", false), ("step_ordered", true)] {
+        c.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let (sim, server, clients) = setup();
+                    emit_events(&server, &clients, ordered);
+                    sim
+                },
+                |mut sim| {
+                    sim.step_until_no_events();
+                    sim
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, bench_emit, bench_step);
+criterion_main!(benches);
@@ -27,6 +27,10 @@ struct Args {
     #[clap(long)]
     use_emit_ordered: bool,
 
+    /// Arity of the heap storing pending events (>= 2)
+    #[clap(long, default_value_t = 2)]
+    heap_arity: usize,
+
     /// Number of events (>= 1)
     #[clap(long, default_value_t = 100)]
     events_count: u64,
@@ -40,6 +44,7 @@ fn main() {
     let args = Args::parse();
 
     let mut sim = Simulation::new(123);
+    sim.set_event_heap_arity(args.heap_arity);
 
     let mut clients = vec![];
     let mut clients_ids = vec![];
@@ -0,0 +1,400 @@
+//! Derive macros for [SimCore](https://docs.rs/simcore) simulation framework.
+//!
+//! The macros are re-exported by `simcore` crate when its `derive` feature is enabled and should be used via these
+//! re-exports.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, FnArg, GenericArgument, Ident, ImplItem, ItemImpl, ItemTrait, Pat,
+    PathArguments, ReceiverKind, ReturnType, Signature, TraitItem, Type,
+};
+
+/// Implements `simcore::async_mode::KeyedEvent` for a struct using the field marked with `#[event_key]` attribute.
+///
+/// If a single field is marked, it must have an integer type, which is converted to `EventKey` via `as`.
+/// If several fields are marked, the key is computed via `simcore::async_mode::composite_key` from the tuple of
+/// their values in the order of declaration.
+#[proc_macro_derive(EventKey, attributes(event_key))]
+pub fn derive_event_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_event_key(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand_event_key(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "EventKey can only be derived for structs",
+        ));
+    };
+    let mut key_fields = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        if field.attrs.iter().any(|attr| attr.path().is_ident("event_key")) {
+            let member = match &field.ident {
+                Some(ident) => quote!(#ident),
+                None => {
+                    let index = syn::Index::from(i);
+                    quote!(#index)
+                }
+            };
+            key_fields.push(member);
+        }
+    }
+    let key = match key_fields.as_slice() {
+        [] => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "EventKey requires at least one field marked with #[event_key]",
+            ));
+        }
+        [member] => quote! {
+            self.#member as ::simcore::async_mode::EventKey
+        },
+        members => {
+            quote! {
+                ::simcore::async_mode::composite_key(&(#(&self.#members,)*))
+            }
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::simcore::async_mode::KeyedEvent for #name #ty_generics #where_clause {
+            fn event_key(&self) -> ::simcore::async_mode::EventKey {
+                #key
+            }
+        }
+    })
+}
+
+/// Implements `simcore::EventHandler` for a type by dispatching events to its methods by event type.
+///
+/// The attribute is applied to an `impl` block of the component. Each method with `&mut self` receiver and a single
+/// argument of type `TypedEvent<T>` becomes a handler of events with payload type `T`. Other methods are left intact.
+/// The events of other types are logged as unhandled. Handling the same type by several methods is a compile error.
+#[proc_macro_attribute]
+pub fn event_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return Error::new_spanned(attr, "event_handler attribute does not accept arguments")
+            .into_compile_error()
+            .into();
+    }
+    let input = parse_macro_input!(item as ItemImpl);
+    expand_event_handler(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_event_handler(input: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some((path, _)) = &input.trait_ {
+        return Err(Error::new_spanned(
+            path,
+            "event_handler must be applied to inherent impl block",
+        ));
+    }
+    let mut handlers: Vec<(&Ident, &Type)> = Vec::new();
+    for item in input.items.iter() {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let Some(event_type) = handled_event_type(&method.sig) else {
+            continue;
+        };
+        let type_name = event_type.to_token_stream().to_string();
+        if let Some((other, _)) = handlers
+            .iter()
+            .find(|(_, ty)| ty.to_token_stream().to_string() == type_name)
+        {
+            return Err(Error::new_spanned(
+                event_type,
+                format!("Events of this type are already handled by method `{}`", other),
+            ));
+        }
+        handlers.push((&method.sig.ident, event_type));
+    }
+    if handlers.is_empty() {
+        return Err(Error::new_spanned(
+            &input.self_ty,
+            "event_handler requires at least one method with `&mut self` and `TypedEvent<T>` arguments",
+        ));
+    }
+
+    let self_ty = &input.self_ty;
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+    let branches = handlers.iter().map(|(method, ty)| {
+        quote! {
+            if event.data.is::<#ty>() {
+                self.#method(::simcore::Event::downcast::<#ty>(event));
+            } else
+        }
+    });
+    Ok(quote! {
+        #input
+
+        impl #impl_generics ::simcore::EventHandler for #self_ty #where_clause {
+            fn on(&mut self, event: ::simcore::Event) {
+                #(#branches)* {
+                    ::simcore::log::log_unhandled_event(event);
+                }
+            }
+        }
+    })
+}
+
+// Returns the payload type T if the method has signature `fn(&mut self, _: TypedEvent<T>)`.
+fn handled_event_type(sig: &Signature) -> Option<&Type> {
+    if sig.inputs.len() != 2 {
+        return None;
+    }
+    let FnArg::Receiver(receiver) = &sig.inputs[0] else {
+        return None;
+    };
+    if !matches!(receiver.kind, ReceiverKind::Reference(_, _, Some(_))) {
+        return None;
+    }
+    let FnArg::Typed(arg) = &sig.inputs[1] else {
+        return None;
+    };
+    let Type::Path(path) = arg.ty.as_ref() else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "TypedEvent" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty),
+        _ => None,
+    }
+}
+
+/// Generates client and server glue for a trait of async request methods.
+///
+/// The attribute is applied to a trait whose methods have `async fn(&self, ...)` signature with simple argument
+/// names. For a trait `Foo` it generates the following items with the visibility of the trait:
+///
+/// - `FooRequest` enum with a variant per method holding its arguments,
+/// - `FooResponse` enum with a variant per method holding its return value,
+/// - `FooClient` which sends requests via `SimulationContext::request` and returns
+///   `Result<T, simcore::async_mode::RequestTimeout>`,
+/// - `FooServer<S>` implementing `simcore::StaticEventHandler`, which calls the methods of `S: Foo` in spawned
+///   tasks and replies with their results.
+///
+/// The argument and return types must implement `Clone` and `Serialize`.
+#[proc_macro_attribute]
+pub fn rpc(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return Error::new_spanned(attr, "rpc attribute does not accept arguments")
+            .into_compile_error()
+            .into();
+    }
+    let input = parse_macro_input!(item as ItemTrait);
+    expand_rpc(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+struct RpcMethod<'a> {
+    name: &'a Ident,
+    variant: Ident,
+    args: Vec<(&'a Ident, &'a Type)>,
+    output: proc_macro2::TokenStream,
+}
+
+fn expand_rpc(input: ItemTrait) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() || input.generics.where_clause.is_some() {
+        return Err(Error::new_spanned(&input.generics, "rpc trait must not be generic"));
+    }
+    let mut methods = Vec::new();
+    for item in input.items.iter() {
+        let TraitItem::Fn(method) = item else {
+            return Err(Error::new_spanned(item, "rpc trait can contain only async methods"));
+        };
+        methods.push(rpc_method(&method.sig)?);
+    }
+    if methods.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "rpc trait requires at least one async method",
+        ));
+    }
+
+    let vis = &input.vis;
+    let trait_name = &input.ident;
+    let request = format_ident!("{}Request", trait_name);
+    let response = format_ident!("{}Response", trait_name);
+    let client = format_ident!("{}Client", trait_name);
+    let server = format_ident!("{}Server", trait_name);
+
+    let request_variants = methods.iter().map(|m| {
+        let variant = &m.variant;
+        let (names, types): (Vec<_>, Vec<_>) = m.args.iter().cloned().unzip();
+        quote!(#variant { #(#names: #types,)* })
+    });
+    let response_variants = methods.iter().map(|m| {
+        let variant = &m.variant;
+        let output = &m.output;
+        quote!(#variant(#output))
+    });
+    let client_methods = methods.iter().map(|m| {
+        let name = m.name;
+        let variant = &m.variant;
+        let output = &m.output;
+        let method_name = name.to_string();
+        let (names, types): (Vec<_>, Vec<_>) = m.args.iter().cloned().unzip();
+        quote! {
+            #[doc = concat!("Calls `", #method_name, "` method of the server.")]
+            pub async fn #name(&self, #(#names: #types),*) -> ::std::result::Result<#output, ::simcore::async_mode::RequestTimeout> {
+                let request = #request::#variant { #(#names,)* };
+                match self.ctx.request::<#request, #response>(self.server, request, self.timeout).await {
+                    ::simcore::async_mode::AwaitResult::Ok(response) => match response.data.data {
+                        #response::#variant(result) => ::std::result::Result::Ok(result),
+                        #[allow(unreachable_patterns)]
+                        _ => ::std::unreachable!("Unexpected response to {} request", #method_name),
+                    },
+                    ::simcore::async_mode::AwaitResult::Timeout { .. } => {
+                        ::std::result::Result::Err(::simcore::async_mode::RequestTimeout {
+                            server: self.server,
+                            method: #method_name,
+                            timeout: self.timeout,
+                        })
+                    }
+                }
+            }
+        }
+    });
+    let server_branches = methods.iter().map(|m| {
+        let name = m.name;
+        let variant = &m.variant;
+        let names: Vec<_> = m.args.iter().map(|(name, _)| name).collect();
+        quote! {
+            #request::#variant { #(#names,)* } => #response::#variant(server.service.#name(#(#names),*).await),
+        }
+    });
+
+    let client_doc = format!("Client of [`{}`] service generated by `rpc` macro.", trait_name);
+    let server_doc = format!("Server of [`{}`] service generated by `rpc` macro.", trait_name);
+    let request_doc = format!("Requests of [`{}`] service.", trait_name);
+    let response_doc = format!("Responses of [`{}`] service.", trait_name);
+    Ok(quote! {
+        #[allow(async_fn_in_trait)]
+        #input
+
+        #[doc = #request_doc]
+        #[derive(Clone, ::simcore::serde::Serialize)]
+        #[serde(crate = "::simcore::serde")]
+        #[allow(missing_docs)]
+        #vis enum #request {
+            #(#request_variants,)*
+        }
+
+        #[doc = #response_doc]
+        #[derive(Clone, ::simcore::serde::Serialize)]
+        #[serde(crate = "::simcore::serde")]
+        #[allow(missing_docs)]
+        #vis enum #response {
+            #(#response_variants,)*
+        }
+
+        #[doc = #client_doc]
+        #vis struct #client<'a> {
+            ctx: &'a ::simcore::SimulationContext,
+            server: ::simcore::Id,
+            timeout: f64,
+        }
+
+        impl<'a> #client<'a> {
+            /// Creates a client sending requests to the specified server via the context of calling component.
+            pub fn new(ctx: &'a ::simcore::SimulationContext, server: ::simcore::Id, timeout: f64) -> Self {
+                Self { ctx, server, timeout }
+            }
+
+            #(#client_methods)*
+        }
+
+        #[doc = #server_doc]
+        #vis struct #server<S> {
+            ctx: ::simcore::SimulationContext,
+            service: ::std::rc::Rc<S>,
+        }
+
+        impl<S: #trait_name + 'static> #server<S> {
+            /// Creates a server serving requests received by the context component via the service implementation.
+            pub fn new(ctx: ::simcore::SimulationContext, service: ::std::rc::Rc<S>) -> Self {
+                Self { ctx, service }
+            }
+        }
+
+        impl<S: #trait_name + 'static> ::simcore::StaticEventHandler for #server<S> {
+            fn on(self: ::std::rc::Rc<Self>, event: ::simcore::Event) {
+                if event.data.is::<::simcore::async_mode::Request<#request>>() {
+                    let request = ::simcore::Event::downcast::<::simcore::async_mode::Request<#request>>(event);
+                    let server = self.clone();
+                    self.ctx.spawn(async move {
+                        let response = match request.data.data.clone() {
+                            #(#server_branches)*
+                        };
+                        server.ctx.reply(&request, response, 0.);
+                    });
+                } else {
+                    ::simcore::log::log_unhandled_event(event);
+                }
+            }
+        }
+    })
+}
+
+fn rpc_method(sig: &Signature) -> syn::Result<RpcMethod<'_>> {
+    if sig.asyncness.is_none() {
+        return Err(Error::new_spanned(sig, "rpc methods must be async"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(&sig.generics, "rpc methods must not be generic"));
+    }
+    let is_ref_self = matches!(
+        sig.inputs.first(),
+        Some(FnArg::Receiver(receiver)) if matches!(receiver.kind, ReceiverKind::Reference(_, _, None))
+    );
+    if !is_ref_self {
+        return Err(Error::new_spanned(sig, "rpc methods must have `&self` receiver"));
+    }
+    let mut args = Vec::new();
+    for arg in sig.inputs.iter().skip(1) {
+        let FnArg::Typed(arg) = arg else {
+            unreachable!("receiver can only be the first argument");
+        };
+        let Pat::Ident(pat) = arg.pat.as_ref() else {
+            return Err(Error::new_spanned(
+                &arg.pat,
+                "rpc method arguments must be simple identifiers",
+            ));
+        };
+        args.push((&pat.ident, arg.ty.as_ref()));
+    }
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => ty.to_token_stream(),
+    };
+    Ok(RpcMethod {
+        name: &sig.ident,
+        variant: Ident::new(&to_camel_case(&sig.ident.to_string()), sig.ident.span()),
+        args,
+        output,
+    })
+}
+
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap();
+            first.to_uppercase().chain(chars).collect::<String>()
+        })
+        .collect()
+}
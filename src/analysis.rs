@@ -0,0 +1,243 @@
+//! Comparison of metrics between runs.
+//!
+//! Simulation experiments frequently compare two configurations, e.g. scheduling policies, by running each of them
+//! one or more times (replications) and comparing the resulting metrics. This module provides
+//! [`compare_runs`] which produces a structured [`ComparisonReport`] with the difference of metric means and
+//! the significance of this difference estimated by Welch's t-test.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::special::{t_quantile, t_two_sided_tail};
+
+/// Values of named metrics produced by a single run.
+pub type RunMetrics = BTreeMap<String, f64>;
+
+/// Summary statistics of metric values over a set of runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleStats {
+    /// Number of values.
+    pub count: usize,
+    /// Mean value.
+    pub mean: f64,
+    /// Sample standard deviation (zero for a single value).
+    pub std_dev: f64,
+}
+
+impl SampleStats {
+    /// Computes the statistics of the specified values.
+    ///
+    /// Panics if `values` is empty.
+    pub fn from_values(values: &[f64]) -> Self {
+        assert!(!values.is_empty(), "Values must not be empty");
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let std_dev = if count > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt()
+        } else {
+            0.
+        };
+        Self { count, mean, std_dev }
+    }
+
+    /// Returns the sample variance (zero for a single value).
+    pub fn variance(&self) -> f64 {
+        self.std_dev.powi(2)
+    }
+
+    /// Returns the half-width of the confidence interval for the mean at the specified confidence level, e.g. 0.95,
+    /// computed using Student's t-distribution.
+    ///
+    /// Returns `None` if there are less than two values.
+    ///
+    /// Panics if the confidence level is not in the range _(0, 1)_.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::analysis::SampleStats;
+    ///
+    /// let stats = SampleStats::from_values(&[9., 10., 11., 10.]);
+    /// let half_width = stats.confidence_half_width(0.95).unwrap();
+    /// assert!((half_width - 1.2992).abs() < 1e-4);
+    /// assert_eq!(SampleStats::from_values(&[1.]).confidence_half_width(0.95), None);
+    /// ```
+    pub fn confidence_half_width(&self, confidence: f64) -> Option<f64> {
+        assert!(
+            confidence > 0. && confidence < 1.,
+            "Confidence level must be in the range (0, 1)"
+        );
+        if self.count < 2 {
+            return None;
+        }
+        let t = t_quantile(1. - (1. - confidence) / 2., (self.count - 1) as f64);
+        Some(t * self.std_dev / (self.count as f64).sqrt())
+    }
+}
+
+/// Comparison of a single metric between two sets of runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricComparison {
+    /// Metric name.
+    pub name: String,
+    /// Statistics of the metric in baseline runs.
+    pub baseline: SampleStats,
+    /// Statistics of the metric in candidate runs.
+    pub candidate: SampleStats,
+    /// Difference of means (candidate minus baseline).
+    pub delta: f64,
+    /// Difference of means relative to the baseline mean, `None` if the baseline mean is zero.
+    pub relative_delta: Option<f64>,
+    /// Two-sided p-value of Welch's t-test for the difference of means,
+    /// `None` if any of the run sets has less than two values.
+    pub p_value: Option<f64>,
+}
+
+impl MetricComparison {
+    /// Returns true if the difference is significant at the specified significance level.
+    ///
+    /// Returns false if the p-value is not available.
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value.is_some_and(|p| p < alpha)
+    }
+}
+
+/// Result of comparing metrics between two sets of runs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComparisonReport {
+    /// Comparisons of metrics present in both run sets, ordered by metric name.
+    pub metrics: Vec<MetricComparison>,
+    /// Names of metrics present only in baseline runs.
+    pub baseline_only: Vec<String>,
+    /// Names of metrics present only in candidate runs.
+    pub candidate_only: Vec<String>,
+}
+
+impl ComparisonReport {
+    /// Returns the comparison of metric with the specified name.
+    pub fn get(&self, name: &str) -> Option<&MetricComparison> {
+        self.metrics.iter().find(|m| m.name == name)
+    }
+
+    /// Returns the comparisons with significant difference at the specified significance level.
+    pub fn significant(&self, alpha: f64) -> impl Iterator<Item = &MetricComparison> {
+        self.metrics.iter().filter(move |m| m.is_significant(alpha))
+    }
+}
+
+impl Display for ComparisonReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self.metrics.iter().map(|m| m.name.len()).max().unwrap_or(0).max(6);
+        writeln!(
+            f,
+            "{:<width$}  {:>12}  {:>12}  {:>12}  {:>9}  {:>8}",
+            "metric", "baseline", "candidate", "delta", "delta %", "p-value"
+        )?;
+        for m in &self.metrics {
+            let relative_delta = m.relative_delta.map_or("-".to_owned(), |d| format!("{:+.2}", d * 100.));
+            let p_value = m.p_value.map_or("-".to_owned(), |p| format!("{:.4}", p));
+            writeln!(
+                f,
+                "{:<width$}  {:>12.4}  {:>12.4}  {:>+12.4}  {:>9}  {:>8}",
+                m.name, m.baseline.mean, m.candidate.mean, m.delta, relative_delta, p_value
+            )?;
+        }
+        for name in &self.baseline_only {
+            writeln!(f, "{:<width$}  only in baseline", name)?;
+        }
+        for name in &self.candidate_only {
+            writeln!(f, "{:<width$}  only in candidate", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares metrics of baseline and candidate run sets.
+///
+/// Each run set consists of metrics produced by one or more runs, e.g. replications with different seeds.
+/// A metric is compared if it is present in at least one run of each set.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::analysis::{compare_runs, RunMetrics};
+///
+/// fn run(latency: f64, throughput: f64) -> RunMetrics {
+///     RunMetrics::from([("latency".to_owned(), latency), ("throughput".to_owned(), throughput)])
+/// }
+///
+/// let baseline = [run(10.2, 100.), run(9.8, 101.), run(10.1, 99.)];
+/// let candidate = [run(8.1, 100.), run(7.9, 99.), run(8.2, 101.)];
+/// let report = compare_runs(&baseline, &candidate);
+///
+/// let latency = report.get("latency").unwrap();
+/// assert!((latency.delta + 1.966).abs() < 1e-3);
+/// assert!(latency.is_significant(0.01));
+/// let throughput = report.get("throughput").unwrap();
+/// assert!(!throughput.is_significant(0.05));
+/// println!("{}", report);
+/// ```
+pub fn compare_runs(baseline: &[RunMetrics], candidate: &[RunMetrics]) -> ComparisonReport {
+    let baseline = collect_values(baseline);
+    let candidate = collect_values(candidate);
+    let mut report = ComparisonReport::default();
+    for (name, values) in baseline.iter() {
+        match candidate.get(name) {
+            Some(candidate_values) => report.metrics.push(compare_values(name, values, candidate_values)),
+            None => report.baseline_only.push(name.to_string()),
+        }
+    }
+    report.candidate_only = candidate
+        .keys()
+        .filter(|name| !baseline.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    report
+}
+
+pub(crate) fn collect_values(runs: &[RunMetrics]) -> BTreeMap<&str, Vec<f64>> {
+    let mut values: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for run in runs {
+        for (name, value) in run {
+            values.entry(name.as_str()).or_default().push(*value);
+        }
+    }
+    values
+}
+
+fn compare_values(name: &str, baseline: &[f64], candidate: &[f64]) -> MetricComparison {
+    let baseline = SampleStats::from_values(baseline);
+    let candidate = SampleStats::from_values(candidate);
+    let delta = candidate.mean - baseline.mean;
+    let relative_delta = if baseline.mean != 0. {
+        Some(delta / baseline.mean.abs())
+    } else {
+        None
+    };
+    let p_value = welch_t_test(&baseline, &candidate);
+    MetricComparison {
+        name: name.to_owned(),
+        baseline,
+        candidate,
+        delta,
+        relative_delta,
+        p_value,
+    }
+}
+
+// Returns the two-sided p-value of Welch's t-test.
+fn welch_t_test(a: &SampleStats, b: &SampleStats) -> Option<f64> {
+    if a.count < 2 || b.count < 2 {
+        return None;
+    }
+    let var_a = a.std_dev.powi(2) / a.count as f64;
+    let var_b = b.std_dev.powi(2) / b.count as f64;
+    let var = var_a + var_b;
+    if var == 0. {
+        // both samples are constant
+        return Some(if a.mean == b.mean { 1. } else { 0. });
+    }
+    let t = (b.mean - a.mean) / var.sqrt();
+    let df = var.powi(2) / (var_a.powi(2) / (a.count - 1) as f64 + var_b.powi(2) / (b.count - 1) as f64);
+    Some(t_two_sided_tail(t, df))
+}
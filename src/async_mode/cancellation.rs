@@ -0,0 +1,268 @@
+//! Cancellation of groups of asynchronous tasks.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+
+use futures::future::{select, Either};
+
+use crate::component::Id;
+use crate::state::SimulationState;
+
+struct TokenState {
+    cancelled: bool,
+    // Wakers of pending CancelledFuture instances with their identifiers.
+    waiters: Vec<(u64, Waker)>,
+    waiter_count: u64,
+    children: Vec<Weak<RefCell<TokenState>>>,
+}
+
+/// Token used for cancelling a group of asynchronous activities, e.g. the tasks serving a request.
+///
+/// The token is cancelled by calling [`cancel`](Self::cancel) on any of its clones. The cancellation is observed by
+/// awaiting [`cancelled`](Self::cancelled), by [`run_until_cancelled`](Self::run_until_cancelled) and by the tasks
+/// spawned via [`TaskScope`]. The tokens can be organized in a tree via [`child_token`](Self::child_token), so that
+/// cancelling a token cancels all its descendants, which is useful for modeling cancellation of nested calls.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::async_mode::CancellationToken;
+/// use simcore::Simulation;
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("client");
+/// let token = CancellationToken::new();
+/// let child = token.child_token();
+///
+/// let request_token = token.clone();
+/// sim.spawn(async move {
+///     let result = request_token.run_until_cancelled(ctx.sleep(10.)).await;
+///     assert!(result.is_none());
+///     assert_eq!(ctx.time(), 0.);
+/// });
+///
+/// token.cancel();
+/// assert!(child.is_cancelled());
+/// sim.step_until_no_events();
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    state: Rc<RefCell<TokenState>>,
+}
+
+impl CancellationToken {
+    /// Creates a new token which is not cancelled.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(TokenState {
+                cancelled: false,
+                waiters: Vec::new(),
+                waiter_count: 0,
+                children: Vec::new(),
+            })),
+        }
+    }
+
+    /// Creates a child token which is cancelled when this token is cancelled.
+    ///
+    /// Cancelling the child token does not affect this token.
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+        let mut state = self.state.borrow_mut();
+        if state.cancelled {
+            child.state.borrow_mut().cancelled = true;
+        } else {
+            state.children.retain(|child| child.strong_count() > 0);
+            state.children.push(Rc::downgrade(&child.state));
+        }
+        child
+    }
+
+    /// Cancels the token and its descendants and wakes the tasks awaiting the cancellation.
+    ///
+    /// The awaiting tasks are resumed in the order in which they started waiting. Cancelling an already
+    /// cancelled token has no effect.
+    pub fn cancel(&self) {
+        let mut wakers = Vec::new();
+        Self::cancel_state(&self.state, &mut wakers);
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn cancel_state(state: &Rc<RefCell<TokenState>>, wakers: &mut Vec<Waker>) {
+        let children = {
+            let mut state = state.borrow_mut();
+            if state.cancelled {
+                return;
+            }
+            state.cancelled = true;
+            wakers.extend(state.waiters.drain(..).map(|(_, waker)| waker));
+            std::mem::take(&mut state.children)
+        };
+        for child in children.iter().filter_map(|child| child.upgrade()) {
+            Self::cancel_state(&child, wakers);
+        }
+    }
+
+    /// Returns true if the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.borrow().cancelled
+    }
+
+    /// Returns a future which completes when the token is cancelled.
+    pub fn cancelled(&self) -> CancelledFuture {
+        let id = {
+            let mut state = self.state.borrow_mut();
+            state.waiter_count += 1;
+            state.waiter_count
+        };
+        CancelledFuture {
+            id,
+            state: self.state.clone(),
+        }
+    }
+
+    /// Runs the future until it completes or the token is cancelled.
+    ///
+    /// Returns the future output or `None` if the token was cancelled first, in which case the future is dropped.
+    pub async fn run_until_cancelled<T>(&self, future: impl Future<Output = T>) -> Option<T> {
+        if self.is_cancelled() {
+            return None;
+        }
+        match select(pin!(future), self.cancelled()).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+}
+
+/// Future that represents asynchronous waiting for cancellation of [`CancellationToken`].
+pub struct CancelledFuture {
+    id: u64,
+    state: Rc<RefCell<TokenState>>,
+}
+
+impl Future for CancelledFuture {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+        let waker = async_ctx.waker().clone();
+        if let Some(entry) = state.waiters.iter_mut().find(|(id, _)| *id == self.id) {
+            entry.1 = waker;
+        } else {
+            state.waiters.push((self.id, waker));
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for CancelledFuture {
+    fn drop(&mut self) {
+        // Take the waker out and drop it when the state borrow is released
+        let _waker = {
+            let mut state = self.state.borrow_mut();
+            let idx = state.waiters.iter().position(|(id, _)| *id == self.id);
+            idx.map(|idx| state.waiters.remove(idx))
+        };
+    }
+}
+
+/// Scope for spawning component tasks which are cancelled together when its token is cancelled.
+///
+/// The cancelled tasks are dropped at their current await point, which cancels their pending waiting for events and
+/// timers. The scope can be cloned and passed to helper tasks, which can spawn further tasks in the same scope or in
+/// a [nested scope](Self::child_scope) which can be cancelled separately.
+///
+/// Scope is created via [`SimulationContext::scope_with_token`](crate::SimulationContext::scope_with_token).
+///
+/// # Examples
+///
+/// ```rust
+/// use std::rc::Rc;
+/// use simcore::async_mode::CancellationToken;
+/// use simcore::{Event, Simulation, SimulationContext, StaticEventHandler};
+///
+/// struct Server {
+///     ctx: SimulationContext,
+/// }
+///
+/// impl StaticEventHandler for Server {
+///     fn on(self: Rc<Self>, _event: Event) {}
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let server = Rc::new(Server { ctx: sim.create_context("server") });
+/// sim.add_static_handler("server", server.clone());
+///
+/// // serve a request with several helper tasks
+/// let token = CancellationToken::new();
+/// let scope = server.ctx.scope_with_token(token.clone());
+/// for delay in [5., 10., 20.] {
+///     let server = server.clone();
+///     let nested_scope = scope.clone();
+///     scope.spawn(async move {
+///         server.ctx.sleep(delay).await;
+///         nested_scope.spawn(async move {
+///             panic!("The request is cancelled before");
+///         });
+///     });
+/// }
+///
+/// // cancel the request after 3 seconds
+/// let canceller = sim.create_context("canceller");
+/// sim.spawn(async move {
+///     canceller.sleep(3.).await;
+///     token.cancel();
+/// });
+///
+/// sim.step_until_no_events();
+/// assert_eq!(sim.time(), 3.);
+/// ```
+#[derive(Clone)]
+pub struct TaskScope {
+    component_id: Id,
+    token: CancellationToken,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+impl TaskScope {
+    pub(crate) fn new(component_id: Id, token: CancellationToken, sim_state: Rc<RefCell<SimulationState>>) -> Self {
+        Self {
+            component_id,
+            token,
+            sim_state,
+        }
+    }
+
+    /// Returns the token of the scope.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Creates a nested scope with the [child token](CancellationToken::child_token) of this scope token.
+    pub fn child_scope(&self) -> Self {
+        Self::new(self.component_id, self.token.child_token(), self.sim_state.clone())
+    }
+
+    /// Spawns a component task which is cancelled when the scope token is cancelled.
+    ///
+    /// The task is not spawned if the token is already cancelled.
+    /// See [`SimulationContext::spawn`](crate::SimulationContext::spawn).
+    pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
+        if self.token.is_cancelled() {
+            return;
+        }
+        let token = self.token.clone();
+        self.sim_state.borrow_mut().spawn_component(self.component_id, async move {
+            token.run_until_cancelled(future).await;
+        });
+    }
+}
@@ -0,0 +1,174 @@
+//! Detection of deadlocks between asynchronous tasks.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+use crate::component::Id;
+
+use super::promise_store::PendingWait;
+use super::EventKey;
+
+/// Wait for event which can never complete, because the simulation has no pending events left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockedWait {
+    /// Identifier of the waiting component.
+    pub component: Id,
+    /// Name of the waiting component.
+    pub component_name: String,
+    /// Name of the awaited event type.
+    pub event_type: &'static str,
+    /// Identifier of the awaited event source, if it is specified.
+    pub src: Option<Id>,
+    /// Name of the awaited event source, if it is specified.
+    pub src_name: Option<String>,
+    /// Key of the awaited event, if it is specified.
+    pub event_key: Option<EventKey>,
+}
+
+impl Display for BlockedWait {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} waits for {}", self.component_name, self.event_type)?;
+        if let Some(key) = self.event_key {
+            write!(f, " with key {}", key)?;
+        }
+        if let Some(src_name) = &self.src_name {
+            write!(f, " from {}", src_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Report of a deadlock between asynchronous tasks, see
+/// [`Simulation::detect_deadlock`](crate::Simulation::detect_deadlock).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeadlockReport {
+    /// Cycles of components waiting for events from each other.
+    ///
+    /// Each cycle is described by the waits between its components, ordered by the identifier of waiting component.
+    pub cycles: Vec<Vec<BlockedWait>>,
+    /// All blocked waits in the order of their creation, including the waits outside cycles.
+    pub blocked: Vec<BlockedWait>,
+}
+
+impl DeadlockReport {
+    // Builds the report from the pending waits, returns None if there are no cycles.
+    pub(crate) fn detect(waits: Vec<PendingWait>, lookup_name: impl Fn(Id) -> String) -> Option<Self> {
+        let blocked: Vec<BlockedWait> = waits
+            .into_iter()
+            .map(|wait| BlockedWait {
+                component: wait.dst,
+                component_name: lookup_name(wait.dst),
+                event_type: wait.type_name,
+                src: wait.src,
+                src_name: wait.src.map(&lookup_name),
+                event_key: wait.event_key,
+            })
+            .collect();
+
+        // wait-for graph with edges from waiting component to the source of awaited event
+        let mut graph: BTreeMap<Id, BTreeSet<Id>> = BTreeMap::new();
+        for wait in blocked.iter() {
+            if let Some(src) = wait.src {
+                graph.entry(wait.component).or_default().insert(src);
+            }
+        }
+
+        let mut cycles = Vec::new();
+        for component_set in strongly_connected_components(&graph) {
+            let is_cycle = component_set.len() > 1 || {
+                let id = *component_set.iter().next().unwrap();
+                graph.get(&id).is_some_and(|next| next.contains(&id))
+            };
+            if !is_cycle {
+                continue;
+            }
+            let mut cycle: Vec<BlockedWait> = blocked
+                .iter()
+                .filter(|wait| {
+                    component_set.contains(&wait.component) && wait.src.is_some_and(|src| component_set.contains(&src))
+                })
+                .cloned()
+                .collect();
+            cycle.sort_by_key(|wait| wait.component);
+            cycles.push(cycle);
+        }
+
+        if cycles.is_empty() {
+            None
+        } else {
+            Some(Self { cycles, blocked })
+        }
+    }
+}
+
+impl Display for DeadlockReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Deadlock detected with {} cycle(s):", self.cycles.len())?;
+        for cycle in self.cycles.iter() {
+            let waits: Vec<String> = cycle.iter().map(|wait| wait.to_string()).collect();
+            write!(f, " [{}]", waits.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Returns strongly connected components of the graph using Tarjan's algorithm, ordered by the minimal node.
+fn strongly_connected_components(graph: &BTreeMap<Id, BTreeSet<Id>>) -> Vec<BTreeSet<Id>> {
+    struct Tarjan<'a> {
+        graph: &'a BTreeMap<Id, BTreeSet<Id>>,
+        index: BTreeMap<Id, usize>,
+        low_link: BTreeMap<Id, usize>,
+        stack: Vec<Id>,
+        on_stack: BTreeSet<Id>,
+        components: Vec<BTreeSet<Id>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: Id) {
+            let index = self.index.len();
+            self.index.insert(node, index);
+            self.low_link.insert(node, index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+            for &next in self.graph.get(&node).into_iter().flatten() {
+                if !self.index.contains_key(&next) {
+                    self.visit(next);
+                    let low = self.low_link[&node].min(self.low_link[&next]);
+                    self.low_link.insert(node, low);
+                } else if self.on_stack.contains(&next) {
+                    let low = self.low_link[&node].min(self.index[&next]);
+                    self.low_link.insert(node, low);
+                }
+            }
+            if self.low_link[&node] == self.index[&node] {
+                let mut component = BTreeSet::new();
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack.remove(&member);
+                    component.insert(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index: BTreeMap::new(),
+        low_link: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+    for &node in graph.keys() {
+        if !tarjan.index.contains_key(&node) {
+            tarjan.visit(node);
+        }
+    }
+    let mut components = tarjan.components;
+    components.sort_by_key(|component| *component.iter().next().unwrap());
+    components
+}
@@ -1,19 +1,86 @@
 //! Asynchronous waiting for events.
 
+use std::any::type_name;
 use std::cell::RefCell;
 use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll, Waker};
 
 use futures::{select, FutureExt};
 
+use crate::async_mode::wait_stats::WaitSite;
 use crate::state::SimulationState;
-use crate::{Event, EventData, Id, TypedEvent};
+use crate::{Event, EventData, EventId, Id, TypedEvent};
 
 /// Type of key that represents the specific details of awaited event.
 pub type EventKey = u64;
 
+/// Computes the event key from the composite key, e.g. a tuple of connection id and sequence number.
+///
+/// The key is obtained by hashing the value with a 64-bit hash function, so unlike packing several identifiers into
+/// a single integer by hand, it uses all bits of each identifier. The same value must be passed to both the key getter
+/// and the receiving method, including the types of tuple elements, i.e. `(1u32, 2u64)` and `(1u64, 2u64)` produce
+/// different keys. The key getters returning composite keys can be registered via
+/// [`Simulation::register_composite_key_getter_for`](crate::Simulation::register_composite_key_getter_for).
+///
+/// Since the values are hashed, different values can produce the same key, and then the event with one value
+/// can be received by the future awaiting the other one. Such collisions are not detected, but their probability
+/// among `n` distinct values is about `n^2 / 2^65`, e.g. below `10^-7` for a million values. If the identifiers fit
+/// into 64 bits together, e.g. two `u32` values, packing them into the key via
+/// [`Simulation::register_key_getter_for`](crate::Simulation::register_key_getter_for) avoids the collisions.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::async_mode::composite_key;
+/// use simcore::Simulation;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Segment {
+///     conn_id: u32,
+///     seq_no: u64,
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let sender_ctx = sim.create_context("sender");
+/// let receiver_ctx = sim.create_context("receiver");
+/// let receiver_id = receiver_ctx.id();
+/// sim.register_composite_key_getter_for::<Segment, _>(|segment| (segment.conn_id, segment.seq_no));
+///
+/// sim.spawn(async move {
+///     let segment = receiver_ctx.recv_event_by_key::<Segment>(composite_key(&(2u32, 1u64))).await;
+///     assert_eq!(segment.data.conn_id, 2);
+///     assert_eq!(receiver_ctx.time(), 2.);
+/// });
+///
+/// sender_ctx.emit(Segment { conn_id: 1, seq_no: 2 }, receiver_id, 1.);
+/// sender_ctx.emit(Segment { conn_id: 2, seq_no: 1 }, receiver_id, 2.);
+/// sim.step_until_no_events();
+/// ```
+pub fn composite_key<K: Hash + ?Sized>(key: &K) -> EventKey {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Trait for event types with a key used for receiving events by key.
+///
+/// The trait can be implemented via `#[derive(EventKey)]` with the key field marked with `#[event_key]` attribute
+/// (requires `derive` feature). If several fields are marked, the key is the [`composite_key`] of their tuple,
+/// which is subject to hash collisions.
+/// The events of such types can be received via
+/// [`SimulationContext::recv_keyed_event`](crate::SimulationContext::recv_keyed_event), which registers the key getter
+/// on first use. The key getter can be also registered explicitly via
+/// [`Simulation::register_keyed_event`](crate::Simulation::register_keyed_event), which is needed to receive such events
+/// via [`SimulationContext::recv_event_by_key`](crate::SimulationContext::recv_event_by_key) and similar methods.
+pub trait KeyedEvent: EventData {
+    /// Returns the event key.
+    fn event_key(&self) -> EventKey;
+}
+
 /// Represents a result of asynchronous waiting for event with timeout (see [`EventFuture::with_timeout`]).
 pub enum AwaitResult<T: EventData> {
     /// Corresponds to successful event receipt.
@@ -36,6 +103,10 @@ pub struct EventFuture<T: EventData> {
     dst: Id,
     src: Option<Id>,
     event_key: Option<EventKey>,
+    // Sequence number of the promise of event satisfying a predicate.
+    predicate_seq: Option<u64>,
+    // Origin of the wait if the wait statistics are enabled.
+    wait_site: Option<Box<WaitSite>>,
     // State with completion info shared with EventPromise.
     state: Rc<RefCell<TypedEventAwaitState<T>>>,
     sim_state: Rc<RefCell<SimulationState>>,
@@ -53,11 +124,32 @@ impl<T: EventData> EventFuture<T> {
             dst,
             src,
             event_key,
+            predicate_seq: None,
+            wait_site: None,
             state,
             sim_state,
         }
     }
 
+    pub(crate) fn set_predicate_seq(&mut self, seq: u64) {
+        self.predicate_seq = Some(seq);
+    }
+
+    pub(crate) fn set_wait_site(&mut self, wait_site: Option<Box<WaitSite>>) {
+        self.wait_site = wait_site;
+    }
+
+    /// Sets the label used to tag the wait in the wait statistics instead of the call site.
+    ///
+    /// Has no effect if the wait statistics are not enabled,
+    /// see [`Simulation::enable_wait_stats`](crate::Simulation::enable_wait_stats).
+    pub fn labeled(mut self, label: impl Into<String>) -> Self {
+        if let Some(site) = self.wait_site.as_mut() {
+            site.label = Some(label.into());
+        }
+        self
+    }
+
     /// Waits for event with specified timeout and returns result (either event of timeout).
     ///
     /// # Examples
@@ -237,10 +329,14 @@ impl<T: EventData> EventFuture<T> {
 
 impl<T: EventData> Future for EventFuture<T> {
     type Output = TypedEvent<T>;
-    fn poll(self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
         let mut state = self.state.as_ref().borrow_mut();
         if state.completed {
             let event = std::mem::take(&mut state.event).expect("Completed EventFuture contains no event");
+            drop(state);
+            if let Some(site) = self.wait_site.take() {
+                self.sim_state.borrow_mut().finish_wait(&site);
+            }
             Poll::Ready(event)
         } else {
             state.waker = Some(async_ctx.waker().clone());
@@ -255,20 +351,313 @@ impl<T: EventData> Drop for EventFuture<T> {
         // removal, because sim_state is already mutably borrowed in SimulationState::cancel_component_promises.
         // Instead, we do the necessary clean up directly in SimulationState::cancel_component_promises and set the
         // manually_dropped flag in the state.
-        if !self.state.borrow().completed && !self.state.borrow().manually_dropped {
-            self.sim_state
-                .borrow_mut()
-                .on_incomplete_event_future_drop::<T>(self.dst, &self.src, self.event_key);
+        if self.state.borrow().manually_dropped {
+            return;
+        }
+        if !self.state.borrow().completed {
+            let mut sim_state = self.sim_state.borrow_mut();
+            if let Some(seq) = self.predicate_seq {
+                sim_state.on_incomplete_predicate_event_future_drop(seq);
+            } else {
+                sim_state.on_incomplete_event_future_drop::<T>(self.dst, &self.src, self.event_key);
+            }
+        }
+        // the abandoned waits are also accounted
+        if let Some(site) = self.wait_site.take() {
+            self.sim_state.borrow_mut().finish_wait(&site);
+        }
+    }
+}
+
+// Multiple events future ----------------------------------------------------------------------------------------------
+
+/// Future that represents asynchronous waiting for multiple events with different keys.
+///
+/// Created via [`SimulationContext::recv_events_by_keys`](crate::SimulationContext::recv_events_by_keys) or
+/// [`SimulationContext::recv_events_by_keys_quorum`](crate::SimulationContext::recv_events_by_keys_quorum).
+pub struct EventsFuture<T: EventData> {
+    // Futures of events which are not received yet, indexed by the position of key.
+    futures: Vec<Option<EventFuture<T>>>,
+    // Received events with the positions of their keys.
+    received: Vec<(usize, TypedEvent<T>)>,
+    quorum: usize,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+impl<T: EventData> EventsFuture<T> {
+    pub(crate) fn new(futures: Vec<EventFuture<T>>, quorum: usize, sim_state: Rc<RefCell<SimulationState>>) -> Self {
+        assert!(
+            quorum <= futures.len(),
+            "Quorum {} exceeds the number of keys {}",
+            quorum,
+            futures.len()
+        );
+        Self {
+            received: Vec::with_capacity(quorum),
+            futures: futures.into_iter().map(Some).collect(),
+            quorum,
+            sim_state,
+        }
+    }
+}
+
+// Received events are never pinned, so the future can be moved regardless of T.
+impl<T: EventData> Unpin for EventsFuture<T> {}
+
+impl<T: EventData> Future for EventsFuture<T> {
+    type Output = Vec<TypedEvent<T>>;
+    fn poll(mut self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        for (idx, slot) in this.futures.iter_mut().enumerate() {
+            if let Some(future) = slot {
+                if let Poll::Ready(event) = Pin::new(future).poll(async_ctx) {
+                    this.received.push((idx, event));
+                    *slot = None;
+                }
+            }
+        }
+        if this.received.len() < this.quorum {
+            return Poll::Pending;
+        }
+        let all = this.quorum == this.futures.len();
+        // drop futures of the remaining events to cancel waiting for them
+        this.futures.clear();
+        let mut received = std::mem::take(&mut this.received);
+        if all {
+            received.sort_by_key(|(idx, _)| *idx);
+        } else {
+            // events are returned in the order of their receipt, extra events received at once are returned
+            // to the pending events
+            received.sort_by(|(_, a), (_, b)| a.time.total_cmp(&b.time).then(a.id.cmp(&b.id)));
+            for (_, event) in received.drain(this.quorum..) {
+                requeue_event(&this.sim_state, event);
+            }
         }
+        Poll::Ready(received.into_iter().map(|(_, event)| event).collect())
     }
 }
 
+// Any event future ----------------------------------------------------------------------------------------------------
+
+/// Future that represents asynchronous waiting for the first of several events, possibly of different types.
+///
+/// Each awaited event is added via [`or`](Self::or) with a function mapping the received event to the common output
+/// type, usually a variant of user-defined enum. The future outputs the mapped first received event, the waiting for
+/// other events is cancelled. If several events are received at once, e.g. before the future is polled, the event
+/// with the earliest time is returned with ties broken by event id, so the result does not depend on the order in
+/// which the events were added. The other events received at once are returned to the pending events and delivered
+/// again before other events at the current time, e.g. to the component handler.
+///
+/// See [`recv_any!`](crate::recv_any) for a shorthand.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::async_mode::AnyEventFuture;
+/// use simcore::{Simulation, TypedEvent};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Ack {
+///     seq: u32,
+/// }
+///
+/// #[derive(Clone, Serialize)]
+/// struct Nack {
+///     seq: u32,
+/// }
+///
+/// enum Reply {
+///     Ack(TypedEvent<Ack>),
+///     Nack(TypedEvent<Nack>),
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let sender_ctx = sim.create_context("sender");
+/// let receiver_ctx = sim.create_context("receiver");
+/// let receiver_id = receiver_ctx.id();
+///
+/// sim.spawn(async move {
+///     let reply = AnyEventFuture::new()
+///         .or(receiver_ctx.recv_event::<Ack>(), Reply::Ack)
+///         .or(receiver_ctx.recv_event::<Nack>(), Reply::Nack)
+///         .await;
+///     match reply {
+///         Reply::Ack(_) => panic!("Unexpected ack"),
+///         Reply::Nack(e) => assert_eq!(e.data.seq, 5),
+///     }
+///     assert_eq!(receiver_ctx.time(), 3.);
+/// });
+///
+/// sender_ctx.emit(Nack { seq: 5 }, receiver_id, 3.);
+/// sim.step_until_no_events();
+/// ```
+pub struct AnyEventFuture<R> {
+    futures: Vec<Box<dyn MappedEventFuture<R>>>,
+}
+
+impl<R: 'static> AnyEventFuture<R> {
+    /// Creates a future without awaited events.
+    ///
+    /// The events must be added via [`or`](Self::or) before awaiting the future.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { futures: Vec::new() }
+    }
+
+    /// Adds the awaited event with the function mapping it to the output.
+    pub fn or<T: EventData>(mut self, future: EventFuture<T>, map: fn(TypedEvent<T>) -> R) -> Self {
+        self.futures.push(Box::new(MappedEventFutureImpl {
+            future,
+            map,
+            received: None,
+        }));
+        self
+    }
+}
+
+impl<R> Future for AnyEventFuture<R> {
+    type Output = R;
+    fn poll(mut self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+        assert!(!self.futures.is_empty(), "AnyEventFuture is awaited without events");
+        let mut first: Option<(f64, EventId, usize)> = None;
+        for (idx, future) in self.futures.iter_mut().enumerate() {
+            if let Some((time, id)) = future.poll_received(async_ctx) {
+                if first.is_none_or(|(t, i, _)| (time, id) < (t, i)) {
+                    first = Some((time, id, idx));
+                }
+            }
+        }
+        let Some((_, _, first_idx)) = first else {
+            return Poll::Pending;
+        };
+        let output = self.futures[first_idx].take_mapped();
+        for future in self.futures.iter_mut() {
+            future.requeue_received();
+        }
+        // drop futures of the remaining events to cancel waiting for them
+        self.futures.clear();
+        Poll::Ready(output)
+    }
+}
+
+trait MappedEventFuture<R> {
+    // Polls the future and returns time and id of the received event, which is kept until it is taken or requeued.
+    fn poll_received(&mut self, async_ctx: &mut Context) -> Option<(f64, EventId)>;
+    // Returns the mapped received event.
+    fn take_mapped(&mut self) -> R;
+    // Returns the received event, if any, to the pending events.
+    fn requeue_received(&mut self);
+}
+
+struct MappedEventFutureImpl<T: EventData, R> {
+    future: EventFuture<T>,
+    map: fn(TypedEvent<T>) -> R,
+    received: Option<TypedEvent<T>>,
+}
+
+impl<T: EventData, R> MappedEventFuture<R> for MappedEventFutureImpl<T, R> {
+    fn poll_received(&mut self, async_ctx: &mut Context) -> Option<(f64, EventId)> {
+        match Pin::new(&mut self.future).poll(async_ctx) {
+            Poll::Ready(event) => {
+                let result = (event.time, event.id);
+                self.received = Some(event);
+                Some(result)
+            }
+            Poll::Pending => None,
+        }
+    }
+
+    fn take_mapped(&mut self) -> R {
+        (self.map)(self.received.take().unwrap())
+    }
+
+    fn requeue_received(&mut self) {
+        if let Some(event) = self.received.take() {
+            requeue_event(&self.future.sim_state, event);
+        }
+    }
+}
+
+// Returns the event received by future, which is not passed to the awaiting task, to the pending events.
+fn requeue_event<T: EventData>(sim_state: &RefCell<SimulationState>, event: TypedEvent<T>) {
+    sim_state.borrow_mut().requeue_event(Event {
+        id: event.id,
+        time: event.time,
+        src: event.src,
+        dst: event.dst,
+        priority: 0,
+        data: Box::new(event.data),
+    });
+}
+
+/// Waits (asynchronously) for the first of events with the listed types destined to the component.
+///
+/// The macro takes the simulation context and the output enum with the variants wrapping [`TypedEvent`] of each
+/// awaited type: `recv_any!(ctx, Enum { Variant(Type), ... })`. It creates [`AnyEventFuture`] which outputs the enum
+/// with the first received event. Each type is received as via
+/// [`SimulationContext::recv_event`](crate::SimulationContext::recv_event), and the ties between events received at
+/// once are broken deterministically by event time and id.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::{recv_any, Simulation, TypedEvent};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Ack {
+///     seq: u32,
+/// }
+///
+/// #[derive(Clone, Serialize)]
+/// struct Nack {
+///     seq: u32,
+/// }
+///
+/// enum Reply {
+///     Ack(TypedEvent<Ack>),
+///     Nack(TypedEvent<Nack>),
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let sender_ctx = sim.create_context("sender");
+/// let receiver_ctx = sim.create_context("receiver");
+/// let receiver_id = receiver_ctx.id();
+///
+/// sim.spawn(async move {
+///     let mut acked = Vec::new();
+///     for _ in 0..3 {
+///         match recv_any!(receiver_ctx, Reply { Ack(Ack), Nack(Nack) }).await {
+///             Reply::Ack(e) => acked.push(e.data.seq),
+///             Reply::Nack(_) => {}
+///         }
+///     }
+///     assert_eq!(acked, vec![1, 3]);
+/// });
+///
+/// sender_ctx.emit(Ack { seq: 1 }, receiver_id, 1.);
+/// sender_ctx.emit(Nack { seq: 2 }, receiver_id, 2.);
+/// sender_ctx.emit(Ack { seq: 3 }, receiver_id, 3.);
+/// sim.step_until_no_events();
+/// ```
+#[macro_export]
+macro_rules! recv_any {
+    ( $ctx:expr, $enum:ident { $( $variant:ident ( $type:ty ) ),+ $(,)? } ) => {{
+        let __ctx = &$ctx;
+        $crate::async_mode::AnyEventFuture::new()
+            $( .or(__ctx.recv_event::<$type>(), $enum::$variant) )+
+    }};
+}
+
 // Event promise -------------------------------------------------------------------------------------------------------
 
 #[derive(Clone)]
 pub(crate) struct EventPromise {
     // State with completion info shared with EventFuture.
     state: Rc<RefCell<dyn EventAwaitState>>,
+    // Name of the awaited event type.
+    type_name: &'static str,
 }
 
 impl EventPromise {
@@ -280,7 +669,15 @@ impl EventPromise {
     ) -> (Self, EventFuture<T>) {
         let state = Rc::new(RefCell::new(TypedEventAwaitState::<T>::default()));
         let future = EventFuture::new(dst, src, event_key, state.clone(), sim_state);
-        (Self { state }, future)
+        let promise = Self {
+            state,
+            type_name: type_name::<T>(),
+        };
+        (promise, future)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
     }
 
     pub fn complete(&self, e: Event) {
@@ -0,0 +1,232 @@
+//! Capacity shared fairly among concurrent activities.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::event::EventId;
+use crate::SimulationContext;
+
+type ActivityId = u64;
+
+#[derive(Serialize, Clone)]
+struct ActivityCompleted {
+    activity_id: ActivityId,
+}
+
+struct Activity {
+    remaining: f64,
+    weight: f64,
+    max_rate: f64,
+    rate: f64,
+}
+
+struct FairShareState {
+    capacity: f64,
+    activities: BTreeMap<ActivityId, Activity>,
+    next_activity: ActivityId,
+    last_update_time: f64,
+    // Completion event of the activity which is expected to complete first.
+    completion: Option<EventId>,
+}
+
+impl FairShareState {
+    fn update(&mut self, time: f64) {
+        let elapsed = time - self.last_update_time;
+        if elapsed > 0. {
+            for activity in self.activities.values_mut() {
+                activity.remaining = (activity.remaining - activity.rate * elapsed).max(0.);
+            }
+        }
+        self.last_update_time = time;
+    }
+
+    // Allocates the capacity via progressive filling: the rates of all activities grow in proportion to their
+    // weights, and the activities reaching their maximum rate keep it while the rest share the remaining capacity.
+    fn allocate(&mut self) {
+        let mut order: Vec<(f64, ActivityId)> = self
+            .activities
+            .iter()
+            .map(|(id, activity)| (activity.max_rate / activity.weight, *id))
+            .collect();
+        order.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let mut capacity = self.capacity;
+        let mut weight: f64 = self.activities.values().map(|activity| activity.weight).sum();
+        for (_, id) in order {
+            let activity = self.activities.get_mut(&id).unwrap();
+            let share = capacity * (activity.weight / weight).min(1.);
+            activity.rate = share.min(activity.max_rate);
+            capacity = (capacity - activity.rate).max(0.);
+            weight -= activity.weight;
+        }
+    }
+
+    // Returns the activity which is expected to complete first with the time until its completion.
+    fn next_completion(&self) -> Option<(ActivityId, f64)> {
+        self.activities
+            .iter()
+            .filter(|(_, activity)| activity.rate > 0.)
+            .map(|(id, activity)| (*id, activity.remaining / activity.rate))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Capacity, such as network bandwidth or disk throughput, shared among concurrent activities performed by
+/// asynchronous tasks.
+///
+/// Each activity processes the specified amount of work, e.g. transfers the specified amount of data. The capacity
+/// is allocated among the active activities according to the weighted max-min fairness: each activity gets the share
+/// of the capacity proportional to its weight, unless it is limited by its maximum rate, in which case the unused
+/// capacity is shared among the other activities. With equal weights and without rate limits, the capacity is split
+/// equally. The rates and completion times of all activities are recomputed when an activity starts or completes
+/// and when the capacity is changed.
+///
+/// The capacity keeps a single pending event for the activity which is expected to complete first, so the number of
+/// events does not depend on the number of concurrent activities.
+///
+/// Fair share is created via [`Simulation::create_fair_share`](crate::Simulation::create_fair_share).
+pub struct FairShare {
+    state: RefCell<FairShareState>,
+    ctx: SimulationContext,
+}
+
+impl FairShare {
+    pub(crate) fn new(ctx: SimulationContext, capacity: f64) -> Self {
+        assert!(capacity > 0., "Fair share capacity must be positive");
+        ctx.register_key_getter_for::<ActivityCompleted>(|event| event.activity_id);
+        let time = ctx.time();
+        Self {
+            state: RefCell::new(FairShareState {
+                capacity,
+                activities: BTreeMap::new(),
+                next_activity: 0,
+                last_update_time: time,
+                completion: None,
+            }),
+            ctx,
+        }
+    }
+
+    /// Returns the shared capacity.
+    pub fn capacity(&self) -> f64 {
+        self.state.borrow().capacity
+    }
+
+    /// Returns the number of active activities.
+    pub fn active(&self) -> usize {
+        self.state.borrow().activities.len()
+    }
+
+    /// Returns the part of the capacity currently allocated to the activities.
+    ///
+    /// The allocated capacity is smaller than the shared one if there are no activities or all activities are
+    /// limited by their maximum rates.
+    pub fn allocated(&self) -> f64 {
+        self.state.borrow().activities.values().map(|activity| activity.rate).sum()
+    }
+
+    /// Performs the activity with the specified amount of work, weight 1 and without rate limit.
+    ///
+    /// This function is asynchronous and its result (future) must be awaited.
+    /// If the future is dropped before completion, the activity is cancelled and its share of the capacity is
+    /// reallocated to the other activities.
+    ///
+    /// Panics if `amount` is negative.
+    pub async fn process(&self, amount: f64) {
+        self.process_weighted(amount, 1., f64::INFINITY).await
+    }
+
+    /// Performs the activity with the specified amount of work, weight and maximum rate.
+    ///
+    /// The maximum rate can be [`f64::INFINITY`] if the activity is not limited.
+    /// See [`process`](Self::process) for details.
+    ///
+    /// Panics if `amount` is negative or `weight` or `max_rate` is not positive.
+    pub async fn process_weighted(&self, amount: f64, weight: f64, max_rate: f64) {
+        assert!(amount >= 0., "Activity amount must be non-negative");
+        assert!(weight > 0., "Activity weight must be positive");
+        assert!(max_rate > 0., "Activity maximum rate must be positive");
+        if amount == 0. {
+            return;
+        }
+        let activity_id = {
+            let mut state = self.state.borrow_mut();
+            state.update(self.ctx.time());
+            let activity_id = state.next_activity;
+            state.next_activity += 1;
+            state.activities.insert(
+                activity_id,
+                Activity {
+                    remaining: amount,
+                    weight,
+                    max_rate,
+                    rate: 0.,
+                },
+            );
+            self.reallocate(&mut state);
+            activity_id
+        };
+        let mut guard = ActivityGuard {
+            share: self,
+            activity_id,
+            completed: false,
+        };
+        self.ctx
+            .recv_event_by_key_from_self::<ActivityCompleted>(activity_id)
+            .await;
+        guard.completed = true;
+        let mut state = self.state.borrow_mut();
+        state.update(self.ctx.time());
+        // the completion event is already delivered
+        state.completion = None;
+        state.activities.remove(&activity_id);
+        self.reallocate(&mut state);
+    }
+
+    /// Changes the shared capacity and recomputes the completion times of the active activities.
+    ///
+    /// Panics if `capacity` is not positive.
+    pub fn set_capacity(&self, capacity: f64) {
+        assert!(capacity > 0., "Fair share capacity must be positive");
+        let mut state = self.state.borrow_mut();
+        state.update(self.ctx.time());
+        state.capacity = capacity;
+        self.reallocate(&mut state);
+    }
+
+    fn reallocate(&self, state: &mut FairShareState) {
+        state.allocate();
+        if let Some(event_id) = state.completion.take() {
+            self.ctx.cancel_event(event_id);
+        }
+        if let Some((activity_id, delay)) = state.next_completion() {
+            let event_id = self.ctx.emit_self(ActivityCompleted { activity_id }, delay);
+            state.completion = Some(event_id);
+        }
+    }
+}
+
+// Cancels the activity if the process future is dropped before completion.
+//
+// The completion events are awaited by the own component of the fair share, which has no event handler, so the
+// waiting tasks are not dropped inside SimulationState::cancel_component_promises and the cleanup can access
+// the simulation state.
+struct ActivityGuard<'a> {
+    share: &'a FairShare,
+    activity_id: ActivityId,
+    completed: bool,
+}
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let share = self.share;
+        let mut state = share.state.borrow_mut();
+        state.update(share.ctx.time());
+        state.activities.remove(&self.activity_id);
+        share.reallocate(&mut state);
+    }
+}
@@ -0,0 +1,463 @@
+//! Mailbox of component for actor-style processing of incoming events.
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::event::{Event, EventData, EventId};
+use crate::metrics::TimeWeightedStat;
+use crate::SimulationContext;
+
+#[derive(Clone, Serialize)]
+struct MailboxNotify {}
+
+/// Statistics of mailbox collected since its creation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MailboxStats {
+    /// Number of events received by the mailbox.
+    pub received: u64,
+    /// Number of events taken from the mailbox.
+    pub taken: u64,
+    /// Time-average number of events in the mailbox.
+    pub mean_length: f64,
+    /// Maximum number of events in the mailbox.
+    pub max_length: usize,
+    /// Average time between the event arrival and taking the event from the mailbox.
+    pub mean_wait_time: f64,
+}
+
+/// Statistics of priority class of mailbox collected since the mailbox creation,
+/// see [`Mailbox::class_stats`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MailboxClassStats {
+    /// Priority of the class.
+    pub priority: i64,
+    /// Number of events of the class received by the mailbox.
+    pub received: u64,
+    /// Number of events of the class taken from the mailbox.
+    pub taken: u64,
+    /// Number of events of the class in the mailbox.
+    pub length: usize,
+    /// Average time between the event arrival and taking the event from the mailbox.
+    pub mean_wait_time: f64,
+    /// Maximum time between the event arrival and taking the event from the mailbox.
+    pub max_wait_time: f64,
+    /// Number of taken events which waited longer than the
+    /// [starvation threshold](Mailbox::set_starvation_threshold).
+    pub starved: u64,
+    /// Maximum time the oldest event of the class is waiting in the mailbox at the current time.
+    pub oldest_wait_time: f64,
+}
+
+type PriorityFn = Rc<dyn Fn(&Event) -> i64>;
+
+#[derive(Default)]
+struct PriorityClass {
+    events: VecDeque<Event>,
+    // Statistics
+    received: u64,
+    taken: u64,
+    total_wait_time: f64,
+    max_wait_time: f64,
+    starved: u64,
+}
+
+struct MailboxState {
+    classes: BTreeMap<i64, PriorityClass>,
+    priorities: FxHashMap<TypeId, PriorityFn>,
+    aging_rate: f64,
+    starvation_threshold: Option<f64>,
+    len: usize,
+    // Set when a task waits for the next event, contains the identifier of emitted notification.
+    waiter: Option<Option<EventId>>,
+    // Statistics
+    length: TimeWeightedStat,
+}
+
+impl MailboxState {
+    // Returns the priority of class whose head event should be taken next.
+    fn next_class(&self, time: f64) -> Option<i64> {
+        let mut best: Option<(f64, f64, i64)> = None;
+        for (priority, class) in self.classes.iter() {
+            let Some(head) = class.events.front() else {
+                continue;
+            };
+            let effective = *priority as f64 - self.aging_rate * (time - head.time);
+            let candidate = (effective, head.time, *priority);
+            if best.is_none_or(|best| (candidate.0, candidate.1) < (best.0, best.1)) {
+                best = Some(candidate);
+            }
+        }
+        best.map(|(_, _, priority)| priority)
+    }
+}
+
+pub(crate) struct MailboxInner {
+    state: RefCell<MailboxState>,
+    ctx: SimulationContext,
+}
+
+impl MailboxInner {
+    // Stores the event delivered to the component and wakes up the waiting task.
+    pub fn push(&self, event: Event) {
+        let mut state = self.state.borrow_mut();
+        let priority = state
+            .priorities
+            .get(&event.data.type_id())
+            .map_or(0, |priority_fn| priority_fn(&event));
+        let class = state.classes.entry(priority).or_default();
+        class.events.push_back(event);
+        class.received += 1;
+        state.len += 1;
+        let len = state.len as f64;
+        state.length.set(len);
+        if let Some(notify @ None) = state.waiter.as_mut() {
+            *notify = Some(self.ctx.emit_self_now(MailboxNotify {}));
+        }
+    }
+}
+
+/// Mailbox which receives the events of registered types destined to the component, so the component can process
+/// them at its own pace instead of the immediate invocation of its handler.
+///
+/// The mailbox is created via [`SimulationContext::create_mailbox`] and receives the events of types registered via
+/// [`accept`](Self::accept) in the order of their delivery. Such events are delivered to the mailbox even if the
+/// component has an event handler or a task awaiting the event via
+/// [`recv_event`](SimulationContext::recv_event). The events are taken from the mailbox via
+/// [`next`](Self::next) by a single task of the component, e.g. an actor loop, which allows modeling the
+/// processing backlog of the component, see [`stats`](Self::stats). The number of events in the mailbox is also
+/// registered as [time-weighted metric](crate::metrics::TimeWeighted) `mailbox_length` of the component.
+///
+/// The events can be divided into priority classes via [`accept_with_priority`](Self::accept_with_priority)
+/// and [`accept_with_priority_fn`](Self::accept_with_priority_fn). The events with smaller priority values are
+/// taken first, and the events of the same class are taken in the order of their delivery. To prevent the starvation
+/// of low-priority classes, the priority of waiting events can be improved over time via
+/// [`set_aging`](Self::set_aging). The statistics of each class, including the waiting times and the number of
+/// starved events, are returned by [`class_stats`](Self::class_stats).
+///
+/// When the mailbox is dropped, the events of registered types are delivered to the component as usual, and the
+/// events remaining in the mailbox are discarded.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::{cast, Simulation};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {
+///     id: u32,
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let client = sim.create_context("client");
+/// let server = sim.create_context("server");
+/// for id in 0..4 {
+///     client.emit(Request { id }, server.id(), 1.);
+/// }
+///
+/// sim.spawn(async move {
+///     let mailbox = server.create_mailbox();
+///     mailbox.accept::<Request>();
+///     // the server processes one request per time unit
+///     loop {
+///         let event = mailbox.next().await;
+///         cast!(match event.data {
+///             Request { id } => {
+///                 server.sleep(1.).await;
+///                 if id == 3 {
+///                     break;
+///                 }
+///             }
+///         });
+///     }
+///     assert_eq!(server.time(), 5.);
+///     let stats = mailbox.stats();
+///     assert_eq!(stats.received, 4);
+///     assert_eq!(stats.max_length, 4);
+///     // the requests wait 0, 1, 2 and 3 time units
+///     assert_eq!(stats.mean_wait_time, 1.5);
+/// });
+/// sim.step_until_no_events();
+/// ```
+pub struct Mailbox {
+    inner: Rc<MailboxInner>,
+}
+
+impl Mailbox {
+    pub(crate) fn new(ctx: SimulationContext) -> Self {
+        let length = TimeWeightedStat::new(&ctx, "mailbox_length", 0.);
+        let inner = Rc::new(MailboxInner {
+            state: RefCell::new(MailboxState {
+                classes: BTreeMap::new(),
+                priorities: FxHashMap::default(),
+                aging_rate: 0.,
+                starvation_threshold: None,
+                len: 0,
+                waiter: None,
+                length,
+            }),
+            ctx,
+        });
+        let sim_state = inner.ctx.sim_state();
+        sim_state
+            .borrow_mut()
+            .register_mailbox(inner.ctx.id(), Rc::downgrade(&inner));
+        Self { inner }
+    }
+
+    /// Registers the event type `T`, whose events destined to the component are delivered to the mailbox
+    /// with the default priority 0.
+    pub fn accept<T: EventData>(&self) {
+        self.accept_with_priority::<T>(0);
+    }
+
+    /// Registers the event type `T`, whose events destined to the component are delivered to the mailbox
+    /// with the specified priority.
+    ///
+    /// Events with smaller `priority` values are taken first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Data {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Control {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// let mailbox = server.create_mailbox();
+    /// mailbox.accept_with_priority::<Data>(1);
+    /// mailbox.accept_with_priority::<Control>(0);
+    /// client.emit(Data {}, server.id(), 1.);
+    /// client.emit(Control {}, server.id(), 2.);
+    /// sim.step_until_no_events();
+    ///
+    /// // the control event is taken first despite the later arrival
+    /// assert_eq!(mailbox.try_next().unwrap().time, 2.);
+    /// assert_eq!(mailbox.try_next().unwrap().time, 1.);
+    /// ```
+    pub fn accept_with_priority<T: EventData>(&self, priority: i64) {
+        self.register::<T>(Rc::new(move |_| priority));
+    }
+
+    /// Registers the event type `T`, whose events destined to the component are delivered to the mailbox
+    /// with the priority computed by the specified function from the event payload.
+    ///
+    /// Events with smaller priority values are taken first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     premium: bool,
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// let mailbox = server.create_mailbox();
+    /// mailbox.accept_with_priority_fn(|request: &Request| if request.premium { 0 } else { 1 });
+    /// client.emit(Request { premium: false }, server.id(), 1.);
+    /// client.emit(Request { premium: true }, server.id(), 2.);
+    /// sim.step_until_no_events();
+    ///
+    /// assert_eq!(mailbox.try_next().unwrap().time, 2.);
+    /// let stats = mailbox.class_stats();
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!((stats[0].priority, stats[0].taken), (0, 1));
+    /// assert_eq!((stats[1].priority, stats[1].length), (1, 1));
+    /// ```
+    pub fn accept_with_priority_fn<T, F>(&self, priority_fn: F)
+    where
+        T: EventData,
+        F: Fn(&T) -> i64 + 'static,
+    {
+        self.register::<T>(Rc::new(move |event| {
+            priority_fn(event.data.downcast_ref::<T>().unwrap())
+        }));
+    }
+
+    fn register<T: EventData>(&self, priority_fn: PriorityFn) {
+        self.inner
+            .state
+            .borrow_mut()
+            .priorities
+            .insert(TypeId::of::<T>(), priority_fn);
+        let sim_state = self.inner.ctx.sim_state();
+        sim_state.borrow_mut().accept_mailbox_event::<T>(self.inner.ctx.id());
+    }
+
+    /// Enables the aging of waiting events: the priority value of event is decreased by `rate` per unit of its
+    /// waiting time when choosing the next event, so the events of low-priority classes are eventually taken
+    /// despite the steady arrival of high-priority events.
+    ///
+    /// The next event is taken from the class whose oldest event has the smallest aged priority value, the events
+    /// of the same class are still taken in the order of their delivery. The aging is disabled by default.
+    ///
+    /// Panics if the rate is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     priority: i64,
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// let mailbox = server.create_mailbox();
+    /// mailbox.accept_with_priority_fn(|request: &Request| request.priority);
+    /// mailbox.set_aging(1.);
+    /// client.emit(Request { priority: 5 }, server.id(), 0.);
+    /// client.emit(Request { priority: 2 }, server.id(), 4.);
+    /// sim.step_until_time(5.);
+    ///
+    /// // at time 5 the aged priorities are 5 - 5 = 0 and 2 - 1 = 1
+    /// assert_eq!(mailbox.try_next().unwrap().time, 0.);
+    /// assert_eq!(mailbox.try_next().unwrap().time, 4.);
+    /// ```
+    pub fn set_aging(&self, rate: f64) {
+        assert!(rate >= 0., "Aging rate must be non-negative, got {}", rate);
+        self.inner.state.borrow_mut().aging_rate = rate;
+    }
+
+    /// Sets the waiting time after which the taken events are counted as starved in
+    /// [`MailboxClassStats::starved`].
+    pub fn set_starvation_threshold(&self, threshold: f64) {
+        self.inner.state.borrow_mut().starvation_threshold = Some(threshold);
+    }
+
+    /// Takes the next event from the mailbox, waiting if necessary until an event is delivered.
+    ///
+    /// This function is asynchronous and its result (future) must be awaited.
+    ///
+    /// Panics if another task already waits for the next event of this mailbox.
+    pub async fn next(&self) -> Event {
+        loop {
+            if let Some(event) = self.try_next() {
+                return event;
+            }
+            {
+                let mut state = self.inner.state.borrow_mut();
+                assert!(
+                    state.waiter.is_none(),
+                    "Mailbox of component {} is already awaited by another task",
+                    self.inner.ctx.name()
+                );
+                state.waiter = Some(None);
+            }
+            let guard = WaiterGuard { inner: &self.inner };
+            self.inner.ctx.recv_event_from_self::<MailboxNotify>().await;
+            // the notification is delivered, so it should not be canceled by the guard
+            self.inner.state.borrow_mut().waiter = None;
+            drop(guard);
+        }
+    }
+
+    /// Takes the next event from the mailbox if it is not empty.
+    pub fn try_next(&self) -> Option<Event> {
+        let time = self.inner.ctx.time();
+        let mut state = self.inner.state.borrow_mut();
+        let priority = state.next_class(time)?;
+        let threshold = state.starvation_threshold;
+        state.len -= 1;
+        let len = state.len as f64;
+        state.length.set(len);
+        let class = state.classes.get_mut(&priority).unwrap();
+        let event = class.events.pop_front().unwrap();
+        let wait_time = time - event.time;
+        class.taken += 1;
+        class.total_wait_time += wait_time;
+        class.max_wait_time = class.max_wait_time.max(wait_time);
+        if threshold.is_some_and(|threshold| wait_time > threshold) {
+            class.starved += 1;
+        }
+        Some(event)
+    }
+
+    /// Returns the number of events in the mailbox.
+    pub fn len(&self) -> usize {
+        self.inner.state.borrow().len
+    }
+
+    /// Returns true if the mailbox is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.state.borrow().len == 0
+    }
+
+    /// Returns the statistics of the mailbox.
+    pub fn stats(&self) -> MailboxStats {
+        let state = self.inner.state.borrow();
+        let length = state.length.stats();
+        let classes = state.classes.values();
+        let taken = classes.clone().map(|class| class.taken).sum::<u64>();
+        let total_wait_time = classes.clone().map(|class| class.total_wait_time).sum::<f64>();
+        MailboxStats {
+            received: classes.map(|class| class.received).sum(),
+            taken,
+            mean_length: length.mean(),
+            max_length: length.max as usize,
+            mean_wait_time: if taken > 0 { total_wait_time / taken as f64 } else { 0. },
+        }
+    }
+
+    /// Returns the statistics of priority classes of the mailbox sorted by priority.
+    ///
+    /// The class is included once the mailbox receives its first event.
+    pub fn class_stats(&self) -> Vec<MailboxClassStats> {
+        let time = self.inner.ctx.time();
+        let state = self.inner.state.borrow();
+        state
+            .classes
+            .iter()
+            .map(|(priority, class)| MailboxClassStats {
+                priority: *priority,
+                received: class.received,
+                taken: class.taken,
+                length: class.events.len(),
+                mean_wait_time: if class.taken > 0 {
+                    class.total_wait_time / class.taken as f64
+                } else {
+                    0.
+                },
+                max_wait_time: class.max_wait_time,
+                starved: class.starved,
+                oldest_wait_time: class.events.front().map_or(0., |event| time - event.time),
+            })
+            .collect()
+    }
+}
+
+// Resets the waiter of mailbox when the future of `next` is dropped before completion, e.g. on timeout.
+struct WaiterGuard<'a> {
+    inner: &'a MailboxInner,
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        let waiter = self.inner.state.borrow_mut().waiter.take();
+        if let Some(Some(notify)) = waiter {
+            self.inner.ctx.cancel_event(notify);
+        }
+    }
+}
@@ -6,9 +6,20 @@ use crate::async_mode_enabled;
 pub(crate) mod macros;
 
 async_mode_enabled!(
+    pub mod cancellation;
+    pub mod deadlock;
     pub mod event_future;
+    pub mod fair_share;
+    pub mod mailbox;
+    pub mod process;
     pub mod queue;
+    pub mod request;
+    pub mod resource;
+    pub mod retry;
     pub mod timer_future;
+    pub mod token_bucket;
+    pub mod wait_stats;
+    pub mod work;
 
     pub(crate) mod channel;
     pub(crate) mod executor;
@@ -17,7 +28,20 @@ async_mode_enabled!(
 
     mod waker;
 
-    pub use event_future::{AwaitResult, EventFuture, EventKey};
+    pub use cancellation::{CancellationToken, CancelledFuture, TaskScope};
+    pub use deadlock::{BlockedWait, DeadlockReport};
+    pub use fair_share::FairShare;
+    pub use mailbox::{Mailbox, MailboxClassStats, MailboxStats};
+    pub use event_future::{composite_key, AnyEventFuture, AwaitResult, EventFuture, EventKey, EventsFuture, KeyedEvent};
+    #[cfg(feature = "derive")]
+    pub use simcore_derive::EventKey;
+    pub use process::{Interrupted, Process};
     pub use timer_future::TimerFuture;
     pub use queue::UnboundedQueue;
+    pub use request::{Request, RequestId, RequestTimeout, Response};
+    pub use resource::{Resource, ResourceStats};
+    pub use retry::RetryPolicy;
+    pub use token_bucket::TokenBucket;
+    pub use wait_stats::WaitStats;
+    pub use work::{SharedRate, Work, WorkHandle};
 );
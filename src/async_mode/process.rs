@@ -0,0 +1,243 @@
+//! Process-oriented modeling facade.
+//!
+//! In the process-interaction style, the model is described by processes, i.e. sequences of activities which take
+//! simulated time, such as customers arriving, waiting for service and departing. This module provides
+//! the [`Process`] abstraction with the classic operations of this style:
+//!
+//! - [`hold`](Process::hold) suspends the process for the specified duration,
+//! - [`passivate`](Process::passivate) suspends the process until it is activated by another process,
+//! - [`activate`](Process::activate) resumes a passive process,
+//! - [`interrupt`](Process::interrupt) aborts the current `hold` or `passivate` of a process.
+//!
+//! Processes are implemented as asynchronous tasks, so they can also use other async mode facilities.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+
+use futures::future::{select, Either};
+use serde::Serialize;
+
+use crate::async_mode::EventKey;
+use crate::component::Id;
+use crate::event::EventId;
+use crate::SimulationContext;
+
+/// Error returned by [`Process::hold`] and [`Process::passivate`] when the process is interrupted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interrupted;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProcessStatus {
+    Running,
+    Holding,
+    Passive,
+    Finished,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+enum Signal {
+    Activate,
+    Interrupt,
+}
+
+#[derive(Clone, Serialize)]
+struct ProcessSignal {
+    wait_id: EventKey,
+    signal: Signal,
+}
+
+struct ProcessState {
+    status: ProcessStatus,
+    // Identifier of the current hold or passivate, used as the key of signal events.
+    wait_id: EventKey,
+    // Signal sent during the current wait along with the identifier of signal event.
+    signal: Option<(Signal, EventId)>,
+    // Interrupt received while the process was running, delivered on the next wait.
+    pending_interrupt: bool,
+}
+
+struct ProcessInner {
+    ctx: SimulationContext,
+    state: RefCell<ProcessState>,
+}
+
+/// Handle of a simulation process.
+///
+/// The handle can be cloned and shared with other processes to [`activate`](Self::activate) or
+/// [`interrupt`](Self::interrupt) this process. Each process is a separate simulation component with its own
+/// [context](Self::ctx).
+///
+/// Process is created via [`Simulation::spawn_process`](crate::Simulation::spawn_process) or
+/// [`Process::spawn_process`].
+#[derive(Clone)]
+pub struct Process {
+    inner: Rc<ProcessInner>,
+}
+
+impl Process {
+    pub(crate) fn spawn<F, Fut>(ctx: SimulationContext, body: F) -> Self
+    where
+        F: FnOnce(Process) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        ctx.register_key_getter_for::<ProcessSignal>(|signal| signal.wait_id);
+        let process = Self {
+            inner: Rc::new(ProcessInner {
+                ctx,
+                state: RefCell::new(ProcessState {
+                    status: ProcessStatus::Running,
+                    wait_id: 0,
+                    signal: None,
+                    pending_interrupt: false,
+                }),
+            }),
+        };
+        let body = body(process.clone());
+        let finished = process.clone();
+        process.inner.ctx.spawn_task(async move {
+            body.await;
+            finished.inner.state.borrow_mut().status = ProcessStatus::Finished;
+        });
+        process
+    }
+
+    /// Creates a new process with specified name executing the specified body.
+    ///
+    /// This allows creating processes from other processes, e.g. a process per arriving customer.
+    /// See [`Simulation::spawn_process`](crate::Simulation::spawn_process).
+    pub fn spawn_process<S, F, Fut>(&self, name: S, body: F) -> Process
+    where
+        S: AsRef<str>,
+        F: FnOnce(Process) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        Process::spawn(self.inner.ctx.create_context(name.as_ref()), body)
+    }
+
+    /// Returns the context of simulation component associated with the process.
+    pub fn ctx(&self) -> &SimulationContext {
+        &self.inner.ctx
+    }
+
+    /// Returns the identifier of simulation component associated with the process.
+    pub fn id(&self) -> Id {
+        self.inner.ctx.id()
+    }
+
+    /// Returns the process name.
+    pub fn name(&self) -> &str {
+        self.inner.ctx.name()
+    }
+
+    /// Returns the current simulation time.
+    pub fn time(&self) -> f64 {
+        self.inner.ctx.time()
+    }
+
+    /// Returns true if the process is passive, i.e. waits for activation.
+    pub fn is_passive(&self) -> bool {
+        self.inner.state.borrow().status == ProcessStatus::Passive
+    }
+
+    /// Returns true if the process body has completed.
+    pub fn is_finished(&self) -> bool {
+        self.inner.state.borrow().status == ProcessStatus::Finished
+    }
+
+    /// Suspends the process for the specified duration.
+    ///
+    /// Returns [`Interrupted`] if the process is interrupted while holding, in which case the process is resumed
+    /// at the time of interrupt. If the process was interrupted while running, returns [`Interrupted`] immediately.
+    ///
+    /// This function is asynchronous and its result (future) must be awaited.
+    pub async fn hold(&self, duration: f64) -> Result<(), Interrupted> {
+        let wait_id = self.begin_wait(ProcessStatus::Holding)?;
+        let ctx = &self.inner.ctx;
+        let sleep = pin!(ctx.sleep(duration));
+        let signal = pin!(ctx.recv_event_by_key_from_self::<ProcessSignal>(wait_id));
+        let signaled = matches!(select(sleep, signal).await, Either::Right(_));
+        self.end_wait(signaled)
+    }
+
+    /// Suspends the process until it is activated via [`activate`](Self::activate).
+    ///
+    /// Returns [`Interrupted`] if the process is interrupted while passive.
+    /// If the process was interrupted while running, returns [`Interrupted`] immediately.
+    ///
+    /// This function is asynchronous and its result (future) must be awaited.
+    pub async fn passivate(&self) -> Result<(), Interrupted> {
+        let wait_id = self.begin_wait(ProcessStatus::Passive)?;
+        self.inner
+            .ctx
+            .recv_event_by_key_from_self::<ProcessSignal>(wait_id)
+            .await;
+        self.end_wait(true)
+    }
+
+    /// Resumes the process if it is passive.
+    ///
+    /// The process is resumed at the current time after the currently running process is suspended.
+    /// Has no effect if the process is not passive or is already activated or interrupted.
+    pub fn activate(&self) {
+        if self.is_passive() {
+            self.send_signal(Signal::Activate);
+        }
+    }
+
+    /// Interrupts the process.
+    ///
+    /// If the process is holding or passive, its current wait is aborted with [`Interrupted`] error.
+    /// If the process is running (e.g. interrupts itself or awaits other futures), the interrupt is delivered
+    /// on its next [`hold`](Self::hold) or [`passivate`](Self::passivate). Has no effect if the process is finished.
+    pub fn interrupt(&self) {
+        let status = self.inner.state.borrow().status;
+        match status {
+            ProcessStatus::Holding | ProcessStatus::Passive => self.send_signal(Signal::Interrupt),
+            ProcessStatus::Running => self.inner.state.borrow_mut().pending_interrupt = true,
+            ProcessStatus::Finished => {}
+        }
+    }
+
+    fn begin_wait(&self, status: ProcessStatus) -> Result<EventKey, Interrupted> {
+        let mut state = self.inner.state.borrow_mut();
+        if state.pending_interrupt {
+            state.pending_interrupt = false;
+            return Err(Interrupted);
+        }
+        state.status = status;
+        state.wait_id += 1;
+        Ok(state.wait_id)
+    }
+
+    fn end_wait(&self, signaled: bool) -> Result<(), Interrupted> {
+        let mut state = self.inner.state.borrow_mut();
+        state.status = ProcessStatus::Running;
+        match state.signal.take() {
+            Some((signal, event_id)) => {
+                if !signaled {
+                    // the hold is completed at the same time, the signal still takes effect
+                    self.inner.ctx.cancel_event(event_id);
+                }
+                match signal {
+                    Signal::Activate => Ok(()),
+                    Signal::Interrupt => Err(Interrupted),
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn send_signal(&self, signal: Signal) {
+        let mut state = self.inner.state.borrow_mut();
+        if state.signal.is_some() {
+            return;
+        }
+        let event_id = self.inner.ctx.emit_self_now(ProcessSignal {
+            wait_id: state.wait_id,
+            signal,
+        });
+        state.signal = Some((signal, event_id));
+    }
+}
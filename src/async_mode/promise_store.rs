@@ -1,14 +1,37 @@
 use std::any::TypeId;
+use std::rc::Rc;
 
 use rustc_hash::FxHashMap;
 
 use super::{event_future::EventPromise, EventKey};
 use crate::{Event, EventData, Id};
 
+// Promises are stored along with the sequence number of their insertion, which defines the order of dropping them.
+// The hash map iteration order depends on TypeId values, which are not stable across platforms and builds.
 #[derive(Clone)]
 pub(crate) struct EventPromiseStore {
-    promises: FxHashMap<AwaitKey, EventPromise>,
-    promises_with_source: FxHashMap<AwaitKey, FxHashMap<Id, EventPromise>>,
+    promises: FxHashMap<AwaitKey, (u64, EventPromise)>,
+    promises_with_source: FxHashMap<AwaitKey, FxHashMap<Id, (u64, EventPromise)>>,
+    // Promises of events satisfying predicates, which are checked in the order of insertion.
+    predicate_promises: Vec<PredicatePromise>,
+    promise_count: u64,
+}
+
+pub(crate) type EventPredicateFn = Rc<dyn Fn(&Event) -> bool>;
+
+#[derive(Clone)]
+struct PredicatePromise {
+    seq: u64,
+    dst: Id,
+    data_type: TypeId,
+    predicate: EventPredicateFn,
+    promise: EventPromise,
+}
+
+impl PredicatePromise {
+    fn matches(&self, event: &Event) -> bool {
+        self.dst == event.dst && self.data_type == event.data.type_id() && (self.predicate)(event)
+    }
 }
 
 impl EventPromiseStore {
@@ -16,6 +39,8 @@ impl EventPromiseStore {
         Self {
             promises: FxHashMap::default(),
             promises_with_source: FxHashMap::default(),
+            predicate_promises: Vec::new(),
+            promise_count: 0,
         }
     }
 
@@ -34,6 +59,7 @@ impl EventPromiseStore {
         }
 
         // store promise
+        let seq = self.promise_count;
         if let Some(src) = src {
             if let Some(promises) = self.promises_with_source.get(&key) {
                 // check that promise with such key and source doesn't exist yet
@@ -44,7 +70,10 @@ impl EventPromiseStore {
                     ));
                 }
             }
-            self.promises_with_source.entry(key).or_default().insert(src, promise);
+            self.promises_with_source
+                .entry(key)
+                .or_default()
+                .insert(src, (seq, promise));
         } else {
             if let Some(promises) = self.promises_with_source.get(&key) {
                 // check that promise with such key and some source doesn't exist yet
@@ -56,11 +85,36 @@ impl EventPromiseStore {
                     ));
                 }
             }
-            self.promises.insert(key, promise);
+            self.promises.insert(key, (seq, promise));
         }
+        self.promise_count += 1;
         Ok(())
     }
 
+    // Stores the promise of event satisfying the predicate and returns its sequence number used for removing it.
+    pub fn insert_with_predicate<T: EventData>(
+        &mut self,
+        dst: Id,
+        predicate: EventPredicateFn,
+        promise: EventPromise,
+    ) -> u64 {
+        let seq = self.promise_count;
+        self.predicate_promises.push(PredicatePromise {
+            seq,
+            dst,
+            data_type: TypeId::of::<T>(),
+            predicate,
+            promise,
+        });
+        self.promise_count += 1;
+        seq
+    }
+
+    pub fn remove_with_predicate(&mut self, seq: u64) -> Option<EventPromise> {
+        let idx = self.predicate_promises.iter().position(|p| p.seq == seq)?;
+        Some(self.predicate_promises.remove(idx).promise)
+    }
+
     pub fn remove<T: EventData>(
         &mut self,
         dst: Id,
@@ -70,12 +124,12 @@ impl EventPromiseStore {
         let key = AwaitKey::new::<T>(dst, event_key);
         if let Some(src) = src {
             if let Some(promises) = self.promises_with_source.get_mut(&key) {
-                promises.remove(src)
+                promises.remove(src).map(|(_, promise)| promise)
             } else {
                 None
             }
         } else {
-            self.promises.remove(&key)
+            self.promises.remove(&key).map(|(_, promise)| promise)
         }
     }
 
@@ -85,43 +139,77 @@ impl EventPromiseStore {
             return true;
         }
         if let Some(promises) = self.promises_with_source.get(&key) {
-            return promises.contains_key(&event.src);
+            if promises.contains_key(&event.src) {
+                return true;
+            }
         }
-        false
+        self.predicate_promises.iter().any(|p| p.matches(event))
     }
 
     pub fn remove_promise_for(&mut self, event: &Event, event_key: Option<EventKey>) -> Option<EventPromise> {
         let key = AwaitKey::new_by_ref(event.dst, event.data.as_ref(), event_key);
-        if let Some(promise) = self.promises.remove(&key) {
+        if let Some((_, promise)) = self.promises.remove(&key) {
             return Some(promise);
         }
         if let Some(promises) = self.promises_with_source.get_mut(&key) {
-            return promises.remove(&event.src);
+            if let Some((_, promise)) = promises.remove(&event.src) {
+                return Some(promise);
+            }
         }
-        None
+        let idx = self.predicate_promises.iter().position(|p| p.matches(event))?;
+        Some(self.predicate_promises.remove(idx).promise)
     }
 
-    pub fn drop_promises_by_dst(&mut self, dst: Id) -> u32 {
-        let mut removed_count = 0;
-        self.promises.retain(|key, promise| {
-            if key.dst == dst {
-                promise.drop_state();
-                removed_count += 1;
-                return false;
-            }
-            true
-        });
-        self.promises_with_source.retain(|key, promises| {
-            if key.dst == dst {
-                promises.iter_mut().for_each(|(_, promise)| {
-                    promise.drop_state();
-                    removed_count += 1;
-                });
-                return false;
+    // Returns the pending waits in the order of promise creation.
+    pub fn pending_waits(&self) -> Vec<PendingWait> {
+        let mut waits: Vec<(u64, PendingWait)> = Vec::new();
+        for (key, (seq, promise)) in self.promises.iter() {
+            waits.push((*seq, PendingWait::new(key, None, promise)));
+        }
+        for (key, promises) in self.promises_with_source.iter() {
+            for (src, (seq, promise)) in promises.iter() {
+                waits.push((*seq, PendingWait::new(key, Some(*src), promise)));
             }
-            true
-        });
-        removed_count
+        }
+        for p in self.predicate_promises.iter() {
+            let wait = PendingWait {
+                dst: p.dst,
+                src: None,
+                event_key: None,
+                type_name: p.promise.type_name(),
+            };
+            waits.push((p.seq, wait));
+        }
+        waits.sort_by_key(|(seq, _)| *seq);
+        waits.into_iter().map(|(_, wait)| wait).collect()
+    }
+
+    pub fn drop_promises_by_dst(&mut self, dst: Id) -> u32 {
+        let mut removed = Vec::new();
+        let keys: Vec<_> = self.promises.keys().filter(|key| key.dst == dst).copied().collect();
+        for key in keys {
+            removed.push(self.promises.remove(&key).unwrap());
+        }
+        let keys: Vec<_> = self
+            .promises_with_source
+            .keys()
+            .filter(|key| key.dst == dst)
+            .copied()
+            .collect();
+        for key in keys {
+            removed.extend(self.promises_with_source.remove(&key).unwrap().into_values());
+        }
+        let (dropped, kept) = std::mem::take(&mut self.predicate_promises)
+            .into_iter()
+            .partition(|p| p.dst == dst);
+        self.predicate_promises = kept;
+        removed.extend(dropped.into_iter().map(|p: PredicatePromise| (p.seq, p.promise)));
+        // dropping the promises may drop the awaiting tasks, so it is done in the order of promise creation
+        removed.sort_by_key(|(seq, _)| *seq);
+        for (_, promise) in removed.iter_mut() {
+            promise.drop_state();
+        }
+        removed.len() as u32
     }
 }
 
@@ -149,3 +237,22 @@ impl AwaitKey {
         }
     }
 }
+
+// Description of a pending wait for event.
+pub(crate) struct PendingWait {
+    pub dst: Id,
+    pub src: Option<Id>,
+    pub event_key: Option<EventKey>,
+    pub type_name: &'static str,
+}
+
+impl PendingWait {
+    fn new(key: &AwaitKey, src: Option<Id>, promise: &EventPromise) -> Self {
+        Self {
+            dst: key.dst,
+            src,
+            event_key: key.event_key,
+            type_name: promise.type_name(),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Request-response interaction between components.
+//!
+//! A request is sent via [`SimulationContext::request`](crate::SimulationContext::request), which wraps the request
+//! payload into [`Request`] with a unique correlation identifier and waits for the matching [`Response`].
+//! The responder receives the request as a usual event and replies via
+//! [`SimulationContext::reply`](crate::SimulationContext::reply).
+//!
+//! The client and server glue for a trait of async request methods can be generated via
+//! `simcore::rpc` attribute macro, which is available when the `derive` feature is enabled.
+
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::async_mode::EventKey;
+use crate::component::Id;
+
+/// Identifier used to match responses with requests.
+pub type RequestId = EventKey;
+
+/// Event carrying request payload.
+#[derive(Clone, Serialize)]
+pub struct Request<T> {
+    /// Request identifier.
+    pub id: RequestId,
+    /// Request payload.
+    pub data: T,
+}
+
+/// Event carrying response payload.
+#[derive(Clone, Serialize)]
+pub struct Response<T> {
+    /// Identifier of the request this response corresponds to.
+    pub request_id: RequestId,
+    /// Response payload.
+    pub data: T,
+}
+
+/// Error returned by the generated RPC clients if the response is not received within the timeout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestTimeout {
+    /// Identifier of the server component.
+    pub server: Id,
+    /// Name of the called method.
+    pub method: &'static str,
+    /// Timeout value.
+    pub timeout: f64,
+}
+
+impl Display for RequestTimeout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request {} to component {} timed out after {}",
+            self.method, self.server, self.timeout
+        )
+    }
+}
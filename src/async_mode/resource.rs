@@ -0,0 +1,303 @@
+//! Shared resource with limited capacity for synchronization of asynchronous tasks.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::event::EventId;
+use crate::metrics::TimeWeightedStat;
+use crate::SimulationContext;
+
+type TicketID = u64;
+
+#[derive(Serialize, Clone)]
+struct ResourceGrant {
+    ticket_id: TicketID,
+}
+
+/// Statistics of resource usage collected since the resource creation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceStats {
+    /// Time-average fraction of resource capacity in use, which accounts for the capacity changes.
+    pub utilization: f64,
+    /// Time-average number of waiting requests.
+    pub mean_queue_length: f64,
+    /// Maximum number of waiting requests.
+    pub max_queue_length: usize,
+    /// Number of completed acquisitions.
+    pub acquisitions: u64,
+    /// Average time between the start and the completion of acquisition.
+    pub mean_wait_time: f64,
+}
+
+struct ResourceState {
+    capacity: u64,
+    // Can exceed the capacity after its reduction until the units are released.
+    in_use: u64,
+    // Waiting requests ordered by priority and ticket, i.e. by request order for equal priorities.
+    waiters: BTreeMap<(i64, TicketID), u64>,
+    // Requests with granted amount, which are not yet resumed, with the identifiers of grant events.
+    granted: FxHashMap<TicketID, (EventId, u64)>,
+    next_ticket: TicketID,
+    // Statistics
+    // Units in use above the reduced capacity are not accounted to keep the utilization within [0, 1].
+    in_use_stat: TimeWeightedStat,
+    capacity_stat: TimeWeightedStat,
+    queue_stat: TimeWeightedStat,
+    acquisitions: u64,
+    total_wait_time: f64,
+}
+
+/// Shared resource with integer capacity, which can be acquired and released by asynchronous tasks.
+///
+/// The resource models a pool of identical units, such as servers, workers or connection slots.
+/// Tasks acquire some amount of units, waiting if necessary until enough units are released by other tasks.
+/// Waiting requests are served in the order of their priority and, for equal priorities, in the order of
+/// [`acquire`](Resource::acquire) calls. A request cannot be overtaken by later requests with the same or lower
+/// priority, even if they require fewer units.
+///
+/// The capacity can be changed during the simulation via [`set_capacity`](Resource::set_capacity), e.g. to model
+/// failures or degradation of servers.
+///
+/// The resource also collects the [statistics](Resource::stats) of its usage. The amount in use, the capacity and
+/// the number of waiting requests are also registered as [time-weighted metrics](crate::metrics::TimeWeighted)
+/// `in_use`, `capacity` and `queue_length` of the resource component.
+///
+/// Resource is created via [`Simulation::create_resource`](crate::Simulation::create_resource).
+pub struct Resource {
+    state: RefCell<ResourceState>,
+    ctx: SimulationContext,
+}
+
+impl Resource {
+    pub(crate) fn new(ctx: SimulationContext, capacity: u64) -> Self {
+        assert!(capacity > 0, "Resource capacity must be positive");
+        ctx.register_key_getter_for::<ResourceGrant>(|grant| grant.ticket_id);
+        Self {
+            state: RefCell::new(ResourceState {
+                capacity,
+                in_use: 0,
+                waiters: BTreeMap::new(),
+                granted: FxHashMap::default(),
+                next_ticket: 0,
+                in_use_stat: TimeWeightedStat::new(&ctx, "in_use", 0.),
+                capacity_stat: TimeWeightedStat::new(&ctx, "capacity", capacity as f64),
+                queue_stat: TimeWeightedStat::new(&ctx, "queue_length", 0.),
+                acquisitions: 0,
+                total_wait_time: 0.,
+            }),
+            ctx,
+        }
+    }
+
+    /// Returns the resource capacity.
+    pub fn capacity(&self) -> u64 {
+        self.state.borrow().capacity
+    }
+
+    /// Returns the amount of currently available units.
+    pub fn available(&self) -> u64 {
+        self.state.borrow().available()
+    }
+
+    /// Returns the amount of currently acquired units.
+    ///
+    /// The amount can exceed the capacity after its reduction via [`set_capacity`](Self::set_capacity).
+    pub fn in_use(&self) -> u64 {
+        self.state.borrow().in_use
+    }
+
+    /// Returns the number of waiting requests.
+    pub fn queue_len(&self) -> usize {
+        self.state.borrow().waiters.len()
+    }
+
+    /// Acquires the specified amount of units, waiting if necessary until they become available.
+    ///
+    /// This function is asynchronous and its result (future) must be awaited.
+    /// If the future is dropped before completion, the request is cancelled.
+    ///
+    /// Panics if `amount` exceeds the current resource capacity.
+    pub async fn acquire(&self, amount: u64) {
+        self.acquire_with_priority(amount, 0).await
+    }
+
+    /// Acquires the specified amount of units with the specified priority, waiting if necessary until
+    /// they become available.
+    ///
+    /// Waiting requests with smaller `priority` values are served first.
+    /// See [`acquire`](Self::acquire) for details.
+    pub async fn acquire_with_priority(&self, amount: u64, priority: i64) {
+        let ticket_id = {
+            let mut state = self.state.borrow_mut();
+            assert!(
+                amount <= state.capacity,
+                "Requested amount {} exceeds resource capacity {}",
+                amount,
+                state.capacity
+            );
+            if state.waiters.is_empty() && state.available() >= amount {
+                state.in_use += amount;
+                state.acquisitions += 1;
+                state.update_stats();
+                return;
+            }
+            let ticket_id = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiters.insert((priority, ticket_id), amount);
+            state.update_stats();
+            ticket_id
+        };
+        let start_time = self.ctx.time();
+        let mut guard = RequestGuard {
+            resource: self,
+            ticket_id,
+            priority,
+            completed: false,
+        };
+        self.ctx
+            .recv_event_by_key_from_self::<ResourceGrant>(ticket_id)
+            .await;
+        guard.completed = true;
+        let mut state = self.state.borrow_mut();
+        state.granted.remove(&ticket_id);
+        state.acquisitions += 1;
+        state.total_wait_time += self.ctx.time() - start_time;
+    }
+
+    /// Releases the specified amount of units and resumes the waiting requests which can be served.
+    ///
+    /// Panics if `amount` exceeds the amount of acquired units.
+    pub fn release(&self, amount: u64) {
+        let mut state = self.state.borrow_mut();
+        assert!(
+            amount <= state.in_use,
+            "Released amount {} exceeds acquired amount {}",
+            amount,
+            state.in_use
+        );
+        state.in_use -= amount;
+        self.grant_waiters(&mut state);
+    }
+
+    /// Changes the resource capacity.
+    ///
+    /// If the capacity is increased, the waiting requests which can be served are resumed. If the capacity is
+    /// reduced below the amount of acquired units, the acquired units are not revoked, but the released units
+    /// are not granted to the waiting requests until the amount in use drops below the new capacity. The waiting
+    /// requests exceeding the reduced capacity keep waiting until the capacity is increased again.
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let resource = sim.create_resource("servers", 2);
+    /// let ctx = sim.create_context("client");
+    /// sim.spawn(async move {
+    ///     resource.acquire(2).await;
+    ///     // one of the servers fails
+    ///     resource.set_capacity(1);
+    ///     assert_eq!(resource.in_use(), 2);
+    ///     assert_eq!(resource.available(), 0);
+    ///     resource.release(1);
+    ///     assert_eq!(resource.available(), 0);
+    ///     resource.release(1);
+    ///     assert_eq!(resource.available(), 1);
+    ///     // the server is repaired
+    ///     ctx.sleep(10.).await;
+    ///     resource.set_capacity(2);
+    ///     assert_eq!(resource.available(), 2);
+    /// });
+    /// sim.step_until_no_events();
+    /// ```
+    pub fn set_capacity(&self, capacity: u64) {
+        assert!(capacity > 0, "Resource capacity must be positive");
+        let mut state = self.state.borrow_mut();
+        state.capacity = capacity;
+        self.grant_waiters(&mut state);
+    }
+
+    /// Returns the statistics of resource usage up to the current time.
+    pub fn stats(&self) -> ResourceStats {
+        let state = self.state.borrow();
+        let in_use = state.in_use_stat.stats();
+        let capacity = state.capacity_stat.stats();
+        let queue = state.queue_stat.stats();
+        ResourceStats {
+            utilization: if capacity.integral > 0. {
+                in_use.integral / capacity.integral
+            } else {
+                in_use.value / capacity.value
+            },
+            mean_queue_length: queue.mean(),
+            max_queue_length: queue.max as usize,
+            acquisitions: state.acquisitions,
+            mean_wait_time: if state.acquisitions > 0 {
+                state.total_wait_time / state.acquisitions as f64
+            } else {
+                0.
+            },
+        }
+    }
+
+    fn grant_waiters(&self, state: &mut ResourceState) {
+        while let Some((&(priority, ticket_id), &amount)) = state.waiters.first_key_value() {
+            if amount > state.available() {
+                break;
+            }
+            state.waiters.remove(&(priority, ticket_id));
+            state.in_use += amount;
+            let event_id = self.ctx.emit_self_now(ResourceGrant { ticket_id });
+            state.granted.insert(ticket_id, (event_id, amount));
+        }
+        state.update_stats();
+    }
+}
+
+impl ResourceState {
+    fn available(&self) -> u64 {
+        self.capacity.saturating_sub(self.in_use)
+    }
+
+    fn update_stats(&mut self) {
+        self.in_use_stat.set(self.in_use.min(self.capacity) as f64);
+        self.capacity_stat.set(self.capacity as f64);
+        self.queue_stat.set(self.waiters.len() as f64);
+    }
+}
+
+// Cancels the request if the acquire future is dropped before completion.
+//
+// The grant events are awaited by the resource's own component, which has no event handler, so the waiting tasks
+// are not dropped inside SimulationState::cancel_component_promises and the cleanup can access the simulation state.
+struct RequestGuard<'a> {
+    resource: &'a Resource,
+    ticket_id: TicketID,
+    priority: i64,
+    completed: bool,
+}
+
+impl Drop for RequestGuard<'_> {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let resource = self.resource;
+        let mut state = resource.state.borrow_mut();
+        if let Some((event_id, amount)) = state.granted.remove(&self.ticket_id) {
+            // units were granted but not received, return them
+            resource.ctx.cancel_event(event_id);
+            state.in_use -= amount;
+        } else {
+            state.waiters.remove(&(self.priority, self.ticket_id));
+        }
+        // removed request could block the subsequent ones
+        resource.grant_waiters(&mut state);
+    }
+}
@@ -0,0 +1,65 @@
+//! Retrying of asynchronous operations with backoff.
+
+use crate::SimulationContext;
+
+/// Policy of retrying failed operations, used by [`SimulationContext::retry`].
+///
+/// After a failed attempt, the operation is retried after a backoff delay, which starts from
+/// [`initial_backoff`](Self::initial_backoff) and is multiplied by [`multiplier`](Self::multiplier) after each retry
+/// up to [`max_backoff`](Self::max_backoff). The [`jitter`](Self::jitter) randomly reduces each delay by up to the
+/// specified fraction using the simulation-wide random number generator, so the retries are deterministic for
+/// the same seed.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: f64,
+    /// Factor by which the delay is increased after each retry.
+    pub multiplier: f64,
+    /// Maximum delay between attempts.
+    pub max_backoff: f64,
+    /// Maximum fraction of the delay randomly subtracted from it, from 0 (no jitter) to 1 (full jitter).
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Creates a policy with specified maximum number of attempts and initial backoff, multiplier 2,
+    /// unlimited maximum backoff and no jitter.
+    pub fn new(max_attempts: u32, initial_backoff: f64) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            multiplier: 2.,
+            max_backoff: f64::INFINITY,
+            jitter: 0.,
+        }
+    }
+
+    /// Returns the policy with the specified jitter.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // Returns the delay before the specified retry (starting from 1).
+    pub(crate) fn backoff(&self, retry: u32, ctx: &SimulationContext) -> f64 {
+        let delay = (self.initial_backoff * self.multiplier.powi(retry as i32 - 1)).min(self.max_backoff);
+        if self.jitter > 0. {
+            delay * (1. - self.jitter * ctx.rand())
+        } else {
+            delay
+        }
+    }
+
+    pub(crate) fn validate(&self) {
+        assert!(self.max_attempts > 0, "Maximum number of attempts must be positive");
+        assert!(self.initial_backoff >= 0., "Initial backoff must be non-negative");
+        assert!(self.multiplier >= 1., "Backoff multiplier must be at least 1");
+        assert!(
+            (0. ..=1.).contains(&self.jitter),
+            "Jitter must be in [0, 1], got {}",
+            self.jitter
+        );
+    }
+}
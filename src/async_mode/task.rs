@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
@@ -14,20 +15,24 @@ type BoxedFuture = Pin<Box<dyn Future<Output = ()>>>;
 pub(crate) struct Task {
     future: RefCell<Option<BoxedFuture>>,
     executor: Sender<Rc<Task>>,
+    // Number of alive tasks in the simulation, decremented when the task is dropped.
+    live_tasks: Rc<Cell<usize>>,
 }
 
 impl Task {
     // Creates a new task from a future.
-    fn new(future: impl Future<Output = ()> + 'static, executor: Sender<Rc<Task>>) -> Self {
+    fn new(future: impl Future<Output = ()> + 'static, executor: Sender<Rc<Task>>, live_tasks: Rc<Cell<usize>>) -> Self {
+        live_tasks.set(live_tasks.get() + 1);
         Self {
             future: RefCell::new(Some(Box::pin(future))),
             executor,
+            live_tasks,
         }
     }
 
     // Converts a future into a task and sends it to executor.
-    pub fn spawn(future: impl Future<Output = ()> + 'static, executor: Sender<Rc<Task>>) {
-        let task = Rc::new(Task::new(future, executor));
+    pub fn spawn(future: impl Future<Output = ()> + 'static, executor: Sender<Rc<Task>>, live_tasks: Rc<Cell<usize>>) {
+        let task = Rc::new(Task::new(future, executor, live_tasks));
         task.schedule();
     }
 
@@ -56,8 +61,98 @@ impl Task {
     }
 }
 
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.live_tasks.set(self.live_tasks.get() - 1);
+    }
+}
+
 impl RcWake for Task {
     fn wake_by_ref(rc_self: &Rc<Self>) {
         rc_self.schedule();
     }
 }
+
+// Limits the number of concurrently running tasks spawned by a component.
+// Excess tasks are queued and started in the order of spawning when the running tasks complete.
+pub(crate) struct TaskLimiter {
+    limit: usize,
+    running: usize,
+    queue: VecDeque<BoxedFuture>,
+    executor: Sender<Rc<Task>>,
+    live_tasks: Rc<Cell<usize>>,
+}
+
+impl TaskLimiter {
+    pub fn new(executor: Sender<Rc<Task>>, live_tasks: Rc<Cell<usize>>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            limit: usize::MAX,
+            running: 0,
+            queue: VecDeque::new(),
+            executor,
+            live_tasks,
+        }))
+    }
+
+    pub fn set_limit(this: &Rc<RefCell<Self>>, limit: usize) {
+        this.borrow_mut().limit = limit;
+        Self::start_queued(this);
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn queued_count(&self) -> usize {
+        self.queue.len()
+    }
+
+    // Spawns the task if the limit is not reached or puts it into the queue otherwise.
+    pub fn spawn(this: &Rc<RefCell<Self>>, future: impl Future<Output = ()> + 'static) {
+        this.borrow_mut().queue.push_back(Box::pin(future));
+        Self::start_queued(this);
+    }
+
+    // Removes the queued tasks, which should be dropped by the caller after releasing borrows of simulation state.
+    pub fn take_queued(&mut self) -> VecDeque<BoxedFuture> {
+        std::mem::take(&mut self.queue)
+    }
+
+    fn start_queued(this: &Rc<RefCell<Self>>) {
+        loop {
+            let mut limiter = this.borrow_mut();
+            if limiter.running >= limiter.limit {
+                break;
+            }
+            let Some(future) = limiter.queue.pop_front() else {
+                break;
+            };
+            limiter.running += 1;
+            let executor = limiter.executor.clone();
+            let live_tasks = limiter.live_tasks.clone();
+            drop(limiter);
+            let permit = TaskPermit { limiter: this.clone() };
+            Task::spawn(
+                async move {
+                    // the permit is released when the task completes or is dropped
+                    let _permit = permit;
+                    future.await
+                },
+                executor,
+                live_tasks,
+            );
+        }
+    }
+}
+
+// Occupies a slot of TaskLimiter while the task is alive.
+struct TaskPermit {
+    limiter: Rc<RefCell<TaskLimiter>>,
+}
+
+impl Drop for TaskPermit {
+    fn drop(&mut self) {
+        self.limiter.borrow_mut().running -= 1;
+        TaskLimiter::start_queued(&self.limiter);
+    }
+}
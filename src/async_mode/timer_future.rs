@@ -7,6 +7,9 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll, Waker};
 
+use futures::future::FusedFuture;
+
+use crate::async_mode::wait_stats::WaitSite;
 use crate::{state::SimulationState, Id};
 
 // Timer identifier.
@@ -15,29 +18,120 @@ pub(crate) type TimerId = u64;
 // Timer future --------------------------------------------------------------------------------------------------------
 
 /// Future that represents asynchronous waiting for timer completion.
+///
+/// The future can be awaited by reference, e.g. in `select!`, to inspect it after another branch completes.
+/// The simulated time remaining until the timer completion is returned by [`remaining`](Self::remaining), and
+/// [`cancel`](Self::cancel) cancels the waiting and returns the remaining time, which is useful for modeling
+/// preempted work which is resumed later.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures::{select, FutureExt};
+/// use serde::Serialize;
+/// use simcore::Simulation;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Preempt {}
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("worker");
+/// let ctx_id = ctx.id();
+/// let root_ctx = sim.create_context("root");
+///
+/// sim.spawn(async move {
+///     let mut work = ctx.sleep(10.);
+///     select! {
+///         _ = &mut work => panic!("Work must be preempted"),
+///         _ = ctx.recv_event::<Preempt>().fuse() => {}
+///     }
+///     let remaining = work.cancel();
+///     assert_eq!(remaining, 6.);
+///     // resume the work after preemption
+///     ctx.sleep(remaining).await;
+///     assert_eq!(ctx.time(), 10.);
+/// });
+///
+/// root_ctx.emit(Preempt {}, ctx_id, 4.);
+/// sim.step_until_no_events();
+/// ```
 pub struct TimerFuture {
     // Unique timer identifier.
     timer_id: TimerId,
+    // The time when the timer will be fired.
+    time: f64,
+    // Whether the future has returned the result.
+    terminated: bool,
+    // Origin of the wait if the wait statistics are enabled.
+    wait_site: Option<Box<WaitSite>>,
     // State with completion info shared with TimerPromise.
     state: Rc<RefCell<TimerAwaitState>>,
     sim_state: Rc<RefCell<SimulationState>>,
 }
 
 impl TimerFuture {
-    fn new(timer_id: TimerId, state: Rc<RefCell<TimerAwaitState>>, sim_state: Rc<RefCell<SimulationState>>) -> Self {
+    fn new(
+        timer_id: TimerId,
+        time: f64,
+        state: Rc<RefCell<TimerAwaitState>>,
+        sim_state: Rc<RefCell<SimulationState>>,
+    ) -> Self {
         Self {
             timer_id,
+            time,
+            terminated: false,
+            wait_site: None,
             state,
             sim_state,
         }
     }
+
+    /// Returns the simulation time when the timer completes.
+    pub fn deadline(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns the simulated time remaining until the timer completion, or zero if the timer is completed.
+    ///
+    /// The remaining time is not affected by the time scale of the context.
+    pub fn remaining(&self) -> f64 {
+        if self.state.borrow().completed {
+            return 0.;
+        }
+        (self.time - self.sim_state.borrow().time()).max(0.)
+    }
+
+    /// Cancels the waiting for timer and returns the remaining time, see [`remaining`](Self::remaining).
+    pub fn cancel(self) -> f64 {
+        self.remaining()
+    }
+
+    pub(crate) fn set_wait_site(&mut self, wait_site: Option<Box<WaitSite>>) {
+        self.wait_site = wait_site;
+    }
+
+    /// Sets the label used to tag the wait in the wait statistics instead of the call site.
+    ///
+    /// Has no effect if the wait statistics are not enabled,
+    /// see [`Simulation::enable_wait_stats`](crate::Simulation::enable_wait_stats).
+    pub fn labeled(mut self, label: impl Into<String>) -> Self {
+        if let Some(site) = self.wait_site.as_mut() {
+            site.label = Some(label.into());
+        }
+        self
+    }
 }
 
 impl Future for TimerFuture {
     type Output = ();
-    fn poll(self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
         let mut state = self.state.as_ref().borrow_mut();
         if state.completed {
+            drop(state);
+            self.terminated = true;
+            if let Some(site) = self.wait_site.take() {
+                self.sim_state.borrow_mut().finish_wait(&site);
+            }
             Poll::Ready(())
         } else {
             state.waker = Some(async_ctx.waker().clone());
@@ -46,17 +140,30 @@ impl Future for TimerFuture {
     }
 }
 
+impl FusedFuture for TimerFuture {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
 impl Drop for TimerFuture {
     fn drop(&mut self) {
         // We cannot call SimulationState::on_incomplete_timer_future_drop when dropping futures on component handler
         // removal, because sim_state is already mutably borrowed in SimulationState::cancel_component_timers.
         // Instead, we do the necessary clean up directly in SimulationState::cancel_component_timers and set the
         // manually_dropped flag in the state.
-        if !self.state.borrow().completed && !self.state.borrow().manually_dropped {
+        if self.state.borrow().manually_dropped {
+            return;
+        }
+        if !self.state.borrow().completed {
             self.sim_state
                 .borrow_mut()
                 .on_incomplete_timer_future_drop(self.timer_id);
         }
+        // the interrupted waits are also accounted
+        if let Some(site) = self.wait_site.take() {
+            self.sim_state.borrow_mut().finish_wait(&site);
+        }
     }
 }
 
@@ -85,7 +192,7 @@ impl TimerPromise {
     }
 
     pub fn future(&self, sim_state: Rc<RefCell<SimulationState>>) -> TimerFuture {
-        TimerFuture::new(self.id, self.state.clone(), sim_state)
+        TimerFuture::new(self.id, self.time, self.state.clone(), sim_state)
     }
 
     pub fn complete(&self) {
@@ -0,0 +1,141 @@
+//! Token bucket for rate limiting of asynchronous tasks.
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use crate::SimulationContext;
+
+struct BucketState {
+    // Can be negative when tokens are reserved by waiting requests.
+    tokens: f64,
+    last_update_time: f64,
+}
+
+/// Token bucket which limits the rate of operations performed by asynchronous tasks.
+///
+/// The bucket is filled with tokens at the constant rate up to its capacity, which limits the burst size.
+/// Each operation takes the specified number of tokens via [`acquire`](TokenBucket::acquire), waiting in simulated
+/// time until enough tokens are accumulated. The waiting requests are served in the order of `acquire` calls.
+///
+/// The bucket is initially full.
+///
+/// Token bucket is created via [`Simulation::create_token_bucket`](crate::Simulation::create_token_bucket).
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: RefCell<BucketState>,
+    ctx: SimulationContext,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(ctx: SimulationContext, rate: f64, capacity: f64) -> Self {
+        assert!(rate > 0., "Token rate must be positive");
+        assert!(capacity > 0., "Bucket capacity must be positive");
+        let time = ctx.time();
+        Self {
+            rate,
+            capacity,
+            state: RefCell::new(BucketState {
+                tokens: capacity,
+                last_update_time: time,
+            }),
+            ctx,
+        }
+    }
+
+    /// Returns the rate of adding tokens to the bucket per unit of time.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Returns the bucket capacity.
+    pub fn capacity(&self) -> f64 {
+        self.capacity
+    }
+
+    /// Returns the number of tokens currently available in the bucket.
+    ///
+    /// Returns zero if there are waiting requests.
+    pub fn available(&self) -> f64 {
+        let mut state = self.state.borrow_mut();
+        self.refill(&mut state);
+        state.tokens.max(0.)
+    }
+
+    /// Takes the specified number of tokens, waiting if necessary until they are accumulated.
+    ///
+    /// Returns a future which must be awaited. The tokens are reserved when this function is called rather than
+    /// when the returned future is first polled, so the requests are served in the order of calls even if the
+    /// futures are polled in a different order. If the future is dropped before completion, the reserved tokens are
+    /// returned to the bucket (the already waiting requests are not resumed earlier).
+    ///
+    /// Panics if `tokens` is negative or exceeds the bucket capacity.
+    pub fn acquire(&self, tokens: f64) -> impl Future<Output = ()> + '_ {
+        assert!(
+            tokens >= 0. && tokens <= self.capacity,
+            "Requested tokens {} must be in [0, {}]",
+            tokens,
+            self.capacity
+        );
+        let deficit = {
+            let mut state = self.state.borrow_mut();
+            self.refill(&mut state);
+            state.tokens -= tokens;
+            -state.tokens
+        };
+        // the timer is started on call, so the waiting time does not depend on the time of the first poll
+        let reservation = (deficit > 0.).then(|| {
+            let guard = ReservationGuard {
+                bucket: self,
+                tokens,
+                completed: false,
+            };
+            (guard, self.ctx.sleep(deficit / self.rate))
+        });
+        async move {
+            if let Some((mut guard, timer)) = reservation {
+                timer.await;
+                guard.completed = true;
+            }
+        }
+    }
+
+    /// Takes the specified number of tokens if they are available without waiting.
+    ///
+    /// Returns `true` if the tokens were taken and `false` otherwise.
+    ///
+    /// Panics if `tokens` is negative.
+    pub fn try_acquire(&self, tokens: f64) -> bool {
+        assert!(tokens >= 0., "Requested tokens {} must be non-negative", tokens);
+        let mut state = self.state.borrow_mut();
+        self.refill(&mut state);
+        if state.tokens >= tokens {
+            state.tokens -= tokens;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let time = self.ctx.time();
+        state.tokens = (state.tokens + (time - state.last_update_time) * self.rate).min(self.capacity);
+        state.last_update_time = time;
+    }
+}
+
+// Returns the reserved tokens if the acquire future is dropped before completion.
+struct ReservationGuard<'a> {
+    bucket: &'a TokenBucket,
+    tokens: f64,
+    completed: bool,
+}
+
+impl Drop for ReservationGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            // the capacity limit is applied on the next refill
+            self.bucket.state.borrow_mut().tokens += self.tokens;
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Statistics of simulated time spent by asynchronous tasks in waiting.
+
+use std::panic::Location;
+
+/// Statistics of waits with the same tag, see [`Simulation::enable_wait_stats`](crate::Simulation::enable_wait_stats).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WaitStats {
+    /// Number of finished waits.
+    pub count: u64,
+    /// Total simulated time spent in waiting.
+    pub total_time: f64,
+    /// Minimum wait duration.
+    pub min_time: f64,
+    /// Maximum wait duration.
+    pub max_time: f64,
+}
+
+impl WaitStats {
+    /// Returns the mean wait duration or zero if there were no waits.
+    pub fn mean_time(&self) -> f64 {
+        if self.count == 0 {
+            0.
+        } else {
+            self.total_time / self.count as f64
+        }
+    }
+
+    pub(crate) fn add(&mut self, duration: f64) {
+        if self.count == 0 {
+            self.min_time = duration;
+            self.max_time = duration;
+        } else {
+            self.min_time = self.min_time.min(duration);
+            self.max_time = self.max_time.max(duration);
+        }
+        self.count += 1;
+        self.total_time += duration;
+    }
+}
+
+// Origin of the instrumented wait, which is identified by the label or the call site.
+pub(crate) struct WaitSite {
+    pub location: &'static Location<'static>,
+    pub label: Option<String>,
+    pub start_time: f64,
+}
+
+impl WaitSite {
+    pub fn tag(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => format!("{}:{}", self.location.file(), self.location.line()),
+        }
+    }
+}
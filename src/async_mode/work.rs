@@ -0,0 +1,400 @@
+//! Preemptible timed work.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+
+use futures::future::FusedFuture;
+
+use crate::async_mode::timer_future::TimerFuture;
+use crate::{state::SimulationState, Id};
+
+// Remaining amount of work below which the work is treated as completed despite the floating-point errors.
+const WORK_EPSILON: f64 = 1e-9;
+
+struct WorkState {
+    // Remaining amount of work, i.e. the time needed to complete it at rate 1.
+    remaining: f64,
+    rate: f64,
+    // Value of the attached shared rate, which multiplies the own rate.
+    shared_rate: f64,
+    attached: bool,
+    paused: bool,
+    last_update_time: f64,
+    completed: bool,
+    // Timer of expected completion, recreated when the work progress changes.
+    timer: Option<TimerFuture>,
+    waker: Option<Waker>,
+}
+
+impl WorkState {
+    fn effective_rate(&self) -> f64 {
+        self.rate * self.shared_rate
+    }
+
+    fn is_running(&self) -> bool {
+        !self.completed && !self.paused && self.effective_rate() > 0.
+    }
+
+    fn update(&mut self, time: f64) {
+        if self.is_running() {
+            self.remaining = (self.remaining - (time - self.last_update_time) * self.effective_rate()).max(0.);
+        }
+        self.last_update_time = time;
+    }
+
+    // Updates the progress, invalidates the completion timer and wakes the task awaiting the work.
+    //
+    // Returns the timer, which must be dropped after releasing the state borrow.
+    fn reschedule(&mut self, time: f64) -> Option<TimerFuture> {
+        self.update(time);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        self.timer.take()
+    }
+
+    fn modify<F: FnOnce(&mut WorkState)>(state: &RefCell<WorkState>, time: f64, f: F) {
+        let mut state = state.borrow_mut();
+        if state.completed {
+            return;
+        }
+        let timer = state.reschedule(time);
+        f(&mut state);
+        drop(state);
+        // dropping the timer cancels it
+        drop(timer);
+    }
+}
+
+/// Future that represents preemptible work performed by asynchronous task.
+///
+/// The work has an amount, which is the simulated time needed to complete it at rate 1, and completes when
+/// this amount is processed. Other tasks or event handlers can [pause](WorkHandle::pause) and
+/// [resume](WorkHandle::resume) the work or change its [rate](WorkHandle::set_rate) via the [`WorkHandle`],
+/// and the completion time is recomputed accordingly. This is useful for modeling CPU-like resources with
+/// varying speed or preemptive scheduling.
+///
+/// The work is created via [`SimulationContext::work`](crate::SimulationContext::work).
+/// If the future is dropped before completion, the work is cancelled.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::Simulation;
+///
+/// let mut sim = Simulation::new(123);
+/// let worker = sim.create_context("worker");
+/// let scheduler = sim.create_context("scheduler");
+///
+/// let work = worker.work(10.);
+/// let handle = work.handle();
+/// sim.spawn(async move {
+///     work.await;
+///     // 2 units at full rate, 4 units at half rate, pause for 3, then 4 units at full rate
+///     assert_eq!(worker.time(), 17.);
+/// });
+/// sim.spawn(async move {
+///     scheduler.sleep(2.).await;
+///     handle.set_rate(0.5);
+///     scheduler.sleep(8.).await;
+///     assert_eq!(handle.remaining(), 4.);
+///     handle.pause();
+///     scheduler.sleep(3.).await;
+///     handle.set_rate(1.);
+///     handle.resume();
+///     assert_eq!(handle.completion_time(), Some(17.));
+/// });
+///
+/// sim.step_until_no_events();
+/// assert_eq!(sim.time(), 17.);
+/// ```
+pub struct Work {
+    handle: WorkHandle,
+    terminated: bool,
+}
+
+impl Work {
+    pub(crate) fn new(component_id: Id, amount: f64, sim_state: Rc<RefCell<SimulationState>>) -> Self {
+        assert!(amount >= 0., "Work amount must be non-negative");
+        let time = sim_state.borrow().time();
+        let state = WorkState {
+            remaining: amount,
+            rate: 1.,
+            shared_rate: 1.,
+            attached: false,
+            paused: false,
+            last_update_time: time,
+            completed: false,
+            timer: None,
+            waker: None,
+        };
+        Self {
+            handle: WorkHandle {
+                component_id,
+                state: Rc::new(RefCell::new(state)),
+                sim_state,
+            },
+            terminated: false,
+        }
+    }
+
+    /// Returns the handle for controlling the work from other tasks.
+    pub fn handle(&self) -> WorkHandle {
+        self.handle.clone()
+    }
+}
+
+impl Future for Work {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, async_ctx: &mut Context) -> Poll<Self::Output> {
+        let time = self.handle.time();
+        let mut state = self.handle.state.borrow_mut();
+        state.update(time);
+        if !state.completed && state.remaining <= WORK_EPSILON {
+            state.remaining = 0.;
+            state.completed = true;
+        }
+        if state.completed {
+            let timer = state.timer.take();
+            drop(state);
+            drop(timer);
+            self.terminated = true;
+            return Poll::Ready(());
+        }
+        if state.is_running() {
+            if state.timer.is_none() {
+                let duration = state.remaining / state.effective_rate();
+                let timer =
+                    self.handle
+                        .sim_state
+                        .borrow_mut()
+                        .create_timer(self.handle.component_id, duration, self.handle.sim_state.clone());
+                state.timer = Some(timer);
+            }
+            if Pin::new(state.timer.as_mut().unwrap()).poll(async_ctx).is_ready() {
+                // the timer may fire slightly earlier or later than computed due to the time tick
+                state.remaining = 0.;
+                state.completed = true;
+                let timer = state.timer.take();
+                drop(state);
+                drop(timer);
+                self.terminated = true;
+                return Poll::Ready(());
+            }
+        }
+        state.waker = Some(async_ctx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl FusedFuture for Work {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// Handle for controlling the [`Work`] from other tasks or event handlers.
+#[derive(Clone)]
+pub struct WorkHandle {
+    component_id: Id,
+    state: Rc<RefCell<WorkState>>,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+impl WorkHandle {
+    /// Returns the remaining amount of work.
+    pub fn remaining(&self) -> f64 {
+        let time = self.time();
+        let mut state = self.state.borrow_mut();
+        state.update(time);
+        state.remaining
+    }
+
+    /// Returns the current rate of work set via [`set_rate`](Self::set_rate).
+    ///
+    /// If the work is attached to [`SharedRate`], the actual rate is the product of this rate and the shared one.
+    pub fn rate(&self) -> f64 {
+        self.state.borrow().rate
+    }
+
+    /// Returns true if the work is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+
+    /// Returns true if the work is completed.
+    pub fn is_completed(&self) -> bool {
+        self.state.borrow().completed
+    }
+
+    /// Returns the expected completion time of the work if it is running at the current rate,
+    /// or `None` if the work is paused, has zero rate or is completed.
+    pub fn completion_time(&self) -> Option<f64> {
+        let time = self.time();
+        let mut state = self.state.borrow_mut();
+        state.update(time);
+        if state.is_running() {
+            Some(time + state.remaining / state.effective_rate())
+        } else {
+            None
+        }
+    }
+
+    /// Pauses the work until it is resumed via [`resume`](Self::resume).
+    ///
+    /// Does nothing if the work is already paused or completed.
+    pub fn pause(&self) {
+        self.modify(|state| state.paused = true);
+    }
+
+    /// Resumes the paused work.
+    ///
+    /// Does nothing if the work is not paused or completed.
+    pub fn resume(&self) {
+        self.modify(|state| state.paused = false);
+    }
+
+    /// Changes the rate of work, i.e. the amount of work processed per unit of time.
+    ///
+    /// The work with zero rate does not progress, similarly to the paused work.
+    ///
+    /// Panics if the rate is negative.
+    pub fn set_rate(&self, rate: f64) {
+        assert!(rate >= 0., "Work rate must be non-negative");
+        self.modify(|state| state.rate = rate);
+    }
+
+    fn modify<F: FnOnce(&mut WorkState)>(&self, f: F) {
+        WorkState::modify(&self.state, self.time(), f);
+    }
+
+    fn time(&self) -> f64 {
+        self.sim_state.borrow().time()
+    }
+}
+
+struct AttachedWork {
+    state: Weak<RefCell<WorkState>>,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+struct SharedRateState {
+    rate: f64,
+    works: Vec<AttachedWork>,
+}
+
+/// Rate shared by multiple works, such as the speed of a processor or the bandwidth of a link.
+///
+/// Works are attached to the shared rate via [`attach`](Self::attach), and the actual rate of each attached work is
+/// the product of its own rate and the shared rate. Changing the shared rate, e.g. when the resource is throttled or
+/// degraded, recomputes the completion times of all attached works in progress and wakes the tasks awaiting them.
+/// Completed and dropped works are detached automatically.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::async_mode::SharedRate;
+/// use simcore::Simulation;
+///
+/// let mut sim = Simulation::new(123);
+/// let cpu = SharedRate::new(1.);
+/// for amount in [4., 10.] {
+///     let ctx = sim.create_context(format!("task-{}", amount));
+///     let work = ctx.work(amount);
+///     cpu.attach(&work.handle());
+///     sim.spawn(async move {
+///         work.await;
+///         // 2 units at full speed, then half speed
+///         assert_eq!(ctx.time(), 2. + (amount - 2.) * 2.);
+///     });
+/// }
+///
+/// sim.step_until_time(2.);
+/// // the processor is throttled
+/// cpu.set(0.5);
+/// sim.step_until_no_events();
+/// assert_eq!(sim.time(), 18.);
+/// ```
+#[derive(Clone)]
+pub struct SharedRate {
+    state: Rc<RefCell<SharedRateState>>,
+}
+
+impl SharedRate {
+    /// Creates the shared rate with the specified value.
+    ///
+    /// Panics if the rate is negative.
+    pub fn new(rate: f64) -> Self {
+        assert!(rate >= 0., "Work rate must be non-negative");
+        Self {
+            state: Rc::new(RefCell::new(SharedRateState {
+                rate,
+                works: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns the current value of the shared rate.
+    pub fn get(&self) -> f64 {
+        self.state.borrow().rate
+    }
+
+    /// Returns the number of attached works in progress.
+    pub fn works(&self) -> usize {
+        self.state
+            .borrow()
+            .works
+            .iter()
+            .filter_map(|work| work.state.upgrade())
+            .filter(|work| !work.borrow().completed)
+            .count()
+    }
+
+    /// Attaches the work, so that its actual rate is multiplied by the shared rate.
+    ///
+    /// Panics if the work is already attached to a shared rate.
+    pub fn attach(&self, work: &WorkHandle) {
+        assert!(
+            !work.state.borrow().attached,
+            "Work is already attached to a shared rate"
+        );
+        work.state.borrow_mut().attached = true;
+        let rate = {
+            let mut state = self.state.borrow_mut();
+            state.works.retain(|work| work.state.strong_count() > 0);
+            state.works.push(AttachedWork {
+                state: Rc::downgrade(&work.state),
+                sim_state: work.sim_state.clone(),
+            });
+            state.rate
+        };
+        WorkState::modify(&work.state, work.time(), |state| state.shared_rate = rate);
+    }
+
+    /// Changes the shared rate and recomputes the completion times of the attached works.
+    ///
+    /// Panics if the rate is negative.
+    pub fn set(&self, rate: f64) {
+        assert!(rate >= 0., "Work rate must be non-negative");
+        let works: Vec<_> = {
+            let mut state = self.state.borrow_mut();
+            state.rate = rate;
+            state
+                .works
+                .retain(|work| work.state.upgrade().is_some_and(|work| !work.borrow().completed));
+            state
+                .works
+                .iter()
+                .map(|work| (work.state.upgrade().unwrap(), work.sim_state.clone()))
+                .collect()
+        };
+        for (work, sim_state) in works {
+            let time = sim_state.borrow().time();
+            WorkState::modify(&work, time, |state| state.shared_rate = rate);
+        }
+    }
+}
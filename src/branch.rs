@@ -0,0 +1,33 @@
+//! Branching of simulation runs.
+//!
+//! What-if analysis often explores several futures of the same run, e.g. the behavior of the system after different
+//! faults injected at the same point or with different random seeds. Instead of re-running the simulation from the
+//! beginning for each future, the running simulation can be copied via [`Simulation::branch`] or
+//! [`Simulation::branch_with_seed`]. The branch gets a deep copy of the simulation state, including the pending
+//! events, the clock and the random number generator, while the components are copied via [`BranchComponent`]
+//! trait. The branches are independent, so running one of them does not affect the others.
+//!
+//! Only the components registered via [`Simulation::add_branchable_handler`] are copied into the branch, so the
+//! simulation can be branched only if all its event handlers are registered in this way. In async mode, the
+//! simulation can be branched only when there are no alive asynchronous tasks, because their state cannot be
+//! copied.
+//!
+//! [`Simulation::branch`]: crate::Simulation::branch
+//! [`Simulation::branch_with_seed`]: crate::Simulation::branch_with_seed
+//! [`Simulation::add_branchable_handler`]: crate::Simulation::add_branchable_handler
+
+use crate::handler::EventHandler;
+use crate::snapshot::ComponentState;
+use crate::SimulationContext;
+
+/// Component which can be copied into a branch of the simulation.
+///
+/// The component state is included in the snapshots of both simulations, which allows comparing the branches
+/// via [`Simulation::snapshot`](crate::Simulation::snapshot).
+pub trait BranchComponent: EventHandler + ComponentState {
+    /// Returns the copy of the component for the branch, which must use the specified context
+    /// instead of the context of this component.
+    fn branch(&self, ctx: SimulationContext) -> Self
+    where
+        Self: Sized;
+}
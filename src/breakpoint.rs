@@ -0,0 +1,158 @@
+//! Breakpoints on delivered events.
+//!
+//! A breakpoint registered via [`Simulation::add_breakpoint`](crate::Simulation::add_breakpoint) matches the events
+//! by their type, source, destination or a predicate over the payload. When the next event to be processed matches
+//! a breakpoint, the simulation pauses before delivering it, so the pending events, current time and the triggering
+//! event can be inspected. The triggering event stays in the pending queue and is processed by the next step without
+//! checking the breakpoints again, so the simulation can be continued step-by-step via
+//! [`Simulation::step`](crate::Simulation::step) or resumed via any other step method.
+
+use crate::component::Id;
+use crate::event::{Event, EventData, EventId};
+
+/// Identifier of a breakpoint.
+pub type BreakpointId = u64;
+
+/// Information about a triggered breakpoint.
+#[derive(Clone)]
+pub struct BreakpointHit {
+    /// Identifier of the breakpoint.
+    pub breakpoint: BreakpointId,
+    /// Copy of the triggering event, which is not processed yet.
+    pub event: Event,
+}
+
+type PredicateFn = Box<dyn Fn(&Event) -> bool>;
+
+/// Specification of events matched by a breakpoint.
+///
+/// The conditions are combined, i.e. the breakpoint matches only the events satisfying all specified conditions.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+///
+/// use simcore::breakpoint::Breakpoint;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {
+///     size: u64,
+/// }
+///
+/// // large requests sent to component 1
+/// let breakpoint = Breakpoint::on_payload(|request: &Request| request.size > 100).to(1);
+/// ```
+pub struct Breakpoint {
+    src: Option<Id>,
+    dst: Option<Id>,
+    predicates: Vec<PredicateFn>,
+}
+
+impl Breakpoint {
+    /// Creates a breakpoint matching all events.
+    pub fn any() -> Self {
+        Self {
+            src: None,
+            dst: None,
+            predicates: Vec::new(),
+        }
+    }
+
+    /// Creates a breakpoint matching the events with payload of the specified type.
+    pub fn on<T: EventData>() -> Self {
+        Self::any().when(|event| event.data.is::<T>())
+    }
+
+    /// Creates a breakpoint matching the events with payload of the specified type satisfying the predicate.
+    pub fn on_payload<T, F>(predicate: F) -> Self
+    where
+        T: EventData,
+        F: Fn(&T) -> bool + 'static,
+    {
+        Self::any().when(move |event| event.data.downcast_ref::<T>().is_some_and(&predicate))
+    }
+
+    /// Restricts the breakpoint to the events emitted by the specified component.
+    pub fn from(mut self, src: Id) -> Self {
+        self.src = Some(src);
+        self
+    }
+
+    /// Restricts the breakpoint to the events destined to the specified component.
+    pub fn to(mut self, dst: Id) -> Self {
+        self.dst = Some(dst);
+        self
+    }
+
+    /// Restricts the breakpoint to the events satisfying the predicate.
+    pub fn when<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Event) -> bool + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        self.src.is_none_or(|src| event.src == src)
+            && self.dst.is_none_or(|dst| event.dst == dst)
+            && self.predicates.iter().all(|predicate| predicate(event))
+    }
+}
+
+// Registered breakpoints with the state of the debugging session.
+#[derive(Default)]
+pub(crate) struct Breakpoints {
+    breakpoints: Vec<(BreakpointId, Breakpoint)>,
+    count: u64,
+    // Triggering event of the last hit, which is processed by the next step without checking the breakpoints.
+    resumed_event: Option<EventId>,
+    hit: Option<BreakpointHit>,
+}
+
+impl Breakpoints {
+    pub fn add(&mut self, breakpoint: Breakpoint) -> BreakpointId {
+        let id = self.count;
+        self.count += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    pub fn remove(&mut self, id: BreakpointId) {
+        self.breakpoints.retain(|(breakpoint_id, _)| *breakpoint_id != id);
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.resumed_event = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    pub fn take_hit(&mut self) -> Option<BreakpointHit> {
+        self.hit.take()
+    }
+
+    // Checks the next event and returns true if the simulation should pause before processing it.
+    pub fn check(&mut self, event: &Event) -> bool {
+        if self.resumed_event.take() == Some(event.id) {
+            return false;
+        }
+        let Some((id, _)) = self
+            .breakpoints
+            .iter()
+            .find(|(_, breakpoint)| breakpoint.matches(event))
+        else {
+            return false;
+        };
+        self.resumed_event = Some(event.id);
+        self.hit = Some(BreakpointHit {
+            breakpoint: *id,
+            event: event.clone(),
+        });
+        true
+    }
+}
@@ -0,0 +1,305 @@
+//! Checkpoints of simulation state.
+//!
+//! Long-running experiments can be suspended and resumed later by saving the simulation state to a file via
+//! [`Simulation::save_checkpoint`](crate::Simulation::save_checkpoint) and loading it via
+//! [`Simulation::load_checkpoint`](crate::Simulation::load_checkpoint). The [`Checkpoint`] contains the simulation
+//! time, the state of the random number generator, the pending events, the named timers, the number of processed
+//! events, the state of [warmup period](crate::warmup) and the states of components registered via [`Simulation::register_state`](crate::Simulation::register_state), which are restored
+//! via [`ComponentState::restore_state`](crate::snapshot::ComponentState::restore_state).
+//!
+//! Since the components and their event handlers are not serialized, the checkpoint is loaded into the simulation
+//! built in the same way as the saved one, i.e. with the same components created in the same order, so that they
+//! get the same identifiers. The events emitted while building the simulation are discarded on loading and
+//! replaced by the pending events from the checkpoint. The event payloads are serialized, so their types must be
+//! registered via [`Simulation::register_checkpoint_event`](crate::Simulation::register_checkpoint_event) in both
+//! simulations.
+//!
+//! The checkpoint also contains the number of events read from each [input](crate::input), e.g. from the
+//! [trace replay](crate::replay). On loading, the inputs of the simulation skip the same number of events, so a run
+//! driven by inputs can be restored at the checkpoint and replayed from there, or diverged by changing the model
+//! after restoring. This requires the inputs to produce the same events as in the saved run.
+//!
+//! The checkpoints can also be saved automatically in memory every N processed events, see
+//! [`Simulation::enable_auto_checkpoints`](crate::Simulation::enable_auto_checkpoints). This allows stepping
+//! backwards via [`Simulation::step_back`](crate::Simulation::step_back), which restores the nearest earlier
+//! checkpoint and deterministically re-executes the simulation forward up to the requested event, e.g. to inspect
+//! the state before a failure found while debugging. The automatic checkpoints also keep the logical clocks, and the
+//! re-executed events are not recorded by the traces, metrics and other observers of the run.
+//!
+//! The checkpoint cannot be saved while there are events emitted via
+//! [`emit_after`](crate::SimulationContext::emit_after) waiting for the preceding events or coalesced events, and
+//! in async mode while there are alive asynchronous tasks, because their state cannot be serialized. The events
+//! emitted by [`ComponentRef`](crate::ComponentRef) are restored as usual events. The diagnostic state, such as
+//! the in-memory trace, logical clocks and ordering checks, is not saved.
+
+use std::any::TypeId;
+use std::collections::{BTreeMap, VecDeque};
+
+use rand_pcg::Pcg64;
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::component::Id;
+use crate::event::{Event, EventData, EventId};
+use crate::logical_clock::LogicalClocks;
+use crate::metadata::RunMetadata;
+use crate::timer::TimerFired;
+
+pub(crate) type DecodeFn = fn(Value) -> serde_json::Result<Box<dyn EventData>>;
+
+pub(crate) fn decode<T: EventData + DeserializeOwned>(value: Value) -> serde_json::Result<Box<dyn EventData>> {
+    serde_json::from_value::<T>(value).map(|data| Box::new(data) as Box<dyn EventData>)
+}
+
+/// Saved state of the simulation, see [module documentation](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Metadata of the saved run.
+    pub metadata: RunMetadata,
+    /// Simulation time.
+    pub time: f64,
+    /// Number of events created before the checkpoint.
+    pub event_count: u64,
+    /// Number of events processed before the checkpoint.
+    #[serde(default)]
+    pub processed_events: u64,
+    /// Names of components by their identifiers.
+    pub components: BTreeMap<Id, String>,
+    /// States of components by their names.
+    pub states: BTreeMap<String, Value>,
+    rand: Pcg64,
+    last_ordered_time: f64,
+    events: Vec<SavedEvent>,
+    timers: Vec<SavedTimer>,
+    #[serde(default)]
+    inputs: BTreeMap<Id, u64>,
+    #[serde(default)]
+    warmup: SavedWarmup,
+}
+
+impl Checkpoint {
+    /// Returns the number of pending events.
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+
+    pub(crate) fn input_positions(&self) -> impl Iterator<Item = (Id, u64)> + '_ {
+        self.inputs.iter().map(|(id, taken)| (*id, *taken))
+    }
+
+    pub(crate) fn warmup(&self) -> &SavedWarmup {
+        &self.warmup
+    }
+}
+
+// State of the warmup period saved in the checkpoint.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SavedWarmup {
+    pub time: Option<f64>,
+    pub end_time: Option<f64>,
+    // Numbers of emitted and processed events at the end of warmup period.
+    pub event_counts: (u64, u64),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedEvent {
+    id: EventId,
+    time: f64,
+    src: Id,
+    dst: Id,
+    #[serde(default)]
+    priority: i64,
+    ordered: bool,
+    #[serde(rename = "type")]
+    type_name: String,
+    data: Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedTimer {
+    component: Id,
+    name: String,
+    event_id: EventId,
+}
+
+// State of the simulation saved in the checkpoint, with deserialized event payloads.
+pub(crate) struct SimulationCheckpoint {
+    pub metadata: RunMetadata,
+    pub time: f64,
+    pub event_count: u64,
+    pub components: BTreeMap<Id, String>,
+    pub rand: Pcg64,
+    pub last_ordered_time: f64,
+    // Pending events with the flags of ordered events, sorted by identifiers.
+    pub events: Vec<(Event, bool)>,
+    pub timers: Vec<(Id, String, EventId)>,
+}
+
+// Codecs of event payloads saved in checkpoints.
+#[derive(Clone)]
+pub(crate) struct CheckpointCodecs {
+    type_names: FxHashMap<TypeId, &'static str>,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+}
+
+impl CheckpointCodecs {
+    pub fn new() -> Self {
+        let mut codecs = Self {
+            type_names: FxHashMap::default(),
+            decoders: FxHashMap::default(),
+        };
+        codecs.register::<TimerFired>();
+        codecs
+    }
+
+    pub fn register<T: EventData + DeserializeOwned>(&mut self) {
+        let name = std::any::type_name::<T>();
+        self.type_names.insert(TypeId::of::<T>(), name);
+        self.decoders.insert(name, decode::<T>);
+    }
+
+    pub fn encode(
+        &self,
+        saved: SimulationCheckpoint,
+        states: BTreeMap<String, Value>,
+        inputs: BTreeMap<Id, u64>,
+        processed_events: u64,
+        warmup: SavedWarmup,
+    ) -> Checkpoint {
+        let events = saved
+            .events
+            .into_iter()
+            .map(|(event, ordered)| {
+                let type_name = self.type_names.get(&event.data.as_ref().type_id()).unwrap_or_else(|| {
+                    panic!(
+                        "Type of pending event {} is not registered via register_checkpoint_event",
+                        event.id
+                    )
+                });
+                SavedEvent {
+                    id: event.id,
+                    time: event.time,
+                    src: event.src,
+                    dst: event.dst,
+                    priority: event.priority,
+                    ordered,
+                    type_name: type_name.to_string(),
+                    data: serde_json::to_value(&event.data).unwrap(),
+                }
+            })
+            .collect();
+        let timers = saved
+            .timers
+            .into_iter()
+            .map(|(component, name, event_id)| SavedTimer {
+                component,
+                name,
+                event_id,
+            })
+            .collect();
+        Checkpoint {
+            metadata: saved.metadata,
+            time: saved.time,
+            event_count: saved.event_count,
+            processed_events,
+            components: saved.components,
+            states,
+            rand: saved.rand,
+            last_ordered_time: saved.last_ordered_time,
+            events,
+            timers,
+            inputs,
+            warmup,
+        }
+    }
+
+    pub fn decode(&self, checkpoint: &Checkpoint) -> SimulationCheckpoint {
+        let events = checkpoint
+            .events
+            .iter()
+            .map(|saved| {
+                let decode = self
+                    .decoders
+                    .get(saved.type_name.as_str())
+                    .unwrap_or_else(|| panic!("Event type {} is not registered", saved.type_name));
+                let data = decode(saved.data.clone()).expect("Failed to deserialize event from checkpoint");
+                let event = Event {
+                    id: saved.id,
+                    time: saved.time,
+                    src: saved.src,
+                    dst: saved.dst,
+                    priority: saved.priority,
+                    data,
+                };
+                (event, saved.ordered)
+            })
+            .collect();
+        let timers = checkpoint
+            .timers
+            .iter()
+            .map(|timer| (timer.component, timer.name.clone(), timer.event_id))
+            .collect();
+        SimulationCheckpoint {
+            metadata: checkpoint.metadata.clone(),
+            time: checkpoint.time,
+            event_count: checkpoint.event_count,
+            components: checkpoint.components.clone(),
+            rand: checkpoint.rand.clone(),
+            last_ordered_time: checkpoint.last_ordered_time,
+            events,
+            timers,
+        }
+    }
+}
+
+// Checkpoints saved automatically every `interval` processed events, the oldest ones are discarded.
+pub(crate) struct AutoCheckpoints {
+    interval: u64,
+    capacity: usize,
+    // Checkpoints with the numbers of processed events, ordered by the latter.
+    checkpoints: VecDeque<(u64, AutoCheckpoint)>,
+}
+
+// Automatic checkpoint along with the in-memory state which is not saved in checkpoints.
+#[derive(Clone)]
+pub(crate) struct AutoCheckpoint {
+    pub checkpoint: Checkpoint,
+    pub logical_clocks: Option<LogicalClocks>,
+}
+
+impl AutoCheckpoints {
+    pub fn new(interval: u64, capacity: usize) -> Self {
+        assert!(interval > 0, "Checkpoint interval must be positive, got {}", interval);
+        assert!(capacity > 0, "Number of checkpoints must be positive, got {}", capacity);
+        Self {
+            interval,
+            capacity,
+            checkpoints: VecDeque::new(),
+        }
+    }
+
+    // Returns true if the checkpoint should be saved after the specified number of processed events.
+    pub fn is_due(&self, processed: u64) -> bool {
+        processed.is_multiple_of(self.interval) && self.checkpoints.back().is_none_or(|(last, _)| *last < processed)
+    }
+
+    pub fn push(&mut self, processed: u64, checkpoint: AutoCheckpoint) {
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back((processed, checkpoint));
+    }
+
+    // Returns the latest checkpoint saved not after the specified number of processed events, and discards
+    // the later checkpoints, which are saved again on re-execution.
+    pub fn rewind(&mut self, processed: u64) -> Option<(u64, AutoCheckpoint)> {
+        let count = self.checkpoints.partition_point(|(saved, _)| *saved <= processed);
+        if count == 0 {
+            return None;
+        }
+        self.checkpoints.truncate(count);
+        self.checkpoints.back().cloned()
+    }
+}
@@ -0,0 +1,195 @@
+//! Export of event traces to Chrome trace format.
+//!
+//! The trace file recorded via [`Simulation::enable_trace_file`](crate::Simulation::enable_trace_file) can be
+//! converted to the Chrome `trace_event` JSON format via [`export_chrome_trace`] and explored visually in
+//! [Perfetto](https://ui.perfetto.dev) or `chrome://tracing`. The components become tracks (threads of a single
+//! process), and the events are shown as follows:
+//!
+//! - each processed event is an instant slice on the track of its destination, with the event type as its name
+//!   and the event identifier, source and payload as its arguments,
+//! - the interval from event emission to its processing or cancellation is an async slice of `in_flight`
+//!   category, which allows to see the pending activities, such as requests waiting for their delivery or timers.
+//!
+//! The simulation time is converted to the trace timestamps in microseconds via
+//! [`ChromeTraceConfig::time_scale`], by default the simulation time is assumed to be in seconds.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use rustc_hash::FxHashMap;
+use serde_json::{json, Value};
+
+use crate::event::EventId;
+use crate::trace_file::{read_trace_file, TraceEventKind, TraceFile, TraceFileRecord};
+
+/// Configuration of Chrome trace export.
+#[derive(Clone, Debug)]
+pub struct ChromeTraceConfig {
+    /// Number of trace microseconds in a unit of simulation time.
+    pub time_scale: f64,
+    /// Whether the intervals from event emission to its processing or cancellation are exported as async slices.
+    pub in_flight: bool,
+    /// Whether the event payloads are exported as slice arguments.
+    pub payloads: bool,
+}
+
+impl ChromeTraceConfig {
+    /// Creates a config exporting all information with simulation time in seconds.
+    pub fn new() -> Self {
+        Self {
+            time_scale: 1e6,
+            in_flight: true,
+            payloads: true,
+        }
+    }
+}
+
+impl Default for ChromeTraceConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts the trace file to Chrome trace file, see [module documentation](self).
+///
+/// Panics if the trace file cannot be read or the output file cannot be written.
+pub fn export_chrome_trace<P, Q>(trace_path: P, output_path: Q, config: &ChromeTraceConfig)
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let trace = read_trace_file(trace_path);
+    let file = File::create(output_path).expect("Failed to create Chrome trace file");
+    let mut writer = BufWriter::new(file);
+    write_chrome_trace(&trace, config, &mut writer);
+    writer.flush().expect("Failed to write Chrome trace file");
+}
+
+/// Writes the trace in Chrome trace format to the writer.
+///
+/// Panics if the writing fails.
+pub fn write_chrome_trace<W: Write>(trace: &TraceFile, config: &ChromeTraceConfig, writer: W) {
+    let mut builder = ChromeTraceBuilder {
+        config,
+        tracks: FxHashMap::default(),
+        events: Vec::new(),
+        emitted: FxHashMap::default(),
+    };
+    builder.events.push(json!({
+        "name": "process_name",
+        "ph": "M",
+        "pid": 1,
+        "args": {"name": "simulation"},
+    }));
+    for record in trace.records.iter() {
+        builder.add(record);
+    }
+    let output = json!({
+        "traceEvents": builder.events,
+        "displayTimeUnit": "ms",
+        "otherData": trace.metadata,
+    });
+    serde_json::to_writer(writer, &output).expect("Failed to write Chrome trace file");
+}
+
+struct ChromeTraceBuilder<'a> {
+    config: &'a ChromeTraceConfig,
+    // Track identifiers of components assigned in the order of their appearance.
+    tracks: FxHashMap<String, usize>,
+    events: Vec<Value>,
+    // Emission times of the events which are not processed or canceled yet.
+    emitted: FxHashMap<EventId, f64>,
+}
+
+impl ChromeTraceBuilder<'_> {
+    fn track(&mut self, name: &str) -> usize {
+        if let Some(tid) = self.tracks.get(name) {
+            return *tid;
+        }
+        let tid = self.tracks.len() + 1;
+        self.tracks.insert(name.to_owned(), tid);
+        self.events.push(json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": 1,
+            "tid": tid,
+            "args": {"name": name},
+        }));
+        self.events.push(json!({
+            "name": "thread_sort_index",
+            "ph": "M",
+            "pid": 1,
+            "tid": tid,
+            "args": {"sort_index": tid},
+        }));
+        tid
+    }
+
+    fn ts(&self, time: f64) -> f64 {
+        time * self.config.time_scale
+    }
+
+    fn add(&mut self, record: &TraceFileRecord) {
+        let dst = self.track(&record.dst);
+        self.track(&record.src);
+        match record.kind {
+            TraceEventKind::Emitted => {
+                self.emitted.insert(record.id, record.time);
+            }
+            TraceEventKind::Processed => {
+                let mut args = json!({"id": record.id, "src": record.src});
+                if self.config.payloads {
+                    if let Some(data) = record.data.as_ref() {
+                        args["data"] = data.clone();
+                    }
+                }
+                self.events.push(json!({
+                    "name": record.type_name,
+                    "cat": "event",
+                    "ph": "i",
+                    "s": "t",
+                    "ts": self.ts(record.time),
+                    "pid": 1,
+                    "tid": dst,
+                    "args": args,
+                }));
+                self.add_in_flight(record, dst, false);
+            }
+            TraceEventKind::Canceled => {
+                self.add_in_flight(record, dst, true);
+            }
+        }
+    }
+
+    // Adds the async slice from event emission to the time of record if the emission is recorded.
+    fn add_in_flight(&mut self, record: &TraceFileRecord, dst: usize, canceled: bool) {
+        let Some(emitted) = self.emitted.remove(&record.id) else {
+            return;
+        };
+        if !self.config.in_flight {
+            return;
+        }
+        let id = format!("0x{:x}", record.id);
+        self.events.push(json!({
+            "name": record.type_name,
+            "cat": "in_flight",
+            "ph": "b",
+            "id": id,
+            "ts": self.ts(emitted),
+            "pid": 1,
+            "tid": dst,
+            "args": {"src": record.src, "dst": record.dst},
+        }));
+        self.events.push(json!({
+            "name": record.type_name,
+            "cat": "in_flight",
+            "ph": "e",
+            "id": id,
+            "ts": self.ts(record.time),
+            "pid": 1,
+            "tid": dst,
+            "args": {"canceled": canceled},
+        }));
+    }
+}
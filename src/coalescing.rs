@@ -0,0 +1,101 @@
+//! Coalescing of event bursts.
+//!
+//! High-frequency models, e.g. sensors emitting updates, can produce many events of the same type destined for
+//! the same component within a very short time. When coalescing is enabled for the component via
+//! [`Simulation::enable_event_coalescing`](crate::Simulation::enable_event_coalescing), such events occurring within
+//! the configured time window after the first event of the burst are merged into a single delivery via
+//! [`EventHandler::on_batch`](crate::EventHandler::on_batch).
+//!
+//! The merged events are not stored in the pending event set, which reduces the overhead of processing the bursts.
+//! The whole burst is delivered at the time of its first event, while the events keep their original times.
+//! Thus the events of the burst can be processed before the events of other types destined for the same component
+//! which occur within the window, so the window should be small enough for such reordering to be acceptable.
+//!
+//! Only the events emitted with a delay via [`emit`](crate::SimulationContext::emit) and similar methods are
+//! coalesced, while the ordered and deferred events are always delivered separately.
+
+use std::any::TypeId;
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+use crate::event::{Event, EventId};
+
+// Pending burst of events, which is delivered together with its first event.
+#[derive(Clone)]
+struct Burst {
+    first_event: EventId,
+    first_time: f64,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Coalescing {
+    windows: FxHashMap<Id, f64>,
+    // Bursts which can be extended with new events by destination and event type.
+    open_bursts: FxHashMap<(Id, TypeId), Burst>,
+    // Events merged into bursts by the first event of the burst.
+    merged_events: FxHashMap<EventId, Vec<Event>>,
+}
+
+impl Coalescing {
+    pub fn set_window(&mut self, dst: Id, window: f64) {
+        assert!(window >= 0., "Coalescing window must be non-negative");
+        self.windows.insert(dst, window);
+    }
+
+    pub fn remove_window(&mut self, dst: Id) {
+        self.windows.remove(&dst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    // Merges the event into the open burst if possible, otherwise returns the event, which is added to the pending
+    // event set and starts a new burst.
+    pub fn coalesce(&mut self, event: Event) -> Option<Event> {
+        let Some(window) = self.windows.get(&event.dst) else {
+            return Some(event);
+        };
+        let key = (event.dst, event.data.type_id());
+        if let Some(burst) = self.open_bursts.get(&key) {
+            if event.time >= burst.first_time && event.time <= burst.first_time + window {
+                self.merged_events.entry(burst.first_event).or_default().push(event);
+                return None;
+            }
+        }
+        self.open_bursts.insert(
+            key,
+            Burst {
+                first_event: event.id,
+                first_time: event.time,
+            },
+        );
+        Some(event)
+    }
+
+    // Closes the burst started by the event and returns the merged events.
+    pub fn take_merged(&mut self, event: &Event) -> Vec<Event> {
+        let key = (event.dst, event.data.type_id());
+        if self.open_bursts.get(&key).is_some_and(|b| b.first_event == event.id) {
+            self.open_bursts.remove(&key);
+        }
+        self.merged_events.remove(&event.id).unwrap_or_default()
+    }
+
+    // Closes the burst started by the canceled event and returns the merged events.
+    pub fn on_event_canceled(&mut self, event_id: EventId) -> Vec<Event> {
+        self.open_bursts.retain(|_, burst| burst.first_event != event_id);
+        self.merged_events.remove(&event_id).unwrap_or_default()
+    }
+
+    // Discards the bursts and merged events, keeping the configured windows.
+    pub fn clear(&mut self) {
+        self.open_bursts.clear();
+        self.merged_events.clear();
+    }
+
+    pub fn merged_events(&self) -> impl Iterator<Item = &Event> {
+        self.merged_events.values().flatten()
+    }
+}
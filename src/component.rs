@@ -2,3 +2,33 @@
 
 /// Identifier of simulation component.
 pub type Id = u32;
+
+/// Reference to simulation component, which allows to detect the reuse of component identifier.
+///
+/// The identifiers of components removed via
+/// [`Simulation::remove_component`](crate::Simulation::remove_component) are reused by the components created
+/// later, so a stored [`Id`] of removed component can silently address a new component. The reference also stores
+/// the generation of the component identifier, which is incremented on each removal. The events emitted via
+/// [`SimulationContext::emit_to_ref`](crate::SimulationContext::emit_to_ref) are not delivered to the components
+/// created after the referenced one is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ComponentRef {
+    id: Id,
+    generation: u32,
+}
+
+impl ComponentRef {
+    pub(crate) fn new(id: Id, generation: u32) -> Self {
+        Self { id, generation }
+    }
+
+    /// Returns the identifier of referenced component.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the generation of the component identifier.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
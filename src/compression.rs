@@ -0,0 +1,183 @@
+//! Compressed streams of time-ordered records.
+//!
+//! Long simulation runs can produce large outputs consisting of records ordered by simulation time, such as event
+//! traces. If the `zstd` feature is enabled, such outputs can be written via [`BlockWriter`], which groups the
+//! records into blocks and compresses each block into a separate zstd frame preceded by a skippable frame with the
+//! time range and size of the block. The stream can be decompressed by the standard `zstd` tool to get the original
+//! records, while [`for_each_block`] decompresses only the blocks overlapping the requested time range and skips
+//! the others without decompressing them. The compressed streams can be detected via [`is_compressed`], which does
+//! not require the `zstd` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "zstd")]
+//! # {
+//! use std::io::{Cursor, Write};
+//! use simcore::compression::{for_each_block, BlockWriter};
+//!
+//! let mut data = Vec::new();
+//! let mut writer = BlockWriter::new(&mut data, 3, 2);
+//! for time in 0..10 {
+//!     writeln!(writer, "record at {}", time).unwrap();
+//!     writer.end_record(time as f64).unwrap();
+//! }
+//! writer.finish().unwrap();
+//! drop(writer);
+//!
+//! let mut records = Vec::new();
+//! for_each_block(Cursor::new(data), 4., 5., |block| {
+//!     records.extend(String::from_utf8(block.to_vec()).unwrap().lines().map(str::to_owned));
+//!     true
+//! })
+//! .unwrap();
+//! // the first block is always decompressed
+//! assert_eq!(records, ["record at 0", "record at 1", "record at 4", "record at 5"]);
+//! # }
+//! ```
+
+use std::io::BufRead;
+#[cfg(feature = "zstd")]
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+
+// Magic number of zstd skippable frame preceding each block.
+const BLOCK_INDEX_MAGIC: u32 = 0x184D2A50;
+
+// Size of block index stored in skippable frame: the times of the first and the last records and the size of the
+// compressed block.
+#[cfg(feature = "zstd")]
+const BLOCK_INDEX_SIZE: u32 = 24;
+
+/// Checks whether the stream starts with a compressed block without consuming it.
+pub fn is_compressed<R: BufRead>(reader: &mut R) -> std::io::Result<bool> {
+    let buf = reader.fill_buf()?;
+    Ok(buf.len() >= 4 && buf[..4] == BLOCK_INDEX_MAGIC.to_le_bytes())
+}
+
+/// Calls the function with the decompressed blocks overlapping the time range `[from, to]` until it returns false.
+///
+/// The first block is always decompressed, since it usually starts with a header of the stream.
+#[cfg(feature = "zstd")]
+pub fn for_each_block<R, F>(mut reader: R, from: f64, to: f64, mut f: F) -> std::io::Result<()>
+where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> bool,
+{
+    let mut first = true;
+    loop {
+        let mut index = [0u8; 8 + BLOCK_INDEX_SIZE as usize];
+        match reader.read_exact(&mut index) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        if index[..4] != BLOCK_INDEX_MAGIC.to_le_bytes() || index[4..8] != BLOCK_INDEX_SIZE.to_le_bytes() {
+            return Err(Error::new(ErrorKind::InvalidData, "Invalid block index"));
+        }
+        let field = |offset: usize| -> [u8; 8] { index[offset..offset + 8].try_into().unwrap() };
+        let first_time = f64::from_le_bytes(field(8));
+        let last_time = f64::from_le_bytes(field(16));
+        let size = u64::from_le_bytes(field(24));
+        if first_time > to && !first {
+            return Ok(());
+        }
+        if last_time < from && !first {
+            reader.seek(SeekFrom::Current(size as i64))?;
+            continue;
+        }
+        first = false;
+        let mut frame = vec![0u8; size as usize];
+        reader.read_exact(&mut frame)?;
+        let block = zstd::stream::decode_all(frame.as_slice())?;
+        if !f(&block) {
+            return Ok(());
+        }
+    }
+}
+
+/// Writer compressing the records in blocks, each preceded by the block index in skippable frame.
+///
+/// The record data is written via [`Write`] implementation and is completed by calling [`end_record`](Self::end_record)
+/// with the record time, which must not decrease. The data written before the first record, e.g. a header, belongs
+/// to the first block. The incomplete block is written by [`finish`](Self::finish) or when the writer is dropped.
+#[cfg(feature = "zstd")]
+pub struct BlockWriter<W: Write> {
+    inner: W,
+    level: i32,
+    block_records: usize,
+    buffer: Vec<u8>,
+    records: usize,
+    first_time: f64,
+    last_time: f64,
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> BlockWriter<W> {
+    /// Creates a writer with the specified zstd compression level and number of records per block.
+    pub fn new(inner: W, level: i32, block_records: usize) -> Self {
+        assert!(block_records > 0, "Block size must be positive, got {}", block_records);
+        Self {
+            inner,
+            level,
+            block_records,
+            buffer: Vec::new(),
+            records: 0,
+            first_time: f64::NAN,
+            last_time: f64::NAN,
+        }
+    }
+
+    /// Completes the record with the specified time and writes the block if it is full.
+    pub fn end_record(&mut self, time: f64) -> std::io::Result<()> {
+        if self.records == 0 {
+            self.first_time = time;
+        }
+        self.last_time = time;
+        self.records += 1;
+        if self.records == self.block_records {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the incomplete block and flushes the underlying writer.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.flush()
+    }
+
+    fn write_block(&mut self) -> std::io::Result<()> {
+        let frame = zstd::bulk::compress(&self.buffer, self.level)?;
+        self.inner.write_all(&BLOCK_INDEX_MAGIC.to_le_bytes())?;
+        self.inner.write_all(&BLOCK_INDEX_SIZE.to_le_bytes())?;
+        self.inner.write_all(&self.first_time.to_le_bytes())?;
+        self.inner.write_all(&self.last_time.to_le_bytes())?;
+        self.inner.write_all(&(frame.len() as u64).to_le_bytes())?;
+        self.inner.write_all(&frame)?;
+        self.buffer.clear();
+        self.records = 0;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Write for BlockWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // the blocks are written only when complete to keep the time ranges of blocks disjoint
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Drop for BlockWriter<W> {
+    fn drop(&mut self) {
+        // the errors are ignored like in BufWriter, call finish to handle them
+        let _ = self.finish();
+    }
+}
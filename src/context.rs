@@ -1,25 +1,46 @@
 //! Accessing simulation from components.
 
-use std::cell::RefCell;
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use rand::distributions::uniform::{SampleRange, SampleUniform};
+use rand::distributions::WeightedIndex;
 use rand::prelude::Distribution;
+use rand_distr::{Exp, LogNormal, Normal, Pareto, Weibull, Zipf};
 
 use crate::async_mode_enabled;
-use crate::component::Id;
-use crate::event::{Event, EventData, EventId};
+use crate::component::{ComponentRef, Id};
+use crate::emit_hook::{EmitHookFn, EmittedEvent};
+use crate::event::{Event, EventData, EventId, EventTypeId};
+use crate::link::Link;
+use crate::logical_clock::LogicalTime;
+use crate::metadata::RunMetadata;
+use crate::replay::short_type_name;
 use crate::state::SimulationState;
+use crate::time::{SimDuration, SimTime};
+use crate::timer::TimerFired;
 
 async_mode_enabled!(
-    use std::any::TypeId;
     use std::any::type_name;
+    use std::hash::Hash;
+    use std::panic::Location;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
 
     use futures::Future;
+    use serde::Serialize;
 
-    use crate::async_mode::event_future::EventFuture;
-    use crate::async_mode::EventKey;
+    use crate::async_mode::cancellation::{CancellationToken, TaskScope};
+    use crate::async_mode::event_future::{EventFuture, EventsFuture};
+    use crate::async_mode::mailbox::Mailbox;
+    use crate::async_mode::request::{Request, Response};
+    use crate::async_mode::retry::RetryPolicy;
+    use crate::async_mode::AwaitResult;
+    use crate::async_mode::{composite_key, EventKey, KeyedEvent};
     use crate::async_mode::timer_future::TimerFuture;
+    use crate::async_mode::work::Work;
+    use crate::event::{EventRef, TypedEvent};
 );
 
 /// A facade for accessing the simulation state and producing events from simulation components.
@@ -27,17 +48,33 @@ pub struct SimulationContext {
     id: Id,
     name: String,
     sim_state: Rc<RefCell<SimulationState>>,
+    // Set when some log rate limit is set, checked before borrowing the state in the log macros.
+    log_limited: Rc<Cell<bool>>,
+    time_scale: Cell<f64>,
+    time_scale_factors: RefCell<Vec<(u64, f64)>>,
+    next_time_scale_id: Cell<u64>,
+    emit_hook: RefCell<Option<EmitHookFn>>,
 }
 
 impl SimulationContext {
     pub(crate) fn new(id: Id, name: &str, sim_state: Rc<RefCell<SimulationState>>) -> Self {
+        let log_limited = sim_state.borrow().log_limited();
         Self {
             id,
             name: name.to_owned(),
             sim_state,
+            log_limited,
+            time_scale: Cell::new(1.),
+            time_scale_factors: RefCell::new(Vec::new()),
+            next_time_scale_id: Cell::new(0),
+            emit_hook: RefCell::new(None),
         }
     }
 
+    pub(crate) fn sim_state(&self) -> Rc<RefCell<SimulationState>> {
+        self.sim_state.clone()
+    }
+
     /// Returns the identifier of component associated with this context.
     ///
     /// # Examples
@@ -86,6 +123,230 @@ impl SimulationContext {
         self.sim_state.borrow().time()
     }
 
+    /// Returns the current simulation time as [`SimTime`], see [`time`](crate::time) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::time::{SimDuration, SimTime};
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.emit_self_in(SomeEvent {}, SimDuration::from_millis(20.));
+    /// sim.step();
+    /// assert_eq!(ctx.now(), SimTime::ZERO + SimDuration::from_millis(20.));
+    /// ```
+    pub fn now(&self) -> SimTime {
+        SimTime::from_secs(self.time())
+    }
+
+    /// Returns true if the log record of this component can be written at the current time according to the
+    /// [output rate limit](crate::rate_limit), and counts the record.
+    ///
+    /// This method is used internally in [`log_info!`](crate::log_info!) and other log macros.
+    #[doc(hidden)]
+    pub fn allow_log_record(&self) -> bool {
+        if !self.log_limited.get() {
+            return true;
+        }
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time();
+        state.allow_log_record(self.id, time)
+    }
+
+    /// Returns the number of the current tick in the discrete time mode.
+    ///
+    /// Panics if the time tick is not set.
+    /// See [`Simulation::set_time_tick`](crate::Simulation::set_time_tick) for examples.
+    pub fn current_tick(&self) -> u64 {
+        let state = self.sim_state.borrow();
+        let time_tick = state.time_tick().expect("Time tick is not set");
+        time_tick.tick_number(state.time())
+    }
+
+    /// Returns the metadata of the simulation run, e.g. for including it into the outputs written by component.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::{Simulation, SimulationContext};
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.set_run_label("scenario", "baseline");
+    /// let comp_ctx = sim.create_context("comp");
+    /// assert_eq!(comp_ctx.run_metadata(), sim.run_metadata());
+    /// ```
+    pub fn run_metadata(&self) -> RunMetadata {
+        self.sim_state.borrow().run_metadata().clone()
+    }
+
+    /// Returns the current time of logical clock of this component.
+    ///
+    /// Panics if logical clocks are not enabled.
+    /// See [`Simulation::enable_logical_clocks`](crate::Simulation::enable_logical_clocks) for examples.
+    pub fn logical_time(&self) -> LogicalTime {
+        self.sim_state.borrow().logical_time(self.id)
+    }
+
+    /// Returns the current time scale of this context, which multiplies the delays of emitted events.
+    ///
+    /// See [`scale_time`](Self::scale_time).
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale.get()
+    }
+
+    /// Multiplies the delays of events emitted via this context by the specified factor until the returned guard
+    /// is dropped, e.g. to model a slowed-down host.
+    ///
+    /// The scale applies to the relative delays passed to `emit...` methods and [`set_timer`](Self::set_timer),
+    /// and in async mode also to `sleep` durations and `request` timeouts. The following methods ignore the scale:
+    /// methods taking an absolute time, such as [`emit_at`](Self::emit_at), tick-based methods
+    /// ([`emit_ticks`](Self::emit_ticks), [`emit_at_tick`](Self::emit_at_tick),
+    /// [`set_timer_ticks`](Self::set_timer_ticks) and `sleep_ticks`), [`emit_over_link`](Self::emit_over_link),
+    /// whose delay is computed by the link, and the timeouts set via `with_timeout` on the futures.
+    ///
+    /// Nested scopes multiply their factors. Dropping the guard removes only its own factor, so the guards can be
+    /// dropped in any order. Note that in async mode the guard applies to all tasks using this context while it is
+    /// alive, use `with_time_scale` to scale the delays of a single task across `.await` points.
+    ///
+    /// Panics if `factor` is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// {
+    ///     let _slowdown = ctx.scale_time(2.);
+    ///     ctx.emit_self(SomeEvent {}, 1.);
+    ///     {
+    ///         let _more_slowdown = ctx.scale_time(1.5);
+    ///         assert_eq!(ctx.time_scale(), 3.);
+    ///         ctx.emit_self(SomeEvent {}, 1.);
+    ///     }
+    ///     assert_eq!(ctx.time_scale(), 2.);
+    /// }
+    /// ctx.emit_self(SomeEvent {}, 1.);
+    ///
+    /// let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    /// assert_eq!(times, vec![1., 2., 3.]);
+    /// ```
+    pub fn scale_time(&self, factor: f64) -> TimeScaleGuard<'_> {
+        assert!(factor > 0., "Time scale factor must be positive, got {}", factor);
+        TimeScaleGuard {
+            ctx: self,
+            id: self.push_time_scale(factor),
+        }
+    }
+
+    fn push_time_scale(&self, factor: f64) -> u64 {
+        let id = self.next_time_scale_id.get();
+        self.next_time_scale_id.set(id + 1);
+        self.time_scale_factors.borrow_mut().push((id, factor));
+        self.time_scale.set(self.time_scale.get() * factor);
+        id
+    }
+
+    fn pop_time_scale(&self, id: u64) {
+        let mut factors = self.time_scale_factors.borrow_mut();
+        factors.retain(|(factor_id, _)| *factor_id != id);
+        // recompute the product instead of dividing to avoid accumulating rounding errors
+        self.time_scale.set(factors.iter().map(|(_, factor)| factor).product());
+    }
+
+    fn scaled(&self, delay: f64) -> f64 {
+        delay * self.time_scale.get()
+    }
+
+    /// Installs the hook which is called for each event emitted via this context, replacing the previous hook.
+    ///
+    /// The hook receives this context and the emitted event, whose delay and payload can be changed by the hook,
+    /// see [`emit_hook`](crate::emit_hook) module. The delay passed to the hook is already scaled by the
+    /// [time scale](Self::scale_time), and for the events emitted at absolute time it is the delay until this time.
+    /// The events emitted from the hook itself, e.g. copies of the emitted event, are not passed to the hook.
+    /// The hook is not applied to the events emitted via other contexts of the same component and to the timer
+    /// events.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Message {
+    ///     hops: u32,
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let node1 = sim.create_context("node1");
+    /// let node2 = sim.create_context("node2");
+    ///
+    /// // adds random jitter to all messages and counts their hops
+    /// node1.set_emit_hook(|ctx, event| {
+    ///     event.delay += ctx.gen_range(0. ..0.1);
+    ///     if let Some(message) = event.data.downcast_mut::<Message>() {
+    ///         message.hops += 1;
+    ///     }
+    /// });
+    /// node1.emit(Message { hops: 0 }, node2.id(), 1.);
+    /// node1.emit(Message { hops: 3 }, node2.id(), 2.);
+    ///
+    /// let events = sim.dump_events();
+    /// assert!(events[0].time > 1. && events[1].time > 2.);
+    /// assert_eq!(events[1].data.downcast_ref::<Message>().unwrap().hops, 4);
+    /// ```
+    pub fn set_emit_hook<F>(&self, hook: F)
+    where
+        F: FnMut(&SimulationContext, &mut EmittedEvent) + 'static,
+    {
+        *self.emit_hook.borrow_mut() = Some(Box::new(hook));
+    }
+
+    /// Removes the hook installed via [`set_emit_hook`](Self::set_emit_hook).
+    pub fn clear_emit_hook(&self) {
+        self.emit_hook.borrow_mut().take();
+    }
+
+    // Boxes the event payload and applies the emission hook, returns the payload and delay.
+    fn hook_event<T: EventData>(&self, data: T, dst: Id, delay: f64) -> (Box<dyn EventData>, f64) {
+        let data: Box<dyn EventData> = Box::new(data);
+        // the hook is taken out while it is called, so the events emitted from the hook are not passed to it
+        let Some(mut hook) = self.emit_hook.borrow_mut().take() else {
+            return (data, delay);
+        };
+        let mut event = EmittedEvent::new(self.id, dst, delay, data);
+        hook(self, &mut event);
+        self.emit_hook.borrow_mut().get_or_insert(hook);
+        (event.data, event.delay)
+    }
+
+    // Applies the emission hook to the event emitted at absolute time, returns the payload and time.
+    fn hook_event_at<T: EventData>(&self, data: T, dst: Id, time: f64) -> (Box<dyn EventData>, f64) {
+        let now = self.time();
+        let delay = time - now;
+        let (data, hooked_delay) = self.hook_event(data, dst, delay);
+        // the original time is kept if the delay is not changed to avoid floating-point errors
+        let time = if hooked_delay == delay {
+            time
+        } else {
+            now + hooked_delay
+        };
+        (data, time)
+    }
+
     /// Returns a random float in the range _[0, 1)_
     /// using the simulation-wide random number generator.
     ///
@@ -132,16 +393,197 @@ impl SimulationContext {
         self.sim_state.borrow_mut().sample_from_distribution(dist)
     }
 
+    /// Returns a random value from the exponential distribution with the specified rate
+    /// using the simulation-wide random number generator.
+    ///
+    /// The mean of the distribution is `1 / rate`. Panics if the rate is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let n = 10000;
+    /// let mean = (0..n).map(|_| ctx.sample_exponential(4.)).sum::<f64>() / n as f64;
+    /// assert!((mean - 0.25).abs() < 0.01);
+    /// ```
+    pub fn sample_exponential(&self, rate: f64) -> f64 {
+        assert!(
+            rate > 0.,
+            "Rate of exponential distribution must be positive, got {}",
+            rate
+        );
+        self.sample_from_distribution(&Exp::new(rate).unwrap())
+    }
+
+    /// Returns a random value from the normal distribution with the specified mean and standard deviation
+    /// using the simulation-wide random number generator.
+    ///
+    /// Panics if the standard deviation is negative or not finite.
+    pub fn sample_normal(&self, mean: f64, std_dev: f64) -> f64 {
+        assert!(
+            std_dev >= 0.,
+            "Standard deviation of normal distribution must be non-negative, got {}",
+            std_dev
+        );
+        let dist = Normal::new(mean, std_dev)
+            .unwrap_or_else(|err| panic!("Invalid parameters of normal distribution: {}", err));
+        self.sample_from_distribution(&dist)
+    }
+
+    /// Returns a random value from the log-normal distribution, whose logarithm is normally distributed with the
+    /// specified mean `mu` and standard deviation `sigma`, using the simulation-wide random number generator.
+    ///
+    /// Panics if `sigma` is negative or not finite.
+    pub fn sample_lognormal(&self, mu: f64, sigma: f64) -> f64 {
+        assert!(
+            sigma >= 0.,
+            "Standard deviation of log-normal distribution must be non-negative, got {}",
+            sigma
+        );
+        let dist = LogNormal::new(mu, sigma)
+            .unwrap_or_else(|err| panic!("Invalid parameters of log-normal distribution: {}", err));
+        self.sample_from_distribution(&dist)
+    }
+
+    /// Returns a random value from the Pareto distribution with the specified scale (minimum value) and shape
+    /// using the simulation-wide random number generator.
+    ///
+    /// Panics if the scale or shape is not positive.
+    pub fn sample_pareto(&self, scale: f64, shape: f64) -> f64 {
+        let dist = Pareto::new(scale, shape)
+            .unwrap_or_else(|err| panic!("Invalid parameters of Pareto distribution: {}", err));
+        self.sample_from_distribution(&dist)
+    }
+
+    /// Returns a random value from the Weibull distribution with the specified scale and shape
+    /// using the simulation-wide random number generator.
+    ///
+    /// Panics if the scale or shape is not positive.
+    pub fn sample_weibull(&self, scale: f64, shape: f64) -> f64 {
+        let dist = Weibull::new(scale, shape)
+            .unwrap_or_else(|err| panic!("Invalid parameters of Weibull distribution: {}", err));
+        self.sample_from_distribution(&dist)
+    }
+
+    /// Returns a random rank in the range _[1, n]_ from the Zipf distribution with the specified exponent
+    /// using the simulation-wide random number generator.
+    ///
+    /// The probability of rank `k` is proportional to `1 / k^exponent`, e.g. for modeling the popularity of items.
+    /// Panics if `n` is zero or the exponent is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let mut counts = vec![0; 10];
+    /// for _ in 0..10000 {
+    ///     let rank = ctx.sample_zipf(10, 1.2);
+    ///     counts[rank as usize - 1] += 1;
+    /// }
+    /// // the first item is the most popular
+    /// assert!(counts[0] > counts[1] && counts[1] > counts[9]);
+    /// ```
+    pub fn sample_zipf(&self, n: u64, exponent: f64) -> u64 {
+        let dist =
+            Zipf::new(n, exponent).unwrap_or_else(|err| panic!("Invalid parameters of Zipf distribution: {}", err));
+        self.sample_from_distribution::<f64, _>(&dist) as u64
+    }
+
     /// Returns a random alphanumeric string of specified length
     /// using the simulation-wide random number generator.
     pub fn random_string(&self, len: usize) -> String {
         self.sim_state.borrow_mut().random_string(len)
     }
 
+    /// Returns a uniformly chosen random element of the slice
+    /// using the simulation-wide random number generator.
+    ///
+    /// Panics if the slice is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let replicas = ["replica-1", "replica-2", "replica-3"];
+    /// let replica = ctx.choose(&replicas);
+    /// assert!(replicas.contains(replica));
+    /// ```
+    pub fn choose<'a, T>(&self, items: &'a [T]) -> &'a T {
+        assert!(!items.is_empty(), "Cannot choose from empty slice");
+        &items[self.gen_range(0..items.len())]
+    }
+
+    /// Returns a random element of the slice chosen with probability proportional to its weight
+    /// using the simulation-wide random number generator.
+    ///
+    /// Panics if the slice is empty, the numbers of items and weights differ, some weight is negative or all weights
+    /// are zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let operations = ["read", "write", "delete"];
+    /// let mut writes = 0;
+    /// for _ in 0..1000 {
+    ///     match *ctx.choose_weighted(&operations, &[0.7, 0.3, 0.]) {
+    ///         "write" => writes += 1,
+    ///         "delete" => panic!("Operation with zero weight is chosen"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// assert!(writes > 250 && writes < 350);
+    /// ```
+    pub fn choose_weighted<'a, T>(&self, items: &'a [T], weights: &[f64]) -> &'a T {
+        assert_eq!(
+            items.len(),
+            weights.len(),
+            "Numbers of items and weights must be equal, got {} items and {} weights",
+            items.len(),
+            weights.len()
+        );
+        assert!(!items.is_empty(), "Cannot choose from empty slice");
+        let dist = WeightedIndex::new(weights).unwrap_or_else(|err| panic!("Invalid weights: {}", err));
+        &items[self.sample_from_distribution(&dist)]
+    }
+
+    /// Shuffles the slice in place using the simulation-wide random number generator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let mut peers: Vec<u32> = (0..10).collect();
+    /// ctx.shuffle(&mut peers);
+    /// peers.sort();
+    /// assert_eq!(peers, (0..10).collect::<Vec<_>>());
+    /// ```
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        self.sim_state.borrow_mut().shuffle(items);
+    }
+
     /// Creates new event with specified payload, destination and delay, returns event id.
     ///
     /// The event time will be `current_time + delay`.
     /// It is not allowed to create events before the current simulation time, so `delay` should be non-negative.
+    /// Events are processed in the order of their time, then their [priority](Self::emit_with_priority) and then
+    /// their creation, i.e. their ids. The events emitted via this method have the priority 0, so events with equal
+    /// time and default priority are processed in the order of their ids.
     ///
     /// The event source will be equal to [`id`](Self::id).
     /// See [`emit_as`](Self::emit_as) if you want to emit event on behalf of some other component.
@@ -204,7 +646,221 @@ impl SimulationContext {
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_event(data, self.id, dst, delay)
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state.borrow_mut().add_boxed_event(data, self.id, dst, delay)
+    }
+
+    /// Creates new event with specified payload, destination, delay and priority, returns event id.
+    ///
+    /// This is a variant of [`emit`](Self::emit), which controls the processing order of events with equal time.
+    /// Such events are processed in the order of their priorities, with smaller values first, and then in the order
+    /// of their creation. The events emitted via other methods have the priority 0, so negative priorities can be
+    /// used to process the event before them and positive ones to process it after them.
+    ///
+    /// The priority does not affect the events with different times.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Update {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Report {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let comp1_ctx = sim.create_context("comp1");
+    /// let comp2_ctx = sim.create_context("comp2");
+    ///
+    /// // the report is processed after all updates at the same time
+    /// comp1_ctx.emit_with_priority(Report {}, comp2_ctx.id(), 1., 1);
+    /// comp1_ctx.emit(Update {}, comp2_ctx.id(), 1.);
+    /// comp1_ctx.emit(Update {}, comp2_ctx.id(), 1.);
+    /// // the priority does not matter for events with different times
+    /// comp1_ctx.emit_with_priority(Update {}, comp2_ctx.id(), 0.5, 1);
+    ///
+    /// let events: Vec<_> = sim.dump_events().iter().map(|e| (e.id, e.priority)).collect();
+    /// assert_eq!(events, vec![(3, 1), (1, 0), (2, 0), (0, 1)]);
+    /// ```
+    pub fn emit_with_priority<T>(&self, data: T, dst: Id, delay: f64, priority: i64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event_with_priority(data, self.id, dst, delay, priority)
+    }
+
+    /// Creates new event with specified payload and destination, which occurs at the specified absolute
+    /// simulation time, returns event id.
+    ///
+    /// This is a variant of [`emit`](Self::emit), which is convenient when the event times are known in advance,
+    /// e.g. are read from a trace. The time is not affected by the [time scale](Self::scale_time) and the event
+    /// occurs exactly at the specified time, unless it is modified by routers or time tick.
+    ///
+    /// Panics if the time is earlier than the current simulation time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_ctx = sim.create_context("server");
+    /// sim.step_until_time(0.1);
+    ///
+    /// // the timestamps are not converted to delays relative to the current time
+    /// for time in [0.3, 0.7, 1.9] {
+    ///     client_ctx.emit_at(Request {}, server_ctx.id(), time);
+    /// }
+    /// let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    /// assert_eq!(times, vec![0.3, 0.7, 1.9]);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_ctx = sim.create_context("server");
+    /// sim.step_until_time(2.);
+    /// client_ctx.emit_at(Request {}, server_ctx.id(), 1.); // will panic because the time is in the past
+    /// ```
+    pub fn emit_at<T>(&self, data: T, dst: Id, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, time) = self.hook_event_at(data, dst, time);
+        self.sim_state.borrow_mut().add_boxed_event_at(data, self.id, dst, time)
+    }
+
+    /// Creates new event with specified payload and destination, which occurs after the specified number of
+    /// ticks from the current tick in the discrete time mode, returns event id.
+    ///
+    /// The event time is computed from the integer tick number, so it is exact. The time is not affected by the
+    /// [time scale](Self::scale_time). See [`tick`](crate::tick) module.
+    ///
+    /// Panics if the time tick is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Round {}
+    ///
+    /// let mut sim = Simulation::with_integer_ticks(123);
+    /// let ctx1 = sim.create_context("comp1");
+    /// let ctx2 = sim.create_context("comp2");
+    /// ctx1.emit_ticks(Round {}, ctx2.id(), 3);
+    /// sim.step();
+    /// assert_eq!(sim.time(), 3.);
+    /// assert_eq!(ctx2.current_tick(), 3);
+    /// ```
+    pub fn emit_ticks<T>(&self, data: T, dst: Id, delay: u64) -> EventId
+    where
+        T: EventData,
+    {
+        let time = self.sim_state.borrow().time_after_ticks(delay);
+        self.emit_at(data, dst, time)
+    }
+
+    /// Creates new event with specified payload and destination, which occurs at the specified tick in the discrete
+    /// time mode, returns event id.
+    ///
+    /// The time is not affected by the [time scale](Self::scale_time).
+    ///
+    /// Panics if the time tick is not set or the tick is earlier than the current one.
+    /// See [`emit_ticks`](Self::emit_ticks).
+    pub fn emit_at_tick<T>(&self, data: T, dst: Id, tick: u64) -> EventId
+    where
+        T: EventData,
+    {
+        let time = self.sim_state.borrow().time_of_tick(tick);
+        self.emit_at(data, dst, time)
+    }
+
+    /// Creates new event with specified payload and destination, which occurs after the specified typed delay,
+    /// returns event id.
+    ///
+    /// This is a variant of [`emit`](Self::emit) accepting [`SimDuration`], see [`time`](crate::time) module.
+    pub fn emit_in<T>(&self, data: T, dst: Id, delay: SimDuration) -> EventId
+    where
+        T: EventData,
+    {
+        self.emit(data, dst, delay.as_secs())
+    }
+
+    /// Creates new event with specified payload and destination, which occurs at the specified typed time,
+    /// returns event id.
+    ///
+    /// This is a variant of [`emit_at`](Self::emit_at) accepting [`SimTime`], see [`time`](crate::time) module.
+    pub fn emit_at_time<T>(&self, data: T, dst: Id, time: SimTime) -> EventId
+    where
+        T: EventData,
+    {
+        self.emit_at(data, dst, time.as_secs())
+    }
+
+    /// Creates new event with specified payload, destination referenced by [`ComponentRef`] and delay,
+    /// returns event id.
+    ///
+    /// Unlike [`emit`](Self::emit), the event is not delivered if the referenced component is removed via
+    /// [`Simulation::remove_component`](crate::Simulation::remove_component) before the event occurs,
+    /// even if its identifier is reused by a new component. Such event is logged as undelivered.
+    ///
+    /// Panics if the referenced component is already removed, i.e. the generation stored in the reference does not
+    /// match the current generation of its identifier, which can be reused by a new component.
+    ///
+    /// See [`Simulation::remove_component`](crate::Simulation::remove_component) for examples.
+    pub fn emit_to_ref<T>(&self, data: T, dst: ComponentRef, delay: f64) -> EventId
+    where
+        T: EventData,
+    {
+        // the stale reference is rejected before the emission hook is applied to the event
+        self.sim_state.borrow().assert_current_ref(self.id, dst);
+        let (data, delay) = self.hook_event(data, dst.id(), self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event_to_ref(data, self.id, dst, delay)
+    }
+
+    /// See [`emit_to_ref`](Self::emit_to_ref) and [`emit_at`](Self::emit_at).
+    pub fn emit_to_ref_at<T>(&self, data: T, dst: ComponentRef, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        self.sim_state.borrow().assert_current_ref(self.id, dst);
+        let (data, time) = self.hook_event_at(data, dst.id(), time);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event_to_ref_at(data, self.id, dst, time)
+    }
+
+    /// Returns the reference to component associated with this context.
+    pub fn component_ref(&self) -> ComponentRef {
+        self.sim_state.borrow().component_ref(self.id)
+    }
+
+    /// Returns true if the referenced component is removed via
+    /// [`Simulation::remove_component`](crate::Simulation::remove_component).
+    pub fn is_removed(&self, component: ComponentRef) -> bool {
+        !self.sim_state.borrow().is_current_ref(component)
     }
 
     /// This and all other `emit_ordered...` functions are special variants of normal `emit_...` functions
@@ -254,7 +910,30 @@ impl SimulationContext {
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_ordered_event(data, self.id, dst, delay)
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event(data, self.id, dst, delay)
+    }
+
+    /// See [`emit_ordered`](Self::emit_ordered) and [`emit_at`](Self::emit_at).
+    pub fn emit_ordered_at<T>(&self, data: T, dst: Id, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, time) = self.hook_event_at(data, dst, time);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event_at(data, self.id, dst, time)
+    }
+
+    /// This is an ordered variant of [`emit_ticks`](Self::emit_ticks), see [`emit_ordered`](Self::emit_ordered).
+    pub fn emit_ordered_ticks<T>(&self, data: T, dst: Id, delay: u64) -> EventId
+    where
+        T: EventData,
+    {
+        let time = self.sim_state.borrow().time_after_ticks(delay);
+        self.emit_ordered_at(data, dst, time)
     }
 
     /// Checks whether it is safe to emit an ordered event with the specified delay.
@@ -288,13 +967,18 @@ impl SimulationContext {
     /// assert!(!comp1_ctx.can_emit_ordered(0.3)); // 1.3 < 1.5
     /// ```
     pub fn can_emit_ordered(&self, delay: f64) -> bool {
-        self.sim_state.borrow().can_add_ordered_event(delay)
+        self.sim_state.borrow().can_add_ordered_event(self.scaled(delay))
     }
 
     /// Creates new immediate (zero-delay) event with specified payload and destination, returns event id.
     ///
     /// This is a shorthand for [`emit`](Self::emit) with zero delay.
     ///
+    /// Zero-delay events are stored in a FIFO queue instead of the heap used for other events, which makes emitting
+    /// and processing them cheaper. This does not change the event order by time, then priority and then id:
+    /// immediate events have the priority 0 and are processed after all previously created events with the current
+    /// time and the priority not greater than 0, in the order of their creation.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -335,19 +1019,122 @@ impl SimulationContext {
     /// sim.step();
     /// assert_eq!(sim.time(), 0.0);
     /// ```
-    pub fn emit_now<T>(&self, data: T, dst: Id) -> EventId
+    pub fn emit_now<T>(&self, data: T, dst: Id) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, delay) = self.hook_event(data, dst, 0.);
+        self.sim_state.borrow_mut().add_boxed_event(data, self.id, dst, delay)
+    }
+
+    /// See [`emit_ordered`](Self::emit_ordered).
+    pub fn emit_ordered_now<T>(&self, data: T, dst: Id) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, delay) = self.hook_event(data, dst, 0.);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event(data, self.id, dst, delay)
+    }
+
+    /// Creates new event with specified payload and destination and the configured default delay, returns event id.
+    ///
+    /// The delay is selected from the delay profiles configured for the event type or the groups of this component
+    /// and the destination, see [`delay`](crate::delay) module for details.
+    ///
+    /// Panics if there is no matching delay profile.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::delay::DelayProfile;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Heartbeat {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_ctx = sim.create_context("server");
+    /// sim.set_component_group("client", "clients");
+    /// sim.set_component_group("server", "servers");
+    /// sim.set_group_delay("clients", "servers", DelayProfile::constant(0.5));
+    /// sim.set_event_delay::<Heartbeat>(DelayProfile::constant(0.1));
+    ///
+    /// client_ctx.emit_with_default_delay(Request {}, server_ctx.id());
+    /// client_ctx.emit_with_default_delay(Heartbeat {}, server_ctx.id());
+    /// let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    /// assert_eq!(times, vec![0.1, 0.5]);
+    /// ```
+    pub fn emit_with_default_delay<T>(&self, data: T, dst: Id) -> EventId
+    where
+        T: EventData,
+    {
+        let delay = self.sim_state.borrow_mut().default_delay(&data, self.id, dst);
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state.borrow_mut().add_boxed_event(data, self.id, dst, delay)
+    }
+
+    /// Creates new event with specified payload and destination, which is transmitted over the link with the
+    /// specified parameters, returns event id.
+    ///
+    /// The event is delivered after the transmission time of the payload with the specified `size` plus the link
+    /// latency. If the link is [serialized](Link::serialized), the transmission starts after the previous
+    /// serialized transmission from this component to the destination is finished. The delay is not affected by
+    /// the [time scale](Self::scale_time). See [`link`](crate::link) module for details.
+    ///
+    /// Panics if the size is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::link::Link;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Packet {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_ctx = sim.create_context("server");
+    ///
+    /// // 100 units of size per time unit with latency 0.5
+    /// let link = Link::new(100., 0.5);
+    /// client_ctx.emit_over_link(Packet {}, server_ctx.id(), 100., &link);
+    /// client_ctx.emit_over_link(Packet {}, server_ctx.id(), 50., &link);
+    ///
+    /// // the serialized transmissions are performed one after another
+    /// let link = Link::serialized(100., 0.5);
+    /// client_ctx.emit_over_link(Packet {}, server_ctx.id(), 100., &link);
+    /// client_ctx.emit_over_link(Packet {}, server_ctx.id(), 50., &link);
+    /// assert_eq!(client_ctx.link_busy_until(server_ctx.id()), Some(1.5));
+    ///
+    /// let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    /// assert_eq!(times, vec![1., 1.5, 1.5, 2.]);
+    /// ```
+    pub fn emit_over_link<T>(&self, data: T, dst: Id, size: f64, link: &Link) -> EventId
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_event(data, self.id, dst, 0.)
+        let delay = self.sim_state.borrow_mut().link_delay(self.id, dst, size, link);
+        let (data, delay) = self.hook_event(data, dst, delay);
+        self.sim_state.borrow_mut().add_boxed_event(data, self.id, dst, delay)
     }
 
-    /// See [`emit_ordered`](Self::emit_ordered).
-    pub fn emit_ordered_now<T>(&self, data: T, dst: Id) -> EventId
-    where
-        T: EventData,
-    {
-        self.sim_state.borrow_mut().add_ordered_event(data, self.id, dst, 0.)
+    /// Returns the time when the last serialized transmission from this component to the destination is finished,
+    /// `None` if there were no such transmissions.
+    ///
+    /// The returned time can be in the past if the channel is idle.
+    ///
+    /// See [`emit_over_link`](Self::emit_over_link).
+    pub fn link_busy_until(&self, dst: Id) -> Option<f64> {
+        self.sim_state.borrow().link_busy_until(self.id, dst)
     }
 
     /// Creates new event for itself with specified payload and delay, returns event id.
@@ -403,7 +1190,37 @@ impl SimulationContext {
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_event(data, self.id, self.id, delay)
+        let (data, delay) = self.hook_event(data, self.id, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event(data, self.id, self.id, delay)
+    }
+
+    /// See [`emit_self`](Self::emit_self) and [`emit_at`](Self::emit_at).
+    pub fn emit_self_at<T>(&self, data: T, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, time) = self.hook_event_at(data, self.id, time);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event_at(data, self.id, self.id, time)
+    }
+
+    /// This is a variant of [`emit_ticks`](Self::emit_ticks) for the case when event is sent to the component itself.
+    pub fn emit_self_ticks<T>(&self, data: T, delay: u64) -> EventId
+    where
+        T: EventData,
+    {
+        self.emit_ticks(data, self.id, delay)
+    }
+
+    /// This is a variant of [`emit_in`](Self::emit_in) for the case when event is sent to the component itself.
+    pub fn emit_self_in<T>(&self, data: T, delay: SimDuration) -> EventId
+    where
+        T: EventData,
+    {
+        self.emit_in(data, self.id, delay)
     }
 
     /// See [`Self::emit_ordered`].
@@ -411,15 +1228,27 @@ impl SimulationContext {
     where
         T: EventData,
     {
+        let (data, delay) = self.hook_event(data, self.id, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event(data, self.id, self.id, delay)
+    }
+
+    /// See [`emit_ordered`](Self::emit_ordered) and [`emit_at`](Self::emit_at).
+    pub fn emit_ordered_self_at<T>(&self, data: T, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, time) = self.hook_event_at(data, self.id, time);
         self.sim_state
             .borrow_mut()
-            .add_ordered_event(data, self.id, self.id, delay)
+            .add_boxed_ordered_event_at(data, self.id, self.id, time)
     }
 
     /// Creates new immediate event for itself with specified payload, returns event id.
     ///
     /// This is a shorthand for [`emit`](Self::emit) with event destination equals [`id`](Self::id)
-    /// and zero delay.
+    /// and zero delay. See [`emit_now`](Self::emit_now) for details on processing of immediate events.
     ///
     /// # Examples
     ///
@@ -470,7 +1299,10 @@ impl SimulationContext {
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_event(data, self.id, self.id, 0.)
+        let (data, delay) = self.hook_event(data, self.id, 0.);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_event(data, self.id, self.id, delay)
     }
 
     /// See [`emit_ordered`](Self::emit_ordered).
@@ -478,15 +1310,21 @@ impl SimulationContext {
     where
         T: EventData,
     {
+        let (data, delay) = self.hook_event(data, self.id, 0.);
         self.sim_state
             .borrow_mut()
-            .add_ordered_event(data, self.id, self.id, 0.)
+            .add_boxed_ordered_event(data, self.id, self.id, delay)
     }
 
     /// Creates new event with specified payload, source, destination and delay, returns event id.
     ///
     /// This is an extended version of [`emit`](Self::emit) for special cases when the event should be emitted
-    /// on behalf of another component.
+    /// on behalf of another component, e.g. by a proxy component forwarding events while preserving their original
+    /// source.
+    ///
+    /// Emitting events on behalf of other components requires the capability granted to the component via
+    /// [`Simulation::allow_emit_as`](crate::Simulation::allow_emit_as), so that no component can spoof the event
+    /// source by mistake. Panics if the capability is not granted and `src` differs from the component id.
     ///
     /// ```rust
     /// use std::cell::RefCell;
@@ -525,6 +1363,7 @@ impl SimulationContext {
     /// let comp2 = Rc::new(RefCell::new(Component { ctx: sim.create_context("comp2") }));
     /// let comp2_id = sim.add_handler("comp2", comp2);
     /// let mut other_ctx = sim.create_context("other");
+    /// sim.allow_emit_as("other");
     /// other_ctx.emit_as(SomeEvent{ some_field: 8 }, comp1_id, comp2_id, 2.4);
     /// sim.step();
     /// assert_eq!(sim.time(), 2.4);
@@ -533,15 +1372,154 @@ impl SimulationContext {
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_event(data, src, dst, delay)
+        self.check_emit_as(src);
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state.borrow_mut().add_boxed_event(data, src, dst, delay)
     }
 
-    /// See [`emit_ordered`](Self::emit_ordered).
+    /// See [`emit_ordered`](Self::emit_ordered) and [`emit_as`](Self::emit_as).
     pub fn emit_ordered_as<T>(&self, data: T, src: Id, dst: Id, delay: f64) -> EventId
     where
         T: EventData,
     {
-        self.sim_state.borrow_mut().add_ordered_event(data, src, dst, delay)
+        self.check_emit_as(src);
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event(data, src, dst, delay)
+    }
+
+    /// See [`emit_as`](Self::emit_as) and [`emit_at`](Self::emit_at).
+    pub fn emit_as_at<T>(&self, data: T, src: Id, dst: Id, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        self.check_emit_as(src);
+        let (data, time) = self.hook_event_at(data, dst, time);
+        self.sim_state.borrow_mut().add_boxed_event_at(data, src, dst, time)
+    }
+
+    /// See [`emit_ordered`](Self::emit_ordered), [`emit_as`](Self::emit_as) and [`emit_at`](Self::emit_at).
+    pub fn emit_ordered_as_at<T>(&self, data: T, src: Id, dst: Id, time: f64) -> EventId
+    where
+        T: EventData,
+    {
+        self.check_emit_as(src);
+        let (data, time) = self.hook_event_at(data, dst, time);
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_ordered_event_at(data, src, dst, time)
+    }
+
+    /// Creates new event with specified payload and destination, which occurs after the specified delay since
+    /// the processing of another event, returns event id.
+    ///
+    /// The preceding event `after` can be a pending event, including the event emitted via this method, or the event
+    /// which is currently processed. This allows to define chains of dependent events without intermediate handler
+    /// code. If the preceding event is canceled, the dependent event is canceled too. The event id is assigned
+    /// immediately, so the dependent event can be canceled before its time is resolved.
+    ///
+    /// If the preceding event is already processed (but is not the current one), the event is never emitted.
+    /// The dependent event is not returned by [`Simulation::dump_events`](crate::Simulation::dump_events) until
+    /// the preceding event is processed. It is matched by the predicates passed to
+    /// [`Simulation::cancel_events`](crate::Simulation::cancel_events) and similar methods, but its time is NaN
+    /// until the preceding event is processed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::{cast, Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Step {
+    ///     name: String,
+    /// }
+    ///
+    /// struct Recorder {
+    ///     steps: Vec<(f64, String)>,
+    /// }
+    ///
+    /// impl EventHandler for Recorder {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Step { name } => {
+    ///                 self.steps.push((event.time, name));
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("ctx");
+    /// let recorder = Rc::new(RefCell::new(Recorder { steps: Vec::new() }));
+    /// let recorder_id = sim.add_handler("recorder", recorder.clone());
+    ///
+    /// let step = |name: &str| Step { name: name.to_owned() };
+    /// let download = ctx.emit(step("download"), recorder_id, 5.);
+    /// let unpack = ctx.emit_after(step("unpack"), recorder_id, download, 2.);
+    /// ctx.emit_after(step("install"), recorder_id, unpack, 1.);
+    /// sim.step_until_no_events();
+    ///
+    /// let steps: Vec<_> = recorder.borrow().steps.iter().map(|(time, name)| (*time, name.clone())).collect();
+    /// assert_eq!(
+    ///     steps,
+    ///     vec![(5., "download".to_owned()), (7., "unpack".to_owned()), (8., "install".to_owned())]
+    /// );
+    /// ```
+    pub fn emit_after<T>(&self, data: T, dst: Id, after: EventId, delay: f64) -> EventId
+    where
+        T: EventData,
+    {
+        let (data, delay) = self.hook_event(data, dst, self.scaled(delay));
+        self.sim_state
+            .borrow_mut()
+            .add_boxed_deferred_event(data, self.id, dst, after, delay)
+    }
+
+    fn check_emit_as(&self, src: Id) {
+        assert!(
+            src == self.id || self.sim_state.borrow().can_emit_as(self.id),
+            "Component {} is not allowed to emit events on behalf of other components, see Simulation::allow_emit_as",
+            self.name
+        );
+    }
+
+    /// Declares the contract allowing at most `max` outstanding events of type `T` emitted by this component to the
+    /// specified destination, see [`contracts`](crate::contracts) module.
+    ///
+    /// An event is outstanding until it is processed or canceled. The contract is checked on each emission of such
+    /// event, and a violation aborts the simulation run or pauses it depending on
+    /// [`Simulation::set_contract_action`](crate::Simulation::set_contract_action). Declaring the contract again
+    /// updates its limit.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Retransmission {
+    ///     seq: u64,
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let sender = sim.create_context("sender");
+    /// let receiver = sim.create_context("receiver");
+    /// sender.declare_max_outstanding::<Retransmission>(receiver.id(), 1);
+    /// let first = sender.emit(Retransmission { seq: 1 }, receiver.id(), 1.);
+    /// sender.cancel_event(first);
+    /// sender.emit(Retransmission { seq: 1 }, receiver.id(), 2.);
+    /// // panics: the second retransmission is sent while the previous one is outstanding
+    /// sender.emit(Retransmission { seq: 1 }, receiver.id(), 3.);
+    /// ```
+    pub fn declare_max_outstanding<T: EventData>(&self, dst: Id, max: usize) {
+        self.sim_state
+            .borrow_mut()
+            .declare_contract(self.id, dst, TypeId::of::<T>(), short_type_name::<T>(), max);
     }
 
     /// Cancels the specified event.
@@ -612,6 +1590,107 @@ impl SimulationContext {
         self.sim_state.borrow_mut().cancel_heap_events(pred);
     }
 
+    /// Sets a named timer which fires after the specified delay.
+    ///
+    /// The timer expiry is delivered to this component as [`TimerFired`] event with the timer name.
+    /// If a timer with the same name is already pending, it is cancelled and replaced by the new one.
+    /// Timer names are local to the component. Returns the identifier of the timer event.
+    ///
+    /// See also [`cancel_timer`](Self::cancel_timer) and [`wait_timer`](Self::wait_timer).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    /// use simcore::timer::TimerFired;
+    ///
+    /// struct Component {
+    ///     fired: Vec<(String, f64)>,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Component {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             TimerFired { name } => {
+    ///                 self.fired.push((name, self.ctx.time()));
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let comp_ctx = sim.create_context("comp");
+    /// let comp = Rc::new(RefCell::new(Component { fired: Vec::new(), ctx: comp_ctx }));
+    /// sim.add_handler("comp", comp.clone());
+    ///
+    /// comp.borrow().ctx.set_timer("retransmit", 5.);
+    /// comp.borrow().ctx.set_timer("heartbeat", 2.);
+    /// sim.step_until_time(1.);
+    /// // replaces the pending timer
+    /// comp.borrow().ctx.set_timer("retransmit", 3.);
+    /// sim.step_until_no_events();
+    /// assert_eq!(comp.borrow().fired, vec![("heartbeat".to_owned(), 2.), ("retransmit".to_owned(), 4.)]);
+    /// ```
+    pub fn set_timer(&self, name: &str, delay: f64) -> EventId {
+        let mut state = self.sim_state.borrow_mut();
+        let event_id = state.add_event(
+            TimerFired { name: name.to_owned() },
+            self.id,
+            self.id,
+            self.scaled(delay),
+        );
+        state.set_named_timer(self.id, name, event_id);
+        event_id
+    }
+
+    /// Sets the named timer firing after the specified number of ticks from the current tick in the discrete time
+    /// mode, see [`set_timer`](Self::set_timer) and [`emit_ticks`](Self::emit_ticks). The delay is not affected by
+    /// the [time scale](Self::scale_time).
+    ///
+    /// Panics if the time tick is not set.
+    pub fn set_timer_ticks(&self, name: &str, delay: u64) -> EventId {
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time_after_ticks(delay);
+        let event_id = state.add_boxed_event_at(Box::new(TimerFired { name: name.to_owned() }), self.id, self.id, time);
+        state.set_named_timer(self.id, name, event_id);
+        event_id
+    }
+
+    /// Sets the named timer firing after the specified typed delay, see [`set_timer`](Self::set_timer).
+    pub fn set_timer_in(&self, name: &str, delay: SimDuration) -> EventId {
+        self.set_timer(name, delay.as_secs())
+    }
+
+    /// Cancels the pending named timer.
+    ///
+    /// Returns `true` if the timer was pending and `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.set_timer("retransmit", 5.);
+    /// assert!(ctx.has_timer("retransmit"));
+    /// assert!(ctx.cancel_timer("retransmit"));
+    /// assert!(!ctx.has_timer("retransmit"));
+    /// assert!(!ctx.cancel_timer("retransmit"));
+    /// assert!(!sim.step());
+    /// ```
+    pub fn cancel_timer(&self, name: &str) -> bool {
+        self.sim_state.borrow_mut().cancel_named_timer(self.id, name)
+    }
+
+    /// Returns `true` if the named timer is set and not yet fired or cancelled.
+    pub fn has_timer(&self, name: &str) -> bool {
+        self.sim_state.borrow().has_named_timer(self.id, name)
+    }
+
     /// Returns component name by its identifier.
     ///
     /// # Examples
@@ -654,6 +1733,134 @@ impl SimulationContext {
         self.sim_state.borrow().lookup_name(id)
     }
 
+    /// Returns the identifier of event type (type of event payload).
+    ///
+    /// See [`Simulation::lookup_event_type_id`](crate::Simulation::lookup_event_type_id).
+    pub fn lookup_event_type_id(&self, event: &Event) -> EventTypeId {
+        self.sim_state.borrow_mut().lookup_event_type_id(event.data.as_ref())
+    }
+
+    /// Returns the name of event type by its identifier.
+    ///
+    /// See [`Simulation::lookup_event_type_name`](crate::Simulation::lookup_event_type_name).
+    pub fn lookup_event_type_name(&self, id: EventTypeId) -> String {
+        self.sim_state.borrow().event_type_name(id).to_owned()
+    }
+
+    /// Adds the value to the counter metric of the component, creating the counter if needed.
+    ///
+    /// See [`metrics`](crate::metrics) module for details.
+    ///
+    /// Panics if the component has a metric of another kind with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.counter_add("bytes", 100.);
+    /// ctx.counter_add("bytes", 50.);
+    /// ctx.counter_inc("requests");
+    /// assert_eq!(ctx.counter("bytes"), 150.);
+    /// assert_eq!(ctx.counter("requests"), 1.);
+    /// assert_eq!(ctx.counter("unknown"), 0.);
+    /// ```
+    pub fn counter_add(&self, name: &str, value: f64) {
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time();
+        state.metrics_mut().counter_add(self.id, name, value, time, &self.name);
+    }
+
+    /// Increments the counter metric of the component by one, creating the counter if needed.
+    ///
+    /// See [`counter_add`](Self::counter_add).
+    pub fn counter_inc(&self, name: &str) {
+        self.counter_add(name, 1.);
+    }
+
+    /// Returns the value of the counter metric of the component, zero if there is no such counter.
+    ///
+    /// See [`counter_add`](Self::counter_add).
+    pub fn counter(&self, name: &str) -> f64 {
+        self.sim_state.borrow().metrics().counter(self.id, name)
+    }
+
+    /// Sets the value of the gauge metric of the component, creating the gauge if needed.
+    ///
+    /// See [`metrics`](crate::metrics) module for details.
+    ///
+    /// Panics if the component has a metric of another kind with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// assert_eq!(ctx.gauge("queue_length"), None);
+    /// ctx.gauge_set("queue_length", 3.);
+    /// ctx.gauge_add("queue_length", -1.);
+    /// assert_eq!(ctx.gauge("queue_length"), Some(2.));
+    /// ```
+    pub fn gauge_set(&self, name: &str, value: f64) {
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time();
+        state.metrics_mut().gauge_set(self.id, name, value, time, &self.name);
+    }
+
+    /// Adds the value to the gauge metric of the component, the missing gauge is considered to be zero.
+    ///
+    /// See [`gauge_set`](Self::gauge_set).
+    pub fn gauge_add(&self, name: &str, value: f64) {
+        let current = self.gauge(name).unwrap_or(0.);
+        self.gauge_set(name, current + value);
+    }
+
+    /// Returns the last value of the gauge metric of the component, `None` if there is no such gauge.
+    ///
+    /// See [`gauge_set`](Self::gauge_set).
+    pub fn gauge(&self, name: &str) -> Option<f64> {
+        self.sim_state.borrow().metrics().gauge(self.id, name)
+    }
+
+    /// Records the value in the histogram metric of the component, creating the histogram if needed.
+    ///
+    /// See [`metrics`](crate::metrics) module for details.
+    ///
+    /// Panics if the component has a metric of another kind with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::metrics::MetricValue;
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.set_histogram_buckets("latency", &[1., 10.]);
+    /// let ctx = sim.create_context("comp");
+    /// for latency in [0.5, 2., 5., 20.] {
+    ///     ctx.histogram_record("latency", latency);
+    /// }
+    /// match sim.metrics().get("comp", "latency").unwrap() {
+    ///     MetricValue::Histogram(histogram) => {
+    ///         assert_eq!(histogram.count, 4);
+    ///         assert_eq!(histogram.max, 20.);
+    ///         assert_eq!(histogram.buckets, vec![1, 2, 1]);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn histogram_record(&self, name: &str, value: f64) {
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time();
+        state
+            .metrics_mut()
+            .histogram_record(self.id, name, value, time, &self.name);
+    }
+
     async_mode_enabled!(
         /// Spawns a new asynchronous task for component associated with this context.
         ///
@@ -822,24 +2029,142 @@ impl SimulationContext {
         ///
         /// let mut sim = Simulation::new(123);
         ///
-        /// let comp_ctx = sim.create_context("comp");
-        /// let comp = Rc::new(Component {ctx: comp_ctx, counter: RefCell::new(0) });
-        /// sim.add_static_handler("comp", comp.clone());
+        /// let comp_ctx = sim.create_context("comp");
+        /// let comp = Rc::new(Component {ctx: comp_ctx, counter: RefCell::new(0) });
+        /// sim.add_static_handler("comp", comp.clone());
+        ///
+        /// comp.clone().on_start(10);
+        ///
+        /// sim.step_until_no_events();
+        ///
+        /// assert_eq!(sim.time(), 10.);
+        /// // 1 + 2 + 3 + ... + 10 = 55
+        /// assert_eq!(*comp.counter.borrow(), 55);
+        /// ```
+        pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
+            self.sim_state.borrow_mut().spawn_component(self.id(), future);
+        }
+
+        /// Sets the maximum number of concurrently running tasks spawned via [`spawn`](Self::spawn).
+        ///
+        /// When the limit is reached, the newly spawned tasks are queued and started in the order of spawning as the
+        /// running tasks complete. The queued tasks are dropped when the component handler is removed. Increasing the
+        /// limit starts the queued tasks immediately, while decreasing it does not affect the running tasks.
+        ///
+        /// Panics if the limit is zero.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        /// use simcore::{Event, Simulation, SimulationContext, StaticEventHandler};
+        ///
+        /// struct Worker {
+        ///     ctx: SimulationContext,
+        /// }
+        ///
+        /// impl StaticEventHandler for Worker {
+        ///     fn on(self: Rc<Self>, _event: Event) {}
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let worker = Rc::new(Worker { ctx: sim.create_context("worker") });
+        /// sim.add_static_handler("worker", worker.clone());
+        /// worker.ctx.set_task_limit(2);
+        ///
+        /// for _ in 0..5 {
+        ///     let task_worker = worker.clone();
+        ///     worker.ctx.spawn(async move {
+        ///         task_worker.ctx.sleep(10.).await;
+        ///     });
+        /// }
+        /// assert_eq!(worker.ctx.queued_task_count(), 3);
+        ///
+        /// sim.step_until_no_events();
+        /// // 5 tasks are processed by 2 workers in 3 rounds
+        /// assert_eq!(sim.time(), 30.);
+        /// ```
+        pub fn set_task_limit(&self, limit: usize) {
+            self.sim_state.borrow_mut().set_task_limit(self.id, limit);
+        }
+
+        /// Returns the number of queued tasks waiting to be started due to the task limit,
+        /// see [`set_task_limit`](Self::set_task_limit).
+        pub fn queued_task_count(&self) -> usize {
+            self.sim_state.borrow().queued_task_count(self.id)
+        }
+
+        /// Creates a scope for spawning tasks of this component which are cancelled when the token is cancelled.
+        ///
+        /// See [`TaskScope`] for examples.
+        pub fn scope_with_token(&self, token: CancellationToken) -> TaskScope {
+            TaskScope::new(self.id, token, self.sim_state.clone())
+        }
+
+        // Spawns a task without the requirement of registered static handler, used by async primitives.
+        pub(crate) fn spawn_task(&self, future: impl Future<Output = ()> + 'static) {
+            self.sim_state.borrow_mut().spawn(future);
+        }
+
+        // Registers a new component and creates its context, used to create components from async tasks.
+        pub(crate) fn create_context(&self, name: &str) -> SimulationContext {
+            let id = self.sim_state.borrow_mut().register(name);
+            SimulationContext::new(id, name, self.sim_state.clone())
+        }
+
+        /// Runs the future with the delays scaled by the specified factor, see [`scale_time`](Self::scale_time).
+        ///
+        /// Unlike the guard returned by [`scale_time`](Self::scale_time), the factor is applied only while the
+        /// wrapped future is polled, so it does not affect other tasks using this context while the future waits.
+        ///
+        /// Panics if `factor` is not positive.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        ///
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let ctx = Rc::new(sim.create_context("comp"));
+        ///
+        /// let slow_ctx = ctx.clone();
+        /// sim.spawn(async move {
+        ///     slow_ctx
+        ///         .with_time_scale(3., async {
+        ///             slow_ctx.sleep(1.).await;
+        ///             assert_eq!(slow_ctx.time(), 3.);
+        ///             slow_ctx.sleep(1.).await;
+        ///         })
+        ///         .await;
+        ///     assert_eq!(slow_ctx.time(), 6.);
+        /// });
         ///
-        /// comp.clone().on_start(10);
+        /// let fast_ctx = ctx.clone();
+        /// sim.spawn(async move {
+        ///     // not scaled while the other task waits
+        ///     fast_ctx.sleep(2.).await;
+        ///     assert_eq!(fast_ctx.time(), 2.);
+        /// });
         ///
         /// sim.step_until_no_events();
-        ///
-        /// assert_eq!(sim.time(), 10.);
-        /// // 1 + 2 + 3 + ... + 10 = 55
-        /// assert_eq!(*comp.counter.borrow(), 55);
+        /// assert_eq!(sim.time(), 6.);
         /// ```
-        pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
-            self.sim_state.borrow_mut().spawn_component(self.id(), future);
+        pub fn with_time_scale<F: Future>(&self, factor: f64, future: F) -> TimeScaledFuture<'_, F> {
+            assert!(factor > 0., "Time scale factor must be positive, got {}", factor);
+            TimeScaledFuture {
+                ctx: self,
+                factor,
+                future: Box::pin(future),
+            }
         }
 
         /// Waits (asynchronously) until `duration` seconds have elapsed.
         ///
+        /// The returned [`TimerFuture`] can report the remaining time if the waiting is interrupted early,
+        /// see [`TimerFuture::cancel`].
+        ///
         /// # Examples
         ///
         /// ```rust
@@ -871,11 +2196,97 @@ impl SimulationContext {
         /// sim.step_until_no_events();
         /// assert_eq!(15., sim.time());
         /// ```
+        #[track_caller]
         pub fn sleep(&self, duration: f64) -> TimerFuture {
             assert!(duration >= 0., "Duration must be a positive value");
-            self.sim_state
-                .borrow_mut()
-                .create_timer(self.id, duration, self.sim_state.clone())
+            let mut sim_state = self.sim_state.borrow_mut();
+            let mut future = sim_state.create_timer(self.id, self.scaled(duration), self.sim_state.clone());
+            future.set_wait_site(sim_state.start_wait(Location::caller()));
+            future
+        }
+
+        /// Waits (asynchronously) for the specified number of ticks from the current tick in the discrete time mode,
+        /// see [`sleep`](Self::sleep) and [`emit_ticks`](Self::emit_ticks). The waiting time is not affected by the
+        /// [time scale](Self::scale_time).
+        ///
+        /// Panics if the time tick is not set.
+        #[track_caller]
+        pub fn sleep_ticks(&self, ticks: u64) -> TimerFuture {
+            let mut sim_state = self.sim_state.borrow_mut();
+            let duration = sim_state.time_after_ticks(ticks) - sim_state.time();
+            let mut future = sim_state.create_timer(self.id, duration, self.sim_state.clone());
+            future.set_wait_site(sim_state.start_wait(Location::caller()));
+            future
+        }
+
+        /// Waits (asynchronously) for the specified typed duration, see [`sleep`](Self::sleep).
+        #[track_caller]
+        pub fn sleep_for(&self, duration: SimDuration) -> TimerFuture {
+            self.sleep(duration.as_secs())
+        }
+
+        /// Creates preemptible work of the specified amount, which completes (asynchronously) after `amount` seconds
+        /// if it is not paused and its rate is not changed.
+        ///
+        /// The work can be paused, resumed or slowed down by other tasks via its [`handle`](Work::handle),
+        /// see [`Work`] for details and examples.
+        ///
+        /// Panics if the amount is negative.
+        pub fn work(&self, amount: f64) -> Work {
+            Work::new(self.id, self.scaled(amount), self.sim_state.clone())
+        }
+
+        /// Performs (asynchronously) the operation with retries on failure according to the specified policy.
+        ///
+        /// The operation is a closure which receives the attempt number (starting from 1) and returns a future
+        /// producing `Result`. On error, the operation is retried after the backoff delay defined by the policy,
+        /// until it succeeds or the maximum number of attempts is reached. Returns the result of the last attempt.
+        ///
+        /// Panics if the policy parameters are invalid.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        /// use simcore::Simulation;
+        /// use simcore::async_mode::RetryPolicy;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let ctx = Rc::new(sim.create_context("client"));
+        ///
+        /// sim.spawn(async move {
+        ///     let policy = RetryPolicy::new(5, 1.);
+        ///     let result = ctx
+        ///         .retry(&policy, |attempt| {
+        ///             let ctx = ctx.clone();
+        ///             async move {
+        ///                 ctx.sleep(0.5).await;
+        ///                 if attempt < 3 { Err(attempt) } else { Ok(attempt) }
+        ///             }
+        ///         })
+        ///         .await;
+        ///     assert_eq!(result, Ok(3));
+        ///     // 3 attempts with backoff delays 1 and 2
+        ///     assert_eq!(ctx.time(), 4.5);
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// ```
+        pub async fn retry<F, Fut, T, E>(&self, policy: &RetryPolicy, mut op: F) -> Result<T, E>
+        where
+            F: FnMut(u32) -> Fut,
+            Fut: Future<Output = Result<T, E>>,
+        {
+            policy.validate();
+            let mut attempt = 1;
+            loop {
+                let result = op(attempt).await;
+                if result.is_ok() || attempt == policy.max_attempts {
+                    return result;
+                }
+                self.sleep(policy.backoff(attempt, self)).await;
+                attempt += 1;
+            }
         }
 
         /// Waits (asynchronously) until all events scheduled at the current time are processed.
@@ -1028,6 +2439,7 @@ impl SimulationContext {
         /// sim.step_until_no_events();
         /// assert_eq!(sim.time(), 100.);
         /// ```
+        #[track_caller]
         pub fn recv_event<T>(&self) -> EventFuture<T>
         where
             T: EventData,
@@ -1071,6 +2483,7 @@ impl SimulationContext {
         /// sim.step_until_no_events();
         /// assert_eq!(sim.time(), 50.);
         /// ```
+        #[track_caller]
         pub fn recv_event_from<T>(&self, src: Id) -> EventFuture<T>
         where
             T: EventData,
@@ -1109,6 +2522,7 @@ impl SimulationContext {
         /// sim.step_until_no_events();
         /// assert_eq!(sim.time(), 10.);
         /// ```
+        #[track_caller]
         pub fn recv_event_from_self<T>(&self) -> EventFuture<T>
         where
             T: EventData,
@@ -1116,12 +2530,112 @@ impl SimulationContext {
             self.recv_event_inner::<T>(self.id, Some(self.id), None)
         }
 
+        /// Creates a [mailbox](Mailbox) of the component, which receives the events of registered types destined to
+        /// the component instead of its handler and awaiting tasks.
+        ///
+        /// Panics if the component already has a mailbox which is not dropped.
+        ///
+        /// See [`Mailbox`] for an example.
+        pub fn create_mailbox(&self) -> Mailbox {
+            Mailbox::new(SimulationContext::new(self.id, &self.name, self.sim_state.clone()))
+        }
+
+        /// Waits (asynchronously) for event of type `T` satisfying the predicate.
+        ///
+        /// The predicate receives the full event including its source, time and payload. The events of type `T`
+        /// which do not satisfy the predicate are delivered as usual, i.e. to other awaiting tasks or to the component
+        /// handler. If several tasks wait for the same event, the event is received by the earliest created future.
+        /// The futures created by other receive methods, e.g. [`recv_event`](Self::recv_event), take precedence.
+        ///
+        /// The predicate is called while the simulation state is borrowed, e.g. when checking whether the next event
+        /// is awaited, and can be called several times for the same event. Thus it must be a pure function of the
+        /// event: calling the methods of simulation context or simulation from the predicate panics.
+        ///
+        /// The returned future outputs the received event and event data.
+        ///
+        /// The timeout for waiting can be set by calling [`EventFuture::with_timeout`] on the returned future.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Message {
+        ///     payload: u32,
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let sender_ctx = sim.create_context("sender");
+        /// let sender_id = sender_ctx.id();
+        /// let receiver_ctx = sim.create_context("receiver");
+        /// let receiver_id = receiver_ctx.id();
+        ///
+        /// sim.spawn(async move {
+        ///     sender_ctx.emit(Message { payload: 1 }, receiver_id, 10.);
+        ///     sender_ctx.emit(Message { payload: 20 }, receiver_id, 20.);
+        ///     sender_ctx.emit(Message { payload: 30 }, receiver_id, 30.);
+        /// });
+        ///
+        /// sim.spawn(async move {
+        ///     let e = receiver_ctx
+        ///         .recv_event_where::<Message>(move |e| e.src == sender_id && e.time > 15. && e.data.payload > 25)
+        ///         .await;
+        ///     assert_eq!(receiver_ctx.time(), 30.);
+        ///     assert_eq!(e.data.payload, 30);
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// assert_eq!(sim.time(), 30.);
+        /// ```
+        #[track_caller]
+        pub fn recv_event_where<T>(&self, predicate: impl Fn(EventRef<'_, T>) -> bool + 'static) -> EventFuture<T>
+        where
+            T: EventData,
+        {
+            let predicate = Rc::new(move |event: &Event| predicate(event.as_typed::<T>().unwrap()));
+            let mut sim_state = self.sim_state.borrow_mut();
+            let mut future = sim_state.create_predicate_event_future::<T>(self.id, predicate, self.sim_state.clone());
+            future.set_wait_site(sim_state.start_wait(Location::caller()));
+            future
+        }
+
         /// Registers a key getter function for event type `T` to be used with
         /// [`recv_event_by_key`](Self::recv_event_by_key) and [`recv_event_by_key_from`](Self::recv_event_by_key_from).
         pub fn register_key_getter_for<T: EventData>(&self, key_getter: impl Fn(&T) -> EventKey + 'static) {
             self.sim_state.borrow_mut().register_key_getter_for::<T>(key_getter);
         }
 
+        /// Registers a function that extracts composite key from events of type `T`.
+        ///
+        /// See [`Simulation::register_composite_key_getter_for`](crate::Simulation::register_composite_key_getter_for).
+        pub fn register_composite_key_getter_for<T, K>(&self, key_getter: impl Fn(&T) -> K + 'static)
+        where
+            T: EventData,
+            K: Hash,
+        {
+            self.sim_state
+                .borrow_mut()
+                .register_key_getter_for::<T>(move |data| composite_key(&key_getter(data)));
+        }
+
+        /// Registers a key getter function for event type `T` to be used only for events delivered to this component.
+        ///
+        /// See [`Simulation::register_component_key_getter_for`](crate::Simulation::register_component_key_getter_for).
+        pub fn register_component_key_getter_for<T: EventData>(&self, key_getter: impl Fn(&T) -> EventKey + 'static) {
+            self.sim_state
+                .borrow_mut()
+                .register_component_key_getter_for::<T>(self.id, key_getter);
+        }
+
+        /// Registers the key getter for event type `T` implementing [`KeyedEvent`].
+        ///
+        /// See [`Simulation::register_keyed_event`](crate::Simulation::register_keyed_event).
+        pub fn register_keyed_event<T: KeyedEvent>(&self) {
+            self.sim_state.borrow_mut().register_key_getter_for::<T>(T::event_key);
+        }
+
         /// Waits (asynchronously) for event of type `T` with key `key` from any component.
         ///
         /// The returned future outputs the received event and event data.
@@ -1129,6 +2643,7 @@ impl SimulationContext {
         /// The timeout for waiting can be set by calling [`EventFuture::with_timeout`] on the returned future.
         ///
         /// See [`recv_event_by_key_from`](Self::recv_event_by_key_from) and [`recv_event`](Self::recv_event) for examples.
+        #[track_caller]
         pub fn recv_event_by_key<T>(&self, key: EventKey) -> EventFuture<T>
         where
             T: EventData,
@@ -1136,6 +2651,72 @@ impl SimulationContext {
             self.recv_event_inner::<T>(self.id, None, Some(key))
         }
 
+        /// Waits (asynchronously) for event of type `T` implementing [`KeyedEvent`] with key `key` from any component.
+        ///
+        /// This is a variant of [`recv_event_by_key`](Self::recv_event_by_key), which registers the key getter for `T`
+        /// on first use like [`register_keyed_event`](Self::register_keyed_event), so the types with
+        /// `#[derive(EventKey)]` can be received without explicit registration. The key getter is not registered if
+        /// another key getter for `T` is already registered for this component or all components. The events of type
+        /// `T` delivered before the first use are not keyed, i.e. they are passed to the event handler.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        /// use simcore::async_mode::{EventKey, KeyedEvent};
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Reply {
+        ///     request_id: u32,
+        /// }
+        ///
+        /// // implemented manually here, can be derived with `#[derive(EventKey)]` and `#[event_key]` on the field
+        /// impl KeyedEvent for Reply {
+        ///     fn event_key(&self) -> EventKey {
+        ///         self.request_id as EventKey
+        ///     }
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let client_ctx = sim.create_context("client");
+        /// let client_id = client_ctx.id();
+        /// let server_ctx = sim.create_context("server");
+        /// let server_id = server_ctx.id();
+        ///
+        /// sim.spawn(async move {
+        ///     let reply = client_ctx.recv_keyed_event::<Reply>(2).await;
+        ///     assert_eq!(reply.data.request_id, 2);
+        ///     let reply = client_ctx.recv_keyed_event_from::<Reply>(server_id, 1).await;
+        ///     assert_eq!(reply.time, 10.);
+        /// });
+        ///
+        /// server_ctx.emit(Reply { request_id: 2 }, client_id, 5.);
+        /// server_ctx.emit(Reply { request_id: 1 }, client_id, 10.);
+        /// sim.step_until_no_events();
+        /// ```
+        #[track_caller]
+        pub fn recv_keyed_event<T>(&self, key: EventKey) -> EventFuture<T>
+        where
+            T: KeyedEvent,
+        {
+            self.sim_state.borrow_mut().register_keyed_event_on_use::<T>(self.id);
+            self.recv_event_inner::<T>(self.id, None, Some(key))
+        }
+
+        /// Waits (asynchronously) for event of type `T` implementing [`KeyedEvent`] with key `key` from component
+        /// `src`.
+        ///
+        /// See [`recv_keyed_event`](Self::recv_keyed_event).
+        #[track_caller]
+        pub fn recv_keyed_event_from<T>(&self, src: Id, key: EventKey) -> EventFuture<T>
+        where
+            T: KeyedEvent,
+        {
+            self.sim_state.borrow_mut().register_keyed_event_on_use::<T>(self.id);
+            self.recv_event_inner::<T>(self.id, Some(src), Some(key))
+        }
+
         /// Waits (asynchronously) for event of type `T` with key `key` from component `src`.
         ///
         /// The returned future outputs the received event and event data.
@@ -1197,6 +2778,7 @@ impl SimulationContext {
         /// sim.step_until_no_events();
         /// assert_eq!(sim.time(), 100.);
         /// ```
+        #[track_caller]
         pub fn recv_event_by_key_from<T>(&self, src: Id, key: EventKey) -> EventFuture<T>
         where
             T: EventData,
@@ -1211,6 +2793,7 @@ impl SimulationContext {
         /// The timeout for waiting can be set by calling [`EventFuture::with_timeout`] on the returned future.
         ///
         /// See [`recv_event_by_key_from`](Self::recv_event_by_key_from) and [`recv_event_from_self`](Self::recv_event_from_self) for examples.
+        #[track_caller]
         pub fn recv_event_by_key_from_self<T>(&self, key: EventKey) -> EventFuture<T>
         where
             T: EventData,
@@ -1218,18 +2801,233 @@ impl SimulationContext {
             self.recv_event_inner::<T>(self.id, Some(self.id), Some(key))
         }
 
+        /// Waits (asynchronously) for the expiry of named timer set via [`set_timer`](Self::set_timer).
+        ///
+        /// The returned future outputs the [`TimerFired`] event once the timer with the specified name fires.
+        /// The timer can be set before or after this call, and the future keeps waiting if the timer is
+        /// replaced. If the timer is cancelled, the future is not completed,
+        /// so consider using [`EventFuture::with_timeout`].
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let ctx = sim.create_context("comp");
+        ///
+        /// sim.spawn(async move {
+        ///     ctx.set_timer("retransmit", 5.);
+        ///     ctx.set_timer("retransmit", 3.);
+        ///     let e = ctx.wait_timer("retransmit").await;
+        ///     assert_eq!(e.data.name, "retransmit");
+        ///     assert_eq!(ctx.time(), 3.);
+        ///     assert!(!ctx.has_timer("retransmit"));
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// assert_eq!(sim.time(), 3.);
+        /// ```
+        #[track_caller]
+        pub fn wait_timer(&self, name: &str) -> EventFuture<TimerFired> {
+            let key = self.sim_state.borrow().timer_key(name);
+            self.recv_event_by_key_from_self::<TimerFired>(key)
+        }
+
+        /// Waits (asynchronously) for events of type `T` with all specified keys from any component.
+        ///
+        /// The returned future outputs the received events in the order of keys.
+        /// This is a convenient replacement for joining futures returned by
+        /// [`recv_event_by_key`](Self::recv_event_by_key) for each key, e.g. in fork-join patterns.
+        ///
+        /// Panics if keys are not unique.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Result {
+        ///     task_id: u64,
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let master_ctx = sim.create_context("master");
+        /// let master_id = master_ctx.id();
+        /// let worker_ctx = sim.create_context("worker");
+        /// sim.register_key_getter_for::<Result>(|result| result.task_id);
+        ///
+        /// sim.spawn(async move {
+        ///     let results = master_ctx.recv_events_by_keys::<Result>([1, 2, 3]).await;
+        ///     let task_ids: Vec<_> = results.iter().map(|e| e.data.task_id).collect();
+        ///     assert_eq!(task_ids, vec![1, 2, 3]);
+        ///     assert_eq!(master_ctx.time(), 30.);
+        /// });
+        ///
+        /// worker_ctx.emit(Result { task_id: 3 }, master_id, 10.);
+        /// worker_ctx.emit(Result { task_id: 1 }, master_id, 20.);
+        /// worker_ctx.emit(Result { task_id: 2 }, master_id, 30.);
+        /// sim.step_until_no_events();
+        /// ```
+        pub fn recv_events_by_keys<T>(&self, keys: impl IntoIterator<Item = EventKey>) -> EventsFuture<T>
+        where
+            T: EventData,
+        {
+            let futures: Vec<_> = keys.into_iter().map(|key| self.recv_event_by_key::<T>(key)).collect();
+            let quorum = futures.len();
+            EventsFuture::new(futures, quorum, self.sim_state.clone())
+        }
+
+        /// Waits (asynchronously) for events of type `T` with any `quorum` of specified keys from any component.
+        ///
+        /// The returned future outputs the first `quorum` received events in the order of their receipt.
+        /// Waiting for the remaining events is cancelled. If more than `quorum` events are received at once, e.g.
+        /// before the future is polled, the extra events are returned to the pending events and delivered again before
+        /// other events at the current time, e.g. to the component handler.
+        ///
+        /// Panics if keys are not unique or `quorum` exceeds the number of keys.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Ack {
+        ///     replica: u64,
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let leader_ctx = sim.create_context("leader");
+        /// let leader_id = leader_ctx.id();
+        /// let replica_ctx = sim.create_context("replica");
+        /// sim.register_key_getter_for::<Ack>(|ack| ack.replica);
+        ///
+        /// sim.spawn(async move {
+        ///     // wait for majority of 5 replicas
+        ///     let acks = leader_ctx.recv_events_by_keys_quorum::<Ack>(0..5, 3).await;
+        ///     let replicas: Vec<_> = acks.iter().map(|e| e.data.replica).collect();
+        ///     assert_eq!(replicas, vec![4, 1, 2]);
+        ///     assert_eq!(leader_ctx.time(), 3.);
+        /// });
+        ///
+        /// for (replica, delay) in [(4, 1.), (1, 2.), (2, 3.)] {
+        ///     replica_ctx.emit(Ack { replica }, leader_id, delay);
+        /// }
+        /// sim.step_until_no_events();
+        /// ```
+        pub fn recv_events_by_keys_quorum<T>(
+            &self,
+            keys: impl IntoIterator<Item = EventKey>,
+            quorum: usize,
+        ) -> EventsFuture<T>
+        where
+            T: EventData,
+        {
+            let futures = keys.into_iter().map(|key| self.recv_event_by_key::<T>(key)).collect();
+            EventsFuture::new(futures, quorum, self.sim_state.clone())
+        }
+
+        /// Sends request to the specified component and waits (asynchronously) for the response
+        /// with the specified timeout.
+        ///
+        /// The request payload is wrapped into [`Request`] event with a unique identifier, which is emitted to `dst`
+        /// without delay. The responder should reply to it via [`reply`](Self::reply). The returned future outputs
+        /// the matching [`Response`] event from `dst` or [`AwaitResult::Timeout`] if the response is not received
+        /// within the timeout.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        /// use simcore::async_mode::{AwaitResult, Request};
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Get {
+        ///     key: String,
+        /// }
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Value {
+        ///     value: Option<String>,
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let client_ctx = sim.create_context("client");
+        /// let server_ctx = sim.create_context("server");
+        /// let server_id = server_ctx.id();
+        ///
+        /// sim.spawn(async move {
+        ///     let request = server_ctx.recv_event::<Request<Get>>().await;
+        ///     assert_eq!(request.data.data.key, "foo");
+        ///     server_ctx.reply(&request, Value { value: Some("bar".to_owned()) }, 1.);
+        /// });
+        ///
+        /// sim.spawn(async move {
+        ///     match client_ctx.request::<_, Value>(server_id, Get { key: "foo".to_owned() }, 10.).await {
+        ///         AwaitResult::Ok(response) => {
+        ///             assert_eq!(response.data.data.value.as_deref(), Some("bar"));
+        ///             assert_eq!(client_ctx.time(), 1.);
+        ///         }
+        ///         AwaitResult::Timeout { .. } => panic!("Unexpected timeout"),
+        ///     }
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// ```
+        pub async fn request<Req, Resp>(&self, dst: Id, data: Req, timeout: f64) -> AwaitResult<Response<Resp>>
+        where
+            Req: Clone + Serialize + 'static,
+            Resp: Clone + Serialize + 'static,
+        {
+            let request_id = {
+                let mut state = self.sim_state.borrow_mut();
+                if state.get_key_getter(TypeId::of::<Response<Resp>>(), self.id).is_none() {
+                    state.register_key_getter_for::<Response<Resp>>(|response| response.request_id);
+                }
+                state.next_request_id()
+            };
+            let response = self.recv_event_by_key_from::<Response<Resp>>(dst, request_id);
+            self.emit_now(Request { id: request_id, data }, dst);
+            response.with_timeout(self.scaled(timeout)).await
+        }
+
+        /// Replies to the request received from another component with the specified delay.
+        ///
+        /// The response payload is wrapped into [`Response`] event with the request identifier and emitted to
+        /// the request source. See [`request`](Self::request) for example.
+        pub fn reply<Req, Resp>(&self, request: &TypedEvent<Request<Req>>, data: Resp, delay: f64) -> EventId
+        where
+            Req: Clone + Serialize + 'static,
+            Resp: Clone + Serialize + 'static,
+        {
+            self.emit(
+                Response {
+                    request_id: request.data.id,
+                    data,
+                },
+                request.src,
+                delay,
+            )
+        }
+
+        #[track_caller]
         fn recv_event_inner<T>(&self, dst: Id, src: Option<Id>, key: Option<EventKey>) -> EventFuture<T>
         where
             T: EventData,
         {
             if key.is_none() {
                 assert!(
-                    self.sim_state.borrow().get_key_getter(TypeId::of::<T>()).is_none(),
+                    self.sim_state.borrow().get_key_getter(TypeId::of::<T>(), dst).is_none(),
                     "Trying to receive event of type with registered key getter, use receive by key for such events"
                 );
             } else {
                 assert!(
-                    self.sim_state.borrow().get_key_getter(TypeId::of::<T>()).is_some(),
+                    self.sim_state.borrow().get_key_getter(TypeId::of::<T>(), dst).is_some(),
                     "Trying to receive event by key for type {} without key getter, register it before using this feature",
                     type_name::<T>()
                 );
@@ -1240,9 +3038,45 @@ impl SimulationContext {
                     .create_event_future::<T>(dst, src, key, self.sim_state.clone());
 
             match future_result {
-                Ok(future) => future,
+                Ok(mut future) => {
+                    future.set_wait_site(self.sim_state.borrow().start_wait(Location::caller()));
+                    future
+                }
                 Err((_, e)) => panic!("Failed to create EventFuture: {}", e),
             }
         }
     );
 }
+
+/// Guard of time scale scope created by [`SimulationContext::scale_time`], which removes its factor on drop.
+pub struct TimeScaleGuard<'a> {
+    ctx: &'a SimulationContext,
+    id: u64,
+}
+
+impl Drop for TimeScaleGuard<'_> {
+    fn drop(&mut self) {
+        self.ctx.pop_time_scale(self.id);
+    }
+}
+
+async_mode_enabled!(
+    /// Future returned by [`SimulationContext::with_time_scale`], which applies the time scale only while the
+    /// wrapped future is polled.
+    pub struct TimeScaledFuture<'a, F> {
+        ctx: &'a SimulationContext,
+        factor: f64,
+        future: Pin<Box<F>>,
+    }
+
+    impl<F: Future> Future for TimeScaledFuture<'_, F> {
+        type Output = F::Output;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let id = self.ctx.push_time_scale(self.factor);
+            let result = self.future.as_mut().poll(cx);
+            self.ctx.pop_time_scale(id);
+            result
+        }
+    }
+);
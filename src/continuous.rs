@@ -0,0 +1,265 @@
+//! Hybrid discrete/continuous simulation.
+//!
+//! Some parts of a model, such as battery charge, temperature or congestion window, evolve continuously and are
+//! naturally described by ordinary differential equations. Instead of approximating them with dense periodic
+//! events, such dynamics can be implemented as a [`ContinuousModel`] registered via
+//! [`Simulation::add_continuous_model`](crate::Simulation::add_continuous_model).
+//!
+//! The simulation advances the state of continuous models between discrete events using the configured
+//! [`Integrator`], so the state is up to date when the next event is processed. The model can also define
+//! threshold functions of its state. When a threshold function crosses zero, the model is stopped at the crossing
+//! time and [`ThresholdCrossed`] event is emitted to the component with the name of the model, which can react to
+//! it, e.g. by changing the model parameters.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::component::Id;
+
+/// Model with continuous state described by ordinary differential equations.
+pub trait ContinuousModel {
+    /// Returns the current state of the model.
+    fn state(&self) -> Vec<f64>;
+
+    /// Sets the state of the model after advancing it to the specified time.
+    fn set_state(&mut self, time: f64, state: &[f64]);
+
+    /// Returns the derivatives of the state at the specified time.
+    fn derivatives(&self, time: f64, state: &[f64]) -> Vec<f64>;
+
+    /// Returns the values of threshold functions at the specified time and state.
+    ///
+    /// [`ThresholdCrossed`] event is emitted when the value of the function with some index crosses zero.
+    /// By default, the model has no threshold functions.
+    fn thresholds(&self, _time: f64, _state: &[f64]) -> Vec<f64> {
+        Vec::new()
+    }
+}
+
+/// Method of numerical integration of continuous model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integrator {
+    /// Explicit Euler method with fixed step.
+    Euler {
+        /// Integration step.
+        step: f64,
+    },
+    /// Classical 4th order Runge-Kutta method with fixed step.
+    RungeKutta4 {
+        /// Integration step.
+        step: f64,
+    },
+    /// 4th order Runge-Kutta method with step size adapted to keep the estimated local error within the tolerance.
+    Adaptive {
+        /// Maximum absolute local error per step.
+        tolerance: f64,
+        /// Maximum integration step.
+        max_step: f64,
+    },
+}
+
+/// Event emitted when a threshold function of continuous model crosses zero,
+/// see [`ContinuousModel::thresholds`].
+#[derive(Clone, Serialize)]
+pub struct ThresholdCrossed {
+    /// Index of the threshold function.
+    pub index: usize,
+    /// True if the function value became positive, false if it became negative.
+    pub rising: bool,
+}
+
+// Precision of locating the threshold crossing time.
+const CROSSING_PRECISION: f64 = 1e-9;
+
+pub(crate) struct ContinuousModelEntry {
+    pub id: Id,
+    model: Rc<RefCell<dyn ContinuousModel>>,
+    integrator: Integrator,
+    time: Cell<f64>,
+    // Last step size for adaptive integrator.
+    adaptive_step: Cell<f64>,
+}
+
+// Result of advancing the model state.
+pub(crate) struct Advance {
+    time: f64,
+    state: Vec<f64>,
+    pub crossing: Option<ThresholdCrossed>,
+}
+
+impl Advance {
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+impl ContinuousModelEntry {
+    pub fn new(id: Id, model: Rc<RefCell<dyn ContinuousModel>>, integrator: Integrator, time: f64) -> Self {
+        let initial_step = match integrator {
+            Integrator::Euler { step } | Integrator::RungeKutta4 { step } => step,
+            Integrator::Adaptive { tolerance, max_step } => {
+                assert!(tolerance > 0., "Integration tolerance must be positive");
+                max_step
+            }
+        };
+        assert!(initial_step > 0., "Integration step must be positive");
+        Self {
+            id,
+            model,
+            integrator,
+            time: Cell::new(time),
+            adaptive_step: Cell::new(initial_step),
+        }
+    }
+
+    // Integrates the model state up to the target time or the first threshold crossing without updating the model.
+    pub fn integrate(&self, target: f64) -> Advance {
+        let model = self.model.borrow();
+        let mut time = self.time.get();
+        let mut state = model.state();
+        let mut thresholds = model.thresholds(time, &state);
+        let mut step = self.adaptive_step.get();
+        while time < target {
+            let (h, next_state) = match self.integrator {
+                Integrator::Euler { step } => {
+                    let h = step.min(target - time);
+                    (h, euler_step(&*model, time, &state, h))
+                }
+                Integrator::RungeKutta4 { step } => {
+                    let h = step.min(target - time);
+                    (h, rk4_step(&*model, time, &state, h))
+                }
+                Integrator::Adaptive { tolerance, max_step } => {
+                    let (h, next_state, next_step) =
+                        adaptive_step(&*model, time, &state, step, tolerance, max_step, target);
+                    step = next_step;
+                    (h, next_state)
+                }
+            };
+            // the last step ends exactly at the target time despite the floating-point errors
+            let next_time = if h >= target - time { target } else { time + h };
+            let next_thresholds = model.thresholds(next_time, &next_state);
+            let crossed = thresholds
+                .iter()
+                .zip(next_thresholds.iter())
+                .position(|(before, after)| crosses(*before, *after));
+            if let Some(index) = crossed {
+                let rising = next_thresholds[index] > thresholds[index];
+                let (time, state) = self.locate_crossing(&*model, time, &state, next_time, index, thresholds[index]);
+                return Advance {
+                    time,
+                    state,
+                    crossing: Some(ThresholdCrossed { index, rising }),
+                };
+            }
+            time = next_time;
+            state = next_state;
+            thresholds = next_thresholds;
+        }
+        if let Integrator::Adaptive { .. } = self.integrator {
+            self.adaptive_step.set(step);
+        }
+        Advance {
+            time: target.max(time),
+            state,
+            crossing: None,
+        }
+    }
+
+    pub fn commit(&self, advance: Advance) {
+        self.time.set(advance.time);
+        self.model.borrow_mut().set_state(advance.time, &advance.state);
+    }
+
+    // Finds the crossing time within the step by bisection, returns the time just after the crossing and the state.
+    fn locate_crossing(
+        &self,
+        model: &dyn ContinuousModel,
+        start_time: f64,
+        start_state: &[f64],
+        end_time: f64,
+        index: usize,
+        start_value: f64,
+    ) -> (f64, Vec<f64>) {
+        let (mut low, mut high) = (start_time, end_time);
+        let mut high_state = self.single_step(model, start_time, start_state, end_time - start_time);
+        while high - low > CROSSING_PRECISION * high.abs().max(1.) {
+            let mid = (low + high) / 2.;
+            let mid_state = self.single_step(model, start_time, start_state, mid - start_time);
+            let mid_value = model.thresholds(mid, &mid_state)[index];
+            if crosses(start_value, mid_value) {
+                high = mid;
+                high_state = mid_state;
+            } else {
+                low = mid;
+            }
+        }
+        (high, high_state)
+    }
+
+    fn single_step(&self, model: &dyn ContinuousModel, time: f64, state: &[f64], h: f64) -> Vec<f64> {
+        match self.integrator {
+            Integrator::Euler { .. } => euler_step(model, time, state, h),
+            Integrator::RungeKutta4 { .. } | Integrator::Adaptive { .. } => rk4_step(model, time, state, h),
+        }
+    }
+}
+
+// Returns true if the threshold function value crosses zero, the value equal to zero is not treated as crossed.
+fn crosses(before: f64, after: f64) -> bool {
+    (before < 0. && after >= 0.) || (before > 0. && after <= 0.)
+}
+
+fn add_scaled(state: &[f64], derivatives: &[f64], h: f64) -> Vec<f64> {
+    state.iter().zip(derivatives.iter()).map(|(x, d)| x + d * h).collect()
+}
+
+fn euler_step(model: &dyn ContinuousModel, time: f64, state: &[f64], h: f64) -> Vec<f64> {
+    add_scaled(state, &model.derivatives(time, state), h)
+}
+
+fn rk4_step(model: &dyn ContinuousModel, time: f64, state: &[f64], h: f64) -> Vec<f64> {
+    let k1 = model.derivatives(time, state);
+    let k2 = model.derivatives(time + h / 2., &add_scaled(state, &k1, h / 2.));
+    let k3 = model.derivatives(time + h / 2., &add_scaled(state, &k2, h / 2.));
+    let k4 = model.derivatives(time + h, &add_scaled(state, &k3, h));
+    (0..state.len())
+        .map(|i| state[i] + h / 6. * (k1[i] + 2. * k2[i] + 2. * k3[i] + k4[i]))
+        .collect()
+}
+
+// Performs RK4 step with error estimated by step doubling, returns the accepted step, the new state
+// and the suggested next step.
+fn adaptive_step(
+    model: &dyn ContinuousModel,
+    time: f64,
+    state: &[f64],
+    step: f64,
+    tolerance: f64,
+    max_step: f64,
+    target: f64,
+) -> (f64, Vec<f64>, f64) {
+    let mut h = step.min(max_step);
+    loop {
+        let h_step = h.min(target - time);
+        let full = rk4_step(model, time, state, h_step);
+        let half = rk4_step(model, time, state, h_step / 2.);
+        let double = rk4_step(model, time + h_step / 2., &half, h_step / 2.);
+        let error = full
+            .iter()
+            .zip(double.iter())
+            .map(|(a, b)| (a - b).abs() / 15.)
+            .fold(0., f64::max);
+        let factor = if error > 0. {
+            (0.9 * (tolerance / error).powf(0.2)).clamp(0.2, 5.)
+        } else {
+            5.
+        };
+        if error <= tolerance || h_step <= CROSSING_PRECISION {
+            return (h_step, double, (h * factor).min(max_step));
+        }
+        h *= factor;
+    }
+}
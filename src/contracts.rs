@@ -0,0 +1,157 @@
+//! Event boundedness contracts.
+//!
+//! Many logic errors, such as duplicate retransmissions or forgotten timer cancellations, manifest as a growing
+//! number of outstanding events between components. A component can declare a contract limiting the number of its
+//! outstanding events of some type to some destination via
+//! [`SimulationContext::declare_max_outstanding`](crate::SimulationContext::declare_max_outstanding). An event is
+//! outstanding from its emission until it is processed or its cancellation takes effect. The contracts are checked
+//! on each emission, and a violation aborts the run or pauses it depending on the action set via
+//! [`Simulation::set_contract_action`](crate::Simulation::set_contract_action).
+
+use std::any::TypeId;
+use std::fmt::{Display, Formatter};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::component::Id;
+use crate::event::{Event, EventId};
+use crate::limits::LimitAction;
+
+/// Violation of event boundedness contract.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractViolation {
+    /// Name of the component which declared the contract.
+    pub component: String,
+    /// Name of the destination component.
+    pub dst: String,
+    /// Event type name without module path.
+    pub event_type: String,
+    /// Maximum number of outstanding events allowed by the contract.
+    pub max_outstanding: usize,
+    /// Number of outstanding events including the violating one.
+    pub outstanding: usize,
+    /// Identifier of the violating event.
+    pub event_id: EventId,
+    /// Simulation time of the violation.
+    pub time: f64,
+}
+
+impl Display for ContractViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Contract violated at time {:.3}: component {} has {} outstanding {} events to {}, at most {} allowed \
+             (violating event {})",
+            self.time, self.component, self.outstanding, self.event_type, self.dst, self.max_outstanding, self.event_id
+        )
+    }
+}
+
+// Contract key: source, destination and event type.
+type ContractKey = (Id, Id, TypeId);
+
+#[derive(Clone)]
+struct Contract {
+    max_outstanding: usize,
+    event_type: &'static str,
+    outstanding: FxHashSet<EventId>,
+}
+
+// Tracks the outstanding events covered by the contracts.
+#[derive(Clone)]
+pub(crate) struct Contracts {
+    contracts: FxHashMap<ContractKey, Contract>,
+    // Contracts of the outstanding events.
+    tracked: FxHashMap<EventId, ContractKey>,
+    action: LimitAction,
+    violations: Vec<ContractViolation>,
+}
+
+impl Contracts {
+    pub fn new() -> Self {
+        Self {
+            contracts: FxHashMap::default(),
+            tracked: FxHashMap::default(),
+            action: LimitAction::Abort,
+            violations: Vec::new(),
+        }
+    }
+
+    pub fn declare(&mut self, key: ContractKey, event_type: &'static str, max_outstanding: usize) {
+        self.contracts
+            .entry(key)
+            .or_insert_with(|| Contract {
+                max_outstanding,
+                event_type,
+                outstanding: FxHashSet::default(),
+            })
+            .max_outstanding = max_outstanding;
+    }
+
+    pub fn set_action(&mut self, action: LimitAction) {
+        self.action = action;
+    }
+
+    pub fn take_violations(&mut self) -> Vec<ContractViolation> {
+        std::mem::take(&mut self.violations)
+    }
+
+    pub fn on_event_emitted(&mut self, event: &Event, time: f64, names: &[String]) {
+        if self.contracts.is_empty() {
+            return;
+        }
+        let key = (event.src, event.dst, event.data.as_ref().type_id());
+        let Some(contract) = self.contracts.get_mut(&key) else {
+            return;
+        };
+        contract.outstanding.insert(event.id);
+        self.tracked.insert(event.id, key);
+        if contract.outstanding.len() <= contract.max_outstanding {
+            return;
+        }
+        let violation = ContractViolation {
+            component: names[event.src as usize].clone(),
+            dst: names[event.dst as usize].clone(),
+            event_type: contract.event_type.to_string(),
+            max_outstanding: contract.max_outstanding,
+            outstanding: contract.outstanding.len(),
+            event_id: event.id,
+            time,
+        };
+        match self.action {
+            LimitAction::Abort => panic!("{}", violation),
+            LimitAction::Pause => self.violations.push(violation),
+        }
+    }
+
+    // Replaces the outstanding events with the specified pending events, e.g. restored from the checkpoint.
+    pub fn reset_outstanding<'a>(&mut self, events: impl Iterator<Item = &'a Event>) {
+        self.tracked.clear();
+        for contract in self.contracts.values_mut() {
+            contract.outstanding.clear();
+        }
+        if self.contracts.is_empty() {
+            return;
+        }
+        for event in events {
+            let key = (event.src, event.dst, event.data.as_ref().type_id());
+            if let Some(contract) = self.contracts.get_mut(&key) {
+                contract.outstanding.insert(event.id);
+                self.tracked.insert(event.id, key);
+            }
+        }
+    }
+
+    // Stops tracking the event when it is delivered or canceled, the canceled events are not outstanding anymore
+    // even though they are removed from the queue lazily.
+    pub fn on_event_removed(&mut self, event_id: EventId) {
+        if self.tracked.is_empty() {
+            return;
+        }
+        if let Some(key) = self.tracked.remove(&event_id) {
+            if let Some(contract) = self.contracts.get_mut(&key) {
+                contract.outstanding.remove(&event_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Co-simulation with external processes.
+//!
+//! The [`CosimBridge`] couples the simulation with an external simulator, possibly written in another language,
+//! which is connected via TCP socket or a pair of byte streams, e.g. the standard input and output of a child
+//! process. When registered via [`Simulation::add_cosim_bridge`](crate::Simulation::add_cosim_bridge), the bridge
+//! acts as a component which proxies the events destined to it to the external simulator and injects the events
+//! produced by the external simulator into the simulation.
+//!
+//! The peers exchange JSON messages, one message per line, with the following `type`s:
+//!
+//! - `event` - event sent in both directions with fields `time`, `src` and `dst` (component names), `event` (type
+//!   name without module path, e.g. `Request`) and `data` (serialized payload). The events sent by the simulation are
+//!   the events delivered to the bridge, so their `time` is the current simulation time and `dst` is the bridge
+//!   name. The events sent by the external simulator are delivered to the simulation components by their `dst`
+//!   names, and `src` is optional.
+//! - `advance` - time synchronization request sent by the simulation with field `until`, which is the time up to
+//!   which the simulation needs to know the external events, or `null` if the simulation has no pending events.
+//! - `grant` - reply to `advance` sent by the external simulator with field `time`. The external simulator must
+//!   first send all its events with time not greater than the granted time, which means that it will not produce
+//!   earlier events. The granted time can be smaller than requested, then the simulation proceeds up to the granted
+//!   time and sends another request. The `null` time means that the external simulator will not produce more
+//!   events.
+//!
+//! The simulation sends the requests lazily, only when it needs to know whether the external simulator has events
+//! before its next pending event, so the external simulator receives all events sent to it before the request
+//! and can advance its clock up to the requested time. The events received from the external simulator must be
+//! ordered by time and must not be earlier than the current simulation time. Since the simulation is blocked while
+//! waiting for the grant, the external simulator should grant the requested time if it does not produce events
+//! without receiving events from the simulation.
+//!
+//! The failures of the external simulator, such as closed connection, invalid messages or events with unregistered
+//! type or unknown destination, do not abort the simulation. Instead, the bridge stops exchanging messages with the
+//! external simulator and the current run is paused after the step in which the failure occurred. The run methods
+//! report it as [`TerminationReason::PeerError`](crate::run::TerminationReason::PeerError), and the error can be
+//! obtained via [`Simulation::take_peer_error`](crate::Simulation::take_peer_error).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::io::Cursor;
+//! use std::rc::Rc;
+//!
+//! use serde::{Deserialize, Serialize};
+//!
+//! use simcore::cosim::CosimBridge;
+//! use simcore::{Event, EventHandler, Simulation};
+//!
+//! #[derive(Clone, Serialize, Deserialize)]
+//! struct Measurement {
+//!     value: f64,
+//! }
+//!
+//! struct Controller {
+//!     log: Vec<(f64, f64)>,
+//! }
+//!
+//! impl EventHandler for Controller {
+//!     fn on(&mut self, event: Event) {
+//!         let measurement = event.data.downcast_ref::<Measurement>().unwrap();
+//!         self.log.push((event.time, measurement.value));
+//!     }
+//! }
+//!
+//! let mut sim = Simulation::new(123);
+//! let controller = Rc::new(RefCell::new(Controller { log: Vec::new() }));
+//! sim.add_handler("controller", controller.clone());
+//!
+//! // scripted replies of external simulator
+//! let replies = concat!(
+//!     r#"{"type": "event", "time": 1.5, "dst": "controller", "event": "Measurement", "data": {"value": 0.7}}"#,
+//!     "\n",
+//!     r#"{"type": "grant", "time": null}"#,
+//!     "\n",
+//! );
+//! let mut bridge = CosimBridge::new(Cursor::new(replies), Vec::new());
+//! bridge.register_event::<Measurement>();
+//! sim.add_cosim_bridge("plant", bridge);
+//!
+//! sim.step_until_no_events();
+//! assert_eq!(controller.borrow().log, vec![(1.5, 0.7)]);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::checkpoint::{decode, DecodeFn};
+use crate::context::SimulationContext;
+use crate::event::{Event, EventData};
+use crate::handler::EventHandler;
+use crate::input::{InputEvent, InputItem, InputSource};
+use crate::replay::short_type_name;
+use crate::state::SimulationState;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Message {
+    Event {
+        time: f64,
+        #[serde(default)]
+        src: String,
+        dst: String,
+        #[serde(rename = "event")]
+        type_name: String,
+        data: Value,
+    },
+    Advance {
+        until: Option<f64>,
+    },
+    Grant {
+        time: Option<f64>,
+    },
+}
+
+/// Bridge to external simulator, see [module documentation](self).
+pub struct CosimBridge {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+}
+
+impl CosimBridge {
+    /// Creates a bridge to the external simulator reading its messages from `reader` and writing the messages to
+    /// `writer`.
+    pub fn new<R, W>(reader: R, writer: W) -> Self
+    where
+        R: Read + 'static,
+        W: Write + 'static,
+    {
+        Self {
+            reader: Box::new(BufReader::new(reader)),
+            writer: Box::new(BufWriter::new(writer)),
+            decoders: FxHashMap::default(),
+        }
+    }
+
+    /// Creates a bridge to the external simulator listening on the specified address.
+    ///
+    /// Returns an error if the connection cannot be established.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let reader = stream.try_clone()?;
+        Ok(Self::new(reader, stream))
+    }
+
+    /// Registers the type of events received from the external simulator, which is matched with the type name
+    /// without module path.
+    pub fn register_event<T>(&mut self) -> &mut Self
+    where
+        T: EventData + DeserializeOwned,
+    {
+        self.decoders.insert(short_type_name::<T>(), decode::<T>);
+        self
+    }
+
+    // Splits the bridge into the component forwarding the events to the external simulator and the input source
+    // injecting the external events.
+    // The failures of the external simulator are reported to `errors`.
+    pub(crate) fn split(
+        self,
+        ctx: SimulationContext,
+        errors: Rc<RefCell<Vec<String>>>,
+    ) -> (CosimForwarder, CosimInput) {
+        let connection = Rc::new(RefCell::new(Connection {
+            reader: self.reader,
+            writer: self.writer,
+            name: ctx.name().to_string(),
+            failed: false,
+            errors,
+        }));
+        let input = CosimInput {
+            connection: connection.clone(),
+            decoders: self.decoders,
+            items: VecDeque::new(),
+            finished: false,
+            sim_state: ctx.sim_state(),
+        };
+        let forwarder = CosimForwarder { connection, ctx };
+        (forwarder, input)
+    }
+}
+
+struct Connection {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    // Name of the bridge component.
+    name: String,
+    // Set after the first failure, since then no messages are exchanged.
+    failed: bool,
+    errors: Rc<RefCell<Vec<String>>>,
+}
+
+impl Connection {
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, message)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn receive(&mut self) -> io::Result<Message> {
+        let mut line = String::new();
+        let size = self.reader.read_line(&mut line)?;
+        if size == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection is closed"));
+        }
+        serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to parse message {}: {}", line.trim(), e),
+            )
+        })
+    }
+
+    fn fail(&mut self, error: io::Error) {
+        self.failed = true;
+        self.errors
+            .borrow_mut()
+            .push(format!("Co-simulation peer of {} failed: {}", self.name, error));
+    }
+}
+
+// Component forwarding the delivered events to the external simulator.
+pub(crate) struct CosimForwarder {
+    connection: Rc<RefCell<Connection>>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for CosimForwarder {
+    fn on(&mut self, event: Event) {
+        if self.connection.borrow().failed {
+            return;
+        }
+        let src = self.ctx.sim_state().borrow().lookup_name(event.src);
+        let message = Message::Event {
+            time: event.time,
+            src,
+            dst: self.ctx.name().to_string(),
+            type_name: serde_type_name::type_name(&event.data).unwrap().to_string(),
+            data: serde_json::to_value(&event.data).unwrap(),
+        };
+        let mut connection = self.connection.borrow_mut();
+        if let Err(e) = connection.send(&message) {
+            connection.fail(e);
+        }
+    }
+}
+
+// Input source requesting the events from the external simulator up to the simulation horizon.
+pub(crate) struct CosimInput {
+    connection: Rc<RefCell<Connection>>,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+    // Items received from the external simulator and not yet read by the simulation.
+    items: VecDeque<InputItem>,
+    finished: bool,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+impl CosimInput {
+    fn read_event(&self, time: f64, dst: String, type_name: String, data: Value) -> io::Result<InputItem> {
+        let invalid_data = |message: String| io::Error::new(ErrorKind::InvalidData, message);
+        let decode = self.decoders.get(type_name.as_str()).ok_or_else(|| {
+            invalid_data(format!(
+                "event type {} is not registered, see CosimBridge::register_event",
+                type_name
+            ))
+        })?;
+        let data = decode(data).map_err(|e| invalid_data(format!("failed to deserialize {}: {}", type_name, e)))?;
+        let dst = self
+            .sim_state
+            .borrow()
+            .try_lookup_id(&dst)
+            .ok_or_else(|| invalid_data(format!("destination component {} does not exist", dst)))?;
+        Ok(InputItem::Event(InputEvent { time, dst, data }))
+    }
+
+    // Requests the events up to the horizon and receives them until the grant.
+    fn advance(&mut self, connection: &mut Connection, horizon: f64) -> io::Result<()> {
+        let until = horizon.is_finite().then_some(horizon);
+        connection.send(&Message::Advance { until })?;
+        connection.writer.flush()?;
+        loop {
+            match connection.receive()? {
+                Message::Event {
+                    time,
+                    dst,
+                    type_name,
+                    data,
+                    ..
+                } => {
+                    let item = self.read_event(time, dst, type_name, data)?;
+                    self.items.push_back(item);
+                }
+                Message::Grant { time: Some(time) } => {
+                    // the events with granted time are already received
+                    self.items.push_back(InputItem::Watermark(time.next_up()));
+                    return Ok(());
+                }
+                Message::Grant { time: None } => {
+                    self.finished = true;
+                    return Ok(());
+                }
+                Message::Advance { .. } => {
+                    return Err(io::Error::new(ErrorKind::InvalidData, "unexpected advance message"));
+                }
+            }
+        }
+    }
+}
+
+impl InputSource for CosimInput {
+    fn next_item(&mut self, horizon: f64) -> Option<InputItem> {
+        if self.items.is_empty() && !self.finished {
+            let connection = self.connection.clone();
+            let mut connection = connection.borrow_mut();
+            if connection.failed {
+                self.finished = true;
+            } else if let Err(e) = self.advance(&mut connection, horizon) {
+                // the events received before the failure are discarded, since the peer did not grant their time
+                self.items.clear();
+                self.finished = true;
+                connection.fail(e);
+            }
+        }
+        self.items.pop_front()
+    }
+}
@@ -0,0 +1,120 @@
+//! Default event delays.
+//!
+//! Latency assumptions of a model, e.g. network delays between groups of components, can be configured centrally
+//! on [`Simulation`](crate::Simulation) instead of being passed to each [`emit`](crate::SimulationContext::emit)
+//! call. The configured delays are used by
+//! [`SimulationContext::emit_with_default_delay`](crate::SimulationContext::emit_with_default_delay), so experiments
+//! with different delay profiles only require changing the configuration.
+//!
+//! The delay of emitted event is selected in the following order:
+//! - the profile of event type set via [`Simulation::set_event_delay`](crate::Simulation::set_event_delay),
+//! - the profile of source and destination groups set via
+//!   [`Simulation::set_group_delay`](crate::Simulation::set_group_delay),
+//! - the default profile set via [`Simulation::set_default_delay`](crate::Simulation::set_default_delay).
+
+use std::any::TypeId;
+use std::rc::Rc;
+
+use rand::prelude::Distribution;
+use rand_pcg::Pcg64;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::component::Id;
+
+/// Profile of event delays, either constant or sampled from a distribution.
+///
+/// The random delays are sampled using the simulation random number generator, so the simulation remains
+/// deterministic.
+#[derive(Clone)]
+pub struct DelayProfile {
+    sample: Rc<dyn Fn(&mut Pcg64) -> f64>,
+}
+
+impl DelayProfile {
+    /// Creates a profile with constant delay.
+    pub fn constant(delay: f64) -> Self {
+        assert!(delay >= 0., "Delay must be non-negative");
+        Self {
+            sample: Rc::new(move |_| delay),
+        }
+    }
+
+    /// Creates a profile with delays sampled from the specified distribution.
+    ///
+    /// Negative sampled delays are replaced with zero.
+    pub fn distribution<D>(dist: D) -> Self
+    where
+        D: Distribution<f64> + 'static,
+    {
+        Self {
+            sample: Rc::new(move |rand| dist.sample(rand).max(0.)),
+        }
+    }
+
+    fn sample(&self, rand: &mut Pcg64) -> f64 {
+        (self.sample)(rand)
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct DelayConfig {
+    default: Option<DelayProfile>,
+    event_types: FxHashMap<TypeId, DelayProfile>,
+    group_ids: FxHashMap<String, usize>,
+    component_groups: FxHashMap<Id, usize>,
+    group_pairs: FxHashMap<(usize, usize), DelayProfile>,
+}
+
+impl DelayConfig {
+    pub fn set_default(&mut self, profile: DelayProfile) {
+        self.default = Some(profile);
+    }
+
+    pub fn set_event_type(&mut self, type_id: TypeId, profile: DelayProfile) {
+        self.event_types.insert(type_id, profile);
+    }
+
+    pub fn set_component_group(&mut self, id: Id, group: &str) {
+        let group_id = self.group_id(group);
+        self.component_groups.insert(id, group_id);
+    }
+
+    pub fn group_components(&self, group: &str) -> Option<FxHashSet<Id>> {
+        let group_id = *self.group_ids.get(group)?;
+        Some(
+            self.component_groups
+                .iter()
+                .filter(|(_, component_group)| **component_group == group_id)
+                .map(|(id, _)| *id)
+                .collect(),
+        )
+    }
+
+    pub fn remove_component(&mut self, id: Id) {
+        self.component_groups.remove(&id);
+    }
+
+    pub fn set_group_pair(&mut self, src_group: &str, dst_group: &str, profile: DelayProfile) {
+        let key = (self.group_id(src_group), self.group_id(dst_group));
+        self.group_pairs.insert(key, profile);
+    }
+
+    // Samples the delay of event using the most specific matching profile, returns None if there is no such profile.
+    pub fn sample(&self, type_id: TypeId, src: Id, dst: Id, rand: &mut Pcg64) -> Option<f64> {
+        let profile = self
+            .event_types
+            .get(&type_id)
+            .or_else(|| {
+                let src_group = self.component_groups.get(&src)?;
+                let dst_group = self.component_groups.get(&dst)?;
+                self.group_pairs.get(&(*src_group, *dst_group))
+            })
+            .or(self.default.as_ref())?;
+        Some(profile.sample(rand))
+    }
+
+    fn group_id(&mut self, group: &str) -> usize {
+        let next_id = self.group_ids.len();
+        *self.group_ids.entry(group.to_owned()).or_insert(next_id)
+    }
+}
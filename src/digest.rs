@@ -0,0 +1,35 @@
+//! Platform-independent hashing of simulation outputs.
+
+// FNV-1a hash function, which unlike the standard hashers does not depend on platform and Rust version,
+// so the hashes can be stored and compared across runs, e.g. in trace digests and configuration hashes.
+pub(crate) struct DigestHasher {
+    state: u64,
+}
+
+impl DigestHasher {
+    pub fn new() -> Self {
+        Self {
+            state: 0xcbf29ce484222325,
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(0x100000001b3);
+        }
+        // separate the variable-length fields
+        self.write_u64(bytes.len() as u64);
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
@@ -0,0 +1,183 @@
+//! Cross-checking of simulation run against reference trace.
+//!
+//! Refactoring a large model is safe only if it preserves the behavior, i.e. the refactored model processes the
+//! same events in the same order. When the divergence guard is enabled via
+//! [`Simulation::enable_divergence_guard`](crate::Simulation::enable_divergence_guard), the simulation compares each
+//! processed event with the next processed event recorded in the reference [trace file](crate::trace_file), e.g.
+//! written by the model before refactoring, and stops at the first mismatch instead of running to completion and
+//! comparing the traces afterwards.
+//!
+//! The found [`Divergence`] includes the expected and actual events, the previous matching events and the snapshot
+//! of pending events at the moment of divergence, which usually points to the changed behavior. The run also
+//! diverges if it processes more events than recorded in the reference trace or runs out of events before
+//! processing all recorded events. Depending on [`DivergenceGuardConfig::action`], the divergence aborts the run with
+//! a panic describing it or pauses the run, so the model can be inspected.
+//!
+//! The reference trace should contain all processed events of the run, i.e. it should be recorded without filtering
+//! processed records by component, type or time. If the reference trace is recorded without payloads, the payloads
+//! are not compared. The fields which are expected to differ, e.g. event identifiers, can be excluded from the
+//! comparison like in [`trace_diff`](crate::trace_diff).
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use crate::limits::LimitAction;
+use crate::trace_diff::{comparison_key, read_records};
+use crate::trace_file::{TraceEventKind, TraceFileRecord};
+
+/// Configuration of divergence guard.
+#[derive(Clone, Debug)]
+pub struct DivergenceGuardConfig {
+    /// Path to the reference trace file.
+    pub reference: PathBuf,
+    /// Number of matching events before the divergence included in the report.
+    pub context: usize,
+    /// Maximum number of pending events included in the report.
+    pub max_pending_events: usize,
+    /// Record fields excluded from the comparison, e.g. `id` or `data.timestamp` for a payload field. The nested
+    /// fields are separated by dots.
+    pub ignored_fields: Vec<String>,
+    /// Action performed on divergence, aborts the run by default.
+    pub action: LimitAction,
+}
+
+impl DivergenceGuardConfig {
+    /// Creates a config comparing all fields with the specified reference trace, which reports 10 previous events
+    /// and 20 pending events and aborts the run on divergence.
+    pub fn new<P: Into<PathBuf>>(reference: P) -> Self {
+        Self {
+            reference: reference.into(),
+            context: 10,
+            max_pending_events: 20,
+            ignored_fields: Vec::new(),
+            action: LimitAction::Abort,
+        }
+    }
+}
+
+/// First divergence of the run from the reference trace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Index of the divergent processed event, counting from 0.
+    pub index: usize,
+    /// Simulation time when the divergence was found.
+    pub time: f64,
+    /// Event recorded in the reference trace, `None` if the run processed more events than recorded.
+    pub expected: Option<TraceFileRecord>,
+    /// Event processed by the run, `None` if the run ran out of events before processing all recorded events.
+    pub actual: Option<TraceFileRecord>,
+    /// Matching events processed before the divergence.
+    pub previous: Vec<TraceFileRecord>,
+    /// Listing of pending events at the moment of divergence,
+    /// see [`Simulation::dump_queue`](crate::Simulation::dump_queue).
+    pub pending_events: String,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Run diverged from reference trace at processed event {} (time {:.3})",
+            self.index, self.time
+        )?;
+        for record in self.previous.iter() {
+            writeln!(f, "  {}", to_json(record))?;
+        }
+        match &self.expected {
+            Some(record) => writeln!(f, "- {}", to_json(record))?,
+            None => writeln!(f, "- <end of reference trace>")?,
+        }
+        match &self.actual {
+            Some(record) => writeln!(f, "+ {}", to_json(record))?,
+            None => writeln!(f, "+ <no more events>")?,
+        }
+        write!(f, "{}", self.pending_events)
+    }
+}
+
+fn to_json(record: &TraceFileRecord) -> String {
+    serde_json::to_string(record).unwrap()
+}
+
+// Compares processed events with the reference trace.
+pub(crate) struct DivergenceGuard {
+    config: DivergenceGuardConfig,
+    reference: Box<dyn Iterator<Item = TraceFileRecord>>,
+    index: usize,
+    previous: VecDeque<TraceFileRecord>,
+    // Expected and actual events of the found divergence.
+    mismatch: Option<(Option<TraceFileRecord>, Option<TraceFileRecord>)>,
+}
+
+impl DivergenceGuard {
+    pub fn new(config: DivergenceGuardConfig) -> Self {
+        let reference =
+            read_records(config.reference.clone()).filter(|record| record.kind == TraceEventKind::Processed);
+        Self {
+            config,
+            reference: Box::new(reference),
+            index: 0,
+            previous: VecDeque::new(),
+            mismatch: None,
+        }
+    }
+
+    pub fn action(&self) -> LimitAction {
+        self.config.action
+    }
+
+    pub fn max_pending_events(&self) -> usize {
+        self.config.max_pending_events
+    }
+
+    pub fn has_mismatch(&self) -> bool {
+        self.mismatch.is_some()
+    }
+
+    pub fn on_event(&mut self, mut actual: TraceFileRecord) {
+        if self.mismatch.is_some() {
+            return;
+        }
+        let Some(expected) = self.reference.next() else {
+            self.mismatch = Some((None, Some(actual)));
+            return;
+        };
+        if expected.data.is_none() {
+            actual.data = None;
+        }
+        if comparison_key(&expected, &self.config.ignored_fields)
+            != comparison_key(&actual, &self.config.ignored_fields)
+        {
+            self.mismatch = Some((Some(expected), Some(actual)));
+            return;
+        }
+        self.index += 1;
+        self.previous.push_back(actual);
+        if self.previous.len() > self.config.context {
+            self.previous.pop_front();
+        }
+    }
+
+    // Called when the run has no more events to process.
+    pub fn on_drained(&mut self) {
+        if self.mismatch.is_some() {
+            return;
+        }
+        if let Some(expected) = self.reference.next() {
+            self.mismatch = Some((Some(expected), None));
+        }
+    }
+
+    pub fn take_divergence(&mut self, time: f64, pending_events: String) -> Option<Divergence> {
+        let (expected, actual) = self.mismatch.take()?;
+        Some(Divergence {
+            index: self.index,
+            time,
+            expected,
+            actual,
+            previous: self.previous.iter().cloned().collect(),
+            pending_events,
+        })
+    }
+}
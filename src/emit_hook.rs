@@ -0,0 +1,40 @@
+//! Hooks applied to the events emitted by a component.
+//!
+//! A hook installed via [`SimulationContext::set_emit_hook`](crate::SimulationContext::set_emit_hook) is called
+//! for each event emitted via this context before the event is added to the simulation. The hook can observe the
+//! event and change its delay or payload, e.g. to add jitter or annotations uniformly to the component output
+//! without changing each `emit` call. Unlike [routers](crate::routing), which are applied to all events in the
+//! simulation, the hook is local to the context.
+
+use crate::component::Id;
+use crate::context::SimulationContext;
+use crate::event::EventData;
+
+pub(crate) type EmitHookFn = Box<dyn FnMut(&SimulationContext, &mut EmittedEvent)>;
+
+/// Event passed to the emission hook.
+pub struct EmittedEvent {
+    src: Id,
+    dst: Id,
+    /// Delay of the event, which is relative to the preceding event for the events emitted via
+    /// [`emit_after`](crate::SimulationContext::emit_after).
+    pub delay: f64,
+    /// Event payload, which can be modified via `downcast_mut` or replaced.
+    pub data: Box<dyn EventData>,
+}
+
+impl EmittedEvent {
+    pub(crate) fn new(src: Id, dst: Id, delay: f64, data: Box<dyn EventData>) -> Self {
+        Self { src, dst, delay, data }
+    }
+
+    /// Returns the identifier of the component emitting the event.
+    pub fn src(&self) -> Id {
+        self.src
+    }
+
+    /// Returns the identifier of event destination.
+    pub fn dst(&self) -> Id {
+        self.dst
+    }
+}
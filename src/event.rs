@@ -7,10 +7,19 @@ use dyn_clone::{clone_trait_object, DynClone};
 use serde::ser::Serialize;
 
 use crate::component::Id;
+use crate::time::SimTime;
 
 /// Event identifier.
 pub type EventId = u64;
 
+/// Identifier of event type (type of event payload).
+///
+/// Event type names are interned, i.e. each event type is assigned a unique identifier which is used instead of
+/// the type name in framework internals. The identifiers are assigned sequentially starting from 0 in the order
+/// of the first use of event type. See [`Simulation::lookup_event_type_id`](crate::Simulation::lookup_event_type_id)
+/// and [`Simulation::lookup_event_type_name`](crate::Simulation::lookup_event_type_name).
+pub type EventTypeId = u32;
+
 /// Trait that should be implemented by event payload.
 pub trait EventData: Downcast + DynClone + erased_serde::Serialize {}
 
@@ -22,6 +31,61 @@ erased_serde::serialize_trait_object!(EventData);
 
 impl<T: Serialize + DynClone + 'static> EventData for T {}
 
+/// Allows using the specified type which does not implement `Serialize` as event payload.
+///
+/// The macro implements `Serialize` for the type by serializing it as a unit struct,
+/// i.e. the payload contents are omitted in event logs and other serde-dependent facilities.
+/// This is useful for payloads containing non-serializable data such as handles or shared references.
+/// The type still must implement `Clone`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use simcore::{cast, opaque_event, Event, EventHandler, Simulation};
+///
+/// #[derive(Clone)]
+/// struct Attach {
+///     buffer: Rc<RefCell<Vec<u8>>>,
+/// }
+///
+/// opaque_event!(Attach);
+///
+/// struct Writer {}
+///
+/// impl EventHandler for Writer {
+///     fn on(&mut self, event: Event) {
+///         cast!(match event.data {
+///             Attach { buffer } => {
+///                 buffer.borrow_mut().push(42);
+///             }
+///         })
+///     }
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let writer_id = sim.add_handler("writer", Rc::new(RefCell::new(Writer {})));
+/// let client_ctx = sim.create_context("client");
+/// let buffer = Rc::new(RefCell::new(Vec::new()));
+/// client_ctx.emit(Attach { buffer: buffer.clone() }, writer_id, 1.);
+/// sim.step_until_no_events();
+/// assert_eq!(*buffer.borrow(), vec![42]);
+/// ```
+#[macro_export]
+macro_rules! opaque_event {
+    ($type:ident) => {
+        impl $crate::serde::Serialize for $type {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::Serializer,
+            {
+                serializer.serialize_unit_struct(stringify!($type))
+            }
+        }
+    };
+}
+
 /// Representation of event.
 #[derive(Clone)]
 pub struct Event {
@@ -35,6 +99,12 @@ pub struct Event {
     pub src: Id,
     /// Identifier of event destination.
     pub dst: Id,
+    /// Priority of event among the events with equal time.
+    ///
+    /// Events with equal time are processed in the order of their priorities (smaller values first) and then
+    /// in the order of their creation. The default priority is 0, see
+    /// [`SimulationContext::emit_with_priority`](crate::SimulationContext::emit_with_priority).
+    pub priority: i64,
     /// Event payload.
     pub data: Box<dyn EventData>,
 }
@@ -49,7 +119,11 @@ impl PartialEq for Event {
 
 impl Ord for Event {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.time.total_cmp(&self.time).then_with(|| other.id.cmp(&self.id))
+        other
+            .time
+            .total_cmp(&self.time)
+            .then_with(|| other.priority.cmp(&self.priority))
+            .then_with(|| other.id.cmp(&self.id))
     }
 }
 
@@ -78,7 +152,41 @@ where
     pub data: T,
 }
 
+/// Borrowed version of [`TypedEvent`], used for inspecting events without taking their ownership.
+#[derive(Clone, Copy)]
+pub struct EventRef<'a, T> {
+    /// Unique event identifier.
+    pub id: EventId,
+    /// Time of event occurrence.
+    pub time: f64,
+    /// Identifier of event source.
+    pub src: Id,
+    /// Identifier of event destination.
+    pub dst: Id,
+    /// Event payload.
+    pub data: &'a T,
+}
+
 impl Event {
+    /// Returns the time of event occurrence as [`SimTime`].
+    pub fn sim_time(&self) -> SimTime {
+        SimTime::from_secs(self.time)
+    }
+
+    /// Returns [`EventRef`] with payload of type `T` or `None` if the payload has another type.
+    pub fn as_typed<T>(&self) -> Option<EventRef<'_, T>>
+    where
+        T: EventData,
+    {
+        self.data.downcast_ref::<T>().map(|data| EventRef {
+            id: self.id,
+            time: self.time,
+            src: self.src,
+            dst: self.dst,
+            data,
+        })
+    }
+
     /// Converts [`Event`] to [`TypedEvent`] of type `T`.
     ///
     /// Panics on downcast error.
@@ -0,0 +1,846 @@
+//! Experiments consisting of multiple simulation runs.
+//!
+//! The results of a single stochastic simulation run depend on the random seed, so the metrics of interest are
+//! usually estimated over multiple independent runs with different seeds (replications). [`Replications`] runs the
+//! user-defined model factory, which builds and runs the simulation with the specified seed and returns its metrics,
+//! for each seed and aggregates the collected metrics into a [`ReplicationReport`] with their means, variances and
+//! confidence intervals.
+//!
+//! The replications can be run in parallel threads via [`Replications::with_threads`], which requires the `thread`
+//! feature. Each replication builds its own simulation on the thread running it, and the report does not depend on
+//! the number of threads, since the runs are ordered by their seeds. The per-run metrics can also be passed to
+//! [`compare_runs`](crate::analysis::compare_runs) to compare the replications of different configurations.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde::Serialize;
+//! use simcore::analysis::RunMetrics;
+//! use simcore::experiment::Replications;
+//! use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+//!
+//! #[derive(Clone, Serialize)]
+//! struct Request {}
+//!
+//! struct Server {
+//!     ctx: SimulationContext,
+//!     busy_until: f64,
+//!     total_latency: f64,
+//!     requests: u64,
+//! }
+//!
+//! impl EventHandler for Server {
+//!     fn on(&mut self, event: Event) {
+//!         cast!(match event.data {
+//!             Request {} => {
+//!                 let start = self.busy_until.max(self.ctx.time());
+//!                 self.busy_until = start + self.ctx.sample_exponential(2.);
+//!                 self.total_latency += self.busy_until - self.ctx.time();
+//!                 self.requests += 1;
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! fn run_model(seed: u64) -> RunMetrics {
+//!     let mut sim = Simulation::new(seed);
+//!     let server_ctx = sim.create_context("server");
+//!     let server_id = server_ctx.id();
+//!     let server = std::rc::Rc::new(std::cell::RefCell::new(Server {
+//!         ctx: server_ctx,
+//!         busy_until: 0.,
+//!         total_latency: 0.,
+//!         requests: 0,
+//!     }));
+//!     sim.add_handler("server", server.clone());
+//!     let client = sim.create_context("client");
+//!     let mut time = 0.;
+//!     for _ in 0..1000 {
+//!         time += client.sample_exponential(1.);
+//!         client.emit(Request {}, server_id, time);
+//!     }
+//!     sim.step_until_no_events();
+//!     let server = server.borrow();
+//!     RunMetrics::from([("latency".to_owned(), server.total_latency / server.requests as f64)])
+//! }
+//!
+//! let report = Replications::new(10).with_base_seed(100).run(run_model);
+//! assert_eq!(report.runs.len(), 10);
+//! assert_eq!(report.runs[3].seed, 103);
+//! let latency = report.get("latency").unwrap();
+//! assert_eq!(latency.count, 10);
+//! // mean latency of M/M/1 queue with arrival rate 1 and service rate 2 is 1
+//! assert!((latency.mean - 1.).abs() < 0.2);
+//! assert!(latency.confidence_half_width(0.95).unwrap() < 0.2);
+//! println!("{}", report);
+//! ```
+//!
+//! # Parameter sweeps
+//!
+//! [`SweepRunner`] runs the simulation for each combination of parameter values from a [`ParamGrid`], optionally
+//! with multiple replications of each combination, and collects a [`SweepResults`] table with a row per run. The
+//! user-defined factory builds the simulation for the specified [`SweepParams`] and seed, and the runner runs it
+//! via [`Simulation::run`] or [`Simulation::run_until_time`], so the failed runs are reported in the table instead of
+//! aborting the sweep. Each row contains the parameter values, the seed, the termination reason and the metrics of
+//! the run, which can be written as CSV via [`SweepResults::write_csv`] for the analysis in external tools or
+//! aggregated per combination via [`SweepResults::summary`]. The results include the [run metadata](crate::metadata)
+//! of the sweep with the labels and configuration set via [`SweepRunner::with_run_label`] and
+//! [`SweepRunner::with_run_config`], which is written at the beginning of the CSV.
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! use serde::Serialize;
+//! use simcore::analysis::RunMetrics;
+//! use simcore::experiment::{ParamGrid, SweepRunner};
+//! use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+//!
+//! #[derive(Clone, Serialize)]
+//! struct Tick {}
+//!
+//! struct Ticker {
+//!     ctx: SimulationContext,
+//!     interval: f64,
+//!     ticks: u64,
+//! }
+//!
+//! impl EventHandler for Ticker {
+//!     fn on(&mut self, event: Event) {
+//!         cast!(match event.data {
+//!             Tick {} => {
+//!                 self.ticks += 1;
+//!                 self.ctx.emit_self(Tick {}, self.interval);
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! let grid = ParamGrid::new().with("interval", [1., 2.]).with("mode", ["a", "b"]);
+//! let results = SweepRunner::new(grid)
+//!     .with_replications(2)
+//!     .with_time_limit(10.)
+//!     .with_run_label("experiment", "ticks")
+//!     .run(
+//!     |params, seed| {
+//!         let mut sim = Simulation::new(seed);
+//!         let ctx = sim.create_context("ticker");
+//!         ctx.emit_self_now(Tick {});
+//!         let ticker = Ticker { ctx, interval: params.f64("interval"), ticks: 0 };
+//!         sim.add_handler("ticker", Rc::new(RefCell::new(ticker)));
+//!         sim
+//!     },
+//!     |_sim, result| RunMetrics::from([("rate".to_owned(), result.processed_events as f64 / result.time)]),
+//! );
+//! assert_eq!(results.rows.len(), 8);
+//! assert_eq!(results.rows[5].params.to_string(), "interval=2, mode=a");
+//! assert_eq!(results.rows[5].seed, 1);
+//! assert_eq!(results.rows[5].metrics["processed_events"], 6.);
+//!
+//! let mut csv = Vec::new();
+//! results.write_csv(&mut csv).unwrap();
+//! let csv = String::from_utf8(csv).unwrap();
+//! let mut lines = csv.lines();
+//! assert!(lines.next().unwrap().starts_with("# {\"metadata\":{"));
+//! assert_eq!(
+//!     lines.next().unwrap(),
+//!     "interval,mode,replication,seed,termination,emitted_events,processed_events,rate,time"
+//! );
+//! assert_eq!(results.summary().len(), 4);
+//! println!("{}", results);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::analysis::{collect_values, RunMetrics, SampleStats};
+use crate::metadata::{config_hash, RunMetadata};
+use crate::run::{RunResult, TerminationReason};
+use crate::Simulation;
+
+/// Runner of replications with consecutive seeds.
+///
+/// See [module](self) documentation for examples.
+#[derive(Clone, Debug)]
+pub struct Replications {
+    count: usize,
+    base_seed: u64,
+    threads: usize,
+}
+
+impl Replications {
+    /// Creates a runner of the specified number of replications with seeds starting from zero.
+    ///
+    /// Panics if the number of replications is zero.
+    pub fn new(count: usize) -> Self {
+        assert!(count > 0, "Number of replications must be positive");
+        Self {
+            count,
+            base_seed: 0,
+            threads: 1,
+        }
+    }
+
+    /// Sets the seed of the first replication, the seeds of the next replications are incremented by one.
+    pub fn with_base_seed(mut self, seed: u64) -> Self {
+        self.base_seed = seed;
+        self
+    }
+
+    /// Runs the replications in the specified number of threads.
+    ///
+    /// Panics if the number of threads is zero.
+    #[cfg(feature = "thread")]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Number of threads must be positive");
+        self.threads = threads;
+        self
+    }
+
+    /// Returns the seeds of replications.
+    pub fn seeds(&self) -> impl Iterator<Item = u64> {
+        let base_seed = self.base_seed;
+        (0..self.count as u64).map(move |i| base_seed + i)
+    }
+
+    /// Runs the model factory for each seed and aggregates the returned metrics.
+    ///
+    /// The factory should build the simulation with the specified seed, run it and return the metrics of the run.
+    /// If the factory panics, the panic is propagated after the completion of the running replications.
+    pub fn run<F>(&self, model: F) -> ReplicationReport
+    where
+        F: Fn(u64) -> RunMetrics + Sync,
+    {
+        let seeds: Vec<u64> = self.seeds().collect();
+        let metrics = if self.threads > 1 {
+            run_parallel(&seeds, self.threads, &|seed: &u64| model(*seed))
+        } else {
+            seeds.iter().map(|seed| model(*seed)).collect()
+        };
+        ReplicationReport::new(
+            seeds
+                .into_iter()
+                .zip(metrics)
+                .map(|(seed, metrics)| ReplicationRun { seed, metrics })
+                .collect(),
+        )
+    }
+}
+
+// Runs the jobs in worker threads, which take the next job once they finish the previous one, and returns the
+// results in the order of jobs.
+fn run_parallel<J, R, F>(jobs: &[J], threads: usize, run: &F) -> Vec<R>
+where
+    J: Sync,
+    R: Send,
+    F: Fn(&J) -> R + Sync,
+{
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = (0..jobs.len()).map(|_| None).collect();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(jobs.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut completed = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= jobs.len() {
+                            break completed;
+                        }
+                        completed.push((index, run(&jobs[index])));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(completed) => {
+                    for (index, result) in completed {
+                        results[index] = Some(result);
+                    }
+                }
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+    results.into_iter().map(|result| result.unwrap()).collect()
+}
+
+/// Metrics of a single replication.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicationRun {
+    /// Seed of the replication.
+    pub seed: u64,
+    /// Metrics returned by the model factory.
+    pub metrics: RunMetrics,
+}
+
+/// Aggregated metrics of replications.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicationReport {
+    /// Metrics of individual replications ordered by seed.
+    pub runs: Vec<ReplicationRun>,
+    /// Statistics of each metric over the replications which returned it, ordered by metric name.
+    pub metrics: BTreeMap<String, SampleStats>,
+}
+
+impl ReplicationReport {
+    fn new(runs: Vec<ReplicationRun>) -> Self {
+        let run_metrics: Vec<RunMetrics> = runs.iter().map(|run| run.metrics.clone()).collect();
+        let metrics = collect_values(&run_metrics)
+            .into_iter()
+            .map(|(name, values)| (name.to_owned(), SampleStats::from_values(&values)))
+            .collect();
+        Self { runs, metrics }
+    }
+
+    /// Returns the statistics of metric with the specified name.
+    pub fn get(&self, name: &str) -> Option<&SampleStats> {
+        self.metrics.get(name)
+    }
+
+    /// Returns the metrics of individual replications, e.g. to pass them to
+    /// [`compare_runs`](crate::analysis::compare_runs).
+    pub fn run_metrics(&self) -> Vec<RunMetrics> {
+        self.runs.iter().map(|run| run.metrics.clone()).collect()
+    }
+}
+
+impl Display for ReplicationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self.metrics.keys().map(|name| name.len()).max().unwrap_or(0).max(6);
+        writeln!(
+            f,
+            "{:<width$}  {:>5}  {:>12}  {:>12}  {:>12}",
+            "metric", "runs", "mean", "std dev", "95% CI ±"
+        )?;
+        for (name, stats) in &self.metrics {
+            let half_width = stats
+                .confidence_half_width(0.95)
+                .map_or("-".to_owned(), |h| format!("{:.4}", h));
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>12.4}  {:>12.4}  {:>12}",
+                name, stats.count, stats.mean, stats.std_dev, half_width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Value of sweep parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    /// Integer value.
+    Int(i64),
+    /// Floating-point value.
+    Float(f64),
+    /// Boolean value.
+    Bool(bool),
+    /// String value.
+    Str(String),
+}
+
+impl ParamValue {
+    /// Returns the value as a number if it is integer or floating-point.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParamValue::Int(value) => Some(*value as f64),
+            ParamValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ParamValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParamValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParamValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ParamValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamValue::Int(value) => write!(f, "{}", value),
+            ParamValue::Float(value) => write!(f, "{}", value),
+            ParamValue::Bool(value) => write!(f, "{}", value),
+            ParamValue::Str(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<i32> for ParamValue {
+    fn from(value: i32) -> Self {
+        ParamValue::Int(value as i64)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self {
+        ParamValue::Int(value)
+    }
+}
+
+impl From<u32> for ParamValue {
+    fn from(value: u32) -> Self {
+        ParamValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self {
+        ParamValue::Float(value)
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Str(value.to_owned())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::Str(value)
+    }
+}
+
+/// Grid of parameter values, whose combinations are run by [`SweepRunner`].
+///
+/// See [module](self) documentation for examples.
+#[derive(Clone, Debug, Default)]
+pub struct ParamGrid {
+    params: Vec<(String, Vec<ParamValue>)>,
+}
+
+impl ParamGrid {
+    /// Creates an empty grid, which has a single combination without parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the parameter with the specified values.
+    ///
+    /// Panics if the parameter is already added or has no values.
+    pub fn with<V, I>(mut self, name: &str, values: I) -> Self
+    where
+        V: Into<ParamValue>,
+        I: IntoIterator<Item = V>,
+    {
+        assert!(
+            self.params.iter().all(|(param, _)| param != name),
+            "Parameter {} is already added",
+            name
+        );
+        let values: Vec<ParamValue> = values.into_iter().map(Into::into).collect();
+        assert!(!values.is_empty(), "Parameter {} has no values", name);
+        self.params.push((name.to_owned(), values));
+        self
+    }
+
+    /// Returns the parameter names in the order of addition.
+    pub fn names(&self) -> Vec<&str> {
+        self.params.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Returns all combinations of parameter values, the last added parameter varies fastest.
+    pub fn combinations(&self) -> Vec<SweepParams> {
+        let mut combinations = vec![SweepParams::default()];
+        for (name, values) in &self.params {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |value| {
+                        let mut params = params.clone();
+                        params.values.push((name.clone(), value.clone()));
+                        params
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+}
+
+/// Combination of parameter values passed to the simulation factory of [`SweepRunner`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SweepParams {
+    values: Vec<(String, ParamValue)>,
+}
+
+impl SweepParams {
+    /// Returns the value of parameter with the specified name.
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.values
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the parameter names and values in the order of grid parameters.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ParamValue)> {
+        self.values.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Returns the numeric value of parameter, integer values are converted.
+    ///
+    /// Panics if the parameter is unknown or is not a number.
+    pub fn f64(&self, name: &str) -> f64 {
+        self.value(name)
+            .as_f64()
+            .unwrap_or_else(|| panic!("Parameter {} is not a number", name))
+    }
+
+    /// Returns the integer value of parameter.
+    ///
+    /// Panics if the parameter is unknown or is not integer.
+    pub fn i64(&self, name: &str) -> i64 {
+        self.value(name)
+            .as_i64()
+            .unwrap_or_else(|| panic!("Parameter {} is not an integer", name))
+    }
+
+    /// Returns the boolean value of parameter.
+    ///
+    /// Panics if the parameter is unknown or is not boolean.
+    pub fn bool(&self, name: &str) -> bool {
+        self.value(name)
+            .as_bool()
+            .unwrap_or_else(|| panic!("Parameter {} is not a boolean", name))
+    }
+
+    /// Returns the string value of parameter.
+    ///
+    /// Panics if the parameter is unknown or is not string.
+    pub fn str(&self, name: &str) -> &str {
+        self.value(name)
+            .as_str()
+            .unwrap_or_else(|| panic!("Parameter {} is not a string", name))
+    }
+
+    fn value(&self, name: &str) -> &ParamValue {
+        self.get(name).unwrap_or_else(|| panic!("Unknown parameter {}", name))
+    }
+}
+
+impl Display for SweepParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runner of parameter sweep, which runs the simulation for each combination of parameter values.
+///
+/// See [module](self) documentation for examples.
+#[derive(Clone, Debug)]
+pub struct SweepRunner {
+    grid: ParamGrid,
+    replications: usize,
+    base_seed: u64,
+    threads: usize,
+    time_limit: Option<f64>,
+    labels: BTreeMap<String, String>,
+    config_hash: Option<String>,
+}
+
+impl SweepRunner {
+    /// Creates a runner of the grid combinations with a single replication with seed zero.
+    pub fn new(grid: ParamGrid) -> Self {
+        Self {
+            grid,
+            replications: 1,
+            base_seed: 0,
+            threads: 1,
+            time_limit: None,
+            labels: BTreeMap::new(),
+            config_hash: None,
+        }
+    }
+
+    /// Sets the number of replications of each combination.
+    ///
+    /// The replications of all combinations use the same seeds, so the combinations are compared under common
+    /// random numbers.
+    ///
+    /// Panics if the number of replications is zero.
+    pub fn with_replications(mut self, count: usize) -> Self {
+        assert!(count > 0, "Number of replications must be positive");
+        self.replications = count;
+        self
+    }
+
+    /// Sets the seed of the first replication, the seeds of the next replications are incremented by one.
+    pub fn with_base_seed(mut self, seed: u64) -> Self {
+        self.base_seed = seed;
+        self
+    }
+
+    /// Runs each simulation until the specified time via [`Simulation::run_until_time`] instead of running it until
+    /// there are no more events.
+    pub fn with_time_limit(mut self, time: f64) -> Self {
+        self.time_limit = Some(time);
+        self
+    }
+
+    /// Sets the label of the sweep included in the metadata of its results, replacing the previous value for the same
+    /// key, see [`Simulation::set_run_label`].
+    pub fn with_run_label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// Sets the configuration of the sweep whose hash is included in the metadata of its results, see
+    /// [`Simulation::set_run_config`].
+    pub fn with_run_config<C: Serialize>(mut self, config: &C) -> Self {
+        self.config_hash = Some(config_hash(config));
+        self
+    }
+
+    /// Runs the simulations in the specified number of threads.
+    ///
+    /// Panics if the number of threads is zero.
+    #[cfg(feature = "thread")]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Number of threads must be positive");
+        self.threads = threads;
+        self
+    }
+
+    /// Runs the simulation for each combination of parameter values and replication seed.
+    ///
+    /// The factory `build` should create the simulation for the specified parameters and seed, which is then run by
+    /// the runner. After each run, the `metrics` function extracts the model-specific metrics from the simulation
+    /// and the run result, which are added to the built-in metrics `time`, `processed_events` and
+    /// `emitted_events` of the run. The panics raised during the run are reported as
+    /// [`TerminationReason::Error`] in the results, while the panics of the factory and the metrics function are
+    /// propagated.
+    pub fn run<B, M>(&self, build: B, metrics: M) -> SweepResults
+    where
+        B: Fn(&SweepParams, u64) -> Simulation + Sync,
+        M: Fn(&Simulation, &RunResult) -> RunMetrics + Sync,
+    {
+        let mut metadata = RunMetadata::new(self.base_seed);
+        metadata.labels = self.labels.clone();
+        metadata.config_hash = self.config_hash.clone();
+        let mut jobs = Vec::new();
+        for (combination, params) in self.grid.combinations().into_iter().enumerate() {
+            for replication in 0..self.replications {
+                jobs.push((combination, params.clone(), replication));
+            }
+        }
+        let run_job = |(combination, params, replication): &(usize, SweepParams, usize)| {
+            let seed = self.base_seed + *replication as u64;
+            let mut sim = build(params, seed);
+            let result = match self.time_limit {
+                Some(time) => sim.run_until_time(time),
+                None => sim.run(),
+            };
+            let mut run_metrics = RunMetrics::from([
+                ("time".to_owned(), result.time),
+                ("processed_events".to_owned(), result.processed_events as f64),
+                ("emitted_events".to_owned(), result.emitted_events as f64),
+            ]);
+            run_metrics.extend(metrics(&sim, &result));
+            SweepRow {
+                combination: *combination,
+                params: params.clone(),
+                replication: *replication,
+                seed,
+                termination: result.termination,
+                metrics: run_metrics,
+            }
+        };
+        let rows = if self.threads > 1 {
+            run_parallel(&jobs, self.threads, &run_job)
+        } else {
+            jobs.iter().map(run_job).collect()
+        };
+        SweepResults {
+            metadata,
+            param_names: self.grid.names().into_iter().map(str::to_owned).collect(),
+            rows,
+        }
+    }
+}
+
+/// Result of a single run of parameter sweep.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepRow {
+    /// Index of the parameter combination in [`ParamGrid::combinations`].
+    pub combination: usize,
+    /// Parameter values of the run.
+    pub params: SweepParams,
+    /// Index of the replication of the combination.
+    pub replication: usize,
+    /// Seed of the run.
+    pub seed: u64,
+    /// Reason of stopping the run.
+    pub termination: TerminationReason,
+    /// Built-in and model-specific metrics of the run.
+    pub metrics: RunMetrics,
+}
+
+/// Results of parameter sweep with a row per run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepResults {
+    /// Metadata of the sweep, where the seed is the base seed and the start time is the time of starting the runs.
+    pub metadata: RunMetadata,
+    /// Names of the grid parameters.
+    pub param_names: Vec<String>,
+    /// Rows ordered by parameter combination and replication.
+    pub rows: Vec<SweepRow>,
+}
+
+impl SweepResults {
+    /// Returns the names of metrics returned by any run in alphabetical order.
+    pub fn metric_names(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.metrics.keys().map(String::as_str))
+            .collect();
+        names.into_iter().collect()
+    }
+
+    /// Returns the statistics of metrics over the replications of each parameter combination.
+    pub fn summary(&self) -> Vec<(SweepParams, BTreeMap<String, SampleStats>)> {
+        let mut groups: Vec<(SweepParams, Vec<RunMetrics>)> = Vec::new();
+        for row in &self.rows {
+            if groups.len() <= row.combination {
+                groups.resize(row.combination + 1, (SweepParams::default(), Vec::new()));
+            }
+            let group = &mut groups[row.combination];
+            group.0 = row.params.clone();
+            group.1.push(row.metrics.clone());
+        }
+        groups
+            .into_iter()
+            .filter(|(_, runs)| !runs.is_empty())
+            .map(|(params, runs)| {
+                let stats = collect_values(&runs)
+                    .into_iter()
+                    .map(|(name, values)| (name.to_owned(), SampleStats::from_values(&values)))
+                    .collect();
+                (params, stats)
+            })
+            .collect()
+    }
+
+    /// Writes the results as CSV with a header and a row per run.
+    ///
+    /// The first line is a comment starting with `#`, which contains the [metadata](Self::metadata) of the sweep in
+    /// JSON format, in the same way as the header of [trace files](crate::trace_file). It can be skipped when
+    /// reading the CSV, e.g. via `comment="#"` in pandas. The columns are the parameters, `replication`, `seed`,
+    /// `termination` and the metrics in alphabetical order. The cells of metrics not returned by the run are empty.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let header = CsvHeader {
+            metadata: &self.metadata,
+        };
+        writeln!(writer, "# {}", serde_json::to_string(&header).unwrap())?;
+        let metric_names = self.metric_names();
+        for row in self.table(&metric_names) {
+            let cells: Vec<String> = row.iter().map(|cell| csv_escape(cell)).collect();
+            writeln!(writer, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn table(&self, metric_names: &[&str]) -> Vec<Vec<String>> {
+        let mut header: Vec<String> = self.param_names.clone();
+        header.extend(["replication", "seed", "termination"].map(str::to_owned));
+        header.extend(metric_names.iter().map(|name| name.to_string()));
+        let mut table = vec![header];
+        for row in &self.rows {
+            let mut cells: Vec<String> = row.params.iter().map(|(_, value)| value.to_string()).collect();
+            cells.push(row.replication.to_string());
+            cells.push(row.seed.to_string());
+            cells.push(termination_label(&row.termination).to_owned());
+            for name in metric_names {
+                cells.push(row.metrics.get(*name).map_or(String::new(), |value| value.to_string()));
+            }
+            table.push(cells);
+        }
+        table
+    }
+}
+
+impl Display for SweepResults {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let table = self.table(&self.metric_names());
+        let mut widths = vec![0; table[0].len()];
+        for row in &table {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in &table {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", cells.join("  "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct CsvHeader<'a> {
+    metadata: &'a RunMetadata,
+}
+
+fn termination_label(termination: &TerminationReason) -> &'static str {
+    match termination {
+        TerminationReason::Drained => "drained",
+        TerminationReason::TimeReached => "time_reached",
+        TerminationReason::StopCondition => "stop_condition",
+        TerminationReason::Budget(_) => "budget",
+        TerminationReason::Paused => "paused",
+        TerminationReason::PeerError(_) => "peer_error",
+        TerminationReason::Error(_) => "error",
+    }
+}
+
+pub(crate) fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
@@ -0,0 +1,325 @@
+//! Generator of stochastic arrivals.
+//!
+//! Models with open workload, such as queueing systems or benchmarks, are usually driven by a source of arrivals
+//! (requests, jobs, customers) with random interarrival times. This module provides a configurable
+//! [`ArrivalGenerator`] component which emits user-defined events to the target components.
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use serde::Serialize;
+
+use crate::component::Id;
+use crate::event::{Event, EventData, EventId};
+use crate::handler::EventHandler;
+use crate::workload::WorkloadModel;
+use crate::{cast, SimulationContext};
+
+type SampleFn<T> = Box<dyn FnMut(&SimulationContext) -> T>;
+type MakeEventFn<T> = Box<dyn FnMut(&SimulationContext, u64) -> T>;
+
+/// Policy of selecting the destination of generated events.
+#[derive(Clone, Debug)]
+pub enum TargetPolicy {
+    /// All events are sent to a single component.
+    Single(Id),
+    /// Events are sent to the components in turn.
+    RoundRobin(Vec<Id>),
+    /// Each event is sent to a component selected uniformly at random.
+    Random(Vec<Id>),
+    /// Each event is sent to a component selected at random with probability proportional to its weight.
+    Weighted(Vec<(Id, f64)>),
+}
+
+enum TargetSelector {
+    Single(Id),
+    RoundRobin(Vec<Id>, usize),
+    Random(Vec<Id>),
+    Weighted(Vec<Id>, WeightedIndex<f64>),
+}
+
+impl TargetSelector {
+    fn new(policy: TargetPolicy) -> Self {
+        match policy {
+            TargetPolicy::Single(id) => Self::Single(id),
+            TargetPolicy::RoundRobin(ids) => {
+                assert!(!ids.is_empty(), "Target list must not be empty");
+                Self::RoundRobin(ids, 0)
+            }
+            TargetPolicy::Random(ids) => {
+                assert!(!ids.is_empty(), "Target list must not be empty");
+                Self::Random(ids)
+            }
+            TargetPolicy::Weighted(targets) => {
+                let (ids, weights): (Vec<_>, Vec<_>) = targets.into_iter().unzip();
+                let dist = WeightedIndex::new(weights).expect("Target weights must be non-negative with positive sum");
+                Self::Weighted(ids, dist)
+            }
+        }
+    }
+
+    fn select(&mut self, ctx: &SimulationContext) -> Id {
+        match self {
+            Self::Single(id) => *id,
+            Self::RoundRobin(ids, next) => {
+                let id = ids[*next];
+                *next = (*next + 1) % ids.len();
+                id
+            }
+            Self::Random(ids) => ids[ctx.gen_range(0..ids.len())],
+            Self::Weighted(ids, dist) => ids[ctx.sample_from_distribution(dist)],
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct NextArrival {}
+
+/// Component which generates arrivals of user-defined events.
+///
+/// The generator produces arrivals separated by random interarrival times, starting from the start time and
+/// until the stop time or the maximum number of arrivals is reached. Each arrival consists of a batch of events,
+/// which are created by the user-defined closure and emitted at the arrival time to the targets selected
+/// according to [`TargetPolicy`]. The closure is passed the generator context and the sequential number of event
+/// starting from 0.
+///
+/// By default, the interarrival times are exponentially distributed with rate 1 (i.e. arrivals form a Poisson
+/// process), the batch size is 1, the start time is 0 and the stop time is not limited.
+///
+/// The generator must be registered as event handler under the name of its context and started via
+/// [`start`](Self::start).
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use serde::Serialize;
+/// use simcore::generator::{ArrivalGenerator, TargetPolicy};
+/// use simcore::{cast, Event, EventHandler, Simulation};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {
+///     id: u64,
+/// }
+///
+/// struct Server {
+///     received: Vec<(u64, f64)>,
+/// }
+///
+/// impl EventHandler for Server {
+///     fn on(&mut self, event: Event) {
+///         cast!(match event.data {
+///             Request { id } => {
+///                 self.received.push((id, event.time));
+///             }
+///         })
+///     }
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let server = Rc::new(RefCell::new(Server { received: Vec::new() }));
+/// let server_id = sim.add_handler("server", server.clone());
+///
+/// let generator = ArrivalGenerator::new(sim.create_context("generator"), |_ctx, id| Request { id })
+///     .with_fixed_interarrival(2.)
+///     .with_start_time(1.)
+///     .with_stop_time(10.)
+///     .with_targets(TargetPolicy::Single(server_id));
+/// let generator = Rc::new(RefCell::new(generator));
+/// sim.add_handler("generator", generator.clone());
+/// generator.borrow_mut().start();
+///
+/// sim.step_until_no_events();
+/// assert_eq!(server.borrow().received, vec![(0, 3.), (1, 5.), (2, 7.), (3, 9.)]);
+/// assert_eq!(generator.borrow().arrival_count(), 4);
+/// ```
+pub struct ArrivalGenerator<T: EventData> {
+    ctx: SimulationContext,
+    make_event: MakeEventFn<T>,
+    interarrival: SampleFn<f64>,
+    batch_size: SampleFn<u64>,
+    start_time: f64,
+    stop_time: f64,
+    max_arrivals: Option<u64>,
+    targets: Option<TargetSelector>,
+    next_arrival: Option<EventId>,
+    arrival_count: u64,
+    event_count: u64,
+}
+
+impl<T: EventData> ArrivalGenerator<T> {
+    /// Creates a generator with the specified context and closure which creates the generated events.
+    pub fn new<F>(ctx: SimulationContext, make_event: F) -> Self
+    where
+        F: FnMut(&SimulationContext, u64) -> T + 'static,
+    {
+        Self {
+            ctx,
+            make_event: Box::new(make_event),
+            interarrival: Box::new(|ctx| ctx.sample_exponential(1.)),
+            batch_size: Box::new(|_| 1),
+            start_time: 0.,
+            stop_time: f64::INFINITY,
+            max_arrivals: None,
+            targets: None,
+            next_arrival: None,
+            arrival_count: 0,
+            event_count: 0,
+        }
+    }
+
+    /// Sets fixed interarrival time.
+    pub fn with_fixed_interarrival(self, interval: f64) -> Self {
+        assert!(interval > 0., "Interarrival time must be positive");
+        self.with_interarrival_fn(move |_| interval)
+    }
+
+    /// Sets exponentially distributed interarrival times with the specified arrival rate.
+    pub fn with_exponential_interarrival(self, rate: f64) -> Self {
+        assert!(rate > 0., "Arrival rate must be positive");
+        self.with_interarrival_fn(move |ctx| ctx.sample_exponential(rate))
+    }
+
+    /// Sets interarrival times sampled from the specified distribution.
+    pub fn with_interarrival_distribution<D>(self, dist: D) -> Self
+    where
+        D: Distribution<f64> + 'static,
+    {
+        self.with_interarrival_fn(move |ctx| ctx.sample_from_distribution(&dist))
+    }
+
+    /// Sets interarrival times sampled from the workload model, see [`workload`](crate::workload) module.
+    pub fn with_workload(self, model: WorkloadModel) -> Self {
+        self.with_interarrival_fn(move |ctx| model.sample_interarrival(ctx))
+    }
+
+    /// Sets interarrival times produced by the specified closure.
+    ///
+    /// The produced values must be non-negative.
+    pub fn with_interarrival_fn<F>(mut self, interarrival: F) -> Self
+    where
+        F: FnMut(&SimulationContext) -> f64 + 'static,
+    {
+        self.interarrival = Box::new(interarrival);
+        self
+    }
+
+    /// Sets fixed number of events generated on each arrival.
+    pub fn with_batch_size(self, size: u64) -> Self {
+        self.with_batch_size_fn(move |_| size)
+    }
+
+    /// Sets the number of events generated on each arrival produced by the specified closure.
+    pub fn with_batch_size_fn<F>(mut self, batch_size: F) -> Self
+    where
+        F: FnMut(&SimulationContext) -> u64 + 'static,
+    {
+        self.batch_size = Box::new(batch_size);
+        self
+    }
+
+    /// Sets the time from which the interarrival time of the first arrival is counted.
+    pub fn with_start_time(mut self, time: f64) -> Self {
+        self.start_time = time;
+        self
+    }
+
+    /// Sets the time after which no arrivals are generated.
+    pub fn with_stop_time(mut self, time: f64) -> Self {
+        self.stop_time = time;
+        self
+    }
+
+    /// Sets the maximum number of generated arrivals.
+    pub fn with_max_arrivals(mut self, count: u64) -> Self {
+        self.max_arrivals = Some(count);
+        self
+    }
+
+    /// Sets the policy of selecting the destination of generated events.
+    ///
+    /// Panics if the target list is empty or target weights are invalid.
+    pub fn with_targets(mut self, policy: TargetPolicy) -> Self {
+        self.targets = Some(TargetSelector::new(policy));
+        self
+    }
+
+    /// Returns the identifier of generator component.
+    pub fn id(&self) -> Id {
+        self.ctx.id()
+    }
+
+    /// Returns the number of generated arrivals.
+    pub fn arrival_count(&self) -> u64 {
+        self.arrival_count
+    }
+
+    /// Returns the number of generated events.
+    pub fn event_count(&self) -> u64 {
+        self.event_count
+    }
+
+    /// Returns true if the generator is started and not yet stopped.
+    pub fn is_active(&self) -> bool {
+        self.next_arrival.is_some()
+    }
+
+    /// Starts generating arrivals.
+    ///
+    /// If the start time has already passed, the first interarrival time is counted from the current time.
+    /// Has no effect if the generator is already active.
+    ///
+    /// Panics if the targets are not set.
+    pub fn start(&mut self) {
+        assert!(self.targets.is_some(), "Generator targets are not set");
+        if self.is_active() {
+            return;
+        }
+        let delay = (self.start_time - self.ctx.time()).max(0.);
+        self.schedule_next_arrival(delay);
+    }
+
+    /// Stops generating arrivals.
+    pub fn stop(&mut self) {
+        if let Some(event_id) = self.next_arrival.take() {
+            self.ctx.cancel_event(event_id);
+        }
+    }
+
+    fn schedule_next_arrival(&mut self, delay: f64) {
+        self.next_arrival = None;
+        if self.max_arrivals.is_some_and(|max| self.arrival_count >= max) {
+            return;
+        }
+        let interarrival = (self.interarrival)(&self.ctx);
+        assert!(interarrival >= 0., "Interarrival time must be non-negative");
+        let delay = delay + interarrival;
+        if self.ctx.time() + delay > self.stop_time {
+            return;
+        }
+        self.next_arrival = Some(self.ctx.emit_self(NextArrival {}, delay));
+    }
+
+    fn on_arrival(&mut self) {
+        self.arrival_count += 1;
+        let batch_size = (self.batch_size)(&self.ctx);
+        let targets = self.targets.as_mut().unwrap();
+        for _ in 0..batch_size {
+            let data = (self.make_event)(&self.ctx, self.event_count);
+            let dst = targets.select(&self.ctx);
+            self.ctx.emit_now(data, dst);
+            self.event_count += 1;
+        }
+        self.schedule_next_arrival(0.);
+    }
+}
+
+impl<T: EventData> EventHandler for ArrivalGenerator<T> {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            NextArrival {} => {
+                self.on_arrival();
+            }
+        })
+    }
+}
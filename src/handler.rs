@@ -53,6 +53,60 @@ pub trait EventHandler {
     /// assert_eq!(comp2.borrow().state, 16);
     /// ```
     fn on(&mut self, event: Event);
+
+    /// Processes a batch of events with equal time destined for this component.
+    ///
+    /// This method is called only for components with enabled event batching, see
+    /// [`Simulation::enable_event_batching`](crate::Simulation::enable_event_batching).
+    /// Events in the batch are sorted in the order of their processing without batching.
+    /// The default implementation calls [`on`](Self::on) for each event in the batch.
+    ///
+    /// Implementing this method allows to amortize the per-event processing overhead when a component
+    /// receives many events at the same time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Vote {}
+    ///
+    /// struct Collector {
+    ///     batch_sizes: Vec<usize>,
+    /// }
+    ///
+    /// impl EventHandler for Collector {
+    ///     fn on(&mut self, event: Event) {
+    ///         self.on_batch(vec![event]);
+    ///     }
+    ///
+    ///     fn on_batch(&mut self, events: Vec<Event>) {
+    ///         self.batch_sizes.push(events.len());
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let voter_ctx = sim.create_context("voter");
+    /// let collector = Rc::new(RefCell::new(Collector { batch_sizes: Vec::new() }));
+    /// let collector_id = sim.add_handler("collector", collector.clone());
+    /// sim.enable_event_batching("collector");
+    /// for round in 1..=3 {
+    ///     for _ in 0..100 {
+    ///         voter_ctx.emit(Vote {}, collector_id, round as f64);
+    ///     }
+    /// }
+    /// sim.step_until_no_events();
+    /// assert_eq!(collector.borrow().batch_sizes, vec![100, 100, 100]);
+    /// ```
+    fn on_batch(&mut self, events: Vec<Event>) {
+        for event in events {
+            self.on(event);
+        }
+    }
 }
 
 /// Enables the use of pattern matching syntax for processing different types of events
@@ -135,6 +189,25 @@ pub enum EventCancellationPolicy {
 }
 
 async_mode_enabled!(
+    /// Specifies whether the event is delivered to the component handler or to the asynchronous task awaiting it
+    /// when the component has both of them.
+    ///
+    /// Set via [`Simulation::set_dispatch_precedence`](crate::Simulation::set_dispatch_precedence) for all
+    /// components or via
+    /// [`Simulation::set_component_dispatch_precedence`](crate::Simulation::set_component_dispatch_precedence)
+    /// for a specific component. The events destined to components without handlers are always delivered to the
+    /// awaiting tasks.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum DispatchPrecedence {
+        /// Deliver the event to the awaiting task, the handler receives only the events which are not awaited.
+        #[default]
+        WaitersFirst,
+        /// Deliver the event to the handler, the tasks can await only the events for components without handlers.
+        HandlerFirst,
+        /// Panic if the event can be delivered both to the handler and to the awaiting task.
+        Error,
+    }
+
     /// Alternative trait for consuming events in async mode.
     ///
     /// This trait supports spawning asynchronous tasks using component's context.
@@ -0,0 +1,159 @@
+//! D-ary heap used to store pending events.
+
+use std::collections::BinaryHeap;
+
+// Max-heap with configurable arity, i.e. the number of children of each node.
+//
+// Compared to the binary heap, heaps with larger arity have smaller depth and place the children of each node
+// in a contiguous memory region, which makes them more cache-friendly for large numbers of elements. The default
+// arity 2 uses the standard binary heap, so only the heaps with other arities use the implementation below.
+#[derive(Clone)]
+pub(crate) struct DaryHeap<T> {
+    storage: Storage<T>,
+}
+
+#[derive(Clone)]
+enum Storage<T> {
+    Binary(BinaryHeap<T>),
+    Dary { data: Vec<T>, arity: usize },
+}
+
+impl<T: Ord> DaryHeap<T> {
+    pub fn new(arity: usize) -> Self {
+        Self::from_vec(Vec::new(), arity)
+    }
+
+    pub fn from_vec(data: Vec<T>, arity: usize) -> Self {
+        assert!(arity >= 2, "Heap arity must be at least 2");
+        let storage = if arity == 2 {
+            Storage::Binary(BinaryHeap::from(data))
+        } else {
+            let mut storage = Storage::Dary { data, arity };
+            storage.rebuild();
+            storage
+        };
+        Self { storage }
+    }
+
+    pub fn arity(&self) -> usize {
+        match &self.storage {
+            Storage::Binary(_) => 2,
+            Storage::Dary { arity, .. } => *arity,
+        }
+    }
+
+    pub fn set_arity(&mut self, arity: usize) {
+        assert!(arity >= 2, "Heap arity must be at least 2");
+        if arity != self.arity() {
+            let data = std::mem::replace(&mut self.storage, Storage::Binary(BinaryHeap::new())).into_vec();
+            *self = Self::from_vec(data, arity);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Binary(heap) => heap.len(),
+            Storage::Dary { data, .. } => data.len(),
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        match &self.storage {
+            Storage::Binary(heap) => heap.peek(),
+            Storage::Dary { data, .. } => data.first(),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        match &mut self.storage {
+            Storage::Binary(heap) => heap.push(item),
+            Storage::Dary { data, arity } => {
+                data.push(item);
+                let pos = data.len() - 1;
+                sift_up(data, *arity, pos);
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Binary(heap) => heap.pop(),
+            Storage::Dary { data, arity } => {
+                if data.is_empty() {
+                    return None;
+                }
+                let item = data.swap_remove(0);
+                if !data.is_empty() {
+                    sift_down(data, *arity, 0);
+                }
+                Some(item)
+            }
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        match &self.storage {
+            Storage::Binary(heap) => heap.as_slice().iter(),
+            Storage::Dary { data, .. } => data.iter(),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.storage.into_vec()
+    }
+}
+
+impl<T: Ord> Storage<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Storage::Binary(heap) => heap.into_vec(),
+            Storage::Dary { data, .. } => data,
+        }
+    }
+
+    fn rebuild(&mut self) {
+        let Storage::Dary { data, arity } = self else {
+            return;
+        };
+        if data.len() < 2 {
+            return;
+        }
+        let last_parent = (data.len() - 2) / *arity;
+        for pos in (0..=last_parent).rev() {
+            sift_down(data, *arity, pos);
+        }
+    }
+}
+
+fn sift_up<T: Ord>(data: &mut [T], arity: usize, mut pos: usize) {
+    while pos > 0 {
+        let parent = (pos - 1) / arity;
+        if data[pos] <= data[parent] {
+            break;
+        }
+        data.swap(pos, parent);
+        pos = parent;
+    }
+}
+
+fn sift_down<T: Ord>(data: &mut [T], arity: usize, mut pos: usize) {
+    let len = data.len();
+    loop {
+        let first_child = pos * arity + 1;
+        if first_child >= len {
+            break;
+        }
+        let last_child = (first_child + arity).min(len);
+        let mut max_child = first_child;
+        for child in first_child + 1..last_child {
+            if data[child] > data[max_child] {
+                max_child = child;
+            }
+        }
+        if data[max_child] <= data[pos] {
+            break;
+        }
+        data.swap(pos, max_child);
+        pos = max_child;
+    }
+}
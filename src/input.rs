@@ -0,0 +1,186 @@
+//! Merging of external input streams.
+//!
+//! Trace-driven simulations and co-simulations receive events from external sources, such as files, channels or
+//! other simulators. Such sources can be registered via [`Simulation::add_input`](crate::Simulation::add_input) as
+//! iterators over [`InputItem`]s. The simulation merges the events of all inputs into its pending event set
+//! following deterministic rules:
+//!
+//! - the events of each input must be ordered by time,
+//! - the events from different inputs with equal time are injected in the order of input registration,
+//! - the input events are injected after the already pending events with equal time.
+//!
+//! The inputs are read lazily, only when the simulation needs to know whether an input has an event before the next
+//! pending event. An input can also produce a [watermark](InputItem::Watermark) which guarantees that it has no
+//! more events with smaller time. This allows the simulation to proceed up to the watermark without reading further
+//! items, which is useful for inputs backed by channels or co-simulators producing events on demand. For example,
+//! reading from a bounded channel blocks the simulation until the producer sends the next event or watermark,
+//! while the producer is blocked when the simulation lags behind, providing the flow control in both directions.
+
+use crate::component::Id;
+use crate::event::EventData;
+
+/// Event supplied by an input stream.
+pub struct InputEvent {
+    /// Time of the event.
+    pub time: f64,
+    /// Identifier of the destination component.
+    pub dst: Id,
+    /// Event payload.
+    pub data: Box<dyn EventData>,
+}
+
+impl InputEvent {
+    /// Creates an input event with specified time, destination and payload.
+    pub fn new<T: EventData>(time: f64, dst: Id, data: T) -> Self {
+        Self {
+            time,
+            dst,
+            data: Box::new(data),
+        }
+    }
+}
+
+/// Item of an input stream.
+pub enum InputItem {
+    /// Event to be injected into the simulation.
+    Event(InputEvent),
+    /// Guarantee that the input has no more events with time smaller than the specified one.
+    Watermark(f64),
+}
+
+// Source of input items, which can produce the items on demand depending on the simulation progress.
+pub(crate) trait InputSource {
+    // Returns the next item, the horizon is the time up to which the simulation needs to know the input events.
+    fn next_item(&mut self, horizon: f64) -> Option<InputItem>;
+}
+
+// Input source reading the items from iterator regardless of the horizon.
+pub(crate) struct IteratorSource<I>(pub I);
+
+impl<I: Iterator<Item = InputItem>> InputSource for IteratorSource<I> {
+    fn next_item(&mut self, _horizon: f64) -> Option<InputItem> {
+        self.0.next()
+    }
+}
+
+pub(crate) struct Input {
+    id: Id,
+    items: Box<dyn InputSource>,
+    // Next event of the input, if it is already read.
+    head: Option<InputEvent>,
+    // Time before which the input has no more events.
+    watermark: f64,
+    finished: bool,
+    // Number of events taken from the input, saved in checkpoints.
+    taken: u64,
+}
+
+impl Input {
+    pub fn new(id: Id, items: Box<dyn InputSource>, time: f64) -> Self {
+        Self {
+            id,
+            items,
+            head: None,
+            watermark: time,
+            finished: false,
+            taken: 0,
+        }
+    }
+
+    // Reads the input until its next event is known or it guarantees no events up to the horizon.
+    fn fill(&mut self, horizon: f64) {
+        while self.head.is_none() && !self.finished && self.watermark <= horizon {
+            match self.items.next_item(horizon) {
+                Some(InputItem::Event(event)) => {
+                    assert!(
+                        event.time >= self.watermark,
+                        "Input event time {} is earlier than the previous event or watermark {}",
+                        event.time,
+                        self.watermark
+                    );
+                    self.watermark = event.time;
+                    self.head = Some(event);
+                }
+                Some(InputItem::Watermark(watermark)) => {
+                    assert!(
+                        watermark >= self.watermark,
+                        "Input watermark {} is earlier than the previous event or watermark {}",
+                        watermark,
+                        self.watermark
+                    );
+                    self.watermark = watermark;
+                }
+                None => self.finished = true,
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct InputGateway {
+    inputs: Vec<Input>,
+}
+
+impl InputGateway {
+    pub fn add(&mut self, input: Input) {
+        self.inputs.push(input);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    // Returns true if some input is not finished or has unread event.
+    pub fn has_pending(&self) -> bool {
+        self.inputs.iter().any(|input| !input.finished || input.head.is_some())
+    }
+
+    // Returns the earliest input event with time not greater than the horizon and the identifier of its input.
+    pub fn next_event(&mut self, horizon: f64) -> Option<(Id, InputEvent)> {
+        let mut horizon = horizon;
+        let mut next: Option<usize> = None;
+        for (i, input) in self.inputs.iter_mut().enumerate() {
+            // the inputs are not read further than the earliest event found so far
+            input.fill(horizon);
+            if let Some(event) = input.head.as_ref() {
+                // the earlier registered input wins the ties
+                if event.time < horizon || (event.time == horizon && next.is_none()) {
+                    horizon = event.time;
+                    next = Some(i);
+                }
+            }
+        }
+        let input = &mut self.inputs[next?];
+        input.taken += 1;
+        Some((input.id, input.head.take().unwrap()))
+    }
+
+    // Returns the number of events taken from each input by input identifiers.
+    pub fn positions(&self) -> Vec<(Id, u64)> {
+        self.inputs.iter().map(|input| (input.id, input.taken)).collect()
+    }
+
+    // Skips the events taken from the input before saving the checkpoint at the specified time.
+    pub fn restore_position(&mut self, id: Id, taken: u64, time: f64) {
+        let input = self
+            .inputs
+            .iter_mut()
+            .find(|input| input.id == id)
+            .unwrap_or_else(|| panic!("Input {} from checkpoint is not registered", id));
+        assert!(
+            input.taken <= taken,
+            "Input {} cannot be rewound to the checkpoint, it has already read more events",
+            id
+        );
+        while input.taken < taken {
+            input.fill(f64::INFINITY);
+            assert!(
+                input.head.take().is_some(),
+                "Input {} has less events than were taken before the checkpoint",
+                id
+            );
+            input.taken += 1;
+        }
+        input.watermark = input.watermark.max(time);
+    }
+}
@@ -134,6 +134,10 @@
 //! The example models a simple scenario where `proc1` emits a request to `proc2` and the simulation runs until `proc1`
 //! receives a response.
 //!
+//! With the `derive` feature, the `EventHandler` implementation dispatching events to callback methods can be
+//! generated by applying the [`event_handler`] attribute to the `impl` block with methods like
+//! `fn on_request(&mut self, event: TypedEvent<Request>)`.
+//!
 //! ### Limitations of Callbacks
 //!
 //! While the callback-based approach is simple and intuitive by organizing all event processing logic in `EventHandler`,
@@ -199,23 +203,83 @@
 #![allow(clippy::needless_doctest_main)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+pub mod analysis;
 pub mod async_mode;
+pub mod branch;
+pub mod breakpoint;
+pub mod checkpoint;
+pub mod chrome_trace;
+pub mod coalescing;
 pub mod component;
+pub mod compression;
 pub mod context;
+pub mod continuous;
+pub mod contracts;
+pub mod cosim;
+pub mod delay;
+mod digest;
+pub mod divergence;
+pub mod emit_hook;
 pub mod event;
+pub mod experiment;
+pub mod generator;
 pub mod handler;
+mod heap;
+pub mod input;
+pub mod limits;
+pub mod link;
 pub mod log;
+pub mod logical_clock;
+pub mod metadata;
+pub mod metrics;
+pub mod metrics_export;
+pub mod mock;
+pub mod observer;
+pub mod ordering;
+#[cfg(feature = "thread")]
+pub mod parallel;
+pub mod producers;
+pub mod queue_dump;
+pub mod rate_limit;
+pub mod replay;
+pub mod resolution;
+pub mod routing;
+pub mod run;
 pub mod simulation;
+pub mod snapshot;
+mod special;
+pub mod spill;
+pub mod startup;
 mod state;
+pub mod state_machine;
+pub mod stop;
+pub mod tick;
+pub mod time;
+pub mod timeout;
+pub mod timer;
+pub mod trace;
+pub mod trace_diff;
+pub mod trace_file;
+pub mod versioning;
+pub mod waiting_queue;
+pub mod warmup;
+pub mod watchpoint;
+pub mod workload;
 
 pub use colored;
-pub use component::Id;
+pub use component::{ComponentRef, Id};
 pub use context::SimulationContext;
-pub use event::{Event, EventData, EventId, TypedEvent};
+pub use event::{Event, EventData, EventId, EventRef, EventTypeId, TypedEvent};
 pub use handler::{EventCancellationPolicy, EventHandler};
+#[doc(hidden)]
+pub use serde;
+#[cfg(feature = "derive")]
+pub use simcore_derive::event_handler;
 pub use simulation::Simulation;
 pub use state::EPSILON;
 
 async_mode_enabled!(
     pub use handler::StaticEventHandler;
+    #[cfg(feature = "derive")]
+    pub use simcore_derive::rpc;
 );
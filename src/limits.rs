@@ -0,0 +1,193 @@
+//! Per-run resource limits.
+//!
+//! A bug in the model, such as a feedback loop where each event emits several new ones, can make the simulation
+//! consume all memory or run forever before anyone notices. The limits set via
+//! [`Simulation::set_resource_limits`](crate::Simulation::set_resource_limits) are checked after each step and
+//! guard the number of pending events, the number of processed events and the simulation time. When some limit is
+//! exceeded, the run is aborted with a panic or paused depending on the [`LimitAction`]. The [`LimitViolation`]
+//! reports the exceeded limit and the components which produced the most events, which usually points to the source
+//! of the runaway loop.
+
+use std::fmt::{Display, Formatter};
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+use crate::event::Event;
+
+/// Action performed when a resource limit is exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitAction {
+    /// Panics with the description of the violation.
+    Abort,
+    /// Pauses the current run like a triggered watchpoint, the violation is returned by
+    /// [`Simulation::take_limit_violation`](crate::Simulation::take_limit_violation).
+    Pause,
+}
+
+/// Resource limits of the simulation run.
+///
+/// By default no limits are set and the run is aborted when some limit is exceeded.
+#[derive(Clone, Debug)]
+pub struct ResourceLimits {
+    /// Maximum number of pending events held in memory, including the canceled events which are not removed from
+    /// the queue yet. The events spilled to disk, the events emitted via
+    /// [`emit_after`](crate::SimulationContext::emit_after) and the coalesced events are not counted.
+    pub max_pending_events: Option<usize>,
+    /// Maximum number of events processed since the limits were set.
+    pub max_processed_events: Option<u64>,
+    /// Maximum simulation time.
+    pub max_time: Option<f64>,
+    /// Action performed when some limit is exceeded.
+    pub action: LimitAction,
+    /// Number of top event producers included in the violation report.
+    pub top_producers: usize,
+}
+
+impl ResourceLimits {
+    /// Creates limits without any restrictions, which abort the run and report 5 top event producers.
+    pub fn new() -> Self {
+        Self {
+            max_pending_events: None,
+            max_processed_events: None,
+            max_time: None,
+            action: LimitAction::Abort,
+            top_producers: 5,
+        }
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resource limit which was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    /// Maximum number of pending events.
+    PendingEvents(usize),
+    /// Maximum number of processed events.
+    ProcessedEvents(u64),
+    /// Maximum simulation time.
+    Time(f64),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::PendingEvents(limit) => write!(f, "pending events limit {}", limit),
+            Limit::ProcessedEvents(limit) => write!(f, "processed events limit {}", limit),
+            Limit::Time(limit) => write!(f, "time limit {}", limit),
+        }
+    }
+}
+
+/// Report of the exceeded resource limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitViolation {
+    /// Exceeded limit.
+    pub limit: Limit,
+    /// Simulation time when the limit was exceeded.
+    pub time: f64,
+    /// Number of pending events held in memory.
+    pub pending_events: usize,
+    /// Number of events processed since the limits were set.
+    pub processed_events: u64,
+    /// Names of components which produced the most events with the numbers of their events, sorted by decreasing
+    /// number of events. The processed events since the limits were set and the pending events are counted.
+    pub top_producers: Vec<(String, u64)>,
+}
+
+impl Display for LimitViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Resource limit exceeded: {} at time {:.3} with {} pending and {} processed events",
+            self.limit, self.time, self.pending_events, self.processed_events
+        )?;
+        if !self.top_producers.is_empty() {
+            let producers: Vec<String> = self
+                .top_producers
+                .iter()
+                .map(|(name, count)| format!("{} ({})", name, count))
+                .collect();
+            write!(f, ", top event producers: {}", producers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Tracks the processed events and checks the limits after each step.
+pub(crate) struct LimitGuard {
+    limits: ResourceLimits,
+    processed_events: u64,
+    // Numbers of processed events by their sources.
+    produced: FxHashMap<Id, u64>,
+}
+
+impl LimitGuard {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            processed_events: 0,
+            produced: FxHashMap::default(),
+        }
+    }
+
+    pub fn action(&self) -> LimitAction {
+        self.limits.action
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        self.processed_events += 1;
+        *self.produced.entry(event.src).or_default() += 1;
+    }
+
+    // Returns the exceeded limit, the pending events limit is checked first since it protects the memory.
+    pub fn check(&self, time: f64, pending_events: usize) -> Option<Limit> {
+        if let Some(limit) = self.limits.max_pending_events.filter(|limit| pending_events > *limit) {
+            return Some(Limit::PendingEvents(limit));
+        }
+        if let Some(limit) = self
+            .limits
+            .max_processed_events
+            .filter(|limit| self.processed_events > *limit)
+        {
+            return Some(Limit::ProcessedEvents(limit));
+        }
+        if let Some(limit) = self.limits.max_time.filter(|limit| time > *limit) {
+            return Some(Limit::Time(limit));
+        }
+        None
+    }
+
+    // Builds the report, the top producers are counted over the processed events and the listed pending events.
+    pub fn violation<N>(
+        &self,
+        limit: Limit,
+        time: f64,
+        pending_events: usize,
+        pending: &[Event],
+        name: N,
+    ) -> LimitViolation
+    where
+        N: Fn(Id) -> String,
+    {
+        let mut produced = self.produced.clone();
+        for event in pending {
+            *produced.entry(event.src).or_default() += 1;
+        }
+        let mut top_producers: Vec<(String, u64)> = produced.into_iter().map(|(id, count)| (name(id), count)).collect();
+        top_producers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_producers.truncate(self.limits.top_producers);
+        LimitViolation {
+            limit,
+            time,
+            pending_events,
+            processed_events: self.processed_events,
+            top_producers,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Transmission of events over links with limited bandwidth.
+//!
+//! Simple network models often describe the communication between components only by the bandwidth and latency
+//! of links. Instead of computing the delays in each model, the events can be emitted via
+//! [`SimulationContext::emit_over_link`](crate::SimulationContext::emit_over_link), which takes the size of
+//! the payload and the [`Link`] parameters and delivers the event after the transmission time `size / rate` plus
+//! the link latency.
+//!
+//! By default, the transmissions are independent, i.e. the link is assumed to have enough capacity for all
+//! concurrent transmissions. If the link is [serialized](Link::serialized), the transmissions between the same
+//! source and destination (channel) are performed one at a time in the order of emitting, so the transmission
+//! starts only after the previous transmission in the channel is finished. This models the queueing of messages
+//! at the sender, e.g. in a TCP connection.
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+
+/// Parameters of link used to transmit events, see [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Link {
+    /// Transmission rate in units of payload size per unit of time.
+    pub rate: f64,
+    /// Latency added to the transmission time.
+    pub latency: f64,
+    /// Whether the transmissions in the same channel are performed one at a time.
+    pub serialized: bool,
+}
+
+impl Link {
+    /// Creates a link with the specified rate and latency, whose transmissions are independent.
+    ///
+    /// Panics if the rate is not positive or the latency is negative.
+    pub fn new(rate: f64, latency: f64) -> Self {
+        assert!(rate > 0., "Link rate must be positive, got {}", rate);
+        assert!(latency >= 0., "Link latency must be non-negative, got {}", latency);
+        Self {
+            rate,
+            latency,
+            serialized: false,
+        }
+    }
+
+    /// Creates a link with the specified rate and latency, whose transmissions in the same channel are performed
+    /// one at a time.
+    ///
+    /// Panics if the rate is not positive or the latency is negative.
+    pub fn serialized(rate: f64, latency: f64) -> Self {
+        Self {
+            serialized: true,
+            ..Self::new(rate, latency)
+        }
+    }
+
+    /// Returns the transmission time of the payload with the specified size.
+    pub fn transmission_time(&self, size: f64) -> f64 {
+        size / self.rate
+    }
+}
+
+// Tracks the end times of serialized transmissions by channel.
+#[derive(Clone, Default)]
+pub(crate) struct LinkChannels {
+    busy_until: FxHashMap<(Id, Id), f64>,
+}
+
+impl LinkChannels {
+    // Returns the delay of event transmitted at the specified time and reserves the channel for serialized link.
+    pub fn transmit(&mut self, src: Id, dst: Id, size: f64, link: &Link, time: f64) -> f64 {
+        assert!(size >= 0., "Payload size must be non-negative, got {}", size);
+        let transmission_time = link.transmission_time(size);
+        if !link.serialized {
+            return transmission_time + link.latency;
+        }
+        let busy_until = self.busy_until.entry((src, dst)).or_insert(time);
+        let start = busy_until.max(time);
+        *busy_until = start + transmission_time;
+        *busy_until - time + link.latency
+    }
+
+    // Returns the time when the last serialized transmission in the channel is finished.
+    pub fn busy_until(&self, src: Id, dst: Id) -> Option<f64> {
+        self.busy_until.get(&(src, dst)).copied()
+    }
+
+    pub fn remove_component(&mut self, id: Id) {
+        self.busy_until.retain(|(src, dst), _| *src != id && *dst != id);
+    }
+}
@@ -1,4 +1,7 @@
 //! Logging facilities.
+//!
+//! The log records of components can be limited per window of simulation time, see [`rate_limit`](crate::rate_limit)
+//! module.
 
 use std::io::IsTerminal;
 
@@ -7,7 +10,7 @@ use log::error;
 use serde_json::json;
 use serde_type_name::type_name;
 
-use crate::event::Event;
+use crate::event::{Event, EventData};
 
 /// Applies the color to the string if stderr (log) goes to console.
 pub fn get_colored(s: &str, color: Color) -> ColoredString {
@@ -49,18 +52,22 @@ pub fn get_colored(s: &str, color: Color) -> ColoredString {
 #[macro_export]
 macro_rules! log_info {
     ($ctx:expr, $msg:expr) => (
-        log::info!(
-            target: $ctx.name(),
-            "[{:.3} {}  {}] {}",
-            $ctx.time(), $crate::log::get_colored("INFO", $crate::colored::Color::Green), $ctx.name(), $msg
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Info) && $ctx.allow_log_record() {
+            log::info!(
+                target: $ctx.name(),
+                "[{:.3} {}  {}] {}",
+                $ctx.time(), $crate::log::get_colored("INFO", $crate::colored::Color::Green), $ctx.name(), $msg
+            )
+        }
     );
     ($ctx:expr, $format:expr, $($arg:tt)+) => (
-        log::info!(
-            target: $ctx.name(),
-            concat!("[{:.3} {}  {}] ", $format),
-            $ctx.time(), $crate::log::get_colored("INFO", $crate::colored::Color::Green), $ctx.name(), $($arg)+
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Info) && $ctx.allow_log_record() {
+            log::info!(
+                target: $ctx.name(),
+                concat!("[{:.3} {}  {}] ", $format),
+                $ctx.time(), $crate::log::get_colored("INFO", $crate::colored::Color::Green), $ctx.name(), $($arg)+
+            )
+        }
     );
 }
 
@@ -72,18 +79,22 @@ macro_rules! log_info {
 #[macro_export]
 macro_rules! log_debug {
     ($ctx:expr, $msg:expr) => (
-        log::debug!(
-            target: $ctx.name(),
-            "[{:.3} {} {}] {}",
-            $ctx.time(), $crate::log::get_colored("DEBUG", $crate::colored::Color::Blue), $ctx.name(), $msg
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Debug) && $ctx.allow_log_record() {
+            log::debug!(
+                target: $ctx.name(),
+                "[{:.3} {} {}] {}",
+                $ctx.time(), $crate::log::get_colored("DEBUG", $crate::colored::Color::Blue), $ctx.name(), $msg
+            )
+        }
     );
     ($ctx:expr, $format:expr, $($arg:tt)+) => (
-        log::debug!(
-            target: $ctx.name(),
-            concat!("[{:.3} {} {}] ", $format),
-            $ctx.time(), $crate::log::get_colored("DEBUG", $crate::colored::Color::Blue), $ctx.name(), $($arg)+
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Debug) && $ctx.allow_log_record() {
+            log::debug!(
+                target: $ctx.name(),
+                concat!("[{:.3} {} {}] ", $format),
+                $ctx.time(), $crate::log::get_colored("DEBUG", $crate::colored::Color::Blue), $ctx.name(), $($arg)+
+            )
+        }
     );
 }
 
@@ -95,18 +106,22 @@ macro_rules! log_debug {
 #[macro_export]
 macro_rules! log_trace {
     ($ctx:expr, $msg:expr) => (
-        log::trace!(
-            target: $ctx.name(),
-            "[{:.3} {} {}] {}",
-            $ctx.time(), $crate::log::get_colored("TRACE", $crate::colored::Color::Cyan), $ctx.name(), $msg
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Trace) && $ctx.allow_log_record() {
+            log::trace!(
+                target: $ctx.name(),
+                "[{:.3} {} {}] {}",
+                $ctx.time(), $crate::log::get_colored("TRACE", $crate::colored::Color::Cyan), $ctx.name(), $msg
+            )
+        }
     );
     ($ctx:expr, $format:expr, $($arg:tt)+) => (
-        log::trace!(
-            target: $ctx.name(),
-            concat!("[{:.3} {} {}] ", $format),
-            $ctx.time(), $crate::log::get_colored("TRACE", $crate::colored::Color::Cyan), $ctx.name(), $($arg)+
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Trace) && $ctx.allow_log_record() {
+            log::trace!(
+                target: $ctx.name(),
+                concat!("[{:.3} {} {}] ", $format),
+                $ctx.time(), $crate::log::get_colored("TRACE", $crate::colored::Color::Cyan), $ctx.name(), $($arg)+
+            )
+        }
     );
 }
 
@@ -118,18 +133,22 @@ macro_rules! log_trace {
 #[macro_export]
 macro_rules! log_error {
     ($ctx:expr, $msg:expr) => (
-        log::error!(
-            target: $ctx.name(),
-            "[{:.3} {} {}] {}",
-            $ctx.time(), $crate::log::get_colored("ERROR", $crate::colored::Color::Red), $ctx.name(), $msg
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Error) && $ctx.allow_log_record() {
+            log::error!(
+                target: $ctx.name(),
+                "[{:.3} {} {}] {}",
+                $ctx.time(), $crate::log::get_colored("ERROR", $crate::colored::Color::Red), $ctx.name(), $msg
+            )
+        }
     );
     ($ctx:expr, $format:expr, $($arg:tt)+) => (
-        log::error!(
-            target: $ctx.name(),
-            concat!("[{:.3} {} {}] ", $format),
-            $ctx.time(), $crate::log::get_colored("ERROR", $crate::colored::Color::Red), $ctx.name(), $($arg)+
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Error) && $ctx.allow_log_record() {
+            log::error!(
+                target: $ctx.name(),
+                concat!("[{:.3} {} {}] ", $format),
+                $ctx.time(), $crate::log::get_colored("ERROR", $crate::colored::Color::Red), $ctx.name(), $($arg)+
+            )
+        }
     );
 }
 
@@ -141,18 +160,22 @@ macro_rules! log_error {
 #[macro_export]
 macro_rules! log_warn {
     ($ctx:expr, $msg:expr) => (
-        log::warn!(
-            target: $ctx.name(),
-            "[{:.3} {}  {}] {}",
-            $ctx.time(), $crate::log::get_colored("WARN", $crate::colored::Color::Yellow), $ctx.name(), $msg
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Warn) && $ctx.allow_log_record() {
+            log::warn!(
+                target: $ctx.name(),
+                "[{:.3} {}  {}] {}",
+                $ctx.time(), $crate::log::get_colored("WARN", $crate::colored::Color::Yellow), $ctx.name(), $msg
+            )
+        }
     );
     ($ctx:expr, $format:expr, $($arg:tt)+) => (
-        log::warn!(
-            target: $ctx.name(),
-            concat!("[{:.3} {}  {}] ", $format),
-            $ctx.time(), $crate::log::get_colored("WARN", $crate::colored::Color::Yellow), $ctx.name(), $($arg)+
-        )
+        if log::log_enabled!(target: $ctx.name(), log::Level::Warn) && $ctx.allow_log_record() {
+            log::warn!(
+                target: $ctx.name(),
+                concat!("[{:.3} {}  {}] ", $format),
+                $ctx.time(), $crate::log::get_colored("WARN", $crate::colored::Color::Yellow), $ctx.name(), $($arg)+
+            )
+        }
     );
 }
 
@@ -191,3 +214,149 @@ pub(crate) fn log_incorrect_event(event: Event, msg: &str) {
         json!({"type": type_name(&event.data).unwrap(), "data": event.data, "src": event.src, "dst": event.dst})
     );
 }
+
+/// Event type with precomputed logging metadata.
+///
+/// Events of such types registered via
+/// [`Simulation::register_loggable_event`](crate::Simulation::register_loggable_event) are logged using
+/// the precomputed type name and the generated serializer of payload fields, which is much cheaper than
+/// the generic serialization of event payload.
+///
+/// This trait should not be implemented manually, use [`loggable_event!`](crate::loggable_event!) macro instead.
+pub trait LoggableEvent: EventData {
+    /// Short name of event type.
+    const NAME: &'static str;
+    /// Names of payload fields in the order of their output.
+    const FIELDS: &'static [&'static str];
+
+    /// Writes event payload as JSON to the buffer.
+    fn write_json(&self, buf: &mut Vec<u8>);
+}
+
+/// Implements [`LoggableEvent`] for the specified event type.
+///
+/// The event type is specified along with the list of its fields to be logged, i.e. `Request { id, size }`.
+/// Unit structs are specified without fields, i.e. `Start`. The logged payload includes only the listed fields
+/// in the specified order.
+///
+/// All fields of the type must be listed, otherwise the macro fails to compile, so that the fields added to the
+/// type later are not silently left out of the log. The fields can be left out explicitly by ending the list with
+/// `..`, i.e. `Request { id, .. }`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::log::LoggableEvent;
+/// use simcore::loggable_event;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {
+///     id: u64,
+///     path: String,
+/// }
+///
+/// #[derive(Clone, Serialize)]
+/// struct Response {
+///     id: u64,
+///     body: String,
+/// }
+///
+/// #[derive(Clone, Serialize)]
+/// struct Start;
+///
+/// loggable_event!(Request { id, path });
+/// loggable_event!(Response { id, .. });
+/// loggable_event!(Start);
+///
+/// assert_eq!(Request::NAME, "Request");
+/// assert_eq!(Request::FIELDS, &["id", "path"]);
+///
+/// let mut buf = Vec::new();
+/// Request { id: 1, path: "/".to_owned() }.write_json(&mut buf);
+/// assert_eq!(buf, br#"{"id":1,"path":"/"}"#);
+///
+/// buf.clear();
+/// Response { id: 1, body: "OK".to_owned() }.write_json(&mut buf);
+/// assert_eq!(buf, br#"{"id":1}"#);
+///
+/// buf.clear();
+/// Start.write_json(&mut buf);
+/// assert_eq!(buf, b"null");
+/// ```
+///
+/// The fields missing from the list without `..` are reported at compile time:
+///
+/// ```compile_fail
+/// use serde::Serialize;
+/// use simcore::loggable_event;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {
+///     id: u64,
+///     path: String,
+/// }
+///
+/// loggable_event!(Request { id });
+/// ```
+#[macro_export]
+macro_rules! loggable_event {
+    ($type:ident { $first:ident $(, $field:ident)* $(,)? }) => {
+        $crate::loggable_event!(@impl $type { $first $(, $field)* } {});
+    };
+    ($type:ident { $first:ident, $($field:ident,)* .. }) => {
+        $crate::loggable_event!(@impl $type { $first $(, $field)* } { .. });
+    };
+    ($type:ident {}) => {
+        impl $crate::log::LoggableEvent for $type {
+            const NAME: &'static str = stringify!($type);
+            const FIELDS: &'static [&'static str] = &[];
+
+            fn write_json(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(b"{}");
+            }
+        }
+    };
+    ($type:ident) => {
+        impl $crate::log::LoggableEvent for $type {
+            const NAME: &'static str = stringify!($type);
+            const FIELDS: &'static [&'static str] = &[];
+
+            fn write_json(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(b"null");
+            }
+        }
+    };
+    (@impl $type:ident { $first:ident $(, $field:ident)* } { $($rest:tt)* }) => {
+        impl $crate::log::LoggableEvent for $type {
+            const NAME: &'static str = stringify!($type);
+            const FIELDS: &'static [&'static str] = &[stringify!($first) $(, stringify!($field))*];
+
+            fn write_json(&self, buf: &mut Vec<u8>) {
+                // fails to compile if some field is not listed without `..`
+                let $type { $first: _, $($field: _,)* $($rest)* } = self;
+                buf.extend_from_slice(concat!("{\"", stringify!($first), "\":").as_bytes());
+                $crate::log::write_json_value(buf, &self.$first);
+                $(
+                    buf.extend_from_slice(concat!(",\"", stringify!($field), "\":").as_bytes());
+                    $crate::log::write_json_value(buf, &self.$field);
+                )*
+                buf.push(b'}');
+            }
+        }
+    };
+}
+
+/// Writes the value as JSON to the buffer.
+///
+/// This function is used internally in [`loggable_event!`](crate::loggable_event!) macro.
+#[doc(hidden)]
+pub fn write_json_value<T: serde::Serialize + ?Sized>(buf: &mut Vec<u8>, value: &T) {
+    serde_json::to_writer(buf, value).unwrap();
+}
+
+pub(crate) type WriteJsonFn = fn(&dyn EventData, &mut Vec<u8>);
+
+pub(crate) fn write_event_json<T: LoggableEvent>(data: &dyn EventData, buf: &mut Vec<u8>) {
+    data.downcast_ref::<T>().unwrap().write_json(buf);
+}
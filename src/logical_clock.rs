@@ -0,0 +1,207 @@
+//! Logical clocks of components.
+//!
+//! When logical clocks are enabled via
+//! [`Simulation::enable_logical_clocks`](crate::Simulation::enable_logical_clocks), the simulation maintains a
+//! Lamport or vector clock for each component. The clock of the event source is incremented when an event is
+//! emitted, and the event is stamped with the resulting time. When the event is dispatched, the clock of the event
+//! destination is merged with the event timestamp and incremented. Thus the model code does not need to carry
+//! the clocks in event payloads to study the causality of events.
+//!
+//! The current time of component clock is available via
+//! [`SimulationContext::logical_time`](crate::SimulationContext::logical_time), while the timestamps of processed
+//! events are recorded in the [memory trace](crate::trace::TraceRecord::logical_time).
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+use crate::event::EventId;
+
+/// Kind of logical clocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogicalClockKind {
+    /// Lamport clocks consisting of a single counter.
+    Lamport,
+    /// Vector clocks consisting of a counter for each component.
+    Vector,
+}
+
+/// Time of logical clock.
+///
+/// The times are partially ordered, the vector times are compared by entries.
+#[derive(Clone, Debug)]
+pub enum LogicalTime {
+    /// Time of Lamport clock.
+    Lamport(u64),
+    /// Time of vector clock, indexed by component identifier.
+    ///
+    /// The missing trailing entries are equal to zero.
+    Vector(Vec<u64>),
+}
+
+impl LogicalTime {
+    fn new(kind: LogicalClockKind) -> Self {
+        match kind {
+            LogicalClockKind::Lamport => Self::Lamport(0),
+            LogicalClockKind::Vector => Self::Vector(Vec::new()),
+        }
+    }
+
+    /// Returns true if this time precedes the other time.
+    ///
+    /// For vector clocks this means that the event with this time causally precedes the event with the other time.
+    /// For Lamport clocks this is only a necessary condition of causal precedence.
+    ///
+    /// Panics if the times belong to clocks of different kinds.
+    pub fn happened_before(&self, other: &LogicalTime) -> bool {
+        match (self, other) {
+            (Self::Lamport(a), Self::Lamport(b)) => a < b,
+            (Self::Vector(_), Self::Vector(_)) => self < other,
+            _ => panic!("Logical times of different kinds are not comparable"),
+        }
+    }
+
+    /// Returns true if neither of the times precedes the other one.
+    ///
+    /// For vector clocks this means that the events with these times are concurrent.
+    ///
+    /// Panics if the times belong to clocks of different kinds.
+    pub fn is_concurrent(&self, other: &LogicalTime) -> bool {
+        !self.happened_before(other) && !other.happened_before(self) && self != other
+    }
+
+    fn tick(&mut self, id: Id) {
+        match self {
+            Self::Lamport(time) => *time += 1,
+            Self::Vector(entries) => {
+                if entries.len() <= id as usize {
+                    entries.resize(id as usize + 1, 0);
+                }
+                entries[id as usize] += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &LogicalTime) {
+        match (self, other) {
+            (Self::Lamport(a), Self::Lamport(b)) => *a = (*a).max(*b),
+            (Self::Vector(a), Self::Vector(b)) => {
+                if a.len() < b.len() {
+                    a.resize(b.len(), 0);
+                }
+                for (x, y) in a.iter_mut().zip(b.iter()) {
+                    *x = (*x).max(*y);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl PartialEq for LogicalTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for LogicalTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Lamport(a), Self::Lamport(b)) => a.partial_cmp(b),
+            (Self::Vector(a), Self::Vector(b)) => {
+                let len = a.len().max(b.len());
+                let (mut less, mut greater) = (false, false);
+                for i in 0..len {
+                    let x = a.get(i).copied().unwrap_or(0);
+                    let y = b.get(i).copied().unwrap_or(0);
+                    less |= x < y;
+                    greater |= x > y;
+                }
+                match (less, greater) {
+                    (false, false) => Some(Ordering::Equal),
+                    (true, false) => Some(Ordering::Less),
+                    (false, true) => Some(Ordering::Greater),
+                    (true, true) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for LogicalTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lamport(time) => write!(f, "{}", time),
+            Self::Vector(entries) => {
+                let entries: Vec<String> = entries.iter().map(|x| x.to_string()).collect();
+                write!(f, "[{}]", entries.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct LogicalClocks {
+    kind: LogicalClockKind,
+    clocks: Vec<LogicalTime>,
+    // Timestamps of pending events.
+    stamps: FxHashMap<EventId, LogicalTime>,
+}
+
+impl LogicalClocks {
+    pub fn new(kind: LogicalClockKind) -> Self {
+        Self {
+            kind,
+            clocks: Vec::new(),
+            stamps: FxHashMap::default(),
+        }
+    }
+
+    pub fn time(&self, id: Id) -> LogicalTime {
+        self.clocks
+            .get(id as usize)
+            .cloned()
+            .unwrap_or_else(|| LogicalTime::new(self.kind))
+    }
+
+    pub fn event_time(&self, event_id: EventId) -> Option<&LogicalTime> {
+        self.stamps.get(&event_id)
+    }
+
+    pub fn on_event_emitted(&mut self, event_id: EventId, src: Id) {
+        let clock = self.clock_mut(src);
+        clock.tick(src);
+        let stamp = clock.clone();
+        self.stamps.insert(event_id, stamp);
+    }
+
+    // Updates the clock of event destination and returns the event timestamp.
+    pub fn on_event_dispatched(&mut self, event_id: EventId, dst: Id) -> Option<LogicalTime> {
+        let stamp = self.stamps.remove(&event_id)?;
+        let clock = self.clock_mut(dst);
+        clock.merge(&stamp);
+        clock.tick(dst);
+        Some(stamp)
+    }
+
+    pub fn on_event_canceled(&mut self, event_id: EventId) {
+        self.stamps.remove(&event_id);
+    }
+
+    // Resets the clock of removed component, whose identifier can be reused.
+    pub fn remove_component(&mut self, id: Id) {
+        if let Some(clock) = self.clocks.get_mut(id as usize) {
+            *clock = LogicalTime::new(self.kind);
+        }
+    }
+
+    fn clock_mut(&mut self, id: Id) -> &mut LogicalTime {
+        if self.clocks.len() <= id as usize {
+            self.clocks.resize(id as usize + 1, LogicalTime::new(self.kind));
+        }
+        &mut self.clocks[id as usize]
+    }
+}
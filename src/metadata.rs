@@ -0,0 +1,58 @@
+//! Run metadata.
+//!
+//! Each simulation run is described by [`RunMetadata`], which includes the random seed, the framework version,
+//! the wall-clock start time and optional user-provided labels and configuration hash. The metadata is attached to
+//! the artifacts written by the framework, i.e. it is logged before processing the first event and is written at
+//! the beginning of each file with spilled events and the CSV with results of
+//! [parameter sweeps](crate::experiment::SweepResults::write_csv), so the origin of these artifacts can be
+//! identified later.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::DigestHasher;
+
+/// Metadata identifying a simulation run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Random seed of the simulation.
+    pub seed: u64,
+    /// Version of simcore crate.
+    pub version: String,
+    /// Wall-clock time of simulation creation in seconds since Unix epoch.
+    pub start_time: f64,
+    /// User-provided labels, e.g. experiment name or scenario parameters.
+    pub labels: BTreeMap<String, String>,
+    /// Hash of the user-provided run configuration, if it is set.
+    pub config_hash: Option<String>,
+}
+
+impl RunMetadata {
+    pub(crate) fn new(seed: u64) -> Self {
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0., |duration| duration.as_secs_f64());
+        Self {
+            seed,
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            start_time,
+            labels: BTreeMap::new(),
+            config_hash: None,
+        }
+    }
+
+    /// Returns the metadata as a JSON string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+// Returns hex-encoded hash of the JSON representation of the configuration, which is the same on all platforms.
+pub(crate) fn config_hash<C: Serialize>(config: &C) -> String {
+    let json = serde_json::to_vec(config).expect("Failed to serialize run configuration");
+    let mut hasher = DigestHasher::new();
+    hasher.write(&json);
+    format!("{:016x}", hasher.finish())
+}
@@ -0,0 +1,876 @@
+//! Metrics collected by components.
+//!
+//! Most models collect the same kinds of statistics, e.g. the number of processed requests, the current queue length
+//! or the distribution of response times. Instead of implementing this plumbing in each model, components can update
+//! the named metrics via the [`SimulationContext`] methods:
+//!
+//! - counters accumulate the added values, e.g. the number of processed requests,
+//!   see [`SimulationContext::counter_add`](crate::SimulationContext::counter_add),
+//! - gauges store the last set value along with its minimum and maximum, e.g. the current queue length,
+//!   see [`SimulationContext::gauge_set`](crate::SimulationContext::gauge_set),
+//! - histograms summarize the recorded values, e.g. response times,
+//!   see [`SimulationContext::histogram_record`](crate::SimulationContext::histogram_record),
+//! - time-weighted metrics average the value of state variable over simulation time, e.g. the average queue length
+//!   or the utilization of server, see [`TimeWeighted`].
+//!
+//! The metrics are keyed by the component and the metric name, so the same name can be used by different
+//! components. The collected metrics are returned by [`Simulation::metrics`](crate::Simulation::metrics) as
+//! [`MetricsReport`], which also aggregates the metrics with the same name over all components and converts them
+//! to [`RunMetrics`] used by the [experiment](crate::experiment) runners. The histograms count the values in buckets
+//! if their bounds are set via [`Simulation::set_histogram_buckets`](crate::Simulation::set_histogram_buckets).
+//!
+//! The metrics are reset at the end of the [warmup period](crate::warmup): the counters and histograms are cleared,
+//! while the gauges and time-weighted metrics keep their last values and start averaging from the end of warmup.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use simcore::metrics::MetricValue;
+//! use simcore::Simulation;
+//!
+//! let mut sim = Simulation::new(123);
+//! let server1 = sim.create_context("server1");
+//! let server2 = sim.create_context("server2");
+//!
+//! server1.counter_inc("requests");
+//! server1.counter_inc("requests");
+//! server2.counter_add("requests", 3.);
+//! server1.gauge_set("queue_length", 5.);
+//! server1.gauge_set("queue_length", 2.);
+//! server1.histogram_record("response_time", 1.5);
+//! server2.histogram_record("response_time", 0.5);
+//!
+//! let report = sim.metrics();
+//! assert_eq!(report.get("server1", "requests"), Some(&MetricValue::Counter(2.)));
+//! assert_eq!(report.aggregate("requests"), Some(MetricValue::Counter(5.)));
+//! match report.get("server1", "queue_length").unwrap() {
+//!     MetricValue::Gauge(gauge) => {
+//!         assert_eq!(gauge.value, 2.);
+//!         assert_eq!(gauge.max, 5.);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! match report.aggregate("response_time").unwrap() {
+//!     MetricValue::Histogram(histogram) => {
+//!         assert_eq!(histogram.count, 2);
+//!         assert_eq!(histogram.mean(), 1.);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//!
+//! let run_metrics = report.run_metrics();
+//! assert_eq!(run_metrics["server2.requests"], 3.);
+//! assert_eq!(run_metrics["requests"], 5.);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+
+use crate::analysis::RunMetrics;
+use crate::component::Id;
+use crate::state::SimulationState;
+use crate::SimulationContext;
+
+/// Kind of metric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Accumulated sum of added values.
+    Counter,
+    /// Last set value.
+    Gauge,
+    /// Summary of recorded values.
+    Histogram,
+    /// Time-weighted average of value.
+    TimeWeighted,
+}
+
+impl Display for MetricKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricKind::Counter => write!(f, "counter"),
+            MetricKind::Gauge => write!(f, "gauge"),
+            MetricKind::Histogram => write!(f, "histogram"),
+            MetricKind::TimeWeighted => write!(f, "time-weighted"),
+        }
+    }
+}
+
+/// Value of gauge metric.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaugeValue {
+    /// Last set value.
+    pub value: f64,
+    /// Minimum set value.
+    pub min: f64,
+    /// Maximum set value.
+    pub max: f64,
+    /// Number of updates.
+    pub updates: u64,
+}
+
+impl GaugeValue {
+    fn new(value: f64) -> Self {
+        Self {
+            value,
+            min: value,
+            max: value,
+            updates: 0,
+        }
+    }
+
+    fn set(&mut self, value: f64) {
+        self.value = value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.updates += 1;
+    }
+}
+
+/// Summary of values recorded by histogram metric.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramValue {
+    /// Number of recorded values.
+    pub count: u64,
+    /// Sum of recorded values.
+    pub sum: f64,
+    /// Sum of squares of recorded values.
+    pub sum_squares: f64,
+    /// Minimum recorded value, infinity if there are no values.
+    pub min: f64,
+    /// Maximum recorded value, negative infinity if there are no values.
+    pub max: f64,
+    /// Upper bounds of buckets, see [`Simulation::set_histogram_buckets`](crate::Simulation::set_histogram_buckets).
+    pub bounds: Vec<f64>,
+    /// Numbers of values in buckets, the value belongs to the first bucket whose bound is not less than the value.
+    /// The last element counts the values greater than all bounds.
+    pub buckets: Vec<u64>,
+}
+
+impl HistogramValue {
+    fn new(bounds: Vec<f64>) -> Self {
+        let buckets = vec![0; bounds.len() + 1];
+        Self {
+            count: 0,
+            sum: 0.,
+            sum_squares: 0.,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            bounds,
+            buckets,
+        }
+    }
+
+    fn record(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_squares += value * value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.buckets[bucket] += 1;
+    }
+
+    fn merge(&mut self, other: &HistogramValue) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        if self.bounds == other.bounds {
+            for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+                *bucket += count;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(std::mem::take(&mut self.bounds));
+    }
+
+    /// Returns the mean of recorded values, zero if there are no values.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.;
+        }
+        self.sum / self.count as f64
+    }
+
+    /// Returns the population standard deviation of recorded values, zero if there are no values.
+    pub fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            return 0.;
+        }
+        let mean = self.mean();
+        (self.sum_squares / self.count as f64 - mean * mean).max(0.).sqrt()
+    }
+}
+
+/// Value of time-weighted metric.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeWeightedValue {
+    /// Last set value.
+    pub value: f64,
+    /// Minimum value.
+    pub min: f64,
+    /// Maximum value.
+    pub max: f64,
+    /// Number of updates.
+    pub updates: u64,
+    /// Time when the averaging started, i.e. the time of metric creation or the end of warmup period.
+    pub start_time: f64,
+    /// Time up to which the value is integrated.
+    pub last_time: f64,
+    /// Integral of value over time from the start time to the last time.
+    pub integral: f64,
+}
+
+impl TimeWeightedValue {
+    fn new(value: f64, time: f64) -> Self {
+        Self {
+            value,
+            min: value,
+            max: value,
+            updates: 0,
+            start_time: time,
+            last_time: time,
+            integral: 0.,
+        }
+    }
+
+    fn advance(&mut self, time: f64) {
+        self.integral += self.value * (time - self.last_time);
+        self.last_time = time;
+    }
+
+    fn set(&mut self, value: f64, time: f64) {
+        self.advance(time);
+        self.value = value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.updates += 1;
+    }
+
+    fn merge(&mut self, other: &TimeWeightedValue) {
+        self.value += other.value;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.updates += other.updates;
+        self.start_time = self.start_time.min(other.start_time);
+        self.last_time = self.last_time.max(other.last_time);
+        self.integral += other.integral;
+    }
+
+    /// Returns the time-weighted average of value, the last value if no time has elapsed since the start time.
+    pub fn mean(&self) -> f64 {
+        let duration = self.last_time - self.start_time;
+        if duration > 0. {
+            self.integral / duration
+        } else {
+            self.value
+        }
+    }
+}
+
+/// Value of metric.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricValue {
+    /// Value of counter.
+    Counter(f64),
+    /// Value of gauge.
+    Gauge(GaugeValue),
+    /// Value of histogram.
+    Histogram(HistogramValue),
+    /// Value of time-weighted metric.
+    TimeWeighted(TimeWeightedValue),
+}
+
+impl MetricValue {
+    /// Returns the kind of metric.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            MetricValue::Counter(_) => MetricKind::Counter,
+            MetricValue::Gauge(_) => MetricKind::Gauge,
+            MetricValue::Histogram(_) => MetricKind::Histogram,
+            MetricValue::TimeWeighted(_) => MetricKind::TimeWeighted,
+        }
+    }
+
+    /// Returns the single number representing the metric: the value of counter, the last value of gauge, the mean
+    /// of histogram or the time-weighted average of time-weighted metric.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            MetricValue::Counter(value) => *value,
+            MetricValue::Gauge(gauge) => gauge.value,
+            MetricValue::Histogram(histogram) => histogram.mean(),
+            MetricValue::TimeWeighted(time_weighted) => time_weighted.mean(),
+        }
+    }
+
+    // Merges the value of the same metric of another component: the counters, the last values of gauges and the
+    // time-weighted values are summed, the histograms are merged.
+    fn merge(&mut self, other: &MetricValue) {
+        match (self, other) {
+            (MetricValue::Counter(value), MetricValue::Counter(other)) => *value += other,
+            (MetricValue::Gauge(gauge), MetricValue::Gauge(other)) => {
+                gauge.value += other.value;
+                gauge.min = gauge.min.min(other.min);
+                gauge.max = gauge.max.max(other.max);
+                gauge.updates += other.updates;
+            }
+            (MetricValue::Histogram(histogram), MetricValue::Histogram(other)) => histogram.merge(other),
+            (MetricValue::TimeWeighted(time_weighted), MetricValue::TimeWeighted(other)) => time_weighted.merge(other),
+            _ => {}
+        }
+    }
+}
+
+impl Display for MetricValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricValue::Counter(value) => write!(f, "{}", value),
+            MetricValue::Gauge(gauge) => write!(f, "{} (min {}, max {})", gauge.value, gauge.min, gauge.max),
+            MetricValue::Histogram(histogram) => {
+                if histogram.count == 0 {
+                    return write!(f, "count 0");
+                }
+                write!(
+                    f,
+                    "count {}, mean {:.3}, std {:.3}, min {}, max {}",
+                    histogram.count,
+                    histogram.mean(),
+                    histogram.std_dev(),
+                    histogram.min,
+                    histogram.max
+                )
+            }
+            MetricValue::TimeWeighted(time_weighted) => write!(
+                f,
+                "mean {:.3}, last {} (min {}, max {})",
+                time_weighted.mean(),
+                time_weighted.value,
+                time_weighted.min,
+                time_weighted.max
+            ),
+        }
+    }
+}
+
+/// Numeric type of value tracked by [`TimeWeighted`].
+pub trait MetricNumber: Copy {
+    /// Converts the value to `f64`.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_metric_number {
+    ($($t:ty),*) => {
+        $(
+            impl MetricNumber for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_metric_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl MetricNumber for bool {
+    fn to_f64(self) -> f64 {
+        if self {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+/// State variable of component whose changes are recorded against simulation time to compute its time-weighted
+/// average, e.g. the average queue length or the utilization of server.
+///
+/// The variable is registered in the metrics of the component as a time-weighted metric with the specified name,
+/// so its statistics are included in [`Simulation::metrics`](crate::Simulation::metrics). Boolean variables are
+/// averaged as 0 and 1, e.g. the average of the busy flag of server is its utilization.
+///
+/// Panics on creation if the component already has a metric of another kind with the same name.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::metrics::{MetricValue, TimeWeighted};
+/// use simcore::Simulation;
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("server");
+/// let mut queue_length = TimeWeighted::new(&ctx, "queue_length", 0usize);
+/// let mut busy = TimeWeighted::new(&ctx, "busy", false);
+///
+/// sim.step_until_time(1.);
+/// queue_length.set(2);
+/// busy.set(true);
+/// sim.step_until_time(3.);
+/// queue_length.set(queue_length.get() - 1);
+/// sim.step_until_time(4.);
+///
+/// // length is 0 in [0, 1), 2 in [1, 3) and 1 in [3, 4)
+/// assert_eq!(queue_length.mean(), 5. / 4.);
+/// assert_eq!(busy.mean(), 0.75);
+/// match sim.metrics().get("server", "queue_length").unwrap() {
+///     MetricValue::TimeWeighted(value) => {
+///         assert_eq!(value.mean(), 5. / 4.);
+///         assert_eq!(value.max, 2.);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub struct TimeWeighted<T: MetricNumber> {
+    value: T,
+    id: Id,
+    name: String,
+    component_name: String,
+    sim_state: Rc<RefCell<SimulationState>>,
+}
+
+impl<T: MetricNumber> TimeWeighted<T> {
+    /// Creates the time-weighted metric of component with the specified name and initial value.
+    pub fn new(ctx: &SimulationContext, name: &str, initial: T) -> Self {
+        let metric = Self {
+            value: initial,
+            id: ctx.id(),
+            name: name.to_owned(),
+            component_name: ctx.name().to_owned(),
+            sim_state: ctx.sim_state(),
+        };
+        metric.record();
+        metric
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> T {
+        self.value
+    }
+
+    /// Sets the value at the current simulation time.
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.record();
+    }
+
+    /// Returns the time-weighted average of value up to the current simulation time.
+    pub fn mean(&self) -> f64 {
+        self.stats().mean()
+    }
+
+    /// Returns the statistics of value up to the current simulation time.
+    pub fn stats(&self) -> TimeWeightedValue {
+        let state = self.sim_state.borrow();
+        state
+            .metrics()
+            .time_weighted(self.id, &self.name, state.time())
+            .expect("Component of time-weighted metric is removed")
+    }
+
+    fn record(&self) {
+        let mut state = self.sim_state.borrow_mut();
+        let time = state.time();
+        state
+            .metrics_mut()
+            .time_weighted_set(self.id, &self.name, self.value.to_f64(), time, &self.component_name);
+    }
+}
+
+// Time-weighted statistics of state variable of built-in model primitive, e.g. the length of waiting queue.
+// The statistics are kept locally, so the primitive can reset them independently of the warmup reset of metrics,
+// and the variable is also registered as a time-weighted metric of the component.
+pub(crate) struct TimeWeightedStat {
+    local: TimeWeightedValue,
+    metric: TimeWeighted<f64>,
+}
+
+impl TimeWeightedStat {
+    pub fn new(ctx: &SimulationContext, name: &str, initial: f64) -> Self {
+        Self {
+            local: TimeWeightedValue::new(initial, ctx.time()),
+            metric: TimeWeighted::new(ctx, name, initial),
+        }
+    }
+
+    // Sets the value at the current simulation time, the unchanged value is not recorded.
+    pub fn set(&mut self, value: f64) {
+        if value == self.local.value {
+            return;
+        }
+        let time = self.metric.sim_state.borrow().time();
+        self.local.set(value, time);
+        self.metric.set(value);
+    }
+
+    // Returns the local statistics integrated up to the current simulation time.
+    pub fn stats(&self) -> TimeWeightedValue {
+        let mut stats = self.local.clone();
+        stats.advance(self.metric.sim_state.borrow().time());
+        stats
+    }
+
+    // Restarts the local statistics from the current simulation time and value.
+    pub fn reset(&mut self) {
+        self.local = TimeWeightedValue::new(self.local.value, self.metric.sim_state.borrow().time());
+    }
+}
+
+/// Recorded updates of metrics by component name and metric name, see
+/// [`Simulation::enable_metric_series`](crate::Simulation::enable_metric_series).
+///
+/// Each update is recorded as a pair of the simulation time and the value: the value of counter after the update,
+/// the set value of gauge or time-weighted metric, or the value recorded by histogram.
+pub type MetricSeries = BTreeMap<String, BTreeMap<String, Vec<(f64, f64)>>>;
+
+/// Metrics collected by components, see [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsReport {
+    /// Metric values by component name and metric name.
+    pub components: BTreeMap<String, BTreeMap<String, MetricValue>>,
+}
+
+impl MetricsReport {
+    /// Returns the value of metric of the specified component.
+    pub fn get(&self, component: &str, name: &str) -> Option<&MetricValue> {
+        self.components.get(component)?.get(name)
+    }
+
+    /// Returns the names of all metrics sorted alphabetically.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .components
+            .values()
+            .flat_map(|metrics| metrics.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Returns the value of metric aggregated over all components having it: the counters, the last values of
+    /// gauges and the time-weighted values are summed, the histograms are merged.
+    ///
+    /// Returns `None` if no component has the metric.
+    pub fn aggregate(&self, name: &str) -> Option<MetricValue> {
+        let mut result: Option<MetricValue> = None;
+        for value in self.components.values().filter_map(|metrics| metrics.get(name)) {
+            match result.as_mut() {
+                Some(result) => result.merge(value),
+                None => result = Some(value.clone()),
+            }
+        }
+        result
+    }
+
+    /// Converts the metrics to [`RunMetrics`] containing the value of each metric of each component named as
+    /// `component.metric` and the aggregated value of each metric named as `metric`,
+    /// see [`MetricValue::as_f64`].
+    pub fn run_metrics(&self) -> RunMetrics {
+        let mut result = RunMetrics::new();
+        for (component, metrics) in self.components.iter() {
+            for (name, value) in metrics.iter() {
+                result.insert(format!("{}.{}", component, name), value.as_f64());
+            }
+        }
+        for name in self.names() {
+            result.insert(name.clone(), self.aggregate(&name).unwrap().as_f64());
+        }
+        result
+    }
+}
+
+impl Display for MetricsReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<(&String, &String, &MetricValue)> = self
+            .components
+            .iter()
+            .flat_map(|(component, metrics)| metrics.iter().map(move |(name, value)| (component, name, value)))
+            .collect();
+        let component_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0).max(9);
+        let name_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0).max(6);
+        writeln!(
+            f,
+            "{:<cw$}  {:<nw$}  {:<9}  value",
+            "component",
+            "metric",
+            "kind",
+            cw = component_width,
+            nw = name_width
+        )?;
+        for (component, name, value) in rows {
+            writeln!(
+                f,
+                "{:<cw$}  {:<nw$}  {:<9}  {}",
+                component,
+                name,
+                value.kind().to_string(),
+                value,
+                cw = component_width,
+                nw = name_width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct MetricEntry {
+    value: MetricValue,
+    // Recorded updates of the metric as pairs of time and value, see `MetricSeries`.
+    series: Vec<(f64, f64)>,
+}
+
+impl MetricEntry {
+    fn merge(&mut self, other: &MetricEntry) {
+        self.value.merge(&other.value);
+        self.series.extend(other.series.iter().copied());
+        self.series.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+}
+
+fn merge_entries(target: &mut BTreeMap<String, MetricEntry>, name: &str, entry: MetricEntry) {
+    match target.get_mut(name) {
+        Some(existing) => existing.merge(&entry),
+        None => {
+            target.insert(name.to_owned(), entry);
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Metrics {
+    // Metrics by component.
+    components: FxHashMap<Id, FxHashMap<String, MetricEntry>>,
+    // Metrics of removed components by component name.
+    removed: BTreeMap<String, BTreeMap<String, MetricEntry>>,
+    // Bucket bounds of histograms by metric name.
+    histogram_bounds: FxHashMap<String, Vec<f64>>,
+    series_enabled: bool,
+}
+
+impl Metrics {
+    // Updates the metric of component creating it with the initial value if needed, and records the value returned
+    // by the update function in the series.
+    #[allow(clippy::too_many_arguments)]
+    fn update<F>(&mut self, id: Id, name: &str, kind: MetricKind, initial: f64, time: f64, component_name: &str, f: F)
+    where
+        F: FnOnce(&mut MetricValue) -> f64,
+    {
+        let metrics = self.components.entry(id).or_default();
+        if !metrics.contains_key(name) {
+            let value = match kind {
+                MetricKind::Counter => MetricValue::Counter(initial),
+                MetricKind::Gauge => MetricValue::Gauge(GaugeValue::new(initial)),
+                MetricKind::Histogram => MetricValue::Histogram(HistogramValue::new(
+                    self.histogram_bounds.get(name).cloned().unwrap_or_default(),
+                )),
+                MetricKind::TimeWeighted => MetricValue::TimeWeighted(TimeWeightedValue::new(initial, time)),
+            };
+            let entry = MetricEntry {
+                value,
+                series: Vec::new(),
+            };
+            metrics.insert(name.to_owned(), entry);
+        }
+        let entry = metrics.get_mut(name).unwrap();
+        assert!(
+            entry.value.kind() == kind,
+            "Metric {} of component {} is a {}, not a {}",
+            name,
+            component_name,
+            entry.value.kind(),
+            kind
+        );
+        let sample = f(&mut entry.value);
+        if self.series_enabled {
+            entry.series.push((time, sample));
+        }
+    }
+
+    fn value(&self, id: Id, name: &str) -> Option<&MetricValue> {
+        self.components
+            .get(&id)
+            .and_then(|metrics| metrics.get(name))
+            .map(|entry| &entry.value)
+    }
+
+    pub fn enable_series(&mut self) {
+        self.series_enabled = true;
+    }
+
+    pub fn series_enabled(&self) -> bool {
+        self.series_enabled
+    }
+
+    pub fn counter_add(&mut self, id: Id, name: &str, delta: f64, time: f64, component_name: &str) {
+        self.update(id, name, MetricKind::Counter, 0., time, component_name, |metric| {
+            let MetricValue::Counter(value) = metric else {
+                unreachable!()
+            };
+            *value += delta;
+            *value
+        });
+    }
+
+    pub fn counter(&self, id: Id, name: &str) -> f64 {
+        match self.value(id, name) {
+            Some(MetricValue::Counter(value)) => *value,
+            _ => 0.,
+        }
+    }
+
+    pub fn gauge_set(&mut self, id: Id, name: &str, value: f64, time: f64, component_name: &str) {
+        self.update(id, name, MetricKind::Gauge, value, time, component_name, |metric| {
+            let MetricValue::Gauge(gauge) = metric else {
+                unreachable!()
+            };
+            gauge.set(value);
+            value
+        });
+    }
+
+    pub fn gauge(&self, id: Id, name: &str) -> Option<f64> {
+        match self.value(id, name) {
+            Some(MetricValue::Gauge(gauge)) => Some(gauge.value),
+            _ => None,
+        }
+    }
+
+    pub fn histogram_record(&mut self, id: Id, name: &str, value: f64, time: f64, component_name: &str) {
+        self.update(id, name, MetricKind::Histogram, 0., time, component_name, |metric| {
+            let MetricValue::Histogram(histogram) = metric else {
+                unreachable!()
+            };
+            histogram.record(value);
+            value
+        });
+    }
+
+    pub fn time_weighted_set(&mut self, id: Id, name: &str, value: f64, time: f64, component_name: &str) {
+        self.update(
+            id,
+            name,
+            MetricKind::TimeWeighted,
+            value,
+            time,
+            component_name,
+            |metric| {
+                let MetricValue::TimeWeighted(time_weighted) = metric else {
+                    unreachable!()
+                };
+                time_weighted.set(value, time);
+                value
+            },
+        );
+    }
+
+    // Returns the value of time-weighted metric integrated up to the specified time.
+    pub fn time_weighted(&self, id: Id, name: &str, time: f64) -> Option<TimeWeightedValue> {
+        match self.value(id, name) {
+            Some(MetricValue::TimeWeighted(time_weighted)) => {
+                let mut time_weighted = time_weighted.clone();
+                time_weighted.advance(time);
+                Some(time_weighted)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn set_histogram_bounds(&mut self, name: &str, bounds: Vec<f64>) {
+        assert!(
+            bounds.windows(2).all(|pair| pair[0] < pair[1]),
+            "Bucket bounds of histogram {} must be increasing",
+            name
+        );
+        assert!(
+            self.components.values().all(|metrics| !metrics.contains_key(name)),
+            "Bucket bounds of histogram {} must be set before recording values",
+            name
+        );
+        self.histogram_bounds.insert(name.to_owned(), bounds);
+    }
+
+    // Keeps the metrics of removed component for the report, since its identifier can be reused.
+    // The time-weighted metrics are integrated up to the removal time.
+    pub fn remove_component(&mut self, id: Id, component_name: &str, time: f64) {
+        if let Some(metrics) = self.components.remove(&id) {
+            let removed = self.removed.entry(component_name.to_owned()).or_default();
+            for (name, mut entry) in metrics {
+                if let MetricValue::TimeWeighted(time_weighted) = &mut entry.value {
+                    time_weighted.advance(time);
+                }
+                merge_entries(removed, &name, entry);
+            }
+        }
+    }
+
+    // Clears the counters, histograms and series, the gauges and time-weighted metrics keep their last values.
+    pub fn reset(&mut self, time: f64) {
+        let components = self.components.values_mut().flat_map(|metrics| metrics.values_mut());
+        let removed = self.removed.values_mut().flat_map(|metrics| metrics.values_mut());
+        for entry in components.chain(removed) {
+            entry.series.clear();
+            match &mut entry.value {
+                MetricValue::Counter(value) => *value = 0.,
+                MetricValue::Gauge(gauge) => *gauge = GaugeValue::new(gauge.value),
+                MetricValue::Histogram(histogram) => histogram.clear(),
+                MetricValue::TimeWeighted(time_weighted) => {
+                    *time_weighted = TimeWeightedValue::new(time_weighted.value, time)
+                }
+            }
+        }
+    }
+
+    // Returns the metrics of existing and removed components by component name, with the time-weighted metrics of
+    // existing components integrated up to the specified time.
+    fn entries<F: Fn(Id) -> String>(
+        &self,
+        lookup_name: F,
+        time: f64,
+    ) -> BTreeMap<String, BTreeMap<String, MetricEntry>> {
+        let mut result = self.removed.clone();
+        for (id, metrics) in self.components.iter() {
+            if metrics.is_empty() {
+                continue;
+            }
+            let component_metrics = result.entry(lookup_name(*id)).or_default();
+            for (name, entry) in metrics.iter() {
+                let mut entry = entry.clone();
+                if let MetricValue::TimeWeighted(time_weighted) = &mut entry.value {
+                    time_weighted.advance(time);
+                }
+                merge_entries(component_metrics, name, entry);
+            }
+        }
+        result
+    }
+
+    pub fn report<F: Fn(Id) -> String>(&self, lookup_name: F, time: f64) -> MetricsReport {
+        let components = self
+            .entries(lookup_name, time)
+            .into_iter()
+            .map(|(component, metrics)| {
+                let values = metrics.into_iter().map(|(name, entry)| (name, entry.value)).collect();
+                (component, values)
+            })
+            .collect();
+        MetricsReport { components }
+    }
+
+    pub fn series<F: Fn(Id) -> String>(&self, lookup_name: F) -> MetricSeries {
+        self.entries(lookup_name, 0.)
+            .into_iter()
+            .map(|(component, metrics)| {
+                let series = metrics.into_iter().map(|(name, entry)| (name, entry.series)).collect();
+                (component, series)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,345 @@
+//! Export of metrics to CSV and Parquet files.
+//!
+//! At the end of a run, the [metrics](crate::metrics) collected by components can be written to files via
+//! [`Simulation::export_metrics`](crate::Simulation::export_metrics), so the results of runs can be loaded directly
+//! into data analysis tools, e.g. pandas or polars. The export consists of two tables in long format:
+//!
+//! - the summary table with a row per metric of each component, which contains the columns `component`, `metric`,
+//!   `kind`, `value` (see [`MetricValue::as_f64`]), `count`, `sum`, `min`, `max`, `mean` and `std_dev`, where
+//!   the statistics not applicable to the metric kind are empty,
+//! - the series table with a row per recorded update of metric, which contains the columns `component`, `metric`,
+//!   `time` and `value`, see [`MetricSeries`]. It is written only if the recording of series is enabled via
+//!   [`Simulation::enable_metric_series`](crate::Simulation::enable_metric_series).
+//!
+//! Each row of both tables starts with the [run metadata](crate::metadata): the `seed`, the `config_hash` and
+//! a column per run label, e.g. the parameter of the run set via
+//! [`Simulation::set_run_label`](crate::Simulation::set_run_label). Thus the tables of multiple runs can be
+//! concatenated and grouped by the run parameters.
+//!
+//! The tables are written in CSV format or, if the `parquet` feature is enabled, in Parquet format.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::experiment::csv_escape;
+use crate::metadata::RunMetadata;
+use crate::metrics::{MetricSeries, MetricValue, MetricsReport};
+
+/// Format of exported tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// CSV with a header row.
+    Csv,
+    /// Parquet file without compression.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Returns the file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Configuration of metrics export.
+#[derive(Clone, Debug)]
+pub struct MetricsExportConfig {
+    /// Directory where the files are written, it is created if it does not exist.
+    pub dir: PathBuf,
+    /// Prefix of file names, the summary and series tables are written to the files named
+    /// `<prefix>summary.<extension>` and `<prefix>series.<extension>`.
+    pub prefix: String,
+    /// Format of the files.
+    pub format: ExportFormat,
+}
+
+impl MetricsExportConfig {
+    /// Creates a config writing CSV files without prefix to the specified directory.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: String::new(),
+            format: ExportFormat::Csv,
+        }
+    }
+
+    /// Returns the path of the summary table.
+    pub fn summary_path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{}summary.{}", self.prefix, self.format.extension()))
+    }
+
+    /// Returns the path of the series table.
+    pub fn series_path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{}series.{}", self.prefix, self.format.extension()))
+    }
+}
+
+/// Writes the summary table of metrics in the specified format, see [module documentation](self).
+pub fn write_summary<W: Write + Send>(
+    report: &MetricsReport,
+    metadata: &RunMetadata,
+    format: ExportFormat,
+    writer: W,
+) -> std::io::Result<()> {
+    summary_table(report, metadata).write(format, writer)
+}
+
+/// Writes the series table of metrics in the specified format, see [module documentation](self).
+pub fn write_series<W: Write + Send>(
+    series: &MetricSeries,
+    metadata: &RunMetadata,
+    format: ExportFormat,
+    writer: W,
+) -> std::io::Result<()> {
+    series_table(series, metadata).write(format, writer)
+}
+
+// Writes the tables to the files specified in the config and returns their paths.
+pub(crate) fn export(
+    config: &MetricsExportConfig,
+    report: &MetricsReport,
+    series: Option<&MetricSeries>,
+    metadata: &RunMetadata,
+) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(&config.dir)?;
+    let mut paths = vec![config.summary_path()];
+    write_file(&paths[0], |writer| {
+        write_summary(report, metadata, config.format, writer)
+    })?;
+    if let Some(series) = series {
+        paths.push(config.series_path());
+        write_file(&paths[1], |writer| {
+            write_series(series, metadata, config.format, writer)
+        })?;
+    }
+    Ok(paths)
+}
+
+fn write_file<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer)?;
+    writer.flush()
+}
+
+enum Column {
+    Int(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+    Str(Vec<Option<String>>),
+}
+
+impl Column {
+    fn cell(&self, row: usize) -> String {
+        match self {
+            Column::Int(values) => values[row].map_or(String::new(), |value| value.to_string()),
+            Column::Float(values) => values[row].map_or(String::new(), |value| value.to_string()),
+            Column::Str(values) => values[row].as_deref().map_or(String::new(), csv_escape),
+        }
+    }
+}
+
+struct Table {
+    columns: Vec<(String, Column)>,
+    rows: usize,
+}
+
+impl Table {
+    // Creates a table with the columns of run metadata repeated in the specified number of rows.
+    fn with_metadata(metadata: &RunMetadata, rows: usize) -> Self {
+        let mut columns = vec![
+            ("seed".to_owned(), Column::Int(vec![Some(metadata.seed as i64); rows])),
+            (
+                "config_hash".to_owned(),
+                Column::Str(vec![metadata.config_hash.clone(); rows]),
+            ),
+        ];
+        for (key, value) in metadata.labels.iter() {
+            columns.push((key.clone(), Column::Str(vec![Some(value.clone()); rows])));
+        }
+        Self { columns, rows }
+    }
+
+    fn add(&mut self, name: &str, column: Column) {
+        self.columns.push((name.to_owned(), column));
+    }
+
+    fn write<W: Write + Send>(&self, format: ExportFormat, writer: W) -> std::io::Result<()> {
+        match format {
+            ExportFormat::Csv => self.write_csv(writer),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => self.write_parquet(writer),
+        }
+    }
+
+    fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let header: Vec<String> = self.columns.iter().map(|(name, _)| csv_escape(name)).collect();
+        writeln!(writer, "{}", header.join(","))?;
+        for row in 0..self.rows {
+            let cells: Vec<String> = self.columns.iter().map(|(_, column)| column.cell(row)).collect();
+            writeln!(writer, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_parquet<W: Write + Send>(&self, writer: W) -> std::io::Result<()> {
+        use std::sync::Arc;
+
+        use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::types::Type;
+
+        // Splits the optional values into the present values and the definition levels.
+        fn levels<T: Clone, U>(values: &[Option<T>], convert: impl Fn(T) -> U) -> (Vec<U>, Vec<i16>) {
+            let present = values.iter().flatten().cloned().map(convert).collect();
+            let levels = values.iter().map(|value| value.is_some() as i16).collect();
+            (present, levels)
+        }
+
+        let mut fields = Vec::with_capacity(self.columns.len());
+        for (name, column) in self.columns.iter() {
+            let builder = match column {
+                Column::Int(_) => Type::primitive_type_builder(name, PhysicalType::INT64),
+                Column::Float(_) => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+                Column::Str(_) => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                    .with_logical_type(Some(LogicalType::String)),
+            };
+            fields.push(Arc::new(builder.with_repetition(Repetition::OPTIONAL).build()?));
+        }
+        let schema = Arc::new(Type::group_type_builder("schema").with_fields(fields).build()?);
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut file_writer = SerializedFileWriter::new(writer, schema, properties)?;
+        let mut row_group = file_writer.next_row_group()?;
+        for (_, column) in self.columns.iter() {
+            let mut column_writer = row_group.next_column()?.unwrap();
+            match column {
+                Column::Int(values) => {
+                    let (values, levels) = levels(values, |value| value);
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Float(values) => {
+                    let (values, levels) = levels(values, |value| value);
+                    column_writer
+                        .typed::<DoubleType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Str(values) => {
+                    let (values, levels) = levels(values, |value| ByteArray::from(value.into_bytes()));
+                    column_writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+            }
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        file_writer.close()?;
+        Ok(())
+    }
+}
+
+fn summary_table(report: &MetricsReport, metadata: &RunMetadata) -> Table {
+    let mut components = Vec::new();
+    let mut names = Vec::new();
+    let mut kinds = Vec::new();
+    let mut values = Vec::new();
+    let mut counts = Vec::new();
+    let mut sums = Vec::new();
+    let mut mins = Vec::new();
+    let mut maxs = Vec::new();
+    let mut means = Vec::new();
+    let mut std_devs = Vec::new();
+    for (component, metrics) in report.components.iter() {
+        for (name, value) in metrics.iter() {
+            components.push(Some(component.clone()));
+            names.push(Some(name.clone()));
+            kinds.push(Some(value.kind().to_string()));
+            values.push(Some(value.as_f64()));
+            let (count, sum, min, max, mean, std_dev) = match value {
+                MetricValue::Counter(_) => (None, None, None, None, None, None),
+                MetricValue::Gauge(gauge) => (
+                    Some(gauge.updates as i64),
+                    None,
+                    Some(gauge.min),
+                    Some(gauge.max),
+                    None,
+                    None,
+                ),
+                MetricValue::Histogram(histogram) => {
+                    let has_values = histogram.count > 0;
+                    (
+                        Some(histogram.count as i64),
+                        Some(histogram.sum),
+                        has_values.then_some(histogram.min),
+                        has_values.then_some(histogram.max),
+                        Some(histogram.mean()),
+                        Some(histogram.std_dev()),
+                    )
+                }
+                MetricValue::TimeWeighted(time_weighted) => (
+                    Some(time_weighted.updates as i64),
+                    Some(time_weighted.integral),
+                    Some(time_weighted.min),
+                    Some(time_weighted.max),
+                    Some(time_weighted.mean()),
+                    None,
+                ),
+            };
+            counts.push(count);
+            sums.push(sum);
+            mins.push(min);
+            maxs.push(max);
+            means.push(mean);
+            std_devs.push(std_dev);
+        }
+    }
+    let mut table = Table::with_metadata(metadata, components.len());
+    table.add("component", Column::Str(components));
+    table.add("metric", Column::Str(names));
+    table.add("kind", Column::Str(kinds));
+    table.add("value", Column::Float(values));
+    table.add("count", Column::Int(counts));
+    table.add("sum", Column::Float(sums));
+    table.add("min", Column::Float(mins));
+    table.add("max", Column::Float(maxs));
+    table.add("mean", Column::Float(means));
+    table.add("std_dev", Column::Float(std_devs));
+    table
+}
+
+fn series_table(series: &MetricSeries, metadata: &RunMetadata) -> Table {
+    let mut components = Vec::new();
+    let mut names = Vec::new();
+    let mut times = Vec::new();
+    let mut values = Vec::new();
+    for (component, metrics) in series.iter() {
+        for (name, points) in metrics.iter() {
+            for (time, value) in points.iter() {
+                components.push(Some(component.clone()));
+                names.push(Some(name.clone()));
+                times.push(Some(*time));
+                values.push(Some(*value));
+            }
+        }
+    }
+    let mut table = Table::with_metadata(metadata, components.len());
+    table.add("component", Column::Str(components));
+    table.add("metric", Column::Str(names));
+    table.add("time", Column::Float(times));
+    table.add("value", Column::Float(values));
+    table
+}
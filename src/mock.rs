@@ -0,0 +1,282 @@
+//! Replay-driven mock components.
+//!
+//! Tests which isolate one component need mocks of its peers, and hand-written mocks usually cover only trivial
+//! behaviors of complex peers. The [`ReplayMock`] is a component which replays the outgoing events of a real
+//! component recorded in a trace file written via
+//! [`Simulation::enable_trace_file`](crate::Simulation::enable_trace_file).
+//!
+//! The recorded behavior of the component is split into steps, each consisting of an input event processed by the
+//! component and the events emitted by the component while processing it. When the mock receives an event matching
+//! the input of some not yet replayed step, it emits the recorded outgoing events of the first such step with the
+//! recorded delays to the components with the recorded destination names. An input matches a step if it has the
+//! same type and, depending on the [config](ReplayMockConfig), the same source name and serialized payload. The
+//! events emitted by the component to itself are replayed as well, so the steps triggered by them are replayed
+//! when these events are received by the mock.
+//!
+//! The trace must be recorded with `emitted` and `processed` records and with payloads, and the types of outgoing
+//! events must be registered via [`ReplayMock::register_event`]. The events emitted by the component before
+//! processing the first recorded input are not replayed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! use serde::{Deserialize, Serialize};
+//!
+//! use simcore::mock::{ReplayMock, ReplayMockConfig};
+//! use simcore::trace_file::TraceFileConfig;
+//! use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+//!
+//! #[derive(Clone, Serialize, Deserialize)]
+//! struct Query {
+//!     key: u64,
+//! }
+//!
+//! #[derive(Clone, Serialize, Deserialize)]
+//! struct Reply {
+//!     value: u64,
+//! }
+//!
+//! struct Database {
+//!     ctx: SimulationContext,
+//! }
+//!
+//! impl EventHandler for Database {
+//!     fn on(&mut self, event: Event) {
+//!         cast!(match event.data {
+//!             Query { key } => {
+//!                 self.ctx.emit(Reply { value: key * 10 }, event.src, 0.5);
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! // record the behavior of the real database
+//! let path = std::env::temp_dir().join(format!("simcore-mock-doc-{}.jsonl", std::process::id()));
+//! let mut sim = Simulation::new(123);
+//! sim.enable_trace_file(TraceFileConfig::new(&path));
+//! let db_ctx = sim.create_context("db");
+//! let db_id = sim.add_handler("db", Rc::new(RefCell::new(Database { ctx: db_ctx })));
+//! let client = sim.create_context("client");
+//! client.emit(Query { key: 1 }, db_id, 1.);
+//! client.emit(Query { key: 2 }, db_id, 2.);
+//! sim.step_until_no_events();
+//! sim.disable_trace_file();
+//!
+//! // replace the database with the mock
+//! let mut sim = Simulation::new(123);
+//! let mut mock = ReplayMock::new(ReplayMockConfig::new(&path, "db"), sim.create_context("db"));
+//! mock.register_event::<Reply>();
+//! let mock = Rc::new(RefCell::new(mock));
+//! let db_id = sim.add_handler("db", mock.clone());
+//! let client = sim.create_context("client");
+//! client.emit(Query { key: 2 }, db_id, 1.);
+//! sim.step_until_no_events();
+//! // the reply to the second query is replayed
+//! assert_eq!(sim.time(), 1.5);
+//! assert_eq!(mock.borrow().remaining_steps(), 1);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::path::PathBuf;
+
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::checkpoint::{decode, DecodeFn};
+use crate::component::Id;
+use crate::context::SimulationContext;
+use crate::event::{Event, EventData};
+use crate::handler::EventHandler;
+use crate::log::log_unhandled_event;
+use crate::replay::short_type_name;
+use crate::trace_file::{read_trace_file, TraceEventKind, TraceFileRecord};
+
+/// Configuration of replay-driven mock.
+#[derive(Clone, Debug)]
+pub struct ReplayMockConfig {
+    /// Path of the trace file.
+    pub path: PathBuf,
+    /// Name of the recorded component whose behavior is replayed.
+    pub component: String,
+    /// Whether the source name of input event must match the recorded one.
+    pub match_sources: bool,
+    /// Whether the serialized payload of input event must match the recorded one.
+    pub match_payloads: bool,
+    /// Whether the events which do not match any recorded step are logged as unhandled instead of panicking.
+    pub ignore_unmatched: bool,
+}
+
+impl ReplayMockConfig {
+    /// Creates a config replaying the specified component from the specified trace file, matching the inputs by
+    /// type, source and payload.
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(path: P, component: S) -> Self {
+        Self {
+            path: path.into(),
+            component: component.into(),
+            match_sources: true,
+            match_payloads: true,
+            ignore_unmatched: false,
+        }
+    }
+}
+
+// Outgoing event whose payload is deserialized when it is emitted, since the event types are registered after
+// reading the trace.
+struct MockOutput {
+    dst: String,
+    delay: f64,
+    type_name: String,
+    data: Value,
+}
+
+struct MockStep {
+    src: String,
+    type_name: String,
+    data: Option<Value>,
+    outputs: Vec<MockOutput>,
+}
+
+/// Component replaying the recorded behavior of a real component, see [module documentation](self).
+pub struct ReplayMock {
+    config: ReplayMockConfig,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+    // Not yet replayed steps in the recorded order.
+    steps: Vec<MockStep>,
+    destinations: FxHashMap<String, Id>,
+    ctx: SimulationContext,
+}
+
+impl ReplayMock {
+    /// Creates a mock with the specified config and context, reading the recorded steps from the trace.
+    ///
+    /// Panics if the trace cannot be read.
+    pub fn new(config: ReplayMockConfig, ctx: SimulationContext) -> Self {
+        let steps = read_steps(&config);
+        Self {
+            config,
+            decoders: FxHashMap::default(),
+            steps,
+            destinations: FxHashMap::default(),
+            ctx,
+        }
+    }
+
+    /// Registers the type of outgoing events, which is matched with the type name without module path recorded
+    /// in the trace.
+    pub fn register_event<T>(&mut self) -> &mut Self
+    where
+        T: EventData + DeserializeOwned,
+    {
+        self.decoders.insert(short_type_name::<T>(), decode::<T>);
+        self
+    }
+
+    /// Returns the number of recorded steps which are not replayed yet.
+    pub fn remaining_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    // Returns the index of the first not yet replayed step whose input matches the event.
+    fn find_step(&self, src: &str, type_name: &str, data: Option<&Value>) -> Option<usize> {
+        self.steps.iter().position(|step| {
+            step.type_name == type_name
+                && (!self.config.match_sources || step.src == src)
+                && (data.is_none() || step.data.as_ref() == data)
+        })
+    }
+
+    fn emit_output(&mut self, output: MockOutput) {
+        let decode = self.decoders.get(output.type_name.as_str()).unwrap_or_else(|| {
+            panic!(
+                "Event type {} from trace is not registered, see ReplayMock::register_event",
+                output.type_name
+            )
+        });
+        let data = decode(output.data).expect("Failed to deserialize event from trace");
+        let state = self.ctx.sim_state();
+        let dst = *self.destinations.entry(output.dst).or_insert_with_key(|name| {
+            state
+                .borrow()
+                .try_lookup_id(name)
+                .unwrap_or_else(|| panic!("Destination component {} of replayed event does not exist", name))
+        });
+        state
+            .borrow_mut()
+            .add_boxed_event(data, self.ctx.id(), dst, output.delay);
+    }
+}
+
+impl EventHandler for ReplayMock {
+    fn on(&mut self, event: Event) {
+        let src = self.ctx.sim_state().borrow().lookup_name(event.src);
+        let type_name = serde_type_name::type_name(&event.data).unwrap();
+        let data = self
+            .config
+            .match_payloads
+            .then(|| serde_json::to_value(&event.data).unwrap());
+        let Some(index) = self.find_step(&src, type_name, data.as_ref()) else {
+            if self.config.ignore_unmatched {
+                log_unhandled_event(event);
+                return;
+            }
+            panic!(
+                "Replay mock {} received event {} from {} which does not match any recorded step",
+                self.ctx.name(),
+                type_name,
+                src
+            );
+        };
+        let step = self.steps.remove(index);
+        for output in step.outputs {
+            self.emit_output(output);
+        }
+    }
+}
+
+fn read_steps(config: &ReplayMockConfig) -> Vec<MockStep> {
+    let mut steps: Vec<MockStep> = Vec::new();
+    for record in read_trace_file(&config.path).records {
+        match record.kind {
+            TraceEventKind::Processed if record.dst == config.component => {
+                steps.push(MockStep {
+                    src: record.src,
+                    type_name: record.type_name,
+                    data: record.data,
+                    outputs: Vec::new(),
+                });
+            }
+            TraceEventKind::Emitted if record.src == config.component => {
+                if let Some(step) = steps.last_mut() {
+                    step.outputs.push(read_output(record));
+                }
+            }
+            _ => {}
+        }
+    }
+    steps
+}
+
+fn read_output(record: TraceFileRecord) -> MockOutput {
+    let event_time = record.event_time.unwrap_or_else(|| {
+        panic!(
+            "Trace record of event {} has no event time, deferred events are not supported",
+            record.id
+        )
+    });
+    let data = record.data.unwrap_or_else(|| {
+        panic!(
+            "Trace record of event {} has no payload, record the trace with payloads",
+            record.id
+        )
+    });
+    MockOutput {
+        dst: record.dst,
+        delay: event_time - record.time,
+        type_name: record.type_name,
+        data,
+    }
+}
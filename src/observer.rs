@@ -0,0 +1,116 @@
+//! Observing simulation steps and time advances.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::event::{Event, EventId};
+
+/// Changes made by a simulation step, passed to [`StepObserver::after_step`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepDelta {
+    /// Identifier of the processed event.
+    pub event_id: EventId,
+    /// Simulation time after the step.
+    pub time: f64,
+    /// Advance of simulation time since the previous observed step.
+    pub time_advance: f64,
+    /// Number of events emitted since the previous observed step, including the events emitted by this step.
+    pub emitted_events: u64,
+}
+
+/// Trait for observing the simulation steps, e.g. for animating the simulation or detecting convergence.
+///
+/// Observers are registered via [`Simulation::add_step_observer`](crate::Simulation::add_step_observer) and are
+/// notified about each step which processes an event, i.e. delivers it to the component handler or to the awaiting
+/// asynchronous task. Events processed in a batch are reported as a single step with the first event of the batch.
+/// In async mode, the steps which only resume tasks on timers are not observed, so their time advance and emitted
+/// events are accounted in the next observed step.
+///
+/// Observers are called outside of event processing, so they can access the simulation state via the shared
+/// references, e.g. to components.
+pub trait StepObserver {
+    /// Called before processing the event.
+    fn before_step(&mut self, _event: &Event) {}
+
+    /// Called after processing the event.
+    fn after_step(&mut self, _delta: &StepDelta) {}
+}
+
+/// Trait for receiving the advances of simulation time, e.g. for driving the visual playback of the simulation at
+/// adjustable speed.
+///
+/// Listeners are registered via
+/// [`Simulation::add_time_advance_listener`](crate::Simulation::add_time_advance_listener) and are notified once
+/// per each distinct simulation time, after all events occurring at this time are processed, i.e. when the next
+/// pending activity is later or there are no more pending events. The advances without processed events, e.g. due to
+/// [`step_until_time`](crate::Simulation::step_until_time) or async timers, are reported with empty events.
+/// The events added at the current time after its advance is reported, e.g. by the code outside of the simulation
+/// between steps, are reported as the next advance with `old_time` equal to `new_time`.
+///
+/// When the simulation is restored from a checkpoint or rewound via [`step_back`](crate::Simulation::step_back),
+/// listeners are notified once via [`on_rewind`](Self::on_rewind) and the events re-executed by `step_back` are not
+/// reported, so the reported times never decrease between rewinds.
+///
+/// Listeners are called outside of event processing, so they can access the simulation state via the shared
+/// references, e.g. to components.
+pub trait TimeAdvanceListener {
+    /// Called when the simulation time advances from `old_time` to `new_time`, with the events processed at
+    /// `new_time` in the order of processing.
+    fn on_time_advance(&mut self, old_time: f64, new_time: f64, events: &[Event]);
+
+    /// Called when the simulation time is moved from `old_time`, the last reported time, to `new_time` by restoring
+    /// a checkpoint or stepping back. The next advance is reported from `new_time`.
+    fn on_rewind(&mut self, _old_time: f64, _new_time: f64) {}
+}
+
+// Registered time advance listeners with the events processed since the last reported advance.
+#[derive(Default)]
+pub(crate) struct TimeAdvances {
+    listeners: Vec<Rc<RefCell<dyn TimeAdvanceListener>>>,
+    reported_time: f64,
+    events: Vec<Event>,
+}
+
+impl TimeAdvances {
+    pub fn add(&mut self, listener: Rc<RefCell<dyn TimeAdvanceListener>>, time: f64) {
+        if self.listeners.is_empty() {
+            self.reset(time);
+        }
+        self.listeners.push(listener);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    pub fn reset(&mut self, time: f64) {
+        self.reported_time = time;
+        self.events.clear();
+    }
+
+    pub fn rewind(&mut self, time: f64) {
+        let old_time = self.reported_time;
+        self.reset(time);
+        for listener in self.listeners.iter() {
+            listener.borrow_mut().on_rewind(old_time, time);
+        }
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        if !self.listeners.is_empty() {
+            self.events.push(event.clone());
+        }
+    }
+
+    // Reports the advance to the specified time, which should be called after all events at this time are processed.
+    pub fn report(&mut self, time: f64) {
+        if time <= self.reported_time && self.events.is_empty() {
+            return;
+        }
+        let old_time = std::mem::replace(&mut self.reported_time, time);
+        let events = std::mem::take(&mut self.events);
+        for listener in self.listeners.iter() {
+            listener.borrow_mut().on_time_advance(old_time, time, &events);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Validation of event ordering assumptions.
+//!
+//! Models often rely on the events sent by one component to another being delivered in the order they were emitted,
+//! e.g. messages sent over a FIFO channel. Such assumptions hold while the delays are constant, but are silently
+//! violated when the delays are randomized, which leads to subtle model errors. The assumptions can be declared via
+//! [`Simulation::assume_fifo`](crate::Simulation::assume_fifo) and
+//! [`Simulation::assume_fifo_to`](crate::Simulation::assume_fifo_to), which enables checking the delivery order of
+//! the corresponding events. Each violation is reported according to the [`OrderingPolicy`].
+
+use std::fmt::{Display, Formatter};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::component::Id;
+use crate::event::{Event, EventId, EventTypeId};
+
+/// Policy of reporting the violations of ordering assumptions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderingPolicy {
+    /// Panic on the first violation (default).
+    Panic,
+    /// Record the violations, which can be obtained via
+    /// [`Simulation::take_ordering_violations`](crate::Simulation::take_ordering_violations).
+    Record,
+}
+
+/// Violation of the FIFO ordering assumption: the event was delivered after the event emitted later between
+/// the same pair of components.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderingViolation {
+    /// Name of the event source.
+    pub src: String,
+    /// Name of the event destination.
+    pub dst: String,
+    /// Identifier of the event delivered out of order.
+    pub event_id: EventId,
+    /// Type of the event delivered out of order.
+    pub event_type: String,
+    /// Delivery time of the event delivered out of order.
+    pub time: f64,
+    /// Identifier of the event emitted later, but delivered before.
+    pub overtaking_event_id: EventId,
+    /// Type of the event emitted later, but delivered before.
+    pub overtaking_event_type: String,
+    /// Delivery time of the event emitted later, but delivered before.
+    pub overtaking_time: f64,
+}
+
+impl Display for OrderingViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "event {} ({}) from {} to {} delivered at {} after event {} ({}) emitted later and delivered at {}",
+            self.event_id,
+            self.event_type,
+            self.src,
+            self.dst,
+            self.time,
+            self.overtaking_event_id,
+            self.overtaking_event_type,
+            self.overtaking_time
+        )
+    }
+}
+
+// Event delivered between the pair of components, which is reported if it overtakes an earlier emitted event.
+#[derive(Clone, Copy)]
+pub(crate) struct DeliveredEvent {
+    pub id: EventId,
+    pub type_id: EventTypeId,
+    pub time: f64,
+}
+
+#[derive(Clone)]
+pub(crate) struct OrderingChecker {
+    pub policy: OrderingPolicy,
+    pairs: FxHashSet<(Id, Id)>,
+    // Destinations receiving events in FIFO order from each source.
+    destinations: FxHashSet<Id>,
+    // Event with the largest identifier delivered between each pair of components.
+    last_delivered: FxHashMap<(Id, Id), DeliveredEvent>,
+    pub violations: Vec<OrderingViolation>,
+}
+
+impl OrderingChecker {
+    pub fn new() -> Self {
+        Self {
+            policy: OrderingPolicy::Panic,
+            pairs: FxHashSet::default(),
+            destinations: FxHashSet::default(),
+            last_delivered: FxHashMap::default(),
+            violations: Vec::new(),
+        }
+    }
+
+    pub fn add_pair(&mut self, src: Id, dst: Id) {
+        self.pairs.insert((src, dst));
+    }
+
+    pub fn add_destination(&mut self, dst: Id) {
+        self.destinations.insert(dst);
+    }
+
+    pub fn is_checked(&self, event: &Event) -> bool {
+        self.destinations.contains(&event.dst) || self.pairs.contains(&(event.src, event.dst))
+    }
+
+    // Records the delivery of checked event, returns the overtaking event if the order is violated.
+    pub fn on_event_delivered(&mut self, event: &Event, type_id: EventTypeId) -> Option<DeliveredEvent> {
+        let delivered = DeliveredEvent {
+            id: event.id,
+            type_id,
+            time: event.time,
+        };
+        match self.last_delivered.get_mut(&(event.src, event.dst)) {
+            Some(last) if last.id > event.id => Some(*last),
+            Some(last) => {
+                *last = delivered;
+                None
+            }
+            None => {
+                self.last_delivered.insert((event.src, event.dst), delivered);
+                None
+            }
+        }
+    }
+
+    // Forgets the assumptions involving the removed component, whose identifier can be reused.
+    pub fn on_component_removed(&mut self, id: Id) {
+        self.pairs.retain(|&(src, dst)| src != id && dst != id);
+        self.destinations.remove(&id);
+        self.last_delivered.retain(|&(src, dst), _| src != id && dst != id);
+    }
+}
@@ -0,0 +1,601 @@
+//! Conservative parallel execution of simulation.
+//!
+//! Large models with many components can be simulated faster by partitioning the components across multiple
+//! threads. [`ParallelSimulation`] runs each partition as a separate [`Simulation`] on a dedicated thread, which
+//! processes the events of its components and exchanges the events sent to the components of other partitions via
+//! channels.
+//!
+//! The synchronization is conservative: a partition processes an event only when no event with a smaller time can
+//! arrive from other partitions. This relies on the _lookahead_ — the minimum delay of events sent between
+//! partitions, which is specified on creation and checked on each emission. Besides the events, the partitions
+//! exchange the lower bounds of the times of their future events (null messages), which allows making progress
+//! without deadlocks.
+//!
+//! The execution is deterministic for a fixed partitioning and seed: the events received from other partitions are
+//! added in the order of their time, source partition and emission order, which does not depend on thread
+//! scheduling. Each partition uses its own random number generator seeded with the simulation seed plus the
+//! partition index.
+//!
+//! The events sent between partitions are serialized, so their types must be registered via
+//! [`ParallelSimulation::register_event`]. The components of other partitions are represented in each partition
+//! by proxy contexts with the same names, so the events are sent as usual via [`lookup_id`](Simulation::lookup_id)
+//! and [`emit`](crate::SimulationContext::emit), while the ordered and deferred events cannot be sent to them.
+//! Continuous models and input gateways are not supported by partitions, which communicate with each other.
+//!
+//! This module requires the `thread` feature.
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
+
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+
+use crate::component::Id;
+use crate::event::{Event, EventData};
+use crate::Simulation;
+
+type DecodeFn = fn(serde_json::Value) -> Box<dyn EventData>;
+
+fn decode<T: EventData + DeserializeOwned>(value: serde_json::Value) -> Box<dyn EventData> {
+    Box::new(serde_json::from_value::<T>(value).expect("Failed to deserialize remote event"))
+}
+
+type Builder<S> = Box<dyn FnOnce(&mut Simulation) -> S + Send>;
+type Command<S> = Box<dyn FnOnce(&mut Simulation, &mut S) + Send>;
+type Panic = Box<dyn std::any::Any + Send>;
+
+// Codecs of events sent between partitions.
+#[derive(Clone, Default)]
+struct Codecs {
+    type_names: FxHashMap<TypeId, &'static str>,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+}
+
+impl Codecs {
+    fn encode(&self, data: &dyn EventData) -> (&'static str, serde_json::Value) {
+        let type_name = *self
+            .type_names
+            .get(&data.type_id())
+            .expect("Type of event sent to remote component is not registered");
+        (type_name, serde_json::to_value(data).unwrap())
+    }
+
+    fn decode(&self, type_name: &str, value: serde_json::Value) -> Box<dyn EventData> {
+        self.decoders[type_name](value)
+    }
+}
+
+// Components of other partitions represented by proxies and the events sent to them.
+#[derive(Clone)]
+pub(crate) struct RemoteComponents {
+    // Partitions of remote components by the identifiers of their proxies.
+    partitions: FxHashMap<Id, usize>,
+    lookahead: f64,
+    outbox: Vec<Event>,
+}
+
+impl RemoteComponents {
+    pub fn contains(&self, id: Id) -> bool {
+        self.partitions.contains_key(&id)
+    }
+
+    pub fn lookahead(&self) -> f64 {
+        self.lookahead
+    }
+
+    pub fn add_event(&mut self, event: Event) {
+        self.outbox.push(event);
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.outbox)
+    }
+}
+
+struct RemoteEvent {
+    time: f64,
+    src: String,
+    dst: String,
+    priority: i64,
+    type_name: &'static str,
+    data: serde_json::Value,
+}
+
+enum Message<S> {
+    Event { from: usize, seq: u64, event: RemoteEvent },
+    // Lower bound of the times of future events sent by the partition.
+    Promise { from: usize, time: f64 },
+    Run { time: f64 },
+    Execute(Command<S>),
+    Stop,
+}
+
+// Status of partition reported to the main thread after building the partition or completing the run.
+struct Status {
+    partition: usize,
+    ok: bool,
+}
+
+struct Partition<S> {
+    index: usize,
+    sim: Simulation,
+    state: S,
+    lookahead: f64,
+    codecs: Codecs,
+    partitions: FxHashMap<Id, usize>,
+    inbox: Receiver<Message<S>>,
+    peers: Vec<Sender<Message<S>>>,
+    status: Sender<Status>,
+    // Lower bounds of the times of future events received from each partition.
+    promises: Vec<f64>,
+    last_promise: f64,
+    // Received events ordered by time, source partition and emission order.
+    received: BTreeMap<(u64, usize, u64), RemoteEvent>,
+    next_seq: u64,
+}
+
+impl<S> Partition<S> {
+    fn serve(&mut self) {
+        while let Ok(message) = self.inbox.recv() {
+            match message {
+                Message::Run { time } => {
+                    if !self.run_until(time) {
+                        return;
+                    }
+                    let _ = self.status.send(Status {
+                        partition: self.index,
+                        ok: true,
+                    });
+                }
+                Message::Execute(command) => command(&mut self.sim, &mut self.state),
+                Message::Stop => return,
+                message => self.receive(message),
+            }
+        }
+    }
+
+    // Runs the partition until the specified time, returns false if the simulation is stopped.
+    fn run_until(&mut self, end: f64) -> bool {
+        loop {
+            self.sim.run_ready_tasks();
+            self.send_events();
+            let next = self.next_time();
+            let safe = self.safe_time();
+            if next <= end && next < safe {
+                self.add_received_events(next);
+                self.sim.step();
+                continue;
+            }
+            if next > end && safe > end {
+                break;
+            }
+            self.send_promise(next.min(safe).min(end) + self.lookahead);
+            match self.inbox.recv() {
+                Ok(Message::Stop) | Err(_) => return false,
+                Ok(message) => self.receive(message),
+            }
+            while let Ok(message) = self.inbox.try_recv() {
+                if let Message::Stop = message {
+                    return false;
+                }
+                self.receive(message);
+            }
+        }
+        self.send_promise(end + self.lookahead);
+        self.sim.step_until_time(end);
+        true
+    }
+
+    fn receive(&mut self, message: Message<S>) {
+        match message {
+            Message::Event { from, seq, event } => {
+                self.received.insert((event.time.to_bits(), from, seq), event);
+            }
+            Message::Promise { from, time } => {
+                self.promises[from] = self.promises[from].max(time);
+            }
+            _ => unreachable!("Unexpected message during partition run"),
+        }
+    }
+
+    // Returns the time of the next local activity or received event.
+    fn next_time(&mut self) -> f64 {
+        let local = self.sim.next_activity_time().unwrap_or(f64::INFINITY);
+        let received = self
+            .received
+            .first_key_value()
+            .map_or(f64::INFINITY, |(_, event)| event.time);
+        local.min(received)
+    }
+
+    // Returns the time before which no more events can be received from other partitions.
+    fn safe_time(&self) -> f64 {
+        self.promises
+            .iter()
+            .enumerate()
+            .filter(|(partition, _)| *partition != self.index)
+            .map(|(_, time)| *time)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn add_received_events(&mut self, time: f64) {
+        while let Some(entry) = self.received.first_entry() {
+            if entry.get().time > time {
+                break;
+            }
+            let event = entry.remove();
+            let src = self.sim.lookup_id(&event.src);
+            let dst = self.sim.lookup_id(&event.dst);
+            let data = self.codecs.decode(event.type_name, event.data);
+            self.sim
+                .sim_state()
+                .borrow_mut()
+                .add_received_event(data, src, dst, event.time, event.priority);
+        }
+    }
+
+    fn send_events(&mut self) {
+        let events = self.sim.sim_state().borrow_mut().take_remote_events();
+        for event in events {
+            let partition = self.partitions[&event.dst];
+            let (type_name, data) = self.codecs.encode(event.data.as_ref());
+            let event = RemoteEvent {
+                time: event.time,
+                src: self.sim.lookup_name(event.src),
+                dst: self.sim.lookup_name(event.dst),
+                priority: event.priority,
+                type_name,
+                data,
+            };
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            let _ = self.peers[partition].send(Message::Event {
+                from: self.index,
+                seq,
+                event,
+            });
+        }
+    }
+
+    fn send_promise(&mut self, time: f64) {
+        if time <= self.last_promise {
+            return;
+        }
+        self.last_promise = time;
+        for (partition, peer) in self.peers.iter().enumerate() {
+            if partition != self.index {
+                let _ = peer.send(Message::Promise { from: self.index, time });
+            }
+        }
+    }
+}
+
+struct PartitionHandle<S> {
+    sender: Sender<Message<S>>,
+    thread: Option<JoinHandle<Option<Panic>>>,
+}
+
+/// Simulation with components partitioned across multiple threads.
+///
+/// The partitions are added via [`add_partition`](Self::add_partition) with the names of their components and
+/// the function building the partition, which creates the components and returns the user-defined state. The threads
+/// are started on the first call to [`step_until_time`](Self::step_until_time) or [`execute`](Self::execute), after
+/// which the partitions and the event types cannot be added.
+///
+/// If a partition panics, the other partitions are stopped and the panic is resumed on the calling thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use serde::{Deserialize, Serialize};
+/// use simcore::parallel::ParallelSimulation;
+/// use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Token {
+///     hops: u32,
+/// }
+///
+/// struct Node {
+///     next: Id,
+///     received: Vec<(f64, u32)>,
+///     ctx: SimulationContext,
+/// }
+///
+/// impl EventHandler for Node {
+///     fn on(&mut self, event: Event) {
+///         cast!(match event.data {
+///             Token { hops } => {
+///                 self.received.push((self.ctx.time(), hops));
+///                 if hops < 5 {
+///                     self.ctx.emit(Token { hops: hops + 1 }, self.next, 1.);
+///                 }
+///             }
+///         })
+///     }
+/// }
+///
+/// fn build(sim: &mut Simulation, name: &str, next: &str) -> Rc<RefCell<Node>> {
+///     let ctx = sim.create_context(name);
+///     // the components of other partitions are represented by proxies
+///     let next = sim.lookup_id(next);
+///     let node = Rc::new(RefCell::new(Node { next, received: Vec::new(), ctx }));
+///     sim.add_handler(name, node.clone());
+///     node
+/// }
+///
+/// let mut sim = ParallelSimulation::new(123, 1.);
+/// sim.register_event::<Token>();
+/// let a = sim.add_partition(&["a"], |sim| {
+///     let node = build(sim, "a", "b");
+///     node.borrow().ctx.emit_self(Token { hops: 0 }, 0.);
+///     node
+/// });
+/// let b = sim.add_partition(&["b"], |sim| build(sim, "b", "a"));
+///
+/// sim.step_until_time(10.);
+/// assert_eq!(sim.time(), 10.);
+/// let received_a = sim.execute(a, |_, node| node.borrow().received.clone());
+/// let received_b = sim.execute(b, |_, node| node.borrow().received.clone());
+/// assert_eq!(received_a, vec![(0., 0), (2., 2), (4., 4)]);
+/// assert_eq!(received_b, vec![(1., 1), (3., 3), (5., 5)]);
+/// ```
+pub struct ParallelSimulation<S> {
+    seed: u64,
+    lookahead: f64,
+    time: f64,
+    codecs: Codecs,
+    components: Vec<Vec<String>>,
+    builders: Vec<Builder<S>>,
+    partitions: Vec<PartitionHandle<S>>,
+    status: Option<Receiver<Status>>,
+}
+
+impl<S: 'static> ParallelSimulation<S> {
+    /// Creates a parallel simulation with the specified seed and lookahead.
+    ///
+    /// The lookahead is the minimum delay of events sent between the components of different partitions.
+    /// Larger lookahead reduces the synchronization overhead.
+    ///
+    /// Panics if the lookahead is not positive.
+    pub fn new(seed: u64, lookahead: f64) -> Self {
+        assert!(lookahead > 0., "Lookahead must be positive");
+        Self {
+            seed,
+            lookahead,
+            time: 0.,
+            codecs: Codecs::default(),
+            components: Vec::new(),
+            builders: Vec::new(),
+            partitions: Vec::new(),
+            status: None,
+        }
+    }
+
+    /// Returns the lookahead.
+    pub fn lookahead(&self) -> f64 {
+        self.lookahead
+    }
+
+    /// Returns the number of partitions.
+    pub fn partitions(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns the time reached by all partitions.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Registers the type of events sent between partitions.
+    ///
+    /// Panics if the simulation is already started.
+    pub fn register_event<T: EventData + DeserializeOwned>(&mut self) {
+        self.assert_not_started();
+        let name = std::any::type_name::<T>();
+        self.codecs.type_names.insert(TypeId::of::<T>(), name);
+        self.codecs.decoders.insert(name, decode::<T>);
+    }
+
+    /// Adds the partition with the specified components and returns its index.
+    ///
+    /// The partition is built on its thread by calling `build`, which must create the specified components and
+    /// can emit the initial events. The components of other partitions are already represented by proxies when
+    /// `build` is called.
+    ///
+    /// Panics if the simulation is already started or some component is already declared by another partition.
+    pub fn add_partition<F>(&mut self, components: &[&str], build: F) -> usize
+    where
+        F: FnOnce(&mut Simulation) -> S + Send + 'static,
+    {
+        self.assert_not_started();
+        for name in components {
+            assert!(
+                !self.components.iter().flatten().any(|other| other == name),
+                "Component {} is already declared by another partition",
+                name
+            );
+        }
+        self.components
+            .push(components.iter().map(|name| name.to_string()).collect());
+        self.builders.push(Box::new(build));
+        self.components.len() - 1
+    }
+
+    /// Runs all partitions until the specified time.
+    ///
+    /// Panics if some partition panics.
+    pub fn step_until_time(&mut self, time: f64) {
+        self.start();
+        for partition in self.partitions.iter() {
+            let _ = partition.sender.send(Message::Run { time });
+        }
+        self.wait_all();
+        self.time = self.time.max(time);
+    }
+
+    /// Executes the command on the thread of the specified partition and returns its result.
+    ///
+    /// Panics if the partition does not exist or panics.
+    pub fn execute<F, R>(&mut self, partition: usize, command: F) -> R
+    where
+        F: FnOnce(&mut Simulation, &mut S) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.start();
+        assert!(
+            partition < self.partitions.len(),
+            "Partition {} does not exist",
+            partition
+        );
+        let (result_sender, result_receiver) = channel();
+        let command: Command<S> = Box::new(move |sim, state| {
+            let _ = result_sender.send(command(sim, state));
+        });
+        let _ = self.partitions[partition].sender.send(Message::Execute(command));
+        match result_receiver.recv() {
+            Ok(result) => result,
+            Err(_) => self.abort(),
+        }
+    }
+
+    fn assert_not_started(&self) {
+        assert!(self.status.is_none(), "Parallel simulation is already started");
+    }
+
+    fn start(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+        let (status_sender, status_receiver) = channel();
+        self.status = Some(status_receiver);
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..self.components.len()).map(|_| channel()).unzip();
+        let builders = std::mem::take(&mut self.builders);
+        for (index, (build, inbox)) in builders.into_iter().zip(receivers).enumerate() {
+            let remote: Vec<(String, usize)> = self
+                .components
+                .iter()
+                .enumerate()
+                .filter(|(partition, _)| *partition != index)
+                .flat_map(|(partition, names)| names.iter().map(move |name| (name.clone(), partition)))
+                .collect();
+            let local = self.components[index].clone();
+            let seed = self.seed.wrapping_add(index as u64);
+            let lookahead = self.lookahead;
+            let codecs = self.codecs.clone();
+            let peers = senders.clone();
+            let status = status_sender.clone();
+            let thread = std::thread::Builder::new()
+                .name(format!("partition-{}", index))
+                .spawn(move || {
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        let mut sim = Simulation::new(seed);
+                        let mut partitions = FxHashMap::default();
+                        for (name, partition) in remote {
+                            partitions.insert(sim.create_context(&name).id(), partition);
+                        }
+                        sim.sim_state().borrow_mut().set_remote_components(RemoteComponents {
+                            partitions: partitions.clone(),
+                            lookahead,
+                            outbox: Vec::new(),
+                        });
+                        let state = build(&mut sim);
+                        for name in local {
+                            sim.lookup_id(&name);
+                        }
+                        // the startup hooks can send events to other partitions before the run
+                        sim.start();
+                        let partition_count = peers.len();
+                        let mut partition = Partition {
+                            index,
+                            sim,
+                            state,
+                            lookahead,
+                            codecs,
+                            partitions,
+                            inbox,
+                            peers,
+                            status: status.clone(),
+                            promises: vec![0.; partition_count],
+                            last_promise: f64::NEG_INFINITY,
+                            received: BTreeMap::new(),
+                            next_seq: 0,
+                        };
+                        let _ = status.send(Status {
+                            partition: index,
+                            ok: true,
+                        });
+                        partition.serve();
+                    }));
+                    let panic = result.err();
+                    if panic.is_some() {
+                        let _ = status.send(Status {
+                            partition: index,
+                            ok: false,
+                        });
+                    }
+                    panic
+                })
+                .expect("Failed to spawn partition thread");
+            self.partitions.push(PartitionHandle {
+                sender: senders[index].clone(),
+                thread: Some(thread),
+            });
+        }
+        self.wait_all();
+    }
+
+    // Waits for the status of each partition, aborts the simulation if some partition has failed.
+    fn wait_all(&mut self) {
+        let mut completed = vec![false; self.partitions.len()];
+        while completed.iter().any(|done| !done) {
+            let status = self
+                .status
+                .as_ref()
+                .unwrap()
+                .recv()
+                .expect("Partition threads have stopped");
+            if !status.ok {
+                self.abort();
+            }
+            completed[status.partition] = true;
+        }
+    }
+
+    // Stops all partitions and resumes the panic of the failed partition.
+    fn abort(&mut self) -> ! {
+        let panic = self.stop().expect("Partition thread has stopped without panic");
+        std::panic::resume_unwind(panic);
+    }
+}
+
+impl<S> ParallelSimulation<S> {
+    // Stops all partitions and returns the first panic of partition threads.
+    fn stop(&mut self) -> Option<Panic> {
+        for partition in self.partitions.iter() {
+            let _ = partition.sender.send(Message::Stop);
+        }
+        let mut first_panic = None;
+        for partition in self.partitions.iter_mut() {
+            if let Some(thread) = partition.thread.take() {
+                let panic = thread.join().unwrap_or_else(Some);
+                if first_panic.is_none() {
+                    first_panic = panic;
+                }
+            }
+        }
+        first_panic
+    }
+}
+
+impl<S> Drop for ParallelSimulation<S> {
+    fn drop(&mut self) {
+        // the panic of partition is reported by the failed calls
+        let _ = self.stop();
+    }
+}
@@ -0,0 +1,196 @@
+//! Diagnostics of event producers.
+//!
+//! When a model explodes combinatorially, it is hard to find the component which emits too many events without
+//! tracing all events and analyzing the trace offline. When the producer statistics are enabled via
+//! [`Simulation::enable_producer_stats`](crate::Simulation::enable_producer_stats), the simulation incrementally
+//! counts the emitted events by their source component and type in time buckets of the configured resolution.
+//! The [`ProducerReport`] returned by [`Simulation::top_producers`](crate::Simulation::top_producers) lists the
+//! components, event types and their combinations which emitted the most events in the specified time window.
+
+use std::any::TypeId;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+use crate::event::Event;
+
+/// Configuration of producer statistics.
+#[derive(Clone, Debug)]
+pub struct ProducerStatsConfig {
+    /// Width of time buckets, the report windows are rounded to the bucket boundaries.
+    pub resolution: f64,
+    /// Duration of the kept history, the buckets older than this duration are dropped to bound the memory usage.
+    /// The whole history is kept if not set.
+    pub history: Option<f64>,
+}
+
+impl ProducerStatsConfig {
+    /// Creates a config with the specified resolution keeping the whole history.
+    pub fn new(resolution: f64) -> Self {
+        Self {
+            resolution,
+            history: None,
+        }
+    }
+}
+
+/// Report of the top event producers in a time window, see [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProducerReport {
+    /// Start of the window rounded down to the bucket boundary.
+    pub from: f64,
+    /// End of the window rounded up to the bucket boundary.
+    pub to: f64,
+    /// Total number of events emitted in the window.
+    pub total: u64,
+    /// Names of source components with the numbers of emitted events, sorted by decreasing number of events.
+    pub components: Vec<(String, u64)>,
+    /// Names of event types with the numbers of emitted events, sorted by decreasing number of events.
+    pub event_types: Vec<(String, u64)>,
+    /// Names of source components and event types with the numbers of emitted events, sorted by decreasing number
+    /// of events.
+    pub sources: Vec<(String, String, u64)>,
+}
+
+impl Display for ProducerReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Top event producers in [{:.3}, {:.3}): {} events",
+            self.from, self.to, self.total
+        )?;
+        for (component, event_type, count) in self.sources.iter() {
+            let share = *count as f64 / self.total as f64 * 100.;
+            writeln!(f, "  {} {}: {} ({:.1}%)", component, event_type, count, share)?;
+        }
+        Ok(())
+    }
+}
+
+// Source of counted events, the events of removed components are kept under their names
+// because the identifiers of such components are reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Source {
+    Component(Id),
+    Removed(usize),
+}
+
+type Bucket = FxHashMap<(Source, TypeId), u64>;
+
+#[derive(Clone)]
+pub(crate) struct ProducerStats {
+    config: ProducerStatsConfig,
+    // Counts of emitted events by source and type in the buckets sorted by index.
+    buckets: VecDeque<(u64, Bucket)>,
+    type_names: FxHashMap<TypeId, &'static str>,
+    removed_names: Vec<String>,
+}
+
+impl ProducerStats {
+    pub fn new(config: ProducerStatsConfig) -> Self {
+        assert!(config.resolution > 0., "Producer stats resolution must be positive");
+        Self {
+            config,
+            buckets: VecDeque::new(),
+            type_names: FxHashMap::default(),
+            removed_names: Vec::new(),
+        }
+    }
+
+    fn bucket_index(&self, time: f64) -> u64 {
+        (time / self.config.resolution).floor().max(0.) as u64
+    }
+
+    pub fn on_event_emitted(&mut self, event: &Event, time: f64) {
+        let index = self.bucket_index(time);
+        if self.buckets.back().is_none_or(|(last, _)| *last < index) {
+            self.buckets.push_back((index, Bucket::default()));
+            if let Some(history) = self.config.history {
+                let oldest = self.bucket_index(time - history);
+                while self.buckets.front().is_some_and(|(first, _)| *first < oldest) {
+                    self.buckets.pop_front();
+                }
+            }
+        }
+        let type_id = event.data.as_ref().type_id();
+        let (_, bucket) = self.buckets.back_mut().unwrap();
+        *bucket.entry((Source::Component(event.src), type_id)).or_default() += 1;
+        self.type_names
+            .entry(type_id)
+            .or_insert_with(|| serde_type_name::type_name(&event.data).unwrap());
+    }
+
+    // Moves the counts of removed component under its name.
+    pub fn remove_component(&mut self, id: Id, name: &str) {
+        let idx = match self.removed_names.iter().position(|removed| removed == name) {
+            Some(idx) => idx,
+            None => {
+                self.removed_names.push(name.to_owned());
+                self.removed_names.len() - 1
+            }
+        };
+        for (_, bucket) in self.buckets.iter_mut() {
+            let keys: Vec<_> = bucket
+                .keys()
+                .filter(|(src, _)| *src == Source::Component(id))
+                .copied()
+                .collect();
+            for key in keys {
+                let count = bucket.remove(&key).unwrap();
+                *bucket.entry((Source::Removed(idx), key.1)).or_default() += count;
+            }
+        }
+    }
+
+    pub fn report<N>(&self, from: f64, to: f64, limit: usize, name: N) -> ProducerReport
+    where
+        N: Fn(Id) -> String,
+    {
+        let first = self.bucket_index(from);
+        let last = self.bucket_index(to);
+        let mut counts: Bucket = FxHashMap::default();
+        for (_, bucket) in self
+            .buckets
+            .iter()
+            .filter(|(index, _)| *index >= first && *index <= last)
+        {
+            for (key, count) in bucket.iter() {
+                *counts.entry(*key).or_default() += count;
+            }
+        }
+        let mut components: BTreeMap<String, u64> = BTreeMap::new();
+        let mut event_types: BTreeMap<String, u64> = BTreeMap::new();
+        let mut sources = Vec::new();
+        for ((src, type_id), count) in counts {
+            let component = match src {
+                Source::Component(id) => name(id),
+                Source::Removed(idx) => self.removed_names[idx].clone(),
+            };
+            let event_type = self.type_names[&type_id].to_string();
+            *components.entry(component.clone()).or_default() += count;
+            *event_types.entry(event_type.clone()).or_default() += count;
+            sources.push((component, event_type, count));
+        }
+        let total = components.values().sum();
+        sources.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+        sources.truncate(limit);
+        ProducerReport {
+            from: first as f64 * self.config.resolution,
+            to: (last + 1) as f64 * self.config.resolution,
+            total,
+            components: top(components, limit),
+            event_types: top(event_types, limit),
+            sources,
+        }
+    }
+}
+
+fn top(counts: BTreeMap<String, u64>, limit: usize) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = counts.into_iter().collect();
+    // the stable sort keeps the names with equal counts sorted
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts.truncate(limit);
+    counts
+}
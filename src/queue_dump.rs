@@ -0,0 +1,102 @@
+//! Human-readable dump of pending events.
+//!
+//! See [`Simulation::dump_queue`](crate::Simulation::dump_queue).
+
+use std::io::Write;
+
+use crate::component::Id;
+use crate::event::Event;
+
+/// Options of pending events dump.
+///
+/// By default all pending events are listed.
+#[derive(Clone, Debug)]
+pub struct QueueDumpOptions {
+    /// Lists only the events with this source component.
+    pub src: Option<Id>,
+    /// Lists only the events with this destination component.
+    pub dst: Option<Id>,
+    /// Lists only the events of this type (name without module path, e.g. `Request`).
+    pub event_type: Option<String>,
+    /// Lists only the events scheduled not later than this time.
+    pub until: Option<f64>,
+    /// Maximum number of listed events.
+    pub limit: Option<usize>,
+    /// Maximum length of payload summary, longer payloads are truncated.
+    pub max_payload_len: usize,
+}
+
+impl QueueDumpOptions {
+    /// Creates options listing all pending events with payloads truncated to 80 characters.
+    pub fn new() -> Self {
+        Self {
+            src: None,
+            dst: None,
+            event_type: None,
+            until: None,
+            limit: None,
+            max_payload_len: 80,
+        }
+    }
+}
+
+impl Default for QueueDumpOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Writes the listing of events sorted by time, the event type names are resolved via serde.
+pub(crate) fn write_queue<W: Write>(
+    writer: &mut W,
+    events: &[Event],
+    options: &QueueDumpOptions,
+    component_name: impl Fn(Id) -> String,
+) -> std::io::Result<()> {
+    let selected: Vec<(&Event, &str)> = events
+        .iter()
+        .map(|event| (event, serde_type_name::type_name(&event.data).unwrap()))
+        .filter(|(event, type_name)| {
+            options.src.is_none_or(|src| event.src == src)
+                && options.dst.is_none_or(|dst| event.dst == dst)
+                && options.event_type.as_ref().is_none_or(|t| t == type_name)
+                && options.until.is_none_or(|until| event.time <= until)
+        })
+        .collect();
+    let shown = options.limit.map_or(selected.len(), |limit| limit.min(selected.len()));
+
+    writeln!(
+        writer,
+        "Pending events: {} total, {} matching, {} shown",
+        events.len(),
+        selected.len(),
+        shown
+    )?;
+    for (event, type_name) in selected.iter().take(shown) {
+        let payload = serde_json::to_string(&event.data).unwrap_or_else(|_| "<unserializable>".to_owned());
+        writeln!(
+            writer,
+            "{:>12.3} #{:<6} {} -> {} {} {}",
+            event.time,
+            event.id,
+            component_name(event.src),
+            component_name(event.dst),
+            type_name,
+            truncate(&payload, options.max_payload_len)
+        )?;
+    }
+    if shown < selected.len() {
+        writeln!(writer, "... {} more", selected.len() - shown)?;
+    }
+    Ok(())
+}
+
+fn truncate(payload: &str, max_len: usize) -> String {
+    if payload.chars().count() <= max_len {
+        payload.to_owned()
+    } else {
+        let mut truncated: String = payload.chars().take(max_len).collect();
+        truncated.push_str("...");
+        truncated
+    }
+}
@@ -0,0 +1,116 @@
+//! Rate limiting of log and trace output.
+//!
+//! Dense bursts of events at particular simulation moments, e.g. a broadcast storm or a mass timeout, can produce
+//! most of the log and trace output of a run, which makes the output files large and skews the analysis of the
+//! recorded events. The output can be limited centrally by [`OutputRateLimit`], which allows each component to
+//! produce at most the specified number of records per window of simulation time. The windows are aligned to the
+//! multiples of the window length, and the records exceeding the limit are dropped until the next window starts.
+//!
+//! The limit is set for all components via
+//! [`Simulation::set_output_rate_limit`](crate::Simulation::set_output_rate_limit) and can be overridden for
+//! specific components via
+//! [`Simulation::set_component_output_rate_limit`](crate::Simulation::set_component_output_rate_limit).
+//! It applies separately to each kind of output listed in [`OutputKind`]:
+//!
+//! - the log records produced by the components via [`log_info!`](crate::log_info!) and other log macros, and the
+//!   records of processed events logged at the trace level, which are attributed to the event destination,
+//! - the records of the [trace file](crate::trace_file), which are attributed to the event source for emitted and
+//!   canceled events and to the event destination for processed events.
+//!
+//! The limiting depends only on the simulation time, so the same records are dropped in the runs with the same
+//! seed. The numbers of dropped records are returned by
+//! [`Simulation::suppressed_output`](crate::Simulation::suppressed_output).
+
+use rustc_hash::FxHashMap;
+
+use crate::component::Id;
+
+/// Maximum number of output records produced by a component per window of simulation time.
+///
+/// See [module](self) documentation for details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputRateLimit {
+    /// Length of the window of simulation time.
+    pub window: f64,
+    /// Maximum number of records per window.
+    pub max_records: u64,
+}
+
+impl OutputRateLimit {
+    /// Creates the limit of records per window of simulation time.
+    ///
+    /// Panics if the window length is not positive.
+    pub fn new(window: f64, max_records: u64) -> Self {
+        assert!(window > 0., "Rate limit window must be positive, got {}", window);
+        Self { window, max_records }
+    }
+}
+
+/// Kind of output limited by [`OutputRateLimit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutputKind {
+    /// Log records of components and processed events.
+    Log,
+    /// Records of the trace file.
+    TraceFile,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct OutputRateLimiter {
+    default: Option<OutputRateLimit>,
+    // Overridden limits of components, `None` disables the limit of component.
+    components: FxHashMap<Id, Option<OutputRateLimit>>,
+    // Current window index and number of records in this window by component.
+    windows: FxHashMap<Id, (u64, u64)>,
+    suppressed: u64,
+}
+
+impl OutputRateLimiter {
+    pub fn set_default(&mut self, limit: Option<OutputRateLimit>) {
+        self.default = limit;
+        self.windows.clear();
+    }
+
+    pub fn set_component(&mut self, id: Id, limit: Option<OutputRateLimit>) {
+        self.components.insert(id, limit);
+        self.windows.remove(&id);
+    }
+
+    pub fn remove_component(&mut self, id: Id) {
+        self.components.remove(&id);
+        self.windows.remove(&id);
+    }
+
+    // Returns true if the default limit or the limit of some component is set.
+    pub fn is_enabled(&self) -> bool {
+        self.default.is_some() || self.components.values().any(Option::is_some)
+    }
+
+    // Returns true if the record of component at the specified time can be written and counts it.
+    pub fn allow(&mut self, id: Id, time: f64) -> bool {
+        let Some(limit) = self.components.get(&id).copied().unwrap_or(self.default) else {
+            return true;
+        };
+        let window = (time / limit.window).floor() as u64;
+        let (current, count) = self.windows.entry(id).or_insert((window, 0));
+        if *current != window {
+            *current = window;
+            *count = 0;
+        }
+        if *count < limit.max_records {
+            *count += 1;
+            true
+        } else {
+            self.suppressed += 1;
+            false
+        }
+    }
+
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    pub fn reset_suppressed(&mut self) {
+        self.suppressed = 0;
+    }
+}
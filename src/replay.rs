@@ -0,0 +1,242 @@
+//! Replay of recorded event traces.
+//!
+//! Trace-driven simulations and regression tests often need to feed a model with the external events recorded in
+//! a previous run. The [`TraceReplay`] reads a trace file written via
+//! [`Simulation::enable_trace_file`](crate::Simulation::enable_trace_file) and re-injects the recorded events at
+//! their recorded times when registered via [`Simulation::add_trace_replay`](crate::Simulation::add_trace_replay).
+//!
+//! The replayed events are taken from the `processed` records, so the trace must be recorded with this kind of
+//! records and with payloads. Usually only the events produced by the external sources of the recorded model, such
+//! as clients or workload generators, are replayed, while the rest of the model is simulated anew. The replayed
+//! events are delivered to the components with the recorded destination names and have the replay input component
+//! as their source. Since the trace contains serialized payloads, the replayed event types must be registered via
+//! [`TraceReplay::register_event`].
+//!
+//! The event types can change after the trace is recorded. If the schema versions of payloads are recorded via
+//! [`TraceFileConfig::set_event_version`](crate::trace_file::TraceFileConfig::set_event_version), the replayed
+//! types can be registered with their current versions via [`TraceReplay::register_event_version`], and the
+//! recorded payloads of older versions are converted step-by-step by the migration functions added via
+//! [`TraceReplay::add_migration`] before deserializing them, see [`versioning`](crate::versioning) module.
+//!
+//! The run driven by trace replay can be restored from a [checkpoint](crate::checkpoint) saved in this run, e.g. to
+//! reproduce a failure without replaying the whole trace. The replay registered in the same way then skips the
+//! events read before the checkpoint and continues from there.
+
+use std::path::PathBuf;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::checkpoint::{decode, DecodeFn};
+use crate::component::Id;
+use crate::event::EventData;
+use crate::input::{InputEvent, InputItem};
+use crate::trace_file::{read_trace_file_range, TraceEventKind};
+use crate::versioning::EventMigrations;
+
+/// Configuration of trace replay.
+///
+/// By default all processed events from the trace are replayed at their recorded times. The filters are combined,
+/// i.e. only the events matching all specified filters are replayed.
+#[derive(Clone, Debug)]
+pub struct TraceReplayConfig {
+    /// Path of the trace file.
+    pub path: PathBuf,
+    /// Replays only the events sent by components with these names.
+    pub sources: Option<Vec<String>>,
+    /// Replays only the events of these types (names without module path, e.g. `Request`).
+    pub event_types: Option<Vec<String>>,
+    /// Replays only the events with recorded time in this range (inclusive).
+    pub time_range: Option<(f64, f64)>,
+    /// Offset added to the recorded event times.
+    pub time_offset: f64,
+}
+
+impl TraceReplayConfig {
+    /// Creates a config replaying all events from the specified trace file.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            sources: None,
+            event_types: None,
+            time_range: None,
+            time_offset: 0.,
+        }
+    }
+}
+
+/// Replay of recorded event trace, see [module documentation](self).
+pub struct TraceReplay {
+    config: TraceReplayConfig,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+    migrations: EventMigrations,
+}
+
+impl TraceReplay {
+    /// Creates a replay with the specified config.
+    pub fn new(config: TraceReplayConfig) -> Self {
+        Self {
+            config,
+            decoders: FxHashMap::default(),
+            migrations: EventMigrations::new(),
+        }
+    }
+
+    /// Registers the type of replayed events, which is matched with the type name without module path recorded
+    /// in the trace.
+    pub fn register_event<T>(&mut self) -> &mut Self
+    where
+        T: EventData + DeserializeOwned,
+    {
+        self.decoders.insert(short_type_name::<T>(), decode::<T>);
+        self
+    }
+
+    /// Registers the type of replayed events with the current schema version of its payload,
+    /// see [`register_event`](Self::register_event).
+    ///
+    /// The recorded payloads of older versions are converted to the current version via the migrations added via
+    /// [`add_migration`](Self::add_migration). The types registered via [`register_event`](Self::register_event)
+    /// have version 0.
+    pub fn register_event_version<T>(&mut self, version: u32) -> &mut Self
+    where
+        T: EventData + DeserializeOwned,
+    {
+        self.migrations.set_version(short_type_name::<T>(), version);
+        self.register_event::<T>()
+    }
+
+    /// Adds the function converting the recorded payload of event type with the specified name (without module
+    /// path) from version `from_version` to the next version.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_json::json;
+    ///
+    /// use simcore::replay::{TraceReplay, TraceReplayConfig};
+    /// use simcore::trace_file::TraceFileConfig;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// mod v1 {
+    ///     #[derive(Clone, serde::Serialize)]
+    ///     pub struct Request {
+    ///         pub size: u32,
+    ///     }
+    /// }
+    ///
+    /// // the new version of the event type with renamed field
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// pub struct Request {
+    ///     bytes: u32,
+    /// }
+    ///
+    /// struct Server {
+    ///     log: Vec<u32>,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         self.log.push(event.data.downcast_ref::<Request>().unwrap().bytes);
+    ///     }
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join(format!("simcore-migration-doc-{}.jsonl", std::process::id()));
+    ///
+    /// // record the trace with the old version
+    /// let mut sim = Simulation::new(123);
+    /// let server = sim.create_context("server");
+    /// let client = sim.create_context("client");
+    /// let mut config = TraceFileConfig::new(&path);
+    /// config.set_event_version::<v1::Request>(1);
+    /// sim.enable_trace_file(config);
+    /// client.emit(v1::Request { size: 10 }, server.id(), 1.);
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// // replay it with the new version
+    /// let mut sim = Simulation::new(123);
+    /// let server = Rc::new(RefCell::new(Server { log: Vec::new() }));
+    /// sim.add_handler("server", server.clone());
+    /// let mut replay = TraceReplay::new(TraceReplayConfig::new(&path));
+    /// replay
+    ///     .register_event_version::<Request>(2)
+    ///     .add_migration("Request", 1, |data| json!({"bytes": data["size"]}));
+    /// sim.add_trace_replay("replay", replay);
+    /// sim.step_until_no_events();
+    /// assert_eq!(server.borrow().log, vec![10]);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn add_migration<F>(&mut self, type_name: &str, from_version: u32, migration: F) -> &mut Self
+    where
+        F: Fn(Value) -> Value + 'static,
+    {
+        self.migrations.add_migration(type_name, from_version, migration);
+        self
+    }
+
+    // Reads the trace and returns the replayed input events, resolving the destination names via `lookup_id`.
+    pub(crate) fn read<L>(mut self, lookup_id: L) -> Vec<InputItem>
+    where
+        L: Fn(&str) -> Id,
+    {
+        let (from, to) = self.config.time_range.unwrap_or((f64::NEG_INFINITY, f64::INFINITY));
+        let trace = read_trace_file_range(&self.config.path, from, to);
+        let sources: Option<FxHashSet<String>> =
+            self.config.sources.take().map(|sources| sources.into_iter().collect());
+        let event_types: Option<FxHashSet<String>> = self
+            .config
+            .event_types
+            .take()
+            .map(|event_types| event_types.into_iter().collect());
+        let mut destinations: FxHashMap<String, Id> = FxHashMap::default();
+        trace
+            .records
+            .into_iter()
+            .filter(|record| {
+                record.kind == TraceEventKind::Processed
+                    && sources.as_ref().is_none_or(|sources| sources.contains(&record.src))
+                    && event_types
+                        .as_ref()
+                        .is_none_or(|event_types| event_types.contains(&record.type_name))
+            })
+            .map(|record| {
+                let decode = self.decoders.get(record.type_name.as_str()).unwrap_or_else(|| {
+                    panic!(
+                        "Event type {} from trace is not registered, see TraceReplay::register_event",
+                        record.type_name
+                    )
+                });
+                let data = record.data.unwrap_or_else(|| {
+                    panic!(
+                        "Trace record of event {} has no payload, record the trace with payloads",
+                        record.id
+                    )
+                });
+                let recorded_version = trace.event_versions.get(&record.type_name).copied().unwrap_or(0);
+                let data = self.migrations.migrate(&record.type_name, recorded_version, data);
+                let data = decode(data).expect("Failed to deserialize event from trace");
+                let dst = *destinations
+                    .entry(record.dst)
+                    .or_insert_with_key(|name| lookup_id(name));
+                InputItem::Event(InputEvent {
+                    time: record.time + self.config.time_offset,
+                    dst,
+                    data,
+                })
+            })
+            .collect()
+    }
+}
+
+// Returns the type name without module path and generic arguments, which matches the name recorded in the trace.
+pub(crate) fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap();
+    name.rsplit("::").next().unwrap()
+}
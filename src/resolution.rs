@@ -0,0 +1,232 @@
+//! Multi-resolution modeling.
+//!
+//! Long simulations usually need the detailed model of a component only during windows of interest, e.g. around a
+//! failure or a load spike, while a cheap coarse model is sufficient the rest of the time. A multi-resolution
+//! component registered via [`Simulation::add_multi_resolution`](crate::Simulation::add_multi_resolution) consists of
+//! one or more pairs of detailed and coarse models, which are regular components with their own names and handlers.
+//! Only the models of the active [`Resolution`] receive events, and the active resolution is swapped at runtime via
+//! [`Simulation::set_resolution`](crate::Simulation::set_resolution).
+//!
+//! The events destined to any model of a pair are delivered to the active model of the pair, so the other components
+//! can keep addressing the same model regardless of the active resolution. This applies both to the events emitted
+//! after the swap and to the pending events emitted before it. The pending events emitted between the models of the
+//! deactivated resolution, e.g. the internal timers of the detailed model, are canceled on swap, since they belong
+//! to the state of the deactivated models.
+//!
+//! The state of the deactivated models is translated to the activated models by the hooks registered via
+//! [`MultiResolution::with_translation`], which are called on each swap after the rerouting of pending events. The
+//! hooks can also emit events from the activated models, e.g. to restart their internal timers.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::collections::VecDeque;
+//! use std::rc::Rc;
+//!
+//! use serde::Serialize;
+//! use simcore::resolution::{MultiResolution, Resolution};
+//! use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+//!
+//! #[derive(Clone, Serialize)]
+//! struct Packet {}
+//!
+//! #[derive(Clone, Serialize)]
+//! struct Transmitted {}
+//!
+//! // transmits the queued packets one by one
+//! struct DetailedLink {
+//!     ctx: SimulationContext,
+//!     queue: VecDeque<f64>,
+//!     delivered: u64,
+//! }
+//!
+//! impl EventHandler for DetailedLink {
+//!     fn on(&mut self, event: Event) {
+//!         cast!(match event.data {
+//!             Packet {} => {
+//!                 self.queue.push_back(event.time);
+//!                 if self.queue.len() == 1 {
+//!                     self.ctx.emit_self(Transmitted {}, 1.);
+//!                 }
+//!             }
+//!             Transmitted {} => {
+//!                 self.queue.pop_front();
+//!                 self.delivered += 1;
+//!                 if !self.queue.is_empty() {
+//!                     self.ctx.emit_self(Transmitted {}, 1.);
+//!                 }
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! // counts the packets as delivered immediately
+//! struct CoarseLink {
+//!     delivered: u64,
+//! }
+//!
+//! impl EventHandler for CoarseLink {
+//!     fn on(&mut self, event: Event) {
+//!         cast!(match event.data {
+//!             Packet {} => {
+//!                 self.delivered += 1;
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! let mut sim = Simulation::new(123);
+//! let detailed_ctx = sim.create_context("link");
+//! let detailed = Rc::new(RefCell::new(DetailedLink { ctx: detailed_ctx, queue: VecDeque::new(), delivered: 0 }));
+//! let link_id = sim.add_handler("link", detailed.clone());
+//! let coarse = Rc::new(RefCell::new(CoarseLink { delivered: 0 }));
+//! sim.add_handler("link-coarse", coarse.clone());
+//! sim.add_multi_resolution(
+//!     "link",
+//!     MultiResolution::new("link", "link-coarse").with_translation(
+//!         detailed.clone(),
+//!         coarse.clone(),
+//!         // the queued packets are considered delivered by the coarse model
+//!         |detailed, coarse| coarse.delivered = detailed.delivered + detailed.queue.len() as u64,
+//!         |coarse, detailed| detailed.delivered = coarse.delivered,
+//!     ),
+//! );
+//!
+//! let client = sim.create_context("client");
+//! for i in 0..10 {
+//!     client.emit(Packet {}, link_id, i as f64 * 0.5);
+//! }
+//! sim.step_until_time(2.);
+//! assert_eq!(detailed.borrow().delivered, 2);
+//!
+//! // the pending packets are delivered to the coarse model, while the transmission timer is canceled
+//! sim.set_resolution("link", Resolution::Coarse);
+//! assert_eq!(sim.resolution("link"), Resolution::Coarse);
+//! sim.step_until_no_events();
+//! assert_eq!(coarse.borrow().delivered, 10);
+//! assert_eq!(sim.time(), 4.5);
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::component::Id;
+
+/// Resolution of multi-resolution component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Detailed models are active.
+    Detailed,
+    /// Coarse models are active.
+    Coarse,
+}
+
+// Translates the state of models to the specified activated resolution.
+type TranslateFn = Box<dyn Fn(Resolution)>;
+
+/// Configuration of multi-resolution component, which consists of pairs of detailed and coarse models.
+///
+/// See [module](self) documentation for details and examples.
+pub struct MultiResolution {
+    pub(crate) models: Vec<(String, String)>,
+    pub(crate) initial: Resolution,
+    translations: Vec<TranslateFn>,
+}
+
+impl MultiResolution {
+    /// Creates a component with the specified names of detailed and coarse models, which starts with the detailed
+    /// resolution.
+    pub fn new(detailed: &str, coarse: &str) -> Self {
+        Self {
+            models: Vec::new(),
+            initial: Resolution::Detailed,
+            translations: Vec::new(),
+        }
+        .with_models(detailed, coarse)
+    }
+
+    /// Adds another pair of detailed and coarse models, which are swapped together with the other pairs.
+    ///
+    /// Panics if the detailed and coarse models are the same component.
+    pub fn with_models(mut self, detailed: &str, coarse: &str) -> Self {
+        assert!(
+            detailed != coarse,
+            "Detailed and coarse models must be different components, got {}",
+            detailed
+        );
+        self.models.push((detailed.to_owned(), coarse.to_owned()));
+        self
+    }
+
+    /// Sets the resolution active after the registration of the component.
+    pub fn with_initial_resolution(mut self, resolution: Resolution) -> Self {
+        self.initial = resolution;
+        self
+    }
+
+    /// Adds the hooks translating the state of detailed model to coarse model and vice versa, which are called
+    /// when the coarse and detailed resolution is activated respectively.
+    pub fn with_translation<D, C, FC, FD>(
+        mut self,
+        detailed: Rc<RefCell<D>>,
+        coarse: Rc<RefCell<C>>,
+        to_coarse: FC,
+        to_detailed: FD,
+    ) -> Self
+    where
+        D: 'static,
+        C: 'static,
+        FC: Fn(&D, &mut C) + 'static,
+        FD: Fn(&C, &mut D) + 'static,
+    {
+        self.translations.push(Box::new(move |resolution| match resolution {
+            Resolution::Coarse => to_coarse(&detailed.borrow(), &mut coarse.borrow_mut()),
+            Resolution::Detailed => to_detailed(&coarse.borrow(), &mut detailed.borrow_mut()),
+        }));
+        self
+    }
+}
+
+// Registered multi-resolution component.
+pub(crate) struct MultiResolutionEntry {
+    pub name: String,
+    // Identifiers of detailed and coarse models.
+    pub models: Vec<(Id, Id)>,
+    pub active: Resolution,
+    translations: Vec<TranslateFn>,
+}
+
+impl MultiResolutionEntry {
+    pub fn new(name: &str, config: MultiResolution, models: Vec<(Id, Id)>) -> Self {
+        Self {
+            name: name.to_owned(),
+            models,
+            active: config.initial,
+            translations: config.translations,
+        }
+    }
+
+    // Returns the identifiers of models with the specified resolution.
+    pub fn model_ids(&self, resolution: Resolution) -> Vec<Id> {
+        self.models
+            .iter()
+            .map(|(detailed, coarse)| match resolution {
+                Resolution::Detailed => *detailed,
+                Resolution::Coarse => *coarse,
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, id: Id) -> bool {
+        self.models
+            .iter()
+            .any(|(detailed, coarse)| *detailed == id || *coarse == id)
+    }
+
+    pub fn translate(&self, resolution: Resolution) {
+        for translate in self.translations.iter() {
+            translate(resolution);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Redirection of emitted events.
+//!
+//! Routers registered via [`Simulation::add_router`](crate::Simulation::add_router) are applied to each emitted
+//! event and can change its destination and add delay. This allows to interpose a middlebox or a network model
+//! between components without changing the components themselves.
+
+use std::rc::Rc;
+
+use crate::component::Id;
+use crate::event::Event;
+
+pub(crate) type RouterFn = Rc<dyn Fn(&Event) -> Option<Route>>;
+
+/// Destination and additional delay of routed event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Route {
+    /// Identifier of new event destination.
+    pub dst: Id,
+    /// Delay added to the event delay.
+    pub delay: f64,
+}
+
+impl Route {
+    /// Creates a route redirecting the event to specified destination without additional delay.
+    pub fn to(dst: Id) -> Self {
+        Self { dst, delay: 0. }
+    }
+
+    /// Sets the additional delay of the routed event.
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.delay = delay;
+        self
+    }
+}
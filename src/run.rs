@@ -0,0 +1,77 @@
+//! Results of simulation runs.
+//!
+//! The run methods, such as [`Simulation::run`](crate::Simulation::run),
+//! [`Simulation::run_until_time`](crate::Simulation::run_until_time) and
+//! [`Simulation::run_until`](crate::Simulation::run_until), step through the simulation like their `step_*`
+//! counterparts, but return a [`RunResult`] which bundles the final time, event counters, snapshot of the registered
+//! component states and the reason why the run stopped.
+//!
+//! Unlike the `step_*` methods, the run methods catch the panics raised during event processing, e.g. by failed
+//! assertions in component handlers, and report them as [`TerminationReason::Error`] along with the names of failed
+//! components. The simulation state after an error may be inconsistent, so the simulation should not be continued.
+
+use std::any::Any;
+
+use crate::limits::LimitViolation;
+use crate::snapshot::StateSnapshot;
+
+/// Reason of stopping the simulation run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TerminationReason {
+    /// There are no more pending events.
+    Drained,
+    /// The simulation time reached the specified time.
+    TimeReached,
+    /// The stop condition is met.
+    StopCondition,
+    /// The resource limit is exceeded,
+    /// see [`Simulation::set_resource_limits`](crate::Simulation::set_resource_limits).
+    ///
+    /// Reported only for [`LimitAction::Pause`](crate::limits::LimitAction::Pause), since the default action aborts
+    /// the run with [`Error`](Self::Error).
+    Budget(LimitViolation),
+    /// The simulation is paused by a watchpoint, breakpoint or contract violation.
+    Paused,
+    /// The co-simulation peer with the specified error failed, e.g. closed the connection or sent invalid message,
+    /// see [`Simulation::take_peer_error`](crate::Simulation::take_peer_error).
+    PeerError(String),
+    /// The panic with the specified message is raised during event processing.
+    Error(String),
+}
+
+/// Result of the simulation run.
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    /// Reason of stopping the run.
+    pub termination: TerminationReason,
+    /// Simulation time at the end of the run.
+    pub time: f64,
+    /// Number of events processed during the run.
+    pub processed_events: u64,
+    /// Number of events created during the run.
+    pub emitted_events: u64,
+    /// Number of pending events at the end of the run.
+    pub pending_events: usize,
+    /// Snapshot of the registered component states at the end of the run,
+    /// see [`Simulation::snapshot`](crate::Simulation::snapshot).
+    pub snapshot: StateSnapshot,
+    /// Names of components which failed during the run, i.e. whose handlers or tasks raised a panic.
+    pub failed_components: Vec<String>,
+}
+
+impl RunResult {
+    /// Returns true if the run stopped due to an error.
+    pub fn is_error(&self) -> bool {
+        matches!(self.termination, TerminationReason::Error(_))
+    }
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_owned()
+    }
+}
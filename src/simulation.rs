@@ -1,30 +1,76 @@
 //! Simulation configuration and execution.
 
-use std::cell::RefCell;
+use std::cell::{Cell, Ref, RefCell};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use log::Level::Trace;
-use log::{debug, log_enabled, trace};
+use log::{debug, info, log_enabled, trace, warn};
 use rand::distributions::uniform::{SampleRange, SampleUniform};
 use rand::prelude::Distribution;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::json;
-use serde_type_name::type_name;
 
-use crate::component::Id;
+use crate::branch::BranchComponent;
+use crate::breakpoint::{Breakpoint, BreakpointHit, BreakpointId, Breakpoints};
+use crate::checkpoint::{AutoCheckpoint, AutoCheckpoints, Checkpoint, CheckpointCodecs};
+use crate::component::{ComponentRef, Id};
 use crate::context::SimulationContext;
+use crate::continuous::{ContinuousModel, ContinuousModelEntry, Integrator};
+use crate::contracts::ContractViolation;
+use crate::cosim::CosimBridge;
+use crate::delay::DelayProfile;
+use crate::divergence::{Divergence, DivergenceGuard, DivergenceGuardConfig};
+use crate::event::{EventData, EventId, EventTypeId};
 use crate::handler::{EventCancellationPolicy, EventHandler};
-use crate::log::log_undelivered_event;
+use crate::input::{Input, InputGateway, InputItem, IteratorSource};
+use crate::limits::{LimitAction, LimitGuard, LimitViolation, ResourceLimits};
+use crate::log::{log_undelivered_event, LoggableEvent};
+use crate::logical_clock::{LogicalClockKind, LogicalTime};
+use crate::metadata::RunMetadata;
+use crate::metrics::{MetricSeries, MetricsReport};
+use crate::metrics_export::{self, MetricsExportConfig};
+use crate::observer::{StepDelta, StepObserver, TimeAdvanceListener, TimeAdvances};
+use crate::ordering::{OrderingPolicy, OrderingViolation};
+use crate::producers::{ProducerReport, ProducerStatsConfig};
+use crate::queue_dump::{write_queue, QueueDumpOptions};
+use crate::rate_limit::{OutputKind, OutputRateLimit};
+use crate::replay::TraceReplay;
+use crate::resolution::{MultiResolution, MultiResolutionEntry, Resolution};
+use crate::routing::Route;
+use crate::run::{panic_message, RunResult, TerminationReason};
+use crate::snapshot::{ComponentState, StateSnapshot};
+use crate::spill::SpillConfig;
+use crate::startup::{startup_order, Dependencies, StartableComponent, StartupEntry};
 use crate::state::SimulationState;
+use crate::stop::StopCondition;
+use crate::tick::{TickPolicy, TimeTick};
+use crate::time::{SimDuration, SimTime};
+use crate::trace::{MemoryTrace, TraceSampling};
+use crate::trace_file::{TraceEventKind, TraceFileConfig, TraceFileRecord};
+use crate::warmup::{ResetStats, WarmupPeriod};
+use crate::watchpoint::{CallbackFn, Watchpoint, WatchpointHit, WatchpointId};
 use crate::{async_mode_disabled, async_mode_enabled, Event};
 
 async_mode_enabled!(
+    use std::collections::BTreeMap;
+    use std::hash::Hash;
+
     use futures::Future;
 
-    use crate::event::EventData;
     use crate::async_mode::channel::channel;
     use crate::async_mode::executor::Executor;
-    use crate::async_mode::{UnboundedQueue, EventKey};
-    use crate::handler::StaticEventHandler;
+    use crate::analysis::RunMetrics;
+    use crate::async_mode::{
+        composite_key, DeadlockReport, FairShare, Process, Resource, TokenBucket, UnboundedQueue, EventKey, KeyedEvent,
+        WaitStats,
+    };
+    use crate::handler::{DispatchPrecedence, StaticEventHandler};
 );
 
 async_mode_disabled!(
@@ -34,6 +80,10 @@ async_mode_disabled!(
     fn build_inner(seed: u64) -> (SimulationState, Executor) {
         (SimulationState::new(seed), Executor {})
     }
+
+    fn branch_inner(sim_state: &SimulationState) -> (SimulationState, Executor) {
+        (sim_state.branch(), Executor {})
+    }
 );
 
 async_mode_enabled!(
@@ -49,12 +99,66 @@ async_mode_enabled!(
         let executor = Executor::new(task_receiver);
         (sim_state, executor)
     }
+
+    fn branch_inner(sim_state: &SimulationState) -> (SimulationState, Executor) {
+        let (task_sender, task_receiver) = channel();
+        let sim_state = sim_state.branch(task_sender);
+        let executor = Executor::new(task_receiver);
+        (sim_state, executor)
+    }
 );
 
+// Creates the copy of the component in the branch of the simulation.
+type BranchFn = Box<dyn Fn(&mut Simulation, &str)>;
+
+struct BranchableComponent {
+    id: Id,
+    branch: BranchFn,
+    component: Rc<dyn std::any::Any>,
+}
+
 /// Represents a simulation, provides methods for its configuration and execution.
 pub struct Simulation {
     sim_state: Rc<RefCell<SimulationState>>,
     handlers: Handlers,
+    batching_enabled: Vec<bool>,
+    metadata_logged: Cell<bool>,
+    step_observers: Vec<Rc<RefCell<dyn StepObserver>>>,
+    // Time and event count after the last observed step.
+    last_observed_step: Cell<(f64, u64)>,
+    time_advances: RefCell<TimeAdvances>,
+    continuous_models: Vec<ContinuousModelEntry>,
+    continuous_lookahead: f64,
+    component_states: Vec<(Id, Rc<RefCell<dyn ComponentState>>)>,
+    branchable_components: Vec<BranchableComponent>,
+    startable_components: Vec<StartupEntry>,
+    started: Cell<bool>,
+    checkpoint_codecs: CheckpointCodecs,
+    auto_checkpoints: RefCell<Option<AutoCheckpoints>>,
+    inputs: RefCell<InputGateway>,
+    stop_condition: RefCell<Option<Box<dyn StopCondition>>>,
+    watchpoints: RefCell<Vec<Watchpoint>>,
+    watchpoint_count: u64,
+    watchpoint_hits: RefCell<Vec<WatchpointHit>>,
+    limits: RefCell<Option<LimitGuard>>,
+    limit_violation: RefCell<Option<LimitViolation>>,
+    divergence_guard: RefCell<Option<DivergenceGuard>>,
+    divergence: RefCell<Option<Divergence>>,
+    multi_resolution: Vec<MultiResolutionEntry>,
+    warmup: RefCell<WarmupPeriod>,
+    processed_events: Cell<u64>,
+    // Destination of the event being processed, used to identify the failed component when the processing panics.
+    dispatched_component: Cell<Option<Id>>,
+    contract_violations: RefCell<Vec<ContractViolation>>,
+    // Failures of co-simulation peers reported by the bridges and not yet checked after the step.
+    pending_peer_errors: Rc<RefCell<Vec<String>>>,
+    peer_error: RefCell<Option<String>>,
+    breakpoints: RefCell<Breakpoints>,
+    // Set when a watchpoint or breakpoint is triggered to stop the current run.
+    pause_requested: Cell<bool>,
+    // Set by each optional subsystem checked on each step when it is enabled, so that the plain steps skip the checks.
+    // The flag is shared with the simulation state and is never reset.
+    step_hooks: Rc<Cell<bool>>,
     // Specific to async mode
     #[allow(dead_code)]
     executor: Executor,
@@ -65,20 +169,93 @@ impl Simulation {
     pub fn new(seed: u64) -> Self {
         let (sim_state, executor) = build_inner(seed);
         Self {
+            step_hooks: sim_state.step_hooks(),
             sim_state: Rc::new(RefCell::new(sim_state)),
             handlers: Vec::new(),
+            batching_enabled: Vec::new(),
+            metadata_logged: Cell::new(false),
+            step_observers: Vec::new(),
+            last_observed_step: Cell::new((0., 0)),
+            time_advances: RefCell::new(TimeAdvances::default()),
+            continuous_models: Vec::new(),
+            continuous_lookahead: 0.,
+            component_states: Vec::new(),
+            branchable_components: Vec::new(),
+            startable_components: Vec::new(),
+            started: Cell::new(false),
+            checkpoint_codecs: CheckpointCodecs::new(),
+            auto_checkpoints: RefCell::new(None),
+            inputs: RefCell::new(InputGateway::default()),
+            stop_condition: RefCell::new(None),
+            watchpoints: RefCell::new(Vec::new()),
+            watchpoint_count: 0,
+            watchpoint_hits: RefCell::new(Vec::new()),
+            limits: RefCell::new(None),
+            limit_violation: RefCell::new(None),
+            divergence_guard: RefCell::new(None),
+            divergence: RefCell::new(None),
+            multi_resolution: Vec::new(),
+            warmup: RefCell::new(WarmupPeriod::default()),
+            processed_events: Cell::new(0),
+            dispatched_component: Cell::new(None),
+            contract_violations: RefCell::new(Vec::new()),
+            pending_peer_errors: Rc::new(RefCell::new(Vec::new())),
+            peer_error: RefCell::new(None),
+            breakpoints: RefCell::new(Breakpoints::default()),
+            pause_requested: Cell::new(false),
             executor,
         }
     }
 
+    /// Creates a new simulation with specified random seed running on integer ticks.
+    ///
+    /// This is equivalent to [`new`](Self::new) followed by setting the unit [time tick](Self::set_time_tick) with
+    /// [`TickPolicy::Strict`], so the simulation time is always an exact integer equal to the current tick number.
+    /// The models are expected to use the tick-based variants of time APIs, see [`tick`](crate::tick) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Round {}
+    ///
+    /// let mut sim = Simulation::with_integer_ticks(123);
+    /// let ctx = sim.create_context("comp");
+    /// for _ in 0..10 {
+    ///     ctx.emit_self_ticks(Round {}, 1);
+    ///     sim.step();
+    /// }
+    /// assert_eq!(sim.current_tick(), 10);
+    /// assert_eq!(sim.time(), 10.);
+    /// ```
+    pub fn with_integer_ticks(seed: u64) -> Self {
+        let mut sim = Self::new(seed);
+        sim.set_time_tick(1., TickPolicy::Strict);
+        sim
+    }
+
     fn register(&mut self, name: &str) -> Id {
         let id = self.sim_state.borrow_mut().register(name);
-        if id as usize == self.handlers.len() {
-            self.handlers.push(None);
-        }
+        self.add_component_slot(id);
         id
     }
 
+    // Extends the per-component vectors to include the specified component.
+    // Components can also be registered in the state directly, e.g. by processes, so the vectors are extended lazily.
+    fn add_component_slot(&mut self, id: Id) {
+        if id as usize >= self.handlers.len() {
+            self.handlers.resize_with(id as usize + 1, || None);
+            self.batching_enabled.resize(id as usize + 1, false);
+        }
+    }
+
+    fn is_batching_enabled(&self, id: Id) -> bool {
+        self.batching_enabled.get(id as usize).copied().unwrap_or(false)
+    }
+
     /// Returns the identifier of component by its name.
     ///
     /// Panics if component with such name does not exist.
@@ -105,6 +282,11 @@ impl Simulation {
         self.sim_state.borrow().lookup_id(name)
     }
 
+    #[cfg(feature = "thread")]
+    pub(crate) fn sim_state(&self) -> &Rc<RefCell<SimulationState>> {
+        &self.sim_state
+    }
+
     /// Returns the name of component by its identifier.
     ///
     /// Panics if component with such Id does not exist.
@@ -131,6 +313,45 @@ impl Simulation {
         self.sim_state.borrow().lookup_name(id)
     }
 
+    /// Returns the identifier of event type (type of event payload).
+    ///
+    /// The identifier is assigned to event type upon the first lookup or logging of event of this type.
+    /// See [`EventTypeId`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Response {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let comp_ctx = sim.create_context("comp");
+    /// comp_ctx.emit_self(Request {}, 1.);
+    /// comp_ctx.emit_self(Response {}, 2.);
+    /// comp_ctx.emit_self(Request {}, 3.);
+    /// let events = sim.dump_events();
+    /// let type_ids: Vec<_> = events.iter().map(|e| sim.lookup_event_type_id(e)).collect();
+    /// assert_eq!(type_ids, vec![0, 1, 0]);
+    /// assert_eq!(sim.lookup_event_type_name(type_ids[1]), "Response");
+    /// ```
+    pub fn lookup_event_type_id(&self, event: &Event) -> EventTypeId {
+        self.sim_state.borrow_mut().lookup_event_type_id(event.data.as_ref())
+    }
+
+    /// Returns the name of event type by its identifier.
+    ///
+    /// Panics if event type with such identifier does not exist.
+    /// See [`lookup_event_type_id`](Self::lookup_event_type_id) for examples.
+    pub fn lookup_event_type_name(&self, id: EventTypeId) -> String {
+        self.sim_state.borrow().event_type_name(id).to_owned()
+    }
+
     /// Creates a new simulation context with specified name.
     ///
     /// # Examples
@@ -279,6 +500,71 @@ impl Simulation {
             id
         }
 
+        /// Sets the precedence of delivering events to the component handlers or to the awaiting asynchronous tasks
+        /// when the component has both of them, see [`DispatchPrecedence`].
+        ///
+        /// The precedence applies to all components unless it is overridden for a specific component via
+        /// [`set_component_dispatch_precedence`](Self::set_component_dispatch_precedence). By default, the event is
+        /// delivered to the awaiting task.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::cell::RefCell;
+        /// use std::rc::Rc;
+        /// use serde::Serialize;
+        /// use simcore::async_mode::AwaitResult;
+        /// use simcore::handler::DispatchPrecedence;
+        /// use simcore::{Event, EventHandler, Simulation};
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Message {}
+        ///
+        /// struct Component {
+        ///     handled: u32,
+        /// }
+        ///
+        /// impl EventHandler for Component {
+        ///     fn on(&mut self, _event: Event) {
+        ///         self.handled += 1;
+        ///     }
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let ctx = sim.create_context("comp");
+        /// let client = sim.create_context("client");
+        /// let comp = Rc::new(RefCell::new(Component { handled: 0 }));
+        /// sim.add_handler("comp", comp.clone());
+        /// sim.set_dispatch_precedence(DispatchPrecedence::HandlerFirst);
+        ///
+        /// client.emit(Message {}, ctx.id(), 1.);
+        /// sim.spawn(async move {
+        ///     // the message is delivered to handler, so the task waits until timeout
+        ///     let result = ctx.recv_event::<Message>().with_timeout(5.).await;
+        ///     assert!(matches!(result, AwaitResult::Timeout { .. }));
+        /// });
+        /// sim.step_until_no_events();
+        /// assert_eq!(comp.borrow().handled, 1);
+        /// assert_eq!(sim.time(), 5.);
+        /// ```
+        pub fn set_dispatch_precedence(&mut self, precedence: DispatchPrecedence) {
+            self.sim_state.borrow_mut().set_dispatch_precedence(precedence);
+        }
+
+        /// Sets the precedence of delivering events to the handler or to the awaiting asynchronous tasks for the
+        /// component with specified name, see [`set_dispatch_precedence`](Self::set_dispatch_precedence).
+        ///
+        /// Panics if component with such name does not exist.
+        pub fn set_component_dispatch_precedence<S>(&mut self, name: S, precedence: DispatchPrecedence)
+        where
+            S: AsRef<str>,
+        {
+            let id = self.lookup_id(name.as_ref());
+            self.sim_state
+                .borrow_mut()
+                .set_component_dispatch_precedence(id, precedence);
+        }
+
         fn add_handler_inner(&mut self, id: Id, handler: Rc<RefCell<dyn EventHandler>>) {
             self.handlers[id as usize] = Some(EventHandlerImpl::Mutable(handler));
         }
@@ -322,7 +608,10 @@ impl Simulation {
         S: AsRef<str>,
     {
         let id = self.lookup_id(name.as_ref());
-        self.handlers[id as usize] = None;
+        if let Some(handler) = self.handlers.get_mut(id as usize) {
+            *handler = None;
+        }
+        self.branchable_components.retain(|c| c.id != id);
         self.sim_state.borrow_mut().on_static_handler_removed(id);
         self.remove_handler_inner(id);
 
@@ -343,662 +632,5162 @@ impl Simulation {
         );
     }
 
+    /// Removes the component with specified name and makes its identifier available for new components.
+    ///
+    /// Unlike [`remove_handler`](Self::remove_handler), the component name is removed too, and the identifier
+    /// of removed component is reused by the next created component with a new name. This avoids the unbounded
+    /// growth of per-component data in models where many components are created and removed during a run.
+    ///
+    /// To avoid addressing the new component with stale identifier, the removed component can be referenced via
+    /// [`ComponentRef`] obtained by [`component_ref`](Self::component_ref). Its generation is incremented on each
+    /// removal, and the events emitted via [`SimulationContext::emit_to_ref`] are not delivered to the new
+    /// component.
+    ///
+    /// Panics if component with such name does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::{Event, EventCancellationPolicy, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Packet {}
+    ///
+    /// struct Connection {
+    ///     received: u32,
+    /// }
+    ///
+    /// impl EventHandler for Connection {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.received += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client = sim.create_context("client");
+    /// let conn1 = Rc::new(RefCell::new(Connection { received: 0 }));
+    /// let conn1_id = sim.add_handler("conn-1", conn1.clone());
+    /// let conn1_ref = sim.component_ref("conn-1");
+    /// client.emit_to_ref(Packet {}, conn1_ref, 1.);
+    ///
+    /// sim.remove_component("conn-1", EventCancellationPolicy::None);
+    /// assert!(sim.is_removed(conn1_ref));
+    ///
+    /// // the identifier is reused by the new component
+    /// let conn2 = Rc::new(RefCell::new(Connection { received: 0 }));
+    /// let conn2_id = sim.add_handler("conn-2", conn2.clone());
+    /// assert_eq!(conn2_id, conn1_id);
+    /// assert_ne!(sim.component_ref("conn-2"), conn1_ref);
+    ///
+    /// // the event emitted to the removed component is not delivered to the new one
+    /// sim.step_until_no_events();
+    /// assert_eq!(conn2.borrow().received, 0);
+    /// ```
+    pub fn remove_component<S>(&mut self, name: S, cancel_policy: EventCancellationPolicy)
+    where
+        S: AsRef<str>,
+    {
+        let id = self.lookup_id(name.as_ref());
+        self.remove_handler(name.as_ref(), cancel_policy);
+        if let Some(enabled) = self.batching_enabled.get_mut(id as usize) {
+            *enabled = false;
+        }
+        self.component_states.retain(|(state_id, _)| *state_id != id);
+        self.startable_components.retain(|entry| entry.id != id);
+        self.watchpoints.borrow_mut().retain(|w| w.component != id);
+        self.sim_state.borrow_mut().unregister(id);
+    }
+
+    /// Returns the reference to component with specified name, see [`ComponentRef`].
+    ///
+    /// Panics if component with such name does not exist.
+    pub fn component_ref(&self, name: &str) -> ComponentRef {
+        let state = self.sim_state.borrow();
+        state.component_ref(state.lookup_id(name))
+    }
+
+    /// Returns true if the referenced component is removed via [`remove_component`](Self::remove_component).
+    pub fn is_removed(&self, component: ComponentRef) -> bool {
+        !self.sim_state.borrow().is_current_ref(component)
+    }
+
+    /// Returns the references to all existing components ordered by their identifiers.
+    ///
+    /// The returned list is not affected by the removal of components, so it can be used to iterate over
+    /// the components while removing some of them.
+    pub fn components(&self) -> Vec<ComponentRef> {
+        self.sim_state.borrow().component_refs()
+    }
+
+    /// Registers the startup hook of component with specified name, which depends on the specified components.
+    ///
+    /// The [`on_start`](StartableComponent::on_start) hook is called when the simulation is started via
+    /// [`start`](Self::start), after the hooks of all dependencies, and receives the identifiers of dependencies
+    /// resolved by their names. The dependencies can be registered after this component or have no startup hooks.
+    /// If the simulation is already started, the hook is called immediately. Registering another hook for the same
+    /// component replaces the previous one.
+    ///
+    /// Panics if component with such name does not exist, or if the simulation is already started and some
+    /// dependency does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::startup::{Dependencies, StartableComponent};
+    /// use simcore::{Event, EventHandler, Id, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// struct Server {
+    ///     requests: u32,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.requests += 1;
+    ///     }
+    /// }
+    ///
+    /// struct Balancer {
+    ///     servers: Vec<Id>,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl StartableComponent for Balancer {
+    ///     fn on_start(&mut self, dependencies: &Dependencies) {
+    ///         self.servers = dependencies.ids();
+    ///         for server in self.servers.iter() {
+    ///             self.ctx.emit(Request {}, *server, 1.);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// // the balancer is created before the servers it depends on
+    /// let balancer = Rc::new(RefCell::new(Balancer { servers: Vec::new(), ctx: sim.create_context("balancer") }));
+    /// sim.register_startable("balancer", balancer.clone(), &["server1", "server2"]);
+    /// let server1 = Rc::new(RefCell::new(Server { requests: 0 }));
+    /// let server2 = Rc::new(RefCell::new(Server { requests: 0 }));
+    /// let server1_id = sim.add_handler("server1", server1.clone());
+    /// let server2_id = sim.add_handler("server2", server2.clone());
+    ///
+    /// // the simulation is started automatically when it is run
+    /// sim.step_until_no_events();
+    /// assert_eq!(balancer.borrow().servers, vec![server1_id, server2_id]);
+    /// assert_eq!(server1.borrow().requests, 1);
+    /// assert_eq!(server2.borrow().requests, 1);
+    /// ```
+    pub fn register_startable<C>(&mut self, name: &str, component: Rc<RefCell<C>>, dependencies: &[&str])
+    where
+        C: StartableComponent + 'static,
+    {
+        let entry = StartupEntry {
+            id: self.lookup_id(name),
+            dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+            component,
+        };
+        if self.started.get() {
+            let dependencies = self.resolve_dependencies(&entry);
+            entry.component.borrow_mut().on_start(&dependencies);
+        } else {
+            self.startable_components.retain(|e| e.id != entry.id);
+            self.startable_components.push(entry);
+        }
+    }
+
+    /// Starts the simulation by calling the startup hooks registered via
+    /// [`register_startable`](Self::register_startable) in topological order of the component dependencies.
+    ///
+    /// The components without dependencies between them are started in the order of registration.
+    /// This method is called automatically before the first step of the simulation, so it is needed only to start
+    /// the components without running the simulation, e.g. to inspect the initial events.
+    /// Does nothing if the simulation is already started.
+    ///
+    /// Panics if some dependency does not exist or the dependencies form a cycle.
+    ///
+    /// ```should_panic
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use simcore::startup::{Dependencies, StartableComponent};
+    /// use simcore::Simulation;
+    ///
+    /// struct Component {}
+    ///
+    /// impl StartableComponent for Component {
+    ///     fn on_start(&mut self, _dependencies: &Dependencies) {}
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.create_context("a");
+    /// sim.create_context("b");
+    /// sim.register_startable("a", Rc::new(RefCell::new(Component {})), &["b"]);
+    /// sim.register_startable("b", Rc::new(RefCell::new(Component {})), &["a"]);
+    /// sim.start(); // will panic because of the dependency cycle a -> b -> a
+    /// ```
+    pub fn start(&mut self) {
+        self.start_components();
+    }
+
+    /// Returns true if the simulation is started, see [`start`](Self::start).
+    pub fn is_started(&self) -> bool {
+        self.started.get()
+    }
+
+    fn start_components(&self) {
+        if self.started.replace(true) {
+            return;
+        }
+        let entries = &self.startable_components;
+        let dependencies: Vec<Dependencies> = entries.iter().map(|e| self.resolve_dependencies(e)).collect();
+        for index in startup_order(entries, &dependencies, |id| self.lookup_name(id)) {
+            entries[index].component.borrow_mut().on_start(&dependencies[index]);
+        }
+    }
+
+    fn resolve_dependencies(&self, entry: &StartupEntry) -> Dependencies {
+        let state = self.sim_state.borrow();
+        entry.resolve(|name| state.try_lookup_id(name), |id| state.lookup_name(id))
+    }
+
     async_mode_disabled!(
         fn remove_handler_inner(&mut self, _id: u32) {}
     );
 
     async_mode_enabled!(
         fn remove_handler_inner(&mut self, id: u32) {
+            // drop queued tasks before cancelling the running ones, which would start the queued tasks on completion
+            let queued_tasks = self.sim_state.borrow_mut().take_component_queued_tasks(id);
+            drop(queued_tasks);
             // cancel pending timers and event promises related to the removed component
             self.sim_state.borrow_mut().cancel_component_timers(id);
             self.sim_state.borrow_mut().cancel_component_promises(id);
         }
     );
 
-    /// Returns the current simulation time.
+    /// Allows the component with specified name to emit events on behalf of other components.
+    ///
+    /// This capability is required by [`SimulationContext::emit_as`] and [`SimulationContext::emit_ordered_as`],
+    /// which are used by proxy components, e.g. routers or interceptors, to forward events while preserving their
+    /// original source.
+    ///
+    /// Panics if component with such name does not exist.
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
     /// use serde::Serialize;
-    /// use simcore::Simulation;
+    /// use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// struct Message {}
+    ///
+    /// struct Router {
+    ///     dst: Id,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Router {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Message {} => {
+    ///                 // forward message preserving its original source
+    ///                 self.ctx.emit_as(Message {}, event.src, self.dst, 1.);
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// struct Server {
+    ///     sources: Vec<Id>,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         self.sources.push(event.src);
+    ///     }
     /// }
     ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
-    /// sim.step();
-    /// assert_eq!(sim.time(), 1.2);
+    /// let client_ctx = sim.create_context("client");
+    /// let server = Rc::new(RefCell::new(Server { sources: Vec::new() }));
+    /// let server_id = sim.add_handler("server", server.clone());
+    /// let router_ctx = sim.create_context("router");
+    /// let router_id = sim.add_handler("router", Rc::new(RefCell::new(Router { dst: server_id, ctx: router_ctx })));
+    /// sim.allow_emit_as("router");
+    ///
+    /// client_ctx.emit(Message {}, router_id, 1.);
+    /// sim.step_until_no_events();
+    /// assert_eq!(server.borrow().sources, vec![client_ctx.id()]);
     /// ```
-    pub fn time(&self) -> f64 {
-        self.sim_state.borrow().time()
+    pub fn allow_emit_as<S>(&mut self, name: S)
+    where
+        S: AsRef<str>,
+    {
+        let id = self.lookup_id(name.as_ref());
+        self.sim_state.borrow_mut().allow_emit_as(id);
     }
 
-    /// Performs a single step through the simulation.
+    /// Registers the continuous model advanced between discrete events with the specified integrator.
     ///
-    /// Takes the next event from the queue, advances the simulation time to event time and tries to process it
-    /// by invoking the [`EventHandler::on`] method of the corresponding event handler.
-    /// If there is no handler registered for component with Id `event.dst`, logs the undelivered event and discards it.
+    /// The model is associated with the component with specified name, which receives
+    /// [`ThresholdCrossed`](crate::continuous::ThresholdCrossed) events when the threshold functions of the model
+    /// cross zero, see [`continuous`](crate::continuous) module. Before processing each event, the registered models
+    /// are advanced to the event time. If a threshold function of some model crosses zero before that time, all
+    /// models are advanced to the crossing time and the crossing event is processed first.
     ///
-    /// Returns `true` if some pending event was found (no matter was it properly processed or not) and `false`
-    /// otherwise. The latter means that there are no pending events, so no progress can be made.
+    /// Note that the models are advanced only up to the time of the last processed event or the time passed to
+    /// [`step_until_time`](Self::step_until_time). When there are no pending events, [`step`](Self::step) advances
+    /// the models only to detect threshold crossings within the lookahead set via
+    /// [`set_continuous_lookahead`](Self::set_continuous_lookahead).
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
     /// use serde::Serialize;
-    /// use simcore::Simulation;
+    /// use simcore::continuous::{ContinuousModel, Integrator, ThresholdCrossed};
+    /// use simcore::{cast, Event, EventHandler, Simulation};
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// struct Wakeup {}
+    ///
+    /// // battery discharged with constant power
+    /// struct Battery {
+    ///     charge: f64,
+    ///     power: f64,
+    /// }
+    ///
+    /// impl ContinuousModel for Battery {
+    ///     fn state(&self) -> Vec<f64> {
+    ///         vec![self.charge]
+    ///     }
+    ///
+    ///     fn set_state(&mut self, _time: f64, state: &[f64]) {
+    ///         self.charge = state[0];
+    ///     }
+    ///
+    ///     fn derivatives(&self, _time: f64, _state: &[f64]) -> Vec<f64> {
+    ///         vec![-self.power]
+    ///     }
+    ///
+    ///     fn thresholds(&self, _time: f64, state: &[f64]) -> Vec<f64> {
+    ///         // low charge
+    ///         vec![state[0] - 20.]
+    ///     }
+    /// }
+    ///
+    /// struct Device {
+    ///     battery: Rc<RefCell<Battery>>,
+    ///     low_charge_time: Option<f64>,
+    /// }
+    ///
+    /// impl EventHandler for Device {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             ThresholdCrossed { index, rising } => {
+    ///                 assert_eq!((index, rising), (0, false));
+    ///                 self.low_charge_time = Some(event.time);
+    ///                 // switch to power saving mode
+    ///                 self.battery.borrow_mut().power = 1.;
+    ///             }
+    ///         })
+    ///     }
     /// }
     ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
-    /// let mut status = sim.step();
-    /// assert!(status);
-    /// assert_eq!(sim.time(), 1.2);
-    /// status = sim.step();
-    /// assert!(!status);
+    /// let battery = Rc::new(RefCell::new(Battery { charge: 100., power: 10. }));
+    /// let device = Rc::new(RefCell::new(Device { battery: battery.clone(), low_charge_time: None }));
+    /// sim.add_handler("device", device.clone());
+    /// sim.add_continuous_model("device", battery.clone(), Integrator::RungeKutta4 { step: 0.1 });
+    /// sim.set_continuous_lookahead(100.);
+    ///
+    /// sim.step_until_no_events();
+    /// assert!((device.borrow().low_charge_time.unwrap() - 8.).abs() < 1e-6);
+    /// assert!((battery.borrow().charge - 20.).abs() < 1e-6);
+    ///
+    /// // the state is advanced before processing the next event
+    /// let ctx = sim.create_context("user");
+    /// ctx.emit_self(Wakeup {}, 10.);
+    /// sim.step();
+    /// assert!((battery.borrow().charge - 10.).abs() < 1e-6);
     /// ```
-    pub fn step(&self) -> bool {
-        self.step_inner()
-    }
+    pub fn add_continuous_model<S, M>(&mut self, name: S, model: Rc<RefCell<M>>, integrator: Integrator) -> Id
+    where
+        S: AsRef<str>,
+        M: ContinuousModel + 'static,
+    {
+        let id = self.register(name.as_ref());
+        let time = self.time();
+        self.continuous_models
+            .push(ContinuousModelEntry::new(id, model, integrator, time));
+        self.enable_step_hooks();
+        id
+    }
+
+    /// Sets the time interval ahead of the current time in which threshold crossings of continuous models are
+    /// detected when there are no pending events.
+    ///
+    /// By default, the lookahead is zero, so the simulation stops when there are no pending events.
+    /// See [`add_continuous_model`](Self::add_continuous_model) for examples.
+    pub fn set_continuous_lookahead(&mut self, lookahead: f64) {
+        assert!(lookahead >= 0., "Lookahead must be non-negative");
+        self.continuous_lookahead = lookahead;
+    }
+
+    /// Registers a watchpoint on the state of component with the specified name and returns its identifier.
+    ///
+    /// The condition is evaluated on the component reference after each event delivered to the component.
+    /// When the condition becomes true, i.e. it was false before, the current run of simulation (e.g.
+    /// [`step_until_no_events`](Self::step_until_no_events) or [`step_until_time`](Self::step_until_time)) is paused
+    /// after the event is processed, and the information about the hit can be obtained via
+    /// [`take_watchpoint_hits`](Self::take_watchpoint_hits). The simulation can be resumed by calling any of the
+    /// step methods.
+    ///
+    /// Panics if component with such name does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Deposit {
+    ///     amount: i64,
+    /// }
+    ///
+    /// struct Account {
+    ///     balance: i64,
+    /// }
+    ///
+    /// impl EventHandler for Account {
+    ///     fn on(&mut self, event: Event) {
+    ///         let deposit = event.data.downcast_ref::<Deposit>().unwrap();
+    ///         self.balance += deposit.amount;
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let account = Rc::new(RefCell::new(Account { balance: 0 }));
+    /// let account_id = sim.add_handler("account", account.clone());
+    /// let watchpoint = sim.add_watchpoint("account", account.clone(), |account| account.balance < 0);
+    ///
+    /// let ctx = sim.create_context("client");
+    /// ctx.emit(Deposit { amount: 10 }, account_id, 1.);
+    /// let bad_event = ctx.emit(Deposit { amount: -20 }, account_id, 2.);
+    /// ctx.emit(Deposit { amount: 30 }, account_id, 3.);
+    ///
+    /// sim.step_until_no_events();
+    /// // the simulation is paused after the event which made the balance negative
+    /// assert_eq!(sim.time(), 2.);
+    /// assert_eq!(account.borrow().balance, -10);
+    /// let hits = sim.take_watchpoint_hits();
+    /// assert_eq!(hits.len(), 1);
+    /// assert_eq!(hits[0].watchpoint, watchpoint);
+    /// assert_eq!(hits[0].event_id, bad_event);
+    ///
+    /// // resume the simulation
+    /// sim.step_until_no_events();
+    /// assert_eq!(account.borrow().balance, 20);
+    /// ```
+    pub fn add_watchpoint<C, F>(&mut self, name: &str, component: Rc<RefCell<C>>, condition: F) -> WatchpointId
+    where
+        C: 'static,
+        F: Fn(&C) -> bool + 'static,
+    {
+        self.add_watchpoint_inner(name, component, condition, None)
+    }
+
+    /// Registers a watchpoint on the state of component which calls the callback instead of pausing the simulation.
+    ///
+    /// See [`add_watchpoint`](Self::add_watchpoint) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Request {}
+    ///
+    /// struct Server {
+    ///     queue_len: usize,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.queue_len += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let server = Rc::new(RefCell::new(Server { queue_len: 0 }));
+    /// let server_id = sim.add_handler("server", server.clone());
+    /// let overloads = Rc::new(RefCell::new(Vec::new()));
+    /// let overloads_clone = overloads.clone();
+    /// sim.add_watchpoint_callback(
+    ///     "server",
+    ///     server.clone(),
+    ///     |server| server.queue_len > 2,
+    ///     move |hit| overloads_clone.borrow_mut().push(hit.time),
+    /// );
+    ///
+    /// let ctx = sim.create_context("client");
+    /// for i in 1..=5 {
+    ///     ctx.emit(Request {}, server_id, i as f64);
+    /// }
+    /// sim.step_until_no_events();
+    /// // the simulation is not paused
+    /// assert_eq!(sim.time(), 5.);
+    /// assert_eq!(*overloads.borrow(), vec![3.]);
+    /// assert!(sim.take_watchpoint_hits().is_empty());
+    /// ```
+    pub fn add_watchpoint_callback<C, F, CB>(
+        &mut self,
+        name: &str,
+        component: Rc<RefCell<C>>,
+        condition: F,
+        callback: CB,
+    ) -> WatchpointId
+    where
+        C: 'static,
+        F: Fn(&C) -> bool + 'static,
+        CB: FnMut(&WatchpointHit) + 'static,
+    {
+        self.add_watchpoint_inner(name, component, condition, Some(Box::new(callback)))
+    }
+
+    fn add_watchpoint_inner<C, F>(
+        &mut self,
+        name: &str,
+        component: Rc<RefCell<C>>,
+        condition: F,
+        callback: Option<CallbackFn>,
+    ) -> WatchpointId
+    where
+        C: 'static,
+        F: Fn(&C) -> bool + 'static,
+    {
+        let component_id = self.lookup_id(name);
+        let id = self.watchpoint_count;
+        self.watchpoint_count += 1;
+        self.watchpoints
+            .borrow_mut()
+            .push(Watchpoint::new(id, component_id, component, condition, callback));
+        self.enable_step_hooks();
+        id
+    }
+
+    /// Removes the watchpoint with the specified identifier.
+    ///
+    /// Does nothing if there is no such watchpoint.
+    pub fn remove_watchpoint(&mut self, id: WatchpointId) {
+        self.watchpoints.borrow_mut().retain(|w| w.id != id);
+    }
+
+    /// Returns the hits of pausing watchpoints since the last call of this method.
+    ///
+    /// See [`add_watchpoint`](Self::add_watchpoint) for examples.
+    pub fn take_watchpoint_hits(&mut self) -> Vec<WatchpointHit> {
+        std::mem::take(&mut *self.watchpoint_hits.borrow_mut())
+    }
+
+    /// Registers a breakpoint on the processed events and returns its identifier, see [`breakpoint`](crate::breakpoint)
+    /// module.
+    ///
+    /// When the next event to be processed matches some breakpoint, the current run of simulation is paused before
+    /// delivering the event, and the information about the hit including the copy of the triggering event can be
+    /// obtained via [`take_breakpoint_hit`](Self::take_breakpoint_hit). At this point the simulation time is not
+    /// advanced to the event time yet, and the triggering event can be found among the pending events returned by
+    /// [`dump_events`](Self::dump_events). The next step processes the triggering event without checking the
+    /// breakpoints, so the simulation can be continued step-by-step via [`step`](Self::step) or resumed via any
+    /// other step method.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::breakpoint::Breakpoint;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Deposit {
+    ///     amount: i64,
+    /// }
+    ///
+    /// struct Account {
+    ///     balance: i64,
+    /// }
+    ///
+    /// impl EventHandler for Account {
+    ///     fn on(&mut self, event: Event) {
+    ///         let deposit = event.data.downcast_ref::<Deposit>().unwrap();
+    ///         self.balance += deposit.amount;
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let account = Rc::new(RefCell::new(Account { balance: 0 }));
+    /// let account_id = sim.add_handler("account", account.clone());
+    /// let breakpoint = sim.add_breakpoint(Breakpoint::on_payload(|deposit: &Deposit| deposit.amount < 0));
+    ///
+    /// let ctx = sim.create_context("client");
+    /// ctx.emit(Deposit { amount: 10 }, account_id, 1.);
+    /// let withdrawal = ctx.emit(Deposit { amount: -20 }, account_id, 2.);
+    /// ctx.emit(Deposit { amount: 30 }, account_id, 3.);
+    ///
+    /// sim.step_until_no_events();
+    /// // the simulation is paused before the withdrawal is processed
+    /// assert_eq!(sim.time(), 1.);
+    /// assert_eq!(account.borrow().balance, 10);
+    /// assert_eq!(sim.dump_events().len(), 2);
+    /// let hit = sim.take_breakpoint_hit().unwrap();
+    /// assert_eq!(hit.breakpoint, breakpoint);
+    /// assert_eq!((hit.event.id, hit.event.time), (withdrawal, 2.));
+    ///
+    /// // process the withdrawal and resume the simulation
+    /// sim.step();
+    /// assert_eq!(account.borrow().balance, -10);
+    /// sim.step_until_no_events();
+    /// assert_eq!(account.borrow().balance, 20);
+    /// ```
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> BreakpointId {
+        self.enable_step_hooks();
+        self.breakpoints.borrow_mut().add(breakpoint)
+    }
+
+    /// Removes the breakpoint with the specified identifier.
+    ///
+    /// Does nothing if there is no such breakpoint.
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) {
+        self.breakpoints.borrow_mut().remove(id);
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.borrow_mut().clear();
+    }
+
+    /// Returns the last breakpoint hit if it was not taken yet.
+    ///
+    /// See [`add_breakpoint`](Self::add_breakpoint) for examples.
+    pub fn take_breakpoint_hit(&mut self) -> Option<BreakpointHit> {
+        self.breakpoints.borrow_mut().take_hit()
+    }
+
+    /// Sets the resource limits of the simulation run, see [`limits`](crate::limits) module.
+    ///
+    /// The limits are checked after each step. When some limit is exceeded, the run is aborted with a panic
+    /// describing the violation or paused depending on [`ResourceLimits::action`]. A paused run can be resumed after
+    /// inspecting the model, since the limits are removed when the run is paused. The number of processed events is
+    /// counted from this call, setting the limits again resets it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    ///
+    /// use simcore::limits::{Limit, LimitAction, ResourceLimits};
+    /// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Retry {}
+    ///
+    /// // faulty component which emits two retries for each one
+    /// struct Client {
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Client {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.ctx.emit_self(Retry {}, 1.);
+    ///         self.ctx.emit_self(Retry {}, 1.);
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("client");
+    /// ctx.emit_self(Retry {}, 0.);
+    /// sim.add_handler("client", std::rc::Rc::new(std::cell::RefCell::new(Client { ctx })));
+    ///
+    /// let mut limits = ResourceLimits::new();
+    /// limits.max_pending_events = Some(100);
+    /// limits.action = LimitAction::Pause;
+    /// sim.set_resource_limits(limits);
+    ///
+    /// sim.step_until_no_events();
+    /// let violation = sim.take_limit_violation().unwrap();
+    /// assert_eq!(violation.limit, Limit::PendingEvents(100));
+    /// assert_eq!(violation.pending_events, 101);
+    /// assert_eq!(violation.top_producers[0].0, "client");
+    /// ```
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        *self.limits.borrow_mut() = Some(LimitGuard::new(limits));
+        self.enable_step_hooks();
+    }
+
+    /// Removes the resource limits set via [`set_resource_limits`](Self::set_resource_limits).
+    pub fn clear_resource_limits(&mut self) {
+        self.limits.borrow_mut().take();
+    }
+
+    /// Returns the violation of resource limits which paused the run, if any, and clears it.
+    ///
+    /// See [`set_resource_limits`](Self::set_resource_limits).
+    pub fn take_limit_violation(&mut self) -> Option<LimitViolation> {
+        self.limit_violation.borrow_mut().take()
+    }
+
+    /// Enables cross-checking of processed events against the reference trace file, see
+    /// [`divergence`](crate::divergence) module.
+    ///
+    /// Only the events processed after this call are checked, so the guard should be enabled before running the
+    /// simulation. Enabling the guard again restarts the comparison from the beginning of the reference trace.
+    ///
+    /// Panics if the reference trace file cannot be read or has invalid format.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::divergence::DivergenceGuardConfig;
+    /// use simcore::limits::LimitAction;
+    /// use simcore::trace_file::TraceFileConfig;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     size: u64,
+    /// }
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Response {
+    ///     size: u64,
+    /// }
+    ///
+    /// struct Server {
+    ///     ctx: SimulationContext,
+    ///     refactored: bool,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Request { size } => {
+    ///                 // the refactored version changes the behavior for large requests
+    ///                 let delay = if self.refactored && size > 100 { 2. } else { 1. };
+    ///                 self.ctx.emit(Response { size }, event.src, delay);
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// fn build(refactored: bool) -> Simulation {
+    ///     let mut sim = Simulation::new(123);
+    ///     let server_ctx = sim.create_context("server");
+    ///     let server_id = server_ctx.id();
+    ///     sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx, refactored })));
+    ///     let client = sim.create_context("client");
+    ///     for (i, size) in [10, 50, 200, 20].into_iter().enumerate() {
+    ///         client.emit(Request { size }, server_id, i as f64 * 0.5);
+    ///     }
+    ///     sim
+    /// }
+    ///
+    /// // record the reference trace with the original model
+    /// let path = std::env::temp_dir().join(format!("simcore-divergence-doc-{}.jsonl", std::process::id()));
+    /// let mut sim = build(false);
+    /// sim.enable_trace_file(TraceFileConfig::new(&path));
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// // run the refactored model against the reference trace
+    /// let mut sim = build(true);
+    /// let mut config = DivergenceGuardConfig::new(&path);
+    /// config.action = LimitAction::Pause;
+    /// sim.enable_divergence_guard(config);
+    /// sim.step_until_no_events();
+    /// let divergence = sim.take_divergence().unwrap();
+    /// // the response to the large request is delayed, so the response to the next request is processed instead
+    /// let (expected, actual) = (divergence.expected.as_ref().unwrap(), divergence.actual.as_ref().unwrap());
+    /// assert_eq!((expected.time, expected.data.as_ref().unwrap()["size"].as_u64()), (2., Some(200)));
+    /// assert_eq!((actual.time, actual.data.as_ref().unwrap()["size"].as_u64()), (2.5, Some(20)));
+    /// assert_eq!(divergence.time, 2.5);
+    /// assert_eq!(divergence.previous.len(), 6);
+    /// // the delayed response is pending
+    /// assert!(divergence.pending_events.contains("3.000"));
+    /// println!("{}", divergence);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn enable_divergence_guard(&mut self, config: DivergenceGuardConfig) {
+        *self.divergence_guard.borrow_mut() = Some(DivergenceGuard::new(config));
+        self.enable_step_hooks();
+    }
+
+    /// Disables the divergence guard enabled via [`enable_divergence_guard`](Self::enable_divergence_guard).
+    pub fn disable_divergence_guard(&mut self) {
+        self.divergence_guard.borrow_mut().take();
+    }
+
+    /// Returns the divergence from the reference trace which paused the run, if any, and clears it.
+    ///
+    /// See [`enable_divergence_guard`](Self::enable_divergence_guard).
+    pub fn take_divergence(&mut self) -> Option<Divergence> {
+        self.divergence.borrow_mut().take()
+    }
+
+    /// Sets the action performed when some event boundedness contract is violated, see
+    /// [`contracts`](crate::contracts) module.
+    ///
+    /// By default the run is aborted with a panic describing the violation. With [`LimitAction::Pause`], the
+    /// violation is logged, the current run is paused after the step in which the violation occurred, and the
+    /// violations can be obtained via [`take_contract_violations`](Self::take_contract_violations).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::limits::LimitAction;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.set_contract_action(LimitAction::Pause);
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// client.declare_max_outstanding::<Request>(server.id(), 2);
+    /// for i in 0..4 {
+    ///     client.emit(Request {}, server.id(), i as f64);
+    /// }
+    ///
+    /// sim.step_until_no_events();
+    /// // the run is paused after the first step
+    /// assert_eq!(sim.time(), 0.);
+    /// let violations = sim.take_contract_violations();
+    /// assert_eq!(violations.len(), 2);
+    /// assert_eq!(violations[0].outstanding, 3);
+    /// assert_eq!(violations[1].outstanding, 4);
+    /// ```
+    pub fn set_contract_action(&mut self, action: LimitAction) {
+        self.sim_state.borrow_mut().set_contract_action(action);
+    }
+
+    /// Returns the contract violations which paused the run since the last call of this method.
+    ///
+    /// See [`set_contract_action`](Self::set_contract_action).
+    pub fn take_contract_violations(&mut self) -> Vec<ContractViolation> {
+        std::mem::take(&mut *self.contract_violations.borrow_mut())
+    }
+
+    /// Switches the simulation to discrete time with the specified tick.
+    ///
+    /// The times of events and asynchronous timers created after this call are snapped to multiples of the tick
+    /// according to the policy, see [`tick`](crate::tick) module. The current tick number is available via
+    /// [`SimulationContext::current_tick`].
+    ///
+    /// Panics if the tick is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::tick::TickPolicy;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Round {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// sim.set_time_tick(0.5, TickPolicy::Ceil);
+    /// ctx.emit_self(Round {}, 0.7);
+    /// ctx.emit_self(Round {}, 1.);
+    /// ctx.emit_self(Round {}, 0.1);
+    /// let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    /// assert_eq!(times, vec![0.5, 1., 1.]);
+    /// sim.step_until_no_events();
+    /// assert_eq!(ctx.current_tick(), 2);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use serde::Serialize;
+    /// use simcore::tick::TickPolicy;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Round {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// sim.set_time_tick(1., TickPolicy::Strict);
+    /// ctx.emit_self(Round {}, 1.5); // will panic because delay is not a multiple of tick
+    /// ```
+    pub fn set_time_tick(&mut self, tick: f64, policy: TickPolicy) {
+        self.sim_state.borrow_mut().set_time_tick(TimeTick::new(tick, policy));
+    }
+
+    /// Sets the delay profile used for events emitted via
+    /// [`emit_with_default_delay`](SimulationContext::emit_with_default_delay) when no more specific profile
+    /// matches the event.
+    ///
+    /// See [`delay`](crate::delay) module for the order of profile selection.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rand::distributions::Uniform;
+    /// use serde::Serialize;
+    /// use simcore::delay::DelayProfile;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Message {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// sim.set_default_delay(DelayProfile::distribution(Uniform::new(1., 2.)));
+    /// ctx.emit_with_default_delay(Message {}, ctx.id());
+    /// let time = sim.dump_events()[0].time;
+    /// assert!((1. ..2.).contains(&time));
+    /// ```
+    pub fn set_default_delay(&mut self, profile: DelayProfile) {
+        self.sim_state.borrow_mut().delays_mut().set_default(profile);
+    }
+
+    /// Sets the delay profile used for events of type `T` emitted via
+    /// [`emit_with_default_delay`](SimulationContext::emit_with_default_delay).
+    ///
+    /// This profile takes precedence over the group and default profiles.
+    /// See [`emit_with_default_delay`](SimulationContext::emit_with_default_delay) for examples.
+    pub fn set_event_delay<T: EventData>(&mut self, profile: DelayProfile) {
+        self.sim_state
+            .borrow_mut()
+            .delays_mut()
+            .set_event_type(std::any::TypeId::of::<T>(), profile);
+    }
+
+    /// Assigns the component with specified name to the group used for selecting delay profiles,
+    /// see [`set_group_delay`](Self::set_group_delay), and for running the group until it is idle,
+    /// see [`step_until_group_idle`](Self::step_until_group_idle).
+    ///
+    /// Each component belongs to at most one group, the previous group of the component is replaced.
+    ///
+    /// Panics if component with such name does not exist.
+    pub fn set_component_group<S>(&mut self, name: S, group: &str)
+    where
+        S: AsRef<str>,
+    {
+        let id = self.lookup_id(name.as_ref());
+        self.sim_state.borrow_mut().delays_mut().set_component_group(id, group);
+    }
+
+    /// Sets the delay profile used for events emitted via
+    /// [`emit_with_default_delay`](SimulationContext::emit_with_default_delay) from components of `src_group`
+    /// to components of `dst_group`.
+    ///
+    /// This profile takes precedence over the default profile.
+    /// See [`emit_with_default_delay`](SimulationContext::emit_with_default_delay) for examples.
+    pub fn set_group_delay(&mut self, src_group: &str, dst_group: &str, profile: DelayProfile) {
+        self.sim_state
+            .borrow_mut()
+            .delays_mut()
+            .set_group_pair(src_group, dst_group, profile);
+    }
+
+    /// Registers a router which can redirect emitted events to other destinations and add delay to them.
+    ///
+    /// The router is called for each emitted event and returns the [`Route`] of the event or `None` if the event
+    /// should not be routed. Routers are tried in the order of their registration and the first returned route is
+    /// applied. The event delivered to the new destination keeps its original source, so interposed components
+    /// should forward events using [`emit`](SimulationContext::emit) and not be matched by the router themselves to
+    /// avoid routing loops. Ordered events keep their order when delayed by routers.
+    ///
+    /// Panics if the route delay is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::routing::Route;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Message {}
+    ///
+    /// struct Inbox {
+    ///     received: Vec<f64>,
+    /// }
+    ///
+    /// impl EventHandler for Inbox {
+    ///     fn on(&mut self, event: Event) {
+    ///         self.received.push(event.time);
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server = Rc::new(RefCell::new(Inbox { received: Vec::new() }));
+    /// let server_id = sim.add_handler("server", server.clone());
+    ///
+    /// // network latency between all components
+    /// sim.add_router(|event| Some(Route::to(event.dst).with_delay(0.5)));
+    /// client_ctx.emit(Message {}, server_id, 1.);
+    /// sim.step_until_no_events();
+    /// assert_eq!(server.borrow().received, vec![1.5]);
+    ///
+    /// // the first matching router is applied
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_id = sim.add_handler("server", server.clone());
+    /// let replica = Rc::new(RefCell::new(Inbox { received: Vec::new() }));
+    /// let replica_id = sim.add_handler("replica", replica.clone());
+    /// sim.add_router(move |event| (event.dst == server_id).then(|| Route::to(replica_id)));
+    /// sim.add_router(|event| Some(Route::to(event.dst).with_delay(0.5)));
+    /// client_ctx.emit(Message {}, server_id, 1.);
+    /// sim.step_until_no_events();
+    /// assert_eq!(replica.borrow().received, vec![1.]);
+    /// ```
+    pub fn add_router<F>(&mut self, router: F)
+    where
+        F: Fn(&Event) -> Option<Route> + 'static,
+    {
+        self.sim_state.borrow_mut().add_router(Rc::new(router));
+    }
+
+    /// Registers the multi-resolution component with the specified name, which swaps the specified detailed and
+    /// coarse models at runtime, see [`resolution`](crate::resolution) module.
+    ///
+    /// The models must be registered before calling this method. The events destined to the models of inactive
+    /// resolution are delivered to the corresponding models of the initial resolution.
+    ///
+    /// Panics if the component with such name already exists, the models do not exist or belong to another
+    /// multi-resolution component.
+    pub fn add_multi_resolution(&mut self, name: &str, config: MultiResolution) {
+        assert!(
+            self.multi_resolution.iter().all(|entry| entry.name != name),
+            "Multi-resolution component {} already exists",
+            name
+        );
+        let mut models: Vec<(Id, Id)> = Vec::new();
+        for (detailed, coarse) in config.models.iter() {
+            let ids = (self.lookup_id(detailed), self.lookup_id(coarse));
+            for (id, model) in [(ids.0, detailed), (ids.1, coarse)] {
+                assert!(
+                    !models.iter().any(|(d, c)| *d == id || *c == id)
+                        && !self.multi_resolution.iter().any(|entry| entry.contains(id)),
+                    "Model {} already belongs to multi-resolution component",
+                    model
+                );
+            }
+            models.push(ids);
+        }
+        let entry = MultiResolutionEntry::new(name, config, models);
+        let mut state = self.sim_state.borrow_mut();
+        for (detailed, coarse) in entry.models.iter() {
+            match entry.active {
+                Resolution::Detailed => state.set_redirect(*coarse, *detailed),
+                Resolution::Coarse => state.set_redirect(*detailed, *coarse),
+            }
+        }
+        drop(state);
+        self.multi_resolution.push(entry);
+    }
+
+    /// Returns the active resolution of multi-resolution component with the specified name.
+    ///
+    /// Panics if the component with such name does not exist.
+    pub fn resolution(&self, name: &str) -> Resolution {
+        self.multi_resolution_entry(name).active
+    }
+
+    /// Swaps the models of multi-resolution component with the specified name to the specified resolution.
+    ///
+    /// The pending events emitted between the models of the deactivated resolution are canceled, and the other
+    /// pending events destined to these models are delivered to the corresponding activated models. Then the state
+    /// translation hooks of the component are called. Does nothing if the resolution is already active.
+    ///
+    /// Panics if the component with such name does not exist.
+    ///
+    /// See [`resolution`](crate::resolution) module for examples.
+    pub fn set_resolution(&mut self, name: &str, resolution: Resolution) {
+        let index = self
+            .multi_resolution
+            .iter()
+            .position(|entry| entry.name == name)
+            .unwrap_or_else(|| panic!("Multi-resolution component {} does not exist", name));
+        let entry = &self.multi_resolution[index];
+        if entry.active == resolution {
+            return;
+        }
+        let deactivated = entry.model_ids(entry.active);
+        let activated = entry.model_ids(resolution);
+        {
+            let mut state = self.sim_state.borrow_mut();
+            state.cancel_events(|e| deactivated.contains(&e.src) && deactivated.contains(&e.dst));
+            for (from, to) in deactivated.iter().zip(activated.iter()) {
+                state.remove_redirect(*to);
+                state.set_redirect(*from, *to);
+            }
+        }
+        self.multi_resolution[index].active = resolution;
+        self.multi_resolution[index].translate(resolution);
+        debug!(
+            target: "simulation",
+            "[{:.3} {} simulation] Swapped resolution: {}",
+            self.time(),
+            crate::log::get_colored("DEBUG", colored::Color::Blue),
+            json!({"name": name, "resolution": format!("{:?}", resolution)})
+        );
+    }
+
+    fn multi_resolution_entry(&self, name: &str) -> &MultiResolutionEntry {
+        self.multi_resolution
+            .iter()
+            .find(|entry| entry.name == name)
+            .unwrap_or_else(|| panic!("Multi-resolution component {} does not exist", name))
+    }
+
+    /// Registers the input stream of events from external source, returns the identifier of the input component.
+    ///
+    /// The input events are injected into the simulation with the input component as their source, following
+    /// the deterministic merging rules described in [`input`](crate::input) module. The input is read lazily while
+    /// the simulation is running, so it can block, e.g. while waiting for the next event from a channel.
+    ///
+    /// Panics if the events or watermarks of the input are not ordered by time, or the input event time is earlier
+    /// than the current simulation time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::input::{InputEvent, InputItem};
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Request {
+    ///     id: u32,
+    /// }
+    ///
+    /// struct Server {
+    ///     log: Vec<(f64, u32)>,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         let request = event.data.downcast_ref::<Request>().unwrap();
+    ///         self.log.push((event.time, request.id));
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let server = Rc::new(RefCell::new(Server { log: Vec::new() }));
+    /// let server_id = sim.add_handler("server", server.clone());
+    ///
+    /// // events from a trace file
+    /// let trace = vec![(1., 1), (3., 3)];
+    /// sim.add_input(
+    ///     "trace",
+    ///     trace
+    ///         .into_iter()
+    ///         .map(move |(time, id)| InputItem::Event(InputEvent::new(time, server_id, Request { id }))),
+    /// );
+    ///
+    /// // events from another thread via bounded channel, where `None` request means watermark
+    /// let (sender, receiver) = mpsc::sync_channel(1);
+    /// let producer = thread::spawn(move || {
+    ///     sender.send((2., Some(2))).unwrap();
+    ///     sender.send((10., None)).unwrap();
+    ///     sender.send((10., Some(4))).unwrap();
+    /// });
+    /// sim.add_input(
+    ///     "producer",
+    ///     receiver.into_iter().map(move |(time, id)| match id {
+    ///         Some(id) => InputItem::Event(InputEvent::new(time, server_id, Request { id })),
+    ///         None => InputItem::Watermark(time),
+    ///     }),
+    /// );
+    ///
+    /// // the simulation can proceed up to the watermark without waiting for the next event
+    /// sim.step_until_time(5.);
+    /// assert_eq!(server.borrow().log, vec![(1., 1), (2., 2), (3., 3)]);
+    ///
+    /// sim.step_until_no_events();
+    /// assert_eq!(server.borrow().log.last(), Some(&(10., 4)));
+    /// producer.join().unwrap();
+    /// ```
+    pub fn add_input<S, I>(&mut self, name: S, items: I) -> Id
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = InputItem>,
+        I::IntoIter: 'static,
+    {
+        let id = self.register(name.as_ref());
+        let input = Input::new(id, Box::new(IteratorSource(items.into_iter())), self.time());
+        self.inputs.borrow_mut().add(input);
+        self.enable_step_hooks();
+        id
+    }
+
+    /// Registers the bridge to external simulator as a component with the specified name, returns its identifier.
+    ///
+    /// The events destined to the component are forwarded to the external simulator, and the events produced by the
+    /// external simulator are injected into the simulation as from an [input](Self::add_input) with the component
+    /// as their source. See [`cosim`](crate::cosim) module for the protocol and examples.
+    ///
+    /// The failure of the external simulator pauses the current run, see
+    /// [`take_peer_error`](Self::take_peer_error).
+    ///
+    /// Panics if the component with such name already has a handler.
+    pub fn add_cosim_bridge<S>(&mut self, name: S, bridge: CosimBridge) -> Id
+    where
+        S: AsRef<str>,
+    {
+        let ctx = self.create_context(name.as_ref());
+        let (forwarder, input) = bridge.split(ctx, self.pending_peer_errors.clone());
+        let id = self.add_handler(name, Rc::new(RefCell::new(forwarder)));
+        let input = Input::new(id, Box::new(input), self.time());
+        self.inputs.borrow_mut().add(input);
+        self.enable_step_hooks();
+        id
+    }
+
+    /// Returns the failure of co-simulation peer which paused the run, if any, and clears it.
+    ///
+    /// After the failure the bridge to the peer no longer exchanges messages with it, so the resumed run proceeds
+    /// without the events of the peer. See [`cosim`](crate::cosim) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Cursor;
+    ///
+    /// use simcore::cosim::CosimBridge;
+    /// use simcore::run::TerminationReason;
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// // external simulator closes the connection without granting the time
+    /// let bridge = CosimBridge::new(Cursor::new(""), Vec::new());
+    /// sim.add_cosim_bridge("plant", bridge);
+    ///
+    /// let result = sim.run();
+    /// let error = "Co-simulation peer of plant failed: connection is closed".to_owned();
+    /// assert_eq!(result.termination, TerminationReason::PeerError(error.clone()));
+    /// assert_eq!(sim.take_peer_error(), Some(error));
+    /// assert_eq!(sim.take_peer_error(), None);
+    /// ```
+    pub fn take_peer_error(&mut self) -> Option<String> {
+        self.peer_error.borrow_mut().take()
+    }
+
+    /// Registers the replay of recorded event trace as an input stream, returns the identifier of the input
+    /// component.
+    ///
+    /// The destinations of replayed events are resolved by their names, so they must be registered before calling
+    /// this method. See [`replay`](crate::replay) module for details.
+    ///
+    /// Panics if the trace file cannot be read, a replayed event has unregistered type or no payload,
+    /// or its destination does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// use simcore::replay::{TraceReplay, TraceReplayConfig};
+    /// use simcore::trace_file::TraceFileConfig;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// pub struct Request {
+    ///     id: u32,
+    /// }
+    ///
+    /// struct Server {
+    ///     log: Vec<(f64, u32)>,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         let request = event.data.downcast_ref::<Request>().unwrap();
+    ///         self.log.push((event.time, request.id));
+    ///     }
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join(format!("simcore-replay-doc-{}.jsonl", std::process::id()));
+    ///
+    /// // record the requests of client
+    /// let mut sim = Simulation::new(123);
+    /// sim.add_handler("server", Rc::new(RefCell::new(Server { log: Vec::new() })));
+    /// let client = sim.create_context("client");
+    /// sim.enable_trace_file(TraceFileConfig::new(&path));
+    /// client.emit(Request { id: 1 }, sim.lookup_id("server"), 1.5);
+    /// client.emit(Request { id: 2 }, sim.lookup_id("server"), 4.);
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// // replay them without client
+    /// let mut sim = Simulation::new(123);
+    /// let server = Rc::new(RefCell::new(Server { log: Vec::new() }));
+    /// sim.add_handler("server", server.clone());
+    /// let mut replay = TraceReplay::new(TraceReplayConfig::new(&path));
+    /// replay.register_event::<Request>();
+    /// sim.add_trace_replay("replay", replay);
+    /// sim.step_until_no_events();
+    /// assert_eq!(server.borrow().log, vec![(1.5, 1), (4., 2)]);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn add_trace_replay<S>(&mut self, name: S, replay: TraceReplay) -> Id
+    where
+        S: AsRef<str>,
+    {
+        let items = replay.read(|name| {
+            self.sim_state
+                .borrow()
+                .try_lookup_id(name)
+                .unwrap_or_else(|| panic!("Destination component {} of replayed event does not exist", name))
+        });
+        self.add_input(name, items)
+    }
+
+    /// Enables batching of events destined for the component with specified name.
+    ///
+    /// When batching is enabled, the events with equal time destined for the component which are processed
+    /// consecutively are delivered via a single call of [`EventHandler::on_batch`]. This reduces the event
+    /// dispatching overhead for components receiving many events at the same time. The order of event processing
+    /// is not changed, and the batch is processed in a single simulation step.
+    ///
+    /// Note that the events in the batch are removed from the pending event set before the batch is processed,
+    /// so these events cannot be cancelled while processing the batch.
+    ///
+    /// Batching is supported only for handlers registered via [`add_handler`](Self::add_handler).
+    /// In async mode, the events awaited by asynchronous activities are not included in batches.
+    ///
+    /// Panics if component with such name does not exist.
+    ///
+    /// See [`EventHandler::on_batch`] for examples.
+    pub fn enable_event_batching<S>(&mut self, name: S)
+    where
+        S: AsRef<str>,
+    {
+        let id = self.lookup_id(name.as_ref());
+        self.add_component_slot(id);
+        self.batching_enabled[id as usize] = true;
+    }
+
+    /// Enables coalescing of event bursts destined for the component with specified name.
+    ///
+    /// The events of the same type which occur within `window` after the first event of the burst are merged
+    /// with it and delivered together via [`EventHandler::on_batch`] at the time of the first event,
+    /// see [`coalescing`](crate::coalescing) module for details.
+    ///
+    /// Panics if component with such name does not exist or the window is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    ///
+    /// use simcore::{cast, Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Reading {
+    ///     value: f64,
+    /// }
+    ///
+    /// struct Monitor {
+    ///     deliveries: Vec<(f64, Vec<f64>)>,
+    /// }
+    ///
+    /// impl EventHandler for Monitor {
+    ///     fn on(&mut self, event: Event) {
+    ///         self.on_batch(vec![event]);
+    ///     }
+    ///
+    ///     fn on_batch(&mut self, events: Vec<Event>) {
+    ///         let time = events[0].time;
+    ///         let mut values = Vec::new();
+    ///         for event in events {
+    ///             cast!(match event.data {
+    ///                 Reading { value } => {
+    ///                     values.push(value);
+    ///                 }
+    ///             })
+    ///         }
+    ///         self.deliveries.push((time, values));
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let monitor = Rc::new(RefCell::new(Monitor { deliveries: Vec::new() }));
+    /// let monitor_id = sim.add_handler("monitor", monitor.clone());
+    /// sim.enable_event_coalescing("monitor", 0.01);
+    ///
+    /// let ctx = sim.create_context("sensor");
+    /// for (time, value) in [(1., 1.), (1.002, 2.), (1.008, 3.), (1.5, 4.), (1.505, 5.)] {
+    ///     ctx.emit(Reading { value }, monitor_id, time);
+    /// }
+    /// sim.step_until_no_events();
+    ///
+    /// assert_eq!(monitor.borrow().deliveries, vec![(1., vec![1., 2., 3.]), (1.5, vec![4., 5.])]);
+    /// // the bursts are delivered at the time of their first events
+    /// assert_eq!(sim.time(), 1.5);
+    /// ```
+    pub fn enable_event_coalescing<S>(&mut self, name: S, window: f64)
+    where
+        S: AsRef<str>,
+    {
+        let id = self.lookup_id(name.as_ref());
+        self.sim_state.borrow_mut().set_coalescing_window(id, window);
+    }
+
+    /// Returns the current simulation time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
+    /// sim.step();
+    /// assert_eq!(sim.time(), 1.2);
+    /// ```
+    pub fn time(&self) -> f64 {
+        self.sim_state.borrow().time()
+    }
+
+    /// Returns the number of the current tick in the discrete time mode.
+    ///
+    /// Panics if the time tick is not set.
+    /// See [`with_integer_ticks`](Self::with_integer_ticks) for examples.
+    pub fn current_tick(&self) -> u64 {
+        let state = self.sim_state.borrow();
+        let time_tick = state.time_tick().expect("Time tick is not set");
+        time_tick.tick_number(state.time())
+    }
+
+    /// Returns the current simulation time as [`SimTime`], see [`time`](crate::time) module.
+    pub fn now(&self) -> SimTime {
+        SimTime::from_secs(self.time())
+    }
+
+    /// Performs a single step through the simulation.
+    ///
+    /// Takes the next event from the queue, advances the simulation time to event time and tries to process it
+    /// by invoking the [`EventHandler::on`] method of the corresponding event handler.
+    /// If there is no handler registered for component with Id `event.dst`, logs the undelivered event and discards it.
+    ///
+    /// Returns `true` if some pending event was found (no matter was it properly processed or not) and `false`
+    /// otherwise. The latter means that there are no pending events, so no progress can be made.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
+    /// let mut status = sim.step();
+    /// assert!(status);
+    /// assert_eq!(sim.time(), 1.2);
+    /// status = sim.step();
+    /// assert!(!status);
+    /// ```
+    pub fn step(&self) -> bool {
+        self.start_components();
+        if !self.metadata_logged.get() {
+            self.log_run_metadata();
+        }
+        if self.step_hooks.get() {
+            // reports the events at the current time if the pending events at this time were canceled between steps
+            self.report_time_advance();
+            self.check_warmup(None);
+            self.save_auto_checkpoint();
+        }
+        let result = self.step_inner();
+        self.dispatched_component.set(None);
+        // the hooks could be enabled by the processed event
+        if self.step_hooks.get() {
+            self.report_time_advance();
+            self.check_limits();
+            self.check_contracts();
+            self.check_peer_errors();
+            self.check_divergence(result);
+        }
+        result
+    }
+
+    // Marks that an optional subsystem checked on each step is enabled, called by the subsystems when they are enabled.
+    fn enable_step_hooks(&self) {
+        self.step_hooks.set(true);
+    }
+
+    fn check_limits(&self) {
+        let mut limits = self.limits.borrow_mut();
+        let Some(guard) = limits.as_ref() else {
+            return;
+        };
+        let (time, pending_events) = {
+            let state = self.sim_state.borrow();
+            (state.time(), state.queued_event_count())
+        };
+        let Some(limit) = guard.check(time, pending_events) else {
+            return;
+        };
+        let state = self.sim_state.borrow();
+        let violation = guard.violation(limit, time, pending_events, &state.dump_events(), |id| {
+            state.component_name(id).to_string()
+        });
+        match guard.action() {
+            LimitAction::Abort => panic!("{}", violation),
+            LimitAction::Pause => {
+                warn!(
+                    target: "simulation",
+                    "[{:.3} {}  simulation] {}",
+                    time,
+                    crate::log::get_colored("WARN", colored::Color::Yellow),
+                    violation
+                );
+                // the limits are disarmed to allow resuming the run
+                limits.take();
+                *self.limit_violation.borrow_mut() = Some(violation);
+                self.pause_requested.set(true);
+            }
+        }
+    }
+
+    fn check_divergence(&self, has_events: bool) {
+        let mut guard = self.divergence_guard.borrow_mut();
+        let Some(guard_ref) = guard.as_mut() else {
+            return;
+        };
+        if !has_events {
+            guard_ref.on_drained();
+        }
+        if !guard_ref.has_mismatch() {
+            return;
+        }
+        let mut options = QueueDumpOptions::new();
+        options.limit = Some(guard_ref.max_pending_events());
+        let mut pending_events = Vec::new();
+        self.dump_queue(&mut pending_events, &options).unwrap();
+        let divergence = guard_ref
+            .take_divergence(self.time(), String::from_utf8(pending_events).unwrap())
+            .unwrap();
+        match guard_ref.action() {
+            LimitAction::Abort => panic!("{}", divergence),
+            LimitAction::Pause => {
+                warn!(
+                    target: "simulation",
+                    "[{:.3} {}  simulation] {}",
+                    divergence.time,
+                    crate::log::get_colored("WARN", colored::Color::Yellow),
+                    divergence
+                );
+                // the guard is disarmed to allow resuming the run
+                guard.take();
+                *self.divergence.borrow_mut() = Some(divergence);
+                self.pause_requested.set(true);
+            }
+        }
+    }
+
+    fn check_contracts(&self) {
+        let violations = self.sim_state.borrow_mut().take_contract_violations();
+        if violations.is_empty() {
+            return;
+        }
+        for violation in violations.iter() {
+            warn!(
+                target: "simulation",
+                "[{:.3} {}  simulation] {}",
+                violation.time,
+                crate::log::get_colored("WARN", colored::Color::Yellow),
+                violation
+            );
+        }
+        self.contract_violations.borrow_mut().extend(violations);
+        self.pause_requested.set(true);
+    }
+
+    fn check_peer_errors(&self) {
+        let errors = std::mem::take(&mut *self.pending_peer_errors.borrow_mut());
+        if errors.is_empty() {
+            return;
+        }
+        for error in errors {
+            warn!(
+                target: "simulation",
+                "[{:.3} {}  simulation] {}",
+                self.time(),
+                crate::log::get_colored("WARN", colored::Color::Yellow),
+                error
+            );
+            *self.peer_error.borrow_mut() = Some(error);
+        }
+        self.pause_requested.set(true);
+    }
+
+    fn log_run_metadata(&self) {
+        self.metadata_logged.set(true);
+        let state = self.sim_state.borrow();
+        info!(
+            target: "simulation",
+            "[{:.3} {}  simulation] Run metadata: {}",
+            state.time(),
+            crate::log::get_colored("INFO", colored::Color::Green),
+            state.run_metadata().to_json()
+        );
+    }
+
+    async_mode_disabled!(
+        fn step_inner(&self) -> bool {
+            if self.step_hooks.get() {
+                self.inject_inputs(f64::INFINITY);
+                self.advance_continuous_models(self.next_activity_time());
+                if self.check_breakpoints() {
+                    return true;
+                }
+            }
+            let event_opt = self.sim_state.borrow_mut().next_event();
+            match event_opt {
+                Some(event) => {
+                    if !self.step_hooks.get() {
+                        self.deliver_event_via_handler(event);
+                        return true;
+                    }
+                    self.notify_before_step(&event);
+                    let (event_id, dst) = (event.id, event.dst);
+                    self.deliver_event_via_handler(event);
+                    self.check_watchpoints(event_id, dst);
+                    self.notify_after_step(event_id);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn deliver_event_via_handler(&self, event: Event) {
+            if let Some(handler_opt) = self.handlers.get(event.dst as usize) {
+                self.on_event_dispatched(&event);
+                let coalesced = self.sim_state.borrow_mut().take_coalesced_events(&event);
+                if let Some(handler) = handler_opt {
+                    let event_id = event.id;
+                    if self.is_batching_enabled(event.dst) || !coalesced.is_empty() {
+                        let batch = self.collect_batch(event, coalesced);
+                        self.with_processed_event(event_id, || handler.borrow_mut().on_batch(batch));
+                    } else {
+                        self.with_processed_event(event_id, || handler.borrow_mut().on(event));
+                    }
+                } else {
+                    self.log_undelivered_events(event, coalesced);
+                }
+            } else {
+                log_undelivered_event(event);
+            }
+        }
+    );
+
+    async_mode_enabled!(
+        fn step_inner(&self) -> bool {
+            if self.process_task() {
+                return true;
+            }
+
+            if self.step_hooks.get() {
+                self.inject_inputs(f64::INFINITY);
+                self.advance_continuous_models(self.next_activity_time());
+            }
+
+            let has_timer = self.sim_state.borrow_mut().peek_timer().is_some();
+            let has_event = self.sim_state.borrow_mut().peek_event().is_some();
+            if !has_timer && !has_event {
+                if let Some(report) = self.detect_deadlock() {
+                    warn!(
+                        target: "simulation",
+                        "[{:.3} {}  simulation] {}",
+                        self.time(),
+                        crate::log::get_colored("WARN", colored::Color::Yellow),
+                        report
+                    );
+                }
+                return false;
+            }
+            if !has_timer {
+                self.process_event();
+                return true;
+            }
+            if !has_event {
+                self.process_timer();
+                return true;
+            }
+
+            let next_timer_time = self.sim_state.borrow_mut().peek_timer().unwrap().time;
+            let next_event_time = self.sim_state.borrow_mut().peek_event().unwrap().time;
+            if next_event_time <= next_timer_time {
+                self.process_event();
+            } else {
+                self.process_timer();
+            }
+
+            true
+        }
+
+        fn process_event(&self) {
+            if self.check_breakpoints() {
+                return;
+            }
+            let event = self.sim_state.borrow_mut().next_event().unwrap();
+            self.notify_before_step(&event);
+            let (event_id, dst) = (event.id, event.dst);
+            let mailbox = self.sim_state.borrow_mut().mailbox_for(&event);
+            if let Some(mailbox) = mailbox {
+                self.on_event_dispatched(&event);
+                self.sim_state.borrow_mut().release_coalesced_events(&event);
+                mailbox.push(event);
+                self.check_watchpoints(event_id, dst);
+                self.notify_after_step(event_id);
+                return;
+            }
+            let event_key = self
+                .sim_state
+                .borrow()
+                .get_key_getter(event.data.type_id(), event.dst)
+                .map(|getter| getter(event.data.as_ref()));
+            if self.delivers_to_waiter(&event, event_key) {
+                self.on_event_dispatched(&event);
+                self.sim_state.borrow_mut().release_coalesced_events(&event);
+                self.sim_state.borrow_mut().complete_event_promise(event, event_key);
+                self.process_task();
+            } else {
+                self.deliver_event_via_handler(event);
+            }
+            self.check_watchpoints(event_id, dst);
+            self.notify_after_step(event_id);
+        }
+
+        // Returns true if the event should complete the promise of awaiting task instead of delivering it to handler.
+        fn delivers_to_waiter(&self, event: &Event, event_key: Option<EventKey>) -> bool {
+            let state = self.sim_state.borrow();
+            if !state.has_event_promise_for(event, event_key) {
+                return false;
+            }
+            if !matches!(self.handlers.get(event.dst as usize), Some(Some(_))) {
+                return true;
+            }
+            match state.dispatch_precedence(event.dst) {
+                DispatchPrecedence::WaitersFirst => true,
+                DispatchPrecedence::HandlerFirst => false,
+                DispatchPrecedence::Error => panic!(
+                    "Event {} of type {} for component {} is awaited by async task and can be processed by handler",
+                    event.id,
+                    serde_type_name::type_name(&event.data).unwrap(),
+                    state.lookup_name(event.dst)
+                ),
+            }
+        }
+
+        fn process_task(&self) -> bool {
+            self.sim_state.borrow_mut().on_task_resumed();
+            let processed = self.executor.process_task();
+            self.sim_state.borrow_mut().set_processed_event(None);
+            processed
+        }
+
+        fn process_timer(&self) {
+            let next_timer = self.sim_state.borrow_mut().next_timer().unwrap();
+            self.sim_state.borrow_mut().on_timer_fired();
+            next_timer.complete();
+            // drop timer to release the pointer to the state
+            drop(next_timer);
+            self.process_task();
+        }
+
+        fn deliver_event_via_handler(&self, event: Event) {
+            if let Some(handler_opt) = self.handlers.get(event.dst as usize) {
+                self.on_event_dispatched(&event);
+                let coalesced = self.sim_state.borrow_mut().take_coalesced_events(&event);
+                if let Some(handler) = handler_opt {
+                    let event_id = event.id;
+                    match handler {
+                        EventHandlerImpl::Mutable(handler) => {
+                            if self.is_batching_enabled(event.dst) || !coalesced.is_empty() {
+                                let batch = self.collect_batch(event, coalesced);
+                                self.with_processed_event(event_id, || handler.borrow_mut().on_batch(batch));
+                            } else {
+                                self.with_processed_event(event_id, || handler.borrow_mut().on(event));
+                            }
+                        }
+                        EventHandlerImpl::Static(handler) => {
+                            self.with_processed_event(event_id, || handler.clone().on(event));
+                            for event in coalesced {
+                                self.on_event_dispatched(&event);
+                                self.with_processed_event(event.id, || handler.clone().on(event));
+                            }
+                        }
+                    }
+                } else {
+                    self.log_undelivered_events(event, coalesced);
+                }
+            } else {
+                log_undelivered_event(event);
+            }
+        }
+    );
+
+    async_mode_disabled!(
+        pub(crate) fn next_activity_time(&self) -> Option<f64> {
+            self.sim_state.borrow_mut().peek_event().map(|e| e.time)
+        }
+
+        pub(crate) fn run_ready_tasks(&self) {}
+    );
+
+    async_mode_enabled!(
+        // Runs the tasks which are ready to make progress without advancing the simulation time.
+        pub(crate) fn run_ready_tasks(&self) {
+            while self.process_task() {}
+        }
+
+        pub(crate) fn next_activity_time(&self) -> Option<f64> {
+            let next_timer_time = self.sim_state.borrow_mut().peek_timer().map(|t| t.time);
+            let next_event_time = self.sim_state.borrow_mut().peek_event().map(|e| e.time);
+            match (next_timer_time, next_event_time) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+    );
+
+    // Injects the input events occurring not later than the next pending activity and the specified time limit.
+    fn inject_inputs(&self, limit: f64) {
+        if self.inputs.borrow().is_empty() {
+            return;
+        }
+        loop {
+            let horizon = self.next_activity_time().map_or(limit, |time| time.min(limit));
+            let next = self.inputs.borrow_mut().next_event(horizon);
+            let Some((src, event)) = next else {
+                break;
+            };
+            let mut state = self.sim_state.borrow_mut();
+            let now = state.time();
+            assert!(
+                event.time >= now,
+                "Input event time {} is earlier than current simulation time {}",
+                event.time,
+                now
+            );
+            state.add_boxed_event(event.data, src, event.dst, event.time - now);
+        }
+    }
+
+    // Advances continuous models up to the time of the next event, but not later than the specified time.
+    fn advance_continuous_models_until(&self, time: f64) {
+        if self.continuous_models.is_empty() {
+            return;
+        }
+        let next_time = self.next_activity_time().map_or(time, |next_time| next_time.min(time));
+        self.advance_continuous_models(Some(next_time));
+    }
+
+    // Advances continuous models up to the time of the next event or the earliest threshold crossing,
+    // in the latter case emits the crossing event.
+    fn advance_continuous_models(&self, next_time: Option<f64>) {
+        if self.continuous_models.is_empty() {
+            return;
+        }
+        let now = self.sim_state.borrow().time();
+        let target = next_time.unwrap_or(now + self.continuous_lookahead);
+        let advances: Vec<_> = self.continuous_models.iter().map(|m| m.integrate(target)).collect();
+        let crossing_time = advances
+            .iter()
+            .filter(|advance| advance.crossing.is_some())
+            .map(|advance| advance.time())
+            .min_by(|a, b| a.total_cmp(b));
+        let (target, advances) = match crossing_time {
+            // the models are advanced only to the earliest crossing
+            Some(crossing_time) => {
+                let advances = self
+                    .continuous_models
+                    .iter()
+                    .zip(advances)
+                    .map(|(model, advance)| {
+                        if advance.crossing.is_some() && advance.time() == crossing_time {
+                            advance
+                        } else {
+                            model.integrate(crossing_time)
+                        }
+                    })
+                    .collect();
+                (crossing_time, advances)
+            }
+            // the models are not advanced ahead of time without pending events
+            None if next_time.is_none() => return,
+            None => (target, advances),
+        };
+        for (model, mut advance) in self.continuous_models.iter().zip(advances) {
+            if let Some(crossing) = advance.crossing.take() {
+                if advance.time() == target {
+                    self.sim_state
+                        .borrow_mut()
+                        .add_event(crossing, model.id, model.id, target - now);
+                }
+            }
+            model.commit(advance);
+        }
+    }
+
+    // Invokes the handler processing the specified event, the events emitted by the handler are attributed to it.
+    fn with_processed_event(&self, event_id: EventId, invoke: impl FnOnce()) {
+        // the processed event is tracked only by the memory trace, which enables the step hooks
+        if !self.step_hooks.get() {
+            invoke();
+            return;
+        }
+        self.sim_state.borrow_mut().set_processed_event(Some(event_id));
+        invoke();
+        self.sim_state.borrow_mut().set_processed_event(None);
+    }
+
+    // Collects the batch of events delivered together with the specified (already logged) event, i.e. the events
+    // coalesced with it and, if batching is enabled, the events with the same time and destination.
+    fn collect_batch(&self, event: Event, coalesced: Vec<Event>) -> Vec<Event> {
+        let (time, dst) = (event.time, event.dst);
+        let mut batch = vec![event];
+        self.append_coalesced_events(&mut batch, coalesced);
+        if !self.is_batching_enabled(dst) {
+            return batch;
+        }
+        loop {
+            let next = self.sim_state.borrow_mut().next_batched_event(time, dst);
+            match next {
+                Some(next) => {
+                    self.on_event_dispatched(&next);
+                    let coalesced = self.sim_state.borrow_mut().take_coalesced_events(&next);
+                    batch.push(next);
+                    self.append_coalesced_events(&mut batch, coalesced);
+                }
+                None => break,
+            }
+        }
+        batch
+    }
+
+    fn append_coalesced_events(&self, batch: &mut Vec<Event>, coalesced: Vec<Event>) {
+        for event in coalesced {
+            self.on_event_dispatched(&event);
+            batch.push(event);
+        }
+    }
+
+    fn log_undelivered_events(&self, event: Event, coalesced: Vec<Event>) {
+        log_undelivered_event(event);
+        for event in coalesced {
+            self.on_event_dispatched(&event);
+            log_undelivered_event(event);
+        }
+    }
+
+    // Returns true if the next event hits a breakpoint, the simulation is paused before processing it.
+    fn check_breakpoints(&self) -> bool {
+        let mut breakpoints = self.breakpoints.borrow_mut();
+        if breakpoints.is_empty() {
+            return false;
+        }
+        let mut state = self.sim_state.borrow_mut();
+        let Some(event) = state.peek_event() else {
+            return false;
+        };
+        if !breakpoints.check(event) {
+            return false;
+        }
+        self.pause_requested.set(true);
+        true
+    }
+
+    fn check_watchpoints(&self, event_id: EventId, dst: Id) {
+        let mut watchpoints = self.watchpoints.borrow_mut();
+        if watchpoints.is_empty() {
+            return;
+        }
+        let time = self.time();
+        for watchpoint in watchpoints.iter_mut().filter(|w| w.component == dst) {
+            if let Some(hit) = watchpoint.check(event_id, time) {
+                self.watchpoint_hits.borrow_mut().push(hit);
+                self.pause_requested.set(true);
+            }
+        }
+    }
+
+    fn notify_before_step(&self, event: &Event) {
+        for observer in self.step_observers.iter() {
+            observer.borrow_mut().before_step(event);
+        }
+    }
+
+    fn notify_after_step(&self, event_id: EventId) {
+        if self.step_observers.is_empty() {
+            return;
+        }
+        let (time, event_count) = {
+            let state = self.sim_state.borrow();
+            (state.time(), state.event_count())
+        };
+        let (last_time, last_event_count) = self.last_observed_step.replace((time, event_count));
+        let delta = StepDelta {
+            event_id,
+            time,
+            time_advance: time - last_time,
+            emitted_events: event_count - last_event_count,
+        };
+        for observer in self.step_observers.iter() {
+            observer.borrow_mut().after_step(&delta);
+        }
+    }
+
+    // Reports the advance to the current time to listeners if there are no more pending activities at this time.
+    fn report_time_advance(&self) {
+        if self.time_advances.borrow().is_empty() {
+            return;
+        }
+        let time = self.time();
+        if self.next_activity_time().is_none_or(|next_time| next_time > time) {
+            self.time_advances.borrow_mut().report(time);
+        }
+    }
+
+    // Updates the per-event bookkeeping and logs the event before it is passed to its handler.
+    fn on_event_dispatched(&self, event: &Event) {
+        self.processed_events.set(self.processed_events.get() + 1);
+        self.dispatched_component.set(Some(event.dst));
+        let mut state = self.sim_state.borrow_mut();
+        if self.step_hooks.get() {
+            self.on_event_dispatched_hooks(event, &state);
+        }
+        state.on_event_dispatched(event);
+        if log_enabled!(Trace) && state.allow_log_record(event.dst, event.time) {
+            state.format_event_log_record(event);
+            let dst_name = state.component_name(event.dst);
+            trace!(
+                target: dst_name,
+                "[{:.3} {} {}] {}",
+                event.time,
+                crate::log::get_colored("EVENT", colored::Color::BrightBlack),
+                dst_name,
+                state.event_log_record()
+            );
+        }
+    }
+
+    // Notifies the time advance listeners, stop condition, limits and divergence guard about the dispatched event.
+    fn on_event_dispatched_hooks(&self, event: &Event, state: &SimulationState) {
+        self.time_advances.borrow_mut().on_event(event);
+        if let Some(condition) = self.stop_condition.borrow_mut().as_mut() {
+            condition.on_event(event);
+        }
+        if let Some(guard) = self.limits.borrow_mut().as_mut() {
+            guard.on_event(event);
+        }
+        if let Some(guard) = self.divergence_guard.borrow_mut().as_mut() {
+            guard.on_event(TraceFileRecord {
+                kind: TraceEventKind::Processed,
+                time: event.time,
+                id: event.id,
+                event_time: Some(event.time),
+                src: state.component_name(event.src).to_owned(),
+                dst: state.component_name(event.dst).to_owned(),
+                type_name: serde_type_name::type_name(&event.data).unwrap().to_owned(),
+                data: Some(serde_json::to_value(&event.data).unwrap()),
+            });
+        }
+    }
+
+    async_mode_enabled!(
+        /// Spawns a new asynchronous task.
+        ///
+        /// The task's type lifetime must be `'static`.
+        /// This means that the spawned task must not contain any references to data owned outside the task.
+        ///
+        /// To spawn methods inside simulation components use [`SimulationContext::spawn`].
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        ///
+        /// let ctx = sim.create_context("client");
+        ///
+        /// sim.spawn(async move {
+        ///     let initial_time = ctx.time();
+        ///     ctx.sleep(5.).await;
+        ///     assert_eq!(ctx.time(), 5.);
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// assert_eq!(sim.time(), 5.);
+        /// ```
+        pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
+            self.sim_state.borrow_mut().spawn(future);
+        }
+
+        /// Sets the maximum number of concurrently running tasks spawned via the context of the specified component.
+        ///
+        /// See [`SimulationContext::set_task_limit`].
+        ///
+        /// Panics if component with such name does not exist or the limit is zero.
+        pub fn set_task_limit<S>(&self, name: S, limit: usize)
+        where
+            S: AsRef<str>,
+        {
+            let id = self.lookup_id(name.as_ref());
+            self.sim_state.borrow_mut().set_task_limit(id, limit);
+        }
+
+        /// Enables collection of statistics of simulated time spent by asynchronous tasks in waiting.
+        ///
+        /// When enabled, the waits via [`SimulationContext::sleep`] and `recv_event*` methods are instrumented to
+        /// record their durations, including the waits which are cancelled or timed out. The statistics are grouped
+        /// by tag, which is the call site (`file:line`) of the method creating the wait or the label set via
+        /// [`EventFuture::labeled`](crate::async_mode::EventFuture::labeled) or
+        /// [`TimerFuture::labeled`](crate::async_mode::TimerFuture::labeled). Only the waits created after enabling
+        /// the statistics are recorded.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Reply {}
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// sim.enable_wait_stats();
+        /// let client_ctx = sim.create_context("client");
+        /// let client_id = client_ctx.id();
+        /// let server_ctx = sim.create_context("server");
+        ///
+        /// sim.spawn(async move {
+        ///     for _ in 0..2 {
+        ///         client_ctx.recv_event::<Reply>().labeled("reply").await;
+        ///         client_ctx.sleep(1.).labeled("think").await;
+        ///     }
+        /// });
+        /// server_ctx.emit(Reply {}, client_id, 4.);
+        /// server_ctx.emit(Reply {}, client_id, 7.);
+        /// sim.step_until_no_events();
+        ///
+        /// let stats = sim.wait_stats();
+        /// assert_eq!(stats["reply"].count, 2);
+        /// assert_eq!(stats["reply"].total_time, 6.);
+        /// assert_eq!(stats["reply"].max_time, 4.);
+        /// assert_eq!(stats["think"].mean_time(), 1.);
+        /// assert_eq!(sim.wait_metrics()["wait.reply.total_time"], 6.);
+        /// ```
+        pub fn enable_wait_stats(&mut self) {
+            self.sim_state.borrow_mut().enable_wait_stats();
+        }
+
+        /// Checks whether the simulation has ended in a deadlock between asynchronous tasks.
+        ///
+        /// The deadlock is detected when there are no pending events and timers left, and there is a cycle of
+        /// components waiting for events from each other, e.g. a client waiting for a response from a server which
+        /// waits for a request from the client. Such waits can never complete, unlike the waits for events from any
+        /// component, e.g. a server waiting for new requests, which are considered as a legitimate termination.
+        /// The waits of asynchronous tasks for other primitives, e.g. queues, are not considered.
+        ///
+        /// Returns the report describing the cycles and all blocked waits, or `None` if there is no deadlock.
+        /// When the deadlock is detected on simulation end, the report is also logged with warning level.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Token {}
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let a_ctx = sim.create_context("a");
+        /// let a_id = a_ctx.id();
+        /// let b_ctx = sim.create_context("b");
+        /// let b_id = b_ctx.id();
+        ///
+        /// // both components wait for the token from each other before sending their own
+        /// sim.spawn(async move {
+        ///     a_ctx.recv_event_from::<Token>(b_id).await;
+        ///     a_ctx.emit(Token {}, b_id, 1.);
+        /// });
+        /// sim.spawn(async move {
+        ///     b_ctx.recv_event_from::<Token>(a_id).await;
+        ///     b_ctx.emit(Token {}, a_id, 1.);
+        /// });
+        /// sim.step_until_no_events();
+        ///
+        /// let report = sim.detect_deadlock().unwrap();
+        /// assert_eq!(report.cycles.len(), 1);
+        /// let waits: Vec<_> = report.cycles[0].iter().map(|wait| (wait.component, wait.src.unwrap())).collect();
+        /// assert_eq!(waits, vec![(a_id, b_id), (b_id, a_id)]);
+        /// ```
+        pub fn detect_deadlock(&self) -> Option<DeadlockReport> {
+            let mut state = self.sim_state.borrow_mut();
+            if state.peek_event().is_some() || state.peek_timer().is_some() {
+                return None;
+            }
+            DeadlockReport::detect(state.pending_event_waits(), |id| state.lookup_name(id))
+        }
+
+        /// Returns the wait statistics by tag, see [`enable_wait_stats`](Self::enable_wait_stats).
+        pub fn wait_stats(&self) -> BTreeMap<String, WaitStats> {
+            self.sim_state.borrow().wait_stats()
+        }
+
+        /// Returns the wait statistics as run metrics, which can be compared via
+        /// [`compare_runs`](crate::analysis::compare_runs).
+        ///
+        /// For each tag, the metrics `wait.<tag>.count`, `wait.<tag>.total_time`, `wait.<tag>.mean_time` and
+        /// `wait.<tag>.max_time` are returned.
+        pub fn wait_metrics(&self) -> RunMetrics {
+            let mut metrics = RunMetrics::new();
+            for (tag, stats) in self.wait_stats() {
+                metrics.insert(format!("wait.{}.count", tag), stats.count as f64);
+                metrics.insert(format!("wait.{}.total_time", tag), stats.total_time);
+                metrics.insert(format!("wait.{}.mean_time", tag), stats.mean_time());
+                metrics.insert(format!("wait.{}.max_time", tag), stats.max_time);
+            }
+            metrics
+        }
+
+        /// Registers a function that extracts [`EventKey`](type@EventKey) from events of a type `T`.
+        ///
+        /// Calling this function is required before using [`SimulationContext::recv_event_by_key`] or
+        /// [`SimulationContext::recv_event_by_key_from`] with type `T`. See examples for these methods.
+        pub fn register_key_getter_for<T: EventData>(&self, key_getter: impl Fn(&T) -> EventKey + 'static) {
+            self.sim_state.borrow_mut().register_key_getter_for::<T>(key_getter);
+        }
+
+        /// Registers a function that extracts composite key, e.g. a tuple of identifiers, from events of a type `T`.
+        ///
+        /// The event key is computed from the returned value via [`composite_key`], which should be also used to
+        /// compute the key passed to [`SimulationContext::recv_event_by_key`]. See [`composite_key`] for examples
+        /// and the probability of key collisions.
+        pub fn register_composite_key_getter_for<T, K>(&self, key_getter: impl Fn(&T) -> K + 'static)
+        where
+            T: EventData,
+            K: Hash,
+        {
+            self.sim_state
+                .borrow_mut()
+                .register_key_getter_for::<T>(move |data| composite_key(&key_getter(data)));
+        }
+
+        /// Registers a function that extracts [`EventKey`](type@EventKey) from events of a type `T` delivered to the specified
+        /// component.
+        ///
+        /// The key getter registered for a component takes precedence over the one registered for all components via
+        /// [`register_key_getter_for`](Self::register_key_getter_for), so different components can key the same event
+        /// type differently.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Packet {
+        ///     conn_id: u64,
+        ///     seq_no: u64,
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let sender_ctx = sim.create_context("sender");
+        /// let router_ctx = sim.create_context("router");
+        /// let router_id = router_ctx.id();
+        /// let receiver_ctx = sim.create_context("receiver");
+        /// let receiver_id = receiver_ctx.id();
+        /// // router awaits packets by connection, while receiver awaits them by sequence number
+        /// sim.register_key_getter_for::<Packet>(|packet| packet.conn_id);
+        /// sim.register_component_key_getter_for::<Packet, _>("receiver", |packet| packet.seq_no);
+        ///
+        /// sim.spawn(async move {
+        ///     let packet = router_ctx.recv_event_by_key::<Packet>(7).await;
+        ///     assert_eq!(packet.data.seq_no, 1);
+        /// });
+        /// sim.spawn(async move {
+        ///     let packet = receiver_ctx.recv_event_by_key::<Packet>(7).await;
+        ///     assert_eq!(packet.data.conn_id, 2);
+        /// });
+        ///
+        /// sender_ctx.emit(Packet { conn_id: 7, seq_no: 1 }, router_id, 1.);
+        /// sender_ctx.emit(Packet { conn_id: 2, seq_no: 7 }, receiver_id, 2.);
+        /// sim.step_until_no_events();
+        /// ```
+        pub fn register_component_key_getter_for<T, S>(&self, name: S, key_getter: impl Fn(&T) -> EventKey + 'static)
+        where
+            T: EventData,
+            S: AsRef<str>,
+        {
+            let id = self.lookup_id(name.as_ref());
+            self.sim_state
+                .borrow_mut()
+                .register_component_key_getter_for::<T>(id, key_getter);
+        }
+
+        /// Registers the key getter for event type `T` implementing [`KeyedEvent`].
+        ///
+        /// This is a replacement for [`register_key_getter_for`](Self::register_key_getter_for) for types with
+        /// `#[derive(EventKey)]` (requires `derive` feature). Registering is not needed when such events are received
+        /// via [`SimulationContext::recv_keyed_event`], which registers the key getter on first use.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use serde::Serialize;
+        /// use simcore::Simulation;
+        /// use simcore::async_mode::{EventKey, KeyedEvent};
+        ///
+        /// #[derive(Clone, Serialize)]
+        /// struct Reply {
+        ///     request_id: u32,
+        /// }
+        ///
+        /// // implemented manually here, can be derived with `#[derive(EventKey)]` and `#[event_key]` on the field
+        /// impl KeyedEvent for Reply {
+        ///     fn event_key(&self) -> EventKey {
+        ///         self.request_id as EventKey
+        ///     }
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let client_ctx = sim.create_context("client");
+        /// let client_id = client_ctx.id();
+        /// let server_ctx = sim.create_context("server");
+        /// sim.register_keyed_event::<Reply>();
+        ///
+        /// sim.spawn(async move {
+        ///     let reply = client_ctx.recv_event_by_key::<Reply>(2).await;
+        ///     assert_eq!(reply.data.request_id, 2);
+        ///     assert_eq!(client_ctx.time(), 20.);
+        /// });
+        ///
+        /// server_ctx.emit(Reply { request_id: 1 }, client_id, 10.);
+        /// server_ctx.emit(Reply { request_id: 2 }, client_id, 20.);
+        /// sim.step_until_no_events();
+        /// ```
+        pub fn register_keyed_event<T: KeyedEvent>(&self) {
+            self.sim_state.borrow_mut().register_key_getter_for::<T>(T::event_key);
+        }
+
+        /// Creates an [`UnboundedQueue`] for producer-consumer communication.
+        ///
+        /// This queue is designed to support convenient communication between several asynchronous tasks
+        /// within a single simulation component. This enables implementing the component logic as a set of
+        /// communicating concurrent activities.
+        ///
+        /// The use of this primitive for inter-component communication is discouraged in favor of passing events
+        /// directly or via intermediate components.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        /// use std::cell::RefCell;
+        /// use simcore::{Simulation, SimulationContext, Event, StaticEventHandler};
+        /// use simcore::async_mode::UnboundedQueue;
+        ///
+        /// struct Message {
+        ///     payload: u32,
+        /// }
+        ///
+        /// struct Component {
+        ///     ctx: SimulationContext,
+        ///     queue: UnboundedQueue<Message>,
+        /// }
+        ///
+        /// impl Component {
+        ///     fn start(self: Rc<Self>) {
+        ///         self.ctx.spawn(self.clone().producer());
+        ///         self.ctx.spawn(self.clone().consumer());
+        ///     }
+        ///
+        ///     async fn producer(self: Rc<Self>) {
+        ///         for i in 0..10 {
+        ///             self.ctx.sleep(5.).await;
+        ///             self.queue.put(Message {payload: i});
+        ///         }
+        ///     }
+        ///
+        ///     async fn consumer(self: Rc<Self>) {
+        ///         for i in 0..10 {
+        ///             let msg = self.queue.take().await;
+        ///             assert_eq!(msg.payload, i);
+        ///         }
+        ///     }
+        /// }
+        ///
+        /// impl StaticEventHandler for Component {
+        ///     fn on(self: Rc<Self>, event: Event) {
+        ///     }
+        /// }
+        ///
+        /// let mut sim = Simulation::new(123);
+        ///
+        /// let comp = Rc::new(Component {
+        ///     ctx: sim.create_context("comp"),
+        ///     queue: sim.create_queue("comp_queue")
+        /// });
+        /// sim.add_static_handler("comp", comp.clone());
+        ///
+        /// comp.start();
+        /// sim.step_until_no_events();
+        ///
+        /// assert_eq!(sim.time(), 50.);
+        /// ```
+        pub fn create_queue<T, S>(&mut self, name: S) -> UnboundedQueue<T>
+        where
+            S: AsRef<str>,
+        {
+            UnboundedQueue::new(self.create_context(name))
+        }
+
+        /// Creates a new [`Resource`] with specified name and capacity.
+        ///
+        /// Panics if `capacity` is zero.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let servers = Rc::new(sim.create_resource("servers", 2));
+        ///
+        /// for i in 0..4 {
+        ///     let servers = servers.clone();
+        ///     let ctx = sim.create_context(format!("job-{}", i));
+        ///     sim.spawn(async move {
+        ///         servers.acquire(1).await;
+        ///         ctx.sleep(10.).await;
+        ///         servers.release(1);
+        ///     });
+        /// }
+        ///
+        /// sim.step_until_no_events();
+        /// // jobs are processed by two servers in two rounds
+        /// assert_eq!(sim.time(), 20.);
+        /// let stats = servers.stats();
+        /// assert_eq!(stats.acquisitions, 4);
+        /// assert_eq!(stats.utilization, 1.);
+        /// assert_eq!(stats.max_queue_length, 2);
+        /// assert_eq!(stats.mean_wait_time, 5.);
+        /// ```
+        pub fn create_resource<S>(&mut self, name: S, capacity: u64) -> Resource
+        where
+            S: AsRef<str>,
+        {
+            Resource::new(self.create_context(name), capacity)
+        }
+
+        /// Creates a new [`TokenBucket`] with specified name, token rate and capacity.
+        ///
+        /// Panics if `rate` or `capacity` is not positive.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::rc::Rc;
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// // 2 tokens per time unit, bursts up to 4 tokens
+        /// let limiter = Rc::new(sim.create_token_bucket("limiter", 2., 4.));
+        /// let ctx = sim.create_context("client");
+        ///
+        /// sim.spawn(async move {
+        ///     let mut send_times = Vec::new();
+        ///     for _ in 0..8 {
+        ///         limiter.acquire(1.).await;
+        ///         send_times.push(ctx.time());
+        ///     }
+        ///     // the first 4 requests are sent immediately, the rest are sent at the token rate
+        ///     assert_eq!(send_times, vec![0., 0., 0., 0., 0.5, 1., 1.5, 2.]);
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// assert_eq!(sim.time(), 2.);
+        /// ```
+        pub fn create_token_bucket<S>(&mut self, name: S, rate: f64, capacity: f64) -> TokenBucket
+        where
+            S: AsRef<str>,
+        {
+            TokenBucket::new(self.create_context(name), rate, capacity)
+        }
+
+        /// Creates a new [`FairShare`] with specified name and capacity.
+        ///
+        /// Panics if `capacity` is not positive.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::cell::RefCell;
+        /// use std::rc::Rc;
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// // link with bandwidth 10
+        /// let link = Rc::new(sim.create_fair_share("link", 10.));
+        /// let completed = Rc::new(RefCell::new(Vec::new()));
+        ///
+        /// // (name, size, weight, max rate, start time)
+        /// let transfers = [
+        ///     ("a", 30., 1., f64::INFINITY, 0.),
+        ///     ("b", 30., 1., 2., 0.),
+        ///     ("c", 20., 3., f64::INFINITY, 1.),
+        /// ];
+        /// for (name, size, weight, max_rate, start) in transfers {
+        ///     let ctx = sim.create_context(name);
+        ///     let link = link.clone();
+        ///     let completed = completed.clone();
+        ///     sim.spawn(async move {
+        ///         ctx.sleep(start).await;
+        ///         link.process_weighted(size, weight, max_rate).await;
+        ///         completed.borrow_mut().push((name, ctx.time()));
+        ///     });
+        /// }
+        ///
+        /// sim.step_until_no_events();
+        /// // b is limited by its maximum rate 2 and the rest is shared by a and c in proportion 1:3,
+        /// // so c completes at 1 + 20 / 6, then a gets 8 and completes at 6.25
+        /// let expected = [("c", 1. + 20. / 6.), ("a", 6.25), ("b", 15.)];
+        /// assert_eq!(completed.borrow().len(), 3);
+        /// for ((name, time), (expected_name, expected_time)) in completed.borrow().iter().zip(expected) {
+        ///     assert_eq!(*name, expected_name);
+        ///     assert!((time - expected_time).abs() < 1e-9);
+        /// }
+        /// assert_eq!(link.active(), 0);
+        /// ```
+        pub fn create_fair_share<S>(&mut self, name: S, capacity: f64) -> FairShare
+        where
+            S: AsRef<str>,
+        {
+            FairShare::new(self.create_context(name), capacity)
+        }
+
+        /// Creates a new [`Process`] with specified name executing the specified body.
+        ///
+        /// The body receives the process handle and is spawned as asynchronous task.
+        /// See [`process`](crate::async_mode::process) module for details.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use std::cell::RefCell;
+        /// use std::rc::Rc;
+        /// use simcore::async_mode::process::Interrupted;
+        /// use simcore::Simulation;
+        ///
+        /// let mut sim = Simulation::new(123);
+        /// let log = Rc::new(RefCell::new(Vec::new()));
+        ///
+        /// let worker_log = log.clone();
+        /// let worker = sim.spawn_process("worker", |p| async move {
+        ///     loop {
+        ///         // wait for a job
+        ///         if p.passivate().await.is_err() {
+        ///             break;
+        ///         }
+        ///         match p.hold(10.).await {
+        ///             Ok(()) => worker_log.borrow_mut().push(format!("done at {}", p.time())),
+        ///             Err(Interrupted) => worker_log.borrow_mut().push(format!("interrupted at {}", p.time())),
+        ///         }
+        ///     }
+        /// });
+        ///
+        /// sim.spawn_process("dispatcher", move |p| async move {
+        ///     p.hold(1.).await.unwrap();
+        ///     worker.activate();
+        ///     p.hold(20.).await.unwrap();
+        ///     worker.activate();
+        ///     p.hold(5.).await.unwrap();
+        ///     worker.interrupt();
+        ///     p.hold(1.).await.unwrap();
+        ///     worker.interrupt();
+        ///     p.hold(1.).await.unwrap();
+        ///     assert!(worker.is_finished());
+        /// });
+        ///
+        /// sim.step_until_no_events();
+        /// assert_eq!(*log.borrow(), vec!["done at 11", "interrupted at 26"]);
+        /// ```
+        pub fn spawn_process<S, F, Fut>(&mut self, name: S, body: F) -> Process
+        where
+            S: AsRef<str>,
+            F: FnOnce(Process) -> Fut,
+            Fut: Future<Output = ()> + 'static,
+        {
+            Process::spawn(self.create_context(name), body)
+        }
+    );
+
+    /// Performs the specified number of steps through the simulation.
+    ///
+    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the specified number of
+    /// steps is made, or `false` is returned (no more pending events).
+    ///
+    /// Returns `true` if there could be more pending events and `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.3);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.4);
+    /// let mut status = sim.steps(2);
+    /// assert!(status);
+    /// assert_eq!(sim.time(), 1.3);
+    /// status = sim.steps(2);
+    /// assert!(!status);
+    /// assert_eq!(sim.time(), 1.4);
+    /// ```
+    pub fn steps(&mut self, step_count: u64) -> bool {
+        self.pause_requested.set(false);
+        for _ in 0..step_count {
+            if !self.step() {
+                return false;
+            }
+            if self.pause_requested.get() {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Steps through the simulation until there are no pending events left.
+    ///
+    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until `false` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.3);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.4);
+    /// sim.step_until_no_events();
+    /// assert_eq!(sim.time(), 1.4);
+    /// ```
+    pub fn step_until_no_events(&mut self) {
+        self.pause_requested.set(false);
+        while self.step() && !self.pause_requested.get() {}
+    }
+
+    /// Steps through the simulation until there are no pending events left, pacing the simulation clock against the
+    /// wall-clock time.
+    ///
+    /// Before processing each event, the method sleeps until the wall-clock time elapsed since the call reaches
+    /// the simulation time elapsed since the call divided by the `speedup` factor. For example, with `speedup`
+    /// equal to 2 the simulation runs twice as fast as real time. If processing the events is slower than the
+    /// configured pace, the events are processed without sleeping until the simulation catches up. The external
+    /// [inputs](crate::input) are read before sleeping, so the events produced by real-time sources should be
+    /// delivered via inputs with watermarks. This is useful for demos and hardware/software-in-the-loop setups.
+    ///
+    /// Panics if `speedup` is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// comp_ctx.emit_self(SomeEvent {}, 0.01);
+    /// comp_ctx.emit_self(SomeEvent {}, 0.02);
+    /// let start = Instant::now();
+    /// sim.run_real_time(2.0);
+    /// assert_eq!(sim.time(), 0.02);
+    /// assert!(start.elapsed() >= Duration::from_millis(10));
+    /// ```
+    pub fn run_real_time(&mut self, speedup: f64) {
+        assert!(
+            speedup > 0.,
+            "Real-time speedup factor must be positive, got {}",
+            speedup
+        );
+        self.start_components();
+        self.pause_requested.set(false);
+        let start = Instant::now();
+        let start_time = self.time();
+        loop {
+            self.run_ready_tasks();
+            self.inject_inputs(f64::INFINITY);
+            if let Some(time) = self.next_activity_time() {
+                let deadline = start + Duration::from_secs_f64((time - start_time).max(0.) / speedup);
+                let now = Instant::now();
+                if deadline > now {
+                    std::thread::sleep(deadline - now);
+                }
+            }
+            if !self.step() || self.pause_requested.get() {
+                break;
+            }
+        }
+    }
+
+    /// Steps through the simulation with duration limit.
+    ///
+    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the next event
+    /// time is above the specified threshold (`initial_time + duration`) or there are no pending events left.
+    ///
+    /// This method also advances the simulation time to `initial_time + duration`. Note that the resulted time may
+    /// slightly differ from the expected value due to the floating point errors. This issue can be avoided by using
+    /// the [`step_until_time`](Self::step_until_time) method.
+    ///
+    /// Returns `true` if there could be more pending events and `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
+    /// let mut status = sim.step_for_duration(1.8);
+    /// assert_eq!(sim.time(), 1.8);
+    /// assert!(status); // there are more events
+    /// status = sim.step_for_duration(1.8);
+    /// assert_eq!(sim.time(), 3.6);
+    /// assert!(!status); // there are no more events
+    /// ```
+    pub fn step_for_duration(&mut self, duration: f64) -> bool {
+        let end_time = self.sim_state.borrow().time() + duration;
+        self.step_until_time(end_time)
+    }
+
+    /// Steps through the simulation until the specified time.
+    ///
+    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the next event
+    /// time is above the specified time or there are no pending events left.
+    ///
+    /// This method also advances the simulation time to the specified time.
+    ///
+    /// Returns `true` if there could be more pending events and `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
+    /// let mut status = sim.step_until_time(1.8);
+    /// assert_eq!(sim.time(), 1.8);
+    /// assert!(status); // there are more events
+    /// status = sim.step_until_time(3.6);
+    /// assert_eq!(sim.time(), 3.6);
+    /// assert!(!status); // there are no more events
+    /// ```
+    pub fn step_until_time(&mut self, time: f64) -> bool {
+        self.start_components();
+        self.pause_requested.set(false);
+        self.step_until_time_inner(time)
+    }
+
+    /// Performs as many steps as needed to reach the specified tick in the discrete time mode, see
+    /// [`step_until_time`](Self::step_until_time).
+    ///
+    /// Returns `true` if there could be more pending events and `false` otherwise.
+    /// Panics if the time tick is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Round {}
+    ///
+    /// let mut sim = Simulation::with_integer_ticks(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.emit_self_ticks(Round {}, 2);
+    /// ctx.emit_self_ticks(Round {}, 5);
+    /// assert!(sim.step_until_tick(3));
+    /// assert_eq!(sim.current_tick(), 3);
+    /// assert!(!sim.step_until_tick(7));
+    /// assert_eq!(sim.current_tick(), 7);
+    /// ```
+    pub fn step_until_tick(&mut self, tick: u64) -> bool {
+        let time = self.sim_state.borrow().time_of_tick(tick);
+        self.step_until_time(time)
+    }
+
+    /// Performs as many steps as needed to advance the simulation time by the specified typed duration, see
+    /// [`step_until_time`](Self::step_until_time).
+    ///
+    /// Returns `true` if there could be more pending events and `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::time::{SimDuration, SimTime};
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Heartbeat {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.emit_self_in(Heartbeat {}, SimDuration::from_millis(300.));
+    /// ctx.emit_self_in(Heartbeat {}, SimDuration::from_millis(700.));
+    /// assert!(sim.step_for(SimDuration::from_millis(500.)));
+    /// assert!(!sim.step_for(SimDuration::from_millis(500.)));
+    /// assert_eq!(sim.now(), SimTime::from_secs(1.));
+    /// ```
+    pub fn step_for(&mut self, duration: SimDuration) -> bool {
+        let time = self.now() + duration;
+        self.step_until_time(time.as_secs())
+    }
+
+    /// Steps through the simulation until the specified condition is met.
+    ///
+    /// This is a convenient wrapper around [`step`](Self::step), which checks the condition before the first step
+    /// and after each step. The conditions from [`stop`](crate::stop) module can be combined to express the typical
+    /// stopping rules, e.g. reaching the required precision of the metric estimate but not later than some time.
+    ///
+    /// Returns `true` if the condition is met and `false` if there are no more pending events or the simulation is
+    /// paused by a watchpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    /// use simcore::stop::{EventCount, StopCondition, TimeReached};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Ping {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Pong {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// for i in 1..=10 {
+    ///     ctx.emit_self(Ping {}, i as f64);
+    ///     ctx.emit_self(Pong {}, i as f64 + 0.5);
+    /// }
+    ///
+    /// assert!(sim.step_until(EventCount::of::<Pong>(3)));
+    /// assert_eq!(sim.time(), 3.5);
+    /// assert!(sim.step_until(EventCount::of::<Ping>(5).and(TimeReached(5.))));
+    /// assert_eq!(sim.time(), 8.);
+    /// assert!(!sim.step_until(TimeReached(100.)));
+    /// assert_eq!(sim.time(), 10.5);
+    /// ```
+    pub fn step_until<C>(&mut self, condition: C) -> bool
+    where
+        C: StopCondition + 'static,
+    {
+        self.start_components();
+        self.pause_requested.set(false);
+        *self.stop_condition.borrow_mut() = Some(Box::new(condition));
+        self.enable_step_hooks();
+        let result = loop {
+            let time = self.time();
+            if self.stop_condition.borrow_mut().as_mut().unwrap().is_met(time) {
+                break true;
+            }
+            if !self.step() || self.pause_requested.get() {
+                break false;
+            }
+        };
+        self.stop_condition.borrow_mut().take();
+        result
+    }
+
+    /// Steps through the simulation until the components of the group are idle.
+    ///
+    /// The group is idle when there are no pending events emitted by or destined for its components, including the
+    /// events emitted via [`emit_after`](SimulationContext::emit_after), and in async mode no runnable tasks, pending
+    /// timers or tasks queued by the [task limit](SimulationContext::set_task_limit) of its components. The
+    /// components are assigned to the group via [`set_component_group`](Self::set_component_group) before calling
+    /// this method. The events of external inputs are taken into account only after their injection.
+    ///
+    /// This allows to run the phases of an experiment, e.g. to let the bootstrap subsystem finish before starting
+    /// the load, without guessing the phase duration. The pending events are scanned after each step, so the
+    /// method is intended for the phases with moderate number of pending events.
+    ///
+    /// Returns `true` if the group is idle and `false` if there are no more pending events while the group is not
+    /// idle or the simulation is paused by a watchpoint.
+    ///
+    /// Panics if the group does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Join {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let node1 = sim.create_context("node1");
+    /// let node2 = sim.create_context("node2");
+    /// let client = sim.create_context("client");
+    /// sim.set_component_group("node1", "bootstrap");
+    /// sim.set_component_group("node2", "bootstrap");
+    ///
+    /// node1.emit(Join {}, node2.id(), 3.);
+    /// node2.emit(Join {}, node1.id(), 4.);
+    /// client.emit_self(Request {}, 1.);
+    /// client.emit_self(Request {}, 10.);
+    ///
+    /// assert!(sim.step_until_group_idle("bootstrap"));
+    /// assert_eq!(sim.time(), 4.);
+    /// ```
+    pub fn step_until_group_idle(&mut self, group: &str) -> bool {
+        self.start_components();
+        self.pause_requested.set(false);
+        let components = self.sim_state.borrow().group_components(group);
+        loop {
+            self.run_ready_tasks();
+            if !self.sim_state.borrow().has_group_activity(&components) {
+                return true;
+            }
+            if !self.step() || self.pause_requested.get() {
+                return false;
+            }
+        }
+    }
+
+    /// Steps through the simulation until there are no pending events left and returns the [`RunResult`].
+    ///
+    /// This is a variant of [`step_until_no_events`](Self::step_until_no_events) which catches the panics raised
+    /// during event processing and reports the reason of stopping the run, see [`run`](crate::run) module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::run::TerminationReason;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     size: u64,
+    /// }
+    ///
+    /// struct Server {}
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         let request = event.data.downcast_ref::<Request>().unwrap();
+    ///         assert!(request.size <= 100, "Request is too large");
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.add_handler("server", Rc::new(RefCell::new(Server {})));
+    /// let client = sim.create_context("client");
+    /// client.emit(Request { size: 10 }, sim.lookup_id("server"), 1.);
+    /// client.emit(Request { size: 20 }, sim.lookup_id("server"), 2.);
+    ///
+    /// let result = sim.run();
+    /// assert_eq!(result.termination, TerminationReason::Drained);
+    /// assert_eq!(result.time, 2.);
+    /// assert_eq!(result.processed_events, 2);
+    ///
+    /// client.emit(Request { size: 1000 }, sim.lookup_id("server"), 1.);
+    /// let result = sim.run();
+    /// assert_eq!(result.termination, TerminationReason::Error("Request is too large".to_owned()));
+    /// assert_eq!(result.failed_components, vec!["server"]);
+    /// ```
+    pub fn run(&mut self) -> RunResult {
+        self.run_with(|sim| {
+            sim.step_until_no_events();
+            TerminationReason::Drained
+        })
+    }
+
+    /// Steps through the simulation until the specified time and returns the [`RunResult`].
+    ///
+    /// This is a variant of [`step_until_time`](Self::step_until_time), see [`run`](Self::run).
+    /// The run stops with [`TerminationReason::TimeReached`] even if there are no more pending events.
+    pub fn run_until_time(&mut self, time: f64) -> RunResult {
+        self.run_with(|sim| {
+            sim.step_until_time(time);
+            TerminationReason::TimeReached
+        })
+    }
+
+    /// Steps through the simulation until the specified condition is met and returns the [`RunResult`].
+    ///
+    /// This is a variant of [`step_until`](Self::step_until), see [`run`](Self::run).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::run::TerminationReason;
+    /// use simcore::stop::EventCount;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Ping {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// for i in 1..=10 {
+    ///     ctx.emit_self(Ping {}, i as f64);
+    /// }
+    ///
+    /// let result = sim.run_until(EventCount::of::<Ping>(3));
+    /// assert_eq!(result.termination, TerminationReason::StopCondition);
+    /// assert_eq!((result.time, result.processed_events, result.pending_events), (3., 3, 7));
+    /// let result = sim.run_until(EventCount::of::<Ping>(100));
+    /// assert_eq!(result.termination, TerminationReason::Drained);
+    /// ```
+    pub fn run_until<C>(&mut self, condition: C) -> RunResult
+    where
+        C: StopCondition + 'static,
+    {
+        self.run_with(|sim| {
+            if sim.step_until(condition) {
+                TerminationReason::StopCondition
+            } else {
+                TerminationReason::Drained
+            }
+        })
+    }
+
+    fn run_with<F>(&mut self, run: F) -> RunResult
+    where
+        F: FnOnce(&mut Self) -> TerminationReason,
+    {
+        let (processed_events, emitted_events) = (self.processed_events.get(), self.sim_state.borrow().event_count());
+        let prior_violation = self.limit_violation.borrow().clone();
+        let prior_peer_error = self.peer_error.borrow().clone();
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| run(self)));
+        let mut failed_components = Vec::new();
+        let termination = match outcome {
+            Ok(reason) => {
+                let violation = self.limit_violation.borrow().clone();
+                let peer_error = self.peer_error.borrow().clone();
+                if !self.pause_requested.get() {
+                    reason
+                } else if let Some(error) = peer_error.filter(|error| prior_peer_error.as_ref() != Some(error)) {
+                    TerminationReason::PeerError(error)
+                } else if let Some(violation) = violation.filter(|v| prior_violation.as_ref() != Some(v)) {
+                    TerminationReason::Budget(violation)
+                } else {
+                    TerminationReason::Paused
+                }
+            }
+            Err(payload) => {
+                self.stop_condition.borrow_mut().take();
+                if let Some(id) = self.dispatched_component.take() {
+                    failed_components.push(self.lookup_name(id));
+                }
+                TerminationReason::Error(panic_message(payload.as_ref()))
+            }
+        };
+        RunResult {
+            termination,
+            time: self.time(),
+            processed_events: self.processed_events.get() - processed_events,
+            emitted_events: self.sim_state.borrow().event_count() - emitted_events,
+            pending_events: self.sim_state.borrow().queued_event_count(),
+            snapshot: self.snapshot(),
+            failed_components,
+        }
+    }
+
+    async_mode_disabled!(
+        fn step_until_time_inner(&mut self, time: f64) -> bool {
+            let mut result = true;
+            loop {
+                self.inject_inputs(time);
+                self.advance_continuous_models_until(time);
+                if let Some(event) = self.sim_state.borrow_mut().peek_event() {
+                    if event.time > time {
+                        break;
+                    }
+                } else {
+                    result = false;
+                    break;
+                }
+                self.step();
+                if self.pause_requested.get() {
+                    return true;
+                }
+            }
+            self.check_warmup(Some(time));
+            self.report_time_advance();
+            self.sim_state.borrow_mut().set_time(time);
+            self.report_time_advance();
+            result || self.inputs.borrow().has_pending()
+        }
+    );
+
+    async_mode_enabled!(
+        fn step_until_time_inner(&mut self, time: f64) -> bool {
+            let mut result;
+            loop {
+                while self.process_task() {}
+                self.inject_inputs(time);
+                self.advance_continuous_models_until(time);
+
+                result = false;
+                let mut step = false;
+
+                if let Some(event) = self.sim_state.borrow_mut().peek_event() {
+                    result = true;
+                    if event.time <= time {
+                        step = true;
+                    }
+                }
+
+                if let Some(timer) = self.sim_state.borrow_mut().peek_timer() {
+                    result = true;
+                    if timer.time <= time {
+                        step = true;
+                    }
+                }
+
+                if step {
+                    self.step();
+                    if self.pause_requested.get() {
+                        return true;
+                    }
+                } else {
+                    break;
+                }
+            }
+            self.check_warmup(Some(time));
+            self.report_time_advance();
+            self.sim_state.borrow_mut().set_time(time);
+            self.report_time_advance();
+            result || self.inputs.borrow().has_pending()
+        }
+    );
+
+    /// Returns a random float in the range _[0, 1)_
+    /// using the simulation-wide random number generator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let f: f64 = sim.rand();
+    /// assert!(f >= 0.0 && f < 1.0);
+    /// ```
+    pub fn rand(&mut self) -> f64 {
+        self.sim_state.borrow_mut().rand()
+    }
+
+    /// Returns a random number in the specified range
+    /// using the simulation-wide random number generator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use simcore::Simulation;
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let n: u32 = sim.gen_range(1..=10);
+    /// assert!(n >= 1 && n <= 10);
+    /// let f: f64 = sim.gen_range(0.1..0.5);
+    /// assert!(f >= 0.1 && f < 0.5);
+    /// ```
+    pub fn gen_range<T, R>(&mut self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.sim_state.borrow_mut().gen_range(range)
+    }
+
+    /// Returns a random value from the specified distribution
+    /// using the simulation-wide random number generator.
+    pub fn sample_from_distribution<T, Dist: Distribution<T>>(&mut self, dist: &Dist) -> T {
+        self.sim_state.borrow_mut().sample_from_distribution(dist)
+    }
+
+    /// Returns a random alphanumeric string of specified length
+    /// using the simulation-wide random number generator.
+    pub fn random_string(&mut self, len: usize) -> String {
+        self.sim_state.borrow_mut().random_string(len)
+    }
+
+    /// Returns the total number of created events.
+    ///
+    /// Note that cancelled events are also counted here. After the end of [warmup period](Self::set_warmup_time),
+    /// only the events created since its end are counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp_ctx = sim.create_context("comp");
+    /// assert_eq!(sim.time(), 0.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
+    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
+    /// assert_eq!(sim.event_count(), 3);
+    /// ```
+    pub fn event_count(&self) -> u64 {
+        self.sim_state.borrow().event_count() - self.warmup.borrow().event_counts().0
+    }
 
-    async_mode_disabled!(
-        fn step_inner(&self) -> bool {
-            let event_opt = self.sim_state.borrow_mut().next_event();
-            match event_opt {
-                Some(event) => {
-                    self.deliver_event_via_handler(event);
-                    true
-                }
-                None => false,
+    /// Returns the number of events processed so far, i.e. delivered to the component handlers or awaiting tasks,
+    /// including the undelivered events discarded due to missing handlers.
+    ///
+    /// After the end of [warmup period](Self::set_warmup_time), only the events processed since its end are counted.
+    pub fn processed_event_count(&self) -> u64 {
+        self.processed_events.get() - self.warmup.borrow().event_counts().1
+    }
+
+    /// Declares the warmup period ending at the specified time, see [`warmup`](crate::warmup) module.
+    ///
+    /// The warmup period ends before processing the first event at or after the warmup time, or when the simulation
+    /// is advanced past the warmup time via [`step_until_time`](Self::step_until_time). The simulation time is set
+    /// to the warmup time and the following statistics are reset:
+    ///
+    /// - the numbers of created and processed events returned by [`event_count`](Self::event_count) and
+    ///   [`processed_event_count`](Self::processed_event_count),
+    /// - the numbers of output records suppressed by [rate limits](Self::set_output_rate_limit),
+    /// - the wait statistics of asynchronous tasks in async mode,
+    /// - the counters and histograms of [component metrics](crate::metrics),
+    /// - the statistics of components registered via [`add_warmup_reset`](Self::add_warmup_reset),
+    ///
+    /// and then the callbacks registered via [`on_warmup_end`](Self::on_warmup_end) are called.
+    ///
+    /// Panics if the warmup time is earlier than the current time or the warmup period has already ended.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::warmup::ResetStats;
+    /// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// struct Server {
+    ///     ctx: SimulationContext,
+    ///     served: u64,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.served += 1;
+    ///         self.ctx.emit_self(Request {}, 1.);
+    ///     }
+    /// }
+    ///
+    /// impl ResetStats for Server {
+    ///     fn reset_stats(&mut self) {
+    ///         self.served = 0;
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("server");
+    /// ctx.emit_self(Request {}, 0.5);
+    /// let server = Rc::new(RefCell::new(Server { ctx, served: 0 }));
+    /// sim.add_handler("server", server.clone());
+    /// sim.add_warmup_reset(server.clone());
+    /// sim.set_warmup_time(10.);
+    ///
+    /// sim.step_until_time(30.);
+    /// assert_eq!(sim.warmup_end_time(), Some(10.));
+    /// // the requests at 10.5, 11.5, ..., 29.5
+    /// assert_eq!(server.borrow().served, 20);
+    /// assert_eq!(sim.processed_event_count(), 20);
+    /// ```
+    pub fn set_warmup_time(&mut self, time: f64) {
+        assert!(
+            self.warmup.borrow().end_time().is_none(),
+            "Warmup period has already ended"
+        );
+        assert!(
+            time >= self.time(),
+            "Warmup time {} is earlier than the current time {}",
+            time,
+            self.time()
+        );
+        self.warmup.borrow_mut().set_time(time);
+        self.enable_step_hooks();
+    }
+
+    /// Returns the warmup time declared via [`set_warmup_time`](Self::set_warmup_time).
+    pub fn warmup_time(&self) -> Option<f64> {
+        self.warmup.borrow().time()
+    }
+
+    /// Returns the time when the warmup period ended, or `None` if it has not ended yet.
+    pub fn warmup_end_time(&self) -> Option<f64> {
+        self.warmup.borrow().end_time()
+    }
+
+    /// Ends the warmup period at the current time and resets the statistics as described in
+    /// [`set_warmup_time`](Self::set_warmup_time), e.g. once the steady state is detected by
+    /// [`WarmupDetector`](crate::warmup::WarmupDetector).
+    ///
+    /// Panics if the warmup period has already ended.
+    pub fn end_warmup(&mut self) {
+        assert!(
+            self.warmup.borrow().end_time().is_none(),
+            "Warmup period has already ended"
+        );
+        self.end_warmup_inner();
+    }
+
+    /// Registers the component whose statistics are reset at the end of the warmup period,
+    /// see [`set_warmup_time`](Self::set_warmup_time).
+    pub fn add_warmup_reset<C>(&mut self, component: Rc<RefCell<C>>)
+    where
+        C: ResetStats + 'static,
+    {
+        self.warmup.borrow_mut().add_reset(component);
+    }
+
+    /// Registers the callback which is called with the current time at the end of the warmup period,
+    /// see [`set_warmup_time`](Self::set_warmup_time).
+    pub fn on_warmup_end<F>(&mut self, callback: F)
+    where
+        F: FnMut(f64) + 'static,
+    {
+        self.warmup.borrow_mut().add_callback(Box::new(callback));
+    }
+
+    // Ends the warmup period if the declared warmup time is not later than the specified time limit or, if the limit
+    // is not specified, the time of the next pending activity.
+    fn check_warmup(&self, limit: Option<f64>) {
+        if self.advance_to_warmup_end(limit) {
+            self.end_warmup_inner();
+        }
+    }
+
+    // Advances the time to the declared warmup time and returns true if the warmup period should end, see
+    // `check_warmup`.
+    fn advance_to_warmup_end(&self, limit: Option<f64>) -> bool {
+        let Some(time) = self.warmup.borrow().pending_time() else {
+            return false;
+        };
+        self.inject_inputs(limit.unwrap_or(time));
+        let Some(horizon) = limit.or_else(|| self.next_activity_time()) else {
+            return false;
+        };
+        if time > horizon {
+            return false;
+        }
+        if time > self.time() {
+            self.report_time_advance();
+            self.sim_state.borrow_mut().set_time(time);
+            self.report_time_advance();
+        }
+        true
+    }
+
+    fn end_warmup_inner(&self) {
+        let time = self.time();
+        self.sim_state.borrow_mut().reset_stats();
+        self.mark_warmup_end();
+        debug!(
+            target: "simulation",
+            "[{:.3} {} simulation] Warmup period ended",
+            time,
+            crate::log::get_colored("DEBUG", colored::Color::Blue)
+        );
+        self.warmup.borrow_mut().reset_user_stats(time);
+    }
+
+    // Records the end of the warmup period at the current time without resetting the statistics.
+    fn mark_warmup_end(&self) {
+        let event_counts = (self.sim_state.borrow().event_count(), self.processed_events.get());
+        self.warmup.borrow_mut().end(self.time(), event_counts);
+    }
+
+    /// Registers the component state to be included in simulation snapshots, see [`snapshot`](Self::snapshot).
+    ///
+    /// Registering another state for the same component replaces the previous one.
+    ///
+    /// Panics if component with such name does not exist.
+    pub fn register_state<C>(&mut self, name: &str, component: Rc<RefCell<C>>)
+    where
+        C: ComponentState + 'static,
+    {
+        let id = self.lookup_id(name);
+        self.component_states.retain(|(state_id, _)| *state_id != id);
+        self.component_states.push((id, component));
+    }
+
+    /// Returns the snapshot of the states of components registered via [`register_state`](Self::register_state).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::Serialize;
+    /// use serde_json::{json, Value};
+    ///
+    /// use simcore::snapshot::ComponentState;
+    /// use simcore::{Event, EventHandler, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// pub struct Request {}
+    ///
+    /// struct Server {
+    ///     processed: u64,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, _event: Event) {
+    ///         self.processed += 1;
+    ///     }
+    /// }
+    ///
+    /// impl ComponentState for Server {
+    ///     fn state(&self) -> Value {
+    ///         json!({"processed": self.processed})
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let server = Rc::new(RefCell::new(Server { processed: 0 }));
+    /// let server_id = sim.add_handler("server", server.clone());
+    /// sim.register_state("server", server);
+    ///
+    /// let ctx = sim.create_context("client");
+    /// ctx.emit(Request {}, server_id, 1.);
+    /// ctx.emit(Request {}, server_id, 2.);
+    /// sim.step();
+    ///
+    /// let snapshot = sim.snapshot();
+    /// assert_eq!(snapshot.time, 1.);
+    /// assert_eq!(snapshot.get("server"), Some(&json!({"processed": 1})));
+    /// assert_eq!(snapshot.get("client"), None);
+    /// ```
+    pub fn snapshot(&self) -> StateSnapshot {
+        let components = self
+            .component_states
+            .iter()
+            .map(|(id, component)| (self.lookup_name(*id), component.borrow().state()))
+            .collect();
+        StateSnapshot {
+            time: self.time(),
+            event_count: self.sim_state.borrow().event_count(),
+            components,
+        }
+    }
+
+    /// Registers event type `T` whose pending events can be saved in checkpoints, see [`checkpoint`](Self::checkpoint).
+    ///
+    /// The events of timers are registered automatically.
+    pub fn register_checkpoint_event<T>(&mut self)
+    where
+        T: EventData + DeserializeOwned,
+    {
+        self.checkpoint_codecs.register::<T>();
+    }
+
+    /// Returns the checkpoint of the current simulation state, which can be restored via
+    /// [`restore_checkpoint`](Self::restore_checkpoint), see [`checkpoint`](crate::checkpoint) module.
+    ///
+    /// The checkpoint includes the states of components registered via [`register_state`](Self::register_state).
+    ///
+    /// Panics if the type of some pending event is not registered via
+    /// [`register_checkpoint_event`](Self::register_checkpoint_event), or if there are events emitted via
+    /// [`emit_after`](SimulationContext::emit_after) waiting for the preceding events, coalesced events or alive
+    /// asynchronous tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_json::{json, Value};
+    ///
+    /// use simcore::snapshot::ComponentState;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// pub struct Tick {}
+    ///
+    /// struct Counter {
+    ///     ticks: u64,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Counter {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Tick {} => {
+    ///                 self.ticks += 1;
+    ///                 self.ctx.emit_self(Tick {}, self.ctx.gen_range(1.0..2.0));
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// impl ComponentState for Counter {
+    ///     fn state(&self) -> Value {
+    ///         json!({"ticks": self.ticks})
+    ///     }
+    ///
+    ///     fn restore_state(&mut self, state: Value) {
+    ///         self.ticks = state["ticks"].as_u64().unwrap();
+    ///     }
+    /// }
+    ///
+    /// // the simulation must be built in the same way before restoring the checkpoint
+    /// fn build() -> (Simulation, Rc<RefCell<Counter>>) {
+    ///     let mut sim = Simulation::new(123);
+    ///     sim.register_checkpoint_event::<Tick>();
+    ///     let ctx = sim.create_context("counter");
+    ///     ctx.emit_self(Tick {}, 1.);
+    ///     let counter = Rc::new(RefCell::new(Counter { ticks: 0, ctx }));
+    ///     sim.add_handler("counter", counter.clone());
+    ///     sim.register_state("counter", counter.clone());
+    ///     (sim, counter)
+    /// }
+    ///
+    /// let (mut sim, counter) = build();
+    /// sim.step_until_time(10.);
+    /// let checkpoint = sim.checkpoint();
+    /// assert_eq!(checkpoint.pending_events(), 1);
+    /// sim.step_until_time(20.);
+    ///
+    /// let (mut resumed, resumed_counter) = build();
+    /// resumed.restore_checkpoint(&checkpoint);
+    /// assert_eq!(resumed.time(), 10.);
+    /// resumed.step_until_time(20.);
+    /// assert_eq!(resumed_counter.borrow().ticks, counter.borrow().ticks);
+    /// assert_eq!(resumed.event_count(), sim.event_count());
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        let saved = self.sim_state.borrow().save_checkpoint();
+        let states = self
+            .component_states
+            .iter()
+            .map(|(id, component)| (self.lookup_name(*id), component.borrow().state()))
+            .collect();
+        let inputs = self.inputs.borrow().positions().into_iter().collect();
+        let warmup = self.warmup.borrow().save();
+        self.checkpoint_codecs
+            .encode(saved, states, inputs, self.processed_events.get(), warmup)
+    }
+
+    /// Restores the simulation state from the checkpoint returned by [`checkpoint`](Self::checkpoint).
+    ///
+    /// The simulation must be built in the same way as the saved one, i.e. have the same components with the same
+    /// identifiers and registered states, see [`checkpoint`](crate::checkpoint) module. The pending events of
+    /// the simulation, including the events merged into coalesced bursts and the events emitted via
+    /// [`emit_after`](SimulationContext::emit_after), are replaced by the ones from the checkpoint, and the states
+    /// of components are restored via [`ComponentState::restore_state`]. The outstanding events of
+    /// [contracts](crate::contracts) declared in the simulation are recomputed from the restored pending events, and
+    /// the counter of processed events and the state of [warmup period](crate::warmup) are restored as well. The
+    /// [inputs](crate::input) skip the events read before the checkpoint. The simulation is considered started, so the startup hooks registered via
+    /// [`register_startable`](Self::register_startable) are not called, and the
+    /// [time advance listeners](Self::add_time_advance_listener) are notified via
+    /// [`TimeAdvanceListener::on_rewind`].
+    ///
+    /// Panics if some component from the checkpoint does not exist or has another identifier, if the state of some
+    /// component from the checkpoint is not registered, if some input from the checkpoint is not registered or has
+    /// less events than were read before the checkpoint, or if the type of some pending event is not registered via
+    /// [`register_checkpoint_event`](Self::register_checkpoint_event).
+    pub fn restore_checkpoint(&mut self, checkpoint: &Checkpoint) {
+        self.restore_checkpoint_inner(checkpoint);
+    }
+
+    fn restore_checkpoint_inner(&self, checkpoint: &Checkpoint) {
+        self.sim_state
+            .borrow()
+            .assert_checkpoint_components(&checkpoint.components);
+        let saved = self.checkpoint_codecs.decode(checkpoint);
+        let mut states = Vec::new();
+        for (name, state) in checkpoint.states.iter() {
+            let component = self
+                .sim_state
+                .borrow()
+                .try_lookup_id(name)
+                .and_then(|id| self.component_states.iter().find(|(state_id, _)| *state_id == id))
+                .map(|(_, component)| component.clone())
+                .unwrap_or_else(|| panic!("State of component {} from checkpoint is not registered", name));
+            states.push((component, state.clone()));
+        }
+        self.sim_state.borrow_mut().load_checkpoint(saved);
+        for (id, taken) in checkpoint.input_positions() {
+            self.inputs.borrow_mut().restore_position(id, taken, checkpoint.time);
+        }
+        for (component, state) in states {
+            component.borrow_mut().restore_state(state);
+        }
+        self.processed_events.set(checkpoint.processed_events);
+        self.warmup.borrow_mut().restore(checkpoint.warmup());
+        self.started.set(true);
+        self.last_observed_step
+            .set((self.time(), self.sim_state.borrow().event_count()));
+        self.time_advances.borrow_mut().rewind(self.time());
+    }
+
+    /// Saves the checkpoint of the current simulation state to the file in JSON format.
+    ///
+    /// See [`checkpoint`](Self::checkpoint).
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) {
+        let file = File::create(path).expect("Failed to create checkpoint file");
+        serde_json::to_writer(BufWriter::new(file), &self.checkpoint()).expect("Failed to write checkpoint");
+    }
+
+    /// Loads the checkpoint from the file saved via [`save_checkpoint`](Self::save_checkpoint) and restores
+    /// the simulation state from it.
+    ///
+    /// See [`restore_checkpoint`](Self::restore_checkpoint).
+    pub fn load_checkpoint<P: AsRef<Path>>(&mut self, path: P) {
+        let file = File::open(path).expect("Failed to open checkpoint file");
+        let checkpoint: Checkpoint = serde_json::from_reader(BufReader::new(file)).expect("Failed to read checkpoint");
+        self.restore_checkpoint(&checkpoint);
+    }
+
+    /// Enables saving the checkpoints in memory every `interval` processed events, keeping at most
+    /// `max_checkpoints` latest ones, which allows stepping backwards via [`step_back`](Self::step_back).
+    ///
+    /// The checkpoints are saved before the processing of the next event, and the saving is postponed while the
+    /// checkpoint cannot be saved, see [`checkpoint`](Self::checkpoint). The checkpoints saved earlier are
+    /// discarded.
+    ///
+    /// Panics if `interval` or `max_checkpoints` is zero.
+    pub fn enable_auto_checkpoints(&mut self, interval: u64, max_checkpoints: usize) {
+        *self.auto_checkpoints.borrow_mut() = Some(AutoCheckpoints::new(interval, max_checkpoints));
+        self.enable_step_hooks();
+    }
+
+    /// Disables saving the checkpoints enabled via [`enable_auto_checkpoints`](Self::enable_auto_checkpoints) and
+    /// discards the saved ones.
+    pub fn disable_auto_checkpoints(&mut self) {
+        self.auto_checkpoints.borrow_mut().take();
+    }
+
+    fn save_auto_checkpoint(&self) {
+        let processed = self.processed_events.get();
+        if !self
+            .auto_checkpoints
+            .borrow()
+            .as_ref()
+            .is_some_and(|checkpoints| checkpoints.is_due(processed))
+            || !self.sim_state.borrow().can_save_checkpoint()
+        {
+            return;
+        }
+        let checkpoint = AutoCheckpoint {
+            checkpoint: self.checkpoint(),
+            logical_clocks: self.sim_state.borrow().logical_clocks().cloned(),
+        };
+        if let Some(checkpoints) = self.auto_checkpoints.borrow_mut().as_mut() {
+            checkpoints.push(processed, checkpoint);
+        }
+    }
+
+    /// Steps the simulation back by the specified number of processed events.
+    ///
+    /// The simulation is restored from the latest automatic checkpoint saved before the target event,
+    /// see [`enable_auto_checkpoints`](Self::enable_auto_checkpoints), and re-executed forward until the number of
+    /// processed events is decreased by `events`, so the next step processes the same event as the original run
+    /// did after that point. The re-execution is deterministic only if all components with mutable state are
+    /// registered via [`register_state`](Self::register_state), since only their states are restored from the
+    /// checkpoint, see [`restore_checkpoint`](Self::restore_checkpoint). The logical clocks are restored along with
+    /// the checkpoint.
+    ///
+    /// The re-executed events have no side effects: they are not written to the trace file and memory trace, do not
+    /// update the metrics and producer statistics, and are not checked by the breakpoints, watchpoints, limits and
+    /// divergence guard, while the contract violations and peer errors found during the re-execution are discarded.
+    /// The automatic checkpoints are not saved again, and if the re-execution passes the end of the
+    /// [warmup period](Self::set_warmup_time), the period is marked as ended without resetting the statistics or
+    /// calling the callbacks again. The [time advance listeners](Self::add_time_advance_listener) are not notified
+    /// about the re-executed events, instead they are notified once via [`TimeAdvanceListener::on_rewind`] from the
+    /// last reported time to the time after stepping back. The step observers and logs see the re-executed events
+    /// again.
+    ///
+    /// Returns false and does nothing if there is no checkpoint saved early enough.
+    ///
+    /// Panics if automatic checkpoints are not enabled or the simulation has external inputs which have read more
+    /// events since the checkpoint, because they cannot be rewound.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_json::{json, Value};
+    ///
+    /// use simcore::snapshot::ComponentState;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// pub struct Deposit {
+    ///     amount: i64,
+    /// }
+    ///
+    /// struct Account {
+    ///     balance: i64,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Account {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Deposit { amount } => {
+    ///                 self.balance += amount;
+    ///                 let amount = self.ctx.gen_range(-10..20);
+    ///                 self.ctx.emit_self(Deposit { amount }, 1.);
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// impl ComponentState for Account {
+    ///     fn state(&self) -> Value {
+    ///         json!(self.balance)
+    ///     }
+    ///
+    ///     fn restore_state(&mut self, state: Value) {
+    ///         self.balance = state.as_i64().unwrap();
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.register_checkpoint_event::<Deposit>();
+    /// sim.enable_auto_checkpoints(10, 5);
+    /// let ctx = sim.create_context("account");
+    /// ctx.emit_self(Deposit { amount: 100 }, 1.);
+    /// let account = Rc::new(RefCell::new(Account { balance: 0, ctx }));
+    /// sim.add_handler("account", account.clone());
+    /// sim.register_state("account", account.clone());
+    ///
+    /// let mut balances = Vec::new();
+    /// for _ in 0..25 {
+    ///     sim.step();
+    ///     balances.push(account.borrow().balance);
+    /// }
+    ///
+    /// // go back to the state after 18 processed events
+    /// assert!(sim.step_back(7));
+    /// assert_eq!(sim.processed_event_count(), 18);
+    /// assert_eq!(sim.time(), 18.);
+    /// assert_eq!(account.borrow().balance, balances[17]);
+    ///
+    /// // the run is reproduced when continued
+    /// sim.step();
+    /// assert_eq!(account.borrow().balance, balances[18]);
+    /// ```
+    pub fn step_back(&self, events: u64) -> bool {
+        let processed = self.processed_events.get();
+        let Some(target) = processed.checked_sub(events) else {
+            return false;
+        };
+        let rewound = self
+            .auto_checkpoints
+            .borrow_mut()
+            .as_mut()
+            .expect("Automatic checkpoints are not enabled, see Simulation::enable_auto_checkpoints")
+            .rewind(target);
+        let Some((_, auto_checkpoint)) = rewound else {
+            return false;
+        };
+        let mut time_advances = self.time_advances.take();
+        self.restore_checkpoint_inner(&auto_checkpoint.checkpoint);
+        if let Some(clocks) = auto_checkpoint.logical_clocks {
+            self.sim_state.borrow_mut().restore_logical_clocks(clocks);
+        }
+
+        let effects = self.sim_state.borrow_mut().suspend_effects();
+        let breakpoints = self.breakpoints.replace(Breakpoints::default());
+        let watchpoints = self.watchpoints.take();
+        let limits = self.limits.take();
+        let divergence_guard = self.divergence_guard.take();
+        let peer_errors = self.pending_peer_errors.take();
+        while self.processed_events.get() < target && self.reexecute_step() {}
+        self.sim_state.borrow_mut().resume_effects(effects);
+        self.breakpoints.replace(breakpoints);
+        self.watchpoints.replace(watchpoints);
+        self.limits.replace(limits);
+        self.divergence_guard.replace(divergence_guard);
+        self.pending_peer_errors.replace(peer_errors);
+        time_advances.rewind(self.time());
+        self.time_advances.replace(time_advances);
+        true
+    }
+
+    // Performs the step of re-execution in `step_back`, skipping the checks with side effects done in `step`.
+    fn reexecute_step(&self) -> bool {
+        if self.advance_to_warmup_end(None) {
+            self.mark_warmup_end();
+        }
+        let result = self.step_inner();
+        self.dispatched_component.set(None);
+        self.sim_state.borrow_mut().take_contract_violations();
+        self.pending_peer_errors.borrow_mut().clear();
+        result
+    }
+
+    /// Registers the event handler for component with specified name, which can be copied into the branches of
+    /// the simulation, see [`branch`](Self::branch). Returns the component Id.
+    ///
+    /// The component state is also registered for snapshots, see [`register_state`](Self::register_state).
+    pub fn add_branchable_handler<S, C>(&mut self, name: S, component: Rc<RefCell<C>>) -> Id
+    where
+        S: AsRef<str>,
+        C: BranchComponent + 'static,
+    {
+        let name = name.as_ref();
+        let id = self.add_handler(name, component.clone());
+        self.register_state(name, component.clone());
+        let source = component.clone();
+        let branch: BranchFn = Box::new(move |sim: &mut Simulation, name: &str| {
+            let ctx = sim.create_context(name);
+            let copy = source.borrow().branch(ctx);
+            sim.add_branchable_handler(name, Rc::new(RefCell::new(copy)));
+        });
+        self.branchable_components
+            .push(BranchableComponent { id, branch, component });
+        id
+    }
+
+    /// Returns the component registered via [`add_branchable_handler`](Self::add_branchable_handler),
+    /// e.g. to inspect or modify the component copied into the branch.
+    ///
+    /// Panics if the component with such name is not registered as branchable or has a different type.
+    pub fn branch_component<C: 'static>(&self, name: &str) -> Rc<RefCell<C>> {
+        let id = self.lookup_id(name);
+        let entry = self
+            .branchable_components
+            .iter()
+            .find(|c| c.id == id)
+            .unwrap_or_else(|| panic!("Component {} is not branchable", name));
+        entry
+            .component
+            .clone()
+            .downcast::<RefCell<C>>()
+            .unwrap_or_else(|_| panic!("Component {} has a different type", name))
+    }
+
+    /// Creates an independent copy of the simulation, which continues from the current state.
+    ///
+    /// The branch gets the copy of the simulation state, including the pending events and the state of the random
+    /// number generator, so running the branch without changes reproduces the original run. The components
+    /// registered via [`add_branchable_handler`](Self::add_branchable_handler) are copied via
+    /// [`BranchComponent::branch`] and can be accessed via [`branch_component`](Self::branch_component).
+    /// The contexts of components without handlers can be obtained in the branch via
+    /// [`create_context`](Self::create_context) with the same names.
+    ///
+    /// The step observers, watchpoints and states registered via [`register_state`](Self::register_state)
+    /// for non-branchable components are not copied.
+    ///
+    /// Panics if some event handler is not registered as branchable, the simulation has continuous models or
+    /// external inputs, or in async mode if there are alive asynchronous tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use serde_json::{json, Value};
+    /// use simcore::branch::BranchComponent;
+    /// use simcore::snapshot::ComponentState;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Crash {}
+    ///
+    /// struct Server {
+    ///     served: u32,
+    ///     crashed: bool,
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Request {} => {
+    ///                 if !self.crashed {
+    ///                     self.served += 1;
+    ///                 }
+    ///                 self.ctx.emit_self(Request {}, self.ctx.gen_range(0.5..1.5));
+    ///             }
+    ///             Crash {} => {
+    ///                 self.crashed = true;
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// impl ComponentState for Server {
+    ///     fn state(&self) -> Value {
+    ///         json!({"served": self.served, "crashed": self.crashed})
+    ///     }
+    /// }
+    ///
+    /// impl BranchComponent for Server {
+    ///     fn branch(&self, ctx: SimulationContext) -> Self {
+    ///         Self { served: self.served, crashed: self.crashed, ctx }
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("server");
+    /// ctx.emit_self(Request {}, 0.);
+    /// sim.add_branchable_handler("server", Rc::new(RefCell::new(Server { served: 0, crashed: false, ctx })));
+    /// sim.step_until_time(50.);
+    ///
+    /// // explore the future with the server crash
+    /// let mut faulty = sim.branch();
+    /// faulty.create_context("operator").emit(Crash {}, faulty.lookup_id("server"), 10.);
+    /// faulty.step_until_time(100.);
+    ///
+    /// // the same future without changes
+    /// let mut normal = sim.branch();
+    /// normal.step_until_time(100.);
+    /// sim.step_until_time(100.);
+    /// assert_eq!(normal.snapshot(), sim.snapshot());
+    ///
+    /// let server = faulty.branch_component::<Server>("server");
+    /// assert!(server.borrow().crashed);
+    /// assert!(server.borrow().served < sim.branch_component::<Server>("server").borrow().served);
+    /// ```
+    pub fn branch(&self) -> Simulation {
+        for (id, handler) in self.handlers.iter().enumerate() {
+            if handler.is_some() && !self.branchable_components.iter().any(|c| c.id == id as Id) {
+                panic!(
+                    "Component {} does not support branching, register it via add_branchable_handler",
+                    self.lookup_name(id as Id)
+                );
             }
         }
-
-        fn deliver_event_via_handler(&self, event: Event) {
-            if let Some(handler_opt) = self.handlers.get(event.dst as usize) {
-                self.log_event(&event);
-                if let Some(handler) = handler_opt {
-                    handler.borrow_mut().on(event);
-                } else {
-                    log_undelivered_event(event);
-                }
-            } else {
-                log_undelivered_event(event);
-            }
+        assert!(
+            self.continuous_models.is_empty(),
+            "Simulation with continuous models cannot be branched"
+        );
+        assert!(
+            self.inputs.borrow().is_empty(),
+            "Simulation with external inputs cannot be branched"
+        );
+        assert!(
+            self.multi_resolution.is_empty(),
+            "Simulation with multi-resolution components cannot be branched"
+        );
+        let (sim_state, executor) = branch_inner(&self.sim_state.borrow());
+        let mut branch = Simulation {
+            step_hooks: sim_state.step_hooks(),
+            sim_state: Rc::new(RefCell::new(sim_state)),
+            handlers: Vec::new(),
+            batching_enabled: Vec::new(),
+            metadata_logged: Cell::new(self.metadata_logged.get()),
+            step_observers: Vec::new(),
+            last_observed_step: Cell::new((0., 0)),
+            time_advances: RefCell::new(TimeAdvances::default()),
+            continuous_models: Vec::new(),
+            continuous_lookahead: self.continuous_lookahead,
+            component_states: Vec::new(),
+            branchable_components: Vec::new(),
+            startable_components: Vec::new(),
+            // the components of the branch are copied in their current state
+            started: Cell::new(self.started.get()),
+            checkpoint_codecs: self.checkpoint_codecs.clone(),
+            auto_checkpoints: RefCell::new(None),
+            inputs: RefCell::new(InputGateway::default()),
+            stop_condition: RefCell::new(None),
+            watchpoints: RefCell::new(Vec::new()),
+            watchpoint_count: 0,
+            watchpoint_hits: RefCell::new(Vec::new()),
+            limits: RefCell::new(None),
+            limit_violation: RefCell::new(None),
+            divergence_guard: RefCell::new(None),
+            divergence: RefCell::new(None),
+            multi_resolution: Vec::new(),
+            warmup: RefCell::new(WarmupPeriod::default()),
+            processed_events: Cell::new(0),
+            dispatched_component: Cell::new(None),
+            contract_violations: RefCell::new(Vec::new()),
+            pending_peer_errors: Rc::new(RefCell::new(Vec::new())),
+            peer_error: RefCell::new(None),
+            breakpoints: RefCell::new(Breakpoints::default()),
+            pause_requested: Cell::new(false),
+            executor,
+        };
+        branch.handlers.resize_with(self.handlers.len(), || None);
+        branch.batching_enabled = self.batching_enabled.clone();
+        for component in self.branchable_components.iter() {
+            let name = self.lookup_name(component.id);
+            (component.branch)(&mut branch, &name);
         }
-    );
+        branch
+    }
 
-    async_mode_enabled!(
-        fn step_inner(&self) -> bool {
-            if self.process_task() {
-                return true;
-            }
+    /// Same as [`branch`](Self::branch), but the random number generator of the branch uses the specified seed,
+    /// which allows exploring different futures from the same state.
+    pub fn branch_with_seed(&self, seed: u64) -> Simulation {
+        let branch = self.branch();
+        branch.sim_state.borrow_mut().reseed(seed);
+        branch
+    }
 
-            let has_timer = self.sim_state.borrow_mut().peek_timer().is_some();
-            let has_event = self.sim_state.borrow_mut().peek_event().is_some();
-            if !has_timer && !has_event {
-                return false;
-            }
-            if !has_timer {
-                self.process_event();
-                return true;
-            }
-            if !has_event {
-                self.process_timer();
-                return true;
-            }
+    /// Cancels events that satisfy the given predicate function.
+    ///
+    /// Note that already processed events cannot be cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::{Event, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp1_ctx = sim.create_context("comp1");
+    /// let mut comp2_ctx = sim.create_context("comp2");
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 1.0);
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 2.0);
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 3.0);
+    /// sim.cancel_events(|e| e.id < 2);
+    /// sim.step();
+    /// assert_eq!(sim.time(), 3.0);
+    /// ```
+    pub fn cancel_events<F>(&mut self, pred: F)
+    where
+        F: Fn(&Event) -> bool,
+    {
+        self.sim_state.borrow_mut().cancel_events(pred);
+    }
 
-            let next_timer_time = self.sim_state.borrow_mut().peek_timer().unwrap().time;
-            let next_event_time = self.sim_state.borrow_mut().peek_event().unwrap().time;
-            if next_event_time <= next_timer_time {
-                self.process_event();
-            } else {
-                self.process_timer();
-            }
+    /// Cancels events that satisfy the given predicate function and returns them.
+    ///
+    /// Note that already processed events cannot be cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::{Event, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut comp1_ctx = sim.create_context("comp1");
+    /// let mut comp2_ctx = sim.create_context("comp2");
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 1.0);
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 2.0);
+    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 3.0);
+    /// let cancelled = sim.cancel_and_get_events(|e| e.id < 2);
+    /// assert_eq!(cancelled.len(), 2);
+    /// sim.step();
+    /// assert_eq!(sim.time(), 3.0);
+    /// ```
+    pub fn cancel_and_get_events<F>(&mut self, pred: F) -> Vec<Event>
+    where
+        F: Fn(&Event) -> bool,
+    {
+        self.sim_state.borrow_mut().cancel_and_get_events(pred)
+    }
 
-            true
-        }
+    /// Returns a copy of pending events sorted by time.
+    ///
+    /// Currently used for model checking in dslab-mp.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::{Event, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct SomeEvent {
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let mut ctx1 = sim.create_context("comp1");
+    /// let mut ctx2 = sim.create_context("comp2");
+    /// let event1 = ctx1.emit(SomeEvent {}, ctx2.id(), 1.0);
+    /// let event2 = ctx2.emit(SomeEvent {}, ctx1.id(), 1.0);
+    /// let event3 = ctx1.emit(SomeEvent {}, ctx2.id(), 2.0);
+    /// let events = sim.dump_events();
+    /// assert_eq!(events.len(), 3);
+    /// assert_eq!((events[0].id, events[0].time), (event1, 1.0));
+    /// assert_eq!((events[1].id, events[1].time), (event2, 1.0));
+    /// assert_eq!((events[2].id, events[2].time), (event3, 2.0));
+    /// ```
+    pub fn dump_events(&self) -> Vec<Event> {
+        self.sim_state.borrow().dump_events()
+    }
 
-        fn process_event(&self) {
-            let event = self.sim_state.borrow_mut().next_event().unwrap();
-            let event_key = self
-                .sim_state
-                .borrow()
-                .get_key_getter(event.data.type_id())
-                .map(|getter| getter(event.data.as_ref()));
-            if self.sim_state.borrow().has_event_promise_for(&event, event_key) {
-                self.log_event(&event);
-                self.sim_state.borrow_mut().complete_event_promise(event, event_key);
-                self.process_task();
-            } else {
-                self.deliver_event_via_handler(event);
-            }
-        }
+    /// Writes a human-readable listing of pending events sorted by time.
+    ///
+    /// Each line contains the event time, identifier, source and destination component names, type and payload
+    /// summary. The listed events can be filtered via [`QueueDumpOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::queue_dump::QueueDumpOptions;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     size: u32,
+    /// }
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Timeout {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let client_ctx = sim.create_context("client");
+    /// let server_ctx = sim.create_context("server");
+    /// client_ctx.emit(Request { size: 10 }, server_ctx.id(), 1.5);
+    /// client_ctx.emit_self(Timeout {}, 10.);
+    ///
+    /// let mut output = Vec::new();
+    /// sim.dump_queue(&mut output, &QueueDumpOptions::new()).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// let lines: Vec<_> = output.lines().collect();
+    /// assert_eq!(lines[0], "Pending events: 2 total, 2 matching, 2 shown");
+    /// assert!(lines[1].ends_with("client -> server Request {\"size\":10}"));
+    /// assert!(lines[2].ends_with("client -> client Timeout {}"));
+    ///
+    /// let mut output = Vec::new();
+    /// let options = QueueDumpOptions {
+    ///     event_type: Some("Timeout".to_owned()),
+    ///     ..Default::default()
+    /// };
+    /// sim.dump_queue(&mut output, &options).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert_eq!(output.lines().count(), 2);
+    /// assert!(output.contains("Timeout"));
+    /// ```
+    pub fn dump_queue<W: std::io::Write>(&self, mut writer: W, options: &QueueDumpOptions) -> std::io::Result<()> {
+        let state = self.sim_state.borrow();
+        let events = state.dump_events();
+        write_queue(&mut writer, &events, options, |id| state.lookup_name(id))
+    }
 
-        fn process_task(&self) -> bool {
-            self.executor.process_task()
-        }
+    /// Returns the metadata of this simulation run.
+    ///
+    /// The metadata is logged at the info level before processing the first event and is written to each file
+    /// with spilled events, so the labels and configuration should be set before running the simulation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     nodes: u32,
+    ///     policy: String,
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.set_run_label("experiment", "scheduling");
+    /// sim.set_run_config(&Config { nodes: 16, policy: "fifo".to_owned() });
+    ///
+    /// let metadata = sim.run_metadata();
+    /// assert_eq!(metadata.seed, 123);
+    /// assert_eq!(metadata.version, env!("CARGO_PKG_VERSION"));
+    /// assert_eq!(metadata.labels["experiment"], "scheduling");
+    /// assert!(metadata.start_time > 0.);
+    ///
+    /// // the same configuration produces the same hash
+    /// let mut other = Simulation::new(456);
+    /// other.set_run_config(&Config { nodes: 16, policy: "fifo".to_owned() });
+    /// assert_eq!(other.run_metadata().config_hash, metadata.config_hash);
+    /// ```
+    pub fn run_metadata(&self) -> RunMetadata {
+        self.sim_state.borrow().run_metadata().clone()
+    }
 
-        fn process_timer(&self) {
-            let next_timer = self.sim_state.borrow_mut().next_timer().unwrap();
-            next_timer.complete();
-            // drop timer to release the pointer to the state
-            drop(next_timer);
-            self.process_task();
+    /// Sets the label of this run included in its metadata, replacing the previous value for the same key.
+    ///
+    /// See [`run_metadata`](Self::run_metadata) for examples.
+    pub fn set_run_label(&mut self, key: &str, value: &str) {
+        self.sim_state.borrow_mut().set_run_label(key, value);
+    }
+
+    /// Sets the configuration of this run, whose hash is included in its metadata.
+    ///
+    /// The hash is computed from the JSON representation of the configuration by a platform-independent hash
+    /// function, so the hashes of runs on different platforms can be compared.
+    ///
+    /// See [`run_metadata`](Self::run_metadata) for examples.
+    pub fn set_run_config<C: Serialize>(&mut self, config: &C) {
+        self.sim_state.borrow_mut().set_run_config(config);
+    }
+
+    /// Registers the observer notified before and after each simulation step which processes an event.
+    ///
+    /// See [`StepObserver`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::observer::{StepDelta, StepObserver};
+    /// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Ping {
+    ///     count: u32,
+    /// }
+    ///
+    /// struct Component {
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Component {
+    ///     fn on(&mut self, event: Event) {
+    ///         let ping = event.data.downcast_ref::<Ping>().unwrap();
+    ///         for _ in 0..ping.count {
+    ///             self.ctx.emit_self(Ping { count: 0 }, 2.);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Observer {
+    ///     steps: Vec<(f64, u64)>,
+    /// }
+    ///
+    /// impl StepObserver for Observer {
+    ///     fn after_step(&mut self, delta: &StepDelta) {
+    ///         self.steps.push((delta.time_advance, delta.emitted_events));
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// ctx.emit_self(Ping { count: 2 }, 1.);
+    /// sim.add_handler("comp", Rc::new(RefCell::new(Component { ctx })));
+    /// let observer = Rc::new(RefCell::new(Observer::default()));
+    /// sim.add_step_observer(observer.clone());
+    ///
+    /// sim.step_until_no_events();
+    /// assert_eq!(observer.borrow().steps, vec![(1., 2), (2., 0), (0., 0)]);
+    /// ```
+    pub fn add_step_observer(&mut self, observer: Rc<RefCell<dyn StepObserver>>) {
+        if self.step_observers.is_empty() {
+            let state = self.sim_state.borrow();
+            self.last_observed_step.set((state.time(), state.event_count()));
         }
+        self.step_observers.push(observer);
+        self.enable_step_hooks();
+    }
+
+    /// Registers the listener of simulation time advances, e.g. for driving the animation of the simulation.
+    ///
+    /// The listener receives the old and new simulation time along with the events processed at the new time,
+    /// see [`TimeAdvanceListener`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::observer::TimeAdvanceListener;
+    /// use simcore::{Event, Simulation};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Frame {}
+    ///
+    /// // plays back the simulation at the specified speed
+    /// struct Player {
+    ///     speed: f64,
+    ///     frames: Vec<(f64, f64, usize)>,
+    /// }
+    ///
+    /// impl TimeAdvanceListener for Player {
+    ///     fn on_time_advance(&mut self, old_time: f64, new_time: f64, events: &[Event]) {
+    ///         let _pause = (new_time - old_time) / self.speed;
+    ///         // std::thread::sleep(std::time::Duration::from_secs_f64(_pause));
+    ///         self.frames.push((old_time, new_time, events.len()));
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let ctx = sim.create_context("comp");
+    /// let player = Rc::new(RefCell::new(Player { speed: 10., frames: Vec::new() }));
+    /// sim.add_time_advance_listener(player.clone());
+    ///
+    /// ctx.emit_self(Frame {}, 1.);
+    /// ctx.emit_self(Frame {}, 1.);
+    /// ctx.emit_self(Frame {}, 2.5);
+    /// sim.step_until_no_events();
+    /// sim.step_until_time(4.);
+    /// assert_eq!(player.borrow().frames, vec![(0., 1., 2), (1., 2.5, 1), (2.5, 4., 0)]);
+    /// ```
+    pub fn add_time_advance_listener(&mut self, listener: Rc<RefCell<dyn TimeAdvanceListener>>) {
+        let time = self.time();
+        self.time_advances.borrow_mut().add(listener, time);
+        self.enable_step_hooks();
+    }
 
-        fn deliver_event_via_handler(&self, event: Event) {
-            if let Some(handler_opt) = self.handlers.get(event.dst as usize) {
-                self.log_event(&event);
-                if let Some(handler) = handler_opt {
-                    match handler {
-                        EventHandlerImpl::Mutable(handler) => handler.borrow_mut().on(event),
-                        EventHandlerImpl::Static(handler) => handler.clone().on(event),
-                    }
-                } else {
-                    log_undelivered_event(event);
-                }
-            } else {
-                log_undelivered_event(event);
-            }
-        }
-    );
+    /// Enables recording of processed events into the in-memory trace.
+    ///
+    /// Only the events dispatched after this call are recorded.
+    /// See [`trace`](Self::trace) for examples.
+    pub fn enable_memory_trace(&mut self) {
+        self.sim_state.borrow_mut().enable_memory_trace();
+    }
 
-    fn log_event(&self, event: &Event) {
-        if log_enabled!(Trace) {
-            let src_name = self.lookup_name(event.src);
-            let dst_name = self.lookup_name(event.dst);
-            trace!(
-                target: &dst_name,
-                "[{:.3} {} {}] {}",
-                event.time,
-                crate::log::get_colored("EVENT", colored::Color::BrightBlack),
-                dst_name,
-                json!({"type": type_name(&event.data).unwrap(), "data": event.data, "src": src_name})
-            );
-        }
+    /// Returns the in-memory trace of processed events.
+    ///
+    /// The returned reference borrows the simulation state, so it should be dropped before continuing the simulation.
+    ///
+    /// Panics if the in-memory trace is not enabled via [`enable_memory_trace`](Self::enable_memory_trace).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use serde::Serialize;
+    /// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Response {}
+    ///
+    /// struct Server {
+    ///     ctx: SimulationContext,
+    /// }
+    ///
+    /// impl EventHandler for Server {
+    ///     fn on(&mut self, event: Event) {
+    ///         cast!(match event.data {
+    ///             Request {} => {
+    ///                 self.ctx.emit(Response {}, event.src, 0.5);
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.enable_memory_trace();
+    /// let server_ctx = sim.create_context("server");
+    /// let server_id = sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx })));
+    /// let client_ctx = sim.create_context("client");
+    /// let request_id = client_ctx.emit(Request {}, server_id, 1.);
+    /// client_ctx.emit(Request {}, server_id, 2.);
+    /// sim.step_until_no_events();
+    ///
+    /// let trace = sim.trace();
+    /// assert_eq!(trace.len(), 4);
+    /// let responses = trace.query().event_type::<Response>().dst(client_ctx.id()).records();
+    /// assert_eq!(responses.len(), 2);
+    /// assert_eq!(trace.query().time_range(0., 1.5).count(), 2);
+    ///
+    /// let chain = trace.causes(responses[0].id);
+    /// assert_eq!(chain.len(), 2);
+    /// assert_eq!(chain[0].id, request_id);
+    /// assert_eq!(trace.effects(request_id).len(), 1);
+    /// ```
+    pub fn trace(&self) -> Ref<'_, MemoryTrace> {
+        Ref::map(self.sim_state.borrow(), |state| {
+            state.memory_trace().expect("Memory trace is not enabled")
+        })
     }
 
-    async_mode_enabled!(
-        /// Spawns a new asynchronous task.
-        ///
-        /// The task's type lifetime must be `'static`.
-        /// This means that the spawned task must not contain any references to data owned outside the task.
-        ///
-        /// To spawn methods inside simulation components use [`SimulationContext::spawn`].
-        ///
-        /// # Examples
-        ///
-        /// ```rust
-        /// use simcore::Simulation;
-        ///
-        /// let mut sim = Simulation::new(123);
-        ///
-        /// let ctx = sim.create_context("client");
-        ///
-        /// sim.spawn(async move {
-        ///     let initial_time = ctx.time();
-        ///     ctx.sleep(5.).await;
-        ///     assert_eq!(ctx.time(), 5.);
-        /// });
-        ///
-        /// sim.step_until_no_events();
-        /// assert_eq!(sim.time(), 5.);
-        /// ```
-        pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
-            self.sim_state.borrow_mut().spawn(future);
-        }
+    /// Sets the sampling of events recorded in the in-memory trace, which is used for events without
+    /// the sampling set via [`set_event_trace_sampling`](Self::set_event_trace_sampling).
+    ///
+    /// The events which are not recorded are counted in [`MemoryTrace::skipped`].
+    ///
+    /// Panics if the in-memory trace is not enabled or the sampling parameters are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::trace::TraceSampling;
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Packet {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Failure {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.enable_memory_trace();
+    /// // record 1% of events, but all failures
+    /// sim.set_trace_sampling(TraceSampling::Probability(0.01));
+    /// sim.set_event_trace_sampling::<Failure>(TraceSampling::All);
+    ///
+    /// let ctx = sim.create_context("node");
+    /// for i in 0..10000 {
+    ///     ctx.emit_self(Packet {}, i as f64);
+    /// }
+    /// ctx.emit_self(Failure {}, 5000.);
+    /// sim.step_until_no_events();
+    ///
+    /// let trace = sim.trace();
+    /// assert_eq!(trace.query().event_type::<Failure>().count(), 1);
+    /// let packets = trace.query().event_type::<Packet>().count();
+    /// assert!(packets > 50 && packets < 150);
+    /// assert_eq!(trace.len() as u64 + trace.skipped(), 10001);
+    /// ```
+    pub fn set_trace_sampling(&mut self, sampling: TraceSampling) {
+        self.sim_state
+            .borrow_mut()
+            .memory_trace_mut()
+            .expect("Memory trace is not enabled")
+            .set_sampling(sampling);
+    }
 
-        /// Registers a function that extracts [`EventKey`] from events of a type `T`.
-        ///
-        /// Calling this function is required before using [`SimulationContext::recv_event_by_key`] or
-        /// [`SimulationContext::recv_event_by_key_from`] with type `T`. See examples for these methods.
-        pub fn register_key_getter_for<T: EventData>(&self, key_getter: impl Fn(&T) -> EventKey + 'static) {
-            self.sim_state.borrow_mut().register_key_getter_for::<T>(key_getter);
-        }
+    /// Sets the sampling of events with payload of type `T` recorded in the in-memory trace.
+    ///
+    /// Panics if the in-memory trace is not enabled or the sampling parameters are invalid.
+    /// See [`set_trace_sampling`](Self::set_trace_sampling) for examples.
+    pub fn set_event_trace_sampling<T: EventData>(&mut self, sampling: TraceSampling) {
+        self.sim_state
+            .borrow_mut()
+            .memory_trace_mut()
+            .expect("Memory trace is not enabled")
+            .set_event_sampling::<T>(sampling);
+    }
 
-        /// Creates an [`UnboundedQueue`] for producer-consumer communication.
-        ///
-        /// This queue is designed to support convenient communication between several asynchronous tasks
-        /// within a single simulation component. This enables implementing the component logic as a set of
-        /// communicating concurrent activities.
-        ///
-        /// The use of this primitive for inter-component communication is discouraged in favor of passing events
-        /// directly or via intermediate components.
-        ///
-        /// # Examples
-        ///
-        /// ```rust
-        /// use std::rc::Rc;
-        /// use std::cell::RefCell;
-        /// use simcore::{Simulation, SimulationContext, Event, StaticEventHandler};
-        /// use simcore::async_mode::UnboundedQueue;
-        ///
-        /// struct Message {
-        ///     payload: u32,
-        /// }
-        ///
-        /// struct Component {
-        ///     ctx: SimulationContext,
-        ///     queue: UnboundedQueue<Message>,
-        /// }
-        ///
-        /// impl Component {
-        ///     fn start(self: Rc<Self>) {
-        ///         self.ctx.spawn(self.clone().producer());
-        ///         self.ctx.spawn(self.clone().consumer());
-        ///     }
-        ///
-        ///     async fn producer(self: Rc<Self>) {
-        ///         for i in 0..10 {
-        ///             self.ctx.sleep(5.).await;
-        ///             self.queue.put(Message {payload: i});
-        ///         }
-        ///     }
-        ///
-        ///     async fn consumer(self: Rc<Self>) {
-        ///         for i in 0..10 {
-        ///             let msg = self.queue.take().await;
-        ///             assert_eq!(msg.payload, i);
-        ///         }
-        ///     }
-        /// }
-        ///
-        /// impl StaticEventHandler for Component {
-        ///     fn on(self: Rc<Self>, event: Event) {
-        ///     }
-        /// }
-        ///
-        /// let mut sim = Simulation::new(123);
-        ///
-        /// let comp = Rc::new(Component {
-        ///     ctx: sim.create_context("comp"),
-        ///     queue: sim.create_queue("comp_queue")
-        /// });
-        /// sim.add_static_handler("comp", comp.clone());
-        ///
-        /// comp.start();
-        /// sim.step_until_no_events();
-        ///
-        /// assert_eq!(sim.time(), 50.);
-        /// ```
-        pub fn create_queue<T, S>(&mut self, name: S) -> UnboundedQueue<T>
-        where
-            S: AsRef<str>,
-        {
-            UnboundedQueue::new(self.create_context(name))
-        }
-    );
+    /// Enables recording of emitted, processed and canceled events to JSON Lines file, see
+    /// [`trace_file`](crate::trace_file) module.
+    ///
+    /// Only the events emitted, processed or canceled after this call are recorded. Enabling the trace file again
+    /// closes the previous file. The file is flushed when the recording is disabled via
+    /// [`disable_trace_file`](Self::disable_trace_file) or the simulation is dropped.
+    ///
+    /// Panics if the file cannot be created.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use serde_json::json;
+    ///
+    /// use simcore::trace_file::{read_trace_file, TraceEventKind, TraceFileConfig};
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     size: u64,
+    /// }
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Heartbeat {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// let path = std::env::temp_dir().join("simcore-trace-file-example.jsonl");
+    /// let mut config = TraceFileConfig::new(&path);
+    /// config.event_types = Some(vec!["Request".to_string()]);
+    /// sim.enable_trace_file(config);
+    ///
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// client.emit(Request { size: 10 }, server.id(), 1.);
+    /// let canceled = client.emit(Request { size: 20 }, server.id(), 2.);
+    /// client.emit_self(Heartbeat {}, 0.5);
+    /// client.cancel_event(canceled);
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// let trace = read_trace_file(&path);
+    /// let kinds: Vec<_> = trace.records.iter().map(|r| (r.kind, r.id)).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     vec![
+    ///         (TraceEventKind::Emitted, 0),
+    ///         (TraceEventKind::Emitted, 1),
+    ///         (TraceEventKind::Canceled, 1),
+    ///         (TraceEventKind::Processed, 0),
+    ///     ]
+    /// );
+    /// assert_eq!(trace.records[3].time, 1.);
+    /// assert_eq!(trace.records[3].src, "client");
+    /// assert_eq!(trace.records[3].dst, "server");
+    /// assert_eq!(trace.records[3].data, Some(json!({"size": 10})));
+    /// ```
+    pub fn enable_trace_file(&mut self, config: TraceFileConfig) {
+        self.sim_state.borrow_mut().enable_trace_file(config);
+    }
 
-    /// Performs the specified number of steps through the simulation.
+    /// Stops recording events to the trace file and flushes it.
     ///
-    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the specified number of
-    /// steps is made, or `false` is returned (no more pending events).
+    /// See [`enable_trace_file`](Self::enable_trace_file).
+    pub fn disable_trace_file(&mut self) {
+        self.sim_state.borrow_mut().disable_trace_file();
+    }
+
+    /// Limits the number of log and trace file records produced by each component per window of simulation time,
+    /// see [`rate_limit`](crate::rate_limit) module.
     ///
-    /// Returns `true` if there could be more pending events and `false` otherwise.
+    /// Passing `None` removes the limit. The limits of components set via
+    /// [`set_component_output_rate_limit`](Self::set_component_output_rate_limit) take precedence over this limit.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use serde::Serialize;
+    /// use simcore::rate_limit::{OutputKind, OutputRateLimit};
+    /// use simcore::trace_file::{read_trace_file, TraceFileConfig};
     /// use simcore::Simulation;
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// struct Ping {}
+    ///
+    /// let path = std::env::temp_dir().join(format!("simcore-rate-limit-doc-{}.jsonl", std::process::id()));
+    /// let mut sim = Simulation::new(123);
+    /// sim.enable_trace_file(TraceFileConfig::new(&path));
+    /// sim.set_output_rate_limit(Some(OutputRateLimit::new(1., 10)));
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// // burst of 100 events at time 1 and a single event at time 2
+    /// for _ in 0..100 {
+    ///     client.emit(Ping {}, server.id(), 1.);
     /// }
+    /// client.emit(Ping {}, server.id(), 2.);
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// // 10 emitted events of client at time 0, 10 processed events of server at time 1 and 1 at time 2
+    /// let trace = read_trace_file(&path);
+    /// assert_eq!(trace.records.len(), 21);
+    /// assert_eq!(sim.suppressed_output(OutputKind::TraceFile), 181);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn set_output_rate_limit(&mut self, limit: Option<OutputRateLimit>) {
+        self.sim_state.borrow_mut().set_output_rate_limit(limit);
+    }
+
+    /// Overrides the output rate limit set via [`set_output_rate_limit`](Self::set_output_rate_limit) for the
+    /// component with the specified name.
+    ///
+    /// Passing `None` removes the limit of component. The override is discarded when the component is removed.
+    ///
+    /// Panics if component with such name does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::rate_limit::{OutputKind, OutputRateLimit};
+    /// use simcore::trace_file::{read_trace_file, TraceEventKind, TraceFileConfig};
+    /// use simcore::Simulation;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Ping {}
     ///
+    /// let path = std::env::temp_dir().join(format!("simcore-rate-limit-comp-doc-{}.jsonl", std::process::id()));
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.3);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.4);
-    /// let mut status = sim.steps(2);
-    /// assert!(status);
-    /// assert_eq!(sim.time(), 1.3);
-    /// status = sim.steps(2);
-    /// assert!(!status);
-    /// assert_eq!(sim.time(), 1.4);
+    /// let mut config = TraceFileConfig::new(&path);
+    /// config.kinds = vec![TraceEventKind::Processed];
+    /// sim.enable_trace_file(config);
+    /// sim.set_output_rate_limit(Some(OutputRateLimit::new(1., 1)));
+    /// let client = sim.create_context("client");
+    /// let monitor = sim.create_context("monitor");
+    /// let server = sim.create_context("server");
+    /// sim.set_component_output_rate_limit("monitor", None);
+    /// for _ in 0..5 {
+    ///     client.emit(Ping {}, server.id(), 1.);
+    ///     client.emit(Ping {}, monitor.id(), 1.);
+    /// }
+    /// sim.step_until_no_events();
+    /// sim.disable_trace_file();
+    ///
+    /// let trace = read_trace_file(&path);
+    /// assert_eq!(trace.records.iter().filter(|r| r.dst == "server").count(), 1);
+    /// assert_eq!(trace.records.iter().filter(|r| r.dst == "monitor").count(), 5);
+    /// assert_eq!(sim.suppressed_output(OutputKind::TraceFile), 4);
+    /// # std::fs::remove_file(&path).unwrap();
     /// ```
-    pub fn steps(&mut self, step_count: u64) -> bool {
-        for _ in 0..step_count {
-            if !self.step() {
-                return false;
-            }
-        }
-        true
+    pub fn set_component_output_rate_limit(&mut self, name: &str, limit: Option<OutputRateLimit>) {
+        let id = self.lookup_id(name);
+        self.sim_state.borrow_mut().set_component_output_rate_limit(id, limit);
     }
 
-    /// Steps through the simulation until there are no pending events left.
+    /// Returns the number of records of the specified output dropped due to the
+    /// [output rate limit](Self::set_output_rate_limit).
+    pub fn suppressed_output(&self, kind: OutputKind) -> u64 {
+        self.sim_state.borrow().suppressed_output(kind)
+    }
+
+    /// Enables counting of emitted events by their source components and types, see
+    /// [`producers`](crate::producers) module.
     ///
-    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until `false` is returned.
+    /// Only the events emitted after this call are counted. Enabling the statistics again resets them.
+    ///
+    /// Panics if the resolution is not positive.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use serde::Serialize;
+    ///
+    /// use simcore::producers::ProducerStatsConfig;
     /// use simcore::Simulation;
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// struct Request {}
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Retry {}
+    ///
+    /// let mut sim = Simulation::new(123);
+    /// sim.enable_producer_stats(ProducerStatsConfig::new(1.));
+    /// let client = sim.create_context("client");
+    /// let proxy = sim.create_context("proxy");
+    /// client.emit_self(Request {}, 0.5);
+    /// sim.step_until_time(10.);
+    /// for _ in 0..5 {
+    ///     proxy.emit_self(Retry {}, 1.);
     /// }
+    /// client.emit_self(Request {}, 1.);
     ///
-    /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.2);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.3);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.4);
-    /// sim.step_until_no_events();
-    /// assert_eq!(sim.time(), 1.4);
+    /// // events emitted in [9, 10]
+    /// let report = sim.top_producers(1., 10);
+    /// assert_eq!(report.total, 6);
+    /// assert_eq!(report.components, vec![("proxy".to_string(), 5), ("client".to_string(), 1)]);
+    /// assert_eq!(report.sources[0], ("proxy".to_string(), "Retry".to_string(), 5));
+    ///
+    /// let report = sim.top_producers_between(0., 5., 10);
+    /// assert_eq!(report.event_types, vec![("Request".to_string(), 1)]);
     /// ```
-    pub fn step_until_no_events(&mut self) {
-        while self.step() {}
+    pub fn enable_producer_stats(&mut self, config: ProducerStatsConfig) {
+        self.sim_state.borrow_mut().enable_producer_stats(config);
     }
 
-    /// Steps through the simulation with duration limit.
+    /// Returns the report of top event producers in the window of the specified duration ending at the current
+    /// time, with at most `limit` entries in each list.
     ///
-    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the next event
-    /// time is above the specified threshold (`initial_time + duration`) or there are no pending events left.
+    /// See [`enable_producer_stats`](Self::enable_producer_stats) for examples.
     ///
-    /// This method also advances the simulation time to `initial_time + duration`. Note that the resulted time may
-    /// slightly differ from the expected value due to the floating point errors. This issue can be avoided by using
-    /// the [`step_until_time`](Self::step_until_time) method.
+    /// Panics if the producer statistics are not enabled.
+    pub fn top_producers(&self, window: f64, limit: usize) -> ProducerReport {
+        let time = self.time();
+        self.top_producers_between(time - window, time, limit)
+    }
+
+    /// Returns the report of top event producers in the specified time window, with at most `limit` entries
+    /// in each list.
     ///
-    /// Returns `true` if there could be more pending events and `false` otherwise.
+    /// The window is extended to the boundaries of time buckets, see [`ProducerStatsConfig::resolution`].
+    ///
+    /// Panics if the producer statistics are not enabled.
+    pub fn top_producers_between(&self, from: f64, to: f64, limit: usize) -> ProducerReport {
+        self.sim_state.borrow().producer_report(from, to, limit)
+    }
+
+    /// Returns the metrics collected by components, see [`metrics`](crate::metrics) module.
+    ///
+    /// The report includes the metrics of removed components.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use serde::Serialize;
     /// use simcore::Simulation;
     ///
-    /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
-    /// }
-    ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
-    /// let mut status = sim.step_for_duration(1.8);
-    /// assert_eq!(sim.time(), 1.8);
-    /// assert!(status); // there are more events
-    /// status = sim.step_for_duration(1.8);
-    /// assert_eq!(sim.time(), 3.6);
-    /// assert!(!status); // there are no more events
+    /// let client = sim.create_context("client");
+    /// let server = sim.create_context("server");
+    /// client.counter_add("sent", 2.);
+    /// server.counter_add("received", 1.);
+    /// server.gauge_set("load", 0.5);
+    ///
+    /// let report = sim.metrics();
+    /// assert_eq!(report.names(), vec!["load", "received", "sent"]);
+    /// assert_eq!(report.get("client", "sent").unwrap().as_f64(), 2.);
+    /// assert!(report.get("client", "received").is_none());
+    /// println!("{}", report);
     /// ```
-    pub fn step_for_duration(&mut self, duration: f64) -> bool {
-        let end_time = self.sim_state.borrow().time() + duration;
-        self.step_until_time(end_time)
+    pub fn metrics(&self) -> MetricsReport {
+        self.sim_state.borrow().metrics_report()
     }
 
-    /// Steps through the simulation until the specified time.
+    /// Sets the upper bounds of buckets of histogram metrics with the specified name in all components,
+    /// see [`HistogramValue::buckets`](crate::metrics::HistogramValue::buckets).
     ///
-    /// This is a convenient wrapper around [`step`](Self::step), which invokes this method until the next event
-    /// time is above the specified time or there are no pending events left.
+    /// See [`SimulationContext::histogram_record`] for examples.
     ///
-    /// This method also advances the simulation time to the specified time.
+    /// Panics if the bounds are not increasing or if the values of histogram are already recorded.
+    pub fn set_histogram_buckets(&mut self, name: &str, bounds: &[f64]) {
+        self.sim_state
+            .borrow_mut()
+            .metrics_mut()
+            .set_histogram_bounds(name, bounds.to_vec());
+    }
+
+    /// Enables the recording of updates of metrics collected by components, which are returned by
+    /// [`metric_series`](Self::metric_series) and exported by [`export_metrics`](Self::export_metrics).
     ///
-    /// Returns `true` if there could be more pending events and `false` otherwise.
+    /// Only the updates made after this call are recorded. The recorded updates are cleared at the end of the
+    /// [warmup period](Self::set_warmup_time).
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use serde::Serialize;
     /// use simcore::Simulation;
     ///
-    /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
-    /// }
-    ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
-    /// let mut status = sim.step_until_time(1.8);
-    /// assert_eq!(sim.time(), 1.8);
-    /// assert!(status); // there are more events
-    /// status = sim.step_until_time(3.6);
-    /// assert_eq!(sim.time(), 3.6);
-    /// assert!(!status); // there are no more events
+    /// sim.enable_metric_series();
+    /// let ctx = sim.create_context("comp");
+    /// ctx.counter_add("requests", 2.);
+    /// sim.step_until_time(5.);
+    /// ctx.counter_inc("requests");
+    ///
+    /// let series = sim.metric_series();
+    /// assert_eq!(series["comp"]["requests"], vec![(0., 2.), (5., 3.)]);
     /// ```
-    pub fn step_until_time(&mut self, time: f64) -> bool {
-        self.step_until_time_inner(time)
+    pub fn enable_metric_series(&mut self) {
+        self.sim_state.borrow_mut().metrics_mut().enable_series();
     }
 
-    async_mode_disabled!(
-        fn step_until_time_inner(&mut self, time: f64) -> bool {
-            let mut result = true;
-            loop {
-                if let Some(event) = self.sim_state.borrow_mut().peek_event() {
-                    if event.time > time {
-                        break;
-                    }
-                } else {
-                    result = false;
-                    break;
-                }
-                self.step();
-            }
-            self.sim_state.borrow_mut().set_time(time);
-            result
-        }
-    );
-
-    async_mode_enabled!(
-        fn step_until_time_inner(&mut self, time: f64) -> bool {
-            let mut result;
-            loop {
-                while self.process_task() {}
-
-                result = false;
-                let mut step = false;
-
-                if let Some(event) = self.sim_state.borrow_mut().peek_event() {
-                    result = true;
-                    if event.time <= time {
-                        step = true;
-                    }
-                }
-
-                if let Some(timer) = self.sim_state.borrow_mut().peek_timer() {
-                    result = true;
-                    if timer.time <= time {
-                        step = true;
-                    }
-                }
-
-                if step {
-                    self.step();
-                } else {
-                    break;
-                }
-            }
-            self.sim_state.borrow_mut().set_time(time);
-            result
-        }
-    );
+    /// Returns the recorded updates of metrics, see [`enable_metric_series`](Self::enable_metric_series).
+    pub fn metric_series(&self) -> MetricSeries {
+        self.sim_state.borrow().metric_series()
+    }
 
-    /// Returns a random float in the range _[0, 1)_
-    /// using the simulation-wide random number generator.
+    /// Writes the metrics collected by components along with the run metadata to the files specified in the config,
+    /// see [`metrics_export`] module. Returns the paths of the written files.
+    ///
+    /// The series table is written only if the recording of series is enabled via
+    /// [`enable_metric_series`](Self::enable_metric_series).
+    ///
+    /// Panics if the files cannot be written.
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use simcore::metrics_export::MetricsExportConfig;
     /// use simcore::Simulation;
     ///
     /// let mut sim = Simulation::new(123);
-    /// let f: f64 = sim.rand();
-    /// assert!(f >= 0.0 && f < 1.0);
+    /// sim.set_run_label("policy", "fifo");
+    /// let ctx = sim.create_context("server");
+    /// ctx.counter_add("requests", 10.);
+    /// ctx.histogram_record("latency", 1.5);
+    ///
+    /// let dir = std::env::temp_dir().join(format!("simcore-metrics-doc-{}", std::process::id()));
+    /// let mut config = MetricsExportConfig::new(&dir);
+    /// config.prefix = "run1_".to_owned();
+    /// let paths = sim.export_metrics(&config);
+    /// assert_eq!(paths, vec![dir.join("run1_summary.csv")]);
+    ///
+    /// let summary = std::fs::read_to_string(&paths[0]).unwrap();
+    /// let lines: Vec<&str> = summary.lines().collect();
+    /// assert_eq!(
+    ///     lines[0],
+    ///     "seed,config_hash,policy,component,metric,kind,value,count,sum,min,max,mean,std_dev"
+    /// );
+    /// assert_eq!(lines[1], "123,,fifo,server,latency,histogram,1.5,1,1.5,1.5,1.5,1.5,0");
+    /// assert_eq!(lines[2], "123,,fifo,server,requests,counter,10,,,,,,");
+    /// std::fs::remove_dir_all(&dir).unwrap();
     /// ```
-    pub fn rand(&mut self) -> f64 {
-        self.sim_state.borrow_mut().rand()
+    pub fn export_metrics(&self, config: &MetricsExportConfig) -> Vec<PathBuf> {
+        let state = self.sim_state.borrow();
+        let series = state.metrics().series_enabled().then(|| state.metric_series());
+        metrics_export::export(config, &state.metrics_report(), series.as_ref(), state.run_metadata())
+            .unwrap_or_else(|e| panic!("Failed to export metrics: {}", e))
     }
 
-    /// Returns a random number in the specified range
-    /// using the simulation-wide random number generator.
+    /// Enables logical clocks of components of the specified kind.
+    ///
+    /// The clock of event source is incremented on emitting an event, and the clock of event destination is merged
+    /// with the event timestamp and incremented on its dispatching, see [`logical_clock`](crate::logical_clock)
+    /// module for details. Only the events emitted after enabling the clocks are stamped, so the clocks should be
+    /// enabled before running the simulation. The timestamps of processed events are recorded in the
+    /// [memory trace](Self::enable_memory_trace).
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::logical_clock::{LogicalClockKind, LogicalTime};
     /// use simcore::Simulation;
     ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Message {}
+    ///
     /// let mut sim = Simulation::new(123);
-    /// let n: u32 = sim.gen_range(1..=10);
-    /// assert!(n >= 1 && n <= 10);
-    /// let f: f64 = sim.gen_range(0.1..0.5);
-    /// assert!(f >= 0.1 && f < 0.5);
+    /// sim.enable_logical_clocks(LogicalClockKind::Vector);
+    /// sim.enable_memory_trace();
+    /// let a = sim.create_context("a");
+    /// let b = sim.create_context("b");
+    /// let c = sim.create_context("c");
+    ///
+    /// let a_to_b = a.emit(Message {}, b.id(), 1.);
+    /// assert_eq!(sim.event_logical_time(a_to_b), Some(LogicalTime::Vector(vec![1])));
+    /// let c_to_b = c.emit(Message {}, b.id(), 2.);
+    /// sim.step_until_no_events();
+    ///
+    /// assert_eq!(b.logical_time(), LogicalTime::Vector(vec![1, 2, 1]));
+    /// let trace = sim.trace();
+    /// let a_to_b_time = trace.get(a_to_b).unwrap().logical_time.clone().unwrap();
+    /// let c_to_b_time = trace.get(c_to_b).unwrap().logical_time.clone().unwrap();
+    /// assert!(a_to_b_time.is_concurrent(&c_to_b_time));
+    /// assert!(a_to_b_time.happened_before(&b.logical_time()));
     /// ```
-    pub fn gen_range<T, R>(&mut self, range: R) -> T
-    where
-        T: SampleUniform,
-        R: SampleRange<T>,
-    {
-        self.sim_state.borrow_mut().gen_range(range)
+    pub fn enable_logical_clocks(&mut self, kind: LogicalClockKind) {
+        self.sim_state.borrow_mut().enable_logical_clocks(kind);
     }
 
-    /// Returns a random value from the specified distribution
-    /// using the simulation-wide random number generator.
-    pub fn sample_from_distribution<T, Dist: Distribution<T>>(&mut self, dist: &Dist) -> T {
-        self.sim_state.borrow_mut().sample_from_distribution(dist)
+    /// Returns the current time of logical clock of the component with specified id.
+    ///
+    /// Panics if logical clocks are not enabled.
+    /// See [`enable_logical_clocks`](Self::enable_logical_clocks) for examples.
+    pub fn logical_time(&self, id: Id) -> LogicalTime {
+        self.sim_state.borrow().logical_time(id)
     }
 
-    /// Returns a random alphanumeric string of specified length
-    /// using the simulation-wide random number generator.
-    pub fn random_string(&mut self, len: usize) -> String {
-        self.sim_state.borrow_mut().random_string(len)
+    /// Returns the logical timestamp of pending event with specified id.
+    ///
+    /// Returns `None` if logical clocks are not enabled, the event is already processed or was emitted before
+    /// enabling the clocks. See [`enable_logical_clocks`](Self::enable_logical_clocks) for examples.
+    pub fn event_logical_time(&self, event_id: EventId) -> Option<LogicalTime> {
+        self.sim_state.borrow().event_logical_time(event_id)
     }
 
-    /// Returns the total number of created events.
+    /// Declares that the events emitted by component `src` to component `dst` are delivered in the order of emission.
     ///
-    /// Note that cancelled events are also counted here.
+    /// The delivery order of such events is checked during the simulation, and each violation, i.e. delivery of the
+    /// event after the event emitted later, is reported according to the [`OrderingPolicy`] set via
+    /// [`set_ordering_policy`](Self::set_ordering_policy). By default, the simulation panics with the description
+    /// of both events. See the [`ordering`](crate::ordering) module for details.
+    ///
+    /// Panics if component with such name does not exist.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use serde::Serialize;
+    /// use simcore::ordering::OrderingPolicy;
     /// use simcore::Simulation;
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
-    /// }
+    /// struct Packet {}
     ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp_ctx = sim.create_context("comp");
-    /// assert_eq!(sim.time(), 0.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 1.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 2.0);
-    /// comp_ctx.emit_self(SomeEvent {}, 3.5);
-    /// assert_eq!(sim.event_count(), 3);
+    /// let sender = sim.create_context("sender");
+    /// let receiver = sim.create_context("receiver");
+    /// sim.assume_fifo("sender", "receiver");
+    /// sim.set_ordering_policy(OrderingPolicy::Record);
+    ///
+    /// // the first packet is delayed, so the second one overtakes it
+    /// let first = sender.emit(Packet {}, receiver.id(), 5.);
+    /// let second = sender.emit(Packet {}, receiver.id(), 1.);
+    /// sim.step_until_no_events();
+    ///
+    /// let violations = sim.take_ordering_violations();
+    /// assert_eq!(violations.len(), 1);
+    /// assert_eq!(violations[0].event_id, first);
+    /// assert_eq!(violations[0].overtaking_event_id, second);
+    /// assert_eq!(violations[0].time, 5.);
     /// ```
-    pub fn event_count(&self) -> u64 {
-        self.sim_state.borrow().event_count()
+    pub fn assume_fifo<S1, S2>(&mut self, src: S1, dst: S2)
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let src = self.lookup_id(src.as_ref());
+        let dst = self.lookup_id(dst.as_ref());
+        self.sim_state.borrow_mut().assume_fifo(src, dst);
     }
 
-    /// Cancels events that satisfy the given predicate function.
+    /// Declares that the events emitted by each component to component `dst` are delivered in the order of emission.
     ///
-    /// Note that already processed events cannot be cancelled.
+    /// This is equivalent to calling [`assume_fifo`](Self::assume_fifo) for each source of events, including
+    /// the components created later. The order of events from different sources is not checked.
+    ///
+    /// Panics if component with such name does not exist.
+    pub fn assume_fifo_to<S>(&mut self, dst: S)
+    where
+        S: AsRef<str>,
+    {
+        let dst = self.lookup_id(dst.as_ref());
+        self.sim_state.borrow_mut().assume_fifo_to(dst);
+    }
+
+    /// Sets the policy of reporting the violations of ordering assumptions declared via
+    /// [`assume_fifo`](Self::assume_fifo).
+    pub fn set_ordering_policy(&mut self, policy: OrderingPolicy) {
+        self.sim_state.borrow_mut().set_ordering_policy(policy);
+    }
+
+    /// Returns the violations of ordering assumptions recorded since the previous call
+    /// if the [`OrderingPolicy::Record`] policy is used.
+    ///
+    /// See [`assume_fifo`](Self::assume_fifo) for examples.
+    pub fn take_ordering_violations(&mut self) -> Vec<OrderingViolation> {
+        self.sim_state.borrow_mut().take_ordering_violations()
+    }
+
+    /// Sets the arity of the heap storing pending events emitted via [`SimulationContext::emit`] and similar methods.
+    ///
+    /// By default, the standard binary heap (arity 2) is used. Heaps with larger arity (e.g. 4 or 8) have smaller
+    /// depth and better memory locality, which can improve the performance of simulations with large numbers of
+    /// pending events.
+    /// The arity does not affect the order of event processing and can be changed at any time.
+    ///
+    /// Panics if `arity` is less than 2.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use serde::Serialize;
-    /// use simcore::{Event, Simulation, SimulationContext};
+    /// use simcore::Simulation;
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
-    /// }
+    /// struct Tick {}
     ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp1_ctx = sim.create_context("comp1");
-    /// let mut comp2_ctx = sim.create_context("comp2");
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 1.0);
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 2.0);
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 3.0);
-    /// sim.cancel_events(|e| e.id < 2);
-    /// sim.step();
-    /// assert_eq!(sim.time(), 3.0);
+    /// sim.set_event_heap_arity(4);
+    /// let ctx = sim.create_context("comp");
+    /// for i in 0..100 {
+    ///     ctx.emit_self(Tick {}, ((i * 37) % 100) as f64);
+    /// }
+    /// let events = sim.dump_events();
+    /// assert!(events.windows(2).all(|w| w[0].time <= w[1].time));
+    /// sim.step_until_no_events();
+    /// assert_eq!(sim.time(), 99.);
     /// ```
-    pub fn cancel_events<F>(&mut self, pred: F)
-    where
-        F: Fn(&Event) -> bool,
-    {
-        self.sim_state.borrow_mut().cancel_events(pred);
+    pub fn set_event_heap_arity(&mut self, arity: usize) {
+        self.sim_state.borrow_mut().set_event_heap_arity(arity);
     }
 
-    /// Cancels events that satisfy the given predicate function and returns them.
+    /// Enables spilling of pending events to disk.
     ///
-    /// Note that already processed events cannot be cancelled.
+    /// When the number of pending events in memory exceeds [`SpillConfig::memory_limit`], only the nearest-time
+    /// fraction of them is kept in memory, while the rest is written to files in [`SpillConfig::dir`]. The spilled
+    /// events are loaded back when they become the nearest pending events, so the order of event processing is the
+    /// same as without spilling.
+    ///
+    /// Only events of types registered via [`register_spillable_event`](Self::register_spillable_event) are spilled.
+    /// Note that methods inspecting all pending events, such as [`cancel_events`](Self::cancel_events) and
+    /// [`dump_events`](Self::dump_events), have to read the spilled events from disk.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use serde::Serialize;
-    /// use simcore::{Event, Simulation, SimulationContext};
+    /// use serde::{Deserialize, Serialize};
+    /// use simcore::Simulation;
+    /// use simcore::spill::SpillConfig;
     ///
-    /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// struct Arrival {
+    ///     job_id: u64,
     /// }
     ///
     /// let mut sim = Simulation::new(123);
-    /// let mut comp1_ctx = sim.create_context("comp1");
-    /// let mut comp2_ctx = sim.create_context("comp2");
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 1.0);
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 2.0);
-    /// comp1_ctx.emit(SomeEvent {}, comp2_ctx.id(), 3.0);
-    /// let cancelled = sim.cancel_and_get_events(|e| e.id < 2);
-    /// assert_eq!(cancelled.len(), 2);
-    /// sim.step();
-    /// assert_eq!(sim.time(), 3.0);
+    /// let trace_ctx = sim.create_context("trace");
+    /// let dir = std::env::temp_dir().join("simcore-spill-example");
+    /// sim.register_spillable_event::<Arrival>();
+    /// sim.enable_event_spilling(SpillConfig::new(&dir, 100));
+    ///
+    /// for job_id in 0..1000 {
+    ///     trace_ctx.emit_ordered_self(Arrival { job_id }, job_id as f64);
+    /// }
+    /// assert_eq!(sim.dump_events().len(), 1000);
+    ///
+    /// sim.step_until_no_events();
+    /// assert_eq!(sim.time(), 999.);
     /// ```
-    pub fn cancel_and_get_events<F>(&mut self, pred: F) -> Vec<Event>
+    pub fn enable_event_spilling(&mut self, config: SpillConfig) {
+        self.sim_state.borrow_mut().enable_event_spilling(config);
+    }
+
+    /// Registers event type `T` whose events can be spilled to disk.
+    ///
+    /// See [`enable_event_spilling`](Self::enable_event_spilling).
+    pub fn register_spillable_event<T>(&mut self)
     where
-        F: Fn(&Event) -> bool,
+        T: EventData + DeserializeOwned,
     {
-        self.sim_state.borrow_mut().cancel_and_get_events(pred)
+        self.sim_state.borrow_mut().register_spillable_event::<T>();
     }
 
-    /// Returns a copy of pending events sorted by time.
+    /// Registers event type `T` with precomputed logging metadata.
     ///
-    /// Currently used for model checking in dslab-mp.
+    /// Events of registered types are logged using the serializer generated by
+    /// [`loggable_event!`](crate::loggable_event!) macro, which reduces the overhead of event logging.
+    /// The registered type is also assigned the short type name returned by
+    /// [`lookup_event_type_name`](Self::lookup_event_type_name).
     ///
     /// # Examples
     ///
     /// ```rust
     /// use serde::Serialize;
-    /// use simcore::{Event, Simulation, SimulationContext};
+    /// use simcore::{loggable_event, Simulation};
     ///
     /// #[derive(Clone, Serialize)]
-    /// struct SomeEvent {
+    /// struct Request {
+    ///     id: u64,
     /// }
     ///
+    /// loggable_event!(Request { id });
+    ///
     /// let mut sim = Simulation::new(123);
-    /// let mut ctx1 = sim.create_context("comp1");
-    /// let mut ctx2 = sim.create_context("comp2");
-    /// let event1 = ctx1.emit(SomeEvent {}, ctx2.id(), 1.0);
-    /// let event2 = ctx2.emit(SomeEvent {}, ctx1.id(), 1.0);
-    /// let event3 = ctx1.emit(SomeEvent {}, ctx2.id(), 2.0);
+    /// sim.register_loggable_event::<Request>();
+    ///
+    /// let ctx = sim.create_context("comp");
+    /// ctx.emit_self(Request { id: 1 }, 1.);
     /// let events = sim.dump_events();
-    /// assert_eq!(events.len(), 3);
-    /// assert_eq!((events[0].id, events[0].time), (event1, 1.0));
-    /// assert_eq!((events[1].id, events[1].time), (event2, 1.0));
-    /// assert_eq!((events[2].id, events[2].time), (event3, 2.0));
+    /// let type_id = sim.lookup_event_type_id(&events[0]);
+    /// assert_eq!(sim.lookup_event_type_name(type_id), "Request");
     /// ```
-    pub fn dump_events(&self) -> Vec<Event> {
-        self.sim_state.borrow().dump_events()
+    pub fn register_loggable_event<T: LoggableEvent>(&mut self) {
+        self.sim_state.borrow_mut().register_loggable_event::<T>();
     }
 }
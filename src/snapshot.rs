@@ -0,0 +1,47 @@
+//! Snapshots of component states.
+//!
+//! Components can expose their internal state by implementing the [`ComponentState`] trait and registering
+//! themselves via [`Simulation::register_state`](crate::Simulation::register_state). The simulation can then
+//! collect a [`StateSnapshot`] of all registered components via
+//! [`Simulation::snapshot`](crate::Simulation::snapshot), which is useful for debugging, checking model invariants
+//! and comparing the states of different runs. The snapshot is taken between simulation steps, so the states of
+//! all components correspond to the same point of the run.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Component with the state which can be included in simulation snapshots.
+pub trait ComponentState {
+    /// Returns the current state of the component as JSON value.
+    fn state(&self) -> Value;
+
+    /// Restores the state of the component from the value returned by [`state`](Self::state).
+    ///
+    /// Called when loading checkpoints, see [`checkpoint`](crate::checkpoint). The default implementation panics,
+    /// so the components whose states are saved in checkpoints must implement it.
+    fn restore_state(&mut self, _state: Value) {
+        panic!("Component does not support restoring its state from checkpoint");
+    }
+}
+
+/// Snapshot of the states of components registered via
+/// [`Simulation::register_state`](crate::Simulation::register_state).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StateSnapshot {
+    /// Simulation time of the snapshot.
+    pub time: f64,
+    /// Number of events created before the snapshot, see [`Simulation::event_count`](crate::Simulation::event_count).
+    pub event_count: u64,
+    /// States of the components by their names.
+    pub components: BTreeMap<String, Value>,
+}
+
+impl StateSnapshot {
+    /// Returns the state of the component with the specified name,
+    /// or `None` if the component state is not registered.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.components.get(name)
+    }
+}
@@ -0,0 +1,131 @@
+//! Special functions used by the statistical analysis of metrics and the fitting of workload distributions.
+
+use std::f64::consts::PI;
+
+// Coefficients of Lanczos approximation with g = 7 and 9 terms, which is accurate to about 15 significant digits.
+const LANCZOS_G: f64 = 7.;
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+// Natural logarithm of the gamma function for positive arguments computed via Lanczos approximation.
+// The arguments below 0.5 are handled via the reflection formula.
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1. - x);
+    }
+    let x = x - 1.;
+    let t = x + LANCZOS_G + 0.5;
+    let series = LANCZOS_COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.));
+    0.5 * (2. * PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// Gamma function for positive arguments.
+pub(crate) fn gamma(x: f64) -> f64 {
+    ln_gamma(x).exp()
+}
+
+// Error function approximation with maximum error 1.5e-7 (Abramowitz and Stegun, formula 7.1.26).
+pub(crate) fn erf(x: f64) -> f64 {
+    let t = 1. / (1. + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let value = 1. - poly * (-x * x).exp();
+    if x >= 0. {
+        value
+    } else {
+        -value
+    }
+}
+
+// Regularized incomplete beta function I_x(a, b).
+//
+// The function is computed from the continued fraction for I_x(a, b) when x < (a + 1) / (a + b + 2), and from the
+// one for 1 - I_{1-x}(b, a) otherwise, which converge quickly in these ranges (Abramowitz and Stegun, formula 26.5.8).
+pub(crate) fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln();
+    if x < (a + 1.) / (a + b + 2.) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1. - ln_front.exp() * beta_continued_fraction(b, a, 1. - x) / b
+    }
+}
+
+// Evaluates the continued fraction of the incomplete beta function via the modified Lentz's method, where the tiny
+// value replaces zero denominators.
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPS: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+    let mut c = 1.;
+    let mut d = 1. - (a + b) * x / (a + 1.);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1. / d;
+    let mut result = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        // even and odd terms of the fraction
+        for numerator in [
+            m * (b - m) * x / ((a + 2. * m - 1.) * (a + 2. * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2. * m) * (a + 2. * m + 1.)),
+        ] {
+            d = 1. + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1. + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1. / d;
+            result *= d * c;
+        }
+        if (d * c - 1.).abs() < EPS {
+            break;
+        }
+    }
+    result
+}
+
+// Probability that the absolute value of Student's t-distributed variable with `df` degrees of freedom exceeds `t`,
+// i.e. the two-sided p-value of t statistic.
+pub(crate) fn t_two_sided_tail(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2., 0.5, df / (df + t * t))
+}
+
+// Returns the quantile of Student's t-distribution for the probability in the range [0.5, 1) found by bisection.
+pub(crate) fn t_quantile(probability: f64, df: f64) -> f64 {
+    let tail = 2. * (1. - probability);
+    let mut low = 0.;
+    let mut high = 1.;
+    while t_two_sided_tail(high, df) > tail {
+        high *= 2.;
+    }
+    for _ in 0..100 {
+        let mid = (low + high) / 2.;
+        if t_two_sided_tail(mid, df) > tail {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.
+}
@@ -0,0 +1,325 @@
+//! Spilling of pending events to disk.
+//!
+//! Simulations with a very large number of pre-scheduled future events (e.g. trace-driven runs) may not fit the
+//! pending event set into memory. When spilling is enabled via
+//! [`Simulation::enable_event_spilling`](crate::Simulation::enable_event_spilling), the simulation keeps only the
+//! nearest-time fraction of pending events in memory and writes the rest to files in the configured directory.
+//! Spilled events are transparently loaded back when they become the nearest pending events, so the order of event
+//! processing is not affected.
+//!
+//! Since event payloads are only required to be serializable, the framework must know how to restore the payload
+//! of a spilled event. Therefore only events of types registered via
+//! [`Simulation::register_spillable_event`](crate::Simulation::register_spillable_event) are spilled, while other
+//! events always stay in memory.
+//!
+//! Each file with spilled events starts with a line containing the [run metadata](crate::metadata).
+
+use std::any::TypeId;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::component::Id;
+use crate::event::{Event, EventData, EventId};
+use crate::metadata::RunMetadata;
+
+/// Configuration of spilling pending events to disk.
+#[derive(Clone)]
+pub struct SpillConfig {
+    /// Directory where the files with spilled events are stored.
+    pub dir: PathBuf,
+    /// Maximum number of pending events kept in memory before spilling is triggered.
+    pub memory_limit: usize,
+    /// Fraction of pending events (the nearest ones by time) kept in memory when spilling is triggered.
+    pub keep_fraction: f64,
+}
+
+impl SpillConfig {
+    /// Creates a config with specified directory and memory limit and default keep fraction of 0.5.
+    pub fn new<P: Into<PathBuf>>(dir: P, memory_limit: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            memory_limit,
+            keep_fraction: 0.5,
+        }
+    }
+}
+
+type DecodeFn = fn(serde_json::Value) -> serde_json::Result<Box<dyn EventData>>;
+
+fn decode<T: EventData + DeserializeOwned>(value: serde_json::Value) -> serde_json::Result<Box<dyn EventData>> {
+    serde_json::from_value::<T>(value).map(|data| Box::new(data) as Box<dyn EventData>)
+}
+
+#[derive(Serialize)]
+struct SpilledEventRef<'a> {
+    id: EventId,
+    time: f64,
+    src: Id,
+    dst: Id,
+    priority: i64,
+    ordered: bool,
+    #[serde(rename = "type")]
+    type_name: &'a str,
+    data: &'a dyn EventData,
+}
+
+#[derive(Deserialize)]
+struct SpilledEvent {
+    id: EventId,
+    time: f64,
+    src: Id,
+    dst: Id,
+    priority: i64,
+    ordered: bool,
+    #[serde(rename = "type")]
+    type_name: String,
+    data: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct SpillHeader<'a> {
+    metadata: &'a RunMetadata,
+}
+
+// File with spilled events sorted by time.
+struct SpillRun {
+    path: PathBuf,
+    first_time: f64,
+    first_priority: i64,
+    first_id: EventId,
+}
+
+impl SpillRun {
+    fn cmp_first(&self, time: f64, priority: i64, id: EventId) -> std::cmp::Ordering {
+        self.first_time
+            .total_cmp(&time)
+            .then_with(|| self.first_priority.cmp(&priority))
+            .then_with(|| self.first_id.cmp(&id))
+    }
+}
+
+// Used to generate unique names of run files for each EventSpill instance.
+static INSTANCE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// Stores pending events spilled to disk and the codecs for restoring them.
+pub(crate) struct EventSpill {
+    config: Option<SpillConfig>,
+    type_names: FxHashMap<TypeId, &'static str>,
+    decoders: FxHashMap<&'static str, DecodeFn>,
+    runs: Vec<SpillRun>,
+    run_count: u64,
+    instance_id: u64,
+    threshold: usize,
+    // Ordered events which were loaded back to the heap, used to exclude them in cancel_heap_events.
+    reloaded_ordered: FxHashSet<EventId>,
+    // First line of run files with run metadata.
+    header: String,
+}
+
+impl EventSpill {
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            type_names: FxHashMap::default(),
+            decoders: FxHashMap::default(),
+            runs: Vec::new(),
+            run_count: 0,
+            instance_id: INSTANCE_COUNT.fetch_add(1, Ordering::Relaxed),
+            threshold: usize::MAX,
+            reloaded_ordered: FxHashSet::default(),
+            header: String::new(),
+        }
+    }
+
+    pub fn enable(&mut self, config: SpillConfig) {
+        assert!(config.memory_limit > 0, "Memory limit must be positive");
+        assert!(
+            config.keep_fraction > 0. && config.keep_fraction < 1.,
+            "Keep fraction must be in (0, 1)"
+        );
+        std::fs::create_dir_all(&config.dir).expect("Failed to create directory for spilled events");
+        self.threshold = config.memory_limit;
+        self.config = Some(config);
+    }
+
+    pub fn set_metadata(&mut self, metadata: &RunMetadata) {
+        self.header = serde_json::to_string(&SpillHeader { metadata }).unwrap();
+    }
+
+    pub fn register<T: EventData + DeserializeOwned>(&mut self) {
+        let name = std::any::type_name::<T>();
+        self.type_names.insert(TypeId::of::<T>(), name);
+        self.decoders.insert(name, decode::<T>);
+    }
+
+    pub fn should_spill(&self, in_memory: usize) -> bool {
+        in_memory > self.threshold
+    }
+
+    pub fn has_runs(&self) -> bool {
+        !self.runs.is_empty()
+    }
+
+    pub fn is_spillable(&self, event: &Event) -> bool {
+        self.type_names.contains_key(&event.data.type_id())
+    }
+
+    pub fn on_ordered_event_reloaded(&mut self, id: EventId) {
+        self.reloaded_ordered.insert(id);
+    }
+
+    pub fn on_reloaded_event_removed(&mut self, id: EventId) {
+        if !self.reloaded_ordered.is_empty() {
+            self.reloaded_ordered.remove(&id);
+        }
+    }
+
+    pub fn is_reloaded_ordered_event(&self, id: EventId) -> bool {
+        !self.reloaded_ordered.is_empty() && self.reloaded_ordered.contains(&id)
+    }
+
+    // Returns the number of events to keep in memory out of `len` events.
+    pub fn keep_count(&self, len: usize) -> usize {
+        let keep_fraction = self.config.as_ref().map_or(1., |c| c.keep_fraction);
+        (len as f64 * keep_fraction).ceil() as usize
+    }
+
+    // Updates the spilling threshold after spilling, so that spilling is not retried immediately
+    // when most of the remaining events cannot be spilled.
+    pub fn on_spilled(&mut self, in_memory: usize) {
+        let config = self.config.as_ref().unwrap();
+        let spilled_per_run = config.memory_limit - self.keep_count(config.memory_limit);
+        self.threshold = (in_memory + spilled_per_run).max(config.memory_limit);
+    }
+
+    // Writes events sorted by time into a new run file.
+    pub fn write_run(&mut self, events: Vec<(Event, bool)>) {
+        if events.is_empty() {
+            return;
+        }
+        let config = self.config.as_ref().unwrap();
+        let path = config.dir.join(format!(
+            "simcore-spill-{}-{}-{}.jsonl",
+            std::process::id(),
+            self.instance_id,
+            self.run_count
+        ));
+        self.run_count += 1;
+        let file = File::create(&path).expect("Failed to create file for spilled events");
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(self.header.as_bytes())
+            .and_then(|_| writer.write_all(b"\n"))
+            .expect("Failed to write spilled events");
+        for (event, ordered) in events.iter() {
+            let record = SpilledEventRef {
+                id: event.id,
+                time: event.time,
+                src: event.src,
+                dst: event.dst,
+                priority: event.priority,
+                ordered: *ordered,
+                type_name: self.type_names[&event.data.type_id()],
+                data: event.data.as_ref(),
+            };
+            serde_json::to_writer(&mut writer, &record).expect("Failed to write spilled event");
+            writer.write_all(b"\n").expect("Failed to write spilled event");
+        }
+        writer.flush().expect("Failed to write spilled events");
+        let (first, _) = &events[0];
+        self.runs.push(SpillRun {
+            path,
+            first_time: first.time,
+            first_priority: first.priority,
+            first_id: first.id,
+        });
+    }
+
+    // Removes and returns the events from the earliest run if this run precedes the next in-memory event
+    // with the given time, priority and id.
+    pub fn take_run_before(&mut self, next: Option<(f64, i64, EventId)>) -> Option<Vec<(Event, bool)>> {
+        let (idx, run) = self
+            .runs
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.cmp_first(b.first_time, b.first_priority, b.first_id))?;
+        if next.is_some_and(|(time, priority, id)| !run.cmp_first(time, priority, id).is_lt()) {
+            return None;
+        }
+        let run = self.runs.swap_remove(idx);
+        let events = self.read_run(&run);
+        std::fs::remove_file(&run.path).expect("Failed to remove file with spilled events");
+        Some(events)
+    }
+
+    // Calls `f` for each spilled event.
+    pub fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(Event, bool),
+    {
+        for run in self.runs.iter() {
+            for (event, ordered) in self.read_run(run) {
+                f(event, ordered)
+            }
+        }
+    }
+
+    fn read_run(&self, run: &SpillRun) -> Vec<(Event, bool)> {
+        let file = File::open(&run.path).expect("Failed to open file with spilled events");
+        BufReader::new(file)
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let line = line.expect("Failed to read spilled event");
+                let record: SpilledEvent = serde_json::from_str(&line).expect("Failed to parse spilled event");
+                let decoder = self.decoders[record.type_name.as_str()];
+                let data = decoder(record.data).expect("Failed to deserialize spilled event payload");
+                let event = Event {
+                    id: record.id,
+                    time: record.time,
+                    src: record.src,
+                    dst: record.dst,
+                    priority: record.priority,
+                    data,
+                };
+                (event, record.ordered)
+            })
+            .collect()
+    }
+}
+
+impl Clone for EventSpill {
+    fn clone(&self) -> Self {
+        let mut clone = Self {
+            config: self.config.clone(),
+            type_names: self.type_names.clone(),
+            decoders: self.decoders.clone(),
+            runs: Vec::new(),
+            run_count: 0,
+            instance_id: INSTANCE_COUNT.fetch_add(1, Ordering::Relaxed),
+            threshold: self.threshold,
+            reloaded_ordered: self.reloaded_ordered.clone(),
+            header: self.header.clone(),
+        };
+        // Runs are copied to separate files, so that each copy can independently remove them.
+        for run in self.runs.iter() {
+            let events = self.read_run(run);
+            clone.write_run(events);
+        }
+        clone
+    }
+}
+
+impl Drop for EventSpill {
+    fn drop(&mut self) {
+        for run in self.runs.iter() {
+            let _ = std::fs::remove_file(&run.path);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Component dependencies and startup ordering.
+//!
+//! Components of large models often have to be initialized after the components they depend on, e.g. a load
+//! balancer needs the identifiers of its backends to send the first requests, and a client should not issue
+//! requests before the server it talks to has scheduled its periodic activities. Instead of relying on the order
+//! of constructing components, a component implementing [`StartableComponent`] can be registered via
+//! [`Simulation::register_startable`](crate::Simulation::register_startable) with the names of components it
+//! depends on. When the simulation is started, the dependency graph is validated and the
+//! [`on_start`](StartableComponent::on_start) hooks are called in topological order, so each component is
+//! started after all its dependencies. The identifiers of dependencies are resolved by their names on the start,
+//! so the components can be created in any order.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::component::Id;
+
+/// Component with a hook called on the simulation start after all its dependencies are started.
+pub trait StartableComponent {
+    /// Starts the component, e.g. emits its initial events, using the resolved identifiers of its dependencies.
+    fn on_start(&mut self, dependencies: &Dependencies);
+}
+
+/// Dependencies of the component resolved on the simulation start.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    ids: Vec<(String, Id)>,
+}
+
+impl Dependencies {
+    /// Returns the identifier of the dependency with the specified name.
+    ///
+    /// Panics if the component does not depend on the component with such name.
+    pub fn id(&self, name: &str) -> Id {
+        self.get(name)
+            .unwrap_or_else(|| panic!("Component {} is not declared as dependency", name))
+    }
+
+    /// Returns the identifier of the dependency with the specified name, or `None` if there is no such dependency.
+    pub fn get(&self, name: &str) -> Option<Id> {
+        self.ids.iter().find(|(dep, _)| dep == name).map(|(_, id)| *id)
+    }
+
+    /// Returns the identifiers of dependencies in the order of their declaration.
+    pub fn ids(&self) -> Vec<Id> {
+        self.ids.iter().map(|(_, id)| *id).collect()
+    }
+
+    /// Returns the number of dependencies.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if the component has no dependencies.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+pub(crate) struct StartupEntry {
+    pub id: Id,
+    pub dependencies: Vec<String>,
+    pub component: Rc<RefCell<dyn StartableComponent>>,
+}
+
+impl StartupEntry {
+    // Resolves the identifiers of dependencies via the specified lookup, panics if some dependency does not exist.
+    pub fn resolve<L, N>(&self, lookup: L, name: N) -> Dependencies
+    where
+        L: Fn(&str) -> Option<Id>,
+        N: Fn(Id) -> String,
+    {
+        let ids = self
+            .dependencies
+            .iter()
+            .map(|dep| {
+                let id = lookup(dep)
+                    .unwrap_or_else(|| panic!("Component {} depends on unknown component {}", name(self.id), dep));
+                (dep.clone(), id)
+            })
+            .collect();
+        Dependencies { ids }
+    }
+}
+
+// Returns the indices of entries in the order of their start, which follows the order of registration unless
+// a component has to be started after its dependencies. Panics if the dependencies form a cycle.
+pub(crate) fn startup_order<N>(entries: &[StartupEntry], dependencies: &[Dependencies], name: N) -> Vec<usize>
+where
+    N: Fn(Id) -> String,
+{
+    let mut order = Vec::with_capacity(entries.len());
+    // 0 - not visited, 1 - in progress, 2 - started
+    let mut marks = vec![0u8; entries.len()];
+    let mut path = Vec::new();
+    for index in 0..entries.len() {
+        visit(index, entries, dependencies, &name, &mut marks, &mut path, &mut order);
+    }
+    order
+}
+
+fn visit<N>(
+    index: usize,
+    entries: &[StartupEntry],
+    dependencies: &[Dependencies],
+    name: &N,
+    marks: &mut [u8],
+    path: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) where
+    N: Fn(Id) -> String,
+{
+    match marks[index] {
+        2 => return,
+        1 => {
+            let start = path.iter().position(|i| *i == index).unwrap();
+            let cycle: Vec<String> = path[start..]
+                .iter()
+                .chain(std::iter::once(&index))
+                .map(|i| name(entries[*i].id))
+                .collect();
+            panic!("Component dependencies form a cycle: {}", cycle.join(" -> "));
+        }
+        _ => {}
+    }
+    marks[index] = 1;
+    path.push(index);
+    for dep in dependencies[index].ids() {
+        // dependencies without startup hooks are always started
+        if let Some(dep_index) = entries.iter().position(|entry| entry.id == dep) {
+            visit(dep_index, entries, dependencies, name, marks, path, order);
+        }
+    }
+    path.pop();
+    marks[index] = 2;
+    order.push(index);
+}
@@ -1,46 +1,152 @@
-use std::collections::{BinaryHeap, VecDeque};
+use std::any::TypeId;
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
 
 use rand::distributions::uniform::{SampleRange, SampleUniform};
 use rand::distributions::{Alphanumeric, DistString};
 use rand::prelude::*;
 use rand_pcg::Pcg64;
 use rustc_hash::{FxHashMap, FxHashSet};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use crate::component::Id;
-use crate::event::{Event, EventData, EventId};
-use crate::log::log_incorrect_event;
+use crate::checkpoint::SimulationCheckpoint;
+use crate::coalescing::Coalescing;
+use crate::component::{ComponentRef, Id};
+use crate::contracts::{ContractViolation, Contracts};
+use crate::delay::DelayConfig;
+use crate::event::{Event, EventData, EventId, EventTypeId};
+use crate::heap::DaryHeap;
+use crate::limits::LimitAction;
+use crate::link::{Link, LinkChannels};
+use crate::log::{
+    log_incorrect_event, log_undelivered_event, write_event_json, write_json_value, LoggableEvent, WriteJsonFn,
+};
+use crate::logical_clock::{LogicalClockKind, LogicalClocks, LogicalTime};
+use crate::metadata::{config_hash, RunMetadata};
+use crate::metrics::{MetricSeries, Metrics, MetricsReport};
+use crate::ordering::{OrderingChecker, OrderingPolicy, OrderingViolation};
+#[cfg(feature = "thread")]
+use crate::parallel::RemoteComponents;
+use crate::producers::{ProducerReport, ProducerStats, ProducerStatsConfig};
+use crate::rate_limit::{OutputKind, OutputRateLimit, OutputRateLimiter};
+use crate::routing::{Route, RouterFn};
+use crate::spill::{EventSpill, SpillConfig};
+use crate::tick::TimeTick;
+use crate::timer::{NamedTimers, TimerFired};
+use crate::trace::MemoryTrace;
+use crate::trace_file::{TraceFileConfig, TraceFileRecorder};
 use crate::{async_mode_disabled, async_mode_enabled};
 
 async_mode_enabled!(
-    use std::any::TypeId;
     use std::cell::RefCell;
-    use std::rc::Rc;
+    use std::collections::BinaryHeap;
+    use std::panic::Location;
+    use std::rc::Weak;
 
     use futures::Future;
 
-    use crate::async_mode::EventKey;
+    use crate::async_mode::{EventKey, KeyedEvent};
     use crate::async_mode::channel::Sender;
-    use crate::async_mode::promise_store::EventPromiseStore;
+    use crate::async_mode::mailbox::MailboxInner;
+    use crate::async_mode::promise_store::{EventPredicateFn, EventPromiseStore, PendingWait};
     use crate::async_mode::event_future::{EventFuture, EventPromise};
-    use crate::async_mode::task::Task;
+    use crate::async_mode::request::RequestId;
+    use crate::async_mode::task::{Task, TaskLimiter};
+    use crate::timer::TimerKeys;
     use crate::async_mode::timer_future::{TimerPromise, TimerId, TimerFuture};
+    use crate::async_mode::wait_stats::{WaitSite, WaitStats};
+    use crate::handler::DispatchPrecedence;
 );
 
 /// Epsilon to compare floating point values for equality.
 pub const EPSILON: f64 = 1e-12;
 
+// Default arity of the heap storing pending events.
+const DEFAULT_HEAP_ARITY: usize = 2;
+
+// Queues storing pending events.
+#[derive(Clone, Copy)]
+enum EventSource {
+    // Binary heap with events emitted via emit...
+    Heap,
+    // Deque with events emitted via emit_ordered...
+    Ordered,
+    // FIFO with zero-delay events emitted via emit...
+    Immediate,
+}
+
+// State of the trace, producer statistics and metrics suspended while step_back re-executes the simulation.
+pub(crate) struct SuspendedEffects {
+    trace_file: TraceFileRecorder,
+    trace: Option<MemoryTrace>,
+    producer_stats: Option<ProducerStats>,
+    metrics: Metrics,
+}
+
+#[derive(Clone)]
+struct EventTypeInfo {
+    name: String,
+    // Serializer of event payload for logging, present for types registered via register_loggable_event.
+    write_json: Option<WriteJsonFn>,
+}
+
 async_mode_disabled!(
     #[derive(Clone)]
     pub struct SimulationState {
         clock: f64,
         rand: Pcg64,
-        events: BinaryHeap<Event>,
+        events: DaryHeap<Event>,
         ordered_events: VecDeque<Event>,
+        // Maximum time of emitted ordered events before routing, used to check the order of emitted events.
+        last_ordered_time: f64,
+        immediate_events: VecDeque<Event>,
         canceled_events: FxHashSet<EventId>,
+        spilled_events: EventSpill,
         event_count: u64,
 
         component_name_to_id: FxHashMap<String, Id>,
         component_names: Vec<String>,
+        // Generations of component identifiers, incremented when the component is removed.
+        generations: Vec<u32>,
+        // Identifiers of removed components, which are reused by new components.
+        free_ids: Vec<Id>,
+        // Destinations of pending events emitted by component reference, with the referenced generations.
+        ref_events: FxHashMap<EventId, (Id, u32)>,
+
+        event_type_ids: FxHashMap<TypeId, EventTypeId>,
+        event_types: Vec<EventTypeInfo>,
+        log_buffer: Vec<u8>,
+        log_limiter: OutputRateLimiter,
+        // Set when some log rate limit is set, shared with the contexts to skip the limiter without borrowing the state.
+        log_limited: Rc<Cell<bool>>,
+        trace: Option<MemoryTrace>,
+        trace_file: TraceFileRecorder,
+        producer_stats: Option<ProducerStats>,
+        metrics: Metrics,
+        link_channels: LinkChannels,
+        contracts: Contracts,
+        logical_clocks: Option<LogicalClocks>,
+        ordering: Option<OrderingChecker>,
+        #[cfg(feature = "thread")]
+        remote: Option<RemoteComponents>,
+        named_timers: NamedTimers,
+        metadata: RunMetadata,
+        emit_as_allowed: FxHashSet<Id>,
+        routers: Vec<RouterFn>,
+        // Destinations of events for the inactive models of multi-resolution components.
+        redirects: FxHashMap<Id, Id>,
+        delays: DelayConfig,
+        time_tick: Option<TimeTick>,
+        // Events emitted relative to pending events, with their delays, by the identifier of preceding event.
+        deferred_events: FxHashMap<EventId, Vec<(Event, f64)>>,
+        last_dispatched_event: Option<EventId>,
+        coalescing: Coalescing,
+        // Set when an optional subsystem checked on each step is enabled, shared with the simulation.
+        step_hooks: Rc<Cell<bool>>,
+        // Set when an optional subsystem processing emitted or dispatched events is enabled.
+        event_hooks: bool,
     }
 );
 
@@ -51,25 +157,79 @@ async_mode_enabled!(
     pub struct SimulationState {
         clock: f64,
         rand: Pcg64,
-        events: BinaryHeap<Event>,
+        events: DaryHeap<Event>,
         ordered_events: VecDeque<Event>,
+        // Maximum time of emitted ordered events before routing, used to check the order of emitted events.
+        last_ordered_time: f64,
+        immediate_events: VecDeque<Event>,
         canceled_events: FxHashSet<EventId>,
+        spilled_events: EventSpill,
         event_count: u64,
 
         component_name_to_id: FxHashMap<String, Id>,
         component_names: Vec<String>,
+        // Generations of component identifiers, incremented when the component is removed.
+        generations: Vec<u32>,
+        // Identifiers of removed components, which are reused by new components.
+        free_ids: Vec<Id>,
+        // Destinations of pending events emitted by component reference, with the referenced generations.
+        ref_events: FxHashMap<EventId, (Id, u32)>,
+
+        event_type_ids: FxHashMap<TypeId, EventTypeId>,
+        event_types: Vec<EventTypeInfo>,
+        log_buffer: Vec<u8>,
+        log_limiter: OutputRateLimiter,
+        // Set when some log rate limit is set, shared with the contexts to skip the limiter without borrowing the state.
+        log_limited: Rc<Cell<bool>>,
+        trace: Option<MemoryTrace>,
+        trace_file: TraceFileRecorder,
+        producer_stats: Option<ProducerStats>,
+        metrics: Metrics,
+        link_channels: LinkChannels,
+        contracts: Contracts,
+        logical_clocks: Option<LogicalClocks>,
+        ordering: Option<OrderingChecker>,
+        #[cfg(feature = "thread")]
+        remote: Option<RemoteComponents>,
+        named_timers: NamedTimers,
+        metadata: RunMetadata,
+        emit_as_allowed: FxHashSet<Id>,
+        routers: Vec<RouterFn>,
+        // Destinations of events for the inactive models of multi-resolution components.
+        redirects: FxHashMap<Id, Id>,
+        delays: DelayConfig,
+        time_tick: Option<TimeTick>,
+        // Events emitted relative to pending events, with their delays, by the identifier of preceding event.
+        deferred_events: FxHashMap<EventId, Vec<(Event, f64)>>,
+        last_dispatched_event: Option<EventId>,
+        coalescing: Coalescing,
+        // Set when an optional subsystem checked on each step is enabled, shared with the simulation.
+        step_hooks: Rc<Cell<bool>>,
+        // Set when an optional subsystem processing emitted or dispatched events is enabled.
+        event_hooks: bool,
 
         // Specific to async mode
         registered_static_handlers: Vec<bool>,
 
         event_promises: EventPromiseStore,
         key_getters: FxHashMap<TypeId, KeyGetterFn>,
+        component_key_getters: FxHashMap<(TypeId, Id), KeyGetterFn>,
+        dispatch_precedence: DispatchPrecedence,
+        component_dispatch_precedence: FxHashMap<Id, DispatchPrecedence>,
 
         timers: BinaryHeap<TimerPromise>,
         canceled_timers: FxHashSet<TimerId>,
         timer_count: u64,
+        timer_keys: TimerKeys,
+
+        request_count: u64,
 
+        task_limiters: FxHashMap<Id, Rc<RefCell<TaskLimiter>>>,
+        wait_stats: Option<BTreeMap<String, WaitStats>>,
+        // Mailboxes of components and the event types delivered to them.
+        mailboxes: FxHashMap<Id, (Weak<MailboxInner>, FxHashSet<TypeId>)>,
         executor: Sender<Rc<Task>>,
+        live_tasks: Rc<Cell<usize>>,
     }
 );
 
@@ -79,57 +239,591 @@ impl SimulationState {
             Self {
                 clock: 0.0,
                 rand: Pcg64::seed_from_u64(seed),
-                events: BinaryHeap::new(),
+                events: DaryHeap::new(DEFAULT_HEAP_ARITY),
                 ordered_events: VecDeque::new(),
+                last_ordered_time: f64::MIN,
+                immediate_events: VecDeque::new(),
                 canceled_events: FxHashSet::default(),
+                spilled_events: EventSpill::new(),
                 event_count: 0,
                 component_name_to_id: FxHashMap::default(),
                 component_names: Vec::new(),
+                generations: Vec::new(),
+                free_ids: Vec::new(),
+                ref_events: FxHashMap::default(),
+                event_type_ids: FxHashMap::default(),
+                event_types: Vec::new(),
+                log_buffer: Vec::new(),
+                log_limiter: OutputRateLimiter::default(),
+                log_limited: Rc::new(Cell::new(false)),
+                trace: None,
+                trace_file: TraceFileRecorder::default(),
+                producer_stats: None,
+                metrics: Metrics::default(),
+                link_channels: LinkChannels::default(),
+                contracts: Contracts::new(),
+                logical_clocks: None,
+                ordering: None,
+                #[cfg(feature = "thread")]
+                remote: None,
+                named_timers: NamedTimers::default(),
+                metadata: RunMetadata::new(seed),
+                emit_as_allowed: FxHashSet::default(),
+                routers: Vec::new(),
+                redirects: FxHashMap::default(),
+                delays: DelayConfig::default(),
+                time_tick: None,
+                deferred_events: FxHashMap::default(),
+                last_dispatched_event: None,
+                coalescing: Coalescing::default(),
+                step_hooks: Rc::new(Cell::new(false)),
+                event_hooks: false,
             }
         }
     );
     async_mode_enabled!(
         pub fn new(seed: u64, executor: Sender<Rc<Task>>) -> Self {
-            Self {
+            let mut state = Self {
                 clock: 0.0,
                 rand: Pcg64::seed_from_u64(seed),
-                events: BinaryHeap::new(),
+                events: DaryHeap::new(DEFAULT_HEAP_ARITY),
                 ordered_events: VecDeque::new(),
+                last_ordered_time: f64::MIN,
+                immediate_events: VecDeque::new(),
                 canceled_events: FxHashSet::default(),
+                spilled_events: EventSpill::new(),
                 event_count: 0,
                 component_name_to_id: FxHashMap::default(),
                 component_names: Vec::new(),
+                generations: Vec::new(),
+                free_ids: Vec::new(),
+                ref_events: FxHashMap::default(),
+                event_type_ids: FxHashMap::default(),
+                event_types: Vec::new(),
+                log_buffer: Vec::new(),
+                log_limiter: OutputRateLimiter::default(),
+                log_limited: Rc::new(Cell::new(false)),
+                trace: None,
+                trace_file: TraceFileRecorder::default(),
+                producer_stats: None,
+                metrics: Metrics::default(),
+                link_channels: LinkChannels::default(),
+                contracts: Contracts::new(),
+                logical_clocks: None,
+                ordering: None,
+                #[cfg(feature = "thread")]
+                remote: None,
+                named_timers: NamedTimers::default(),
+                metadata: RunMetadata::new(seed),
+                emit_as_allowed: FxHashSet::default(),
+                routers: Vec::new(),
+                redirects: FxHashMap::default(),
+                delays: DelayConfig::default(),
+                time_tick: None,
+                deferred_events: FxHashMap::default(),
+                last_dispatched_event: None,
+                coalescing: Coalescing::default(),
+                step_hooks: Rc::new(Cell::new(false)),
+                event_hooks: false,
                 // Specific to async mode
                 registered_static_handlers: Vec::new(),
                 event_promises: EventPromiseStore::new(),
                 key_getters: FxHashMap::default(),
+                component_key_getters: FxHashMap::default(),
+                dispatch_precedence: DispatchPrecedence::default(),
+                component_dispatch_precedence: FxHashMap::default(),
                 timers: BinaryHeap::new(),
                 canceled_timers: FxHashSet::default(),
                 timer_count: 0,
+                timer_keys: TimerKeys::default(),
+                request_count: 0,
+                task_limiters: FxHashMap::default(),
+                wait_stats: None,
+                mailboxes: FxHashMap::default(),
                 executor,
-            }
+                live_tasks: Rc::new(Cell::new(0)),
+            };
+            let timer_keys = state.timer_keys.clone();
+            state.register_key_getter_for::<TimerFired>(move |timer| timer_keys.key(&timer.name));
+            state
         }
     );
 
+    async_mode_disabled!(
+        // Returns the copy of the state for the branch of the simulation.
+        pub fn branch(&self) -> Self {
+            let mut state = self.clone();
+            state.log_limited = Rc::new(Cell::new(self.log_limited.get()));
+            state.step_hooks = Rc::new(Cell::new(self.step_hooks.get()));
+            state
+        }
+    );
+
+    async_mode_enabled!(
+        // Returns the copy of the state for the branch of the simulation, which uses the specified executor.
+        pub fn branch(&self, executor: Sender<Rc<Task>>) -> Self {
+            assert_eq!(
+                self.live_tasks.get(),
+                0,
+                "Simulation with alive asynchronous tasks cannot be branched"
+            );
+            assert!(
+                self.mailboxes.values().all(|(mailbox, _)| mailbox.strong_count() == 0),
+                "Simulation with component mailboxes cannot be branched"
+            );
+            let mut state = self.clone();
+            let live_tasks = Rc::new(Cell::new(0));
+            state.task_limiters = self
+                .task_limiters
+                .iter()
+                .map(|(id, limiter)| {
+                    let copy = TaskLimiter::new(executor.clone(), live_tasks.clone());
+                    TaskLimiter::set_limit(&copy, limiter.borrow().limit());
+                    (*id, copy)
+                })
+                .collect();
+            state.executor = executor;
+            state.live_tasks = live_tasks;
+            state.log_limited = Rc::new(Cell::new(self.log_limited.get()));
+            state.step_hooks = Rc::new(Cell::new(self.step_hooks.get()));
+            state
+        }
+    );
+
+    // Replaces the random number generator with the one using the specified seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = Pcg64::seed_from_u64(seed);
+        self.metadata.seed = seed;
+    }
+
     pub fn register(&mut self, name: &str) -> Id {
         if let Some(&id) = self.component_name_to_id.get(name) {
             return id;
         }
-        let id = self.component_name_to_id.len() as Id;
+        if let Some(id) = self.free_ids.pop() {
+            self.component_name_to_id.insert(name.to_owned(), id);
+            self.component_names[id as usize] = name.to_owned();
+            return id;
+        }
+        let id = self.component_names.len() as Id;
         self.component_name_to_id.insert(name.to_owned(), id);
         self.component_names.push(name.to_owned());
+        self.generations.push(0);
         self.on_register();
         id
     }
 
+    // Removes the component name and makes its identifier available for new components.
+    pub fn unregister(&mut self, id: Id) {
+        let name = &self.component_names[id as usize];
+        self.component_name_to_id.remove(name);
+        self.generations[id as usize] += 1;
+        self.free_ids.push(id);
+        self.emit_as_allowed.remove(&id);
+        self.delays.remove_component(id);
+        self.coalescing.remove_window(id);
+        self.log_limiter.remove_component(id);
+        self.log_limited.set(self.log_limiter.is_enabled());
+        self.trace_file.limiter_mut().remove_component(id);
+        self.redirects.retain(|from, to| *from != id && *to != id);
+        self.link_channels.remove_component(id);
+        self.named_timers.remove_component(id);
+        if let Some(clocks) = self.logical_clocks.as_mut() {
+            clocks.remove_component(id);
+        }
+        if let Some(stats) = self.producer_stats.as_mut() {
+            stats.remove_component(id, &self.component_names[id as usize]);
+        }
+        self.metrics
+            .remove_component(id, &self.component_names[id as usize], self.time());
+        if let Some(ordering) = self.ordering.as_mut() {
+            ordering.on_component_removed(id);
+        }
+        self.on_unregister(id);
+    }
+
+    pub fn component_ref(&self, id: Id) -> ComponentRef {
+        ComponentRef::new(id, self.generations[id as usize])
+    }
+
+    pub fn is_current_ref(&self, component: ComponentRef) -> bool {
+        self.generations[component.id() as usize] == component.generation()
+    }
+
+    pub fn component_refs(&self) -> Vec<ComponentRef> {
+        let mut refs: Vec<ComponentRef> = self
+            .component_name_to_id
+            .values()
+            .map(|id| self.component_ref(*id))
+            .collect();
+        refs.sort_by_key(|r| r.id());
+        refs
+    }
+
+    pub fn add_boxed_event_to_ref(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: ComponentRef,
+        delay: f64,
+    ) -> EventId {
+        let event_id = self.add_boxed_event(data, src, dst.id(), delay);
+        self.ref_events.insert(event_id, (dst.id(), dst.generation()));
+        event_id
+    }
+
+    // Checks that the referenced component is not removed, i.e. its identifier is not reused by another component.
+    pub fn assert_current_ref(&self, src: Id, dst: ComponentRef) {
+        let generation = self.generations[dst.id() as usize];
+        assert!(
+            generation == dst.generation(),
+            "Component {} referenced by {} is removed (referenced generation {}, current generation {})",
+            dst.id(),
+            self.component_name(src),
+            dst.generation(),
+            generation
+        );
+    }
+
+    pub fn add_boxed_event_to_ref_at(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: ComponentRef,
+        time: f64,
+    ) -> EventId {
+        let event_id = self.add_boxed_event_at(data, src, dst.id(), time);
+        self.ref_events.insert(event_id, (dst.id(), dst.generation()));
+        event_id
+    }
+
+    // Returns true if the event was emitted by reference to the component which is removed.
+    fn is_stale_event(&self, event: &Event) -> bool {
+        match self.ref_events.get(&event.id) {
+            Some(&(dst, generation)) => dst == event.dst && self.generations[dst as usize] != generation,
+            None => false,
+        }
+    }
+
     pub fn lookup_id(&self, name: &str) -> Id {
         *self.component_name_to_id.get(name).unwrap()
     }
 
+    pub fn try_lookup_id(&self, name: &str) -> Option<Id> {
+        self.component_name_to_id.get(name).copied()
+    }
+
     pub fn lookup_name(&self, id: Id) -> String {
         self.component_names[id as usize].clone()
     }
 
+    pub fn component_name(&self, id: Id) -> &str {
+        &self.component_names[id as usize]
+    }
+
+    pub fn allow_emit_as(&mut self, id: Id) {
+        self.emit_as_allowed.insert(id);
+    }
+
+    pub fn can_emit_as(&self, id: Id) -> bool {
+        self.emit_as_allowed.contains(&id)
+    }
+
+    pub fn lookup_event_type_id(&mut self, data: &dyn EventData) -> EventTypeId {
+        if let Some(&id) = self.event_type_ids.get(&data.type_id()) {
+            return id;
+        }
+        let name = serde_type_name::type_name(&data).unwrap().to_owned();
+        self.add_event_type(data.type_id(), name, None)
+    }
+
+    pub fn event_type_name(&self, id: EventTypeId) -> &str {
+        &self.event_types[id as usize].name
+    }
+
+    pub fn register_loggable_event<T: LoggableEvent>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if let Some(&id) = self.event_type_ids.get(&type_id) {
+            let info = &mut self.event_types[id as usize];
+            info.name = T::NAME.to_owned();
+            info.write_json = Some(write_event_json::<T>);
+        } else {
+            self.add_event_type(type_id, T::NAME.to_owned(), Some(write_event_json::<T>));
+        }
+    }
+
+    fn add_event_type(&mut self, type_id: TypeId, name: String, write_json: Option<WriteJsonFn>) -> EventTypeId {
+        let id = self.event_types.len() as EventTypeId;
+        self.event_type_ids.insert(type_id, id);
+        self.event_types.push(EventTypeInfo { name, write_json });
+        id
+    }
+
+    // Formats event log record into the internal buffer, which can be then accessed via event_log_record.
+    pub fn format_event_log_record(&mut self, event: &Event) {
+        let type_id = self.lookup_event_type_id(event.data.as_ref());
+        let info = &self.event_types[type_id as usize];
+        let buf = &mut self.log_buffer;
+        buf.clear();
+        buf.extend_from_slice(b"{\"type\":");
+        write_json_value(buf, info.name.as_str());
+        buf.extend_from_slice(b",\"data\":");
+        match info.write_json {
+            Some(write_json) => write_json(event.data.as_ref(), buf),
+            None => write_json_value(buf, event.data.as_ref()),
+        }
+        buf.extend_from_slice(b",\"src\":");
+        write_json_value(buf, self.component_names[event.src as usize].as_str());
+        buf.push(b'}');
+    }
+
+    pub fn event_log_record(&self) -> &str {
+        std::str::from_utf8(&self.log_buffer).unwrap()
+    }
+
+    pub fn run_metadata(&self) -> &RunMetadata {
+        &self.metadata
+    }
+
+    pub fn set_run_label(&mut self, key: &str, value: &str) {
+        self.metadata.labels.insert(key.to_owned(), value.to_owned());
+        self.spilled_events.set_metadata(&self.metadata);
+        self.trace_file.set_metadata(&self.metadata);
+    }
+
+    pub fn set_run_config<C: Serialize>(&mut self, config: &C) {
+        self.metadata.config_hash = Some(config_hash(config));
+        self.spilled_events.set_metadata(&self.metadata);
+        self.trace_file.set_metadata(&self.metadata);
+    }
+
+    pub fn enable_memory_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(MemoryTrace::new(self.metadata.seed));
+            self.step_hooks.set(true);
+            self.event_hooks = true;
+        }
+    }
+
+    pub fn enable_trace_file(&mut self, config: TraceFileConfig) {
+        self.trace_file.disable();
+        self.trace_file.enable(config, &self.metadata);
+        self.event_hooks = true;
+    }
+
+    pub fn disable_trace_file(&mut self) {
+        self.trace_file.disable();
+    }
+
+    pub fn enable_producer_stats(&mut self, config: ProducerStatsConfig) {
+        self.producer_stats = Some(ProducerStats::new(config));
+        self.event_hooks = true;
+    }
+
+    pub fn declare_contract(&mut self, src: Id, dst: Id, type_id: TypeId, event_type: &'static str, max: usize) {
+        self.contracts.declare((src, dst, type_id), event_type, max);
+        self.step_hooks.set(true);
+        self.event_hooks = true;
+    }
+
+    // Returns the flag which is set when an optional subsystem checked on each step is enabled.
+    pub fn step_hooks(&self) -> Rc<Cell<bool>> {
+        self.step_hooks.clone()
+    }
+
+    pub fn set_contract_action(&mut self, action: LimitAction) {
+        self.contracts.set_action(action);
+    }
+
+    pub fn take_contract_violations(&mut self) -> Vec<ContractViolation> {
+        self.contracts.take_violations()
+    }
+
+    pub fn producer_report(&self, from: f64, to: f64, limit: usize) -> ProducerReport {
+        let stats = self
+            .producer_stats
+            .as_ref()
+            .expect("Producer stats are not enabled, see enable_producer_stats");
+        stats.report(from, to, limit, |id| self.component_names[id as usize].clone())
+    }
+
+    pub fn memory_trace(&self) -> Option<&MemoryTrace> {
+        self.trace.as_ref()
+    }
+
+    pub fn memory_trace_mut(&mut self) -> Option<&mut MemoryTrace> {
+        self.trace.as_mut()
+    }
+
+    pub fn on_event_dispatched(&mut self, event: &Event) {
+        self.last_dispatched_event = Some(event.id);
+        if !self.deferred_events.is_empty() {
+            if let Some(deferred) = self.deferred_events.remove(&event.id) {
+                for (deferred_event, delay) in deferred {
+                    self.schedule_deferred_event(deferred_event, delay);
+                }
+            }
+        }
+        if !self.event_hooks {
+            return;
+        }
+        self.contracts.on_event_removed(event.id);
+        let logical_time = self
+            .logical_clocks
+            .as_mut()
+            .and_then(|clocks| clocks.on_event_dispatched(event.id, event.dst));
+        if let Some(trace) = self.trace.as_mut() {
+            trace.on_event_dispatched(event, logical_time);
+        }
+        self.trace_file.on_event_processed(event, &self.component_names);
+        if self
+            .ordering
+            .as_ref()
+            .is_some_and(|ordering| ordering.is_checked(event))
+        {
+            self.check_event_order(event);
+        }
+    }
+
+    fn check_event_order(&mut self, event: &Event) {
+        let type_id = self.lookup_event_type_id(event.data.as_ref());
+        let ordering = self.ordering.as_mut().unwrap();
+        let Some(overtaking) = ordering.on_event_delivered(event, type_id) else {
+            return;
+        };
+        let violation = OrderingViolation {
+            src: self.component_names[event.src as usize].clone(),
+            dst: self.component_names[event.dst as usize].clone(),
+            event_id: event.id,
+            event_type: self.event_type_name(type_id).to_owned(),
+            time: event.time,
+            overtaking_event_id: overtaking.id,
+            overtaking_event_type: self.event_type_name(overtaking.type_id).to_owned(),
+            overtaking_time: overtaking.time,
+        };
+        let ordering = self.ordering.as_mut().unwrap();
+        match ordering.policy {
+            OrderingPolicy::Panic => panic!("FIFO ordering assumption is violated: {}", violation),
+            OrderingPolicy::Record => ordering.violations.push(violation),
+        }
+    }
+
+    fn ordering_mut(&mut self) -> &mut OrderingChecker {
+        self.event_hooks = true;
+        self.ordering.get_or_insert_with(OrderingChecker::new)
+    }
+
+    pub fn assume_fifo(&mut self, src: Id, dst: Id) {
+        self.ordering_mut().add_pair(src, dst);
+    }
+
+    pub fn assume_fifo_to(&mut self, dst: Id) {
+        self.ordering_mut().add_destination(dst);
+    }
+
+    pub fn set_ordering_policy(&mut self, policy: OrderingPolicy) {
+        self.ordering_mut().policy = policy;
+    }
+
+    pub fn take_ordering_violations(&mut self) -> Vec<OrderingViolation> {
+        self.ordering
+            .as_mut()
+            .map(|ordering| std::mem::take(&mut ordering.violations))
+            .unwrap_or_default()
+    }
+
+    pub fn enable_logical_clocks(&mut self, kind: LogicalClockKind) {
+        self.logical_clocks = Some(LogicalClocks::new(kind));
+        self.event_hooks = true;
+    }
+
+    pub fn logical_time(&self, id: Id) -> LogicalTime {
+        self.logical_clocks
+            .as_ref()
+            .expect("Logical clocks are not enabled")
+            .time(id)
+    }
+
+    pub fn logical_clocks(&self) -> Option<&LogicalClocks> {
+        self.logical_clocks.as_ref()
+    }
+
+    pub fn restore_logical_clocks(&mut self, clocks: LogicalClocks) {
+        self.logical_clocks = Some(clocks);
+        self.event_hooks = true;
+    }
+
+    // Disables the trace, producer statistics and metrics updates while the simulation is re-executed by step_back,
+    // returns their state to be resumed after the re-execution.
+    pub fn suspend_effects(&mut self) -> SuspendedEffects {
+        SuspendedEffects {
+            trace_file: std::mem::take(&mut self.trace_file),
+            trace: self.trace.take(),
+            producer_stats: self.producer_stats.take(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    pub fn resume_effects(&mut self, effects: SuspendedEffects) {
+        self.trace_file = effects.trace_file;
+        self.trace = effects.trace;
+        self.producer_stats = effects.producer_stats;
+        self.metrics = effects.metrics;
+    }
+
+    pub fn event_logical_time(&self, event_id: EventId) -> Option<LogicalTime> {
+        self.logical_clocks
+            .as_ref()
+            .and_then(|clocks| clocks.event_time(event_id).cloned())
+    }
+
+    // Sets the event which is being processed by handler, the events emitted until it is reset are its children.
+    pub fn set_processed_event(&mut self, id: Option<EventId>) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.set_current_event(id);
+        }
+    }
+
+    async_mode_enabled!(
+        pub fn on_timer_fired(&mut self) {
+            if let Some(trace) = self.trace.as_mut() {
+                trace.on_timer_fired();
+            }
+        }
+
+        // Returns the key of TimerFired events of timer with the specified name.
+        pub fn timer_key(&self, name: &str) -> EventKey {
+            self.timer_keys.key(name)
+        }
+
+        // Called before polling asynchronous task, the emitted events are attributed to the event which resumed it.
+        pub fn on_task_resumed(&mut self) {
+            if let Some(trace) = self.trace.as_mut() {
+                trace.on_task_resumed();
+            }
+        }
+    );
+
+    pub fn set_named_timer(&mut self, component_id: Id, name: &str, event_id: EventId) {
+        if let Some(prev_event_id) = self.named_timers.set(component_id, name, event_id) {
+            self.mark_canceled(prev_event_id);
+        }
+    }
+
+    pub fn cancel_named_timer(&mut self, component_id: Id, name: &str) -> bool {
+        match self.named_timers.remove(component_id, name) {
+            Some(event_id) => {
+                self.mark_canceled(event_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_named_timer(&self, component_id: Id, name: &str) -> bool {
+        self.named_timers.get(component_id, name).is_some()
+    }
+
     pub fn time(&self) -> f64 {
         self.clock
     }
@@ -158,48 +852,445 @@ impl SimulationState {
         Alphanumeric.sample_string(&mut self.rand, len)
     }
 
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        items.shuffle(&mut self.rand);
+    }
+
+    pub fn set_output_rate_limit(&mut self, limit: Option<OutputRateLimit>) {
+        self.log_limiter.set_default(limit);
+        self.trace_file.limiter_mut().set_default(limit);
+        self.log_limited.set(self.log_limiter.is_enabled());
+    }
+
+    pub fn set_component_output_rate_limit(&mut self, id: Id, limit: Option<OutputRateLimit>) {
+        self.log_limiter.set_component(id, limit);
+        self.trace_file.limiter_mut().set_component(id, limit);
+        self.log_limited.set(self.log_limiter.is_enabled());
+    }
+
+    // Returns the flag which is set when some log rate limit is set.
+    pub fn log_limited(&self) -> Rc<Cell<bool>> {
+        self.log_limited.clone()
+    }
+
+    pub fn allow_log_record(&mut self, id: Id, time: f64) -> bool {
+        !self.log_limited.get() || self.log_limiter.allow(id, time)
+    }
+
+    pub fn suppressed_output(&self, kind: OutputKind) -> u64 {
+        match kind {
+            OutputKind::Log => self.log_limiter.suppressed(),
+            OutputKind::TraceFile => self.trace_file.limiter().suppressed(),
+        }
+    }
+
+    // Resets the built-in statistics at the end of the warmup period.
+    pub fn reset_stats(&mut self) {
+        self.log_limiter.reset_suppressed();
+        self.trace_file.limiter_mut().reset_suppressed();
+        self.reset_wait_stats();
+        self.metrics.reset(self.time());
+    }
+
+    pub fn metrics_mut(&mut self) -> &mut Metrics {
+        &mut self.metrics
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub fn metrics_report(&self) -> MetricsReport {
+        self.metrics.report(|id| self.lookup_name(id), self.time())
+    }
+
+    pub fn metric_series(&self) -> MetricSeries {
+        self.metrics.series(|id| self.lookup_name(id))
+    }
+
+    pub fn link_delay(&mut self, src: Id, dst: Id, size: f64, link: &Link) -> f64 {
+        let time = self.time();
+        self.link_channels.transmit(src, dst, size, link, time)
+    }
+
+    pub fn link_busy_until(&self, src: Id, dst: Id) -> Option<f64> {
+        self.link_channels.busy_until(src, dst)
+    }
+
+    pub fn delays_mut(&mut self) -> &mut DelayConfig {
+        &mut self.delays
+    }
+
+    pub fn default_delay(&mut self, data: &dyn EventData, src: Id, dst: Id) -> f64 {
+        match self.delays.sample(data.type_id(), src, dst, &mut self.rand) {
+            Some(delay) => delay,
+            None => panic!(
+                "No default delay is configured for event {} from {} to {}",
+                serde_type_name::type_name(&data).unwrap(),
+                self.component_name(src),
+                self.component_name(dst)
+            ),
+        }
+    }
+
     pub fn add_event<T>(&mut self, data: T, src: Id, dst: Id, delay: f64) -> EventId
     where
         T: EventData,
     {
+        self.add_boxed_event(Box::new(data), src, dst, delay)
+    }
+
+    pub fn add_boxed_event(&mut self, data: Box<dyn EventData>, src: Id, dst: Id, delay: f64) -> EventId {
+        self.add_boxed_event_with_time(data, src, dst, delay, self.clock + delay.max(0.), 0)
+    }
+
+    pub fn add_boxed_event_with_priority(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        delay: f64,
+        priority: i64,
+    ) -> EventId {
+        self.add_boxed_event_with_time(data, src, dst, delay, self.clock + delay.max(0.), priority)
+    }
+
+    pub fn add_boxed_event_at(&mut self, data: Box<dyn EventData>, src: Id, dst: Id, time: f64) -> EventId {
+        let delay = self.delay_until(time);
+        self.add_boxed_event_with_time(data, src, dst, delay, time, 0)
+    }
+
+    // Returns the delay until the specified event time, which must not be earlier than the current time.
+    fn delay_until(&self, time: f64) -> f64 {
+        assert!(
+            time >= self.clock,
+            "Event time {} is earlier than the current time {}",
+            time,
+            self.clock
+        );
+        time - self.clock
+    }
+
+    // The event time is passed along with the delay to avoid floating-point errors when emitting at absolute time.
+    fn add_boxed_event_with_time(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        delay: f64,
+        time: f64,
+        priority: i64,
+    ) -> EventId {
+        #[cfg(feature = "thread")]
+        if self.remote.as_ref().is_some_and(|remote| remote.contains(dst)) {
+            return self.add_remote_event(data, src, dst, delay, time, priority);
+        }
         let event_id = self.event_count;
-        let event = Event {
+        let mut event = Event {
             id: event_id,
-            time: self.clock + delay.max(0.),
+            time,
             src,
             dst,
-            data: Box::new(data),
+            priority,
+            data,
         };
-        if delay >= -EPSILON {
-            self.events.push(event);
-            self.event_count += 1;
-            event_id
-        } else {
+        if delay < -EPSILON {
             log_incorrect_event(event, &format!("negative delay {}", delay));
             panic!("Event delay is negative! It is not allowed to add events from the past.");
         }
+        if !self.event_hooks {
+            // no optional subsystem processes the emitted events, so the event is queued as is
+            self.push_event(event, delay);
+            self.event_count += 1;
+            return event_id;
+        }
+        let route_delay = self.route_event(&mut event);
+        event.time = self.snap_time(event.time);
+        self.on_event_emitted(&event);
+        let event = if self.coalescing.is_enabled() {
+            self.coalescing.coalesce(event)
+        } else {
+            Some(event)
+        };
+        if let Some(event) = event {
+            self.push_event(event, delay + route_delay);
+        }
+        self.event_count += 1;
+        self.spill_events_if_needed();
+        event_id
+    }
+
+    // Adds the event to the queue of zero-delay events or to the heap.
+    fn push_event(&mut self, event: Event, delay: f64) {
+        // zero-delay events bypass the heap, the FIFO order matches the event order
+        // because such events have the current time, the default priority and the greatest id
+        if delay <= 0. && event.priority == 0 && self.immediate_events.back().is_none_or(|e| e.time <= event.time) {
+            self.immediate_events.push_back(event);
+        } else {
+            self.events.push(event);
+        }
+    }
+
+    // Notifies the trace, statistics, contracts and logical clocks about the emitted event.
+    fn on_event_emitted(&mut self, event: &Event) {
+        self.trace_file
+            .on_event_emitted(event, self.clock, &self.component_names);
+        if let Some(stats) = self.producer_stats.as_mut() {
+            stats.on_event_emitted(event, self.clock);
+        }
+        self.contracts
+            .on_event_emitted(event, self.clock, &self.component_names);
+        if let Some(trace) = self.trace.as_mut() {
+            trace.on_event_emitted(event.id);
+        }
+        if let Some(clocks) = self.logical_clocks.as_mut() {
+            clocks.on_event_emitted(event.id, event.src);
+        }
+    }
+
+    #[cfg(feature = "thread")]
+    pub fn set_remote_components(&mut self, remote: RemoteComponents) {
+        self.remote = Some(remote);
+    }
+
+    // Stores the event sent to the component simulated by another partition of parallel simulation.
+    #[cfg(feature = "thread")]
+    fn add_remote_event(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        delay: f64,
+        time: f64,
+        priority: i64,
+    ) -> EventId {
+        let event_id = self.event_count;
+        let event = Event {
+            id: event_id,
+            time,
+            src,
+            dst,
+            priority,
+            data,
+        };
+        let remote = self.remote.as_mut().unwrap();
+        if delay < remote.lookahead() {
+            log_incorrect_event(event, &format!("delay {} below lookahead", delay));
+            panic!(
+                "Delay of event sent to remote component {} is less than lookahead {}",
+                self.component_names[dst as usize],
+                self.remote.as_ref().unwrap().lookahead()
+            );
+        }
+        remote.add_event(event);
+        self.event_count += 1;
+        event_id
+    }
+
+    #[cfg(feature = "thread")]
+    fn assert_not_remote(&self, dst: Id) {
+        assert!(
+            !self.remote.as_ref().is_some_and(|remote| remote.contains(dst)),
+            "Ordered and deferred events cannot be sent to remote component {}",
+            self.component_names[dst as usize]
+        );
+    }
+
+    #[cfg(feature = "thread")]
+    pub fn take_remote_events(&mut self) -> Vec<Event> {
+        self.remote
+            .as_mut()
+            .map(|remote| remote.take_events())
+            .unwrap_or_default()
+    }
+
+    // Adds the event received from another partition of parallel simulation.
+    #[cfg(feature = "thread")]
+    pub fn add_received_event(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        time: f64,
+        priority: i64,
+    ) -> EventId {
+        assert!(time >= self.clock, "Received event is from the past");
+        let event_id = self.event_count;
+        self.events.push(Event {
+            id: event_id,
+            time,
+            src,
+            dst,
+            priority,
+            data,
+        });
+        self.event_count += 1;
+        self.spill_events_if_needed();
+        event_id
+    }
+
+    pub fn set_time_tick(&mut self, time_tick: TimeTick) {
+        self.time_tick = Some(time_tick);
+        self.event_hooks = true;
+    }
+
+    pub fn time_tick(&self) -> Option<TimeTick> {
+        self.time_tick
+    }
+
+    // Returns the time of the tick which is the specified number of ticks after the current one.
+    pub fn time_after_ticks(&self, ticks: u64) -> f64 {
+        let time_tick = self.time_tick.expect("Time tick is not set");
+        time_tick.time_of(time_tick.tick_number(self.clock) + ticks)
+    }
+
+    pub fn time_of_tick(&self, tick: u64) -> f64 {
+        self.time_tick.expect("Time tick is not set").time_of(tick)
+    }
+
+    pub fn snap_time(&self, time: f64) -> f64 {
+        match self.time_tick {
+            Some(time_tick) => time_tick.snap(time),
+            None => time,
+        }
+    }
+
+    pub fn add_router(&mut self, router: RouterFn) {
+        self.routers.push(router);
+        self.event_hooks = true;
+    }
+
+    // Applies the first matching router to the event, returns the added delay.
+    fn route_event(&self, event: &mut Event) -> f64 {
+        if self.routers.is_empty() {
+            self.redirect_event(event);
+            return 0.;
+        }
+        let route = self.routers.iter().find_map(|router| router(event));
+        let delay = match route {
+            Some(Route { dst, delay }) => {
+                assert!(delay >= 0., "Route delay is negative: {}", delay);
+                event.dst = dst;
+                event.time += delay;
+                delay
+            }
+            None => 0.,
+        };
+        self.redirect_event(event);
+        delay
+    }
+
+    pub fn set_redirect(&mut self, from: Id, to: Id) {
+        self.redirects.insert(from, to);
+        self.event_hooks = true;
+    }
+
+    pub fn remove_redirect(&mut self, from: Id) {
+        self.redirects.remove(&from);
+    }
+
+    // Changes the destination of event for the inactive model of multi-resolution component to the active model.
+    fn redirect_event(&self, event: &mut Event) {
+        if self.redirects.is_empty() {
+            return;
+        }
+        if let Some(&dst) = self.redirects.get(&event.dst) {
+            event.dst = dst;
+        }
+    }
+
+    pub fn add_boxed_deferred_event(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        after: EventId,
+        delay: f64,
+    ) -> EventId {
+        assert!(
+            delay >= 0.,
+            "Event delay is negative! It is not allowed to add events from the past."
+        );
+        assert!(after < self.event_count, "Event {} does not exist", after);
+        #[cfg(feature = "thread")]
+        self.assert_not_remote(dst);
+        let event_id = self.event_count;
+        let event = Event {
+            id: event_id,
+            time: f64::NAN,
+            src,
+            dst,
+            priority: 0,
+            data,
+        };
+        self.event_count += 1;
+        self.on_event_emitted(&event);
+        if self.last_dispatched_event == Some(after) {
+            // the preceding event is being processed
+            self.schedule_deferred_event(event, delay);
+        } else {
+            self.deferred_events.entry(after).or_default().push((event, delay));
+        }
+        event_id
+    }
+
+    fn schedule_deferred_event(&mut self, mut event: Event, delay: f64) {
+        event.time = self.clock + delay;
+        self.route_event(&mut event);
+        event.time = self.snap_time(event.time);
+        // the event id can be smaller than the ids of pending events, so it is stored in the heap
+        self.events.push(event);
+        self.spill_events_if_needed();
+    }
+
+    pub fn add_boxed_ordered_event(&mut self, data: Box<dyn EventData>, src: Id, dst: Id, delay: f64) -> EventId {
+        self.add_ordered_event_with_time(data, src, dst, delay, self.clock + delay)
+    }
+
+    pub fn add_boxed_ordered_event_at(&mut self, data: Box<dyn EventData>, src: Id, dst: Id, time: f64) -> EventId {
+        let delay = self.delay_until(time);
+        self.add_ordered_event_with_time(data, src, dst, delay, time)
     }
 
-    pub fn add_ordered_event<T>(&mut self, data: T, src: Id, dst: Id, delay: f64) -> EventId
-    where
-        T: EventData,
-    {
+    fn add_ordered_event_with_time(
+        &mut self,
+        data: Box<dyn EventData>,
+        src: Id,
+        dst: Id,
+        delay: f64,
+        time: f64,
+    ) -> EventId {
         if !self.can_add_ordered_event(delay) {
             panic!("Event order is broken! Ordered events should be added in non-decreasing order of their time.");
         }
+        #[cfg(feature = "thread")]
+        self.assert_not_remote(dst);
         let last_time = self.ordered_events.back().map_or(f64::MIN, |x| x.time);
         let event_id = self.event_count;
-        let event = Event {
+        let mut event = Event {
             id: event_id,
-            // max is used to enforce time order despite the floating-point errors
-            time: last_time.max(self.clock + delay),
+            time,
             src,
             dst,
-            data: Box::new(data),
+            priority: 0,
+            data,
         };
+        if self.event_hooks {
+            self.route_event(&mut event);
+            event.time = self.snap_time(event.time);
+        }
+        // max is used to enforce time order despite the floating-point errors and delays added by routers
+        event.time = last_time.max(event.time);
         if delay >= 0. {
+            self.last_ordered_time = self.last_ordered_time.max(time);
+            if self.event_hooks {
+                self.on_event_emitted(&event);
+            }
             self.ordered_events.push_back(event);
             self.event_count += 1;
+            if self.event_hooks {
+                self.spill_events_if_needed();
+            }
             event_id
         } else {
             log_incorrect_event(event, &format!("negative delay {}", delay));
@@ -208,79 +1299,212 @@ impl SimulationState {
     }
 
     pub fn can_add_ordered_event(&self, delay: f64) -> bool {
-        if let Some(evt) = self.ordered_events.back() {
-            // small epsilon is used to account for floating-point errors
-            if delay + self.clock < evt.time - EPSILON {
-                return false;
-            }
-        }
-        true
+        // small epsilon is used to account for floating-point errors
+        delay + self.clock >= self.last_ordered_time - EPSILON
     }
 
     pub fn next_event(&mut self) -> Option<Event> {
         loop {
-            let maybe_heap = self.events.peek();
-            let maybe_deque = self.ordered_events.front();
-            if maybe_heap.is_some() && (maybe_deque.is_none() || maybe_heap.unwrap() > maybe_deque.unwrap()) {
-                let event = self.events.pop().unwrap();
-                if !self.canceled_events.remove(&event.id) {
-                    self.clock = event.time;
-                    return Some(event);
+            self.load_spilled_events();
+            let source = self.next_event_source()?;
+            let mut event = self.pop_event(source);
+            if self.canceled_events.remove(&event.id) {
+                self.on_canceled_event_removed(event.id);
+            } else if !self.ref_events.is_empty() && self.remove_stale_event(&event) {
+                continue;
+            } else {
+                // the pending events emitted before the model swap are delivered to the active model
+                self.redirect_event(&mut event);
+                self.clock = event.time;
+                self.update_named_timers(&event);
+                return Some(event);
+            }
+        }
+    }
+
+    // Removes the tracking of event emitted by component reference, returns true if the event is stale and dropped.
+    fn remove_stale_event(&mut self, event: &Event) -> bool {
+        let stale = self.is_stale_event(event);
+        self.ref_events.remove(&event.id);
+        if stale {
+            log_undelivered_event(event.clone());
+            self.on_canceled_event_removed(event.id);
+        }
+        stale
+    }
+
+    fn on_canceled_event_removed(&mut self, event_id: EventId) {
+        // events deferred until the canceled event are canceled transitively
+        let mut canceled = vec![event_id];
+        while let Some(event_id) = canceled.pop() {
+            self.contracts.on_event_removed(event_id);
+            if let Some(clocks) = self.logical_clocks.as_mut() {
+                clocks.on_event_canceled(event_id);
+            }
+            self.ref_events.remove(&event_id);
+            if let Some(deferred) = self.deferred_events.remove(&event_id) {
+                for (event, _) in deferred {
+                    // the dependent event could be canceled explicitly, but it never reaches the queue now
+                    self.canceled_events.remove(&event.id);
+                    canceled.push(event.id);
                 }
-            } else if maybe_deque.is_some() {
-                let event = self.ordered_events.pop_front().unwrap();
-                if !self.canceled_events.remove(&event.id) {
-                    self.clock = event.time;
-                    return Some(event);
+            }
+            if self.coalescing.is_enabled() {
+                // the events merged into the burst of canceled event are delivered separately
+                for event in self.coalescing.on_event_canceled(event_id) {
+                    self.events.push(event);
                 }
+            }
+        }
+    }
+
+    pub fn set_coalescing_window(&mut self, dst: Id, window: f64) {
+        self.coalescing.set_window(dst, window);
+        self.event_hooks = true;
+    }
+
+    // Returns the pending events merged into the burst started by the dispatched event.
+    pub fn take_coalesced_events(&mut self, event: &Event) -> Vec<Event> {
+        if !self.coalescing.is_enabled() {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for event in self.coalescing.take_merged(event) {
+            if self.canceled_events.remove(&event.id) {
+                self.on_canceled_event_removed(event.id);
             } else {
-                return None;
+                self.contracts.on_event_removed(event.id);
+                self.update_named_timers(&event);
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    // Removes the named timer whose event is dispatched.
+    fn update_named_timers(&mut self, event: &Event) {
+        if !self.named_timers.is_empty() {
+            if let Some(timer) = event.data.downcast_ref::<TimerFired>() {
+                self.named_timers.on_timer_fired(event.dst, &timer.name, event.id);
             }
         }
     }
 
+    async_mode_enabled!(
+        // Returns the events merged into the burst started by the event to the pending event set,
+        // used when the event is awaited by async activity instead of being delivered to the handler.
+        pub fn release_coalesced_events(&mut self, event: &Event) {
+            for event in self.take_coalesced_events(event) {
+                self.events.push(event);
+            }
+        }
+    );
+
     pub fn peek_event(&mut self) -> Option<&Event> {
         loop {
-            let heap_event = self.events.peek();
-            let heap_event_id = heap_event.map(|e| e.id).unwrap_or(0);
-            let deque_event = self.ordered_events.front();
-            let deque_event_id = deque_event.map(|e| e.id).unwrap_or(0);
-
-            if heap_event.is_some() && (deque_event.is_none() || heap_event.unwrap() > deque_event.unwrap()) {
-                if self.canceled_events.remove(&heap_event_id) {
-                    self.events.pop().unwrap();
-                } else {
-                    return self.events.peek();
-                }
-            } else if deque_event.is_some() {
-                if self.canceled_events.remove(&deque_event_id) {
-                    self.ordered_events.pop_front().unwrap();
-                } else {
-                    return self.ordered_events.front();
-                }
+            self.load_spilled_events();
+            let source = self.next_event_source()?;
+            let event_id = self.front_event(source).id;
+            if self.canceled_events.remove(&event_id) {
+                self.pop_event(source);
+                self.on_canceled_event_removed(event_id);
+            } else if !self.ref_events.is_empty() && self.is_stale_event(self.front_event(source)) {
+                let event = self.pop_event(source);
+                self.remove_stale_event(&event);
             } else {
-                return None;
+                return Some(self.front_event(source));
+            }
+        }
+    }
+
+    // Returns the next event if it has the specified time and destination and is not awaited by async activity.
+    pub fn next_batched_event(&mut self, time: f64, dst: Id) -> Option<Event> {
+        self.peek_event()?;
+        let event = self.front_event(self.next_event_source()?);
+        if event.time != time || event.dst != dst || self.is_awaited_event(event) {
+            return None;
+        }
+        self.next_event()
+    }
+
+    async_mode_disabled!(
+        fn is_awaited_event(&self, _event: &Event) -> bool {
+            false
+        }
+    );
+
+    async_mode_enabled!(
+        fn is_awaited_event(&self, event: &Event) -> bool {
+            let event_key = self
+                .get_key_getter(event.data.type_id(), event.dst)
+                .map(|getter| getter(event.data.as_ref()));
+            self.has_event_promise_for(event, event_key)
+        }
+    );
+
+    // Returns the source of the next pending event according to the event order (by time, priority and id).
+    fn next_event_source(&self) -> Option<EventSource> {
+        let mut next: Option<(&Event, EventSource)> = None;
+        let candidates = [
+            (self.events.peek(), EventSource::Heap),
+            (self.ordered_events.front(), EventSource::Ordered),
+            (self.immediate_events.front(), EventSource::Immediate),
+        ];
+        for (event, source) in candidates {
+            if let Some(event) = event {
+                // event ordering is inverted to be used with max-heap, so the next event is the greatest one
+                if next.is_none_or(|(next_event, _)| event > next_event) {
+                    next = Some((event, source));
+                }
+            }
+        }
+        next.map(|(_, source)| source)
+    }
+
+    fn front_event(&self, source: EventSource) -> &Event {
+        match source {
+            EventSource::Heap => self.events.peek().unwrap(),
+            EventSource::Ordered => self.ordered_events.front().unwrap(),
+            EventSource::Immediate => self.immediate_events.front().unwrap(),
+        }
+    }
+
+    fn pop_event(&mut self, source: EventSource) -> Event {
+        match source {
+            EventSource::Heap => {
+                let event = self.events.pop().unwrap();
+                self.spilled_events.on_reloaded_event_removed(event.id);
+                event
             }
+            EventSource::Ordered => self.ordered_events.pop_front().unwrap(),
+            EventSource::Immediate => self.immediate_events.pop_front().unwrap(),
         }
     }
 
     pub fn cancel_event(&mut self, id: EventId) {
-        self.canceled_events.insert(id);
+        self.mark_canceled(id);
+    }
+
+    // Marks the pending event as canceled, it is removed when it reaches the front of the queue.
+    // Returns false if the event is already canceled.
+    fn mark_canceled(&mut self, id: EventId) -> bool {
+        if !self.canceled_events.insert(id) {
+            return false;
+        }
+        self.contracts.on_event_removed(id);
+        self.trace_file.on_event_canceled(id, self.clock, &self.component_names);
+        if let Some(trace) = self.trace.as_mut() {
+            trace.on_event_canceled(id);
+        }
+        true
     }
 
     pub fn cancel_events<F>(&mut self, pred: F)
     where
         F: Fn(&Event) -> bool,
     {
-        for event in self.events.iter() {
-            if pred(event) {
-                self.canceled_events.insert(event.id);
-            }
-        }
-        for event in self.ordered_events.iter() {
-            if pred(event) {
-                self.canceled_events.insert(event.id);
-            }
+        for id in self.find_pending_events(|event, _| pred(event), |event| event.id) {
+            self.mark_canceled(id);
         }
     }
 
@@ -288,19 +1512,8 @@ impl SimulationState {
     where
         F: Fn(&Event) -> bool,
     {
-        let mut events = Vec::new();
-        for event in self.events.iter() {
-            if pred(event) {
-                self.canceled_events.insert(event.id);
-                events.push(event.clone());
-            }
-        }
-        for event in self.ordered_events.iter() {
-            if pred(event) {
-                self.canceled_events.insert(event.id);
-                events.push(event.clone());
-            }
-        }
+        let mut events = self.find_pending_events(|event, _| pred(event), Event::clone);
+        events.retain(|event| self.mark_canceled(event.id));
         events
     }
 
@@ -309,17 +1522,54 @@ impl SimulationState {
     where
         F: Fn(&Event) -> bool,
     {
-        for event in self.events.iter() {
-            if pred(event) {
-                self.canceled_events.insert(event.id);
+        let ids = self.find_pending_events(
+            |event, ordered| !ordered && !self.spilled_events.is_reloaded_ordered_event(event.id) && pred(event),
+            |event| event.id,
+        );
+        for id in ids {
+            self.mark_canceled(id);
+        }
+    }
+
+    // Maps the pending events satisfying the predicate, which also receives the flag of ordered event.
+    fn find_pending_events<F, M, R>(&self, pred: F, map: M) -> Vec<R>
+    where
+        F: Fn(&Event, bool) -> bool,
+        M: Fn(&Event) -> R,
+    {
+        let mut events = Vec::new();
+        // deferred events are scheduled via the heap, so they are treated as unordered
+        let unordered = self
+            .events
+            .iter()
+            .chain(self.immediate_events.iter())
+            .chain(self.coalescing.merged_events())
+            .chain(self.deferred_events.values().flatten().map(|(event, _)| event));
+        for (event, ordered) in unordered
+            .map(|event| (event, false))
+            .chain(self.ordered_events.iter().map(|event| (event, true)))
+        {
+            if pred(event, ordered) {
+                events.push(map(event));
             }
         }
+        self.spilled_events.for_each(|event, ordered| {
+            if pred(&event, ordered) {
+                events.push(map(&event));
+            }
+        });
+        events
     }
 
     pub fn event_count(&self) -> u64 {
         self.event_count
     }
 
+    // Returns the number of events in memory queues, including the canceled events which are not removed yet.
+    pub fn queued_event_count(&self) -> usize {
+        self.events.len() + self.ordered_events.len() + self.immediate_events.len()
+    }
+
     pub fn dump_events(&self) -> Vec<Event> {
         let mut output = Vec::new();
         for event in self.events.iter() {
@@ -327,20 +1577,288 @@ impl SimulationState {
                 output.push((*event).clone())
             }
         }
-        for event in self.ordered_events.iter() {
+        for event in self
+            .ordered_events
+            .iter()
+            .chain(self.immediate_events.iter())
+            .chain(self.coalescing.merged_events())
+        {
             if !self.canceled_events.contains(&event.id) {
                 output.push((*event).clone())
             }
         }
+        self.spilled_events.for_each(|event, _| {
+            if !self.canceled_events.contains(&event.id) {
+                output.push(event)
+            }
+        });
         output.sort();
-        // Because the sorting order of events is inverted to be used with BinaryHeap
+        // Because the sorting order of events is inverted to be used with max-heap
         output.reverse();
         output
     }
 
+    // Checkpoints ----------------------------------------------------------------------------------------------------
+
+    // Returns true if the checkpoint can be saved, see save_checkpoint.
+    pub fn can_save_checkpoint(&self) -> bool {
+        self.deferred_events.is_empty() && self.coalescing.merged_events().next().is_none() && !self.has_tasks()
+    }
+
+    pub fn save_checkpoint(&self) -> SimulationCheckpoint {
+        assert!(
+            self.deferred_events.is_empty(),
+            "Checkpoint cannot be saved while there are events emitted via emit_after"
+        );
+        assert!(
+            self.coalescing.merged_events().next().is_none(),
+            "Checkpoint cannot be saved while there are coalesced events"
+        );
+        self.assert_no_tasks("Checkpoint cannot be saved");
+        let mut events = Vec::new();
+        for event in self.events.iter().chain(self.immediate_events.iter()) {
+            if !self.canceled_events.contains(&event.id) {
+                events.push((event.clone(), false));
+            }
+        }
+        for event in self.ordered_events.iter() {
+            if !self.canceled_events.contains(&event.id) {
+                events.push((event.clone(), true));
+            }
+        }
+        self.spilled_events.for_each(|event, ordered| {
+            if !self.canceled_events.contains(&event.id) {
+                events.push((event, ordered));
+            }
+        });
+        events.sort_by_key(|(event, _)| event.id);
+        SimulationCheckpoint {
+            metadata: self.metadata.clone(),
+            time: self.clock,
+            event_count: self.event_count,
+            components: self
+                .component_name_to_id
+                .iter()
+                .map(|(name, id)| (*id, name.clone()))
+                .collect(),
+            rand: self.rand.clone(),
+            last_ordered_time: self.last_ordered_time,
+            events,
+            timers: self.named_timers.entries(),
+        }
+    }
+
+    // Checks that each component from the checkpoint exists and has the same identifier.
+    pub fn assert_checkpoint_components(&self, components: &BTreeMap<Id, String>) {
+        for (id, name) in components.iter() {
+            assert!(
+                self.component_name_to_id.get(name) == Some(id),
+                "Component {} with identifier {} from checkpoint does not exist",
+                name,
+                id
+            );
+        }
+    }
+
+    // Replaces the clock, random number generator and pending events with the ones from the checkpoint.
+    pub fn load_checkpoint(&mut self, checkpoint: SimulationCheckpoint) {
+        assert!(
+            !self.spilled_events.has_runs(),
+            "Checkpoint cannot be loaded into simulation with spilled events"
+        );
+        self.assert_no_tasks("Checkpoint cannot be loaded into simulation");
+        self.clock = checkpoint.time;
+        self.rand = checkpoint.rand;
+        self.event_count = checkpoint.event_count;
+        self.last_ordered_time = checkpoint.last_ordered_time;
+        self.contracts
+            .reset_outstanding(checkpoint.events.iter().map(|(event, _)| event));
+        let mut heap_events = Vec::new();
+        self.ordered_events.clear();
+        for (event, ordered) in checkpoint.events {
+            if ordered {
+                self.ordered_events.push_back(event);
+            } else {
+                heap_events.push(event);
+            }
+        }
+        self.events = DaryHeap::from_vec(heap_events, self.events.arity());
+        self.immediate_events.clear();
+        self.canceled_events.clear();
+        self.ref_events.clear();
+        self.deferred_events.clear();
+        self.coalescing.clear();
+        self.last_dispatched_event = None;
+        self.named_timers = NamedTimers::default();
+        for (component, name, event_id) in checkpoint.timers {
+            self.named_timers.set(component, &name, event_id);
+        }
+        self.spill_events_if_needed();
+    }
+
+    fn assert_no_tasks(&self, message: &str) {
+        assert!(!self.has_tasks(), "{} with alive asynchronous tasks", message);
+    }
+
+    async_mode_disabled!(
+        fn has_tasks(&self) -> bool {
+            false
+        }
+    );
+
+    async_mode_enabled!(
+        fn has_tasks(&self) -> bool {
+            self.live_tasks.get() > 0 || !self.timers.is_empty()
+        }
+    );
+
+    // Group activity -------------------------------------------------------------------------------------------------
+
+    pub fn group_components(&self, group: &str) -> FxHashSet<Id> {
+        self.delays
+            .group_components(group)
+            .unwrap_or_else(|| panic!("Component group {} does not exist", group))
+    }
+
+    // Returns true if some pending event is emitted by or destined for the components, or some of them has pending
+    // timers or queued tasks in async mode.
+    pub fn has_group_activity(&self, components: &FxHashSet<Id>) -> bool {
+        let involves = |event: &Event| {
+            !self.canceled_events.contains(&event.id)
+                && (components.contains(&event.src) || components.contains(&event.dst))
+        };
+        let has_event = self
+            .events
+            .iter()
+            .chain(self.ordered_events.iter())
+            .chain(self.immediate_events.iter())
+            .chain(self.coalescing.merged_events())
+            .chain(self.deferred_events.values().flatten().map(|(event, _)| event))
+            .any(involves);
+        if has_event {
+            return true;
+        }
+        let mut has_spilled = false;
+        self.spilled_events.for_each(|event, _| has_spilled |= involves(&event));
+        has_spilled || self.has_group_tasks(components)
+    }
+
+    async_mode_disabled!(
+        fn has_group_tasks(&self, _components: &FxHashSet<Id>) -> bool {
+            false
+        }
+    );
+
+    async_mode_enabled!(
+        fn has_group_tasks(&self, components: &FxHashSet<Id>) -> bool {
+            self.timers
+                .iter()
+                .any(|timer| components.contains(&timer.component_id) && !self.canceled_timers.contains(&timer.id))
+                || components.iter().any(|id| self.queued_task_count(*id) > 0)
+        }
+    );
+
+    // Spilling events to disk ----------------------------------------------------------------------------------------
+
+    pub fn set_event_heap_arity(&mut self, arity: usize) {
+        self.events.set_arity(arity);
+    }
+
+    pub fn enable_event_spilling(&mut self, config: SpillConfig) {
+        self.spilled_events.enable(config);
+        self.event_hooks = true;
+        self.spilled_events.set_metadata(&self.metadata);
+        self.spill_events_if_needed();
+    }
+
+    pub fn register_spillable_event<T: EventData + DeserializeOwned>(&mut self) {
+        self.spilled_events.register::<T>();
+    }
+
+    // Moves the farthest pending events to disk if the number of events in memory exceeds the limit.
+    fn spill_events_if_needed(&mut self) {
+        if !self
+            .spilled_events
+            .should_spill(self.events.len() + self.ordered_events.len())
+        {
+            return;
+        }
+        let mut spilled = Vec::new();
+        let mut canceled = Vec::new();
+
+        let arity = self.events.arity();
+        let mut heap_events = std::mem::replace(&mut self.events, DaryHeap::new(arity)).into_vec();
+        // Event ordering is inverted to be used with max-heap, so this sorts events from the nearest to the farthest
+        heap_events.sort_by(|a, b| b.cmp(a));
+        let keep_count = self.spilled_events.keep_count(heap_events.len());
+        let farthest = heap_events.split_off(keep_count.min(heap_events.len()));
+        for event in farthest {
+            if self.canceled_events.remove(&event.id) {
+                self.spilled_events.on_reloaded_event_removed(event.id);
+                canceled.push(event.id);
+                continue;
+            }
+            if self.spilled_events.is_spillable(&event) {
+                let ordered = self.spilled_events.is_reloaded_ordered_event(event.id);
+                self.spilled_events.on_reloaded_event_removed(event.id);
+                spilled.push((event, ordered));
+            } else {
+                heap_events.push(event);
+            }
+        }
+        self.events = DaryHeap::from_vec(heap_events, arity);
+
+        let keep_count = self.spilled_events.keep_count(self.ordered_events.len());
+        let tail = self.ordered_events.split_off(keep_count.min(self.ordered_events.len()));
+        for event in tail {
+            if self.canceled_events.remove(&event.id) {
+                canceled.push(event.id);
+                continue;
+            }
+            if self.spilled_events.is_spillable(&event) {
+                spilled.push((event, true));
+            } else {
+                self.ordered_events.push_back(event);
+            }
+        }
+
+        // the dropped canceled events release the events merged into their bursts or deferred after them,
+        // which is done after the heap is rebuilt to keep the released events in memory
+        for event_id in canceled {
+            self.on_canceled_event_removed(event_id);
+        }
+
+        spilled.sort_by(|(a, _), (b, _)| b.cmp(a));
+        self.spilled_events.write_run(spilled);
+        self.spilled_events
+            .on_spilled(self.events.len() + self.ordered_events.len());
+    }
+
+    // Loads spilled events back to memory if they precede the nearest in-memory events.
+    fn load_spilled_events(&mut self) {
+        while self.spilled_events.has_runs() {
+            let next_event = self.next_event_source().map(|source| {
+                let event = self.front_event(source);
+                (event.time, event.priority, event.id)
+            });
+            if let Some(events) = self.spilled_events.take_run_before(next_event) {
+                for (event, ordered) in events {
+                    if ordered {
+                        self.spilled_events.on_ordered_event_reloaded(event.id);
+                    }
+                    self.events.push(event);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
     async_mode_disabled!(
         fn on_register(&mut self) {}
+        fn on_unregister(&mut self, _id: Id) {}
         pub fn on_static_handler_removed(&mut self, _id: Id) {}
+        fn reset_wait_stats(&mut self) {}
     );
 
     async_mode_enabled!(
@@ -350,6 +1868,30 @@ impl SimulationState {
             self.registered_static_handlers.push(false)
         }
 
+        fn on_unregister(&mut self, id: Id) {
+            self.component_dispatch_precedence.remove(&id);
+            self.mailboxes.remove(&id);
+            self.component_key_getters
+                .retain(|(_, component_id), _| *component_id != id);
+            self.task_limiters.remove(&id);
+            self.registered_static_handlers[id as usize] = false;
+        }
+
+        pub fn set_dispatch_precedence(&mut self, precedence: DispatchPrecedence) {
+            self.dispatch_precedence = precedence;
+        }
+
+        pub fn set_component_dispatch_precedence(&mut self, id: Id, precedence: DispatchPrecedence) {
+            self.component_dispatch_precedence.insert(id, precedence);
+        }
+
+        pub fn dispatch_precedence(&self, id: Id) -> DispatchPrecedence {
+            self.component_dispatch_precedence
+                .get(&id)
+                .copied()
+                .unwrap_or(self.dispatch_precedence)
+        }
+
         pub fn on_static_handler_added(&mut self, id: Id) {
             self.registered_static_handlers[id as usize] = true;
         }
@@ -364,10 +1906,39 @@ impl SimulationState {
                 .map_or_else(|| false, |flag| *flag)
         }
 
+        // Mailboxes ---------------------------------------------------------------------------------------------------
+
+        pub fn register_mailbox(&mut self, id: Id, mailbox: Weak<MailboxInner>) {
+            if let Some((existing, _)) = self.mailboxes.get(&id) {
+                assert!(
+                    existing.strong_count() == 0,
+                    "Component {} already has a mailbox",
+                    self.lookup_name(id)
+                );
+            }
+            self.mailboxes.insert(id, (mailbox, FxHashSet::default()));
+        }
+
+        pub fn accept_mailbox_event<T: EventData>(&mut self, id: Id) {
+            if let Some((_, types)) = self.mailboxes.get_mut(&id) {
+                types.insert(TypeId::of::<T>());
+            }
+        }
+
+        // Returns the mailbox which should receive the event instead of its destination.
+        pub fn mailbox_for(&mut self, event: &Event) -> Option<Rc<MailboxInner>> {
+            let (mailbox, types) = self.mailboxes.get(&event.dst)?;
+            let Some(mailbox) = mailbox.upgrade() else {
+                self.mailboxes.remove(&event.dst);
+                return None;
+            };
+            types.contains(&event.data.type_id()).then_some(mailbox)
+        }
+
         // Spawning async tasks ----------------------------------------------------------------------------------------
 
         pub fn spawn(&mut self, future: impl Future<Output = ()> + 'static) {
-            Task::spawn(future, self.executor.clone());
+            Task::spawn(future, self.executor.clone(), self.live_tasks.clone());
         }
 
         pub fn spawn_component(&mut self, component_id: Id, future: impl Future<Output = ()> + 'static) {
@@ -377,7 +1948,69 @@ impl SimulationState {
                 Register static handler for component {} before spawning tasks for it (empty impl StaticEventHandler is OK).",
                 component_id,
             );
-            Task::spawn(future, self.executor.clone());
+            if let Some(limiter) = self.task_limiters.get(&component_id) {
+                TaskLimiter::spawn(limiter, future);
+            } else {
+                Task::spawn(future, self.executor.clone(), self.live_tasks.clone());
+            }
+        }
+
+        pub fn set_task_limit(&mut self, component_id: Id, limit: usize) {
+            assert!(limit > 0, "Task limit must be positive");
+            let limiter = self
+                .task_limiters
+                .entry(component_id)
+                .or_insert_with(|| TaskLimiter::new(self.executor.clone(), self.live_tasks.clone()));
+            TaskLimiter::set_limit(limiter, limit);
+        }
+
+        pub fn queued_task_count(&self, component_id: Id) -> usize {
+            self.task_limiters
+                .get(&component_id)
+                .map_or(0, |limiter| limiter.borrow().queued_count())
+        }
+
+        // Returns the queued tasks of the component, which must be dropped after releasing the state borrow.
+        pub fn take_component_queued_tasks(&mut self, component_id: Id) -> Vec<impl Future<Output = ()>> {
+            self.task_limiters
+                .get(&component_id)
+                .map_or_else(Vec::new, |limiter| limiter.borrow_mut().take_queued().into())
+        }
+
+        // Wait statistics ---------------------------------------------------------------------------------------------
+
+        pub fn enable_wait_stats(&mut self) {
+            if self.wait_stats.is_none() {
+                self.wait_stats = Some(BTreeMap::new());
+            }
+        }
+
+        pub fn wait_stats(&self) -> BTreeMap<String, WaitStats> {
+            self.wait_stats.clone().unwrap_or_default()
+        }
+
+        fn reset_wait_stats(&mut self) {
+            if let Some(stats) = self.wait_stats.as_mut() {
+                stats.clear();
+            }
+        }
+
+        // Returns the site of a new wait if the wait statistics are enabled.
+        pub fn start_wait(&self, location: &'static Location<'static>) -> Option<Box<WaitSite>> {
+            self.wait_stats.as_ref().map(|_| {
+                Box::new(WaitSite {
+                    location,
+                    label: None,
+                    start_time: self.clock,
+                })
+            })
+        }
+
+        pub fn finish_wait(&mut self, site: &WaitSite) {
+            let duration = self.clock - site.start_time;
+            if let Some(stats) = self.wait_stats.as_mut() {
+                stats.entry(site.tag()).or_default().add(duration);
+            }
         }
 
         // Timers ------------------------------------------------------------------------------------------------------
@@ -388,7 +2021,8 @@ impl SimulationState {
             timeout: f64,
             sim_state: Rc<RefCell<SimulationState>>,
         ) -> TimerFuture {
-            let timer_promise = TimerPromise::new(self.timer_count, component_id, self.time() + timeout);
+            let time = self.snap_time(self.time() + timeout);
+            let timer_promise = TimerPromise::new(self.timer_count, component_id, time);
             let timer_future = timer_promise.future(sim_state);
             self.timers.push(timer_promise);
             self.timer_count += 1;
@@ -470,10 +2104,30 @@ impl SimulationState {
             }
         }
 
+        pub fn create_predicate_event_future<T: EventData>(
+            &mut self,
+            dst: Id,
+            predicate: EventPredicateFn,
+            sim_state: Rc<RefCell<SimulationState>>,
+        ) -> EventFuture<T> {
+            let (promise, mut future) = EventPromise::contract(dst, None, None, sim_state);
+            let seq = self.event_promises.insert_with_predicate::<T>(dst, predicate, promise);
+            future.set_predicate_seq(seq);
+            future
+        }
+
         pub fn has_event_promise_for(&self, event: &Event, event_key: Option<EventKey>) -> bool {
             self.event_promises.has_promise_for(event, event_key)
         }
 
+        // Returns the event received by async task, which did not consume it, to the pending events.
+        // The event is delivered again before other events at the current time.
+        pub fn requeue_event(&mut self, mut event: Event) {
+            event.time = event.time.max(self.clock);
+            event.priority = i64::MIN;
+            self.events.push(event);
+        }
+
         pub fn complete_event_promise(&mut self, event: Event, event_key: Option<EventKey>) {
             // panics if there is no promise
             let promise = self.event_promises.remove_promise_for(&event, event_key).unwrap();
@@ -505,26 +2159,66 @@ impl SimulationState {
             self.event_promises.remove::<T>(dst, src, event_key);
         }
 
+        pub fn pending_event_waits(&self) -> Vec<PendingWait> {
+            self.event_promises.pending_waits()
+        }
+
+        // Called by dropped EventFuture with predicate that was not completed.
+        pub fn on_incomplete_predicate_event_future_drop(&mut self, seq: u64) {
+            self.event_promises.remove_with_predicate(seq);
+        }
+
         // Event key getters -------------------------------------------------------------------------------------------
 
         pub fn register_key_getter_for<T: EventData>(&mut self, key_getter: impl Fn(&T) -> EventKey + 'static) {
-            self.key_getters.insert(
-                TypeId::of::<T>(),
-                Rc::new(move |raw_data| {
-                    if let Some(data) = raw_data.downcast_ref::<T>() {
-                        key_getter(data)
-                    } else {
-                        panic!(
-                            "Key getter for type {} is incorrectly used for type {}",
-                            std::any::type_name::<T>(),
-                            serde_type_name::type_name(&raw_data).unwrap(),
-                        );
-                    }
-                }),
-            );
+            self.key_getters
+                .insert(TypeId::of::<T>(), Self::wrap_key_getter(key_getter));
         }
 
-        pub fn get_key_getter(&self, type_id: TypeId) -> Option<KeyGetterFn> {
+        // Registers the key getter of keyed event type unless there is a key getter for the type and component.
+        pub fn register_keyed_event_on_use<T: KeyedEvent>(&mut self, dst: Id) {
+            if self.get_key_getter(TypeId::of::<T>(), dst).is_none() {
+                self.register_key_getter_for::<T>(T::event_key);
+            }
+        }
+
+        pub fn register_component_key_getter_for<T: EventData>(
+            &mut self,
+            component_id: Id,
+            key_getter: impl Fn(&T) -> EventKey + 'static,
+        ) {
+            self.component_key_getters
+                .insert((TypeId::of::<T>(), component_id), Self::wrap_key_getter(key_getter));
+        }
+
+        fn wrap_key_getter<T: EventData>(key_getter: impl Fn(&T) -> EventKey + 'static) -> KeyGetterFn {
+            Rc::new(move |raw_data| {
+                if let Some(data) = raw_data.downcast_ref::<T>() {
+                    key_getter(data)
+                } else {
+                    panic!(
+                        "Key getter for type {} is incorrectly used for type {}",
+                        std::any::type_name::<T>(),
+                        serde_type_name::type_name(&raw_data).unwrap(),
+                    );
+                }
+            })
+        }
+
+        pub fn next_request_id(&mut self) -> RequestId {
+            let id = self.request_count;
+            self.request_count += 1;
+            id
+        }
+
+        // Returns the key getter for events of the specified type delivered to the specified component.
+        // The key getter registered for the component takes precedence over the global one.
+        pub fn get_key_getter(&self, type_id: TypeId, dst: Id) -> Option<KeyGetterFn> {
+            if !self.component_key_getters.is_empty() {
+                if let Some(key_getter) = self.component_key_getters.get(&(type_id, dst)) {
+                    return Some(key_getter.clone());
+                }
+            }
             self.key_getters.get(&type_id).cloned()
         }
     );
@@ -0,0 +1,251 @@
+//! Timed state machines.
+//!
+//! Protocol models frequently describe component behavior as a set of states with transitions triggered by events
+//! or by timeouts. This module provides a [`StateMachine`] helper which keeps the current state, performs declared
+//! transitions, calls the enter and exit hooks, and manages the timers of timed transitions via
+//! [named timers](crate::timer).
+
+use std::any::TypeId;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rustc_hash::FxHashMap;
+
+use crate::event::{Event, EventData};
+use crate::timer::TimerFired;
+use crate::SimulationContext;
+
+/// Transition between states of [`StateMachine`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition<S> {
+    /// Previous state.
+    pub from: S,
+    /// New state.
+    pub to: S,
+}
+
+type GuardFn = Box<dyn Fn(&dyn EventData) -> bool>;
+type HookFn<S> = Box<dyn FnMut(&SimulationContext, Transition<S>)>;
+
+struct EventTransition<S> {
+    guard: Option<GuardFn>,
+    to: S,
+}
+
+/// Helper for implementing components as state machines with timed and event-triggered transitions.
+///
+/// The state machine is owned by a component, which passes the received events to [`handle`](Self::handle).
+/// Timed transitions are performed when the machine stays in a state for the specified time, using a named timer
+/// of the component with the machine name. The timer is set on entering the state and cancelled on leaving it.
+/// Event-triggered transitions are performed on receiving events of the specified type, optionally satisfying
+/// a guard. The enter and exit hooks are called on each transition, including transitions to the same state,
+/// which also restart the timer.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use serde::Serialize;
+/// use simcore::state_machine::{StateMachine, Transition};
+/// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum State {
+///     Idle,
+///     Waiting,
+///     Done,
+/// }
+///
+/// #[derive(Clone, Serialize)]
+/// struct Start {}
+///
+/// #[derive(Clone, Serialize)]
+/// struct Ack {}
+///
+/// struct Component {
+///     ctx: SimulationContext,
+///     fsm: StateMachine<State>,
+///     transitions: Vec<(Transition<State>, f64)>,
+/// }
+///
+/// impl EventHandler for Component {
+///     fn on(&mut self, event: Event) {
+///         if let Some(transition) = self.fsm.handle(&self.ctx, &event) {
+///             self.transitions.push((transition, self.ctx.time()));
+///         }
+///     }
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("comp");
+/// let mut fsm = StateMachine::new("fsm", State::Idle)
+///     .with_event_transition::<Start>(State::Idle, State::Waiting)
+///     .with_event_transition::<Ack>(State::Waiting, State::Done)
+///     .with_timed_transition(State::Waiting, 5., State::Idle);
+/// fsm.start(&ctx);
+/// let comp = Rc::new(RefCell::new(Component { ctx, fsm, transitions: Vec::new() }));
+/// let comp_id = sim.add_handler("comp", comp.clone());
+///
+/// let client = sim.create_context("client");
+/// client.emit(Start {}, comp_id, 1.);
+/// client.emit(Start {}, comp_id, 7.);
+/// client.emit(Ack {}, comp_id, 9.);
+/// sim.step_until_no_events();
+///
+/// let transitions: Vec<_> = comp.borrow().transitions.iter().map(|(t, time)| (t.to, *time)).collect();
+/// assert_eq!(
+///     transitions,
+///     vec![(State::Waiting, 1.), (State::Idle, 6.), (State::Waiting, 7.), (State::Done, 9.)]
+/// );
+/// ```
+pub struct StateMachine<S> {
+    name: String,
+    state: S,
+    entered_at: f64,
+    timed_transitions: FxHashMap<S, (f64, S)>,
+    event_transitions: FxHashMap<(S, TypeId), Vec<EventTransition<S>>>,
+    enter_hooks: FxHashMap<S, HookFn<S>>,
+    exit_hooks: FxHashMap<S, HookFn<S>>,
+}
+
+impl<S> StateMachine<S>
+where
+    S: Copy + Eq + Hash + Debug + 'static,
+{
+    /// Creates a state machine with the specified name and initial state.
+    ///
+    /// The name is used as the name of timer, so it must be unique among the named timers of the component.
+    pub fn new(name: &str, initial: S) -> Self {
+        Self {
+            name: name.to_owned(),
+            state: initial,
+            entered_at: 0.,
+            timed_transitions: FxHashMap::default(),
+            event_transitions: FxHashMap::default(),
+            enter_hooks: FxHashMap::default(),
+            exit_hooks: FxHashMap::default(),
+        }
+    }
+
+    /// Adds transition from state `from` to state `to` performed after staying in `from` for `delay`.
+    ///
+    /// Panics if the timed transition from this state is already added.
+    pub fn with_timed_transition(mut self, from: S, delay: f64, to: S) -> Self {
+        assert!(delay >= 0., "Transition delay must be non-negative");
+        let prev = self.timed_transitions.insert(from, (delay, to));
+        assert!(
+            prev.is_none(),
+            "Timed transition from state {:?} is already added",
+            from
+        );
+        self
+    }
+
+    /// Adds transition from state `from` to state `to` performed on receiving event of type `T`.
+    ///
+    /// If there are several transitions for the same state and event type, the first added one
+    /// with satisfied guard is performed.
+    pub fn with_event_transition<T: EventData>(self, from: S, to: S) -> Self {
+        self.add_event_transition::<T>(from, to, None)
+    }
+
+    /// Adds transition from state `from` to state `to` performed on receiving event of type `T`
+    /// if the event payload satisfies the specified guard.
+    pub fn with_guarded_event_transition<T, F>(self, from: S, to: S, guard: F) -> Self
+    where
+        T: EventData,
+        F: Fn(&T) -> bool + 'static,
+    {
+        let guard: GuardFn = Box::new(move |data| guard(data.downcast_ref::<T>().unwrap()));
+        self.add_event_transition::<T>(from, to, Some(guard))
+    }
+
+    /// Sets the hook called on entering the specified state.
+    pub fn with_enter_hook<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: FnMut(&SimulationContext, Transition<S>) + 'static,
+    {
+        self.enter_hooks.insert(state, Box::new(hook));
+        self
+    }
+
+    /// Sets the hook called on leaving the specified state.
+    pub fn with_exit_hook<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: FnMut(&SimulationContext, Transition<S>) + 'static,
+    {
+        self.exit_hooks.insert(state, Box::new(hook));
+        self
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Returns the time of entering the current state.
+    pub fn entered_at(&self) -> f64 {
+        self.entered_at
+    }
+
+    /// Starts the state machine in its initial state.
+    ///
+    /// Calls the enter hook of the initial state with transition from and to this state,
+    /// and sets the timer of its timed transition.
+    pub fn start(&mut self, ctx: &SimulationContext) {
+        let state = self.state;
+        self.enter(ctx, Transition { from: state, to: state });
+    }
+
+    /// Processes the event received by the component and performs the matching transition, if any.
+    ///
+    /// Returns the performed transition or `None` if the event does not trigger a transition.
+    /// The fired timers of this state machine are also processed here.
+    pub fn handle(&mut self, ctx: &SimulationContext, event: &Event) -> Option<Transition<S>> {
+        let data = event.data.as_ref();
+        let to = if let Some(timer) = data.downcast_ref::<TimerFired>() {
+            if timer.name != self.name {
+                return None;
+            }
+            self.timed_transitions.get(&self.state)?.1
+        } else {
+            self.event_transitions
+                .get(&(self.state, data.type_id()))?
+                .iter()
+                .find(|transition| transition.guard.as_ref().is_none_or(|guard| guard(data)))?
+                .to
+        };
+        Some(self.transition(ctx, to))
+    }
+
+    /// Performs the transition to the specified state, e.g. in response to conditions checked by the component.
+    pub fn transition(&mut self, ctx: &SimulationContext, to: S) -> Transition<S> {
+        let transition = Transition { from: self.state, to };
+        ctx.cancel_timer(&self.name);
+        if let Some(hook) = self.exit_hooks.get_mut(&transition.from) {
+            hook(ctx, transition);
+        }
+        self.enter(ctx, transition);
+        transition
+    }
+
+    fn enter(&mut self, ctx: &SimulationContext, transition: Transition<S>) {
+        self.state = transition.to;
+        self.entered_at = ctx.time();
+        if let Some(&(delay, _)) = self.timed_transitions.get(&transition.to) {
+            ctx.set_timer(&self.name, delay);
+        }
+        if let Some(hook) = self.enter_hooks.get_mut(&transition.to) {
+            hook(ctx, transition);
+        }
+    }
+
+    fn add_event_transition<T: EventData>(mut self, from: S, to: S, guard: Option<GuardFn>) -> Self {
+        self.event_transitions
+            .entry((from, TypeId::of::<T>()))
+            .or_default()
+            .push(EventTransition { guard, to });
+        self
+    }
+}
@@ -0,0 +1,356 @@
+//! Stop conditions.
+//!
+//! Simulation experiments often run until a statistically sound stopping rule is satisfied rather than for a fixed
+//! time, e.g. until the mean response time is estimated with the required precision. This module provides reusable
+//! conditions, which are passed to [`Simulation::step_until`](crate::Simulation::step_until) and can be combined
+//! via [`and`](StopCondition::and) and [`or`](StopCondition::or):
+//!
+//! - [`TimeReached`] is met when the simulation time reaches the specified time,
+//! - [`EventCount`] is met after the specified number of events of some type are processed,
+//! - [`RelativePrecision`] and [`ConfidenceWidth`] are met when the confidence interval of the [`Metric`] mean
+//!   becomes narrow enough.
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::analysis::SampleStats;
+use crate::event::{Event, EventData};
+
+/// Condition of stopping the simulation checked by [`Simulation::step_until`](crate::Simulation::step_until).
+pub trait StopCondition {
+    /// Called before processing each event.
+    fn on_event(&mut self, _event: &Event) {}
+
+    /// Returns true if the simulation should stop, checked before the first step and after each step.
+    fn is_met(&mut self, time: f64) -> bool;
+
+    /// Returns the condition which is met when both conditions are met.
+    fn and<C: StopCondition>(self, other: C) -> And<Self, C>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Returns the condition which is met when any of the conditions is met.
+    fn or<C: StopCondition>(self, other: C) -> Or<Self, C>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+}
+
+impl StopCondition for Box<dyn StopCondition> {
+    fn on_event(&mut self, event: &Event) {
+        (**self).on_event(event)
+    }
+
+    fn is_met(&mut self, time: f64) -> bool {
+        (**self).is_met(time)
+    }
+}
+
+/// Condition which is met when both conditions are met, see [`StopCondition::and`].
+pub struct And<A, B>(A, B);
+
+impl<A: StopCondition, B: StopCondition> StopCondition for And<A, B> {
+    fn on_event(&mut self, event: &Event) {
+        self.0.on_event(event);
+        self.1.on_event(event);
+    }
+
+    fn is_met(&mut self, time: f64) -> bool {
+        // both conditions are checked to keep their state up to date
+        let a = self.0.is_met(time);
+        let b = self.1.is_met(time);
+        a && b
+    }
+}
+
+/// Condition which is met when any of the conditions is met, see [`StopCondition::or`].
+pub struct Or<A, B>(A, B);
+
+impl<A: StopCondition, B: StopCondition> StopCondition for Or<A, B> {
+    fn on_event(&mut self, event: &Event) {
+        self.0.on_event(event);
+        self.1.on_event(event);
+    }
+
+    fn is_met(&mut self, time: f64) -> bool {
+        let a = self.0.is_met(time);
+        let b = self.1.is_met(time);
+        a || b
+    }
+}
+
+/// Condition which is met when the simulation time reaches the specified time.
+///
+/// Unlike [`Simulation::step_until_time`](crate::Simulation::step_until_time), the condition does not advance the
+/// simulation time to the specified time, so the simulation stops after processing the first event with the time
+/// not smaller than the specified one.
+pub struct TimeReached(pub f64);
+
+impl StopCondition for TimeReached {
+    fn is_met(&mut self, time: f64) -> bool {
+        time >= self.0
+    }
+}
+
+/// Condition which is met after the specified number of events of some type are processed.
+pub struct EventCount {
+    type_id: TypeId,
+    target: u64,
+    count: u64,
+}
+
+impl EventCount {
+    /// Creates the condition which is met after processing `count` events of type `T`.
+    pub fn of<T: EventData>(count: u64) -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            target: count,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of processed events of the type.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl StopCondition for EventCount {
+    fn on_event(&mut self, event: &Event) {
+        if event.data.type_id() == self.type_id {
+            self.count += 1;
+        }
+    }
+
+    fn is_met(&mut self, _time: f64) -> bool {
+        self.count >= self.target
+    }
+}
+
+#[derive(Default)]
+struct MetricState {
+    batch_size: usize,
+    // Sum of the values in the current batch.
+    batch_sum: f64,
+    batch_count: usize,
+    // Welford's accumulators over the batch means.
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+/// Metric of the simulation model, whose observations are used by the precision-based stop conditions.
+///
+/// The metric is a shared handle, so it can be cloned into the model components, which record the observations via
+/// [`record`](Self::record), and into the stop conditions. The confidence interval of the metric mean assumes that
+/// the observations are independent. Since the consecutive observations of a single run are usually correlated,
+/// e.g. response times of requests served by the same queue, the metric can average the observations in batches of
+/// the specified size (the method of batch means), so that the batch means are used as the observations.
+#[derive(Clone)]
+pub struct Metric {
+    state: Rc<RefCell<MetricState>>,
+}
+
+impl Default for Metric {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric {
+    /// Creates the metric which uses each observation separately.
+    pub fn new() -> Self {
+        Self::with_batch_size(1)
+    }
+
+    /// Creates the metric which averages the observations in batches of the specified size.
+    ///
+    /// Panics if the size is zero.
+    pub fn with_batch_size(size: usize) -> Self {
+        assert!(size > 0, "Batch size must be positive");
+        let state = MetricState {
+            batch_size: size,
+            ..Default::default()
+        };
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Records the observation.
+    pub fn record(&self, value: f64) {
+        let mut state = self.state.borrow_mut();
+        state.batch_sum += value;
+        state.batch_count += 1;
+        if state.batch_count == state.batch_size {
+            let batch_mean = state.batch_sum / state.batch_size as f64;
+            state.batch_sum = 0.;
+            state.batch_count = 0;
+            state.count += 1;
+            let delta = batch_mean - state.mean;
+            state.mean += delta / state.count as f64;
+            state.m2 += delta * (batch_mean - state.mean);
+        }
+    }
+
+    /// Returns the statistics of the complete batches, or `None` if there are no complete batches.
+    pub fn stats(&self) -> Option<SampleStats> {
+        let state = self.state.borrow();
+        if state.count == 0 {
+            return None;
+        }
+        let std_dev = if state.count > 1 {
+            (state.m2 / (state.count - 1) as f64).sqrt()
+        } else {
+            0.
+        };
+        Some(SampleStats {
+            count: state.count,
+            mean: state.mean,
+            std_dev,
+        })
+    }
+}
+
+/// Condition which is met when the half-width of the confidence interval of the metric mean relative to the mean
+/// does not exceed the specified precision.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use serde::Serialize;
+/// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+/// use simcore::stop::{EventCount, Metric, RelativePrecision, StopCondition};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Request {}
+///
+/// struct Server {
+///     response_time: Metric,
+///     ctx: SimulationContext,
+/// }
+///
+/// impl EventHandler for Server {
+///     fn on(&mut self, event: Event) {
+///         cast!(match event.data {
+///             Request {} => {
+///                 self.response_time.record(self.ctx.gen_range(1.0..3.0));
+///                 self.ctx.emit_self(Request {}, 1.);
+///             }
+///         })
+///     }
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("server");
+/// let response_time = Metric::new();
+/// ctx.emit_self(Request {}, 0.);
+/// sim.add_handler("server", Rc::new(RefCell::new(Server { response_time: response_time.clone(), ctx })));
+///
+/// // estimate the mean response time within 1% with 95% confidence, but process at most 100000 requests
+/// let condition = RelativePrecision::new(&response_time, 0.01, 0.95).or(EventCount::of::<Request>(100000));
+/// assert!(sim.step_until(condition));
+///
+/// let stats = response_time.stats().unwrap();
+/// assert!(stats.confidence_half_width(0.95).unwrap() <= 0.01 * stats.mean);
+/// assert!((stats.mean - 2.).abs() < 0.04);
+/// ```
+pub struct RelativePrecision {
+    metric: Metric,
+    precision: f64,
+    confidence: f64,
+    min_observations: usize,
+}
+
+impl RelativePrecision {
+    /// Creates the condition with the relative precision, e.g. 0.05, and confidence level, e.g. 0.95.
+    ///
+    /// The condition requires at least 10 observations (batches) of the metric,
+    /// see [`with_min_observations`](Self::with_min_observations).
+    ///
+    /// Panics if the precision is not positive or the confidence level is not in the range _(0, 1)_.
+    pub fn new(metric: &Metric, precision: f64, confidence: f64) -> Self {
+        assert!(precision > 0., "Precision must be positive");
+        assert!(
+            confidence > 0. && confidence < 1.,
+            "Confidence level must be in the range (0, 1)"
+        );
+        Self {
+            metric: metric.clone(),
+            precision,
+            confidence,
+            min_observations: 10,
+        }
+    }
+
+    /// Sets the minimum number of observations (batches) of the metric, which protects from stopping too early
+    /// because of the accidentally small variance of the first observations.
+    pub fn with_min_observations(mut self, count: usize) -> Self {
+        self.min_observations = count.max(2);
+        self
+    }
+}
+
+impl StopCondition for RelativePrecision {
+    fn is_met(&mut self, _time: f64) -> bool {
+        let Some(stats) = self.metric.stats().filter(|stats| stats.count >= self.min_observations) else {
+            return false;
+        };
+        let half_width = stats.confidence_half_width(self.confidence).unwrap();
+        half_width <= self.precision * stats.mean.abs()
+    }
+}
+
+/// Condition which is met when the width of the confidence interval of the metric mean does not exceed
+/// the specified value.
+pub struct ConfidenceWidth {
+    metric: Metric,
+    width: f64,
+    confidence: f64,
+    min_observations: usize,
+}
+
+impl ConfidenceWidth {
+    /// Creates the condition with the target width of the confidence interval and confidence level, e.g. 0.95.
+    ///
+    /// The condition requires at least 10 observations (batches) of the metric,
+    /// see [`with_min_observations`](Self::with_min_observations).
+    ///
+    /// Panics if the width is not positive or the confidence level is not in the range _(0, 1)_.
+    pub fn new(metric: &Metric, width: f64, confidence: f64) -> Self {
+        assert!(width > 0., "Confidence interval width must be positive");
+        assert!(
+            confidence > 0. && confidence < 1.,
+            "Confidence level must be in the range (0, 1)"
+        );
+        Self {
+            metric: metric.clone(),
+            width,
+            confidence,
+            min_observations: 10,
+        }
+    }
+
+    /// Sets the minimum number of observations (batches) of the metric.
+    pub fn with_min_observations(mut self, count: usize) -> Self {
+        self.min_observations = count.max(2);
+        self
+    }
+}
+
+impl StopCondition for ConfidenceWidth {
+    fn is_met(&mut self, _time: f64) -> bool {
+        let Some(stats) = self.metric.stats().filter(|stats| stats.count >= self.min_observations) else {
+            return false;
+        };
+        2. * stats.confidence_half_width(self.confidence).unwrap() <= self.width
+    }
+}
@@ -0,0 +1,63 @@
+//! Discrete tick-based time.
+//!
+//! Some models, such as synchronous-round distributed algorithms or cycle-approximate hardware models, use
+//! discrete time instead of continuous one. When the tick is set via
+//! [`Simulation::set_time_tick`](crate::Simulation::set_time_tick), the times of all emitted events and
+//! asynchronous timers are snapped to multiples of the tick according to the [`TickPolicy`].
+//!
+//! The models which need exact time arithmetic can express all delays and times in integer ticks via the
+//! tick-based variants of the time APIs, such as [`SimulationContext::emit_ticks`](crate::SimulationContext::emit_ticks)
+//! or [`Simulation::step_until_tick`](crate::Simulation::step_until_tick). The time of each tick is computed from its
+//! integer number, so the times do not accumulate floating-point errors. With the unit tick set via
+//! [`Simulation::with_integer_ticks`](crate::Simulation::with_integer_ticks), the simulation time is always an exact
+//! integer equal to the current tick number.
+
+// Relative tolerance used to treat times which differ from a multiple of tick due to floating-point errors as aligned.
+const TICK_TOLERANCE: f64 = 1e-9;
+
+/// Policy of snapping event times to ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickPolicy {
+    /// Rounds the time to the nearest tick, so the events with delays smaller than half of the tick occur
+    /// at the current time.
+    Round,
+    /// Rounds the time up to the next tick, so the events never occur earlier than requested.
+    Ceil,
+    /// Panics if the time is not a multiple of the tick.
+    Strict,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct TimeTick {
+    tick: f64,
+    policy: TickPolicy,
+}
+
+impl TimeTick {
+    pub fn new(tick: f64, policy: TickPolicy) -> Self {
+        assert!(tick > 0., "Time tick must be positive");
+        Self { tick, policy }
+    }
+
+    // Returns the number of the tick nearest to the time.
+    pub fn tick_number(&self, time: f64) -> u64 {
+        (time / self.tick).round() as u64
+    }
+
+    pub fn time_of(&self, tick: u64) -> f64 {
+        tick as f64 * self.tick
+    }
+
+    pub fn snap(&self, time: f64) -> f64 {
+        let ticks = time / self.tick;
+        let nearest = ticks.round();
+        let aligned = (ticks - nearest).abs() <= TICK_TOLERANCE * nearest.max(1.);
+        let snapped = match self.policy {
+            _ if aligned => nearest,
+            TickPolicy::Round => nearest,
+            TickPolicy::Ceil => ticks.ceil(),
+            TickPolicy::Strict => panic!("Time {} is not a multiple of time tick {}", time, self.tick),
+        };
+        snapped * self.tick
+    }
+}
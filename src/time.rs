@@ -0,0 +1,228 @@
+//! Typed simulation time.
+//!
+//! By default, the simulation time and delays are represented as bare `f64` numbers of seconds. In large models
+//! this makes it easy to mix up the time units or to pass an absolute time where a delay is expected. The
+//! [`SimTime`] and [`SimDuration`] types wrap the time and delay values respectively, are constructed from
+//! explicit units and support only the meaningful arithmetic, e.g. a duration can be added to a time, but two times
+//! can only be subtracted to obtain the duration between them.
+//!
+//! The typed variants of the time APIs, such as [`SimulationContext::now`](crate::SimulationContext::now),
+//! [`SimulationContext::emit_in`](crate::SimulationContext::emit_in) or
+//! [`Simulation::step_for`](crate::Simulation::step_for), accept and return these types and can be used in place of
+//! their `f64` counterparts. Both types are serialized as the number of seconds, so they can be used in event
+//! payloads.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use simcore::time::{SimDuration, SimTime};
+//!
+//! let start = SimTime::from_secs(1.5);
+//! let timeout = SimDuration::from_millis(250.);
+//! let deadline = start + timeout * 2.;
+//! assert_eq!(deadline, SimTime::from_secs(2.));
+//! assert_eq!((deadline - start).as_millis(), 500.);
+//! assert!(timeout < SimDuration::from_secs(1.));
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// Point in simulation time measured in seconds since the simulation start.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SimTime(f64);
+
+impl SimTime {
+    /// Start of the simulation.
+    pub const ZERO: SimTime = SimTime(0.);
+
+    /// Creates the time from the number of seconds since the simulation start.
+    ///
+    /// Panics if the value is negative or not a number.
+    pub fn from_secs(secs: f64) -> Self {
+        assert!(secs >= 0., "Simulation time must be non-negative, got {}", secs);
+        Self(secs)
+    }
+
+    /// Returns the number of seconds since the simulation start.
+    pub fn as_secs(self) -> f64 {
+        self.0
+    }
+
+    /// Returns the duration elapsed since the earlier time.
+    ///
+    /// Panics if the specified time is later than this one.
+    pub fn since(self, earlier: SimTime) -> SimDuration {
+        self - earlier
+    }
+}
+
+impl Display for SimTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Non-negative span of simulation time, e.g. event delay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SimDuration(f64);
+
+impl SimDuration {
+    /// Zero duration.
+    pub const ZERO: SimDuration = SimDuration(0.);
+
+    /// Creates the duration from the number of seconds.
+    ///
+    /// Panics if the value is negative or not a number.
+    pub fn from_secs(secs: f64) -> Self {
+        assert!(secs >= 0., "Simulation duration must be non-negative, got {}", secs);
+        Self(secs)
+    }
+
+    /// Creates the duration from the number of milliseconds.
+    pub fn from_millis(millis: f64) -> Self {
+        Self::from_secs(millis / 1e3)
+    }
+
+    /// Creates the duration from the number of microseconds.
+    pub fn from_micros(micros: f64) -> Self {
+        Self::from_secs(micros / 1e6)
+    }
+
+    /// Creates the duration from the number of nanoseconds.
+    pub fn from_nanos(nanos: f64) -> Self {
+        Self::from_secs(nanos / 1e9)
+    }
+
+    /// Returns the duration in seconds.
+    pub fn as_secs(self) -> f64 {
+        self.0
+    }
+
+    /// Returns the duration in milliseconds.
+    pub fn as_millis(self) -> f64 {
+        self.0 * 1e3
+    }
+
+    /// Returns the duration in microseconds.
+    pub fn as_micros(self) -> f64 {
+        self.0 * 1e6
+    }
+
+    /// Returns the duration in nanoseconds.
+    pub fn as_nanos(self) -> f64 {
+        self.0 * 1e9
+    }
+}
+
+impl Display for SimDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<std::time::Duration> for SimDuration {
+    fn from(duration: std::time::Duration) -> Self {
+        Self(duration.as_secs_f64())
+    }
+}
+
+impl Add<SimDuration> for SimTime {
+    type Output = SimTime;
+
+    fn add(self, rhs: SimDuration) -> SimTime {
+        SimTime(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign<SimDuration> for SimTime {
+    fn add_assign(&mut self, rhs: SimDuration) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub<SimDuration> for SimTime {
+    type Output = SimTime;
+
+    fn sub(self, rhs: SimDuration) -> SimTime {
+        SimTime::from_secs(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign<SimDuration> for SimTime {
+    fn sub_assign(&mut self, rhs: SimDuration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub<SimTime> for SimTime {
+    type Output = SimDuration;
+
+    fn sub(self, rhs: SimTime) -> SimDuration {
+        SimDuration::from_secs(self.0 - rhs.0)
+    }
+}
+
+impl Add for SimDuration {
+    type Output = SimDuration;
+
+    fn add(self, rhs: SimDuration) -> SimDuration {
+        SimDuration(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for SimDuration {
+    fn add_assign(&mut self, rhs: SimDuration) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for SimDuration {
+    type Output = SimDuration;
+
+    fn sub(self, rhs: SimDuration) -> SimDuration {
+        SimDuration::from_secs(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for SimDuration {
+    fn sub_assign(&mut self, rhs: SimDuration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f64> for SimDuration {
+    type Output = SimDuration;
+
+    fn mul(self, rhs: f64) -> SimDuration {
+        SimDuration::from_secs(self.0 * rhs)
+    }
+}
+
+impl Mul<SimDuration> for f64 {
+    type Output = SimDuration;
+
+    fn mul(self, rhs: SimDuration) -> SimDuration {
+        rhs * self
+    }
+}
+
+impl Div<f64> for SimDuration {
+    type Output = SimDuration;
+
+    fn div(self, rhs: f64) -> SimDuration {
+        SimDuration::from_secs(self.0 / rhs)
+    }
+}
+
+impl Div for SimDuration {
+    type Output = f64;
+
+    fn div(self, rhs: SimDuration) -> f64 {
+        self.0 / rhs.0
+    }
+}
@@ -0,0 +1,286 @@
+//! Timeout tables.
+//!
+//! Protocol models often track many deadlines per entity, e.g. retransmission, keep-alive and idle timeouts of each
+//! connection or request. Using a separate event for each deadline results in creating and canceling enormous
+//! numbers of events, since most timeouts are extended or canceled before they expire. [`TimeoutTable`] stores
+//! such deadlines outside the pending event set and keeps at most one wake-up event per table scheduled at the
+//! earliest deadline. Setting, extending and canceling deadlines only update the table, while all deadlines
+//! expiring at the same time are reported in a single [`TimeoutsExpired`] event.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::component::Id;
+use crate::event::EventId;
+use crate::state::SimulationState;
+use crate::SimulationContext;
+
+/// Event delivered to the owner of [`TimeoutTable`] when some of its deadlines expire.
+///
+/// The expired timeouts should be obtained via [`TimeoutTable::take_expired`].
+#[derive(Clone, Serialize)]
+pub struct TimeoutsExpired {
+    /// Name of the timeout table.
+    pub table: String,
+}
+
+/// Expired timeout returned by [`TimeoutTable::take_expired`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timeout<K> {
+    /// Entity of the timeout.
+    pub key: K,
+    /// Timeout name.
+    pub name: String,
+    /// Deadline of the timeout.
+    pub deadline: f64,
+}
+
+// Position of the deadline in the ordered set: the deadline bits and the sequence number.
+// Bit patterns of non-negative floats are ordered in the same way as their values.
+type Position = (u64, u64);
+
+#[derive(Clone, Copy)]
+struct Wakeup {
+    event_id: EventId,
+    deadline: f64,
+    // Event time, which can differ from the deadline if the time tick is set.
+    time: f64,
+}
+
+/// Table of named deadlines tracked per entity, such as connection or request.
+///
+/// Each deadline is identified by the entity key and the timeout name. The table schedules a single
+/// [`TimeoutsExpired`] event to the component of the context passed on creation at the earliest deadline. On this
+/// event the component should call [`take_expired`](Self::take_expired), which returns all expired timeouts ordered
+/// by the deadline and the time of setting, and schedules the next wake-up.
+///
+/// Extending the deadline or canceling the timeout does not reschedule the wake-up event unless the table becomes
+/// empty. Therefore the wake-up can occur when there are no expired timeouts, and `take_expired` returns an empty
+/// list in this case.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+/// use simcore::timeout::{TimeoutTable, TimeoutsExpired};
+///
+/// struct Client {
+///     timeouts: TimeoutTable<u32>,
+///     expired: Vec<(u32, String, f64)>,
+///     ctx: SimulationContext,
+/// }
+///
+/// impl EventHandler for Client {
+///     fn on(&mut self, event: Event) {
+///         cast!(match event.data {
+///             TimeoutsExpired { .. } => {
+///                 for timeout in self.timeouts.take_expired() {
+///                     self.expired.push((timeout.key, timeout.name, self.ctx.time()));
+///                 }
+///             }
+///         })
+///     }
+/// }
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("client");
+/// let timeouts = TimeoutTable::new(&ctx, "requests");
+/// let client = Rc::new(RefCell::new(Client { timeouts, expired: Vec::new(), ctx }));
+/// sim.add_handler("client", client.clone());
+///
+/// for request in 0..3 {
+///     client.borrow_mut().timeouts.set(request, "response", 5.);
+/// }
+/// sim.step_until_time(2.);
+/// // response to request 1 is received
+/// assert!(client.borrow_mut().timeouts.cancel(&1, "response"));
+/// // request 2 is extended
+/// client.borrow_mut().timeouts.set(2, "response", 5.);
+///
+/// sim.step_until_no_events();
+/// assert_eq!(
+///     client.borrow().expired,
+///     vec![(0, "response".to_owned(), 5.), (2, "response".to_owned(), 7.)]
+/// );
+/// ```
+pub struct TimeoutTable<K> {
+    name: String,
+    component_id: Id,
+    sim_state: Rc<RefCell<SimulationState>>,
+    deadlines: BTreeMap<Position, (K, String)>,
+    positions: FxHashMap<K, FxHashMap<String, Position>>,
+    next_seq: u64,
+    wakeup: Option<Wakeup>,
+}
+
+impl<K: Clone + Eq + Hash> TimeoutTable<K> {
+    /// Creates an empty table with the specified name, which delivers wake-up events to the component of the context.
+    pub fn new(ctx: &SimulationContext, name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            component_id: ctx.id(),
+            sim_state: ctx.sim_state(),
+            deadlines: BTreeMap::new(),
+            positions: FxHashMap::default(),
+            next_seq: 0,
+            wakeup: None,
+        }
+    }
+
+    /// Returns the table name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of pending timeouts.
+    pub fn len(&self) -> usize {
+        self.deadlines.len()
+    }
+
+    /// Returns true if there are no pending timeouts.
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    /// Sets the timeout of the entity which expires after the specified delay.
+    ///
+    /// If the timeout with the same name is already pending for the entity, its deadline is replaced.
+    ///
+    /// Panics if the delay is negative.
+    pub fn set(&mut self, key: K, name: &str, delay: f64) {
+        assert!(delay >= 0., "Timeout delay must be non-negative");
+        let deadline = self.time() + delay;
+        let position = (deadline.to_bits(), self.next_seq);
+        self.next_seq += 1;
+        let timeouts = self.positions.entry(key.clone()).or_default();
+        let prev = match timeouts.get_mut(name) {
+            Some(pos) => Some(std::mem::replace(pos, position)),
+            None => {
+                timeouts.insert(name.to_owned(), position);
+                None
+            }
+        };
+        let entry = match prev {
+            Some(prev) => self.deadlines.remove(&prev).unwrap(),
+            None => (key, name.to_owned()),
+        };
+        self.deadlines.insert(position, entry);
+        self.update_wakeup();
+    }
+
+    /// Returns the deadline of the pending timeout, or `None` if there is no such timeout.
+    pub fn deadline(&self, key: &K, name: &str) -> Option<f64> {
+        let position = self.positions.get(key)?.get(name)?;
+        Some(f64::from_bits(position.0))
+    }
+
+    /// Returns the earliest deadline among the pending timeouts.
+    pub fn next_deadline(&self) -> Option<f64> {
+        self.deadlines.keys().next().map(|position| f64::from_bits(position.0))
+    }
+
+    /// Cancels the pending timeout.
+    ///
+    /// Returns `true` if the timeout was pending and `false` otherwise.
+    pub fn cancel(&mut self, key: &K, name: &str) -> bool {
+        let Some(timeouts) = self.positions.get_mut(key) else {
+            return false;
+        };
+        let Some(position) = timeouts.remove(name) else {
+            return false;
+        };
+        if timeouts.is_empty() {
+            self.positions.remove(key);
+        }
+        self.deadlines.remove(&position);
+        self.update_wakeup();
+        true
+    }
+
+    /// Cancels all pending timeouts of the entity, e.g. when the connection is closed.
+    ///
+    /// Returns the number of canceled timeouts.
+    pub fn cancel_all(&mut self, key: &K) -> usize {
+        let Some(timeouts) = self.positions.remove(key) else {
+            return 0;
+        };
+        for position in timeouts.values() {
+            self.deadlines.remove(position);
+        }
+        self.update_wakeup();
+        timeouts.len()
+    }
+
+    /// Removes and returns the timeouts with deadlines not later than the current time
+    /// ordered by the deadline and the time of setting.
+    ///
+    /// Should be called on [`TimeoutsExpired`] event to schedule the next wake-up.
+    pub fn take_expired(&mut self) -> Vec<Timeout<K>> {
+        let mut limit = self.time();
+        if let Some(wakeup) = self.wakeup.filter(|wakeup| wakeup.time <= limit) {
+            limit = limit.max(wakeup.deadline);
+            self.wakeup = None;
+        }
+        let mut expired = Vec::new();
+        while let Some(entry) = self.deadlines.first_entry() {
+            let deadline = f64::from_bits(entry.key().0);
+            if deadline > limit {
+                break;
+            }
+            let (key, name) = entry.remove();
+            let timeouts = self.positions.get_mut(&key).unwrap();
+            timeouts.remove(&name);
+            if timeouts.is_empty() {
+                self.positions.remove(&key);
+            }
+            expired.push(Timeout { key, name, deadline });
+        }
+        self.update_wakeup();
+        expired
+    }
+
+    // Schedules the wake-up event if the earliest deadline precedes the pending one,
+    // and cancels the pending event if there are no timeouts.
+    fn update_wakeup(&mut self) {
+        let next_deadline = self.next_deadline();
+        let mut state = self.sim_state.borrow_mut();
+        match (self.wakeup, next_deadline) {
+            (Some(wakeup), Some(deadline)) if wakeup.deadline <= deadline => {}
+            (wakeup, next_deadline) => {
+                if let Some(wakeup) = wakeup {
+                    state.cancel_event(wakeup.event_id);
+                }
+                self.wakeup = next_deadline.map(|deadline| {
+                    let event = TimeoutsExpired {
+                        table: self.name.clone(),
+                    };
+                    let delay = deadline - state.time();
+                    Wakeup {
+                        event_id: state.add_event(event, self.component_id, self.component_id, delay),
+                        deadline,
+                        time: state.snap_time(deadline),
+                    }
+                });
+            }
+        }
+    }
+
+    fn time(&self) -> f64 {
+        self.sim_state.borrow().time()
+    }
+}
+
+impl<K> Drop for TimeoutTable<K> {
+    fn drop(&mut self) {
+        if let Some(wakeup) = self.wakeup {
+            self.sim_state.borrow_mut().cancel_event(wakeup.event_id);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Named timers.
+//!
+//! Named timers simplify the typical timer bookkeeping in component models, e.g. for retransmissions or heartbeats.
+//! A timer is set via [`SimulationContext::set_timer`](crate::SimulationContext::set_timer) with a name unique
+//! within the component. Setting a timer with the same name replaces the previous one, and the timer can be
+//! cancelled by its name via [`SimulationContext::cancel_timer`](crate::SimulationContext::cancel_timer).
+//!
+//! The timer expiry is delivered to the component as [`TimerFired`] event, or can be awaited in async mode via
+//! [`SimulationContext::wait_timer`](crate::SimulationContext::wait_timer). The awaited timers are matched by
+//! their names, which are kept for the simulation lifetime, so the timers with unbounded number of distinct names,
+//! e.g. including a sequence number, should be delivered to the component handler instead.
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::async_mode_enabled;
+use crate::component::Id;
+use crate::event::EventId;
+
+async_mode_enabled!(
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::async_mode::EventKey;
+
+    // Keys of TimerFired events used for awaiting timers in async mode. The keys are assigned to timer names
+    // sequentially, so unlike hashes the keys of different names never collide. The mapping is shared with the key
+    // getter of TimerFired events.
+    #[derive(Clone, Default)]
+    pub(crate) struct TimerKeys {
+        keys: Rc<RefCell<FxHashMap<String, EventKey>>>,
+    }
+
+    impl TimerKeys {
+        pub fn key(&self, name: &str) -> EventKey {
+            let mut keys = self.keys.borrow_mut();
+            if let Some(&key) = keys.get(name) {
+                return key;
+            }
+            let key = keys.len() as EventKey;
+            keys.insert(name.to_owned(), key);
+            key
+        }
+    }
+);
+
+/// Event produced on the expiry of named timer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimerFired {
+    /// Timer name.
+    pub name: String,
+}
+
+// Stores pending named timers of components.
+#[derive(Clone, Default)]
+pub(crate) struct NamedTimers {
+    timers: FxHashMap<Id, FxHashMap<String, EventId>>,
+}
+
+impl NamedTimers {
+    // Stores the timer event and returns the event of replaced timer, if any.
+    pub fn set(&mut self, component_id: Id, name: &str, event_id: EventId) -> Option<EventId> {
+        let timers = self.timers.entry(component_id).or_default();
+        match timers.get_mut(name) {
+            Some(id) => Some(std::mem::replace(id, event_id)),
+            None => {
+                timers.insert(name.to_owned(), event_id);
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, component_id: Id, name: &str) -> Option<EventId> {
+        self.timers.get(&component_id)?.get(name).copied()
+    }
+
+    pub fn remove(&mut self, component_id: Id, name: &str) -> Option<EventId> {
+        let timers = self.timers.get_mut(&component_id)?;
+        let event_id = timers.remove(name);
+        if timers.is_empty() {
+            self.timers.remove(&component_id);
+        }
+        event_id
+    }
+
+    // Removes the timer if its event is fired.
+    pub fn on_timer_fired(&mut self, component_id: Id, name: &str, event_id: EventId) {
+        if self.get(component_id, name) == Some(event_id) {
+            self.remove(component_id, name);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    pub fn remove_component(&mut self, component_id: Id) {
+        self.timers.remove(&component_id);
+    }
+
+    // Returns the pending timers as (component, name, event) sorted by components and names.
+    pub fn entries(&self) -> Vec<(Id, String, EventId)> {
+        let mut entries: Vec<_> = self
+            .timers
+            .iter()
+            .flat_map(|(id, timers)| timers.iter().map(|(name, event_id)| (*id, name.clone(), *event_id)))
+            .collect();
+        entries.sort();
+        entries
+    }
+}
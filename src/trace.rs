@@ -0,0 +1,365 @@
+//! In-memory trace of processed events.
+//!
+//! When the in-memory trace is enabled via [`Simulation::enable_memory_trace`](crate::Simulation::enable_memory_trace),
+//! the simulation records each dispatched event along with its causal parent, i.e. the event whose processing
+//! emitted it. The recorded trace can be accessed via [`Simulation::trace`](crate::Simulation::trace) and queried
+//! by time range, component and event type, and the causal chains of events can be followed without exporting
+//! the trace to files.
+//!
+//! Recording all events of long runs can be too expensive, so the trace can record only a sample of events
+//! selected according to [`TraceSampling`] set for all events or per event type. The sampling is deterministic,
+//! i.e. the same events are recorded in the runs with the same seed, and does not affect the random numbers
+//! generated by the simulation.
+
+use std::any::TypeId;
+
+use rustc_hash::FxHashMap;
+use serde_type_name::type_name;
+
+use crate::async_mode_enabled;
+use crate::component::Id;
+use crate::digest::DigestHasher;
+use crate::event::{Event, EventData, EventId};
+use crate::logical_clock::LogicalTime;
+
+/// Record of a dispatched event.
+#[derive(Clone)]
+pub struct TraceRecord {
+    /// Event identifier.
+    pub id: EventId,
+    /// Time of event occurrence.
+    pub time: f64,
+    /// Identifier of event source.
+    pub src: Id,
+    /// Identifier of event destination.
+    pub dst: Id,
+    /// Identifier of the event whose processing emitted this event, if any.
+    ///
+    /// Events emitted from asynchronous tasks are attributed to the event which resumed the task. Events emitted
+    /// while processing a [batch](crate::EventHandler::on_batch) are attributed to the first event of the batch.
+    /// Events emitted outside of event processing, e.g. between simulation steps, have no parent.
+    pub parent: Option<EventId>,
+    /// Copy of event payload.
+    pub data: Box<dyn EventData>,
+    /// Logical timestamp of the event, if [logical clocks](crate::logical_clock) are enabled.
+    pub logical_time: Option<LogicalTime>,
+}
+
+/// Sampling of events recorded in [`MemoryTrace`].
+///
+/// The sampling is set via [`Simulation::set_trace_sampling`](crate::Simulation::set_trace_sampling) for all events
+/// and via [`Simulation::set_event_trace_sampling`](crate::Simulation::set_event_trace_sampling) for events of
+/// specific type.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TraceSampling {
+    /// Record all events (default).
+    #[default]
+    All,
+    /// Record each event with the specified probability.
+    ///
+    /// The decision depends only on the simulation seed and the event identifier.
+    Probability(f64),
+    /// Record every n-th event of each type starting from the first one.
+    EveryNth(u64),
+    /// Do not record events.
+    Disabled,
+}
+
+impl TraceSampling {
+    fn validate(&self) {
+        match *self {
+            Self::Probability(p) => assert!((0. ..=1.).contains(&p), "Sampling probability must be in [0, 1]"),
+            Self::EveryNth(n) => assert!(n > 0, "Sampling interval must be positive"),
+            Self::All | Self::Disabled => {}
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Sampler {
+    default: TraceSampling,
+    by_type: FxHashMap<TypeId, TraceSampling>,
+    // Numbers of events to skip before the next sampled one by type, used for every-n-th sampling.
+    counters: FxHashMap<TypeId, u64>,
+    seed: u64,
+}
+
+impl Sampler {
+    fn is_sampled(&mut self, event: &Event) -> bool {
+        let type_id = event.data.as_ref().type_id();
+        match self.by_type.get(&type_id).copied().unwrap_or(self.default) {
+            TraceSampling::All => true,
+            TraceSampling::Probability(p) => {
+                let hash = splitmix64(self.seed ^ splitmix64(event.id));
+                // 53 random bits are converted to the uniform value in [0, 1)
+                ((hash >> 11) as f64 / (1u64 << 53) as f64) < p
+            }
+            TraceSampling::EveryNth(n) => {
+                // number of events to skip before the next sampled one
+                let skipped = self.counters.entry(type_id).or_default();
+                if *skipped == 0 {
+                    *skipped = n - 1;
+                    true
+                } else {
+                    *skipped -= 1;
+                    false
+                }
+            }
+            TraceSampling::Disabled => false,
+        }
+    }
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// In-memory trace of processed events.
+#[derive(Clone, Default)]
+pub struct MemoryTrace {
+    records: Vec<TraceRecord>,
+    record_index: FxHashMap<EventId, usize>,
+    children: FxHashMap<EventId, Vec<EventId>>,
+    // Parents of emitted events which are not dispatched yet.
+    pending_parents: FxHashMap<EventId, EventId>,
+    // Event which is being processed by handler or asynchronous task.
+    current_event: Option<EventId>,
+    // Last dispatched event, which is the parent of events emitted by the tasks resumed after it.
+    resume_event: Option<EventId>,
+    sampler: Sampler,
+    skipped: u64,
+}
+
+impl MemoryTrace {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            sampler: Sampler {
+                seed,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn set_sampling(&mut self, sampling: TraceSampling) {
+        sampling.validate();
+        self.sampler.default = sampling;
+    }
+
+    pub(crate) fn set_event_sampling<T: EventData>(&mut self, sampling: TraceSampling) {
+        sampling.validate();
+        self.sampler.by_type.insert(TypeId::of::<T>(), sampling);
+    }
+
+    pub(crate) fn on_event_emitted(&mut self, id: EventId) {
+        if let Some(parent) = self.current_event {
+            self.pending_parents.insert(id, parent);
+        }
+    }
+
+    pub(crate) fn on_event_canceled(&mut self, id: EventId) {
+        self.pending_parents.remove(&id);
+    }
+
+    // Sets the event which is being processed, the events emitted until it is reset are its children.
+    // Events emitted while processing the skipped event still refer to it as their parent.
+    pub(crate) fn set_current_event(&mut self, id: Option<EventId>) {
+        self.current_event = id;
+    }
+
+    pub(crate) fn on_event_dispatched(&mut self, event: &Event, logical_time: Option<LogicalTime>) {
+        let parent = self.pending_parents.remove(&event.id);
+        self.resume_event = Some(event.id);
+        if !self.sampler.is_sampled(event) {
+            self.skipped += 1;
+            return;
+        }
+        if let Some(parent) = parent {
+            self.children.entry(parent).or_default().push(event.id);
+        }
+        self.record_index.insert(event.id, self.records.len());
+        self.records.push(TraceRecord {
+            id: event.id,
+            time: event.time,
+            src: event.src,
+            dst: event.dst,
+            parent,
+            data: event.data.clone(),
+            logical_time,
+        });
+    }
+
+    async_mode_enabled!(
+        pub(crate) fn on_timer_fired(&mut self) {
+            self.resume_event = None;
+        }
+
+        pub(crate) fn on_task_resumed(&mut self) {
+            self.current_event = self.resume_event;
+        }
+    );
+
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns true if there are no recorded events.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the number of processed events which were not recorded due to the [sampling](TraceSampling).
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Returns all recorded events in the order of their processing.
+    pub fn records(&self) -> &[TraceRecord] {
+        &self.records
+    }
+
+    /// Returns the record of event with specified identifier, if it was processed.
+    pub fn get(&self, id: EventId) -> Option<&TraceRecord> {
+        self.record_index.get(&id).map(|&idx| &self.records[idx])
+    }
+
+    /// Creates a query over recorded events.
+    ///
+    /// See [`Simulation::trace`](crate::Simulation::trace) for examples.
+    pub fn query(&self) -> TraceQuery<'_> {
+        TraceQuery {
+            trace: self,
+            time_range: None,
+            component: None,
+            src: None,
+            dst: None,
+            type_filter: None,
+        }
+    }
+
+    /// Returns the causal chain of event with specified identifier, i.e. the sequence of events
+    /// starting from the root cause and ending with the specified event.
+    ///
+    /// Returns an empty vector if the event was not processed. If the events are sampled, the chain starts
+    /// after the latest event which was not recorded.
+    pub fn causes(&self, id: EventId) -> Vec<&TraceRecord> {
+        let mut chain = Vec::new();
+        let mut next = self.get(id);
+        while let Some(record) = next {
+            chain.push(record);
+            next = record.parent.and_then(|parent| self.get(parent));
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the processed events directly or transitively caused by event with specified identifier,
+    /// in the order of their processing.
+    ///
+    /// If the events are sampled, the effects of events which were not recorded are not included.
+    pub fn effects(&self, id: EventId) -> Vec<&TraceRecord> {
+        let mut effects = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if let Some(children) = self.children.get(&id) {
+                for &child in children {
+                    effects.push(self.get(child).unwrap());
+                    stack.push(child);
+                }
+            }
+        }
+        effects.sort_by_key(|record| self.record_index[&record.id]);
+        effects
+    }
+
+    /// Returns the digest of recorded events, which includes their identifiers, times, sources, destinations,
+    /// causal parents, payload type names and serialized payloads.
+    ///
+    /// The digest is computed by a platform-independent hash function, so the runs of the same simulation with
+    /// the same seed produce the same digest on all platforms. Comparing the digest with a value obtained on
+    /// another platform allows to check the determinism of simulation in tests.
+    pub fn digest(&self) -> u64 {
+        let mut hasher = DigestHasher::new();
+        for record in self.records.iter() {
+            hasher.write_u64(record.id);
+            hasher.write_u64(record.time.to_bits());
+            hasher.write_u64(record.src as u64);
+            hasher.write_u64(record.dst as u64);
+            hasher.write_u64(record.parent.map_or(u64::MAX, |parent| parent));
+            hasher.write(type_name(&record.data).unwrap().as_bytes());
+            hasher.write(&serde_json::to_vec(&record.data).unwrap());
+        }
+        hasher.finish()
+    }
+}
+
+/// Query over events recorded in [`MemoryTrace`].
+///
+/// The filters are combined, i.e. the query returns events matching all specified filters.
+pub struct TraceQuery<'a> {
+    trace: &'a MemoryTrace,
+    time_range: Option<(f64, f64)>,
+    component: Option<Id>,
+    src: Option<Id>,
+    dst: Option<Id>,
+    type_filter: Option<fn(&dyn EventData) -> bool>,
+}
+
+impl<'a> TraceQuery<'a> {
+    /// Selects events with time in the specified range (inclusive).
+    pub fn time_range(mut self, from: f64, to: f64) -> Self {
+        self.time_range = Some((from, to));
+        self
+    }
+
+    /// Selects events sent or received by the specified component.
+    pub fn component(mut self, id: Id) -> Self {
+        self.component = Some(id);
+        self
+    }
+
+    /// Selects events sent by the specified component.
+    pub fn src(mut self, id: Id) -> Self {
+        self.src = Some(id);
+        self
+    }
+
+    /// Selects events received by the specified component.
+    pub fn dst(mut self, id: Id) -> Self {
+        self.dst = Some(id);
+        self
+    }
+
+    /// Selects events with payload of type `T`.
+    pub fn event_type<T: EventData>(mut self) -> Self {
+        self.type_filter = Some(|data| data.is::<T>());
+        self
+    }
+
+    /// Returns the matching events in the order of their processing.
+    pub fn records(&self) -> Vec<&'a TraceRecord> {
+        self.trace
+            .records
+            .iter()
+            .filter(|record| self.matches(record))
+            .collect()
+    }
+
+    /// Returns the number of matching events.
+    pub fn count(&self) -> usize {
+        self.trace.records.iter().filter(|record| self.matches(record)).count()
+    }
+
+    fn matches(&self, record: &TraceRecord) -> bool {
+        self.time_range
+            .is_none_or(|(from, to)| record.time >= from && record.time <= to)
+            && self.component.is_none_or(|id| record.src == id || record.dst == id)
+            && self.src.is_none_or(|id| record.src == id)
+            && self.dst.is_none_or(|id| record.dst == id)
+            && self.type_filter.is_none_or(|filter| filter(record.data.as_ref()))
+    }
+}
@@ -0,0 +1,319 @@
+//! Comparison of recorded event traces.
+//!
+//! Finding the regression between two runs by eyeballing their traces does not scale to traces with millions of
+//! records. The [`diff_trace_files`] compares two trace files written via
+//! [`Simulation::enable_trace_file`](crate::Simulation::enable_trace_file) and reports the first divergences
+//! together with the matching records before and after them.
+//!
+//! The traces are read incrementally and compared record by record, so the comparison stops after finding the
+//! configured number of divergences without reading the rest of the files. When the records differ, the traces are
+//! realigned by searching for the closest pair of matching records within the configured window, so the inserted,
+//! removed and changed records are reported as a single divergence. The fields which are expected to differ between
+//! the runs, such as event identifiers or timestamps inside payloads, can be excluded from the comparison.
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::io::BufRead;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::trace_file::{open_trace_file, TraceFileRecord};
+
+/// Configuration of trace comparison.
+#[derive(Clone, Debug)]
+pub struct TraceDiffConfig {
+    /// Maximum number of reported divergences, the comparison stops after finding them.
+    pub max_divergences: usize,
+    /// Number of matching records reported before and after each divergence.
+    pub context: usize,
+    /// Maximum number of records skipped in each trace when realigning the traces after a divergence.
+    pub window: usize,
+    /// Record fields excluded from the comparison, e.g. `id` or `data.timestamp` for a payload field. The nested
+    /// fields are separated by dots.
+    pub ignored_fields: Vec<String>,
+}
+
+impl TraceDiffConfig {
+    /// Creates a config reporting the first 10 divergences with 3 records of context and comparing all fields.
+    pub fn new() -> Self {
+        Self {
+            max_divergences: 10,
+            context: 3,
+            window: 100,
+            ignored_fields: Vec::new(),
+        }
+    }
+}
+
+impl Default for TraceDiffConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Divergence of compared traces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceDivergence {
+    /// Index of the first divergent record in the left trace, counting from 0.
+    pub left_index: usize,
+    /// Index of the first divergent record in the right trace, counting from 0.
+    pub right_index: usize,
+    /// Records of the left trace which do not match the right trace.
+    pub left: Vec<TraceFileRecord>,
+    /// Records of the right trace which do not match the left trace.
+    pub right: Vec<TraceFileRecord>,
+    /// Pairs of matching left and right records before the divergence.
+    pub before: Vec<(TraceFileRecord, TraceFileRecord)>,
+    /// Pairs of matching left and right records after the divergence.
+    pub after: Vec<(TraceFileRecord, TraceFileRecord)>,
+}
+
+/// Result of trace comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceDiff {
+    /// Found divergences in the order of their occurrence.
+    pub divergences: Vec<TraceDivergence>,
+    /// Whether the comparison was stopped after finding the maximum number of divergences.
+    pub truncated: bool,
+}
+
+impl TraceDiff {
+    /// Returns true if no divergences were found.
+    pub fn is_empty(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+impl Display for TraceDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for divergence in self.divergences.iter() {
+            writeln!(
+                f,
+                "@@ left {}, right {} @@",
+                divergence.left_index, divergence.right_index
+            )?;
+            for (record, _) in divergence.before.iter() {
+                writeln!(f, "  {}", to_json(record))?;
+            }
+            for record in divergence.left.iter() {
+                writeln!(f, "- {}", to_json(record))?;
+            }
+            for record in divergence.right.iter() {
+                writeln!(f, "+ {}", to_json(record))?;
+            }
+            for (record, _) in divergence.after.iter() {
+                writeln!(f, "  {}", to_json(record))?;
+            }
+        }
+        if self.truncated {
+            writeln!(f, "(comparison stopped after {} divergences)", self.divergences.len())?;
+        }
+        Ok(())
+    }
+}
+
+fn to_json(record: &TraceFileRecord) -> String {
+    serde_json::to_string(record).unwrap()
+}
+
+/// Compares two trace files, see [module documentation](self).
+///
+/// Panics if some file cannot be read or has invalid format.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use serde::Serialize;
+///
+/// use simcore::trace_diff::{diff_trace_files, TraceDiffConfig};
+/// use simcore::trace_file::TraceFileConfig;
+/// use simcore::{Event, EventHandler, Simulation, SimulationContext};
+///
+/// #[derive(Clone, Serialize)]
+/// struct Ping {
+///     round: u64,
+/// }
+///
+/// struct Pinger {
+///     rounds: u64,
+///     ctx: SimulationContext,
+/// }
+///
+/// impl EventHandler for Pinger {
+///     fn on(&mut self, event: Event) {
+///         let round = event.data.downcast_ref::<Ping>().unwrap().round;
+///         if round < self.rounds {
+///             self.ctx.emit_self(Ping { round: round + 1 }, 1.);
+///         }
+///     }
+/// }
+///
+/// fn record(name: &str, rounds: u64) -> std::path::PathBuf {
+///     let path = std::env::temp_dir().join(format!("simcore-diff-doc-{}-{}.jsonl", name, std::process::id()));
+///     let mut sim = Simulation::new(123);
+///     sim.enable_trace_file(TraceFileConfig::new(&path));
+///     let ctx = sim.create_context("pinger");
+///     ctx.emit_self(Ping { round: 0 }, 0.);
+///     sim.add_handler("pinger", Rc::new(RefCell::new(Pinger { rounds, ctx })));
+///     sim.step_until_no_events();
+///     sim.disable_trace_file();
+///     path
+/// }
+///
+/// let (left, right) = (record("left", 5), record("right", 3));
+/// let diff = diff_trace_files(&left, &right, &TraceDiffConfig::new());
+/// assert_eq!(diff.divergences.len(), 1);
+/// // the right trace misses the emission and processing of the last two pings
+/// let divergence = &diff.divergences[0];
+/// assert_eq!((divergence.left_index, divergence.right_index), (8, 8));
+/// assert_eq!(divergence.left.len(), 4);
+/// assert!(divergence.right.is_empty());
+/// assert_eq!(divergence.before.len(), 3);
+/// # std::fs::remove_file(&left).unwrap();
+/// # std::fs::remove_file(&right).unwrap();
+/// ```
+pub fn diff_trace_files<L, R>(left: L, right: R, config: &TraceDiffConfig) -> TraceDiff
+where
+    L: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    diff_traces(read_records(left), read_records(right), config)
+}
+
+/// Compares two sequences of trace records, see [module documentation](self).
+///
+/// The records are consumed lazily, so the sequences can be backed by large files or produced on the fly.
+pub fn diff_traces<L, R>(left: L, right: R, config: &TraceDiffConfig) -> TraceDiff
+where
+    L: IntoIterator<Item = TraceFileRecord>,
+    R: IntoIterator<Item = TraceFileRecord>,
+{
+    let mut left = Lookahead::new(left.into_iter(), &config.ignored_fields);
+    let mut right = Lookahead::new(right.into_iter(), &config.ignored_fields);
+    let mut divergences = Vec::new();
+    let mut before: VecDeque<(TraceFileRecord, TraceFileRecord)> = VecDeque::new();
+    let mut truncated = false;
+    loop {
+        match (left.get(0), right.get(0)) {
+            (None, None) => break,
+            (Some(l), Some(r)) if l.0 == r.0 => {
+                before.push_back((left.pop(), right.pop()));
+                if before.len() > config.context {
+                    before.pop_front();
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if divergences.len() == config.max_divergences {
+            truncated = true;
+            break;
+        }
+        let (left_skipped, right_skipped) = realign(&mut left, &mut right, config.window);
+        let (left_index, right_index) = (left.index, right.index);
+        let left_records = (0..left_skipped).map(|_| left.pop()).collect();
+        let right_records = (0..right_skipped).map(|_| right.pop()).collect();
+        let mut after = Vec::new();
+        while after.len() < config.context {
+            match (left.get(after.len()), right.get(after.len())) {
+                (Some(l), Some(r)) if l.0 == r.0 => after.push((l.1.clone(), r.1.clone())),
+                _ => break,
+            }
+        }
+        divergences.push(TraceDivergence {
+            left_index,
+            right_index,
+            left: left_records,
+            right: right_records,
+            before: before.drain(..).collect(),
+            after,
+        });
+    }
+    TraceDiff { divergences, truncated }
+}
+
+// Returns the numbers of records to skip in the left and right traces to reach the closest matching records or
+// the ends of both traces. If there are no such records within the window, a single record is skipped in each trace.
+fn realign<L, R>(left: &mut Lookahead<L>, right: &mut Lookahead<R>, window: usize) -> (usize, usize)
+where
+    L: Iterator<Item = TraceFileRecord>,
+    R: Iterator<Item = TraceFileRecord>,
+{
+    for distance in 1..=2 * window {
+        for left_skipped in distance.saturating_sub(window)..=distance.min(window) {
+            let right_skipped = distance - left_skipped;
+            let matches = match (left.get(left_skipped), right.get(right_skipped)) {
+                (Some(l), Some(r)) => l.0 == r.0,
+                (None, None) => true,
+                _ => false,
+            };
+            if matches {
+                return (left_skipped, right_skipped);
+            }
+        }
+    }
+    (left.get(0).is_some() as usize, right.get(0).is_some() as usize)
+}
+
+// Trace records with their comparison keys, read on demand.
+struct Lookahead<'a, I> {
+    records: I,
+    ignored_fields: &'a [String],
+    buffer: VecDeque<(Value, TraceFileRecord)>,
+    // Index of the first buffered record in the trace.
+    index: usize,
+}
+
+impl<'a, I: Iterator<Item = TraceFileRecord>> Lookahead<'a, I> {
+    fn new(records: I, ignored_fields: &'a [String]) -> Self {
+        Self {
+            records,
+            ignored_fields,
+            buffer: VecDeque::new(),
+            index: 0,
+        }
+    }
+
+    fn get(&mut self, offset: usize) -> Option<&(Value, TraceFileRecord)> {
+        while self.buffer.len() <= offset {
+            let record = self.records.next()?;
+            let key = comparison_key(&record, self.ignored_fields);
+            self.buffer.push_back((key, record));
+        }
+        self.buffer.get(offset)
+    }
+
+    fn pop(&mut self) -> TraceFileRecord {
+        self.get(0);
+        self.index += 1;
+        self.buffer.pop_front().unwrap().1
+    }
+}
+
+pub(crate) fn comparison_key(record: &TraceFileRecord, ignored_fields: &[String]) -> Value {
+    let mut key = serde_json::to_value(record).unwrap();
+    for field in ignored_fields {
+        let (parent, name) = field.rsplit_once('.').unwrap_or(("", field));
+        let parent = if parent.is_empty() {
+            Some(&mut key)
+        } else {
+            key.pointer_mut(&format!("/{}", parent.replace('.', "/")))
+        };
+        if let Some(object) = parent.and_then(|parent| parent.as_object_mut()) {
+            object.remove(name);
+        }
+    }
+    key
+}
+
+// Reads the records of trace file lazily, skipping the header.
+pub(crate) fn read_records<P: AsRef<Path>>(path: P) -> impl Iterator<Item = TraceFileRecord> {
+    open_trace_file(path).lines().skip(1).map(|line| {
+        let line = line.expect("Failed to read trace file");
+        serde_json::from_str(&line).expect("Failed to parse trace file record")
+    })
+}
@@ -0,0 +1,515 @@
+//! Recording of event trace to JSON Lines file.
+//!
+//! When the trace file is enabled via [`Simulation::enable_trace_file`](crate::Simulation::enable_trace_file),
+//! the simulation writes a record for each emitted, processed and canceled event to the file configured by
+//! [`TraceFileConfig`]. The file starts with a line containing the [run metadata](crate::metadata) as of the
+//! first written record, so the labels and configuration set right after enabling the trace file are included.
+//! It is followed by one JSON object per line with the following fields:
+//!
+//! - `kind` - `emitted`, `processed` or `canceled`,
+//! - `time` - simulation time when the event was emitted, processed or canceled,
+//! - `id` - event identifier,
+//! - `event_time` - time of event occurrence, or `null` for the events emitted via
+//!   [`emit_after`](crate::SimulationContext::emit_after) whose time is not known yet,
+//! - `src` and `dst` - names of event source and destination,
+//! - `type` - event type name without module path, e.g. `Request`,
+//! - `data` - serialized event payload, omitted for canceled events and if payloads are disabled.
+//!
+//! The records can be filtered by kind, component, event type and time. The canceled events are recorded only
+//! if their emission passed the filters, i.e. the cancellation of already processed or unknown events is not
+//! recorded. The number of records written for dense bursts of events can be limited via
+//! [`Simulation::set_output_rate_limit`](crate::Simulation::set_output_rate_limit), see
+//! [`rate_limit`](crate::rate_limit) module. The recorded file can be read back via [`read_trace_file`].
+//!
+//! The schema versions of event payloads can be set via [`TraceFileConfig::set_event_version`]. They are written
+//! to the first line of the file, so that the payloads recorded before changing the event types can be migrated
+//! when the trace is replayed, see [`TraceReplay::add_migration`](crate::replay::TraceReplay::add_migration).
+//!
+//! If the `zstd` feature is enabled, the trace can be compressed by setting [`TraceFileConfig::format`] to
+//! [`TraceFileFormat::Zstd`]. The records are then written in blocks, each compressed into a separate zstd frame
+//! and preceded by a skippable frame with the time range and size of the block, see [`compression`](crate::compression)
+//! module. The file can be decompressed by the standard `zstd` tool to get the JSON Lines trace, while
+//! [`read_trace_file_range`] reads only the blocks overlapping the requested time range. The compressed traces are
+//! read transparently by all functions reading trace files.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::component::Id;
+use crate::compression::is_compressed;
+#[cfg(feature = "zstd")]
+use crate::compression::{for_each_block, BlockWriter};
+use crate::event::{Event, EventData, EventId};
+use crate::metadata::RunMetadata;
+use crate::rate_limit::OutputRateLimiter;
+use crate::replay::short_type_name;
+
+/// Kind of trace file record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceEventKind {
+    /// Event is emitted.
+    Emitted,
+    /// Event is processed.
+    Processed,
+    /// Pending event is canceled.
+    Canceled,
+}
+
+/// Format of trace file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceFileFormat {
+    /// Plain JSON Lines.
+    Json,
+    /// JSON Lines compressed with zstd in blocks of records, see [module documentation](self).
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// Compression level from 1 to 22, or 0 for the default level.
+        level: i32,
+        /// Maximum number of records in a block, which bounds the amount of data decompressed when seeking.
+        block_records: usize,
+    },
+}
+
+/// Configuration of recording event trace to JSON Lines file.
+///
+/// By default all kinds of records are written for all events with their payloads. The filters are combined,
+/// i.e. only the records matching all specified filters are written.
+#[derive(Clone, Debug)]
+pub struct TraceFileConfig {
+    /// Path of the trace file, which is overwritten if it exists.
+    pub path: PathBuf,
+    /// Recorded kinds of records.
+    pub kinds: Vec<TraceEventKind>,
+    /// Records only the events sent or received by components with these names.
+    pub components: Option<Vec<String>>,
+    /// Records only the events of these types (names without module path, e.g. `Request`).
+    pub event_types: Option<Vec<String>>,
+    /// Records only the records with time in this range (inclusive).
+    pub time_range: Option<(f64, f64)>,
+    /// Whether the event payloads are written.
+    pub payloads: bool,
+    /// Schema versions of event payloads by event type names without module path, the payloads of other types
+    /// have version 0.
+    pub event_versions: BTreeMap<String, u32>,
+    /// Format of the file.
+    pub format: TraceFileFormat,
+}
+
+impl TraceFileConfig {
+    /// Creates a config recording all events with their payloads to the specified file.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            kinds: vec![
+                TraceEventKind::Emitted,
+                TraceEventKind::Processed,
+                TraceEventKind::Canceled,
+            ],
+            components: None,
+            event_types: None,
+            time_range: None,
+            payloads: true,
+            event_versions: BTreeMap::new(),
+            format: TraceFileFormat::Json,
+        }
+    }
+
+    /// Sets the schema version of the payloads of event type `T`, which should be incremented on incompatible
+    /// changes of the type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use simcore::trace_file::TraceFileConfig;
+    ///
+    /// #[derive(Clone, Serialize)]
+    /// struct Request {
+    ///     size: u64,
+    /// }
+    ///
+    /// let mut config = TraceFileConfig::new("trace.jsonl");
+    /// config.set_event_version::<Request>(2);
+    /// assert_eq!(config.event_versions.get("Request"), Some(&2));
+    /// ```
+    pub fn set_event_version<T: EventData>(&mut self, version: u32) -> &mut Self {
+        self.event_versions.insert(short_type_name::<T>().to_owned(), version);
+        self
+    }
+}
+
+/// Record of trace file read via [`read_trace_file`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceFileRecord {
+    /// Kind of record.
+    pub kind: TraceEventKind,
+    /// Simulation time when the event was emitted, processed or canceled.
+    pub time: f64,
+    /// Event identifier.
+    pub id: EventId,
+    /// Time of event occurrence, if known.
+    pub event_time: Option<f64>,
+    /// Name of event source.
+    pub src: String,
+    /// Name of event destination.
+    pub dst: String,
+    /// Event type name without module path.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// Serialized event payload, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// Contents of trace file read via [`read_trace_file`].
+#[derive(Clone, Debug)]
+pub struct TraceFile {
+    /// Metadata of the recorded run.
+    pub metadata: RunMetadata,
+    /// Schema versions of event payloads, see [`TraceFileConfig::event_versions`].
+    pub event_versions: BTreeMap<String, u32>,
+    /// Records in the order of their writing.
+    pub records: Vec<TraceFileRecord>,
+}
+
+/// Reads the trace file written by the simulation.
+///
+/// Panics if the file cannot be read or has invalid format.
+pub fn read_trace_file<P: AsRef<Path>>(path: P) -> TraceFile {
+    read_trace_file_range(path, f64::NEG_INFINITY, f64::INFINITY)
+}
+
+/// Reads the records of the trace file with time in the specified range (inclusive).
+///
+/// The records of the trace file are ordered by time, so the reading stops after the end of the range. For the
+/// compressed trace files only the blocks overlapping the range are decompressed, see
+/// [module documentation](self).
+///
+/// Panics if the file cannot be read or has invalid format.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use simcore::trace_file::{read_trace_file_range, TraceFileConfig};
+/// use simcore::Simulation;
+///
+/// #[derive(Clone, Serialize)]
+/// struct Ping {}
+///
+/// let path = std::env::temp_dir().join(format!("simcore-trace-range-doc-{}.jsonl", std::process::id()));
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("comp");
+/// sim.enable_trace_file(TraceFileConfig::new(&path));
+/// for i in 1..=10 {
+///     ctx.emit_self(Ping {}, i as f64);
+/// }
+/// sim.step_until_no_events();
+/// sim.disable_trace_file();
+///
+/// let trace = read_trace_file_range(&path, 4., 6.);
+/// let times: Vec<_> = trace.records.iter().map(|r| r.time).collect();
+/// assert_eq!(times, vec![4., 5., 6.]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_trace_file_range<P: AsRef<Path>>(path: P, from: f64, to: f64) -> TraceFile {
+    let path = path.as_ref();
+    let mut header = None;
+    let mut records = Vec::new();
+    let mut read_lines = |lines: &mut dyn Iterator<Item = std::io::Result<String>>| -> bool {
+        if header.is_none() {
+            let line = lines
+                .next()
+                .expect("Trace file is empty")
+                .expect("Failed to read trace file");
+            header = Some(serde_json::from_str::<TraceFileHeader>(&line).expect("Failed to parse trace file header"));
+        }
+        for line in lines {
+            let line = line.expect("Failed to read trace file");
+            let record: TraceFileRecord = serde_json::from_str(&line).expect("Failed to parse trace file record");
+            if record.time > to {
+                return false;
+            }
+            if record.time >= from {
+                records.push(record);
+            }
+        }
+        true
+    };
+    let mut reader = BufReader::new(File::open(path).expect("Failed to open trace file"));
+    if is_compressed(&mut reader).expect("Failed to read trace file") {
+        #[cfg(feature = "zstd")]
+        for_each_block(reader, from, to, |block| read_lines(&mut block.lines())).expect("Failed to read trace file");
+        #[cfg(not(feature = "zstd"))]
+        panic!(
+            "Trace file {} is compressed, enable the zstd feature to read it",
+            path.display()
+        );
+    } else {
+        read_lines(&mut reader.lines());
+    }
+    let header = header.expect("Trace file is empty");
+    TraceFile {
+        metadata: header.metadata,
+        event_versions: header.event_versions,
+        records,
+    }
+}
+
+// Opens the trace file for reading its lines including the header, decompressing it if needed.
+pub(crate) fn open_trace_file<P: AsRef<Path>>(path: P) -> Box<dyn BufRead> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path).expect("Failed to open trace file"));
+    if is_compressed(&mut reader).expect("Failed to read trace file") {
+        #[cfg(feature = "zstd")]
+        return Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader).expect("Failed to read trace file"),
+        ));
+        #[cfg(not(feature = "zstd"))]
+        panic!(
+            "Trace file {} is compressed, enable the zstd feature to read it",
+            path.display()
+        );
+    }
+    Box::new(reader)
+}
+
+#[derive(Serialize, Deserialize)]
+struct TraceFileHeader {
+    metadata: RunMetadata,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    event_versions: BTreeMap<String, u32>,
+}
+
+#[derive(Serialize)]
+struct RecordRef<'a> {
+    kind: TraceEventKind,
+    time: f64,
+    id: EventId,
+    event_time: f64,
+    src: &'a str,
+    dst: &'a str,
+    #[serde(rename = "type")]
+    type_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<&'a dyn EventData>,
+}
+
+// Output of trace file, which is either written directly or compressed in blocks.
+enum TraceSink {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "zstd")]
+    Blocks(BlockWriter<BufWriter<File>>),
+}
+
+impl TraceSink {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            TraceSink::Plain(writer) => writer,
+            #[cfg(feature = "zstd")]
+            TraceSink::Blocks(writer) => writer,
+        }
+    }
+
+    // Writes the line, the header line is written without time.
+    fn write_line<T: Serialize>(&mut self, line: &T, time: Option<f64>) {
+        let writer = self.writer();
+        serde_json::to_writer(&mut *writer, line).expect("Failed to write trace file");
+        writer.write_all(b"\n").expect("Failed to write trace file");
+        #[cfg(feature = "zstd")]
+        if let (TraceSink::Blocks(writer), Some(time)) = (self, time) {
+            writer.end_record(time).expect("Failed to write trace file");
+        }
+        #[cfg(not(feature = "zstd"))]
+        let _ = time;
+    }
+
+    fn flush(&mut self) {
+        match self {
+            TraceSink::Plain(writer) => writer.flush().expect("Failed to write trace file"),
+            #[cfg(feature = "zstd")]
+            TraceSink::Blocks(writer) => writer.finish().expect("Failed to write trace file"),
+        }
+    }
+}
+
+struct TraceFileWriter {
+    sink: TraceSink,
+    // Header which is written before the first record, so that it includes the metadata set after enabling.
+    header: Option<TraceFileHeader>,
+    kinds: FxHashSet<TraceEventKind>,
+    components: Option<FxHashSet<String>>,
+    event_types: Option<FxHashSet<String>>,
+    time_range: Option<(f64, f64)>,
+    payloads: bool,
+    // Event times, sources, destinations and type names of the emitted events passing the filters,
+    // used to record their cancellation.
+    pending: FxHashMap<EventId, (f64, Id, Id, &'static str)>,
+}
+
+impl TraceFileWriter {
+    fn matches(&self, src: Id, dst: Id, type_name: &str, names: &[String]) -> bool {
+        self.components.as_ref().is_none_or(|components| {
+            components.contains(&names[src as usize]) || components.contains(&names[dst as usize])
+        }) && self
+            .event_types
+            .as_ref()
+            .is_none_or(|event_types| event_types.contains(type_name))
+    }
+
+    fn is_written(&self, kind: TraceEventKind, time: f64) -> bool {
+        self.kinds.contains(&kind) && self.time_range.is_none_or(|(from, to)| time >= from && time <= to)
+    }
+
+    fn write(&mut self, record: &RecordRef) {
+        self.write_header();
+        self.sink.write_line(record, Some(record.time));
+    }
+
+    fn write_header(&mut self) {
+        if let Some(header) = self.header.take() {
+            self.sink.write_line(&header, None);
+        }
+    }
+
+    fn write_event(&mut self, kind: TraceEventKind, time: f64, event: &Event, type_name: &str, names: &[String]) {
+        let record = RecordRef {
+            kind,
+            time,
+            id: event.id,
+            event_time: event.time,
+            src: &names[event.src as usize],
+            dst: &names[event.dst as usize],
+            type_name,
+            data: self.payloads.then_some(event.data.as_ref()),
+        };
+        self.write(&record);
+    }
+}
+
+// Writes the trace file if it is enabled.
+#[derive(Default)]
+pub(crate) struct TraceFileRecorder {
+    writer: Option<TraceFileWriter>,
+    limiter: OutputRateLimiter,
+}
+
+impl Clone for TraceFileRecorder {
+    // The branches of the simulation do not write to the trace file of the original simulation.
+    fn clone(&self) -> Self {
+        Self {
+            writer: None,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+impl TraceFileRecorder {
+    pub fn enable(&mut self, config: TraceFileConfig, metadata: &RunMetadata) {
+        let file = BufWriter::new(File::create(&config.path).expect("Failed to create trace file"));
+        let sink = match config.format {
+            TraceFileFormat::Json => TraceSink::Plain(file),
+            #[cfg(feature = "zstd")]
+            TraceFileFormat::Zstd { level, block_records } => {
+                TraceSink::Blocks(BlockWriter::new(file, level, block_records))
+            }
+        };
+        let header = TraceFileHeader {
+            metadata: metadata.clone(),
+            event_versions: config.event_versions,
+        };
+        self.writer = Some(TraceFileWriter {
+            sink,
+            header: Some(header),
+            kinds: config.kinds.into_iter().collect(),
+            components: config.components.map(|components| components.into_iter().collect()),
+            event_types: config.event_types.map(|event_types| event_types.into_iter().collect()),
+            time_range: config.time_range,
+            payloads: config.payloads,
+            pending: FxHashMap::default(),
+        });
+    }
+
+    pub fn limiter_mut(&mut self) -> &mut OutputRateLimiter {
+        &mut self.limiter
+    }
+
+    pub fn limiter(&self) -> &OutputRateLimiter {
+        &self.limiter
+    }
+
+    pub fn disable(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            writer.write_header();
+            writer.sink.flush();
+        }
+    }
+
+    // Updates the run metadata written to the file if no records are written yet.
+    pub fn set_metadata(&mut self, metadata: &RunMetadata) {
+        if let Some(header) = self.writer.as_mut().and_then(|writer| writer.header.as_mut()) {
+            header.metadata = metadata.clone();
+        }
+    }
+
+    pub fn on_event_emitted(&mut self, event: &Event, time: f64, names: &[String]) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        let type_name = serde_type_name::type_name(&event.data).unwrap();
+        if !writer.matches(event.src, event.dst, type_name, names) {
+            return;
+        }
+        if writer.kinds.contains(&TraceEventKind::Canceled) {
+            writer
+                .pending
+                .insert(event.id, (event.time, event.src, event.dst, type_name));
+        }
+        if writer.is_written(TraceEventKind::Emitted, time) && self.limiter.allow(event.src, time) {
+            writer.write_event(TraceEventKind::Emitted, time, event, type_name, names);
+        }
+    }
+
+    pub fn on_event_processed(&mut self, event: &Event, names: &[String]) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        writer.pending.remove(&event.id);
+        let type_name = serde_type_name::type_name(&event.data).unwrap();
+        if writer.is_written(TraceEventKind::Processed, event.time)
+            && writer.matches(event.src, event.dst, type_name, names)
+            && self.limiter.allow(event.dst, event.time)
+        {
+            writer.write_event(TraceEventKind::Processed, event.time, event, type_name, names);
+        }
+    }
+
+    pub fn on_event_canceled(&mut self, id: EventId, time: f64, names: &[String]) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        let Some((event_time, src, dst, type_name)) = writer.pending.remove(&id) else {
+            return;
+        };
+        if writer.is_written(TraceEventKind::Canceled, time) && self.limiter.allow(src, time) {
+            let record = RecordRef {
+                kind: TraceEventKind::Canceled,
+                time,
+                id,
+                event_time,
+                src: &names[src as usize],
+                dst: &names[dst as usize],
+                type_name,
+                data: None,
+            };
+            writer.write(&record);
+        }
+    }
+}
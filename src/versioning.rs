@@ -0,0 +1,115 @@
+//! Schema versions of event payloads.
+//!
+//! The event payloads recorded outside of the simulation are stored in serialized form, so the recordings made
+//! before changing the event types, e.g. renaming or adding fields, cannot be deserialized into the current types.
+//! [`EventMigrations`] keeps the current schema versions of event types, which should be incremented on
+//! incompatible changes of the types, and the functions converting the serialized payloads of each version to the
+//! next one. The payloads recorded with older versions are converted step-by-step to the current version via
+//! [`EventMigrations::migrate`] before deserializing them, so the old recordings remain usable without keeping the
+//! old binaries around.
+//!
+//! The event types are identified by their names without module path and generic arguments, e.g. `Request`. The
+//! types without registered version have version 0.
+//!
+//! The versions of event types are recorded in the trace files via
+//! [`TraceFileConfig::set_event_version`](crate::trace_file::TraceFileConfig::set_event_version), and the recorded
+//! payloads are migrated on replay via the versions and migrations registered in
+//! [`TraceReplay`](crate::replay::TraceReplay).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use serde_json::json;
+//! use simcore::versioning::EventMigrations;
+//!
+//! // the field `size` of version 0 is renamed to `bytes` in version 1, and the field `priority` is added in version 2
+//! #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Request {
+//!     bytes: u32,
+//!     priority: u8,
+//! }
+//!
+//! let mut migrations = EventMigrations::new();
+//! migrations
+//!     .set_version("Request", 2)
+//!     .add_migration("Request", 0, |data| json!({"bytes": data["size"]}))
+//!     .add_migration("Request", 1, |mut data| {
+//!         data["priority"] = json!(0);
+//!         data
+//!     });
+//!
+//! let data = migrations.migrate("Request", 0, json!({"size": 10}));
+//! let request: Request = serde_json::from_value(data).unwrap();
+//! assert_eq!(request, Request { bytes: 10, priority: 0 });
+//! ```
+
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+
+type MigrationFn = Box<dyn Fn(Value) -> Value>;
+
+/// Schema versions of event types and migrations of their serialized payloads, see [module documentation](self).
+#[derive(Default)]
+pub struct EventMigrations {
+    versions: FxHashMap<String, u32>,
+    migrations: FxHashMap<(String, u32), MigrationFn>,
+}
+
+impl EventMigrations {
+    /// Creates an empty registry, where all event types have version 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the current schema version of event type with the specified name (without module path).
+    pub fn set_version(&mut self, type_name: &str, version: u32) -> &mut Self {
+        self.versions.insert(type_name.to_owned(), version);
+        self
+    }
+
+    /// Returns the current schema version of event type with the specified name (without module path).
+    pub fn version(&self, type_name: &str) -> u32 {
+        self.versions.get(type_name).copied().unwrap_or(0)
+    }
+
+    /// Adds the function converting the serialized payload of event type with the specified name (without module
+    /// path) from version `from_version` to the next version.
+    pub fn add_migration<F>(&mut self, type_name: &str, from_version: u32, migration: F) -> &mut Self
+    where
+        F: Fn(Value) -> Value + 'static,
+    {
+        self.migrations
+            .insert((type_name.to_owned(), from_version), Box::new(migration));
+        self
+    }
+
+    /// Converts the serialized payload of event type recorded with the specified version to the current version
+    /// of the type by applying the migrations of all versions in between.
+    ///
+    /// Panics if the recorded version is newer than the current one or some of the required migrations is not
+    /// added.
+    pub fn migrate(&self, type_name: &str, recorded_version: u32, mut data: Value) -> Value {
+        let version = self.version(type_name);
+        assert!(
+            recorded_version <= version,
+            "Recorded version {} of event type {} is newer than the registered version {}",
+            recorded_version,
+            type_name,
+            version
+        );
+        for from_version in recorded_version..version {
+            let migration = self
+                .migrations
+                .get(&(type_name.to_owned(), from_version))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Migration of event type {} from version {} is not added",
+                        type_name, from_version
+                    )
+                });
+            data = migration(data);
+        }
+        data
+    }
+}
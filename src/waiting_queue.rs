@@ -0,0 +1,247 @@
+//! Waiting queues with built-in statistics.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::metrics::TimeWeightedStat;
+use crate::state::SimulationState;
+use crate::SimulationContext;
+
+/// Order in which items are removed from [`WaitingQueue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueDiscipline {
+    /// First in, first out.
+    Fifo,
+    /// Last in, first out.
+    Lifo,
+    /// Items with smaller priority values first, first in, first out for equal priorities.
+    Priority,
+}
+
+/// Statistics of waiting queue collected since the queue creation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueueStats {
+    /// Time-average number of items in the queue.
+    pub mean_length: f64,
+    /// Maximum number of items in the queue.
+    pub max_length: usize,
+    /// Number of items added to the queue.
+    pub enqueued: u64,
+    /// Number of items removed from the queue.
+    pub dequeued: u64,
+    /// Average time spent in the queue by removed items.
+    pub mean_wait_time: f64,
+    /// Maximum time spent in the queue by removed items.
+    pub max_wait_time: f64,
+}
+
+struct QueueEntry<T> {
+    item: T,
+    enqueue_time: f64,
+}
+
+/// Queue of waiting items, such as customers or requests, which collects the statistics of its usage.
+///
+/// The queue automatically records the time-average and maximum queue length, and the time spent in the queue by
+/// items, using the simulation time of the context passed on creation. The order of removing items is defined by
+/// [`QueueDiscipline`].
+///
+/// The queue length is also registered as a [time-weighted metric](crate::metrics::TimeWeighted) of the component
+/// with the name passed on creation, so it is included in [`Simulation::metrics`](crate::Simulation::metrics).
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::Simulation;
+/// use simcore::waiting_queue::{QueueDiscipline, WaitingQueue};
+///
+/// let mut sim = Simulation::new(123);
+/// let ctx = sim.create_context("server");
+/// let mut queue = WaitingQueue::new(&ctx, "queue", QueueDiscipline::Fifo);
+///
+/// queue.push("a");
+/// sim.step_until_time(2.);
+/// queue.push("b");
+/// sim.step_until_time(4.);
+/// assert_eq!(queue.pop(), Some("a"));
+/// assert_eq!(queue.len(), 1);
+///
+/// let stats = queue.stats();
+/// assert_eq!(stats.mean_length, 1.5);
+/// assert_eq!(stats.max_length, 2);
+/// assert_eq!(stats.mean_wait_time, 4.);
+/// assert_eq!(sim.metrics().get("server", "queue").unwrap().as_f64(), 1.5);
+/// ```
+pub struct WaitingQueue<T> {
+    discipline: QueueDiscipline,
+    entries: BTreeMap<(i64, i64), QueueEntry<T>>,
+    next_seq: i64,
+    sim_state: Rc<RefCell<SimulationState>>,
+    // Statistics
+    length: TimeWeightedStat,
+    enqueued: u64,
+    dequeued: u64,
+    total_wait_time: f64,
+    max_wait_time: f64,
+}
+
+impl<T> WaitingQueue<T> {
+    /// Creates an empty queue with the specified discipline, which uses the time of the specified context.
+    ///
+    /// The queue length is recorded as a time-weighted metric of the component with the specified name,
+    /// so the queues of the same component should have different names.
+    ///
+    /// Panics if the component already has a metric of another kind with the same name.
+    pub fn new(ctx: &SimulationContext, name: &str, discipline: QueueDiscipline) -> Self {
+        Self {
+            discipline,
+            entries: BTreeMap::new(),
+            next_seq: 0,
+            sim_state: ctx.sim_state(),
+            length: TimeWeightedStat::new(ctx, name, 0.),
+            enqueued: 0,
+            dequeued: 0,
+            total_wait_time: 0.,
+            max_wait_time: 0.,
+        }
+    }
+
+    /// Returns the queue discipline.
+    pub fn discipline(&self) -> QueueDiscipline {
+        self.discipline
+    }
+
+    /// Returns the number of items in the queue.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the item to the queue.
+    ///
+    /// In priority queue, the item gets the default priority 0.
+    pub fn push(&mut self, item: T) {
+        self.insert(item, 0);
+    }
+
+    /// Adds the item with the specified priority to the queue.
+    ///
+    /// Items with smaller `priority` values are removed first.
+    ///
+    /// Panics if the queue discipline is not [`QueueDiscipline::Priority`].
+    pub fn push_with_priority(&mut self, item: T, priority: i64) {
+        assert_eq!(
+            self.discipline,
+            QueueDiscipline::Priority,
+            "Priorities are supported only by priority queue"
+        );
+        self.insert(item, priority);
+    }
+
+    /// Returns the next item to be removed without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.entries.first_key_value().map(|(_, entry)| &entry.item)
+    }
+
+    /// Removes the next item from the queue according to the queue discipline.
+    pub fn pop(&mut self) -> Option<T> {
+        let (_, entry) = self.entries.pop_first()?;
+        self.on_removed(&entry);
+        self.update_length();
+        Some(entry.item)
+    }
+
+    /// Removes the items which satisfy the specified predicate, e.g. customers reneging from the queue, and
+    /// returns them in the queue order.
+    ///
+    /// The removed items are accounted in the statistics.
+    pub fn remove_if<F>(&mut self, mut pred: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let keys: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| pred(&entry.item))
+            .map(|(key, _)| *key)
+            .collect();
+        let mut removed = Vec::with_capacity(keys.len());
+        for key in keys {
+            let entry = self.entries.remove(&key).unwrap();
+            self.on_removed(&entry);
+            removed.push(entry.item);
+        }
+        self.update_length();
+        removed
+    }
+
+    /// Returns an iterator over the queued items in the queue order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|entry| &entry.item)
+    }
+
+    /// Returns the statistics of the queue up to the current time.
+    pub fn stats(&self) -> QueueStats {
+        let length = self.length.stats();
+        QueueStats {
+            mean_length: length.mean(),
+            max_length: length.max as usize,
+            enqueued: self.enqueued,
+            dequeued: self.dequeued,
+            mean_wait_time: if self.dequeued > 0 {
+                self.total_wait_time / self.dequeued as f64
+            } else {
+                0.
+            },
+            max_wait_time: self.max_wait_time,
+        }
+    }
+
+    /// Resets the statistics of the queue, e.g. after the warmup period.
+    ///
+    /// The statistics are collected from the current time, with the maximum length initialized to the current one.
+    /// The wait times of the items already in the queue are accounted from their enqueue time.
+    pub fn reset_stats(&mut self) {
+        self.length.reset();
+        self.enqueued = 0;
+        self.dequeued = 0;
+        self.total_wait_time = 0.;
+        self.max_wait_time = 0.;
+    }
+
+    fn insert(&mut self, item: T, priority: i64) {
+        let time = self.sim_state.borrow().time();
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let key = match self.discipline {
+            QueueDiscipline::Fifo => (0, seq),
+            QueueDiscipline::Lifo => (0, -seq),
+            QueueDiscipline::Priority => (priority, seq),
+        };
+        self.entries.insert(
+            key,
+            QueueEntry {
+                item,
+                enqueue_time: time,
+            },
+        );
+        self.enqueued += 1;
+        self.update_length();
+    }
+
+    fn on_removed(&mut self, entry: &QueueEntry<T>) {
+        let wait_time = self.sim_state.borrow().time() - entry.enqueue_time;
+        self.dequeued += 1;
+        self.total_wait_time += wait_time;
+        self.max_wait_time = self.max_wait_time.max(wait_time);
+    }
+
+    fn update_length(&mut self) {
+        self.length.set(self.entries.len() as f64);
+    }
+}
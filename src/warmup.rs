@@ -0,0 +1,368 @@
+//! Detection of the warmup period.
+//!
+//! Simulations of non-terminating systems usually start from an empty state, e.g. with empty queues, which biases
+//! the collected statistics until the system reaches the steady state. The common approach is to discard the
+//! observations made during the initial warmup period. This module implements the MSER-k heuristic (Marginal
+//! Standard Error Rule), which selects the truncation point minimizing the standard error of the mean of the
+//! remaining observations averaged in batches of size _k_, usually 5.
+//!
+//! The heuristic can be applied to a series of observations via [`mser`], or to the observations of multiple metrics
+//! recorded during the run via [`WarmupDetector`], which can also notify the model when the steady state is
+//! reached to reset its statistics.
+//!
+//! When the warmup time is known in advance, it can be declared via
+//! [`Simulation::set_warmup_time`](crate::Simulation::set_warmup_time). Once the simulation time reaches the warmup
+//! time, the simulation resets its built-in counters and the statistics of components implementing [`ResetStats`]
+//! registered via [`Simulation::add_warmup_reset`](crate::Simulation::add_warmup_reset), so the steady-state
+//! measurements exclude the transient behavior without bookkeeping in each component.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::checkpoint::SavedWarmup;
+use crate::waiting_queue::WaitingQueue;
+
+/// Batch size of MSER-5 heuristic.
+pub const MSER_BATCH_SIZE: usize = 5;
+
+/// Returns the number of initial observations which should be truncated according to MSER-k heuristic with
+/// the specified batch size.
+///
+/// The truncation point is selected among the batch boundaries in the first half of the series. Returns `None` if
+/// the series has less than two full batches or the optimal truncation point reaches the middle of the series,
+/// which means that the run is too short to detect the steady state.
+///
+/// Panics if the batch size is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use simcore::warmup::{mser, MSER_BATCH_SIZE};
+///
+/// // linear growth during the first 50 observations followed by oscillation around 50
+/// let values: Vec<f64> = (0..500)
+///     .map(|i| if i < 50 { i as f64 } else { 50. + (i % 3) as f64 - 1. })
+///     .collect();
+/// let truncation = mser(&values, MSER_BATCH_SIZE).unwrap();
+/// assert!(truncation >= 40 && truncation <= 55);
+///
+/// // the series without steady state
+/// let values: Vec<f64> = (0..500).map(|i| i as f64).collect();
+/// assert_eq!(mser(&values, MSER_BATCH_SIZE), None);
+/// ```
+pub fn mser(values: &[f64], batch_size: usize) -> Option<usize> {
+    assert!(batch_size > 0, "Batch size must be positive");
+    let batches: Vec<f64> = values
+        .chunks_exact(batch_size)
+        .map(|batch| batch.iter().sum::<f64>() / batch_size as f64)
+        .collect();
+    let n = batches.len();
+    if n < 2 {
+        return None;
+    }
+    // suffix sums allow computing the statistic for all truncation points in linear time
+    let max_d = n / 2;
+    let mut sum: f64 = batches[max_d + 1..].iter().sum();
+    let mut sum_sq: f64 = batches[max_d + 1..].iter().map(|b| b * b).sum();
+    let mut best = (max_d, f64::INFINITY);
+    for d in (0..=max_d).rev() {
+        sum += batches[d];
+        sum_sq += batches[d] * batches[d];
+        let count = (n - d) as f64;
+        let mean = sum / count;
+        let statistic = (sum_sq / count - mean * mean).max(0.) / count;
+        if statistic <= best.1 {
+            best = (d, statistic);
+        }
+    }
+    // the statistic still decreasing in the middle of the series indicates that the steady state is not reached
+    if best.0 == max_d {
+        return None;
+    }
+    Some(best.0 * batch_size)
+}
+
+/// Warmup period of a single metric recorded by [`WarmupDetector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricWarmup {
+    /// Number of recorded observations.
+    pub observations: usize,
+    /// Number of initial observations which should be truncated, `None` if the steady state is not detected.
+    pub truncation: Option<usize>,
+    /// Time of the first observation after the truncation point, `None` if the steady state is not detected.
+    pub time: Option<f64>,
+}
+
+type SteadyStateCallback = Box<dyn FnMut(f64)>;
+
+#[derive(Default)]
+struct Series {
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+/// Detector of the warmup period over multiple metrics.
+///
+/// The model records the observations of metrics, e.g. waiting times of requests or periodic samples of queue
+/// lengths, via [`record`](Self::record). The warmup period of each metric is estimated via [`mser`] heuristic,
+/// and the warmup period of the whole model ends when all metrics reach the steady state.
+///
+/// The detector periodically checks the recorded observations and calls the callbacks registered via
+/// [`on_steady_state`](Self::on_steady_state) once the steady state is detected, which allows resetting the model
+/// statistics automatically.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use simcore::warmup::WarmupDetector;
+///
+/// let steady_time = Rc::new(Cell::new(None));
+/// let steady_time_ = steady_time.clone();
+/// let mut detector = WarmupDetector::new().with_check_interval(50);
+/// detector.on_steady_state(move |time| steady_time_.set(Some(time)));
+///
+/// for i in 0..1000 {
+///     let time = i as f64;
+///     // response time grows while the system fills up
+///     let response_time = if i < 100 { i as f64 / 10. } else { 10. + (i % 5) as f64 };
+///     detector.record("response_time", time, response_time);
+/// }
+///
+/// let warmup = detector.warmup_time().unwrap();
+/// assert!(warmup >= 90. && warmup <= 110.);
+/// assert!(detector.is_steady());
+/// assert!(steady_time.get().is_some());
+/// ```
+pub struct WarmupDetector {
+    batch_size: usize,
+    min_observations: usize,
+    check_interval: usize,
+    metrics: BTreeMap<String, Series>,
+    observations_since_check: usize,
+    steady_time: Option<f64>,
+    callbacks: Vec<SteadyStateCallback>,
+}
+
+impl Default for WarmupDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WarmupDetector {
+    /// Creates a detector using MSER-5 heuristic, which requires at least 100 observations of each metric and
+    /// checks the steady state every 100 observations.
+    pub fn new() -> Self {
+        Self {
+            batch_size: MSER_BATCH_SIZE,
+            min_observations: 100,
+            check_interval: 100,
+            metrics: BTreeMap::new(),
+            observations_since_check: 0,
+            steady_time: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Sets the batch size of MSER heuristic.
+    ///
+    /// Panics if the size is zero.
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "Batch size must be positive");
+        self.batch_size = size;
+        self
+    }
+
+    /// Sets the minimum number of observations of each metric required to detect the steady state.
+    pub fn with_min_observations(mut self, count: usize) -> Self {
+        self.min_observations = count;
+        self
+    }
+
+    /// Sets the number of recorded observations (over all metrics) between the automatic checks of steady state.
+    ///
+    /// Panics if the interval is zero.
+    pub fn with_check_interval(mut self, count: usize) -> Self {
+        assert!(count > 0, "Check interval must be positive");
+        self.check_interval = count;
+        self
+    }
+
+    /// Registers the callback which is called with the detected warmup time once the steady state is reached.
+    pub fn on_steady_state<F>(&mut self, callback: F)
+    where
+        F: FnMut(f64) + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Records the observation of the metric made at the specified time.
+    ///
+    /// Panics if the time is smaller than the time of the previous observation of this metric.
+    pub fn record(&mut self, metric: &str, time: f64, value: f64) {
+        let series = match self.metrics.get_mut(metric) {
+            Some(series) => series,
+            None => self.metrics.entry(metric.to_owned()).or_default(),
+        };
+        if let Some(last_time) = series.times.last() {
+            assert!(
+                time >= *last_time,
+                "Observation time {} is earlier than the previous observation time {} of metric {}",
+                time,
+                last_time,
+                metric
+            );
+        }
+        series.times.push(time);
+        series.values.push(value);
+        self.observations_since_check += 1;
+        if self.steady_time.is_none() && self.observations_since_check >= self.check_interval {
+            self.check();
+        }
+    }
+
+    /// Returns the names of recorded metrics.
+    pub fn metrics(&self) -> impl Iterator<Item = &str> {
+        self.metrics.keys().map(|name| name.as_str())
+    }
+
+    /// Estimates the warmup period of the metric, or returns `None` if the metric is not recorded.
+    pub fn metric_warmup(&self, metric: &str) -> Option<MetricWarmup> {
+        self.metrics.get(metric).map(|series| self.analyze(series))
+    }
+
+    /// Estimates the warmup time of the model as the maximum warmup time over the metrics.
+    ///
+    /// Returns `None` if no metrics are recorded or the steady state is not detected for some metric.
+    pub fn warmup_time(&self) -> Option<f64> {
+        if self.metrics.is_empty() {
+            return None;
+        }
+        self.metrics
+            .values()
+            .map(|series| self.analyze(series).time)
+            .try_fold(f64::NEG_INFINITY, |max, time| time.map(|time| max.max(time)))
+    }
+
+    /// Returns true if the steady state was detected by the automatic checks.
+    pub fn is_steady(&self) -> bool {
+        self.steady_time.is_some()
+    }
+
+    /// Returns the warmup time detected by the automatic checks.
+    pub fn steady_time(&self) -> Option<f64> {
+        self.steady_time
+    }
+
+    fn analyze(&self, series: &Series) -> MetricWarmup {
+        let observations = series.values.len();
+        let truncation = if observations >= self.min_observations {
+            mser(&series.values, self.batch_size)
+        } else {
+            None
+        };
+        MetricWarmup {
+            observations,
+            truncation,
+            time: truncation.map(|d| series.times[d.min(observations - 1)]),
+        }
+    }
+
+    fn check(&mut self) {
+        self.observations_since_check = 0;
+        if let Some(time) = self.warmup_time() {
+            self.steady_time = Some(time);
+            for callback in self.callbacks.iter_mut() {
+                callback(time);
+            }
+        }
+    }
+}
+
+/// Component or metric whose statistics are reset at the end of the warmup period,
+/// see [`Simulation::add_warmup_reset`](crate::Simulation::add_warmup_reset).
+pub trait ResetStats {
+    /// Resets the collected statistics.
+    fn reset_stats(&mut self);
+}
+
+impl<T> ResetStats for WaitingQueue<T> {
+    fn reset_stats(&mut self) {
+        WaitingQueue::reset_stats(self)
+    }
+}
+
+// Declared warmup period of simulation with the statistics reset at its end.
+#[derive(Default)]
+pub(crate) struct WarmupPeriod {
+    time: Option<f64>,
+    end_time: Option<f64>,
+    // Numbers of emitted and processed events at the end of warmup period.
+    event_counts: (u64, u64),
+    resets: Vec<Rc<RefCell<dyn ResetStats>>>,
+    callbacks: Vec<SteadyStateCallback>,
+}
+
+impl WarmupPeriod {
+    pub fn set_time(&mut self, time: f64) {
+        self.time = Some(time);
+    }
+
+    pub fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    pub fn end_time(&self) -> Option<f64> {
+        self.end_time
+    }
+
+    // Returns the declared warmup time if the period has not ended yet.
+    pub fn pending_time(&self) -> Option<f64> {
+        self.time.filter(|_| self.end_time.is_none())
+    }
+
+    pub fn add_reset(&mut self, component: Rc<RefCell<dyn ResetStats>>) {
+        self.resets.push(component);
+    }
+
+    pub fn add_callback(&mut self, callback: SteadyStateCallback) {
+        self.callbacks.push(callback);
+    }
+
+    pub fn event_counts(&self) -> (u64, u64) {
+        self.event_counts
+    }
+
+    // Ends the warmup period at the specified time, the user statistics are reset by `reset_user_stats`.
+    pub fn end(&mut self, time: f64, event_counts: (u64, u64)) {
+        self.end_time = Some(time);
+        self.event_counts = event_counts;
+    }
+
+    pub fn save(&self) -> SavedWarmup {
+        SavedWarmup {
+            time: self.time,
+            end_time: self.end_time,
+            event_counts: self.event_counts,
+        }
+    }
+
+    // Restores the period from the checkpoint, keeping the declared warmup time if the checkpoint has none.
+    pub fn restore(&mut self, saved: &SavedWarmup) {
+        self.time = saved.time.or(self.time);
+        self.end_time = saved.end_time;
+        self.event_counts = saved.event_counts;
+    }
+
+    pub fn reset_user_stats(&mut self, time: f64) {
+        for component in self.resets.iter() {
+            component.borrow_mut().reset_stats();
+        }
+        for callback in self.callbacks.iter_mut() {
+            callback(time);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Watchpoints on component state.
+//!
+//! A watchpoint registered via [`Simulation::add_watchpoint`](crate::Simulation::add_watchpoint) checks a
+//! user-defined condition on the component state after each event delivered to the component. When the condition
+//! becomes true, the watchpoint is triggered and the simulation pauses or the callback is called. This helps to
+//! find the event which corrupted the component state without adding checks to the component code.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::component::Id;
+use crate::event::EventId;
+
+/// Identifier of a watchpoint.
+pub type WatchpointId = u64;
+
+/// Information about a triggered watchpoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WatchpointHit {
+    /// Identifier of the watchpoint.
+    pub watchpoint: WatchpointId,
+    /// Identifier of the watched component.
+    pub component: Id,
+    /// Identifier of the event whose processing made the condition true.
+    pub event_id: EventId,
+    /// Time of the event.
+    pub time: f64,
+}
+
+type ConditionFn = Box<dyn Fn() -> bool>;
+pub(crate) type CallbackFn = Box<dyn FnMut(&WatchpointHit)>;
+
+pub(crate) struct Watchpoint {
+    pub id: WatchpointId,
+    pub component: Id,
+    condition: ConditionFn,
+    // Value of the condition after the last check, the watchpoint is triggered when it changes to true.
+    was_true: bool,
+    // Called when the watchpoint is triggered, the simulation is paused if there is no callback.
+    callback: Option<CallbackFn>,
+}
+
+impl Watchpoint {
+    pub fn new<C, F>(
+        id: WatchpointId,
+        component_id: Id,
+        component: Rc<RefCell<C>>,
+        condition: F,
+        callback: Option<CallbackFn>,
+    ) -> Self
+    where
+        C: 'static,
+        F: Fn(&C) -> bool + 'static,
+    {
+        let condition: ConditionFn = Box::new(move || condition(&component.borrow()));
+        let was_true = condition();
+        Self {
+            id,
+            component: component_id,
+            condition,
+            was_true,
+            callback,
+        }
+    }
+
+    // Checks the condition and returns the hit if the watchpoint is triggered and should pause the simulation.
+    pub fn check(&mut self, event_id: EventId, time: f64) -> Option<WatchpointHit> {
+        let is_true = (self.condition)();
+        let triggered = is_true && !self.was_true;
+        self.was_true = is_true;
+        if !triggered {
+            return None;
+        }
+        let hit = WatchpointHit {
+            watchpoint: self.id,
+            component: self.component,
+            event_id,
+            time,
+        };
+        match self.callback.as_mut() {
+            Some(callback) => {
+                callback(&hit);
+                None
+            }
+            None => Some(hit),
+        }
+    }
+}
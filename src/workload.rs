@@ -0,0 +1,518 @@
+//! Recording and synthesis of workloads.
+//!
+//! Experiments often need to reproduce the workload observed in some run, e.g. in a detailed model or a trace of
+//! a real system, and to scale it up or down. This module records the empirical interarrival times and sizes of
+//! arrivals, fits them with a small set of distributions and synthesizes statistically similar workloads in later
+//! runs:
+//!
+//! - [`WorkloadRecorder`] records the arrivals of events of some type during a run, it is registered as a
+//!   [time advance listener](crate::Simulation::add_time_advance_listener). The arrivals can also be extracted from
+//!   the recorded trace file via [`Workload::from_trace`].
+//! - [`Workload`] holds the recorded interarrival times and sizes, it can be saved to a file and loaded back.
+//! - [`WorkloadModel`] is produced by [`Workload::fit`] or [`Workload::empirical`] and samples the interarrival
+//!   times and sizes of synthesized arrivals using the simulation random number generator. The model can be scaled
+//!   via [`scale_rate`](WorkloadModel::scale_rate) and [`scale_size`](WorkloadModel::scale_size) and passed to
+//!   [`ArrivalGenerator::with_workload`](crate::generator::ArrivalGenerator::with_workload).
+//!
+//! Each sample is fitted with the distributions using the maximum likelihood estimates of their parameters. The
+//! exponential distribution is preferred as the simplest model unless the Kolmogorov-Smirnov test rejects it at 5%
+//! significance level. Otherwise the log-normal, Pareto and Weibull distributions are fitted and the one with the
+//! smallest Kolmogorov-Smirnov distance to the sample is selected. These distributions are fitted only to samples
+//! with positive values, so the zero interarrival times of simultaneous arrivals limit the choice to the
+//! exponential distribution.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use serde::Serialize;
+//! use simcore::generator::{ArrivalGenerator, TargetPolicy};
+//! use simcore::workload::{FittedDistribution, WorkloadRecorder};
+//! use simcore::Simulation;
+//!
+//! #[derive(Clone, Serialize)]
+//! struct Request {
+//!     size: f64,
+//! }
+//!
+//! // record the workload produced by Poisson arrivals with rate 5
+//! let mut sim = Simulation::new(123);
+//! let server_id = sim.create_context("server").id();
+//! let generator = ArrivalGenerator::new(sim.create_context("generator"), |ctx, _| Request {
+//!     size: ctx.sample_lognormal(1., 0.5),
+//! })
+//! .with_exponential_interarrival(5.)
+//! .with_max_arrivals(2000)
+//! .with_targets(TargetPolicy::Single(server_id));
+//! let generator = Rc::new(RefCell::new(generator));
+//! sim.add_handler("generator", generator.clone());
+//! let recorder = Rc::new(RefCell::new(
+//!     WorkloadRecorder::new::<Request>().to(server_id).with_size(|request: &Request| request.size),
+//! ));
+//! sim.add_time_advance_listener(recorder.clone());
+//! generator.borrow_mut().start();
+//! sim.step_until_no_events();
+//!
+//! // fit the recorded workload and double its arrival rate
+//! let model = recorder.borrow().workload().fit().scale_rate(2.);
+//! assert!(matches!(model.interarrival, FittedDistribution::Exponential { .. }));
+//! assert!(matches!(model.size, Some(FittedDistribution::LogNormal { .. })));
+//! assert!((model.mean_interarrival() - 0.1).abs() < 0.01);
+//!
+//! // synthesize the scaled workload in another run
+//! let mut sim = Simulation::new(456);
+//! let server_id = sim.create_context("server").id();
+//! let size_model = model.clone();
+//! let generator = ArrivalGenerator::new(sim.create_context("generator"), move |ctx, _| Request {
+//!     size: size_model.sample_size(ctx),
+//! })
+//! .with_workload(model)
+//! .with_stop_time(100.)
+//! .with_targets(TargetPolicy::Single(server_id));
+//! let generator = Rc::new(RefCell::new(generator));
+//! sim.add_handler("generator", generator.clone());
+//! generator.borrow_mut().start();
+//! sim.step_until_no_events();
+//! assert!((generator.borrow().arrival_count() as f64 - 1000.).abs() < 100.);
+//! ```
+
+use std::any::TypeId;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Id;
+use crate::event::{Event, EventData};
+use crate::observer::TimeAdvanceListener;
+use crate::special::{erf, gamma};
+use crate::trace_file::{TraceEventKind, TraceFile};
+use crate::SimulationContext;
+
+/// Recorded interarrival times and sizes of arrivals.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Workload {
+    /// Times between consecutive arrivals, starting from the second arrival.
+    pub interarrivals: Vec<f64>,
+    /// Sizes of arrivals, empty if the sizes are not recorded.
+    pub sizes: Vec<f64>,
+}
+
+impl Workload {
+    /// Creates the workload from the times and optional sizes of arrivals, which should be ordered by time.
+    ///
+    /// Panics if the sizes are specified not for all arrivals.
+    pub fn from_arrivals(arrivals: &[(f64, Option<f64>)]) -> Self {
+        let interarrivals = arrivals.windows(2).map(|pair| pair[1].0 - pair[0].0).collect();
+        let sizes: Vec<f64> = arrivals.iter().filter_map(|(_, size)| *size).collect();
+        assert!(
+            sizes.is_empty() || sizes.len() == arrivals.len(),
+            "Sizes must be specified for all arrivals"
+        );
+        Self { interarrivals, sizes }
+    }
+
+    /// Extracts the workload from the processed events of the specified type (name without module path) in the
+    /// trace file, optionally restricted to the specified destination.
+    ///
+    /// The size of arrival is read from the event payload by JSON pointer, e.g. `/size`, if it is specified.
+    /// Panics if the payload is not recorded or the size is not a number.
+    pub fn from_trace(trace: &TraceFile, type_name: &str, dst: Option<&str>, size_pointer: Option<&str>) -> Self {
+        let arrivals: Vec<(f64, Option<f64>)> = trace
+            .records
+            .iter()
+            .filter(|record| record.kind == TraceEventKind::Processed && record.type_name == type_name)
+            .filter(|record| dst.is_none_or(|dst| record.dst == dst))
+            .map(|record| {
+                let size = size_pointer.map(|pointer| {
+                    let data = record
+                        .data
+                        .as_ref()
+                        .expect("Event payload is not recorded in trace file");
+                    data.pointer(pointer)
+                        .and_then(|value| value.as_f64())
+                        .unwrap_or_else(|| panic!("Size {} of event {} is not a number", pointer, record.id))
+                });
+                (record.time, size)
+            })
+            .collect();
+        Self::from_arrivals(&arrivals)
+    }
+
+    /// Loads the workload saved via [`save`](Self::save).
+    ///
+    /// Panics if the file cannot be read or has invalid format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let file = File::open(path).expect("Failed to open workload file");
+        serde_json::from_reader(BufReader::new(file)).expect("Failed to parse workload file")
+    }
+
+    /// Saves the workload to the file in JSON format.
+    ///
+    /// Panics if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        let file = File::create(path).expect("Failed to create workload file");
+        serde_json::to_writer(BufWriter::new(file), self).expect("Failed to write workload file");
+    }
+
+    /// Returns the number of recorded arrivals.
+    pub fn arrival_count(&self) -> usize {
+        if self.interarrivals.is_empty() {
+            self.sizes.len()
+        } else {
+            self.interarrivals.len() + 1
+        }
+    }
+
+    /// Returns the model with the distributions fitted to the recorded interarrival times and sizes.
+    ///
+    /// Panics if there are less than two recorded arrivals.
+    pub fn fit(&self) -> WorkloadModel {
+        assert!(
+            !self.interarrivals.is_empty(),
+            "Workload must contain at least two arrivals"
+        );
+        WorkloadModel::new(
+            FittedDistribution::fit(&self.interarrivals),
+            (!self.sizes.is_empty()).then(|| FittedDistribution::fit(&self.sizes)),
+        )
+    }
+
+    /// Returns the model which resamples the recorded interarrival times and sizes.
+    ///
+    /// Panics if there are less than two recorded arrivals.
+    pub fn empirical(&self) -> WorkloadModel {
+        assert!(
+            !self.interarrivals.is_empty(),
+            "Workload must contain at least two arrivals"
+        );
+        WorkloadModel::new(
+            FittedDistribution::Empirical(self.interarrivals.clone()),
+            (!self.sizes.is_empty()).then(|| FittedDistribution::Empirical(self.sizes.clone())),
+        )
+    }
+}
+
+type SizeFn = Box<dyn Fn(&Event) -> f64>;
+
+/// Recorder of the arrivals of events of some type.
+///
+/// The recorder is registered via [`Simulation::add_time_advance_listener`](crate::Simulation::add_time_advance_listener)
+/// and records the processed events of the specified type, including the events processed in batches.
+/// See [module](self) documentation for examples.
+pub struct WorkloadRecorder {
+    type_id: TypeId,
+    dst: Option<Id>,
+    size: Option<SizeFn>,
+    last_time: Option<f64>,
+    workload: Workload,
+}
+
+impl WorkloadRecorder {
+    /// Creates a recorder of the events of type `T` without recording their sizes.
+    pub fn new<T: EventData>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            dst: None,
+            size: None,
+            last_time: None,
+            workload: Workload::default(),
+        }
+    }
+
+    /// Restricts the recorder to the events destined to the specified component.
+    pub fn to(mut self, dst: Id) -> Self {
+        self.dst = Some(dst);
+        self
+    }
+
+    /// Records the sizes of arrivals computed from the event payloads of type `T`.
+    ///
+    /// Panics if `T` differs from the type passed to [`new`](Self::new).
+    pub fn with_size<T, F>(mut self, size: F) -> Self
+    where
+        T: EventData,
+        F: Fn(&T) -> f64 + 'static,
+    {
+        assert!(
+            TypeId::of::<T>() == self.type_id,
+            "Size function must accept the recorded event type"
+        );
+        self.size = Some(Box::new(move |event| size(event.data.downcast_ref::<T>().unwrap())));
+        self
+    }
+
+    /// Records the arrival at the specified time with optional size.
+    ///
+    /// This is called automatically for the processed events, but can be used to record arbitrary arrivals.
+    pub fn record(&mut self, time: f64, size: Option<f64>) {
+        if let Some(last_time) = self.last_time.replace(time) {
+            self.workload.interarrivals.push(time - last_time);
+        }
+        if let Some(size) = size {
+            self.workload.sizes.push(size);
+        }
+    }
+
+    /// Returns the recorded workload.
+    pub fn workload(&self) -> &Workload {
+        &self.workload
+    }
+}
+
+impl TimeAdvanceListener for WorkloadRecorder {
+    fn on_time_advance(&mut self, _old_time: f64, _new_time: f64, events: &[Event]) {
+        for event in events {
+            if event.data.as_ref().type_id() != self.type_id || self.dst.is_some_and(|dst| event.dst != dst) {
+                continue;
+            }
+            let size = self.size.as_ref().map(|size| size(event));
+            self.record(event.time, size);
+        }
+    }
+}
+
+/// Distribution fitted to the sample of non-negative values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FittedDistribution {
+    /// All values are equal.
+    Constant(f64),
+    /// Exponential distribution with the specified rate.
+    Exponential {
+        /// Rate parameter.
+        rate: f64,
+    },
+    /// Log-normal distribution, whose logarithm has the specified mean and standard deviation.
+    LogNormal {
+        /// Mean of the logarithm.
+        mu: f64,
+        /// Standard deviation of the logarithm.
+        sigma: f64,
+    },
+    /// Pareto distribution with the specified scale (minimum value) and shape.
+    Pareto {
+        /// Scale parameter.
+        scale: f64,
+        /// Shape parameter.
+        shape: f64,
+    },
+    /// Weibull distribution with the specified scale and shape.
+    Weibull {
+        /// Scale parameter.
+        scale: f64,
+        /// Shape parameter.
+        shape: f64,
+    },
+    /// Values are sampled uniformly from the recorded sample.
+    Empirical(Vec<f64>),
+}
+
+impl FittedDistribution {
+    /// Fits the distributions to the sample and returns the selected one, see [module](self) documentation.
+    ///
+    /// Panics if the sample is empty or contains negative values.
+    pub fn fit(values: &[f64]) -> Self {
+        assert!(!values.is_empty(), "Cannot fit distribution to empty sample");
+        assert!(
+            values.iter().all(|value| *value >= 0.),
+            "Cannot fit distribution to sample with negative values"
+        );
+        if values.iter().all(|value| *value == values[0]) {
+            return Self::Constant(values[0]);
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        if let Some(exponential) = fit_exponential(&sorted) {
+            // critical value of Kolmogorov-Smirnov test at 5% significance level
+            if exponential.ks_distance(&sorted) <= 1.36 / (sorted.len() as f64).sqrt() || sorted[0] == 0. {
+                return exponential;
+            }
+        }
+        [fit_lognormal(&sorted), fit_pareto(&sorted), fit_weibull(&sorted)]
+            .into_iter()
+            .flatten()
+            .map(|dist| (dist.ks_distance(&sorted), dist))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, dist)| dist)
+            .unwrap_or_else(|| Self::Empirical(values.to_vec()))
+    }
+
+    /// Returns a random value from the distribution using the simulation random number generator.
+    pub fn sample(&self, ctx: &SimulationContext) -> f64 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Exponential { rate } => ctx.sample_exponential(*rate),
+            Self::LogNormal { mu, sigma } => ctx.sample_lognormal(*mu, *sigma),
+            Self::Pareto { scale, shape } => ctx.sample_pareto(*scale, *shape),
+            Self::Weibull { scale, shape } => ctx.sample_weibull(*scale, *shape),
+            Self::Empirical(values) => *ctx.choose(values),
+        }
+    }
+
+    /// Returns the mean of the distribution, which is infinite for Pareto distribution with shape not greater
+    /// than 1.
+    pub fn mean(&self) -> f64 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Exponential { rate } => 1. / rate,
+            Self::LogNormal { mu, sigma } => (mu + sigma * sigma / 2.).exp(),
+            Self::Pareto { scale, shape } => {
+                if *shape > 1. {
+                    shape * scale / (shape - 1.)
+                } else {
+                    f64::INFINITY
+                }
+            }
+            Self::Weibull { scale, shape } => scale * gamma(1. + 1. / shape),
+            Self::Empirical(values) => values.iter().sum::<f64>() / values.len() as f64,
+        }
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        match self {
+            Self::Exponential { rate } => 1. - (-rate * x).exp(),
+            Self::LogNormal { mu, sigma } => 0.5 * (1. + erf((x.ln() - mu) / (sigma * std::f64::consts::SQRT_2))),
+            Self::Pareto { scale, shape } => 1. - (scale / x).powf(*shape),
+            Self::Weibull { scale, shape } => 1. - (-(x / scale).powf(*shape)).exp(),
+            Self::Constant(_) | Self::Empirical(_) => unreachable!(),
+        }
+    }
+
+    // Returns the maximum distance between the distribution and empirical CDFs of the sorted sample.
+    fn ks_distance(&self, sorted: &[f64]) -> f64 {
+        let n = sorted.len() as f64;
+        sorted
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let cdf = self.cdf(*x);
+                (cdf - i as f64 / n).max((i + 1) as f64 / n - cdf)
+            })
+            .fold(0., f64::max)
+    }
+}
+
+fn fit_exponential(values: &[f64]) -> Option<FittedDistribution> {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (mean > 0.).then(|| FittedDistribution::Exponential { rate: 1. / mean })
+}
+
+fn fit_lognormal(values: &[f64]) -> Option<FittedDistribution> {
+    let n = values.len() as f64;
+    let mu = values.iter().map(|x| x.ln()).sum::<f64>() / n;
+    let sigma = (values.iter().map(|x| (x.ln() - mu).powi(2)).sum::<f64>() / n).sqrt();
+    (sigma > 0.).then_some(FittedDistribution::LogNormal { mu, sigma })
+}
+
+fn fit_pareto(values: &[f64]) -> Option<FittedDistribution> {
+    let scale = values[0];
+    let log_sum = values.iter().map(|x| (x / scale).ln()).sum::<f64>();
+    (log_sum > 0.).then(|| FittedDistribution::Pareto {
+        scale,
+        shape: values.len() as f64 / log_sum,
+    })
+}
+
+fn fit_weibull(values: &[f64]) -> Option<FittedDistribution> {
+    // values are normalized by the maximum to avoid overflow, which does not change the shape estimate
+    let max = values[values.len() - 1];
+    let normalized: Vec<f64> = values.iter().map(|x| x / max).collect();
+    let n = normalized.len() as f64;
+    let mean_log = normalized.iter().map(|x| x.ln()).sum::<f64>() / n;
+    // the shape estimate is the root of the increasing function found by bisection
+    let equation = |shape: f64| {
+        let (sum, weighted_sum) = normalized.iter().fold((0., 0.), |(sum, weighted_sum), x| {
+            let power = x.powf(shape);
+            (sum + power, weighted_sum + power * x.ln())
+        });
+        weighted_sum / sum - 1. / shape - mean_log
+    };
+    let (mut low, mut high) = (0.01, 100.);
+    if equation(low) > 0. || equation(high) < 0. {
+        return None;
+    }
+    for _ in 0..100 {
+        let mid = (low + high) / 2.;
+        if equation(mid) < 0. {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    let shape = (low + high) / 2.;
+    let scale = max * (normalized.iter().map(|x| x.powf(shape)).sum::<f64>() / n).powf(1. / shape);
+    Some(FittedDistribution::Weibull { scale, shape })
+}
+
+/// Model of workload, which samples the interarrival times and sizes of synthesized arrivals.
+///
+/// See [module](self) documentation for examples.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadModel {
+    /// Distribution of interarrival times.
+    pub interarrival: FittedDistribution,
+    /// Distribution of sizes, if the sizes are recorded.
+    pub size: Option<FittedDistribution>,
+    /// Factor of arrival rate, the interarrival times are divided by it.
+    pub rate_scale: f64,
+    /// Factor of sizes.
+    pub size_scale: f64,
+}
+
+impl WorkloadModel {
+    /// Creates the model with the specified distributions of interarrival times and sizes.
+    pub fn new(interarrival: FittedDistribution, size: Option<FittedDistribution>) -> Self {
+        Self {
+            interarrival,
+            size,
+            rate_scale: 1.,
+            size_scale: 1.,
+        }
+    }
+
+    /// Multiplies the arrival rate by the specified factor.
+    ///
+    /// Panics if the factor is not positive.
+    pub fn scale_rate(mut self, factor: f64) -> Self {
+        assert!(factor > 0., "Rate scale factor must be positive, got {}", factor);
+        self.rate_scale *= factor;
+        self
+    }
+
+    /// Multiplies the sizes of arrivals by the specified factor.
+    ///
+    /// Panics if the factor is negative.
+    pub fn scale_size(mut self, factor: f64) -> Self {
+        assert!(factor >= 0., "Size scale factor must be non-negative, got {}", factor);
+        self.size_scale *= factor;
+        self
+    }
+
+    /// Returns a random interarrival time using the simulation random number generator.
+    pub fn sample_interarrival(&self, ctx: &SimulationContext) -> f64 {
+        self.interarrival.sample(ctx) / self.rate_scale
+    }
+
+    /// Returns a random size of arrival using the simulation random number generator.
+    ///
+    /// Panics if the sizes are not recorded.
+    pub fn sample_size(&self, ctx: &SimulationContext) -> f64 {
+        let size = self.size.as_ref().expect("Workload model has no size distribution");
+        size.sample(ctx) * self.size_scale
+    }
+
+    /// Returns the mean interarrival time of the scaled workload.
+    pub fn mean_interarrival(&self) -> f64 {
+        self.interarrival.mean() / self.rate_scale
+    }
+
+    /// Returns the mean size of the scaled workload.
+    ///
+    /// Panics if the sizes are not recorded.
+    pub fn mean_size(&self) -> f64 {
+        let size = self.size.as_ref().expect("Workload model has no size distribution");
+        size.mean() * self.size_scale
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use simcore::branch::BranchComponent;
+use simcore::snapshot::ComponentState;
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+struct Counter {
+    received: u32,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Counter {
+    fn on(&mut self, _event: Event) {
+        self.received += 1;
+        self.ctx.emit_self(Ping {}, 1.);
+    }
+}
+
+impl ComponentState for Counter {
+    fn state(&self) -> Value {
+        json!(self.received)
+    }
+}
+
+impl BranchComponent for Counter {
+    fn branch(&self, ctx: SimulationContext) -> Self {
+        Self {
+            received: self.received,
+            ctx,
+        }
+    }
+}
+
+fn build() -> Simulation {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("counter");
+    ctx.emit_self(Ping {}, 0.);
+    sim.add_branchable_handler("counter", Rc::new(RefCell::new(Counter { received: 0, ctx })));
+    sim
+}
+
+#[test]
+fn test_branch_after_tasks_completed() {
+    let mut sim = build();
+    let worker = sim.create_context("worker");
+    sim.spawn(async move {
+        worker.sleep(5.).await;
+    });
+    sim.step_until_time(10.);
+
+    let mut branch = sim.branch();
+    // tasks can be spawned in the branch
+    let worker = branch.create_context("worker");
+    let done = Rc::new(RefCell::new(false));
+    let done_clone = done.clone();
+    branch.spawn(async move {
+        worker.sleep(5.).await;
+        *done_clone.borrow_mut() = true;
+    });
+    branch.step_until_time(20.);
+    sim.step_until_time(20.);
+    assert!(*done.borrow());
+    assert_eq!(branch.snapshot().get("counter"), Some(&json!(21)));
+    assert_eq!(sim.snapshot().get("counter"), Some(&json!(21)));
+}
+
+#[test]
+#[should_panic(expected = "Simulation with alive asynchronous tasks cannot be branched")]
+fn test_branch_with_alive_tasks_panics() {
+    let mut sim = build();
+    let worker = sim.create_context("worker");
+    sim.spawn(async move {
+        worker.sleep(50.).await;
+    });
+    sim.step_until_time(10.);
+    sim.branch();
+}
@@ -0,0 +1,189 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::async_mode::{CancellationToken, TaskScope};
+use simcore::{cast, Event, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct CancelRequest {
+    id: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Reply {
+    value: u32,
+}
+
+struct Server {
+    ctx: SimulationContext,
+    requests: RefCell<Vec<(u64, CancellationToken)>>,
+    log: RefCell<Vec<String>>,
+}
+
+impl Server {
+    fn new(ctx: SimulationContext) -> Self {
+        Self {
+            ctx,
+            requests: RefCell::new(Vec::new()),
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn log(&self, message: String) {
+        self.log.borrow_mut().push(format!("{} {}", self.ctx.time(), message));
+    }
+
+    // Serves request via a tree of helper tasks with depth `depth`.
+    async fn call(self: Rc<Self>, scope: TaskScope, request_id: u64, depth: u32) {
+        if depth > 0 {
+            let child_scope = scope.child_scope();
+            child_scope.spawn(self.clone().call(child_scope.clone(), request_id, depth - 1));
+        }
+        self.ctx.sleep(10. * (depth + 1) as f64).await;
+        self.log(format!("request {} finished at depth {}", request_id, depth));
+    }
+}
+
+impl StaticEventHandler for Server {
+    fn on(self: Rc<Self>, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                let token = CancellationToken::new();
+                self.requests.borrow_mut().push((id, token.clone()));
+                let scope = self.ctx.scope_with_token(token);
+                scope.spawn(self.clone().call(scope.clone(), id, 2));
+            }
+            CancelRequest { id } => {
+                let requests = self.requests.borrow();
+                let (_, token) = requests.iter().find(|(request_id, _)| *request_id == id).unwrap();
+                token.cancel();
+            }
+            Reply { value } => {
+                self.log(format!("reply {} via handler", value));
+            }
+        })
+    }
+}
+
+#[test]
+fn test_cancel_request_tree() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = Rc::new(Server::new(sim.create_context("server")));
+    let server_id = sim.add_static_handler("server", server.clone());
+
+    client.emit(Request { id: 1 }, server_id, 0.);
+    client.emit(Request { id: 2 }, server_id, 0.);
+    client.emit(CancelRequest { id: 1 }, server_id, 15.);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        *server.log.borrow(),
+        vec![
+            "10 request 1 finished at depth 0",
+            "10 request 2 finished at depth 0",
+            "20 request 2 finished at depth 1",
+            "30 request 2 finished at depth 2",
+        ]
+    );
+}
+
+#[test]
+fn test_cancel_child_scope() {
+    let mut sim = Simulation::new(123);
+    let server = Rc::new(Server::new(sim.create_context("server")));
+    sim.add_static_handler("server", server.clone());
+
+    let scope = server.ctx.scope_with_token(CancellationToken::new());
+    let child_scope = scope.child_scope();
+    for (scope, name) in [(&scope, "parent"), (&child_scope, "child")] {
+        let server = server.clone();
+        scope.spawn(async move {
+            server.ctx.sleep(10.).await;
+            server.log(name.to_owned());
+        });
+    }
+    child_scope.token().cancel();
+    assert!(!scope.token().is_cancelled());
+    sim.step_until_no_events();
+
+    assert_eq!(*server.log.borrow(), vec!["10 parent"]);
+}
+
+#[test]
+fn test_cancelled_task_releases_event_promises() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = Rc::new(Server::new(sim.create_context("server")));
+    let server_id = sim.add_static_handler("server", server.clone());
+
+    let rc = Rc::new(());
+    let token = CancellationToken::new();
+    let scope = server.ctx.scope_with_token(token.clone());
+    let server_clone = server.clone();
+    let rc_clone = rc.clone();
+    scope.spawn(async move {
+        let _rc = rc_clone;
+        let reply = server_clone.ctx.recv_event::<Reply>().await;
+        server_clone.log(format!("reply {} via task", reply.data.value));
+    });
+    sim.step();
+    assert_eq!(Rc::strong_count(&rc), 2);
+
+    token.cancel();
+    sim.step();
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    // the reply is delivered to the handler since the waiting task is cancelled
+    client.emit(Reply { value: 7 }, server_id, 5.);
+    sim.step_until_no_events();
+    assert_eq!(*server.log.borrow(), vec!["5 reply 7 via handler"]);
+
+    // spawning in the cancelled scope has no effect
+    let server_clone = server.clone();
+    scope.spawn(async move {
+        server_clone.log("spawned after cancel".to_owned());
+    });
+    sim.step_until_no_events();
+    assert_eq!(server.log.borrow().len(), 1);
+}
+
+#[test]
+fn test_run_until_cancelled() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let token = CancellationToken::new();
+    let results = Rc::new(RefCell::new(Vec::new()));
+
+    let task_token = token.clone();
+    let task_results = results.clone();
+    sim.spawn(async move {
+        let result = task_token
+            .run_until_cancelled(async {
+                ctx.sleep(5.).await;
+                ctx.time()
+            })
+            .await;
+        task_results.borrow_mut().push(result);
+        let result = task_token.run_until_cancelled(ctx.sleep(10.)).await;
+        task_results.borrow_mut().push(result.map(|_| ctx.time()));
+        task_token.cancelled().await;
+        task_results.borrow_mut().push(Some(ctx.time()));
+    });
+
+    let canceller = sim.create_context("canceller");
+    sim.spawn(async move {
+        canceller.sleep(8.).await;
+        token.cancel();
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*results.borrow(), vec![Some(5.), None, Some(8.)]);
+}
@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{EventCancellationPolicy, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    session: u64,
+    seq: u64,
+}
+
+#[test]
+fn test_components_with_different_keys() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let by_session = Rc::new(sim.create_context("by_session"));
+    let by_seq = Rc::new(sim.create_context("by_seq"));
+    let plain = Rc::new(sim.create_context("plain"));
+    // each component registers its own key getter, as done by independent libraries
+    by_session.register_component_key_getter_for::<Message>(|message| message.session);
+    by_seq.register_component_key_getter_for::<Message>(|message| message.seq);
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    for (ctx, key) in [(by_session.clone(), 1), (by_seq.clone(), 1)] {
+        let log = log.clone();
+        sim.spawn(async move {
+            let e = ctx.recv_event_by_key::<Message>(key).await;
+            log.borrow_mut()
+                .push((ctx.name().to_owned(), e.data.session, e.data.seq, ctx.time()));
+        });
+    }
+    // component without key getter receives events without key
+    let plain_log = log.clone();
+    let plain_ctx = plain.clone();
+    sim.spawn(async move {
+        let e = plain_ctx.recv_event::<Message>().await;
+        plain_log
+            .borrow_mut()
+            .push((plain_ctx.name().to_owned(), e.data.session, e.data.seq, plain_ctx.time()));
+    });
+
+    sender.emit(Message { session: 1, seq: 5 }, by_seq.id(), 1.);
+    sender.emit(Message { session: 2, seq: 1 }, by_session.id(), 2.);
+    sender.emit(Message { session: 5, seq: 1 }, by_seq.id(), 3.);
+    sender.emit(Message { session: 1, seq: 2 }, by_session.id(), 4.);
+    sender.emit(Message { session: 3, seq: 3 }, plain.id(), 5.);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            ("by_seq".to_owned(), 5, 1, 3.),
+            ("by_session".to_owned(), 1, 2, 4.),
+            ("plain".to_owned(), 3, 3, 5.)
+        ]
+    );
+}
+
+#[test]
+fn test_component_key_getter_overrides_global() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let global = sim.create_context("global");
+    let global_id = global.id();
+    let custom = sim.create_context("custom");
+    let custom_id = custom.id();
+    sim.register_key_getter_for::<Message>(|message| message.session);
+    sim.register_component_key_getter_for::<Message, _>("custom", |message| message.seq);
+
+    sim.spawn(async move {
+        let e = global.recv_event_by_key::<Message>(10).await;
+        assert_eq!(e.data.seq, 20);
+        assert_eq!(global.time(), 2.);
+    });
+    sim.spawn(async move {
+        let e = custom.recv_event_by_key::<Message>(10).await;
+        assert_eq!(e.data.session, 20);
+        assert_eq!(custom.time(), 4.);
+    });
+
+    sender.emit(Message { session: 20, seq: 10 }, global_id, 1.);
+    sender.emit(Message { session: 10, seq: 20 }, global_id, 2.);
+    sender.emit(Message { session: 10, seq: 20 }, custom_id, 3.);
+    sender.emit(Message { session: 20, seq: 10 }, custom_id, 4.);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+}
+
+#[test]
+#[should_panic(expected = "Trying to receive event of type with registered key getter")]
+fn test_recv_without_key_with_component_key_getter() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.register_component_key_getter_for::<Message>(|message| message.seq);
+    sim.spawn(async move {
+        ctx.recv_event::<Message>().await;
+    });
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_component_key_getter_is_not_inherited_by_reused_id() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let old = sim.create_context("old");
+    old.register_component_key_getter_for::<Message>(|message| message.session);
+    sim.remove_component("old", EventCancellationPolicy::None);
+
+    let new = sim.create_context("new");
+    assert_eq!(new.id(), old.id());
+    sim.register_key_getter_for::<Message>(|message| message.seq);
+    let new_id = new.id();
+    sim.spawn(async move {
+        let e = new.recv_event_by_key::<Message>(2).await;
+        assert_eq!(e.data.session, 1);
+    });
+    sender.emit(Message { session: 2, seq: 1 }, new_id, 1.);
+    sender.emit(Message { session: 1, seq: 2 }, new_id, 2.);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+}
@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Request {}
+
+#[derive(Clone, Serialize)]
+struct Response {}
+
+#[test]
+fn test_cycle_of_three_components() {
+    let mut sim = Simulation::new(123);
+    let ctxs: Vec<_> = ["a", "b", "c"].iter().map(|name| sim.create_context(name)).collect();
+    let ids: Vec<_> = ctxs.iter().map(|ctx| ctx.id()).collect();
+    let server = sim.create_context("server");
+
+    for (i, ctx) in ctxs.into_iter().enumerate() {
+        let next_id = ids[(i + 1) % 3];
+        sim.spawn(async move {
+            ctx.recv_event_from::<Response>(next_id).await;
+        });
+    }
+    // waiting for requests from any component is not a deadlock
+    sim.spawn(async move {
+        server.recv_event::<Request>().await;
+    });
+
+    assert!(sim.detect_deadlock().is_none());
+    sim.step_until_no_events();
+
+    let report = sim.detect_deadlock().unwrap();
+    assert_eq!(report.cycles.len(), 1);
+    let cycle: Vec<_> = report.cycles[0]
+        .iter()
+        .map(|wait| (wait.component_name.as_str(), wait.src_name.as_deref().unwrap()))
+        .collect();
+    assert_eq!(cycle, vec![("a", "b"), ("b", "c"), ("c", "a")]);
+    assert_eq!(report.blocked.len(), 4);
+    assert_eq!(report.blocked[3].component_name, "server");
+    assert_eq!(report.blocked[3].src, None);
+    assert!(report.blocked[3].event_type.ends_with("Request"));
+}
+
+#[test]
+fn test_request_response_deadlock() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let client_id = client.id();
+    let server = sim.create_context("server");
+    let server_id = server.id();
+
+    sim.spawn(async move {
+        client.emit(Request {}, server_id, 1.);
+        client.recv_event_from::<Response>(server_id).await;
+    });
+    // server is waiting for the second request before responding, while client is waiting for the response
+    sim.spawn(async move {
+        server.recv_event_from::<Request>(client_id).await;
+        server.recv_event_from::<Request>(client_id).await;
+        server.emit(Response {}, client_id, 1.);
+    });
+    sim.step_until_no_events();
+
+    let report = sim.detect_deadlock().unwrap();
+    assert_eq!(
+        report.cycles[0].iter().map(|wait| wait.to_string()).collect::<Vec<_>>(),
+        vec![
+            format!("client waits for {} from server", std::any::type_name::<Response>()),
+            format!("server waits for {} from client", std::any::type_name::<Request>()),
+        ]
+    );
+}
+
+#[test]
+fn test_no_deadlock() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let client_id = client.id();
+    let server = sim.create_context("server");
+    let server_id = server.id();
+
+    sim.spawn(async move {
+        client.emit(Request {}, server_id, 1.);
+        client.recv_event_from::<Response>(server_id).await;
+    });
+    sim.spawn(async move {
+        loop {
+            server.recv_event_from::<Request>(client_id).await;
+            server.emit(Response {}, client_id, 1.);
+        }
+    });
+    sim.step_until_no_events();
+
+    // server waits for the next request, but client is not waiting for server
+    assert!(sim.detect_deadlock().is_none());
+}
+
+#[test]
+fn test_wait_for_self() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+
+    sim.spawn(async move {
+        ctx.recv_event_from_self::<Response>().await;
+    });
+    sim.step_until_no_events();
+
+    let report = sim.detect_deadlock().unwrap();
+    assert_eq!(report.cycles.len(), 1);
+    assert_eq!(report.cycles[0][0].component, report.cycles[0][0].src.unwrap());
+}
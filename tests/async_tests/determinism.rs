@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::handler::EventCancellationPolicy;
+use simcore::{Event, EventHandler, Simulation};
+
+#[derive(Clone, Serialize)]
+struct A {}
+
+#[derive(Clone, Serialize)]
+struct B {}
+
+#[derive(Clone, Serialize)]
+struct C {}
+
+#[derive(Clone, Serialize)]
+struct D {}
+
+#[derive(Clone, Serialize)]
+struct E {}
+
+#[derive(Clone, Serialize)]
+struct F {}
+
+#[derive(Clone, Serialize)]
+struct G {}
+
+#[derive(Clone, Serialize)]
+struct H {}
+
+struct NoopHandler {}
+
+impl EventHandler for NoopHandler {
+    fn on(&mut self, _event: Event) {}
+}
+
+// Records its index on drop.
+struct DropGuard {
+    idx: usize,
+    log: Rc<RefCell<Vec<usize>>>,
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.log.borrow_mut().push(self.idx);
+    }
+}
+
+// Promises of the tasks are stored by event type, whose TypeId does not define a stable order.
+#[test]
+fn test_cancelled_tasks_are_dropped_in_creation_order() {
+    let mut sim = Simulation::new(123);
+    let ctx = Rc::new(sim.create_context("comp"));
+    sim.add_handler("comp", Rc::new(RefCell::new(NoopHandler {})));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    macro_rules! spawn_waiting_for {
+        ($idx:expr, $type:ty) => {{
+            let ctx = ctx.clone();
+            let guard = DropGuard {
+                idx: $idx,
+                log: log.clone(),
+            };
+            sim.spawn(async move {
+                let _guard = guard;
+                ctx.recv_event::<$type>().await;
+            });
+        }};
+    }
+    spawn_waiting_for!(0, D);
+    spawn_waiting_for!(1, B);
+    spawn_waiting_for!(2, C);
+    spawn_waiting_for!(3, A);
+    spawn_waiting_for!(4, H);
+    spawn_waiting_for!(5, F);
+    spawn_waiting_for!(6, E);
+    spawn_waiting_for!(7, G);
+    sim.step_until_no_events();
+    assert!(log.borrow().is_empty());
+
+    sim.remove_handler("comp", EventCancellationPolicy::None);
+    assert_eq!(*log.borrow(), (0..8).collect::<Vec<_>>());
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::async_mode::AwaitResult;
+use simcore::handler::DispatchPrecedence;
+use simcore::{Event, EventCancellationPolicy, EventHandler, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    seq: u32,
+}
+
+#[derive(Default)]
+struct Component {
+    handled: Vec<u32>,
+}
+
+impl EventHandler for Component {
+    fn on(&mut self, event: Event) {
+        self.handled.push(event.data.downcast_ref::<Message>().unwrap().seq);
+    }
+}
+
+struct StaticComponent {
+    handled: RefCell<Vec<u32>>,
+}
+
+impl StaticEventHandler for StaticComponent {
+    fn on(self: Rc<Self>, event: Event) {
+        self.handled
+            .borrow_mut()
+            .push(event.data.downcast_ref::<Message>().unwrap().seq);
+    }
+}
+
+// Sends two messages to the component with handler, the first one is awaited by the task until timeout.
+fn run(sim: &mut Simulation, name: &str) -> (Vec<u32>, Vec<u32>) {
+    let ctx = sim.create_context(name);
+    let client = sim.create_context(format!("{}-client", name));
+    let handler = Rc::new(RefCell::new(Component::default()));
+    sim.add_handler(name, handler.clone());
+    let awaited = Rc::new(RefCell::new(Vec::new()));
+    spawn_waiter(sim, ctx, &client, awaited.clone());
+    sim.step_until_no_events();
+    let handled = handler.borrow().handled.clone();
+    let awaited = awaited.borrow().clone();
+    (handled, awaited)
+}
+
+fn spawn_waiter(sim: &Simulation, ctx: SimulationContext, client: &SimulationContext, awaited: Rc<RefCell<Vec<u32>>>) {
+    client.emit(Message { seq: 1 }, ctx.id(), 1.);
+    client.emit(Message { seq: 2 }, ctx.id(), 2.);
+    sim.spawn(async move {
+        if let AwaitResult::Ok(event) = ctx.recv_event::<Message>().with_timeout(1.5).await {
+            awaited.borrow_mut().push(event.data.seq);
+        }
+    });
+}
+
+#[test]
+fn test_waiters_first_by_default() {
+    let mut sim = Simulation::new(123);
+    let (handled, awaited) = run(&mut sim, "comp");
+    assert_eq!(awaited, vec![1]);
+    assert_eq!(handled, vec![2]);
+}
+
+#[test]
+fn test_handler_first() {
+    let mut sim = Simulation::new(123);
+    sim.set_dispatch_precedence(DispatchPrecedence::HandlerFirst);
+    let (handled, awaited) = run(&mut sim, "comp");
+    assert!(awaited.is_empty());
+    assert_eq!(handled, vec![1, 2]);
+}
+
+#[test]
+fn test_component_precedence_overrides_default() {
+    let mut sim = Simulation::new(123);
+    sim.set_dispatch_precedence(DispatchPrecedence::HandlerFirst);
+    sim.create_context("waiter");
+    sim.set_component_dispatch_precedence("waiter", DispatchPrecedence::WaitersFirst);
+    let (handled, awaited) = run(&mut sim, "waiter");
+    assert_eq!(awaited, vec![1]);
+    assert_eq!(handled, vec![2]);
+}
+
+#[test]
+fn test_static_handler_first() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let client = sim.create_context("client");
+    let handler = Rc::new(StaticComponent {
+        handled: RefCell::new(Vec::new()),
+    });
+    sim.add_static_handler("comp", handler.clone());
+    sim.set_component_dispatch_precedence("comp", DispatchPrecedence::HandlerFirst);
+    let awaited = Rc::new(RefCell::new(Vec::new()));
+    spawn_waiter(&sim, ctx, &client, awaited.clone());
+    sim.step_until_no_events();
+
+    assert!(awaited.borrow().is_empty());
+    assert_eq!(*handler.handled.borrow(), vec![1, 2]);
+}
+
+#[test]
+fn test_waiters_receive_events_without_handler() {
+    let mut sim = Simulation::new(123);
+    sim.set_dispatch_precedence(DispatchPrecedence::Error);
+    let ctx = sim.create_context("comp");
+    let client = sim.create_context("client");
+    let awaited = Rc::new(RefCell::new(Vec::new()));
+    spawn_waiter(&sim, ctx, &client, awaited.clone());
+    sim.step_until_no_events();
+    assert_eq!(*awaited.borrow(), vec![1]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Event 0 of type Message for component comp is awaited by async task and can be processed by handler"
+)]
+fn test_error_on_conflict() {
+    let mut sim = Simulation::new(123);
+    sim.set_dispatch_precedence(DispatchPrecedence::Error);
+    run(&mut sim, "comp");
+}
+
+#[test]
+fn test_precedence_is_reset_on_component_removal() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("old");
+    sim.set_component_dispatch_precedence("old", DispatchPrecedence::HandlerFirst);
+    sim.remove_component("old", EventCancellationPolicy::None);
+    // the new component reuses the identifier of the removed one
+    let (handled, awaited) = run(&mut sim, "new");
+    assert_eq!(awaited, vec![1]);
+    assert_eq!(handled, vec![2]);
+}
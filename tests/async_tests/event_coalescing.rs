@@ -0,0 +1,65 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{Event, Simulation, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Update {
+    value: u32,
+}
+
+struct Receiver {
+    received: RefCell<Vec<(f64, u32)>>,
+}
+
+impl StaticEventHandler for Receiver {
+    fn on(self: Rc<Self>, event: Event) {
+        let value = event.data.downcast_ref::<Update>().unwrap().value;
+        self.received.borrow_mut().push((event.time, value));
+    }
+}
+
+#[test]
+fn test_awaited_event_releases_burst() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("receiver");
+    let receiver_id = ctx.id();
+    sim.enable_event_coalescing("receiver", 0.1);
+    let sender = sim.create_context("sender");
+    let awaited = Rc::new(RefCell::new(Vec::new()));
+
+    let awaited_clone = awaited.clone();
+    sim.spawn(async move {
+        for _ in 0..3 {
+            let event = ctx.recv_event::<Update>().await;
+            awaited_clone.borrow_mut().push((ctx.time(), event.data.value));
+        }
+    });
+    sender.emit(Update { value: 1 }, receiver_id, 1.);
+    sender.emit(Update { value: 2 }, receiver_id, 1.05);
+    sender.emit(Update { value: 3 }, receiver_id, 1.06);
+    sim.step_until_no_events();
+
+    // the events merged with awaited event are delivered separately
+    assert_eq!(*awaited.borrow(), vec![(1., 1), (1.05, 2), (1.06, 3)]);
+}
+
+#[test]
+fn test_static_handler_receives_burst() {
+    let mut sim = Simulation::new(123);
+    let receiver = Rc::new(Receiver {
+        received: RefCell::new(Vec::new()),
+    });
+    let receiver_id = sim.add_static_handler("receiver", receiver.clone());
+    sim.enable_event_coalescing("receiver", 0.1);
+    let sender = sim.create_context("sender");
+    sender.emit(Update { value: 1 }, receiver_id, 1.);
+    sender.emit(Update { value: 2 }, receiver_id, 1.05);
+    sender.emit(Update { value: 3 }, receiver_id, 2.);
+    sim.step_until_no_events();
+
+    assert_eq!(*receiver.received.borrow(), vec![(1., 1), (1.05, 2), (2., 3)]);
+    assert_eq!(sim.time(), 2.);
+}
@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::{select, FutureExt};
+
+use simcore::Simulation;
+
+fn assert_times(actual: &[(&str, f64)], expected: &[(&str, f64)]) {
+    assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+    for ((name, time), (expected_name, expected_time)) in actual.iter().zip(expected) {
+        assert_eq!(name, expected_name);
+        assert!(
+            (time - expected_time).abs() < 1e-9,
+            "{} completed at {}, expected {}",
+            name,
+            time,
+            expected_time
+        );
+    }
+}
+
+#[test]
+fn test_equal_share() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("disk", 4.));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    // (name, amount, start time)
+    for (name, amount, start) in [("a", 8., 0.), ("b", 4., 0.), ("c", 6., 1.)] {
+        let ctx = sim.create_context(name);
+        let share = share.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            ctx.sleep(start).await;
+            share.process(amount).await;
+            log.borrow_mut().push((name, ctx.time()));
+        });
+    }
+
+    sim.step_until_time(0.5);
+    assert_eq!(share.active(), 2);
+    assert_eq!(share.allocated(), 4.);
+    sim.step_until_no_events();
+    // until 1: a and b get 2, remaining a 6, b 2
+    // until 2.5: a, b and c get 4/3, b completes, remaining a 4, c 4
+    // until 4.5: a and c get 2 and complete
+    assert_times(&log.borrow(), &[("b", 2.5), ("a", 4.5), ("c", 4.5)]);
+    assert_eq!(share.active(), 0);
+    assert_eq!(share.allocated(), 0.);
+}
+
+#[test]
+fn test_weights_and_max_rates() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("link", 12.));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    // (name, amount, weight, max rate)
+    let activities = [
+        ("a", 10., 1., f64::INFINITY),
+        ("b", 30., 2., f64::INFINITY),
+        ("c", 3., 5., 1.),
+    ];
+    for (name, amount, weight, max_rate) in activities {
+        let ctx = sim.create_context(name);
+        let share = share.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            share.process_weighted(amount, weight, max_rate).await;
+            log.borrow_mut().push((name, ctx.time()));
+        });
+    }
+
+    sim.step_until_no_events();
+    // c is limited by its maximum rate 1, and a and b share 11 in proportion 1:2, so a completes at 30 / 11
+    // when b has 10 remaining, then b gets 11 until c completes at 3 and the whole capacity for the remaining 7
+    assert_times(
+        &log.borrow(),
+        &[("a", 30. / 11.), ("c", 3.), ("b", 3. + 7. / 12.)],
+    );
+}
+
+#[test]
+fn test_max_rate_leaves_capacity_unused() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("link", 10.));
+    let ctx = sim.create_context("client");
+
+    let share_clone = share.clone();
+    sim.spawn(async move {
+        share_clone.process_weighted(6., 1., 2.).await;
+        assert_eq!(ctx.time(), 3.);
+    });
+
+    sim.step_until_time(1.);
+    assert_eq!(share.allocated(), 2.);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_capacity_change() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("link", 2.));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    for (name, amount) in [("a", 4.), ("b", 8.)] {
+        let ctx = sim.create_context(name);
+        let share = share.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            share.process(amount).await;
+            log.borrow_mut().push((name, ctx.time()));
+        });
+    }
+
+    let controller = sim.create_context("controller");
+    let controller_share = share.clone();
+    sim.spawn(async move {
+        controller.sleep(2.).await;
+        controller_share.set_capacity(6.);
+    });
+
+    sim.step_until_no_events();
+    // until 2: a and b get 1, remaining a 2, b 6
+    // until 8 / 3: a and b get 3, a completes, then b gets 6 for the remaining 4
+    assert_times(&log.borrow(), &[("a", 2. + 2. / 3.), ("b", 2. + 2. / 3. + 4. / 6.)]);
+    assert_eq!(share.capacity(), 6.);
+}
+
+#[test]
+fn test_cancelled_activity() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("link", 2.));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let impatient = sim.create_context("impatient");
+    let impatient_share = share.clone();
+    let impatient_log = log.clone();
+    sim.spawn(async move {
+        select! {
+            _ = impatient_share.process(100.).fuse() => {
+                impatient_log.borrow_mut().push(("impatient", impatient.time()));
+            }
+            _ = impatient.sleep(2.).fuse() => {}
+        }
+    });
+
+    let patient = sim.create_context("patient");
+    let patient_share = share.clone();
+    let patient_log = log.clone();
+    sim.spawn(async move {
+        patient_share.process(4.).await;
+        patient_log.borrow_mut().push(("patient", patient.time()));
+    });
+
+    sim.step_until_no_events();
+    // the patient activity gets 1 until 2, then 2 for the remaining 2
+    assert_times(&log.borrow(), &[("patient", 3.)]);
+    assert_eq!(share.active(), 0);
+}
+
+#[test]
+fn test_single_pending_event() {
+    let mut sim = Simulation::new(123);
+    let share = Rc::new(sim.create_fair_share("link", 100.));
+    let completed = Rc::new(RefCell::new(0));
+
+    for i in 0..100 {
+        let ctx = sim.create_context(format!("client-{}", i));
+        let share = share.clone();
+        let completed = completed.clone();
+        sim.spawn(async move {
+            share.process(1. + i as f64).await;
+            *completed.borrow_mut() += 1;
+            assert!(ctx.time() > 0.);
+        });
+    }
+
+    sim.step_until_time(1.);
+    assert_eq!(share.active(), 99);
+    assert_eq!(sim.dump_events().len(), 1);
+    sim.step_until_no_events();
+    assert_eq!(*completed.borrow(), 100);
+    // the total work is completed at full capacity
+    assert!((sim.time() - 5050. / 100.).abs() < 1e-9);
+}
+
+#[test]
+fn test_zero_amount() {
+    let mut sim = Simulation::new(123);
+    let share = sim.create_fair_share("link", 1.);
+    let ctx = sim.create_context("client");
+    sim.spawn(async move {
+        share.process(0.).await;
+        assert_eq!(ctx.time(), 0.);
+        assert_eq!(share.active(), 0);
+    });
+    sim.step_until_no_events();
+    assert_eq!(sim.event_count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Activity weight must be positive")]
+fn test_zero_weight() {
+    let mut sim = Simulation::new(123);
+    let share = sim.create_fair_share("link", 1.);
+    sim.spawn(async move {
+        share.process_weighted(1., 0., 1.).await;
+    });
+    sim.step_until_no_events();
+}
@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{Event, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Job {
+    duration: f64,
+}
+
+// Processes each job in a task sleeping for the job duration.
+struct Worker {
+    ctx: SimulationContext,
+}
+
+impl StaticEventHandler for Worker {
+    fn on(self: Rc<Self>, event: Event) {
+        let job = event.data.downcast_ref::<Job>().unwrap().clone();
+        let worker = self.clone();
+        self.ctx.spawn(async move {
+            worker.ctx.sleep(job.duration).await;
+        });
+    }
+}
+
+fn build() -> (Simulation, SimulationContext) {
+    let mut sim = Simulation::new(123);
+    let worker_ctx = sim.create_context("worker");
+    sim.add_static_handler("worker", Rc::new(Worker { ctx: worker_ctx }));
+    sim.set_component_group("worker", "workers");
+    let client = sim.create_context("client");
+    (sim, client)
+}
+
+#[test]
+fn test_pending_timers() {
+    let (mut sim, client) = build();
+    let worker_id = sim.lookup_id("worker");
+    client.emit(Job { duration: 5. }, worker_id, 1.);
+    client.emit(Job { duration: 1. }, worker_id, 2.);
+    client.emit_self(Job { duration: 0. }, 100.);
+    assert!(sim.step_until_group_idle("workers"));
+    assert_eq!(sim.time(), 6.);
+}
+
+#[test]
+fn test_queued_tasks() {
+    let (mut sim, client) = build();
+    sim.set_task_limit("worker", 1);
+    let worker_id = sim.lookup_id("worker");
+    for _ in 0..3 {
+        client.emit(Job { duration: 2. }, worker_id, 1.);
+    }
+    assert!(sim.step_until_group_idle("workers"));
+    assert_eq!(sim.time(), 7.);
+}
+
+#[test]
+fn test_spawned_tasks() {
+    let (mut sim, client) = build();
+    let worker_ctx = sim.create_context("worker");
+    client.emit_self(Job { duration: 0. }, 50.);
+    sim.spawn(async move {
+        worker_ctx.sleep(3.).await;
+        worker_ctx.sleep(4.).await;
+    });
+    assert!(sim.step_until_group_idle("workers"));
+    assert_eq!(sim.time(), 7.);
+}
@@ -0,0 +1,171 @@
+use serde::Serialize;
+
+use simcore::async_mode::{composite_key, EventKey, KeyedEvent};
+use simcore::Simulation;
+
+#[derive(Clone, Serialize, EventKey)]
+struct Reply {
+    #[event_key]
+    request_id: u32,
+    value: String,
+}
+
+#[derive(Clone, Serialize, EventKey)]
+struct Ack(String, #[event_key] usize);
+
+#[derive(Clone, Serialize, EventKey)]
+struct Segment {
+    #[event_key]
+    conn_id: u32,
+    len: u64,
+    #[event_key]
+    seq_no: u64,
+}
+
+#[test]
+fn test_derived_event_key() {
+    let reply = Reply {
+        request_id: 7,
+        value: "foo".to_owned(),
+    };
+    assert_eq!(reply.event_key(), 7);
+    assert_eq!(Ack("bar".to_owned(), 3).event_key(), 3 as EventKey);
+    let segment = Segment {
+        conn_id: 1,
+        len: 100,
+        seq_no: 2,
+    };
+    assert_eq!(segment.event_key(), composite_key(&(1u32, 2u64)));
+    assert_ne!(segment.event_key(), composite_key(&(2u32, 1u64)));
+}
+
+#[test]
+fn test_recv_keyed_events() {
+    let mut sim = Simulation::new(123);
+    let client_ctx = sim.create_context("client");
+    let client_id = client_ctx.id();
+    let server_ctx = sim.create_context("server");
+    sim.register_keyed_event::<Reply>();
+    client_ctx.register_keyed_event::<Ack>();
+
+    sim.spawn(async move {
+        let (ack, reply) = futures::join!(
+            client_ctx.recv_event_by_key::<Ack>(1),
+            client_ctx.recv_event_by_key::<Reply>(2)
+        );
+        assert_eq!(ack.data.0, "ack");
+        assert_eq!(ack.time, 5.);
+        assert_eq!(reply.data.value, "second");
+        assert_eq!(reply.time, 20.);
+    });
+
+    server_ctx.emit(Ack("ack".to_owned(), 1), client_id, 5.);
+    server_ctx.emit(
+        Reply {
+            request_id: 1,
+            value: "first".to_owned(),
+        },
+        client_id,
+        10.,
+    );
+    server_ctx.emit(
+        Reply {
+            request_id: 2,
+            value: "second".to_owned(),
+        },
+        client_id,
+        20.,
+    );
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_recv_composite_keyed_events() {
+    let mut sim = Simulation::new(123);
+    let client_ctx = sim.create_context("client");
+    let client_id = client_ctx.id();
+    let server_ctx = sim.create_context("server");
+    sim.register_keyed_event::<Segment>();
+    client_ctx.register_composite_key_getter_for::<Reply, _>(|reply| (reply.request_id, reply.value.clone()));
+
+    sim.spawn(async move {
+        let (segment, reply) = futures::join!(
+            client_ctx.recv_event_by_key::<Segment>(composite_key(&(2u32, 1u64))),
+            client_ctx.recv_event_by_key::<Reply>(composite_key(&(1u32, "second")))
+        );
+        assert_eq!(segment.data.len, 20);
+        assert_eq!(segment.time, 2.);
+        assert_eq!(reply.time, 4.);
+    });
+
+    // the values would collide if the identifiers were packed by adding them
+    for (time, conn_id, seq_no) in [(1., 1, 2), (2., 2, 1)] {
+        let segment = Segment {
+            conn_id,
+            len: time as u64 * 10,
+            seq_no,
+        };
+        server_ctx.emit(segment, client_id, time);
+    }
+    for (time, value) in [(3., "first"), (4., "second")] {
+        let reply = Reply {
+            request_id: 1,
+            value: value.to_owned(),
+        };
+        server_ctx.emit(reply, client_id, time);
+    }
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+}
+
+#[test]
+fn test_recv_keyed_events_without_registration() {
+    let mut sim = Simulation::new(123);
+    let client_ctx = sim.create_context("client");
+    let client_id = client_ctx.id();
+    let server_ctx = sim.create_context("server");
+    let server_id = server_ctx.id();
+
+    sim.spawn(async move {
+        let reply = client_ctx.recv_keyed_event::<Reply>(2).await;
+        assert_eq!(reply.data.value, "second");
+        let ack = client_ctx.recv_keyed_event_from::<Ack>(server_id, 3).await;
+        assert_eq!(ack.time, 30.);
+        // the registered key getter is used by other receive methods
+        let reply = client_ctx.recv_event_by_key::<Reply>(1).await;
+        assert_eq!(reply.data.value, "first");
+    });
+
+    server_ctx.emit(Ack("ack".to_owned(), 3), client_id, 30.);
+    for (time, request_id, value) in [(20., 2, "second"), (40., 1, "first")] {
+        let reply = Reply {
+            request_id,
+            value: value.to_owned(),
+        };
+        server_ctx.emit(reply, client_id, time);
+    }
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 40.);
+}
+
+#[test]
+fn test_recv_keyed_event_keeps_registered_key_getter() {
+    let mut sim = Simulation::new(123);
+    let client_ctx = sim.create_context("client");
+    let client_id = client_ctx.id();
+    let server_ctx = sim.create_context("server");
+    client_ctx.register_component_key_getter_for::<Reply>(|reply| reply.value.len() as EventKey);
+
+    sim.spawn(async move {
+        let reply = client_ctx.recv_keyed_event::<Reply>(5).await;
+        assert_eq!(reply.data.value, "first");
+    });
+
+    // the reply is matched by the length of its value instead of the request id
+    let reply = Reply {
+        request_id: 7,
+        value: "first".to_owned(),
+    };
+    server_ctx.emit(reply, client_id, 10.);
+    sim.step_until_no_events();
+}
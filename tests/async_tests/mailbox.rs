@@ -0,0 +1,266 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::{select, FutureExt};
+use serde::Serialize;
+
+use simcore::{cast, Event, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+struct Server {
+    ctx: SimulationContext,
+    pings: RefCell<u32>,
+    processed: RefCell<Vec<(u32, f64)>>,
+}
+
+impl Server {
+    async fn actor_loop(self: Rc<Self>, requests: u32) {
+        let mailbox = self.ctx.create_mailbox();
+        mailbox.accept::<Request>();
+        for _ in 0..requests {
+            let event = mailbox.next().await;
+            cast!(match event.data {
+                Request { id } => {
+                    self.ctx.sleep(2.).await;
+                    self.processed.borrow_mut().push((id, self.ctx.time()));
+                }
+            });
+        }
+    }
+}
+
+impl StaticEventHandler for Server {
+    fn on(self: Rc<Self>, event: Event) {
+        cast!(match event.data {
+            Ping {} => {
+                *self.pings.borrow_mut() += 1;
+            }
+            Request { .. } => {
+                panic!("Request must be delivered to mailbox");
+            }
+        })
+    }
+}
+
+fn build() -> (Simulation, SimulationContext, Rc<Server>) {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = Rc::new(Server {
+        ctx: sim.create_context("server"),
+        pings: RefCell::new(0),
+        processed: RefCell::new(Vec::new()),
+    });
+    sim.add_static_handler("server", server.clone());
+    (sim, client, server)
+}
+
+#[test]
+fn test_processing_backlog() {
+    let (mut sim, client, server) = build();
+    let server_id = server.ctx.id();
+    for id in 0..3 {
+        client.emit(Request { id }, server_id, 1.);
+        client.emit(Ping {}, server_id, 1.);
+    }
+    client.emit(Request { id: 3 }, server_id, 10.);
+    server.ctx.spawn(server.clone().actor_loop(4));
+    sim.step_until_no_events();
+
+    assert_eq!(*server.processed.borrow(), vec![(0, 3.), (1, 5.), (2, 7.), (3, 12.)]);
+    // events of other types are delivered to the handler
+    assert_eq!(*server.pings.borrow(), 3);
+    assert_eq!(sim.time(), 12.);
+}
+
+#[test]
+fn test_mailbox_stats() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let server_id = server.id();
+    let mailbox = Rc::new(server.create_mailbox());
+    mailbox.accept::<Request>();
+    client.emit(Request { id: 0 }, server_id, 1.);
+    client.emit(Request { id: 1 }, server_id, 1.);
+    client.emit(Request { id: 2 }, server_id, 3.);
+
+    sim.step_until_time(2.);
+    assert_eq!(mailbox.len(), 2);
+    assert_eq!(mailbox.try_next().unwrap().time, 1.);
+    sim.step_until_time(4.);
+    assert_eq!(mailbox.len(), 2);
+    assert!(mailbox.try_next().is_some());
+    assert!(mailbox.try_next().is_some());
+    assert!(mailbox.try_next().is_none());
+    assert!(mailbox.is_empty());
+
+    let stats = mailbox.stats();
+    assert_eq!(stats.received, 3);
+    assert_eq!(stats.taken, 3);
+    assert_eq!(stats.max_length, 2);
+    // waits are 1, 3 and 1
+    assert_eq!(stats.mean_wait_time, 5. / 3.);
+    // length is 0 in [0, 1), 2 in [1, 2), 1 in [2, 3) and 2 in [3, 4)
+    assert_eq!(stats.mean_length, 5. / 4.);
+    // at time 4 the mailbox is empty
+    assert_eq!(sim.metrics().get("server", "mailbox_length").unwrap().as_f64(), 5. / 4.);
+}
+
+#[test]
+fn test_next_with_timeout() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let server_id = server.id();
+    let results = Rc::new(RefCell::new(Vec::new()));
+    client.emit(Request { id: 0 }, server_id, 5.);
+    client.emit(Request { id: 1 }, server_id, 6.);
+
+    let results_clone = results.clone();
+    sim.spawn(async move {
+        let mailbox = server.create_mailbox();
+        mailbox.accept::<Request>();
+        loop {
+            let timed_out = select! {
+                _ = mailbox.next().fuse() => false,
+                _ = server.sleep(2.).fuse() => true,
+            };
+            results_clone.borrow_mut().push((server.time(), timed_out));
+            if !timed_out && server.time() >= 6. {
+                break;
+            }
+        }
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*results.borrow(), vec![(2., true), (4., true), (5., false), (6., false)]);
+}
+
+#[test]
+fn test_dropped_mailbox() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let server_id = server.id();
+    let received = Rc::new(RefCell::new(Vec::new()));
+    client.emit(Request { id: 0 }, server_id, 1.);
+    client.emit(Request { id: 1 }, server_id, 2.);
+
+    let received_clone = received.clone();
+    sim.spawn(async move {
+        {
+            let mailbox = server.create_mailbox();
+            mailbox.accept::<Request>();
+            let event = mailbox.next().await;
+            received_clone.borrow_mut().push(("mailbox", event.time));
+        }
+        let event = server.recv_event::<Request>().await;
+        received_clone.borrow_mut().push(("recv_event", event.time));
+        // the mailbox can be created again after the previous one is dropped
+        server.create_mailbox();
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*received.borrow(), vec![("mailbox", 1.), ("recv_event", 2.)]);
+}
+
+#[test]
+#[should_panic(expected = "Component server already has a mailbox")]
+fn test_second_mailbox() {
+    let mut sim = Simulation::new(123);
+    let server = sim.create_context("server");
+    let _mailbox = server.create_mailbox();
+    server.create_mailbox();
+}
+
+#[test]
+#[should_panic(expected = "Mailbox of component server is already awaited by another task")]
+fn test_concurrent_next() {
+    let mut sim = Simulation::new(123);
+    let server = Rc::new(sim.create_context("server"));
+    let mailbox = Rc::new(server.create_mailbox());
+    for _ in 0..2 {
+        let mailbox = mailbox.clone();
+        sim.spawn(async move {
+            mailbox.next().await;
+        });
+    }
+    sim.step_until_no_events();
+}
+
+#[derive(Clone, Serialize)]
+struct Job {
+    class: i64,
+}
+
+// Serves jobs of two classes arriving every time unit with the service time 1.
+fn serve_jobs(aging: Option<f64>) -> Vec<simcore::async_mode::MailboxClassStats> {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = Rc::new(sim.create_context("server"));
+    let server_id = server.id();
+    let mailbox = Rc::new(server.create_mailbox());
+    mailbox.accept_with_priority_fn(|job: &Job| job.class);
+    mailbox.set_starvation_threshold(5.);
+    if let Some(rate) = aging {
+        mailbox.set_aging(rate);
+    }
+    for i in 0..20 {
+        client.emit(Job { class: 0 }, server_id, i as f64);
+        client.emit(Job { class: 1 }, server_id, i as f64);
+    }
+
+    let mailbox_clone = mailbox.clone();
+    sim.spawn(async move {
+        loop {
+            mailbox_clone.next().await;
+            server.sleep(1.).await;
+        }
+    });
+    sim.step_until_time(30.);
+    mailbox.class_stats()
+}
+
+#[test]
+fn test_priority_starvation() {
+    let stats = serve_jobs(None);
+    assert_eq!(stats.len(), 2);
+    let (high, low) = (&stats[0], &stats[1]);
+    assert_eq!((high.priority, low.priority), (0, 1));
+    assert_eq!(high.received, 20);
+    assert_eq!(high.taken, 20);
+    assert_eq!(high.max_wait_time, 0.);
+    assert_eq!(high.starved, 0);
+    // the low-priority jobs are served only after all high-priority jobs
+    assert_eq!(low.taken, 11);
+    assert_eq!(low.length, 9);
+    assert_eq!(low.max_wait_time, 20.);
+    assert_eq!(low.starved, 11);
+    assert_eq!(low.oldest_wait_time, 19.);
+}
+
+#[test]
+fn test_priority_aging() {
+    let stats = serve_jobs(Some(0.5));
+    let (high, low) = (&stats[0], &stats[1]);
+    // the low-priority jobs waiting for 2 time units overtake the new high-priority jobs
+    assert!(high.max_wait_time > 0.);
+    assert!(low.taken > 11);
+    assert!(low.max_wait_time < 20.);
+    assert_eq!(high.taken + low.taken, 31);
+}
+
+#[test]
+#[should_panic(expected = "Aging rate must be non-negative, got -1")]
+fn test_negative_aging() {
+    let mut sim = Simulation::new(123);
+    let server = sim.create_context("server");
+    server.create_mailbox().set_aging(-1.);
+}
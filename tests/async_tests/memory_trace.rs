@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Request {}
+
+#[derive(Clone, Serialize)]
+struct Reply {}
+
+#[test]
+fn test_events_emitted_by_tasks_are_attributed_to_resuming_event() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    let server_ctx = sim.create_context("server");
+    let client_ctx = sim.create_context("client");
+    let client_id = client_ctx.id();
+
+    sim.spawn(async move {
+        let request = server_ctx.recv_event::<Request>().await;
+        server_ctx.emit(Reply {}, request.src, 1.);
+        // the task is resumed by the timer, so the event has no parent
+        server_ctx.sleep(1.).await;
+        server_ctx.emit(Reply {}, request.src, 1.);
+    });
+    let request = client_ctx.emit(Request {}, sim.lookup_id("server"), 1.);
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    let replies: Vec<_> = trace
+        .query()
+        .dst(client_id)
+        .records()
+        .iter()
+        .map(|record| record.parent)
+        .collect();
+    assert_eq!(replies, vec![Some(request), None]);
+}
@@ -1,7 +1,42 @@
+mod branching;
+mod cancellation;
+mod component_key_getters;
 mod conflict_waiting;
+mod deadlock;
+mod event_coalescing;
+mod fair_share;
+mod determinism;
+mod dispatch_precedence;
 mod future_drop;
+mod group_idle;
+#[cfg(feature = "derive")]
+mod keyed_event;
+mod mailbox;
+mod memory_trace;
+mod named_timers;
+#[cfg(feature = "thread")]
+mod parallel;
+mod process;
 mod queue;
+mod recv_any;
 mod recv_event;
 mod recv_event_by_key;
+mod recv_event_where;
+mod recv_events_by_keys;
+mod request;
+mod resource;
+mod retry;
+#[cfg(feature = "derive")]
+mod rpc;
+mod run_result;
 mod select;
 mod sleep;
+mod step_observer;
+mod stop_conditions;
+mod task_limit;
+mod time_advance;
+mod time_scale;
+mod time_tick;
+mod token_bucket;
+mod wait_stats;
+mod work;
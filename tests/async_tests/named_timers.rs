@@ -0,0 +1,40 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use simcore::async_mode::AwaitResult;
+use simcore::Simulation;
+
+#[test]
+fn test_wait_timer() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let task_log = log.clone();
+    sim.spawn(async move {
+        ctx.set_timer("a", 5.);
+        ctx.set_timer("b", 2.);
+        let e = ctx.wait_timer("a").await;
+        task_log.borrow_mut().push((e.data.name, ctx.time()));
+
+        // only the replacing timer fires
+        ctx.set_timer("c", 10.);
+        ctx.set_timer("c", 1.);
+        let e = ctx.wait_timer("c").await;
+        task_log.borrow_mut().push((e.data.name, ctx.time()));
+
+        // the timer is cancelled, so waiting times out
+        ctx.set_timer("d", 1.);
+        ctx.cancel_timer("d");
+        let res = ctx.wait_timer("d").with_timeout(3.).await;
+        assert!(matches!(res, AwaitResult::Timeout { .. }));
+        task_log.borrow_mut().push(("timeout".to_owned(), ctx.time()));
+    });
+
+    sim.step_until_no_events();
+    // timer b is not awaited and is delivered to the component without handler
+    assert_eq!(
+        *log.borrow(),
+        vec![("a".to_owned(), 5.), ("c".to_owned(), 6.), ("timeout".to_owned(), 9.)]
+    );
+}
@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::parallel::ParallelSimulation;
+use simcore::{cast, Event, EventHandler, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Request {
+    id: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Response {
+    id: u32,
+}
+
+struct Server {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.ctx.emit(Response { id }, event.src, 1.5);
+            }
+        })
+    }
+}
+
+#[test]
+fn test_remote_requests_from_process() {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.register_event::<Request>();
+    sim.register_event::<Response>();
+    let client = sim.add_partition(&["client"], |sim| {
+        let ctx = sim.create_context("client");
+        let server = sim.lookup_id("server");
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_ = log.clone();
+        sim.spawn(async move {
+            for id in 0..3 {
+                ctx.emit(Request { id }, server, 1.);
+                let response = ctx.recv_event::<Response>().await;
+                assert_eq!(response.data.id, id);
+                log_.borrow_mut().push(ctx.time());
+                ctx.sleep(0.5).await;
+            }
+        });
+        log
+    });
+    sim.add_partition(&["server"], |sim| {
+        let ctx = sim.create_context("server");
+        sim.add_handler("server", Rc::new(RefCell::new(Server { ctx })));
+        Rc::new(RefCell::new(Vec::new()))
+    });
+
+    sim.step_until_time(100.);
+    let log = sim.execute(client, |_, log| log.borrow().clone());
+    assert_eq!(log, vec![2.5, 5.5, 8.5]);
+}
@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use simcore::async_mode::{Interrupted, Process};
+use simcore::{EventCancellationPolicy, Simulation};
+
+#[test]
+fn test_processes_spawn_processes() {
+    let mut sim = Simulation::new(123);
+    let servers = Rc::new(sim.create_resource("servers", 1));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let source_log = log.clone();
+    sim.spawn_process("source", move |p| async move {
+        for i in 0..3 {
+            let servers = servers.clone();
+            let log = source_log.clone();
+            p.spawn_process(format!("customer-{}", i), move |c| async move {
+                servers.acquire(1).await;
+                c.hold(3.).await.unwrap();
+                servers.release(1);
+                log.borrow_mut().push((c.name().to_owned(), c.time()));
+            });
+            p.hold(1.).await.unwrap();
+        }
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            ("customer-0".to_owned(), 3.),
+            ("customer-1".to_owned(), 6.),
+            ("customer-2".to_owned(), 9.)
+        ]
+    );
+    assert_eq!(sim.lookup_name(4), "customer-2");
+}
+
+#[test]
+fn test_interrupt_running_process() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+
+    let process = sim.spawn_process("process", move |p| async move {
+        // interrupt is received while awaiting other future
+        ctx.sleep(5.).await;
+        assert_eq!(p.hold(10.).await, Err(Interrupted));
+        assert_eq!(p.time(), 5.);
+        assert_eq!(p.hold(10.).await, Ok(()));
+        assert_eq!(p.time(), 15.);
+    });
+    process.interrupt();
+    // activation of process which is not passive has no effect
+    process.activate();
+
+    sim.step_until_no_events();
+    assert!(process.is_finished());
+    assert_eq!(sim.time(), 15.);
+}
+
+#[test]
+fn test_signal_at_hold_completion_time() {
+    let mut sim = Simulation::new(123);
+    let holder_cell = Rc::new(RefCell::new(None));
+
+    // interrupter is spawned first, so its hold is completed before the hold of holder with the same end time
+    let interrupter_holder = holder_cell.clone();
+    sim.spawn_process("interrupter", move |p| async move {
+        p.hold(5.).await.unwrap();
+        let holder: Process = interrupter_holder.borrow().clone().unwrap();
+        holder.interrupt();
+        // repeated signals are ignored
+        holder.interrupt();
+        p.hold(0.).await.unwrap();
+        assert!(holder.is_passive());
+        holder.activate();
+        holder.activate();
+    });
+
+    let holder = sim.spawn_process("holder", |p| async move {
+        assert_eq!(p.hold(5.).await, Err(Interrupted));
+        assert_eq!(p.time(), 5.);
+        assert_eq!(p.passivate().await, Ok(()));
+        assert_eq!(p.time(), 5.);
+        assert_eq!(p.hold(1.).await, Ok(()));
+    });
+    *holder_cell.borrow_mut() = Some(holder.clone());
+
+    sim.step_until_no_events();
+    assert!(holder.is_finished());
+    assert_eq!(sim.time(), 6.);
+}
+
+#[test]
+fn test_remove_spawned_process_component() {
+    let mut sim = Simulation::new(123);
+    sim.spawn_process("parent", move |p| async move {
+        p.spawn_process("child", |c| async move {
+            c.hold(1.).await.unwrap();
+        });
+    });
+    sim.step_until_no_events();
+    assert_eq!(sim.lookup_id("child"), 1);
+
+    // the child is registered by the parent task, so it is not known to the simulation before
+    sim.enable_event_batching("child");
+    sim.remove_component("child", EventCancellationPolicy::All);
+    // the identifier of removed component is reused
+    let ctx = sim.create_context("other");
+    assert_eq!(ctx.id(), 1);
+    assert_eq!(sim.lookup_name(1), "other");
+}
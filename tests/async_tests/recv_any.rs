@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, recv_any, Event, Simulation, SimulationContext, StaticEventHandler, TypedEvent};
+
+#[derive(Clone, Serialize)]
+struct Ack {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Nack {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Close {}
+
+enum Reply {
+    Ack(TypedEvent<Ack>),
+    Nack(TypedEvent<Nack>),
+    Close(TypedEvent<Close>),
+}
+
+struct Component {
+    ctx: SimulationContext,
+    handled: RefCell<Vec<String>>,
+}
+
+impl StaticEventHandler for Component {
+    fn on(self: Rc<Self>, event: Event) {
+        cast!(match event.data {
+            Ack { seq } => {
+                self.handled.borrow_mut().push(format!("ack {}", seq));
+            }
+            Nack { seq } => {
+                self.handled.borrow_mut().push(format!("nack {}", seq));
+            }
+        })
+    }
+}
+
+fn describe(reply: &Reply) -> String {
+    match reply {
+        Reply::Ack(e) => format!("ack {}", e.data.seq),
+        Reply::Nack(e) => format!("nack {}", e.data.seq),
+        Reply::Close(e) => format!("close {}", e.src),
+    }
+}
+
+#[test]
+fn test_recv_any_types() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let ctx = sim.create_context("comp");
+    let ctx_id = ctx.id();
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let received_clone = received.clone();
+    sim.spawn(async move {
+        loop {
+            let reply = recv_any!(ctx, Reply { Ack(Ack), Nack(Nack), Close(Close) }).await;
+            received_clone.borrow_mut().push((describe(&reply), ctx.time()));
+            if let Reply::Close(_) = reply {
+                break;
+            }
+        }
+    });
+
+    sender.emit(Nack { seq: 1 }, ctx_id, 1.);
+    sender.emit(Ack { seq: 2 }, ctx_id, 2.);
+    sender.emit(Close {}, ctx_id, 3.);
+    sender.emit(Ack { seq: 3 }, ctx_id, 4.);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        *received.borrow(),
+        vec![
+            ("nack 1".to_owned(), 1.),
+            ("ack 2".to_owned(), 2.),
+            ("close 0".to_owned(), 3.)
+        ]
+    );
+}
+
+#[test]
+fn test_other_types_go_to_handler() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let comp = Rc::new(Component {
+        ctx: sim.create_context("comp"),
+        handled: RefCell::new(Vec::new()),
+    });
+    let comp_id = sim.add_static_handler("comp", comp.clone());
+
+    let comp_clone = comp.clone();
+    sim.spawn(async move {
+        let reply = recv_any!(comp_clone.ctx, Reply { Nack(Nack), Close(Close) }).await;
+        assert_eq!(describe(&reply), "nack 2");
+    });
+
+    sender.emit(Ack { seq: 1 }, comp_id, 1.);
+    sender.emit(Nack { seq: 2 }, comp_id, 2.);
+    // waiting for the remaining types is cancelled after receiving the first event
+    sender.emit(Nack { seq: 3 }, comp_id, 3.);
+    sim.step_until_no_events();
+
+    assert_eq!(*comp.handled.borrow(), vec!["ack 1", "nack 3"]);
+}
+
+#[test]
+fn test_events_received_at_once() {
+    for (ack_time, nack_time, expected, handled) in [
+        (2., 1., "nack 2", "ack 1"),
+        (1., 2., "ack 1", "nack 2"),
+        // same time events are ordered by id
+        (1., 1., "ack 1", "nack 2"),
+    ] {
+        let mut sim = Simulation::new(123);
+        let sender = sim.create_context("sender");
+        let comp = Rc::new(Component {
+            ctx: sim.create_context("comp"),
+            handled: RefCell::new(Vec::new()),
+        });
+        let comp_id = sim.add_static_handler("comp", comp.clone());
+
+        let received = Rc::new(RefCell::new(None));
+        let received_clone = received.clone();
+        let comp_clone = comp.clone();
+        sim.spawn(async move {
+            let ctx = &comp_clone.ctx;
+            let future = recv_any!(ctx, Reply { Nack(Nack), Ack(Ack) });
+            // both events are received before the future is polled
+            ctx.sleep(10.).await;
+            let reply = future.await;
+            *received_clone.borrow_mut() = Some(describe(&reply));
+        });
+
+        sender.emit(Ack { seq: 1 }, comp_id, ack_time);
+        sender.emit(Nack { seq: 2 }, comp_id, nack_time);
+        sim.step_until_no_events();
+
+        assert_eq!(received.borrow().as_deref(), Some(expected));
+        // the other event is returned to the pending events and delivered to the handler
+        assert_eq!(*comp.handled.borrow(), vec![handled]);
+        assert_eq!(sim.time(), 10.);
+    }
+}
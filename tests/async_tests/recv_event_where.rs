@@ -0,0 +1,201 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::async_mode::AwaitResult;
+use simcore::{cast, Event, EventCancellationPolicy, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    payload: u32,
+}
+
+struct Component {
+    ctx: SimulationContext,
+    handled: RefCell<Vec<u32>>,
+}
+
+impl Component {
+    fn new(ctx: SimulationContext) -> Self {
+        Self {
+            ctx,
+            handled: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl StaticEventHandler for Component {
+    fn on(self: Rc<Self>, event: Event) {
+        cast!(match event.data {
+            Message { payload } => {
+                self.handled.borrow_mut().push(payload);
+            }
+        })
+    }
+}
+
+#[test]
+fn test_non_matching_events_go_to_handler() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let sender_id = sender.id();
+    let other = sim.create_context("other");
+    let comp = Rc::new(Component::new(sim.create_context("comp")));
+    let comp_id = sim.add_static_handler("comp", comp.clone());
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let received_clone = received.clone();
+    let comp_clone = comp.clone();
+    sim.spawn(async move {
+        let e = comp_clone
+            .ctx
+            .recv_event_where::<Message>(move |e| e.src == sender_id && e.data.payload % 2 == 0)
+            .await;
+        received_clone.borrow_mut().push((e.data.payload, comp_clone.ctx.time()));
+    });
+
+    sender.emit(Message { payload: 1 }, comp_id, 1.);
+    other.emit(Message { payload: 2 }, comp_id, 2.);
+    sender.emit(Message { payload: 4 }, comp_id, 3.);
+    sender.emit(Message { payload: 6 }, comp_id, 4.);
+    sim.step_until_no_events();
+
+    assert_eq!(*received.borrow(), vec![(4, 3.)]);
+    assert_eq!(*comp.handled.borrow(), vec![1, 2, 6]);
+}
+
+#[test]
+fn test_predicate_with_timeout() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let comp = Rc::new(Component::new(sim.create_context("comp")));
+    let comp_id = sim.add_static_handler("comp", comp.clone());
+
+    let comp_clone = comp.clone();
+    sim.spawn(async move {
+        let res = comp_clone
+            .ctx
+            .recv_event_where::<Message>(|e| e.data.payload > 10)
+            .with_timeout(5.)
+            .await;
+        assert!(matches!(res, AwaitResult::Timeout { .. }));
+        assert_eq!(comp_clone.ctx.time(), 5.);
+
+        let res = comp_clone
+            .ctx
+            .recv_event_where::<Message>(|e| e.time > 7.)
+            .with_timeout(5.)
+            .await;
+        match res {
+            AwaitResult::Ok(e) => assert_eq!(e.data.payload, 3),
+            AwaitResult::Timeout { .. } => panic!("Unexpected timeout"),
+        }
+        assert_eq!(comp_clone.ctx.time(), 8.);
+    });
+
+    sender.emit(Message { payload: 1 }, comp_id, 2.);
+    sender.emit(Message { payload: 20 }, comp_id, 6.);
+    sender.emit(Message { payload: 3 }, comp_id, 8.);
+    sim.step_until_no_events();
+
+    // the event emitted after the timeout is not received by the expired future
+    assert_eq!(*comp.handled.borrow(), vec![1, 20]);
+}
+
+#[test]
+fn test_multiple_waiters_in_creation_order() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let ctx = Rc::new(sim.create_context("comp"));
+    let ctx_id = ctx.id();
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    for (task, threshold) in [(0, 5), (1, 0), (2, 5)] {
+        let ctx = ctx.clone();
+        let received = received.clone();
+        sim.spawn(async move {
+            let e = ctx.recv_event_where::<Message>(move |e| e.data.payload > threshold).await;
+            received.borrow_mut().push((task, e.data.payload));
+        });
+    }
+
+    sender.emit(Message { payload: 10 }, ctx_id, 1.);
+    sender.emit(Message { payload: 1 }, ctx_id, 2.);
+    sender.emit(Message { payload: 10 }, ctx_id, 3.);
+    sim.step_until_no_events();
+
+    assert_eq!(*received.borrow(), vec![(0, 10), (1, 1), (2, 10)]);
+}
+
+#[test]
+fn test_plain_receive_takes_precedence() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let ctx = Rc::new(sim.create_context("comp"));
+    let ctx_id = ctx.id();
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let ctx_clone = ctx.clone();
+    let received_clone = received.clone();
+    sim.spawn(async move {
+        let e = ctx_clone.recv_event_where::<Message>(|_| true).await;
+        received_clone.borrow_mut().push(("where", e.data.payload));
+    });
+    let received_clone = received.clone();
+    sim.spawn(async move {
+        let e = ctx.recv_event::<Message>().await;
+        received_clone.borrow_mut().push(("plain", e.data.payload));
+    });
+
+    sender.emit(Message { payload: 1 }, ctx_id, 1.);
+    sender.emit(Message { payload: 2 }, ctx_id, 2.);
+    sim.step_until_no_events();
+
+    assert_eq!(*received.borrow(), vec![("plain", 1), ("where", 2)]);
+}
+
+#[test]
+#[should_panic(expected = "already mutably borrowed")]
+fn test_predicate_cannot_use_context() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let ctx = Rc::new(sim.create_context("comp"));
+    let ctx_id = ctx.id();
+
+    let predicate_ctx = ctx.clone();
+    sim.spawn(async move {
+        ctx.recv_event_where::<Message>(move |e| e.time >= predicate_ctx.time()).await;
+    });
+
+    sender.emit(Message { payload: 1 }, ctx_id, 1.);
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_predicate_futures_drop_on_remove_handler() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let comp = Rc::new(Component::new(sim.create_context("comp")));
+    let comp_id = sim.add_static_handler("comp", comp.clone());
+
+    let rc = Rc::new(());
+    let rc_clone = rc.clone();
+    let comp_clone = comp.clone();
+    comp.ctx.spawn(async move {
+        let _rc = rc_clone;
+        comp_clone.ctx.recv_event_where::<Message>(|e| e.data.payload > 10).await;
+        panic!("Future must be dropped");
+    });
+    sim.step_until_no_events();
+    assert_eq!(Rc::strong_count(&rc), 2);
+
+    sim.remove_handler("comp", EventCancellationPolicy::None);
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    sim.add_static_handler("comp", comp.clone());
+    sender.emit(Message { payload: 20 }, comp_id, 1.);
+    sim.step_until_no_events();
+
+    assert_eq!(*comp.handled.borrow(), vec![20]);
+}
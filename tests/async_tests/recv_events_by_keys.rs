@@ -0,0 +1,74 @@
+use serde::Serialize;
+
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Reply {
+    key: u64,
+}
+
+#[test]
+fn test_recv_events_by_keys_quorum_releases_remaining_keys() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let comp_id = ctx.id();
+    let sender = sim.create_context("sender");
+    sim.register_key_getter_for::<Reply>(|reply| reply.key);
+
+    sim.spawn(async move {
+        let replies = ctx.recv_events_by_keys_quorum::<Reply>(0..4, 2).await;
+        let keys: Vec<_> = replies.iter().map(|e| e.data.key).collect();
+        assert_eq!(keys, vec![2, 0]);
+        assert_eq!(ctx.time(), 2.);
+        // the remaining keys can be awaited again
+        let replies = ctx.recv_events_by_keys::<Reply>([3, 1]).await;
+        let times: Vec<_> = replies.iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![4., 3.]);
+        // empty set of keys is completed immediately
+        assert!(ctx.recv_events_by_keys::<Reply>([]).await.is_empty());
+        assert_eq!(ctx.time(), 4.);
+    });
+
+    for (key, delay) in [(2, 1.), (0, 2.), (1, 3.), (3, 4.)] {
+        sender.emit(Reply { key }, comp_id, delay);
+    }
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+}
+
+#[test]
+#[should_panic(expected = "Quorum 3 exceeds the number of keys 2")]
+fn test_recv_events_by_keys_quorum_too_large() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.register_key_getter_for::<Reply>(|reply| reply.key);
+    drop(ctx.recv_events_by_keys_quorum::<Reply>([1, 2], 3));
+}
+
+#[test]
+fn test_recv_events_by_keys_quorum_requeues_extra_events() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let comp_id = ctx.id();
+    let sender = sim.create_context("sender");
+    sim.register_key_getter_for::<Reply>(|reply| reply.key);
+
+    sim.spawn(async move {
+        let future = ctx.recv_events_by_keys_quorum::<Reply>(0..3, 2);
+        // all events are received before the future is polled
+        ctx.sleep(10.).await;
+        let replies = future.await;
+        let keys: Vec<_> = replies.iter().map(|e| e.data.key).collect();
+        assert_eq!(keys, vec![1, 2]);
+        // the extra event is not lost
+        let reply = ctx.recv_event_by_key::<Reply>(0).await;
+        assert_eq!(reply.time, 10.);
+        assert_eq!(ctx.time(), 10.);
+    });
+
+    for (key, delay) in [(1, 1.), (2, 2.), (0, 3.)] {
+        sender.emit(Reply { key }, comp_id, delay);
+    }
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 10.);
+}
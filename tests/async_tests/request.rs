@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::future::join_all;
+use serde::Serialize;
+
+use simcore::async_mode::{AwaitResult, Request};
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Square {
+    x: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Result {
+    value: u64,
+}
+
+// Replies to requests via callbacks with delay proportional to request payload.
+struct Server {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        let request = Event::downcast::<Request<Square>>(event);
+        let x = request.data.data.x;
+        self.ctx.reply(&request, Result { value: x * x }, x as f64);
+    }
+}
+
+#[test]
+fn test_concurrent_requests_with_timeout() {
+    let mut sim = Simulation::new(123);
+    let server_ctx = sim.create_context("server");
+    let server_id = sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx })));
+    let client_ctx = Rc::new(sim.create_context("client"));
+    let results = Rc::new(RefCell::new(Vec::new()));
+
+    let task_results = results.clone();
+    sim.spawn(async move {
+        // responses arrive in the reverse order, the last one after timeout
+        let requests = [3, 2, 1, 6].map(|x| {
+            let ctx = client_ctx.clone();
+            async move { (x, ctx.request::<_, Result>(server_id, Square { x }, 5.).await) }
+        });
+        for (x, res) in join_all(requests).await {
+            match res {
+                AwaitResult::Ok(response) => {
+                    assert_eq!(response.src, server_id);
+                    task_results.borrow_mut().push((x, Some(response.data.data.value), response.time));
+                }
+                AwaitResult::Timeout { src, .. } => {
+                    assert_eq!(src, Some(server_id));
+                    task_results.borrow_mut().push((x, None, client_ctx.time()));
+                }
+            }
+        }
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(
+        *results.borrow(),
+        vec![(3, Some(9), 3.), (2, Some(4), 2.), (1, Some(1), 1.), (6, None, 5.)]
+    );
+    assert_eq!(sim.time(), 6.);
+}
+
+#[test]
+fn test_async_responder() {
+    let mut sim = Simulation::new(123);
+    let server_ctx = sim.create_context("server");
+    let server_id = server_ctx.id();
+    let client_ctx = sim.create_context("client");
+
+    sim.spawn(async move {
+        for _ in 0..3 {
+            let request = server_ctx.recv_event::<Request<Square>>().await;
+            server_ctx.sleep(1.).await;
+            server_ctx.reply(&request, Result { value: request.data.data.x + 1 }, 0.);
+        }
+    });
+
+    sim.spawn(async move {
+        let mut x = 0;
+        for _ in 0..3 {
+            match client_ctx.request::<_, Result>(server_id, Square { x }, 10.).await {
+                AwaitResult::Ok(response) => x = response.data.data.value,
+                AwaitResult::Timeout { .. } => panic!("Unexpected timeout"),
+            }
+        }
+        assert_eq!(x, 3);
+        assert_eq!(client_ctx.time(), 3.);
+    });
+
+    sim.step_until_no_events();
+}
@@ -0,0 +1,186 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::{select, FutureExt};
+
+use simcore::Simulation;
+
+#[test]
+fn test_resource_priority_and_order() {
+    let mut sim = Simulation::new(123);
+    let resource = Rc::new(sim.create_resource("resource", 3));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    // holds the whole resource until time 10
+    let holder_ctx = sim.create_context("holder");
+    let holder_resource = resource.clone();
+    sim.spawn(async move {
+        holder_resource.acquire(3).await;
+        holder_ctx.sleep(10.).await;
+        holder_resource.release(3);
+    });
+
+    // (name, amount, priority, start time)
+    let requests = [("a", 2, 0, 1.), ("b", 1, 0, 2.), ("c", 2, -1, 3.), ("d", 1, 0, 4.)];
+    for (name, amount, priority, start) in requests {
+        let ctx = sim.create_context(name);
+        let resource = resource.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            ctx.sleep(start).await;
+            resource.acquire_with_priority(amount, priority).await;
+            log.borrow_mut().push((name, ctx.time()));
+            ctx.sleep(5.).await;
+            resource.release(amount);
+        });
+    }
+
+    sim.step_until_no_events();
+    // c has higher priority, then a, b and d are served in request order
+    // a cannot be served at 10 together with c, and b cannot overtake a
+    assert_eq!(*log.borrow(), vec![("c", 10.), ("a", 15.), ("b", 15.), ("d", 20.)]);
+    assert_eq!(resource.available(), 3);
+
+    let stats = resource.stats();
+    assert_eq!(stats.acquisitions, 5);
+    assert_eq!(stats.max_queue_length, 4);
+    assert_eq!(stats.mean_wait_time, (7. + 14. + 13. + 16.) / 5.);
+    // in use: 3 units for 10, 2 units for 5, 3 units for 5, 1 unit for 5
+    assert_eq!(stats.utilization, (30. + 10. + 15. + 5.) / (3. * 25.));
+}
+
+#[test]
+fn test_resource_request_cancellation() {
+    let mut sim = Simulation::new(123);
+    let resource = Rc::new(sim.create_resource("resource", 2));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let holder_ctx = sim.create_context("holder");
+    let holder_resource = resource.clone();
+    sim.spawn(async move {
+        holder_resource.acquire(1).await;
+        holder_ctx.sleep(10.).await;
+        holder_resource.release(1);
+    });
+
+    // waits for the whole resource with timeout, blocking subsequent requests until timeout
+    let impatient_ctx = sim.create_context("impatient");
+    let impatient_resource = resource.clone();
+    let impatient_log = log.clone();
+    sim.spawn(async move {
+        impatient_ctx.sleep(1.).await;
+        select! {
+            _ = impatient_resource.acquire(2).fuse() => {
+                impatient_log.borrow_mut().push(("impatient", impatient_ctx.time()));
+            }
+            _ = impatient_ctx.sleep(5.).fuse() => {}
+        }
+    });
+
+    let patient_ctx = sim.create_context("patient");
+    let patient_resource = resource.clone();
+    let patient_log = log.clone();
+    sim.spawn(async move {
+        patient_ctx.sleep(2.).await;
+        patient_resource.acquire(1).await;
+        patient_log.borrow_mut().push(("patient", patient_ctx.time()));
+        patient_resource.release(1);
+    });
+
+    sim.step_until_no_events();
+    // patient request is served when impatient one is cancelled
+    assert_eq!(*log.borrow(), vec![("patient", 6.)]);
+    assert_eq!(resource.available(), 2);
+    assert_eq!(resource.queue_len(), 0);
+}
+
+#[test]
+fn test_resource_capacity_change() {
+    let mut sim = Simulation::new(123);
+    let resource = Rc::new(sim.create_resource("resource", 2));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    // each request holds one unit for 10
+    for (name, start) in [("a", 0.), ("b", 0.), ("c", 1.), ("d", 2.)] {
+        let ctx = sim.create_context(name);
+        let resource = resource.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            ctx.sleep(start).await;
+            resource.acquire(1).await;
+            log.borrow_mut().push((name, ctx.time()));
+            ctx.sleep(10.).await;
+            resource.release(1);
+        });
+    }
+
+    // degraded at 5, restored at 20
+    let controller_ctx = sim.create_context("controller");
+    let controller_resource = resource.clone();
+    sim.spawn(async move {
+        controller_ctx.sleep(5.).await;
+        controller_resource.set_capacity(1);
+        assert_eq!(controller_resource.in_use(), 2);
+        assert_eq!(controller_resource.available(), 0);
+        controller_ctx.sleep(15.).await;
+        controller_resource.set_capacity(3);
+    });
+
+    sim.step_until_no_events();
+    // a and b release at 10, but only one unit is available, so d waits for c to release it at 20,
+    // when the capacity is also increased
+    assert_eq!(*log.borrow(), vec![("a", 0.), ("b", 0.), ("c", 10.), ("d", 20.)]);
+    assert_eq!(resource.capacity(), 3);
+    assert_eq!(resource.available(), 3);
+
+    let stats = resource.stats();
+    // in use: 2 of 2 units for 5, 1 of 1 unit for 25, capacity 3 units for 10 since 20
+    assert_eq!(stats.utilization, (10. + 25.) / (2. * 5. + 15. + 3. * 10.));
+
+    // the state of resource is also recorded in the metrics of its component
+    let metrics = sim.metrics();
+    let metric = |name| metrics.get("resource", name).unwrap().as_f64();
+    assert_eq!(metric("capacity"), (2. * 5. + 15. + 3. * 10.) / 30.);
+    assert_eq!(metric("in_use"), (10. + 25.) / 30.);
+    assert_eq!(metric("queue_length"), stats.mean_queue_length);
+}
+
+#[test]
+fn test_resource_capacity_increase_grants_waiters() {
+    let mut sim = Simulation::new(123);
+    let resource = Rc::new(sim.create_resource("resource", 1));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    for name in ["a", "b", "c"] {
+        let ctx = sim.create_context(name);
+        let resource = resource.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            resource.acquire(1).await;
+            log.borrow_mut().push((name, ctx.time()));
+        });
+    }
+
+    let controller_ctx = sim.create_context("controller");
+    let controller_resource = resource.clone();
+    sim.spawn(async move {
+        controller_ctx.sleep(3.).await;
+        controller_resource.set_capacity(3);
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(*log.borrow(), vec![("a", 0.), ("b", 3.), ("c", 3.)]);
+    assert_eq!(resource.queue_len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Requested amount 2 exceeds resource capacity 1")]
+fn test_resource_acquire_exceeding_reduced_capacity() {
+    let mut sim = Simulation::new(123);
+    let resource = sim.create_resource("resource", 2);
+    resource.set_capacity(1);
+    sim.spawn(async move {
+        resource.acquire(2).await;
+    });
+    sim.step_until_no_events();
+}
@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use simcore::async_mode::RetryPolicy;
+use simcore::Simulation;
+
+// Runs the operation failing on all attempts and returns its result and attempt times.
+fn run_failing(seed: u64, policy: RetryPolicy) -> (Result<(), u32>, Vec<f64>) {
+    let mut sim = Simulation::new(seed);
+    let ctx = Rc::new(sim.create_context("client"));
+    let times = Rc::new(RefCell::new(Vec::new()));
+    let result = Rc::new(RefCell::new(None));
+
+    let task_times = times.clone();
+    let task_result = result.clone();
+    sim.spawn(async move {
+        let res = ctx
+            .retry(&policy, |attempt| {
+                task_times.borrow_mut().push(ctx.time());
+                async move { Err::<(), _>(attempt) }
+            })
+            .await;
+        *task_result.borrow_mut() = Some(res);
+    });
+    sim.step_until_no_events();
+
+    let result = result.borrow_mut().take().unwrap();
+    let times = times.borrow().clone();
+    (result, times)
+}
+
+#[test]
+fn test_retry_exponential_backoff() {
+    let mut policy = RetryPolicy::new(6, 1.);
+    policy.max_backoff = 5.;
+    let (result, times) = run_failing(123, policy);
+    assert_eq!(result, Err(6));
+    // delays 1, 2, 4, 5, 5
+    assert_eq!(times, vec![0., 1., 3., 7., 12., 17.]);
+}
+
+#[test]
+fn test_retry_jitter_is_deterministic() {
+    let policy = RetryPolicy::new(4, 2.).with_jitter(0.5);
+    let (result, times) = run_failing(123, policy.clone());
+    assert_eq!(result, Err(4));
+    let delays: Vec<_> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    for (delay, max_delay) in delays.iter().zip([2., 4., 8.]) {
+        assert!(*delay > max_delay / 2. && *delay <= max_delay, "{}", delay);
+    }
+    assert_eq!(run_failing(123, policy.clone()).1, times);
+    assert_ne!(run_failing(456, policy).1, times);
+}
+
+#[test]
+fn test_retry_success_without_retries() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("client");
+    sim.spawn(async move {
+        let mut calls = 0;
+        let res = ctx
+            .retry(&RetryPolicy::new(3, 1.), |_| {
+                calls += 1;
+                async { Ok::<_, ()>("done") }
+            })
+            .await;
+        assert_eq!(res, Ok("done"));
+        assert_eq!(calls, 1);
+        assert_eq!(ctx.time(), 0.);
+    });
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "Jitter must be in [0, 1], got 2")]
+fn test_retry_invalid_jitter() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("client");
+    sim.spawn(async move {
+        let _ = ctx
+            .retry(&RetryPolicy::new(3, 1.).with_jitter(2.), |_| async { Ok::<_, ()>(()) })
+            .await;
+    });
+    sim.step_until_no_events();
+}
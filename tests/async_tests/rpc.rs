@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use simcore::async_mode::RequestTimeout;
+use simcore::{rpc, Simulation, SimulationContext};
+
+#[rpc]
+pub trait KeyValue {
+    async fn get(&self, key: String) -> Option<u64>;
+    async fn put(&self, key: String, value: u64);
+    async fn add_all(&self, keys: Vec<String>, delta: u64) -> usize;
+}
+
+// Storage serving each request with delay equal to the number of touched keys.
+struct Storage {
+    ctx: SimulationContext,
+    data: RefCell<HashMap<String, u64>>,
+}
+
+impl KeyValue for Storage {
+    async fn get(&self, key: String) -> Option<u64> {
+        self.ctx.sleep(1.).await;
+        self.data.borrow().get(&key).copied()
+    }
+
+    async fn put(&self, key: String, value: u64) {
+        self.ctx.sleep(1.).await;
+        self.data.borrow_mut().insert(key, value);
+    }
+
+    async fn add_all(&self, keys: Vec<String>, delta: u64) -> usize {
+        self.ctx.sleep(keys.len() as f64).await;
+        let mut data = self.data.borrow_mut();
+        for key in keys.iter() {
+            *data.entry(key.clone()).or_default() += delta;
+        }
+        keys.len()
+    }
+}
+
+fn build() -> (Simulation, SimulationContext, Rc<Storage>) {
+    let mut sim = Simulation::new(123);
+    let storage = Rc::new(Storage {
+        ctx: sim.create_context("storage"),
+        data: RefCell::new(HashMap::new()),
+    });
+    let server = KeyValueServer::new(sim.create_context("storage"), storage.clone());
+    sim.add_static_handler("storage", Rc::new(server));
+    let client_ctx = sim.create_context("client");
+    (sim, client_ctx, storage)
+}
+
+#[test]
+fn test_calls() {
+    let (mut sim, client_ctx, storage) = build();
+    let server_id = sim.lookup_id("storage");
+    sim.spawn(async move {
+        let client = KeyValueClient::new(&client_ctx, server_id, 10.);
+        assert_eq!(client.get("foo".to_owned()).await, Ok(None));
+        assert_eq!(client.put("foo".to_owned(), 5).await, Ok(()));
+        assert_eq!(client_ctx.time(), 2.);
+        let keys = vec!["foo".to_owned(), "bar".to_owned()];
+        assert_eq!(client.add_all(keys, 3).await, Ok(2));
+        assert_eq!(client.get("foo".to_owned()).await, Ok(Some(8)));
+        assert_eq!(client_ctx.time(), 5.);
+    });
+    sim.step_until_no_events();
+    assert_eq!(storage.data.borrow()["bar"], 3);
+}
+
+#[test]
+fn test_concurrent_calls() {
+    let (mut sim, client_ctx, _) = build();
+    let server_id = sim.lookup_id("storage");
+    let client_ctx = Rc::new(client_ctx);
+    let results = Rc::new(RefCell::new(Vec::new()));
+    for i in 0..3 {
+        let ctx = client_ctx.clone();
+        let results = results.clone();
+        sim.spawn(async move {
+            let client = KeyValueClient::new(&ctx, server_id, 10.);
+            let keys = (0..3 - i).map(|k| k.to_string()).collect();
+            let added = client.add_all(keys, 1).await.unwrap();
+            results.borrow_mut().push((ctx.time(), added));
+        });
+    }
+    sim.step_until_no_events();
+    // the requests are served concurrently, so each response matches its request
+    assert_eq!(*results.borrow(), vec![(1., 1), (2., 2), (3., 3)]);
+}
+
+#[test]
+fn test_timeout() {
+    let (mut sim, client_ctx, storage) = build();
+    let server_id = sim.lookup_id("storage");
+    sim.spawn(async move {
+        let client = KeyValueClient::new(&client_ctx, server_id, 1.5);
+        let keys = (0..3).map(|k| k.to_string()).collect();
+        assert_eq!(
+            client.add_all(keys, 1).await,
+            Err(RequestTimeout {
+                server: server_id,
+                method: "add_all",
+                timeout: 1.5,
+            })
+        );
+        assert_eq!(client_ctx.time(), 1.5);
+    });
+    sim.step_until_no_events();
+    // the server still completes the request, the late response is discarded
+    assert_eq!(sim.time(), 3.);
+    assert_eq!(storage.data.borrow().len(), 3);
+}
+
+#[test]
+fn test_timeout_display() {
+    let timeout = RequestTimeout {
+        server: 1,
+        method: "get",
+        timeout: 2.,
+    };
+    assert_eq!(timeout.to_string(), "Request get to component 1 timed out after 2");
+}
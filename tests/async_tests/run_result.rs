@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+use simcore::run::TerminationReason;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Message {
+    fail: bool,
+}
+
+#[test]
+fn test_task_error() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let client = sim.create_context("client");
+    client.emit(Message { fail: false }, ctx.id(), 1.);
+    client.emit(Message { fail: true }, ctx.id(), 2.);
+
+    sim.spawn(async move {
+        loop {
+            let event = ctx.recv_event::<Message>().await;
+            assert!(!event.data.fail, "Task failed");
+        }
+    });
+    let result = sim.run();
+
+    assert_eq!(result.termination, TerminationReason::Error("Task failed".to_owned()));
+    assert_eq!(result.failed_components, vec!["comp"]);
+    assert_eq!((result.time, result.processed_events), (2., 2));
+}
@@ -1,7 +1,15 @@
-use futures::{stream::FuturesUnordered, StreamExt};
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use futures::{select, stream::FuturesUnordered, FutureExt, StreamExt};
+use serde::Serialize;
+
+use simcore::time::{SimDuration, SimTime};
 use simcore::Simulation;
 
+#[derive(Clone, Serialize)]
+struct Preempt {}
+
 #[test]
 fn test_sleep() {
     let mut sim = Simulation::new(123);
@@ -34,3 +42,96 @@ fn test_sleep() {
 
     sim.step_until_no_events();
 }
+
+#[test]
+fn test_sleep_remaining_time() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+
+    sim.spawn(async move {
+        ctx.sleep(1.).await;
+        let sleep = ctx.sleep(10.);
+        assert_eq!(sleep.deadline(), 11.);
+        assert_eq!(sleep.remaining(), 10.);
+        ctx.sleep(4.).await;
+        assert_eq!(sleep.remaining(), 6.);
+        sleep.await;
+        assert_eq!(ctx.time(), 11.);
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 11.);
+}
+
+#[test]
+fn test_sleep_cancel() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let ctx_id = ctx.id();
+    let root_ctx = sim.create_context("root");
+
+    sim.spawn(async move {
+        let mut sleep = ctx.sleep(10.);
+        select! {
+            _ = &mut sleep => panic!("Sleep must be interrupted"),
+            _ = ctx.recv_event::<Preempt>().fuse() => {}
+        }
+        assert_eq!(sleep.cancel(), 7.);
+    });
+
+    root_ctx.emit(Preempt {}, ctx_id, 3.);
+    sim.step_until_no_events();
+    // the cancelled timer does not advance the simulation time
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_resume_after_preemptions() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let ctx_id = ctx.id();
+    let root_ctx = sim.create_context("root");
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let log_clone = log.clone();
+    sim.spawn(async move {
+        let mut remaining = 10.;
+        loop {
+            let mut work = ctx.sleep(remaining);
+            select! {
+                _ = &mut work => break,
+                _ = ctx.recv_event::<Preempt>().fuse() => {
+                    remaining = work.cancel();
+                    log_clone.borrow_mut().push((ctx.time(), remaining));
+                    // preempted for 5 seconds
+                    ctx.sleep(5.).await;
+                }
+            }
+        }
+        log_clone.borrow_mut().push((ctx.time(), 0.));
+    });
+
+    root_ctx.emit(Preempt {}, ctx_id, 2.);
+    root_ctx.emit(Preempt {}, ctx_id, 10.);
+    sim.step_until_no_events();
+
+    assert_eq!(*log.borrow(), vec![(2., 8.), (10., 5.), (20., 0.)]);
+}
+
+#[test]
+fn test_sleep_for() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let times = Rc::new(RefCell::new(Vec::new()));
+
+    let times_clone = times.clone();
+    sim.spawn(async move {
+        ctx.sleep_for(SimDuration::from_millis(1500.)).await;
+        times_clone.borrow_mut().push(ctx.now());
+        ctx.sleep_for(SimDuration::ZERO).await;
+        times_clone.borrow_mut().push(ctx.now());
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*times.borrow(), vec![SimTime::from_secs(1.5), SimTime::from_secs(1.5)]);
+}
@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::observer::{StepDelta, StepObserver};
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Message {}
+
+#[derive(Default)]
+struct Recorder {
+    steps: Vec<(f64, f64, u64)>,
+}
+
+impl StepObserver for Recorder {
+    fn after_step(&mut self, delta: &StepDelta) {
+        self.steps.push((delta.time, delta.time_advance, delta.emitted_events));
+    }
+}
+
+#[test]
+fn test_timer_steps_are_accounted_in_next_observed_step() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_step_observer(recorder.clone());
+
+    sim.spawn(async move {
+        ctx.sleep(2.).await;
+        ctx.emit_self(Message {}, 1.);
+        ctx.emit_self(Message {}, 1.);
+        ctx.recv_event::<Message>().await;
+        ctx.sleep(1.).await;
+    });
+    sim.step_until_no_events();
+
+    // the first message resumes the awaiting task, the second one is delivered to the component without handler
+    assert_eq!(*recorder.borrow().steps, vec![(3., 3., 2), (3., 0., 0)]);
+    assert_eq!(sim.time(), 4.);
+}
@@ -0,0 +1,24 @@
+use serde::Serialize;
+
+use simcore::stop::{EventCount, TimeReached};
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+#[test]
+fn test_awaited_events_are_counted() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.spawn(async move {
+        loop {
+            ctx.emit_self(Ping {}, 1.);
+            ctx.recv_event::<Ping>().await;
+        }
+    });
+
+    assert!(sim.step_until(EventCount::of::<Ping>(5)));
+    assert_eq!(sim.time(), 5.);
+    assert!(sim.step_until(TimeReached(7.5)));
+    assert_eq!(sim.time(), 8.);
+}
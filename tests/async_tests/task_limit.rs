@@ -0,0 +1,166 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventCancellationPolicy, Simulation, SimulationContext, StaticEventHandler};
+
+#[derive(Clone, Serialize)]
+struct Job {
+    id: u32,
+    duration: f64,
+}
+
+struct Worker {
+    ctx: SimulationContext,
+    active: Cell<usize>,
+    max_active: Cell<usize>,
+    log: RefCell<Vec<(u32, f64, f64)>>,
+    rejected: RefCell<Vec<u32>>,
+    max_queue: usize,
+}
+
+impl Worker {
+    fn new(ctx: SimulationContext, max_queue: usize) -> Self {
+        Self {
+            ctx,
+            active: Cell::new(0),
+            max_active: Cell::new(0),
+            log: RefCell::new(Vec::new()),
+            rejected: RefCell::new(Vec::new()),
+            max_queue,
+        }
+    }
+
+    async fn process(self: Rc<Self>, id: u32, duration: f64) {
+        let start = self.ctx.time();
+        self.active.set(self.active.get() + 1);
+        self.max_active.set(self.max_active.get().max(self.active.get()));
+        self.ctx.sleep(duration).await;
+        self.active.set(self.active.get() - 1);
+        self.log.borrow_mut().push((id, start, self.ctx.time()));
+    }
+}
+
+impl StaticEventHandler for Worker {
+    fn on(self: Rc<Self>, event: Event) {
+        cast!(match event.data {
+            Job { id, duration } => {
+                if self.ctx.queued_task_count() >= self.max_queue {
+                    self.rejected.borrow_mut().push(id);
+                } else {
+                    self.ctx.spawn(self.clone().process(id, duration));
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn test_task_limit() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let worker = Rc::new(Worker::new(sim.create_context("worker"), usize::MAX));
+    let worker_id = sim.add_static_handler("worker", worker.clone());
+    sim.set_task_limit("worker", 2);
+
+    for (id, duration) in [(0, 10.), (1, 5.), (2, 3.), (3, 1.), (4, 2.)] {
+        client.emit(Job { id, duration }, worker_id, 0.);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(worker.max_active.get(), 2);
+    // queued jobs are started in the order of spawning when a slot is released
+    assert_eq!(
+        *worker.log.borrow(),
+        vec![(1, 0., 5.), (2, 5., 8.), (3, 8., 9.), (0, 0., 10.), (4, 9., 11.)]
+    );
+}
+
+#[test]
+fn test_increase_task_limit() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let worker = Rc::new(Worker::new(sim.create_context("worker"), usize::MAX));
+    let worker_id = sim.add_static_handler("worker", worker.clone());
+    worker.ctx.set_task_limit(1);
+
+    for id in 0..4 {
+        client.emit(Job { id, duration: 10. }, worker_id, 0.);
+    }
+    sim.step_for_duration(5.);
+    assert_eq!(worker.ctx.queued_task_count(), 3);
+
+    worker.ctx.set_task_limit(3);
+    assert_eq!(worker.ctx.queued_task_count(), 1);
+    sim.step_until_no_events();
+
+    assert_eq!(worker.max_active.get(), 3);
+    assert_eq!(sim.time(), 20.);
+}
+
+#[test]
+fn test_load_shedding() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let worker = Rc::new(Worker::new(sim.create_context("worker"), 2));
+    let worker_id = sim.add_static_handler("worker", worker.clone());
+    worker.ctx.set_task_limit(1);
+
+    for id in 0..5 {
+        client.emit(Job { id, duration: 10. }, worker_id, id as f64);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(*worker.rejected.borrow(), vec![3, 4]);
+    assert_eq!(worker.log.borrow().len(), 3);
+    assert_eq!(sim.time(), 30.);
+}
+
+#[test]
+fn test_queued_tasks_drop_on_remove_handler() {
+    let mut sim = Simulation::new(123);
+    let worker = Rc::new(Worker::new(sim.create_context("worker"), usize::MAX));
+    sim.add_static_handler("worker", worker.clone());
+    worker.ctx.set_task_limit(1);
+
+    let rc = Rc::new(());
+    for _ in 0..3 {
+        let rc = rc.clone();
+        let worker_clone = worker.clone();
+        worker.ctx.spawn(async move {
+            let _rc = rc;
+            worker_clone.ctx.sleep(10.).await;
+            panic!("Task must be dropped");
+        });
+    }
+    sim.step_for_duration(5.);
+    assert_eq!(Rc::strong_count(&rc), 4);
+    assert_eq!(worker.ctx.queued_task_count(), 2);
+
+    sim.remove_handler("worker", EventCancellationPolicy::None);
+    assert_eq!(Rc::strong_count(&rc), 1);
+    assert_eq!(worker.ctx.queued_task_count(), 0);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 5.);
+}
+
+#[test]
+fn test_task_limit_is_not_inherited_by_reused_id() {
+    let mut sim = Simulation::new(123);
+    let old = Rc::new(Worker::new(sim.create_context("old"), usize::MAX));
+    sim.add_static_handler("old", old.clone());
+    old.ctx.set_task_limit(1);
+    sim.remove_component("old", EventCancellationPolicy::All);
+
+    let new = Rc::new(Worker::new(sim.create_context("new"), usize::MAX));
+    assert_eq!(new.ctx.id(), old.ctx.id());
+    sim.add_static_handler("new", new.clone());
+    for id in 0..3 {
+        new.ctx.spawn(new.clone().process(id, 1.));
+    }
+    sim.step_until_no_events();
+    assert_eq!(new.max_active.get(), 3);
+    assert_eq!(sim.time(), 1.);
+}
@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::observer::TimeAdvanceListener;
+use simcore::{Event, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Message {}
+
+#[derive(Default)]
+struct Recorder {
+    advances: Vec<(f64, f64, usize)>,
+}
+
+impl TimeAdvanceListener for Recorder {
+    fn on_time_advance(&mut self, old_time: f64, new_time: f64, events: &[Event]) {
+        self.advances.push((old_time, new_time, events.len()));
+    }
+}
+
+#[test]
+fn test_timer_and_event_advances() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_time_advance_listener(recorder.clone());
+
+    sim.spawn(async move {
+        ctx.sleep(2.).await;
+        ctx.emit_self(Message {}, 1.);
+        ctx.emit_self(Message {}, 1.);
+        ctx.recv_event::<Message>().await;
+        ctx.sleep(1.).await;
+    });
+    sim.step_until_no_events();
+
+    // the first message resumes the awaiting task, the second one is delivered to the component without handler
+    assert_eq!(*recorder.borrow().advances, vec![(0., 2., 0), (2., 3., 2), (3., 4., 0)]);
+}
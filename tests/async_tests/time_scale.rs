@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use simcore::Simulation;
+
+#[test]
+fn test_scaled_sleep() {
+    let mut sim = Simulation::new(123);
+    let ctx = Rc::new(sim.create_context("host"));
+
+    let task_ctx = ctx.clone();
+    sim.spawn(async move {
+        task_ctx.sleep(1.).await;
+        assert_eq!(task_ctx.time(), 1.);
+        {
+            let _scope = task_ctx.scale_time(3.);
+            task_ctx.sleep(1.).await;
+            assert_eq!(task_ctx.time(), 4.);
+        }
+        task_ctx.sleep(1.).await;
+        assert_eq!(task_ctx.time(), 5.);
+    });
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 5.);
+    assert_eq!(ctx.time_scale(), 1.);
+}
+
+#[test]
+fn test_scale_guards_dropped_out_of_order() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("host");
+
+    let first = ctx.scale_time(2.);
+    let second = ctx.scale_time(3.);
+    assert_eq!(ctx.time_scale(), 6.);
+    drop(first);
+    assert_eq!(ctx.time_scale(), 3.);
+    drop(second);
+    assert_eq!(ctx.time_scale(), 1.);
+}
+
+#[test]
+fn test_task_time_scale_does_not_leak_to_other_tasks() {
+    let mut sim = Simulation::new(123);
+    let ctx = Rc::new(sim.create_context("host"));
+
+    let slow_ctx = ctx.clone();
+    sim.spawn(async move {
+        slow_ctx
+            .with_time_scale(2., async {
+                slow_ctx.sleep(1.).await;
+                assert_eq!(slow_ctx.time(), 2.);
+                slow_ctx.sleep(3.).await;
+                assert_eq!(slow_ctx.time(), 8.);
+            })
+            .await;
+        assert_eq!(slow_ctx.time_scale(), 1.);
+    });
+
+    let other_ctx = ctx.clone();
+    sim.spawn(async move {
+        other_ctx
+            .with_time_scale(5., async {
+                other_ctx.sleep(1.).await;
+                assert_eq!(other_ctx.time(), 5.);
+            })
+            .await;
+        other_ctx.sleep(1.).await;
+        assert_eq!(other_ctx.time(), 6.);
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 8.);
+    assert_eq!(ctx.time_scale(), 1.);
+}
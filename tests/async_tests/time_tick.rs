@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::tick::TickPolicy;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Message {}
+
+#[test]
+fn test_sleep_and_timeout_are_snapped() {
+    let mut sim = Simulation::new(123);
+    sim.set_time_tick(1., TickPolicy::Ceil);
+    let ctx = sim.create_context("comp");
+    let times = Rc::new(RefCell::new(Vec::new()));
+
+    let times_clone = times.clone();
+    sim.spawn(async move {
+        ctx.sleep(0.3).await;
+        times_clone.borrow_mut().push((ctx.time(), ctx.current_tick()));
+        ctx.sleep(2.).await;
+        times_clone.borrow_mut().push((ctx.time(), ctx.current_tick()));
+        ctx.recv_event::<Message>().with_timeout(0.5).await;
+        times_clone.borrow_mut().push((ctx.time(), ctx.current_tick()));
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*times.borrow(), vec![(1., 1), (3., 3), (4., 4)]);
+}
+
+#[test]
+fn test_sleep_ticks() {
+    let mut sim = Simulation::new(123);
+    sim.set_time_tick(0.1, TickPolicy::Strict);
+    let ctx = sim.create_context("comp");
+    let ticks = Rc::new(RefCell::new(Vec::new()));
+
+    let ticks_clone = ticks.clone();
+    sim.spawn(async move {
+        for _ in 0..30 {
+            ctx.sleep_ticks(1).await;
+            ticks_clone.borrow_mut().push((ctx.current_tick(), ctx.time()));
+        }
+    });
+    sim.step_until_no_events();
+
+    let ticks = ticks.borrow();
+    assert_eq!(ticks.len(), 30);
+    for (i, (tick, time)) in ticks.iter().enumerate() {
+        assert_eq!(*tick, i as u64 + 1);
+        assert_eq!(*time, (i + 1) as f64 * 0.1);
+    }
+}
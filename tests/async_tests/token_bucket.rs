@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::{join, select, FutureExt};
+
+use simcore::Simulation;
+
+#[test]
+fn test_token_bucket_rate() {
+    let mut sim = Simulation::new(123);
+    let bucket = Rc::new(sim.create_token_bucket("bucket", 10., 5.));
+    let ctx = sim.create_context("client");
+    let send_times = Rc::new(RefCell::new(Vec::new()));
+
+    let client_bucket = bucket.clone();
+    let client_send_times = send_times.clone();
+    sim.spawn(async move {
+        // empty the bucket and wait for full refill
+        client_bucket.acquire(5.).await;
+        assert!(!client_bucket.try_acquire(1.));
+        ctx.sleep(1.).await;
+        assert_eq!(client_bucket.available(), 5.);
+        for tokens in [3., 3., 4.] {
+            client_bucket.acquire(tokens).await;
+            client_send_times.borrow_mut().push(ctx.time());
+        }
+    });
+
+    sim.step_until_no_events();
+    // 3 tokens are available at 1, then 1 token is missing, then all 4 tokens are missing
+    assert_eq!(*send_times.borrow(), vec![1., 1.1, 1.5]);
+    assert_eq!(bucket.available(), 0.);
+}
+
+#[test]
+fn test_token_bucket_fifo_and_cancellation() {
+    let mut sim = Simulation::new(123);
+    let bucket = Rc::new(sim.create_token_bucket("bucket", 1., 2.));
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    // (name, tokens, start time, timeout)
+    let clients = [("a", 2., 0., 10.), ("b", 2., 0.5, 1.), ("c", 1., 1., 10.)];
+    for (name, tokens, start, timeout) in clients {
+        let ctx = sim.create_context(name);
+        let bucket = bucket.clone();
+        let log = log.clone();
+        sim.spawn(async move {
+            ctx.sleep(start).await;
+            select! {
+                _ = bucket.acquire(tokens).fuse() => {
+                    log.borrow_mut().push((name, ctx.time()));
+                }
+                _ = ctx.sleep(timeout).fuse() => {
+                    log.borrow_mut().push((name, -1.));
+                }
+            }
+        });
+    }
+
+    sim.step_until_no_events();
+    // a takes the full bucket, b reserves 2 tokens (ready at 2) but is cancelled at 1.5,
+    // c reserves after b and waits until 3 although b's tokens were returned
+    assert_eq!(*log.borrow(), vec![("a", 0.), ("b", -1.), ("c", 3.)]);
+    // initial 2 tokens + 3 accumulated - 5 reserved + 2 returned by b
+    assert_eq!(bucket.available(), 2.);
+}
+
+#[test]
+fn test_token_bucket_serves_in_order_of_calls() {
+    let mut sim = Simulation::new(123);
+    let bucket = Rc::new(sim.create_token_bucket("bucket", 1., 2.));
+    let ctx = sim.create_context("client");
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let client_log = log.clone();
+    sim.spawn(async move {
+        // empty the bucket
+        bucket.acquire(2.).await;
+        let first = bucket.acquire(1.);
+        let second = bucket.acquire(1.);
+        // the futures are polled in the reverse order of their creation
+        join!(
+            async {
+                second.await;
+                client_log.borrow_mut().push(("second", ctx.time()));
+            },
+            async {
+                first.await;
+                client_log.borrow_mut().push(("first", ctx.time()));
+            }
+        );
+    });
+
+    sim.step_until_no_events();
+    assert_eq!(*log.borrow(), vec![("first", 1.), ("second", 2.)]);
+}
+
+#[test]
+#[should_panic(expected = "Requested tokens -1 must be non-negative")]
+fn test_try_acquire_negative_tokens() {
+    let mut sim = Simulation::new(123);
+    let bucket = sim.create_token_bucket("bucket", 10., 5.);
+    bucket.try_acquire(-1.);
+}
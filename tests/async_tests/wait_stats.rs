@@ -0,0 +1,102 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::async_mode::AwaitResult;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Message {}
+
+#[test]
+fn test_wait_stats_by_call_site() {
+    let mut sim = Simulation::new(123);
+    sim.enable_wait_stats();
+    let ctx = sim.create_context("comp");
+    let ctx_id = ctx.id();
+    let sender = sim.create_context("sender");
+    let tags = Rc::new(RefCell::new(Vec::new()));
+
+    let tags_clone = tags.clone();
+    sim.spawn(async move {
+        for i in 1..=3 {
+            let (sleep, tag) = (ctx.sleep(i as f64), format!("{}:{}", file!(), line!()));
+            sleep.await;
+            tags_clone.borrow_mut().push(tag);
+        }
+        let (recv, tag) = (ctx.recv_event::<Message>(), format!("{}:{}", file!(), line!()));
+        recv.await;
+        tags_clone.borrow_mut().push(tag);
+    });
+    sender.emit(Message {}, ctx_id, 10.);
+    sim.step_until_no_events();
+
+    let stats = sim.wait_stats();
+    assert_eq!(stats.len(), 2);
+    let sleep_stats = &stats[&tags.borrow()[0]];
+    assert_eq!(sleep_stats.count, 3);
+    assert_eq!(sleep_stats.total_time, 6.);
+    assert_eq!(sleep_stats.min_time, 1.);
+    assert_eq!(sleep_stats.max_time, 3.);
+    assert_eq!(sleep_stats.mean_time(), 2.);
+    let recv_stats = &stats[&tags.borrow()[3]];
+    assert_eq!(recv_stats.count, 1);
+    assert_eq!(recv_stats.total_time, 4.);
+}
+
+#[test]
+fn test_interrupted_waits_are_recorded() {
+    let mut sim = Simulation::new(123);
+    sim.enable_wait_stats();
+    let ctx = sim.create_context("comp");
+
+    sim.spawn(async move {
+        let res = ctx.recv_event::<Message>().labeled("timeout").with_timeout(5.).await;
+        assert!(matches!(res, AwaitResult::Timeout { .. }));
+        let sleep = ctx.sleep(10.).labeled("cancelled");
+        ctx.sleep(2.).labeled("short").await;
+        assert_eq!(sleep.cancel(), 8.);
+    });
+    sim.step_until_no_events();
+
+    let metrics = sim.wait_metrics();
+    assert_eq!(metrics["wait.timeout.total_time"], 5.);
+    assert_eq!(metrics["wait.cancelled.total_time"], 2.);
+    assert_eq!(metrics["wait.short.count"], 1.);
+    assert_eq!(metrics.len(), 12);
+}
+
+#[test]
+fn test_wait_stats_disabled() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+
+    sim.spawn(async move {
+        ctx.sleep(1.).labeled("sleep").await;
+    });
+    sim.step_until_no_events();
+
+    assert!(sim.wait_stats().is_empty());
+    assert!(sim.wait_metrics().is_empty());
+}
+
+#[test]
+fn test_wait_stats_reset_after_warmup() {
+    let mut sim = Simulation::new(123);
+    sim.enable_wait_stats();
+    sim.set_warmup_time(5.);
+    let ctx = sim.create_context("comp");
+
+    sim.spawn(async move {
+        for _ in 0..10 {
+            ctx.sleep(1.).labeled("sleep").await;
+        }
+    });
+    sim.step_until_no_events();
+
+    // the sleeps ending at 5, 6, ..., 10 are recorded after the warmup period
+    let metrics = sim.wait_metrics();
+    assert_eq!(metrics["wait.sleep.count"], 6.);
+    assert_eq!(metrics["wait.sleep.total_time"], 6.);
+}
@@ -0,0 +1,250 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::{select, FutureExt};
+use serde::Serialize;
+
+use simcore::async_mode::{SharedRate, WorkHandle};
+use simcore::{cast, Event, EventHandler, Simulation};
+
+#[derive(Clone, Serialize)]
+struct SetRate {
+    rate: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct Cancel {}
+
+#[test]
+fn test_work_without_changes() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let done = Rc::new(RefCell::new(None));
+
+    let done_clone = done.clone();
+    sim.spawn(async move {
+        ctx.work(5.).await;
+        *done_clone.borrow_mut() = Some(ctx.time());
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*done.borrow(), Some(5.));
+}
+
+#[test]
+fn test_zero_work() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let done = Rc::new(RefCell::new(false));
+
+    let done_clone = done.clone();
+    sim.spawn(async move {
+        ctx.work(0.).await;
+        assert_eq!(ctx.time(), 0.);
+        *done_clone.borrow_mut() = true;
+    });
+    sim.step_until_no_events();
+
+    assert!(*done.borrow());
+}
+
+#[test]
+fn test_pause_and_resume() {
+    let mut sim = Simulation::new(123);
+    let worker = sim.create_context("worker");
+    let scheduler = sim.create_context("scheduler");
+    let done = Rc::new(RefCell::new(None));
+
+    let work = worker.work(10.);
+    let handle = work.handle();
+    let done_clone = done.clone();
+    sim.spawn(async move {
+        work.await;
+        *done_clone.borrow_mut() = Some(worker.time());
+    });
+    sim.spawn(async move {
+        scheduler.sleep(4.).await;
+        handle.pause();
+        assert!(handle.is_paused());
+        assert_eq!(handle.completion_time(), None);
+        scheduler.sleep(100.).await;
+        assert_eq!(handle.remaining(), 6.);
+        // repeated pause has no effect
+        handle.pause();
+        handle.resume();
+        assert_eq!(handle.completion_time(), Some(110.));
+        scheduler.sleep(1.).await;
+        handle.pause();
+        scheduler.sleep(1.).await;
+        handle.resume();
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*done.borrow(), Some(111.));
+}
+
+#[test]
+fn test_rate_change_from_event_handler() {
+    struct Cpu {
+        handle: Option<WorkHandle>,
+    }
+
+    impl EventHandler for Cpu {
+        fn on(&mut self, event: Event) {
+            cast!(match event.data {
+                SetRate { rate } => {
+                    self.handle.as_ref().unwrap().set_rate(rate);
+                }
+            })
+        }
+    }
+
+    let mut sim = Simulation::new(123);
+    let worker = sim.create_context("worker");
+    let cpu = Rc::new(RefCell::new(Cpu { handle: None }));
+    let cpu_id = sim.add_handler("cpu", cpu.clone());
+    let root = sim.create_context("root");
+    let done = Rc::new(RefCell::new(None));
+
+    let work = worker.work(10.);
+    cpu.borrow_mut().handle = Some(work.handle());
+    let done_clone = done.clone();
+    sim.spawn(async move {
+        work.await;
+        *done_clone.borrow_mut() = Some(worker.time());
+    });
+    // 2 units at rate 1, 4 units at rate 2, 0 units for 5 seconds, 4 units at rate 4
+    root.emit(SetRate { rate: 2. }, cpu_id, 2.);
+    root.emit(SetRate { rate: 0. }, cpu_id, 4.);
+    root.emit(SetRate { rate: 4. }, cpu_id, 9.);
+    sim.step_until_no_events();
+
+    assert_eq!(*done.borrow(), Some(10.));
+    assert!(cpu.borrow().handle.as_ref().unwrap().is_completed());
+    assert_eq!(cpu.borrow().handle.as_ref().unwrap().remaining(), 0.);
+}
+
+#[test]
+fn test_changes_after_completion_are_ignored() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+
+    let work = ctx.work(3.);
+    let handle = work.handle();
+    sim.spawn(async move {
+        work.await;
+    });
+    sim.step_until_no_events();
+
+    assert!(handle.is_completed());
+    handle.set_rate(5.);
+    handle.pause();
+    assert_eq!(handle.rate(), 1.);
+    assert!(!handle.is_paused());
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_dropped_work_is_cancelled() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let ctx_id = ctx.id();
+    let root = sim.create_context("root");
+    let result = Rc::new(RefCell::new(None));
+
+    let result_clone = result.clone();
+    sim.spawn(async move {
+        let mut work = ctx.work(10.);
+        let handle = work.handle();
+        select! {
+            _ = work => panic!("Work must be cancelled"),
+            _ = ctx.recv_event::<Cancel>().fuse() => {}
+        }
+        *result_clone.borrow_mut() = Some((ctx.time(), handle.remaining()));
+    });
+    root.emit(Cancel {}, ctx_id, 3.);
+    sim.step_until_no_events();
+
+    assert_eq!(*result.borrow(), Some((3., 7.)));
+    // the completion timer is cancelled
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_time_scale() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let done = Rc::new(RefCell::new(None));
+
+    let done_clone = done.clone();
+    sim.spawn(async move {
+        let _scope = ctx.scale_time(2.);
+        ctx.work(5.).await;
+        *done_clone.borrow_mut() = Some(ctx.time());
+    });
+    sim.step_until_no_events();
+
+    assert_eq!(*done.borrow(), Some(10.));
+}
+
+#[test]
+#[should_panic(expected = "Work rate must be non-negative")]
+fn test_negative_rate() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    ctx.work(1.).handle().set_rate(-1.);
+}
+
+
+#[test]
+fn test_shared_rate() {
+    let mut sim = Simulation::new(123);
+    let link = SharedRate::new(2.);
+    let done = Rc::new(RefCell::new(Vec::new()));
+
+    let mut handles = Vec::new();
+    for (name, amount) in [("a", 4.), ("b", 20.)] {
+        let ctx = sim.create_context(name);
+        let work = ctx.work(amount);
+        link.attach(&work.handle());
+        handles.push(work.handle());
+        let done = done.clone();
+        sim.spawn(async move {
+            work.await;
+            done.borrow_mut().push((name, ctx.time()));
+        });
+    }
+    // b has own rate 0.5, so its actual rate is 1
+    handles[1].set_rate(0.5);
+    assert_eq!(handles[0].completion_time(), Some(2.));
+    assert_eq!(handles[1].completion_time(), Some(20.));
+    assert_eq!(link.works(), 2);
+
+    sim.step_until_time(4.);
+    assert_eq!(link.works(), 1);
+    assert_eq!(handles[1].remaining(), 16.);
+    // throttled
+    link.set(0.5);
+    assert_eq!(handles[1].rate(), 0.5);
+    assert_eq!(handles[1].completion_time(), Some(68.));
+    sim.step_until_time(8.);
+    // stopped
+    link.set(0.);
+    assert_eq!(handles[1].completion_time(), None);
+    sim.step_until_time(100.);
+    assert_eq!(handles[1].remaining(), 15.);
+    link.set(1.);
+
+    sim.step_until_no_events();
+    assert_eq!(*done.borrow(), vec![("a", 2.), ("b", 130.)]);
+}
+
+#[test]
+#[should_panic(expected = "Work is already attached to a shared rate")]
+fn test_shared_rate_attach_twice() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("worker");
+    let work = ctx.work(1.);
+    SharedRate::new(1.).attach(&work.handle());
+    SharedRate::new(2.).attach(&work.handle());
+}
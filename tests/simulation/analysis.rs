@@ -0,0 +1,79 @@
+//! Tests of cross-run metric comparison.
+
+use simcore::analysis::{compare_runs, RunMetrics, SampleStats};
+
+fn runs(metric: &str, values: &[f64]) -> Vec<RunMetrics> {
+    values
+        .iter()
+        .map(|value| RunMetrics::from([(metric.to_owned(), *value)]))
+        .collect()
+}
+
+fn assert_close(actual: f64, expected: f64, eps: f64) {
+    assert!((actual - expected).abs() < eps, "{} != {}", actual, expected);
+}
+
+#[test]
+fn test_sample_stats() {
+    let stats = SampleStats::from_values(&[2., 4., 4., 4., 5., 5., 7., 9.]);
+    assert_eq!(stats.count, 8);
+    assert_eq!(stats.mean, 5.);
+    assert_close(stats.std_dev, (32. / 7f64).sqrt(), 1e-12);
+    assert_eq!(SampleStats::from_values(&[3.]).std_dev, 0.);
+}
+
+#[test]
+fn test_welch_p_values() {
+    // equal sizes and variances: t = 2.228 with 10 degrees of freedom corresponds to p = 0.05
+    let sd = (3f64 / 2.228f64.powi(2)).sqrt();
+    let baseline: Vec<_> = (0..6)
+        .map(|i| if i % 2 == 0 { -sd } else { sd } * (5f64 / 6.).sqrt())
+        .collect();
+    let candidate: Vec<_> = baseline.iter().map(|v| v + 1.).collect();
+    let report = compare_runs(&runs("m", &baseline), &runs("m", &candidate));
+    let m = report.get("m").unwrap();
+    assert_close(m.delta, 1., 1e-12);
+    assert_close(m.p_value.unwrap(), 0.05, 1e-4);
+    assert!(!m.is_significant(0.01));
+    assert!(m.is_significant(0.06));
+    assert_eq!(m.relative_delta, None);
+
+    // samples with unequal variances: t = 2.455 with 24.99 degrees of freedom
+    let a = [
+        27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7, 21.4,
+    ];
+    let b = [
+        27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5, 24.4,
+    ];
+    let report = compare_runs(&runs("m", &a), &runs("m", &b));
+    assert_close(report.get("m").unwrap().p_value.unwrap(), 0.02138, 1e-5);
+}
+
+#[test]
+fn test_degenerate_and_missing_metrics() {
+    let mut baseline = runs("constant", &[1., 1.]);
+    baseline[0].insert("old".to_owned(), 5.);
+    let mut candidate = runs("constant", &[2., 2.]);
+    candidate[1].insert("new".to_owned(), 5.);
+    candidate[1].insert("single".to_owned(), 1.);
+    baseline[1].insert("single".to_owned(), 2.);
+
+    let report = compare_runs(&baseline, &candidate);
+    let constant = report.get("constant").unwrap();
+    assert_eq!(constant.p_value, Some(0.));
+    assert_eq!(constant.relative_delta, Some(1.));
+    let single = report.get("single").unwrap();
+    assert_eq!(single.p_value, None);
+    assert_eq!(single.relative_delta, Some(-0.5));
+    assert_eq!(report.baseline_only, vec!["old"]);
+    assert_eq!(report.candidate_only, vec!["new"]);
+    assert_eq!(
+        report.significant(0.05).map(|m| m.name.as_str()).collect::<Vec<_>>(),
+        vec!["constant"]
+    );
+
+    let output = report.to_string();
+    assert!(output.starts_with("metric"));
+    assert!(output.contains("old       only in baseline"));
+    assert_eq!(output.lines().count(), 5);
+}
@@ -0,0 +1,118 @@
+//! Tests of arrival generator component.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::distributions::Uniform;
+use serde::Serialize;
+
+use simcore::generator::{ArrivalGenerator, TargetPolicy};
+use simcore::{cast, Event, EventHandler, Id, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Job {
+    id: u64,
+}
+
+#[derive(Default)]
+struct Sink {
+    jobs: Vec<(u64, f64)>,
+}
+
+impl EventHandler for Sink {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job { id } => {
+                self.jobs.push((id, event.time));
+            }
+        })
+    }
+}
+
+fn add_sinks(sim: &mut Simulation, count: usize) -> (Vec<Id>, Vec<Rc<RefCell<Sink>>>) {
+    let sinks: Vec<_> = (0..count).map(|_| Rc::new(RefCell::new(Sink::default()))).collect();
+    let ids = sinks
+        .iter()
+        .enumerate()
+        .map(|(i, sink)| sim.add_handler(format!("sink-{}", i), sink.clone()))
+        .collect();
+    (ids, sinks)
+}
+
+fn add_generator(sim: &mut Simulation, generator: ArrivalGenerator<Job>) -> Rc<RefCell<ArrivalGenerator<Job>>> {
+    let generator = Rc::new(RefCell::new(generator));
+    sim.add_handler("generator", generator.clone());
+    generator.borrow_mut().start();
+    generator
+}
+
+#[test]
+fn test_batches_and_round_robin() {
+    let mut sim = Simulation::new(123);
+    let (ids, sinks) = add_sinks(&mut sim, 2);
+    let generator = ArrivalGenerator::new(sim.create_context("generator"), |_, id| Job { id })
+        .with_fixed_interarrival(1.)
+        .with_batch_size(3)
+        .with_max_arrivals(2)
+        .with_targets(TargetPolicy::RoundRobin(ids));
+    let generator = add_generator(&mut sim, generator);
+
+    sim.step_until_no_events();
+    assert_eq!(sinks[0].borrow().jobs, vec![(0, 1.), (2, 1.), (4, 2.)]);
+    assert_eq!(sinks[1].borrow().jobs, vec![(1, 1.), (3, 2.), (5, 2.)]);
+    assert_eq!(generator.borrow().arrival_count(), 2);
+    assert_eq!(generator.borrow().event_count(), 6);
+    assert!(!generator.borrow().is_active());
+}
+
+#[test]
+fn test_poisson_arrivals() {
+    let mut sim = Simulation::new(123);
+    let (ids, sinks) = add_sinks(&mut sim, 3);
+    let generator = ArrivalGenerator::new(sim.create_context("generator"), |_, id| Job { id })
+        .with_exponential_interarrival(10.)
+        .with_start_time(100.)
+        .with_stop_time(1100.)
+        .with_targets(TargetPolicy::Weighted(vec![(ids[0], 1.), (ids[1], 3.), (ids[2], 0.)]));
+    let generator = add_generator(&mut sim, generator);
+
+    sim.step_until_no_events();
+    let count = generator.borrow().event_count();
+    // about 10 arrivals per unit of time during 1000 units
+    assert!((9500..10500).contains(&count), "{}", count);
+    let sink0 = sinks[0].borrow().jobs.len() as f64;
+    let sink1 = sinks[1].borrow().jobs.len() as f64;
+    assert!((sink1 / sink0 - 3.).abs() < 0.3, "{} {}", sink0, sink1);
+    assert!(sinks[2].borrow().jobs.is_empty());
+    let times: Vec<_> = sinks
+        .iter()
+        .flat_map(|s| s.borrow().jobs.clone())
+        .map(|(_, t)| t)
+        .collect();
+    assert!(times.iter().all(|&t| (100. ..=1100.).contains(&t)));
+}
+
+#[test]
+fn test_random_batch_sizes_and_stop() {
+    let mut sim = Simulation::new(123);
+    let (ids, sinks) = add_sinks(&mut sim, 2);
+    let generator = ArrivalGenerator::new(sim.create_context("generator"), |_, id| Job { id })
+        .with_interarrival_distribution(Uniform::new(1., 3.))
+        .with_batch_size_fn(|ctx| ctx.gen_range(1..=4))
+        .with_targets(TargetPolicy::Random(ids));
+    let generator = add_generator(&mut sim, generator);
+
+    sim.step_until_time(50.);
+    generator.borrow_mut().stop();
+    assert!(!generator.borrow().is_active());
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 50.);
+
+    let arrivals = generator.borrow().arrival_count();
+    let events = generator.borrow().event_count();
+    assert!((17..=50).contains(&arrivals), "{}", arrivals);
+    assert!(events >= arrivals && events <= 4 * arrivals);
+    let received = sinks.iter().map(|s| s.borrow().jobs.len() as u64).sum::<u64>();
+    assert_eq!(received, events);
+    assert!(sinks.iter().all(|s| !s.borrow().jobs.is_empty()));
+}
@@ -0,0 +1,208 @@
+//! Tests of simulation branching.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use simcore::branch::BranchComponent;
+use simcore::input::{InputEvent, InputItem};
+use simcore::snapshot::ComponentState;
+use simcore::timer::TimerFired;
+use simcore::{cast, Event, EventCancellationPolicy, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Job {
+    size: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct Slowdown {
+    factor: f64,
+}
+
+struct Worker {
+    ctx: SimulationContext,
+    peer: Id,
+    speed: f64,
+    done: u32,
+    log: Vec<(f64, u32)>,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job { size } => {
+                self.done += 1;
+                self.log.push((self.ctx.time(), self.done));
+                let next = Job {
+                    size: self.ctx.gen_range(1.0..2.0),
+                };
+                self.ctx.emit(next, self.peer, size / self.speed);
+            }
+            Slowdown { factor } => {
+                self.speed /= factor;
+            }
+            TimerFired { name } => {
+                self.log.push((self.ctx.time(), name.len() as u32));
+            }
+        })
+    }
+}
+
+impl ComponentState for Worker {
+    fn state(&self) -> Value {
+        json!({"done": self.done, "speed": self.speed, "log": self.log})
+    }
+}
+
+impl BranchComponent for Worker {
+    fn branch(&self, ctx: SimulationContext) -> Self {
+        Self {
+            ctx,
+            peer: self.peer,
+            speed: self.speed,
+            done: self.done,
+            log: self.log.clone(),
+        }
+    }
+}
+
+struct Plain {}
+
+impl EventHandler for Plain {
+    fn on(&mut self, _event: Event) {}
+}
+
+fn build(seed: u64) -> Simulation {
+    let mut sim = Simulation::new(seed);
+    let a_ctx = sim.create_context("a");
+    let b_ctx = sim.create_context("b");
+    let (a_id, b_id) = (a_ctx.id(), b_ctx.id());
+    a_ctx.emit_self(Job { size: 1. }, 0.);
+    b_ctx.set_timer("heartbeat", 30.);
+    for (name, ctx, peer) in [("a", a_ctx, b_id), ("b", b_ctx, a_id)] {
+        let worker = Worker {
+            ctx,
+            peer,
+            speed: 1.,
+            done: 0,
+            log: Vec::new(),
+        };
+        sim.add_branchable_handler(name, Rc::new(RefCell::new(worker)));
+    }
+    sim
+}
+
+#[test]
+fn test_branch_reproduces_original_run() {
+    let mut sim = build(123);
+    sim.step_until_time(20.);
+    let mut branch = sim.branch();
+    assert_eq!(branch.snapshot(), sim.snapshot());
+    assert_eq!(branch.event_count(), sim.event_count());
+
+    sim.step_until_time(100.);
+    // running the original does not affect the branch
+    assert_eq!(branch.time(), 20.);
+    branch.step_until_time(100.);
+    assert_eq!(branch.snapshot(), sim.snapshot());
+
+    // the branch matches the run without branching
+    let mut reference = build(123);
+    reference.step_until_time(100.);
+    assert_eq!(reference.snapshot(), sim.snapshot());
+}
+
+#[test]
+fn test_branches_with_different_seeds() {
+    let mut sim = build(123);
+    sim.step_until_time(20.);
+    let mut first = sim.branch_with_seed(1);
+    let mut second = sim.branch_with_seed(2);
+    let mut second_again = sim.branch_with_seed(2);
+    for branch in [&mut first, &mut second, &mut second_again] {
+        branch.step_until_time(100.);
+    }
+    assert_ne!(first.snapshot(), second.snapshot());
+    assert_eq!(second.snapshot(), second_again.snapshot());
+    assert_eq!(second.run_metadata().seed, 2);
+
+    // the runs are equal before the branching point
+    let log = |sim: &Simulation| sim.branch_component::<Worker>("a").borrow().log.clone();
+    let before = |log: Vec<(f64, u32)>| log.into_iter().filter(|(t, _)| *t <= 20.).collect::<Vec<_>>();
+    assert_eq!(before(log(&first)), before(log(&second)));
+}
+
+#[test]
+fn test_fault_injection_in_branch() {
+    let mut sim = build(123);
+    sim.step_until_time(20.);
+
+    let mut faulty = sim.branch();
+    let b_id = faulty.lookup_id("b");
+    let operator = faulty.create_context("operator");
+    operator.emit(Slowdown { factor: 2. }, b_id, 0.);
+    faulty.step_until_time(100.);
+    sim.step_until_time(100.);
+
+    assert_eq!(faulty.branch_component::<Worker>("b").borrow().speed, 0.5);
+    assert_eq!(sim.branch_component::<Worker>("b").borrow().speed, 1.);
+    let done = |sim: &Simulation| sim.snapshot().get("a").unwrap()["done"].as_u64().unwrap();
+    assert!(done(&faulty) < done(&sim));
+    // the operator is created only in the branch
+    assert!(!sim.components().iter().any(|c| sim.lookup_name(c.id()) == "operator"));
+}
+
+#[test]
+fn test_branch_of_branch() {
+    let mut sim = build(123);
+    sim.step_until_time(10.);
+    let mut branch = sim.branch();
+    branch.step_until_time(20.);
+    let mut nested = branch.branch();
+    // the timer set before the first branching fires in the nested branch
+    nested.step_until_time(100.);
+    sim.step_until_time(100.);
+    assert_eq!(nested.snapshot(), sim.snapshot());
+    let log = nested.branch_component::<Worker>("b").borrow().log.clone();
+    assert!(log.contains(&(30., "heartbeat".len() as u32)));
+}
+
+#[test]
+fn test_removed_handlers_are_not_required() {
+    let mut sim = build(123);
+    sim.add_handler("plain", Rc::new(RefCell::new(Plain {})));
+    sim.remove_handler("plain", EventCancellationPolicy::None);
+    sim.step_until_time(10.);
+    let mut branch = sim.branch();
+    branch.step_until_time(20.);
+}
+
+#[test]
+#[should_panic(expected = "Component plain does not support branching, register it via add_branchable_handler")]
+fn test_non_branchable_handler_panics() {
+    let mut sim = build(123);
+    sim.add_handler("plain", Rc::new(RefCell::new(Plain {})));
+    sim.branch();
+}
+
+#[test]
+#[should_panic(expected = "Simulation with external inputs cannot be branched")]
+fn test_inputs_panic() {
+    let mut sim = build(123);
+    let a_id = sim.lookup_id("a");
+    sim.add_input(
+        "input",
+        vec![InputItem::Event(InputEvent::new(1., a_id, Job { size: 1. }))],
+    );
+    sim.branch();
+}
+
+#[test]
+#[should_panic(expected = "Component a has a different type")]
+fn test_wrong_component_type_panics() {
+    let sim = build(123);
+    sim.branch_component::<Plain>("a");
+}
@@ -0,0 +1,130 @@
+//! Tests of breakpoints on processed events.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::breakpoint::Breakpoint;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Response {
+    id: u64,
+}
+
+struct Server {
+    processed: Vec<u64>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.processed.push(id);
+                self.ctx.emit(Response { id }, event.src, 0.5);
+            }
+        })
+    }
+}
+
+fn build() -> (Simulation, Rc<RefCell<Server>>, SimulationContext, Id) {
+    let mut sim = Simulation::new(123);
+    let server = Rc::new(RefCell::new(Server {
+        processed: Vec::new(),
+        ctx: sim.create_context("server"),
+    }));
+    let server_id = sim.add_handler("server", server.clone());
+    let client = sim.create_context("client");
+    for id in 0..5 {
+        client.emit(Request { id }, server_id, id as f64);
+    }
+    (sim, server, client, server_id)
+}
+
+#[test]
+fn test_payload_breakpoint() {
+    let (mut sim, server, _client, server_id) = build();
+    let breakpoint = sim.add_breakpoint(Breakpoint::on_payload(|request: &Request| request.id == 2));
+    sim.step_until_no_events();
+
+    assert_eq!(server.borrow().processed, vec![0, 1]);
+    assert_eq!(sim.time(), 1.5);
+    let hit = sim.take_breakpoint_hit().unwrap();
+    assert_eq!(hit.breakpoint, breakpoint);
+    assert_eq!(hit.event.time, 2.);
+    assert_eq!(hit.event.dst, server_id);
+    assert_eq!(hit.event.data.downcast_ref::<Request>().unwrap().id, 2);
+    assert!(sim.take_breakpoint_hit().is_none());
+    // the triggering event is still pending
+    assert!(sim.dump_events().iter().any(|event| event.id == hit.event.id));
+
+    sim.step_until_no_events();
+    assert_eq!(server.borrow().processed, vec![0, 1, 2, 3, 4]);
+    assert!(sim.take_breakpoint_hit().is_none());
+}
+
+#[test]
+fn test_step_by_step() {
+    let (mut sim, server, client, _server_id) = build();
+    sim.add_breakpoint(Breakpoint::on::<Response>().to(client.id()));
+    sim.step_until_no_events();
+    assert_eq!(server.borrow().processed, vec![0]);
+    assert_eq!(sim.time(), 0.);
+    assert_eq!(sim.take_breakpoint_hit().unwrap().event.time, 0.5);
+
+    // the first step processes the triggering event
+    assert!(sim.step());
+    assert_eq!(sim.time(), 0.5);
+    assert!(sim.take_breakpoint_hit().is_none());
+    assert!(sim.step());
+    assert_eq!(server.borrow().processed, vec![0, 1]);
+    // the next response hits the breakpoint again
+    assert!(sim.step());
+    assert_eq!(sim.time(), 1.);
+    assert_eq!(sim.take_breakpoint_hit().unwrap().event.time, 1.5);
+}
+
+#[test]
+fn test_source_breakpoint() {
+    let (mut sim, server, _client, server_id) = build();
+    sim.add_breakpoint(Breakpoint::any().from(server_id).when(|event| event.time > 3.));
+    sim.step_until_no_events();
+    assert_eq!(server.borrow().processed, vec![0, 1, 2, 3]);
+    let hit = sim.take_breakpoint_hit().unwrap();
+    assert_eq!((hit.event.src, hit.event.time), (server_id, 3.5));
+}
+
+#[test]
+fn test_step_until_time() {
+    let (mut sim, server, _client, _server_id) = build();
+    sim.add_breakpoint(Breakpoint::on_payload(|request: &Request| request.id == 3));
+    assert!(sim.step_until_time(10.));
+    assert_eq!(sim.time(), 2.5);
+    assert!(sim.take_breakpoint_hit().is_some());
+    assert!(!sim.step_until_time(10.));
+    assert_eq!(sim.time(), 10.);
+    assert_eq!(server.borrow().processed, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_remove_breakpoints() {
+    let (mut sim, server, _client, _server_id) = build();
+    let breakpoint = sim.add_breakpoint(Breakpoint::on::<Request>());
+    sim.add_breakpoint(Breakpoint::on::<Response>());
+    sim.remove_breakpoint(breakpoint);
+    sim.step_until_no_events();
+    assert_eq!(server.borrow().processed, vec![0]);
+    assert!(sim.take_breakpoint_hit().is_some());
+
+    sim.clear_breakpoints();
+    sim.step_until_no_events();
+    assert_eq!(server.borrow().processed, vec![0, 1, 2, 3, 4]);
+    assert!(sim.take_breakpoint_hit().is_none());
+}
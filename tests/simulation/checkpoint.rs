@@ -0,0 +1,296 @@
+//! Tests of checkpoints of simulation state.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use simcore::checkpoint::Checkpoint;
+use simcore::limits::LimitAction;
+use simcore::snapshot::ComponentState;
+use simcore::timer::TimerFired;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Job {
+    id: u64,
+    size: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Report {}
+
+#[derive(Clone, Serialize)]
+struct Unregistered {}
+
+struct Generator {
+    next_id: u64,
+    server: u32,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Generator {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Report {} => {
+                let size = self.ctx.gen_range(0.5..1.5);
+                self.ctx.emit(Job { id: self.next_id, size }, self.server, 0.1);
+                self.next_id += 1;
+                self.ctx.emit_ordered_self(Report {}, self.ctx.gen_range(0.5..1.0));
+            }
+        })
+    }
+}
+
+impl ComponentState for Generator {
+    fn state(&self) -> Value {
+        json!({"next_id": self.next_id})
+    }
+
+    fn restore_state(&mut self, state: Value) {
+        self.next_id = state["next_id"].as_u64().unwrap();
+    }
+}
+
+struct Server {
+    processed: Vec<(f64, u64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job { id, .. } => {
+                self.processed.push((self.ctx.time(), id));
+                self.ctx.set_timer("idle", 3.);
+            }
+            TimerFired { .. } => {
+                self.processed.push((self.ctx.time(), u64::MAX));
+            }
+        })
+    }
+}
+
+impl ComponentState for Server {
+    fn state(&self) -> Value {
+        json!({"processed": self.processed})
+    }
+
+    fn restore_state(&mut self, state: Value) {
+        self.processed = serde_json::from_value(state["processed"].clone()).unwrap();
+    }
+}
+
+struct Model {
+    sim: Simulation,
+    generator: Rc<RefCell<Generator>>,
+    server: Rc<RefCell<Server>>,
+}
+
+fn build() -> Model {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Job>();
+    sim.register_checkpoint_event::<Report>();
+    let server_ctx = sim.create_context("server");
+    let server = Rc::new(RefCell::new(Server {
+        processed: Vec::new(),
+        ctx: server_ctx,
+    }));
+    let server_id = sim.add_handler("server", server.clone());
+    sim.register_state("server", server.clone());
+    let generator_ctx = sim.create_context("generator");
+    generator_ctx.emit_ordered_self(Report {}, 0.);
+    let generator = Rc::new(RefCell::new(Generator {
+        next_id: 0,
+        server: server_id,
+        ctx: generator_ctx,
+    }));
+    sim.add_handler("generator", generator.clone());
+    sim.register_state("generator", generator.clone());
+    Model { sim, generator, server }
+}
+
+#[test]
+fn test_resumed_run_matches_uninterrupted() {
+    let mut model = build();
+    model.sim.step_until_time(50.);
+    let expected = model.server.borrow().processed.clone();
+    let expected_count = model.sim.event_count();
+    assert!(expected.len() > 50);
+
+    let mut first = build();
+    first.sim.step_until_time(20.);
+    let checkpoint = first.sim.checkpoint();
+    assert_eq!(checkpoint.time, 20.);
+    assert_eq!(checkpoint.event_count, first.sim.event_count());
+    assert_eq!(checkpoint.states.len(), 2);
+    assert!(checkpoint.pending_events() > 0);
+
+    let mut resumed = build();
+    resumed.sim.restore_checkpoint(&checkpoint);
+    assert_eq!(resumed.sim.time(), 20.);
+    assert_eq!(resumed.sim.event_count(), first.sim.event_count());
+    assert_eq!(resumed.generator.borrow().next_id, first.generator.borrow().next_id);
+    resumed.sim.step_until_time(50.);
+    assert_eq!(resumed.server.borrow().processed, expected);
+    assert_eq!(resumed.sim.event_count(), expected_count);
+}
+
+#[test]
+fn test_save_and_load_file() {
+    let mut model = build();
+    model.sim.step_until_time(30.);
+    let expected = model.server.borrow().processed.clone();
+
+    let path = std::env::temp_dir().join(format!("simcore-checkpoint-{}.json", std::process::id()));
+    let mut first = build();
+    first.sim.step_until_time(12.5);
+    first.sim.save_checkpoint(&path);
+
+    let checkpoint: Checkpoint = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(checkpoint.time, 12.5);
+    assert_eq!(checkpoint.components.len(), 2);
+
+    let mut resumed = build();
+    resumed.sim.load_checkpoint(&path);
+    std::fs::remove_file(&path).unwrap();
+    resumed.sim.step_until_time(30.);
+    assert_eq!(resumed.server.borrow().processed, expected);
+}
+
+#[test]
+fn test_named_timer_restored() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.set_timer("timeout", 5.);
+    sim.step_until_time(1.);
+    let checkpoint = sim.checkpoint();
+
+    let mut resumed = Simulation::new(123);
+    let resumed_ctx = resumed.create_context("comp");
+    resumed_ctx.set_timer("other", 2.);
+    resumed.restore_checkpoint(&checkpoint);
+    assert!(resumed_ctx.has_timer("timeout"));
+    assert!(!resumed_ctx.has_timer("other"));
+    // the restored timer can be canceled by its name
+    assert!(resumed_ctx.cancel_timer("timeout"));
+    assert!(!resumed.step());
+}
+
+#[test]
+fn test_canceled_events_not_saved() {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Report>();
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(Report {}, 1.);
+    let canceled = ctx.emit_self(Report {}, 2.);
+    ctx.cancel_event(canceled);
+    assert_eq!(sim.checkpoint().pending_events(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Type of pending event 0 is not registered via register_checkpoint_event")]
+fn test_unregistered_event_type() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(Unregistered {}, 1.);
+    sim.checkpoint();
+}
+
+#[test]
+#[should_panic(expected = "Component server with identifier 0 from checkpoint does not exist")]
+fn test_mismatched_components() {
+    let mut model = build();
+    model.sim.step_until_time(5.);
+    let checkpoint = model.sim.checkpoint();
+
+    let mut other = Simulation::new(123);
+    other.register_checkpoint_event::<Job>();
+    other.register_checkpoint_event::<Report>();
+    other.create_context("generator");
+    other.create_context("server");
+    other.restore_checkpoint(&checkpoint);
+}
+
+#[test]
+#[should_panic(expected = "State of component generator from checkpoint is not registered")]
+fn test_unregistered_state() {
+    let mut model = build();
+    model.sim.step_until_time(5.);
+    let checkpoint = model.sim.checkpoint();
+
+    let mut other = Simulation::new(123);
+    other.register_checkpoint_event::<Job>();
+    other.register_checkpoint_event::<Report>();
+    other.create_context("server");
+    other.create_context("generator");
+    other.restore_checkpoint(&checkpoint);
+}
+
+#[test]
+#[should_panic(expected = "Checkpoint cannot be saved while there are events emitted via emit_after")]
+fn test_deferred_events() {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Report>();
+    let ctx = sim.create_context("comp");
+    let first = ctx.emit_self(Report {}, 1.);
+    ctx.emit_after(Report {}, ctx.id(), first, 1.);
+    sim.checkpoint();
+}
+
+#[derive(Default)]
+struct Recorder {
+    times: Vec<f64>,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, event: Event) {
+        self.times.push(event.time);
+    }
+}
+
+#[test]
+fn test_coalesced_and_deferred_events_discarded() {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Report>();
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+    sim.enable_event_coalescing("recorder", 0.1);
+    let ctx = sim.create_context("sender");
+    let first = ctx.emit(Report {}, recorder_id, 1.);
+    let checkpoint = sim.checkpoint();
+
+    // the events emitted after the checkpoint are merged into the burst of the first event or deferred after it
+    ctx.emit(Report {}, recorder_id, 1.05);
+    ctx.emit_after(Report {}, recorder_id, first, 1.);
+    sim.restore_checkpoint(&checkpoint);
+    sim.step_until_no_events();
+    assert_eq!(recorder.borrow().times, vec![1.]);
+}
+
+#[test]
+fn test_run_state_restored_in_fresh_simulation() {
+    let Model { mut sim, .. } = build();
+    sim.set_warmup_time(5.);
+    sim.step_until_time(10.);
+    let checkpoint = sim.checkpoint();
+    // the events processed during warmup are counted in the checkpoint
+    assert!(checkpoint.processed_events > sim.processed_event_count());
+
+    let Model { sim: mut restored, .. } = build();
+    restored.set_contract_action(LimitAction::Pause);
+    let generator = restored.create_context("generator");
+    generator.declare_max_outstanding::<Report>(generator.id(), 1);
+    restored.restore_checkpoint(&checkpoint);
+    assert_eq!(restored.warmup_end_time(), Some(5.));
+    assert_eq!(restored.event_count(), sim.event_count());
+    assert_eq!(restored.processed_event_count(), sim.processed_event_count());
+
+    // the pending report from the checkpoint is outstanding
+    let extra = generator.emit_self(Report {}, 100.);
+    restored.step();
+    let violations = restored.take_contract_violations();
+    assert_eq!(violations[0].event_id, extra);
+    assert_eq!(violations[0].outstanding, 2);
+}
@@ -0,0 +1,150 @@
+//! Tests of Chrome trace export.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use simcore::chrome_trace::{export_chrome_trace, write_chrome_trace, ChromeTraceConfig};
+use simcore::trace_file::{read_trace_file, TraceFileConfig};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Response {
+    id: u64,
+}
+
+struct Server {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.ctx.emit(Response { id }, event.src, 0.5);
+            }
+        })
+    }
+}
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-chrome-{}-{}.{}", name, std::process::id(), ext))
+}
+
+// Records a run with two requests, one of which is canceled.
+fn record(name: &str) -> PathBuf {
+    let path = temp_path(name, "jsonl");
+    let mut sim = Simulation::new(123);
+    let server_ctx = sim.create_context("server");
+    let server_id = sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx })));
+    let client = sim.create_context("client");
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    client.emit(Request { id: 1 }, server_id, 1.);
+    let canceled = client.emit(Request { id: 2 }, server_id, 2.);
+    sim.step();
+    client.cancel_event(canceled);
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    path
+}
+
+fn convert(name: &str, config: &ChromeTraceConfig) -> Vec<Value> {
+    let path = record(name);
+    let trace = read_trace_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    let mut output = Vec::new();
+    write_chrome_trace(&trace, config, &mut output);
+    let output: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(output["otherData"]["seed"], 123);
+    output["traceEvents"].as_array().unwrap().clone()
+}
+
+fn phases<'a>(events: &'a [Value], ph: &str) -> Vec<&'a Value> {
+    events.iter().filter(|e| e["ph"] == ph).collect()
+}
+
+#[test]
+fn test_tracks() {
+    let events = convert("tracks", &ChromeTraceConfig::new());
+    let tracks: Vec<_> = phases(&events, "M")
+        .into_iter()
+        .filter(|e| e["name"] == "thread_name")
+        .map(|e| (e["tid"].as_u64().unwrap(), e["args"]["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(tracks, vec![(1, "server"), (2, "client")]);
+}
+
+#[test]
+fn test_processed_events() {
+    let events = convert("processed", &ChromeTraceConfig::new());
+    let instants = phases(&events, "i");
+    assert_eq!(instants.len(), 2);
+    assert_eq!(instants[0]["name"], "Request");
+    assert_eq!(instants[0]["ts"], 1e6);
+    assert_eq!(instants[0]["tid"], 1);
+    assert_eq!(
+        instants[0]["args"],
+        json!({"id": 0, "src": "client", "data": {"id": 1}})
+    );
+    assert_eq!(instants[1]["name"], "Response");
+    assert_eq!(instants[1]["ts"], 1.5e6);
+    assert_eq!(instants[1]["tid"], 2);
+}
+
+#[test]
+fn test_in_flight_slices() {
+    let mut config = ChromeTraceConfig::new();
+    config.time_scale = 1.;
+    let events = convert("in-flight", &config);
+    let slices: Vec<_> = phases(&events, "b")
+        .into_iter()
+        .zip(phases(&events, "e"))
+        .map(|(b, e)| {
+            assert_eq!(b["id"], e["id"]);
+            (
+                b["name"].as_str().unwrap(),
+                b["ts"].as_f64().unwrap(),
+                e["ts"].as_f64().unwrap(),
+                e["args"]["canceled"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        slices,
+        vec![
+            ("Request", 0., 1., false),
+            ("Request", 0., 1., true),
+            ("Response", 1., 1.5, false),
+        ]
+    );
+}
+
+#[test]
+fn test_disabled_details() {
+    let mut config = ChromeTraceConfig::new();
+    config.in_flight = false;
+    config.payloads = false;
+    let events = convert("disabled", &config);
+    assert!(phases(&events, "b").is_empty());
+    assert!(phases(&events, "i").iter().all(|e| e["args"].get("data").is_none()));
+}
+
+#[test]
+fn test_export_file() {
+    let path = record("export");
+    let output_path = temp_path("export", "json");
+    export_chrome_trace(&path, &output_path, &ChromeTraceConfig::new());
+    let output: Value = serde_json::from_reader(std::fs::File::open(&output_path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&output_path).unwrap();
+    assert_eq!(output["displayTimeUnit"], "ms");
+    assert_eq!(phases(output["traceEvents"].as_array().unwrap(), "i").len(), 2);
+}
@@ -0,0 +1,178 @@
+//! Tests of component removal with identifier reuse.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::logical_clock::{LogicalClockKind, LogicalTime};
+use simcore::producers::ProducerStatsConfig;
+use simcore::{Event, EventCancellationPolicy, EventHandler, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+#[derive(Default)]
+struct Counter {
+    received: u32,
+}
+
+impl EventHandler for Counter {
+    fn on(&mut self, _event: Event) {
+        self.received += 1;
+    }
+}
+
+#[test]
+fn test_removed_ids_are_reused() {
+    let mut sim = Simulation::new(123);
+    let a = sim.add_handler("a", Rc::new(RefCell::new(Counter::default())));
+    let b = sim.add_handler("b", Rc::new(RefCell::new(Counter::default())));
+    sim.remove_component("a", EventCancellationPolicy::None);
+    sim.remove_component("b", EventCancellationPolicy::None);
+
+    let c = sim.create_context("c").id();
+    let d = sim.create_context("d").id();
+    let e = sim.create_context("e").id();
+    assert_eq!(vec![c, d], vec![b, a]);
+    assert_eq!(e, 2);
+    assert_eq!(sim.lookup_name(c), "c");
+    assert_eq!(sim.component_ref("c").generation(), 1);
+    assert_eq!(sim.component_ref("e").generation(), 0);
+}
+
+#[test]
+#[should_panic]
+fn test_removed_name_is_unknown() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("a");
+    sim.remove_component("a", EventCancellationPolicy::None);
+    sim.lookup_id("a");
+}
+
+#[test]
+fn test_events_by_reference_are_not_delivered_to_new_component() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let old = Rc::new(RefCell::new(Counter::default()));
+    let old_id = sim.add_handler("old", old.clone());
+    let old_ref = sim.component_ref("old");
+    client.emit_to_ref(Ping {}, old_ref, 1.);
+    client.emit_to_ref(Ping {}, old_ref, 2.);
+    // events emitted by identifier are delivered to any component with this identifier
+    client.emit(Ping {}, old_id, 3.);
+
+    sim.step();
+    assert_eq!(old.borrow().received, 1);
+    sim.remove_component("old", EventCancellationPolicy::None);
+    assert!(sim.is_removed(old_ref));
+    assert!(client.is_removed(old_ref));
+
+    let new = Rc::new(RefCell::new(Counter::default()));
+    let new_id = sim.add_handler("new", new.clone());
+    assert_eq!(new_id, old_id);
+    let new_ref = sim.component_ref("new");
+    assert!(!sim.is_removed(new_ref));
+    client.emit_to_ref(Ping {}, new_ref, 0.5);
+
+    sim.step_until_no_events();
+    assert_eq!(old.borrow().received, 1);
+    assert_eq!(new.borrow().received, 2);
+}
+
+#[test]
+#[should_panic(expected = "Component 0 referenced by client is removed")]
+fn test_emit_to_removed_component() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("old");
+    let client = sim.create_context("client");
+    let old_ref = sim.component_ref("old");
+    sim.remove_component("old", EventCancellationPolicy::None);
+    client.emit_to_ref(Ping {}, old_ref, 1.);
+}
+
+#[test]
+#[should_panic(
+    expected = "Component 0 referenced by client is removed (referenced generation 0, current generation 1)"
+)]
+fn test_emit_to_stale_ref_of_reused_id() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("old");
+    let client = sim.create_context("client");
+    let old_ref = sim.component_ref("old");
+    sim.remove_component("old", EventCancellationPolicy::None);
+
+    // the new component reuses the slot of removed one, so its reference differs only in generation
+    let new = Rc::new(RefCell::new(Counter::default()));
+    let new_id = sim.add_handler("new", new.clone());
+    assert_eq!(new_id, old_ref.id());
+    let new_ref = sim.component_ref("new");
+    assert_eq!(new_ref.generation(), old_ref.generation() + 1);
+    client.emit_to_ref(Ping {}, new_ref, 1.);
+    sim.step_until_no_events();
+    assert_eq!(new.borrow().received, 1);
+
+    // the stale reference is rejected instead of addressing the new component
+    client.emit_to_ref(Ping {}, old_ref, 1.);
+}
+
+#[test]
+fn test_cancel_incoming_events_on_removal() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let old_id = sim.add_handler("old", Rc::new(RefCell::new(Counter::default())));
+    client.emit(Ping {}, old_id, 1.);
+    sim.remove_component("old", EventCancellationPolicy::Incoming);
+
+    let new = Rc::new(RefCell::new(Counter::default()));
+    sim.add_handler("new", new.clone());
+    sim.step_until_no_events();
+    assert_eq!(new.borrow().received, 0);
+}
+
+#[test]
+fn test_iterate_while_removing() {
+    let mut sim = Simulation::new(123);
+    for i in 0..5 {
+        sim.create_context(format!("node-{}", i));
+    }
+    sim.remove_component("node-1", EventCancellationPolicy::None);
+
+    let components = sim.components();
+    assert_eq!(components.iter().map(|c| c.id()).collect::<Vec<_>>(), vec![0, 2, 3, 4]);
+    for component in components {
+        if component.id() % 2 == 0 {
+            let name = sim.lookup_name(component.id());
+            sim.remove_component(name, EventCancellationPolicy::None);
+        }
+    }
+    let names: Vec<String> = sim.components().iter().map(|c| sim.lookup_name(c.id())).collect();
+    assert_eq!(names, vec!["node-3"]);
+}
+
+#[test]
+fn test_reused_id_does_not_inherit_component_state() {
+    let mut sim = Simulation::new(123);
+    sim.enable_logical_clocks(LogicalClockKind::Lamport);
+    sim.enable_producer_stats(ProducerStatsConfig::new(10.));
+    let old = sim.create_context("old");
+    old.set_timer("timeout", 5.);
+    old.emit_self(Ping {}, 1.);
+    old.emit_self(Ping {}, 2.);
+    sim.step_until_time(3.);
+    assert_eq!(old.logical_time(), LogicalTime::Lamport(5));
+
+    let old_id = old.id();
+    sim.remove_component("old", EventCancellationPolicy::All);
+    let new = sim.create_context("new");
+    assert_eq!(new.id(), old_id);
+    assert!(!new.has_timer("timeout"));
+    assert_eq!(new.logical_time(), LogicalTime::Lamport(0));
+    // the timer with the same name is not replaced by the timer of removed component
+    new.set_timer("timeout", 1.);
+    assert!(new.has_timer("timeout"));
+
+    new.emit_self(Ping {}, 1.);
+    let report = sim.top_producers_between(0., 10., 10);
+    assert_eq!(report.components, vec![("old".to_owned(), 3), ("new".to_owned(), 2)]);
+}
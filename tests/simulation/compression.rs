@@ -0,0 +1,76 @@
+//! Tests of compressed streams of time-ordered records.
+
+use std::io::{BufReader, Cursor, Write};
+
+use simcore::compression::{for_each_block, is_compressed, BlockWriter};
+
+// Writes the header and one record per time with the specified number of records per block.
+fn write_records(times: &[f64], block_records: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut writer = BlockWriter::new(&mut data, 3, block_records);
+    writeln!(writer, "header").unwrap();
+    for time in times {
+        writeln!(writer, "{}", time).unwrap();
+        writer.end_record(*time).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    data
+}
+
+fn read_records(data: &[u8], from: f64, to: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for_each_block(Cursor::new(data), from, to, |block| {
+        lines.extend(std::str::from_utf8(block).unwrap().lines().map(str::to_owned));
+        true
+    })
+    .unwrap();
+    lines
+}
+
+#[test]
+fn test_decompress_all() {
+    let times = [0., 1., 1., 2.5, 3., 4., 7.];
+    let data = write_records(&times, 3);
+    assert!(is_compressed(&mut BufReader::new(data.as_slice())).unwrap());
+    let mut expected = vec!["header".to_owned()];
+    expected.extend(times.iter().map(|t| t.to_string()));
+    // the stream is readable by standard zstd decoder
+    let decoded = zstd::stream::decode_all(data.as_slice()).unwrap();
+    assert_eq!(
+        String::from_utf8(decoded).unwrap().lines().collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(read_records(&data, f64::NEG_INFINITY, f64::INFINITY), expected);
+}
+
+#[test]
+fn test_time_range() {
+    let times: Vec<f64> = (0..10).map(|t| t as f64).collect();
+    let data = write_records(&times, 2);
+    // the blocks are [header, 0, 1], [2, 3], [4, 5], [6, 7], [8, 9]
+    assert_eq!(read_records(&data, 3., 4.), ["header", "0", "1", "2", "3", "4", "5"]);
+    assert_eq!(read_records(&data, 8.5, 20.), ["header", "0", "1", "8", "9"]);
+    assert_eq!(read_records(&data, 20., 30.), ["header", "0", "1"]);
+}
+
+#[test]
+fn test_stop_reading() {
+    let times: Vec<f64> = (0..10).map(|t| t as f64).collect();
+    let data = write_records(&times, 2);
+    let mut blocks = 0;
+    for_each_block(Cursor::new(data), 0., 10., |_| {
+        blocks += 1;
+        blocks < 2
+    })
+    .unwrap();
+    assert_eq!(blocks, 2);
+}
+
+#[test]
+fn test_plain_stream() {
+    let data = b"{\"metadata\":{}}\n{\"time\":0.0}\n{\"time\":1.0}\n";
+    assert!(!is_compressed(&mut BufReader::new(data.as_slice())).unwrap());
+    let err = for_each_block(Cursor::new(data), 0., 1., |_| true).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,201 @@
+//! Tests of continuous models.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::continuous::{ContinuousModel, Integrator, ThresholdCrossed};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Probe {}
+
+// x' = -x
+struct Decay {
+    x: f64,
+    time: f64,
+}
+
+impl ContinuousModel for Decay {
+    fn state(&self) -> Vec<f64> {
+        vec![self.x]
+    }
+
+    fn set_state(&mut self, time: f64, state: &[f64]) {
+        self.time = time;
+        self.x = state[0];
+    }
+
+    fn derivatives(&self, _time: f64, state: &[f64]) -> Vec<f64> {
+        vec![-state[0]]
+    }
+}
+
+struct Recorder {
+    model: Rc<RefCell<Decay>>,
+    values: Vec<(f64, f64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, _event: Event) {
+        let model = self.model.borrow();
+        self.values.push((self.ctx.time(), model.x));
+    }
+}
+
+fn decay_error(integrator: Integrator) -> f64 {
+    let mut sim = Simulation::new(123);
+    let model = Rc::new(RefCell::new(Decay { x: 1., time: 0. }));
+    sim.add_continuous_model("decay", model.clone(), integrator);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        model,
+        values: Vec::new(),
+        ctx: sim.create_context("recorder"),
+    }));
+    sim.add_handler("recorder", recorder.clone());
+    for time in [0.5, 1., 2., 3.] {
+        recorder.borrow().ctx.emit_self(Probe {}, time);
+    }
+    sim.step_until_no_events();
+
+    let values = recorder.borrow().values.clone();
+    assert_eq!(values.len(), 4);
+    values
+        .iter()
+        .map(|(time, x)| (x - (-time).exp()).abs())
+        .fold(0., f64::max)
+}
+
+#[test]
+fn test_integrators_accuracy() {
+    let euler = decay_error(Integrator::Euler { step: 0.01 });
+    let rk4 = decay_error(Integrator::RungeKutta4 { step: 0.1 });
+    let adaptive = decay_error(Integrator::Adaptive {
+        tolerance: 1e-10,
+        max_step: 1.,
+    });
+    assert!(euler < 1e-2);
+    assert!(rk4 < 1e-6);
+    assert!(adaptive < 1e-8);
+}
+
+// Room temperature controlled by a heater, which is switched on below 18 and off above 22 degrees.
+struct Room {
+    temperature: f64,
+    heating: bool,
+}
+
+impl ContinuousModel for Room {
+    fn state(&self) -> Vec<f64> {
+        vec![self.temperature]
+    }
+
+    fn set_state(&mut self, _time: f64, state: &[f64]) {
+        self.temperature = state[0];
+    }
+
+    fn derivatives(&self, _time: f64, _state: &[f64]) -> Vec<f64> {
+        vec![if self.heating { 2. } else { -1. }]
+    }
+
+    fn thresholds(&self, _time: f64, state: &[f64]) -> Vec<f64> {
+        vec![state[0] - 18., state[0] - 22.]
+    }
+}
+
+struct Thermostat {
+    room: Rc<RefCell<Room>>,
+    switches: Vec<(f64, usize, bool)>,
+}
+
+impl EventHandler for Thermostat {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            ThresholdCrossed { index, rising } => {
+                self.switches.push((event.time, index, rising));
+                let mut room = self.room.borrow_mut();
+                if index == 0 && !rising {
+                    room.heating = true;
+                } else if index == 1 && rising {
+                    room.heating = false;
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn test_threshold_crossings() {
+    let mut sim = Simulation::new(123);
+    let room = Rc::new(RefCell::new(Room {
+        temperature: 20.,
+        heating: false,
+    }));
+    let thermostat = Rc::new(RefCell::new(Thermostat {
+        room: room.clone(),
+        switches: Vec::new(),
+    }));
+    sim.add_handler("thermostat", thermostat.clone());
+    sim.add_continuous_model("thermostat", room.clone(), Integrator::Euler { step: 0.5 });
+    sim.step_until_time(20.);
+
+    // cooling 20 -> 18 takes 2, heating 18 -> 22 takes 2, cooling 22 -> 18 takes 4
+    let switches = thermostat.borrow().switches.clone();
+    let expected = [
+        (2., 0, false),
+        (4., 1, true),
+        (8., 0, false),
+        (10., 1, true),
+        (14., 0, false),
+        (16., 1, true),
+        (20., 0, false),
+    ];
+    assert_eq!(switches.len(), expected.len());
+    for ((time, index, rising), (expected_time, expected_index, expected_rising)) in switches.iter().zip(expected) {
+        assert!((time - expected_time).abs() < 1e-6);
+        assert_eq!((*index, *rising), (expected_index, expected_rising));
+    }
+}
+
+#[test]
+fn test_models_are_advanced_to_earliest_crossing() {
+    let mut sim = Simulation::new(123);
+    let decay = Rc::new(RefCell::new(Decay { x: 1., time: 0. }));
+    sim.add_continuous_model("decay", decay.clone(), Integrator::RungeKutta4 { step: 0.1 });
+    let room = Rc::new(RefCell::new(Room {
+        temperature: 19.,
+        heating: false,
+    }));
+    sim.add_continuous_model("room", room.clone(), Integrator::RungeKutta4 { step: 0.1 });
+    let ctx = sim.create_context("probe");
+    ctx.emit_self(Probe {}, 5.);
+
+    // crossing event of room at time 1 is processed before the probe event
+    assert!(sim.step());
+    assert!((sim.time() - 1.).abs() < 1e-6);
+    assert_eq!(decay.borrow().time, sim.time());
+    assert!((decay.borrow().x - (-1f64).exp()).abs() < 1e-6);
+    assert!((room.borrow().temperature - 18.).abs() < 1e-6);
+    assert!(sim.step());
+    assert_eq!(sim.time(), 5.);
+    assert_eq!(decay.borrow().time, 5.);
+}
+
+#[test]
+fn test_no_lookahead_without_events() {
+    let mut sim = Simulation::new(123);
+    let room = Rc::new(RefCell::new(Room {
+        temperature: 19.,
+        heating: false,
+    }));
+    sim.add_continuous_model("room", room.clone(), Integrator::RungeKutta4 { step: 0.1 });
+    assert!(!sim.step());
+    assert_eq!(room.borrow().temperature, 19.);
+
+    // the model is not advanced when no crossing is found within the lookahead
+    sim.set_continuous_lookahead(0.5);
+    assert!(!sim.step());
+    assert_eq!(room.borrow().temperature, 19.);
+}
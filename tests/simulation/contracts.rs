@@ -0,0 +1,225 @@
+//! Tests of event boundedness contracts.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::limits::LimitAction;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Packet {
+    seq: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Ack {
+    seq: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Timeout {
+    seq: u64,
+}
+
+// Sends packets one by one and retransmits the packet on the first timeout, optionally canceling the previous
+// transmission.
+struct Sender {
+    receiver: Id,
+    cancel_previous: bool,
+    retransmitted: bool,
+    last_packet: Option<u64>,
+    ctx: SimulationContext,
+}
+
+impl Sender {
+    fn send(&mut self, seq: u64) {
+        if self.cancel_previous {
+            if let Some(previous) = self.last_packet {
+                self.ctx.cancel_event(previous);
+            }
+        }
+        self.last_packet = Some(self.ctx.emit(Packet { seq }, self.receiver, 3.));
+        self.ctx.emit_self(Timeout { seq }, 2.);
+    }
+}
+
+impl EventHandler for Sender {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Ack { seq } => {
+                self.ctx
+                    .cancel_events(|e| e.data.downcast_ref::<Timeout>().is_some_and(|t| t.seq == seq));
+                if seq < 3 {
+                    self.send(seq + 1);
+                }
+            }
+            Timeout { seq } => {
+                if !self.retransmitted {
+                    self.retransmitted = true;
+                    self.send(seq);
+                }
+            }
+        })
+    }
+}
+
+struct Receiver {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Receiver {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Packet { seq } => {
+                self.ctx.emit(Ack { seq }, event.src, 0.);
+            }
+        })
+    }
+}
+
+fn build(cancel_previous: bool) -> (Simulation, Rc<RefCell<Sender>>) {
+    let mut sim = Simulation::new(123);
+    let sender_ctx = sim.create_context("sender");
+    let receiver_ctx = sim.create_context("receiver");
+    let receiver_id = sim.add_handler("receiver", Rc::new(RefCell::new(Receiver { ctx: receiver_ctx })));
+    sender_ctx.declare_max_outstanding::<Packet>(receiver_id, 1);
+    let sender = Rc::new(RefCell::new(Sender {
+        receiver: receiver_id,
+        cancel_previous,
+        retransmitted: false,
+        last_packet: None,
+        ctx: sender_ctx,
+    }));
+    sim.add_handler("sender", sender.clone());
+    (sim, sender)
+}
+
+#[test]
+#[should_panic(
+    expected = "Contract violated at time 2.000: component sender has 2 outstanding Packet events to receiver, at most 1 allowed"
+)]
+fn test_duplicate_retransmission() {
+    let (mut sim, sender) = build(false);
+    sender.borrow_mut().send(1);
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_canceled_retransmission() {
+    let (mut sim, sender) = build(true);
+    sender.borrow_mut().send(1);
+    sim.step_until_no_events();
+    assert!(sim.take_contract_violations().is_empty());
+}
+
+#[test]
+fn test_pause() {
+    let (mut sim, sender) = build(false);
+    sim.set_contract_action(LimitAction::Pause);
+    sender.borrow_mut().send(1);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    let violations = sim.take_contract_violations();
+    assert_eq!(violations.len(), 1);
+    let violation = &violations[0];
+    assert_eq!(violation.component, "sender");
+    assert_eq!(violation.dst, "receiver");
+    assert_eq!(violation.event_type, "Packet");
+    assert_eq!((violation.max_outstanding, violation.outstanding), (1, 2));
+    assert_eq!(violation.time, 2.);
+    assert!(sim.take_contract_violations().is_empty());
+
+    // the run can be resumed
+    sim.step_until_time(10.);
+    // the next packet is sent while the retransmitted one is outstanding
+    assert_eq!(sim.time(), 3.);
+    assert_eq!(sim.take_contract_violations().len(), 1);
+}
+
+#[test]
+fn test_processed_events() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    client.declare_max_outstanding::<Packet>(server.id(), 2);
+    for seq in 0..10 {
+        client.emit(Packet { seq }, server.id(), 1.);
+        client.emit(Packet { seq }, server.id(), 1.);
+        sim.step_until_no_events();
+    }
+    assert!(sim.take_contract_violations().is_empty());
+}
+
+#[test]
+fn test_contract_scope() {
+    let mut sim = Simulation::new(123);
+    sim.set_contract_action(LimitAction::Pause);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let other = sim.create_context("other");
+    client.declare_max_outstanding::<Packet>(server.id(), 1);
+    client.emit(Packet { seq: 0 }, server.id(), 1.);
+    // other destinations, types and sources are not covered by the contract
+    client.emit(Packet { seq: 1 }, other.id(), 1.);
+    client.emit(Ack { seq: 1 }, server.id(), 1.);
+    other.emit(Packet { seq: 1 }, server.id(), 1.);
+    sim.step();
+    assert!(sim.take_contract_violations().is_empty());
+}
+
+#[test]
+fn test_redeclared_contract() {
+    let mut sim = Simulation::new(123);
+    sim.set_contract_action(LimitAction::Pause);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    client.declare_max_outstanding::<Packet>(server.id(), 1);
+    client.declare_max_outstanding::<Packet>(server.id(), 3);
+    for seq in 0..4 {
+        client.emit(Packet { seq }, server.id(), 1.);
+    }
+    client.cancel_events(|event| event.data.downcast_ref::<Packet>().is_some_and(|p| p.seq < 2));
+    client.emit(Packet { seq: 4 }, server.id(), 1.);
+    sim.step();
+    let violations = sim.take_contract_violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].outstanding, 4);
+    assert_eq!(violations[0].max_outstanding, 3);
+}
+
+// Returns the violations found so far by processing an event not covered by the contracts.
+fn take_violations(sim: &mut Simulation, client: &SimulationContext, server: Id) -> usize {
+    client.emit_now(Ack { seq: 0 }, server);
+    sim.step();
+    sim.take_contract_violations().len()
+}
+
+#[test]
+fn test_canceled_events_are_not_outstanding() {
+    let mut sim = Simulation::new(123);
+    sim.set_contract_action(LimitAction::Pause);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    client.declare_max_outstanding::<Packet>(server.id(), 1000);
+    let ids: Vec<_> = (0..1000)
+        .map(|seq| client.emit(Packet { seq }, server.id(), 1. + seq as f64))
+        .collect();
+    for id in ids.iter().step_by(2) {
+        client.cancel_event(*id);
+    }
+    // canceling the same event again does not change the number of outstanding events
+    client.cancel_event(ids[0]);
+    for seq in 1000..1500 {
+        client.emit(Packet { seq }, server.id(), 1.);
+    }
+    assert_eq!(take_violations(&mut sim, &client, server.id()), 0);
+    client.emit(Packet { seq: 1500 }, server.id(), 1.);
+    assert_eq!(take_violations(&mut sim, &client, server.id()), 1);
+
+    // the delivered events are not outstanding too
+    sim.step_until_no_events();
+    client.emit(Packet { seq: 1501 }, server.id(), 1.);
+    assert_eq!(take_violations(&mut sim, &client, server.id()), 0);
+}
@@ -0,0 +1,248 @@
+//! Tests of co-simulation with external processes.
+
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::net::TcpListener;
+use std::rc::Rc;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use simcore::cosim::CosimBridge;
+use simcore::run::TerminationReason;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Response {
+    id: u64,
+}
+
+struct Client {
+    responses: Vec<(f64, u64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Client {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Response { id } => {
+                self.responses.push((self.ctx.time(), id));
+            }
+        })
+    }
+}
+
+fn add_client(sim: &mut Simulation) -> Rc<RefCell<Client>> {
+    let client = Rc::new(RefCell::new(Client {
+        responses: Vec::new(),
+        ctx: sim.create_context("client"),
+    }));
+    sim.add_handler("client", client.clone());
+    client
+}
+
+// External simulator replying to each request after one time unit, returns the received messages.
+fn run_echo_peer(listener: TcpListener) -> Vec<Value> {
+    let (stream, _) = listener.accept().unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut received = Vec::new();
+    let mut pending: Vec<(f64, u64)> = Vec::new();
+    for line in BufReader::new(stream).lines() {
+        let message: Value = serde_json::from_str(&line.unwrap()).unwrap();
+        received.push(message.clone());
+        match message["type"].as_str().unwrap() {
+            "event" => pending.push((
+                message["time"].as_f64().unwrap() + 1.,
+                message["data"]["id"].as_u64().unwrap(),
+            )),
+            "advance" => {
+                // without the limit the earliest pending responses are sent
+                let until = message["until"]
+                    .as_f64()
+                    .or_else(|| pending.iter().map(|(time, _)| *time).reduce(f64::min));
+                let mut replies = Vec::new();
+                if let Some(until) = until {
+                    pending.retain(|(time, id)| {
+                        let ready = *time <= until;
+                        if ready {
+                            replies.push(json!({"type": "event", "time": time, "src": "echo", "dst": "client", "event": "Response", "data": {"id": id}}));
+                        }
+                        !ready
+                    });
+                }
+                replies.push(json!({"type": "grant", "time": until}));
+                for reply in replies {
+                    writeln!(writer, "{}", reply).unwrap();
+                }
+                if until.is_none() {
+                    break;
+                }
+            }
+            _ => panic!("Unexpected message {}", message),
+        }
+    }
+    received
+}
+
+fn connect_echo(sim: &mut Simulation) -> (Id, thread::JoinHandle<Vec<Value>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let peer = thread::spawn(move || run_echo_peer(listener));
+    let mut bridge = CosimBridge::connect(addr).unwrap();
+    bridge.register_event::<Response>();
+    (sim.add_cosim_bridge("echo", bridge), peer)
+}
+
+#[test]
+fn test_tcp_peer() {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let (echo_id, peer) = connect_echo(&mut sim);
+    client.borrow().ctx.emit(Request { id: 1 }, echo_id, 1.);
+    client.borrow().ctx.emit(Request { id: 2 }, echo_id, 1.5);
+    sim.step_until_no_events();
+    assert_eq!(client.borrow().responses, vec![(2., 1), (2.5, 2)]);
+    assert_eq!(sim.time(), 2.5);
+
+    let received = peer.join().unwrap();
+    let first = &received[1];
+    assert_eq!(
+        first,
+        &json!({"type": "event", "time": 1., "src": "client", "dst": "echo", "event": "Request", "data": {"id": 1}})
+    );
+    // the peer is asked to advance up to the next pending event
+    assert_eq!(received[0], json!({"type": "advance", "until": 1.}));
+    assert_eq!(received[2], json!({"type": "advance", "until": 1.5}));
+    assert_eq!(received.last().unwrap(), &json!({"type": "advance", "until": null}));
+}
+
+#[test]
+fn test_step_until_time() {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let (echo_id, peer) = connect_echo(&mut sim);
+    client.borrow().ctx.emit(Request { id: 1 }, echo_id, 1.);
+    client.borrow().ctx.emit(Request { id: 2 }, echo_id, 3.);
+    assert!(sim.step_until_time(2.5));
+    assert_eq!(client.borrow().responses, vec![(2., 1)]);
+    sim.step_until_no_events();
+    assert_eq!(client.borrow().responses, vec![(2., 1), (4., 2)]);
+    peer.join().unwrap();
+}
+
+// Writer collecting the messages sent by the simulation.
+#[derive(Clone, Default)]
+struct SharedWriter(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn scripted_bridge(replies: &[Value]) -> (CosimBridge, SharedWriter) {
+    let replies: String = replies.iter().map(|reply| format!("{}\n", reply)).collect();
+    let writer = SharedWriter::default();
+    let mut bridge = CosimBridge::new(Cursor::new(replies), writer.clone());
+    bridge.register_event::<Response>();
+    (bridge, writer)
+}
+
+#[test]
+fn test_partial_grant() {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let (bridge, writer) = scripted_bridge(&[
+        json!({"type": "grant", "time": 1.}),
+        json!({"type": "event", "time": 2., "dst": "client", "event": "Response", "data": {"id": 7}}),
+        json!({"type": "grant", "time": 2.}),
+        json!({"type": "grant", "time": null}),
+    ]);
+    sim.add_cosim_bridge("peer", bridge);
+    client.borrow().ctx.emit_self(Response { id: 0 }, 3.);
+    sim.step_until_no_events();
+    assert_eq!(client.borrow().responses, vec![(2., 7), (3., 0)]);
+
+    let sent = String::from_utf8(writer.0.borrow().clone()).unwrap();
+    let sent: Vec<Value> = sent.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(
+        sent,
+        vec![
+            json!({"type": "advance", "until": 3.}),
+            json!({"type": "advance", "until": 3.}),
+            json!({"type": "advance", "until": 3.}),
+        ]
+    );
+}
+
+#[test]
+fn test_unregistered_event() {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let (bridge, _writer) = scripted_bridge(&[
+        json!({"type": "event", "time": 1., "dst": "client", "event": "Request", "data": {"id": 1}}),
+        json!({"type": "grant", "time": null}),
+    ]);
+    sim.add_cosim_bridge("peer", bridge);
+    sim.step_until_no_events();
+    assert_eq!(
+        sim.take_peer_error().unwrap(),
+        "Co-simulation peer of peer failed: event type Request is not registered, see CosimBridge::register_event"
+    );
+    assert!(client.borrow().responses.is_empty());
+}
+
+#[test]
+fn test_unknown_destination() {
+    let mut sim = Simulation::new(123);
+    let (bridge, _writer) = scripted_bridge(&[
+        json!({"type": "event", "time": 1., "dst": "server", "event": "Response", "data": {"id": 1}}),
+        json!({"type": "grant", "time": null}),
+    ]);
+    sim.add_cosim_bridge("peer", bridge);
+    let result = sim.run();
+    assert_eq!(
+        result.termination,
+        TerminationReason::PeerError(
+            "Co-simulation peer of peer failed: destination component server does not exist".to_owned()
+        )
+    );
+}
+
+#[test]
+fn test_closed_connection() {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let (bridge, writer) = scripted_bridge(&[
+        json!({"type": "event", "time": 1., "dst": "client", "event": "Response", "data": {"id": 1}}),
+        json!({"type": "grant", "time": 1.}),
+    ]);
+    let peer = sim.add_cosim_bridge("peer", bridge);
+    client.borrow().ctx.emit(Request { id: 2 }, peer, 2.);
+    client.borrow().ctx.emit_self(Response { id: 3 }, 3.);
+
+    // the run is paused when the peer fails
+    let result = sim.run();
+    assert_eq!(
+        result.termination,
+        TerminationReason::PeerError("Co-simulation peer of peer failed: connection is closed".to_owned())
+    );
+    assert_eq!(client.borrow().responses, vec![(1., 1)]);
+    // the resumed run proceeds without the peer
+    let result = sim.run();
+    assert_eq!(result.termination, TerminationReason::Drained);
+    assert_eq!(client.borrow().responses, vec![(1., 1), (3., 3)]);
+    assert!(sim.take_peer_error().is_some());
+    let sent = String::from_utf8(writer.0.borrow().clone()).unwrap();
+    assert_eq!(sent.lines().count(), 2);
+}
@@ -0,0 +1,98 @@
+//! Tests of default event delays.
+
+use rand::distributions::Uniform;
+use serde::Serialize;
+
+use simcore::delay::DelayProfile;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Request {}
+
+#[derive(Clone, Serialize)]
+struct Heartbeat {}
+
+fn build() -> Simulation {
+    let mut sim = Simulation::new(123);
+    for name in ["client1", "client2", "server1", "server2", "monitor"] {
+        sim.create_context(name);
+    }
+    sim.set_component_group("client1", "clients");
+    sim.set_component_group("client2", "clients");
+    sim.set_component_group("server1", "servers");
+    sim.set_component_group("server2", "servers");
+    sim.set_group_delay("clients", "servers", DelayProfile::constant(1.));
+    sim.set_group_delay("servers", "clients", DelayProfile::constant(2.));
+    sim.set_group_delay("servers", "servers", DelayProfile::constant(0.1));
+    sim
+}
+
+fn emit_delay<T: simcore::EventData>(sim: &mut Simulation, data: T, src: &str, dst: &str) -> f64 {
+    let ctx = sim.create_context(src);
+    let dst_id = sim.lookup_id(dst);
+    let event_id = ctx.emit_with_default_delay(data, dst_id);
+    let event = sim.dump_events().into_iter().find(|e| e.id == event_id).unwrap();
+    event.time - sim.time()
+}
+
+#[test]
+fn test_group_delays() {
+    let mut sim = build();
+    assert_eq!(emit_delay(&mut sim, Request {}, "client1", "server2"), 1.);
+    assert_eq!(emit_delay(&mut sim, Request {}, "server2", "client2"), 2.);
+    assert_eq!(emit_delay(&mut sim, Request {}, "server1", "server2"), 0.1);
+}
+
+#[test]
+fn test_profile_precedence() {
+    let mut sim = build();
+    sim.set_default_delay(DelayProfile::constant(5.));
+    sim.set_event_delay::<Heartbeat>(DelayProfile::constant(0.01));
+    // event type profile overrides group profile
+    assert_eq!(emit_delay(&mut sim, Heartbeat {}, "client1", "server1"), 0.01);
+    assert_eq!(emit_delay(&mut sim, Request {}, "client1", "server1"), 1.);
+    // default profile is used for components without group and pairs without profile
+    assert_eq!(emit_delay(&mut sim, Request {}, "monitor", "server1"), 5.);
+    assert_eq!(emit_delay(&mut sim, Request {}, "client1", "client2"), 5.);
+    // group can be changed
+    sim.set_component_group("monitor", "servers");
+    assert_eq!(emit_delay(&mut sim, Request {}, "monitor", "server1"), 0.1);
+}
+
+#[test]
+fn test_random_delays_are_deterministic() {
+    let run = || {
+        let mut sim = build();
+        sim.set_group_delay("clients", "servers", DelayProfile::distribution(Uniform::new(1., 3.)));
+        (0..10)
+            .map(|_| emit_delay(&mut sim, Request {}, "client1", "server1"))
+            .collect::<Vec<_>>()
+    };
+    let delays = run();
+    assert!(delays.iter().all(|delay| (1. ..3.).contains(delay)));
+    assert!(delays.windows(2).any(|w| w[0] != w[1]));
+    assert_eq!(delays, run());
+}
+
+#[test]
+fn test_time_scale_is_applied() {
+    let mut sim = build();
+    let ctx = sim.create_context("client1");
+    let server_id = sim.lookup_id("server1");
+    let _scope = ctx.scale_time(3.);
+    ctx.emit_with_default_delay(Request {}, server_id);
+    assert_eq!(sim.dump_events()[0].time, 3.);
+}
+
+#[test]
+#[should_panic(expected = "No default delay is configured for event Request from monitor to server1")]
+fn test_no_matching_profile() {
+    let mut sim = build();
+    emit_delay(&mut sim, Request {}, "monitor", "server1");
+}
+
+#[test]
+#[should_panic(expected = "Delay must be non-negative")]
+fn test_negative_constant_delay() {
+    DelayProfile::constant(-1.);
+}
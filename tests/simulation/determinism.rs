@@ -0,0 +1,79 @@
+//! Tests of simulation determinism.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Ping {
+    hops: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Timeout {
+    value: f64,
+}
+
+struct Node {
+    ctx: SimulationContext,
+    peers: Vec<Id>,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Ping { hops } => {
+                if hops < 20 {
+                    // u32 is used instead of usize, whose sampling depends on the pointer width
+                    let peer = self.peers[self.ctx.gen_range(0..self.peers.len() as u32) as usize];
+                    let delay = self.ctx.gen_range(0.1..1.0);
+                    self.ctx.emit(Ping { hops: hops + 1 }, peer, delay);
+                }
+                let timeout = self.ctx.emit_self(Timeout { value: self.ctx.rand() }, 0.5);
+                if self.ctx.rand() < 0.5 {
+                    self.ctx.cancel_event(timeout);
+                }
+            }
+            Timeout { .. } => {}
+        })
+    }
+}
+
+fn run(seed: u64) -> u64 {
+    let mut sim = Simulation::new(seed);
+    sim.enable_memory_trace();
+    let names: Vec<_> = (0..5).map(|i| format!("node{}", i)).collect();
+    let contexts: Vec<_> = names.iter().map(|name| sim.create_context(name)).collect();
+    let ids: Vec<_> = contexts.iter().map(|ctx| ctx.id()).collect();
+    for ctx in contexts {
+        let name = ctx.name().to_owned();
+        let node = Node {
+            ctx,
+            peers: ids.clone(),
+        };
+        sim.add_handler(name, Rc::new(RefCell::new(node)));
+    }
+    let client = sim.create_context("client");
+    for &id in ids.iter() {
+        client.emit(Ping { hops: 0 }, id, 0.);
+    }
+    sim.step_until_no_events();
+    let digest = sim.trace().digest();
+    digest
+}
+
+#[test]
+fn test_trace_digest_is_reproducible() {
+    assert_eq!(run(123), run(123));
+    assert_ne!(run(123), run(456));
+}
+
+// The digest must be the same on all platforms, the test fails if the event processing order
+// or the generated random values differ.
+#[test]
+fn test_trace_digest_is_platform_independent() {
+    assert_eq!(run(123), 8103609553920897138);
+}
@@ -0,0 +1,113 @@
+//! Tests of built-in distribution samplers.
+
+use simcore::{Simulation, SimulationContext};
+
+const SAMPLES: usize = 20000;
+
+fn mean<F: Fn(&SimulationContext) -> f64>(sample: F) -> f64 {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    (0..SAMPLES).map(|_| sample(&ctx)).sum::<f64>() / SAMPLES as f64
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 0.02 * expected.abs().max(1.),
+        "Sample mean {} differs from expected {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_sample_means() {
+    assert_close(mean(|ctx| ctx.sample_exponential(0.5)), 2.);
+    assert_close(mean(|ctx| ctx.sample_normal(5., 2.)), 5.);
+    assert_close(mean(|ctx| ctx.sample_lognormal(0., 0.5)), (0.125f64).exp());
+    assert_close(mean(|ctx| ctx.sample_pareto(1., 3.)), 1.5);
+    assert_close(mean(|ctx| ctx.sample_weibull(2., 1.)), 2.);
+    assert_close(mean(|ctx| ctx.sample_weibull(1., 2.)), 0.886227);
+}
+
+#[test]
+fn test_sample_ranges() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    for _ in 0..1000 {
+        assert!(ctx.sample_exponential(1.) >= 0.);
+        assert!(ctx.sample_lognormal(1., 2.) > 0.);
+        assert!(ctx.sample_pareto(3., 1.5) >= 3.);
+        assert!(ctx.sample_weibull(1., 0.5) >= 0.);
+        assert!((1..=7).contains(&ctx.sample_zipf(7, 1.5)));
+    }
+}
+
+#[test]
+fn test_zipf_frequencies() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let mut counts = [0usize; 5];
+    for _ in 0..SAMPLES {
+        counts[ctx.sample_zipf(5, 1.) as usize - 1] += 1;
+    }
+    let harmonic: f64 = (1..=5).map(|k| 1. / k as f64).sum();
+    for (i, count) in counts.iter().enumerate() {
+        let expected = 1. / (i + 1) as f64 / harmonic;
+        assert!((*count as f64 / SAMPLES as f64 - expected).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_samples_are_reproducible() {
+    let samples = |seed| {
+        let mut sim = Simulation::new(seed);
+        let ctx = sim.create_context("comp");
+        (0..100)
+            .map(|_| {
+                (
+                    ctx.sample_normal(0., 1.),
+                    ctx.sample_lognormal(0., 1.),
+                    ctx.sample_pareto(1., 2.),
+                    ctx.sample_zipf(100, 1.1),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(samples(123), samples(123));
+    assert_ne!(samples(123), samples(321));
+}
+
+#[test]
+#[should_panic(expected = "Rate of exponential distribution must be positive, got 0")]
+fn test_invalid_exponential_rate() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").sample_exponential(0.);
+}
+
+#[test]
+#[should_panic(expected = "Standard deviation of normal distribution must be non-negative, got -1")]
+fn test_invalid_normal_std_dev() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").sample_normal(0., -1.);
+}
+
+#[test]
+#[should_panic(expected = "Invalid parameters of log-normal distribution")]
+fn test_invalid_lognormal_sigma() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").sample_lognormal(0., f64::INFINITY);
+}
+
+#[test]
+#[should_panic(expected = "Invalid parameters of Pareto distribution")]
+fn test_invalid_pareto_shape() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").sample_pareto(1., 0.);
+}
+
+#[test]
+#[should_panic(expected = "Invalid parameters of Zipf distribution")]
+fn test_invalid_zipf_size() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").sample_zipf(0, 1.);
+}
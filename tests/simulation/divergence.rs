@@ -0,0 +1,252 @@
+//! Tests of cross-checking the run against reference trace.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::divergence::DivergenceGuardConfig;
+use simcore::limits::LimitAction;
+use simcore::run::TerminationReason;
+use simcore::trace_file::TraceFileConfig;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Tick {
+    round: u64,
+    value: u64,
+}
+
+#[derive(Clone, Default)]
+struct Variant {
+    rounds: u64,
+    // round whose value is changed
+    changed_round: Option<u64>,
+    // emits and cancels an extra event in each round, which shifts the event identifiers
+    extra_event: bool,
+}
+
+struct Counter {
+    ctx: SimulationContext,
+    variant: Variant,
+}
+
+impl EventHandler for Counter {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Tick { round, .. } => {
+                if self.variant.extra_event {
+                    let id = self.ctx.emit_self(Tick { round, value: 0 }, 10.);
+                    self.ctx.cancel_event(id);
+                }
+                if round + 1 < self.variant.rounds {
+                    let value = if self.variant.changed_round == Some(round + 1) {
+                        0
+                    } else {
+                        round + 1
+                    };
+                    self.ctx.emit_self(
+                        Tick {
+                            round: round + 1,
+                            value,
+                        },
+                        1.,
+                    );
+                }
+            }
+        })
+    }
+}
+
+fn build(variant: Variant) -> Simulation {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("counter");
+    ctx.emit_self(Tick { round: 0, value: 0 }, 0.);
+    sim.add_handler("counter", Rc::new(RefCell::new(Counter { ctx, variant })));
+    sim
+}
+
+fn record_reference(name: &str, rounds: u64, payloads: bool) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("simcore-divergence-{}-{}.jsonl", name, std::process::id()));
+    let mut sim = build(Variant {
+        rounds,
+        ..Default::default()
+    });
+    let mut config = TraceFileConfig::new(&path);
+    config.payloads = payloads;
+    sim.enable_trace_file(config);
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    path
+}
+
+fn pausing_config(path: &PathBuf) -> DivergenceGuardConfig {
+    let mut config = DivergenceGuardConfig::new(path);
+    config.action = LimitAction::Pause;
+    config
+}
+
+#[test]
+fn test_no_divergence() {
+    let path = record_reference("same", 10, true);
+    let mut sim = build(Variant {
+        rounds: 10,
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(DivergenceGuardConfig::new(&path));
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(sim.time(), 9.);
+    assert!(sim.take_divergence().is_none());
+}
+
+#[test]
+fn test_changed_payload() {
+    let path = record_reference("payload", 10, true);
+    let mut sim = build(Variant {
+        rounds: 10,
+        changed_round: Some(6),
+        ..Default::default()
+    });
+    let mut config = pausing_config(&path);
+    config.context = 3;
+    sim.enable_divergence_guard(config);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+
+    // the run is paused after processing the divergent event
+    assert_eq!(sim.time(), 6.);
+    let divergence = sim.take_divergence().unwrap();
+    assert_eq!(divergence.index, 6);
+    assert_eq!(divergence.time, 6.);
+    let expected = divergence.expected.as_ref().unwrap();
+    let actual = divergence.actual.as_ref().unwrap();
+    assert_eq!(expected.data.as_ref().unwrap()["value"], 6);
+    assert_eq!(actual.data.as_ref().unwrap()["value"], 0);
+    assert_eq!(expected.id, actual.id);
+    let rounds: Vec<_> = divergence
+        .previous
+        .iter()
+        .map(|record| record.data.as_ref().unwrap()["round"].as_u64().unwrap())
+        .collect();
+    assert_eq!(rounds, vec![3, 4, 5]);
+    assert!(divergence.pending_events.starts_with("Pending events: 1 total"));
+
+    let output = divergence.to_string();
+    assert!(output.starts_with("Run diverged from reference trace at processed event 6 (time 6.000)"));
+    assert!(output.contains(r#"- {"kind":"processed","time":6.0"#));
+    assert!(output.contains(r#"+ {"kind":"processed","time":6.0"#));
+
+    // the guard is disarmed, so the run can be resumed
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 9.);
+    assert!(sim.take_divergence().is_none());
+}
+
+#[test]
+fn test_abort() {
+    let path = record_reference("abort", 5, true);
+    let mut sim = build(Variant {
+        rounds: 5,
+        changed_round: Some(2),
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(DivergenceGuardConfig::new(&path));
+    let result = sim.run();
+    std::fs::remove_file(&path).unwrap();
+    match result.termination {
+        TerminationReason::Error(message) => {
+            assert!(message.starts_with("Run diverged from reference trace at processed event 2 (time 2.000)"))
+        }
+        reason => panic!("Unexpected termination reason {:?}", reason),
+    }
+}
+
+#[test]
+fn test_extra_events() {
+    let path = record_reference("extra", 5, true);
+    let mut sim = build(Variant {
+        rounds: 7,
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(pausing_config(&path));
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    let divergence = sim.take_divergence().unwrap();
+    assert_eq!(divergence.index, 5);
+    assert!(divergence.expected.is_none());
+    assert_eq!(divergence.actual.as_ref().unwrap().time, 5.);
+    assert!(divergence.to_string().contains("- <end of reference trace>"));
+}
+
+#[test]
+fn test_missing_events() {
+    let path = record_reference("missing", 5, true);
+    let mut sim = build(Variant {
+        rounds: 3,
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(pausing_config(&path));
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    let divergence = sim.take_divergence().unwrap();
+    assert_eq!(divergence.index, 3);
+    assert_eq!(divergence.time, 2.);
+    assert_eq!(divergence.expected.unwrap().time, 3.);
+    assert!(divergence.actual.is_none());
+    assert!(divergence.pending_events.starts_with("Pending events: 0 total"));
+}
+
+#[test]
+fn test_ignored_fields() {
+    let path = record_reference("ignored", 5, true);
+    let variant = Variant {
+        rounds: 5,
+        extra_event: true,
+        ..Default::default()
+    };
+
+    let mut sim = build(variant.clone());
+    sim.enable_divergence_guard(pausing_config(&path));
+    sim.step_until_no_events();
+    assert_eq!(sim.take_divergence().unwrap().index, 1);
+
+    let mut sim = build(variant);
+    let mut config = pausing_config(&path);
+    config.ignored_fields = vec!["id".to_owned()];
+    sim.enable_divergence_guard(config);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert!(sim.take_divergence().is_none());
+}
+
+#[test]
+fn test_reference_without_payloads() {
+    let path = record_reference("no-payloads", 5, false);
+    let mut sim = build(Variant {
+        rounds: 5,
+        changed_round: Some(2),
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(pausing_config(&path));
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert!(sim.take_divergence().is_none());
+}
+
+#[test]
+fn test_disable() {
+    let path = record_reference("disable", 5, true);
+    let mut sim = build(Variant {
+        rounds: 5,
+        changed_round: Some(2),
+        ..Default::default()
+    });
+    sim.enable_divergence_guard(DivergenceGuardConfig::new(&path));
+    sim.step();
+    sim.disable_divergence_guard();
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(sim.time(), 4.);
+}
@@ -0,0 +1,137 @@
+//! Tests of emitting events relative to other events.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Task {
+    name: &'static str,
+}
+
+#[derive(Clone, Serialize)]
+struct Start {}
+
+struct Worker {
+    log: Vec<(&'static str, f64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Task { name } => {
+                self.log.push((name, self.ctx.time()));
+            }
+            Start {} => {
+                // event scheduled relative to the event being processed
+                self.ctx
+                    .emit_after(Task { name: "cleanup" }, self.ctx.id(), event.id, 3.);
+            }
+        })
+    }
+}
+
+fn build() -> (Simulation, SimulationContext, Rc<RefCell<Worker>>, Id) {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("client");
+    let worker = Rc::new(RefCell::new(Worker {
+        log: Vec::new(),
+        ctx: sim.create_context("worker"),
+    }));
+    let worker_id = sim.add_handler("worker", worker.clone());
+    (sim, ctx, worker, worker_id)
+}
+
+#[test]
+fn test_chain_and_fan_out() {
+    let (mut sim, ctx, worker, worker_id) = build();
+    let a = ctx.emit(Task { name: "a" }, worker_id, 1.);
+    let b = ctx.emit_after(Task { name: "b" }, worker_id, a, 2.);
+    ctx.emit_after(Task { name: "c1" }, worker_id, b, 1.);
+    ctx.emit_after(Task { name: "c2" }, worker_id, b, 0.);
+    ctx.emit(Task { name: "other" }, worker_id, 3.);
+    // dependent events are not pending until the preceding event is processed
+    assert_eq!(sim.dump_events().len(), 2);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        worker.borrow().log,
+        vec![("a", 1.), ("b", 3.), ("c2", 3.), ("other", 3.), ("c1", 4.)]
+    );
+}
+
+#[test]
+fn test_relative_to_current_event() {
+    let (mut sim, ctx, worker, worker_id) = build();
+    ctx.emit(Start {}, worker_id, 2.);
+    sim.step_until_no_events();
+    assert_eq!(worker.borrow().log, vec![("cleanup", 5.)]);
+}
+
+#[test]
+fn test_cancellation_is_transitive() {
+    let (mut sim, ctx, worker, worker_id) = build();
+    let a = ctx.emit(Task { name: "a" }, worker_id, 1.);
+    let b = ctx.emit_after(Task { name: "b" }, worker_id, a, 1.);
+    ctx.emit_after(Task { name: "c" }, worker_id, b, 1.);
+    let d = ctx.emit(Task { name: "d" }, worker_id, 1.);
+    let e = ctx.emit_after(Task { name: "e" }, worker_id, d, 1.);
+    ctx.emit_after(Task { name: "f" }, worker_id, e, 1.);
+    ctx.cancel_event(a);
+    // dependent event can be canceled before its time is resolved
+    ctx.cancel_event(e);
+    sim.step_until_no_events();
+
+    assert_eq!(worker.borrow().log, vec![("d", 1.)]);
+}
+
+#[test]
+fn test_cancel_events_matches_dependent_events() {
+    let (mut sim, ctx, worker, worker_id) = build();
+    let a = ctx.emit(Task { name: "a" }, worker_id, 1.);
+    let b = ctx.emit_after(Task { name: "b" }, worker_id, a, 1.);
+    ctx.emit_after(Task { name: "c" }, worker_id, b, 1.);
+    let d = ctx.emit(Task { name: "d" }, worker_id, 1.);
+    let e = ctx.emit_after(Task { name: "e" }, worker_id, d, 1.);
+
+    let canceled = sim.cancel_and_get_events(|event| event.id == b);
+    assert_eq!(canceled.len(), 1);
+    assert_eq!(canceled[0].id, b);
+    // the time of dependent event is not resolved yet
+    assert!(canceled[0].time.is_nan());
+    sim.cancel_events(|event| event.id == e);
+    sim.step_until_no_events();
+
+    assert_eq!(worker.borrow().log, vec![("a", 1.), ("d", 1.)]);
+}
+
+#[test]
+fn test_time_scale_is_applied() {
+    let (mut sim, ctx, worker, worker_id) = build();
+    let a = ctx.emit(Task { name: "a" }, worker_id, 1.);
+    {
+        let _scope = ctx.scale_time(2.);
+        ctx.emit_after(Task { name: "b" }, worker_id, a, 1.5);
+    }
+    sim.step_until_no_events();
+    assert_eq!(worker.borrow().log, vec![("a", 1.), ("b", 4.)]);
+}
+
+#[test]
+#[should_panic(expected = "Event 10 does not exist")]
+fn test_unknown_event() {
+    let (_sim, ctx, _worker, worker_id) = build();
+    ctx.emit_after(Task { name: "a" }, worker_id, 10, 1.);
+}
+
+#[test]
+#[should_panic(expected = "Event delay is negative")]
+fn test_negative_delay() {
+    let (_sim, ctx, _worker, worker_id) = build();
+    let a = ctx.emit(Task { name: "a" }, worker_id, 1.);
+    ctx.emit_after(Task { name: "b" }, worker_id, a, -1.);
+}
@@ -0,0 +1,133 @@
+//! Tests of emitting events on behalf of other components.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Response {
+    id: u32,
+}
+
+struct Proxy {
+    server_id: Id,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Proxy {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.ctx.emit_as(Request { id }, event.src, self.server_id, 1.);
+            }
+        })
+    }
+}
+
+struct Server {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                // reply directly to the original source
+                self.ctx.emit(Response { id }, event.src, 1.);
+            }
+        })
+    }
+}
+
+struct Client {
+    responses: Vec<(u32, Id, f64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Client {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Response { id } => {
+                self.responses.push((id, event.src, self.ctx.time()));
+            }
+        })
+    }
+}
+
+fn build(allow: bool) -> (Simulation, Rc<RefCell<Client>>) {
+    let mut sim = Simulation::new(123);
+    let client = Rc::new(RefCell::new(Client {
+        responses: Vec::new(),
+        ctx: sim.create_context("client"),
+    }));
+    sim.add_handler("client", client.clone());
+    let server_ctx = sim.create_context("server");
+    let server_id = sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx })));
+    let proxy_ctx = sim.create_context("proxy");
+    sim.add_handler(
+        "proxy",
+        Rc::new(RefCell::new(Proxy {
+            server_id,
+            ctx: proxy_ctx,
+        })),
+    );
+    if allow {
+        sim.allow_emit_as("proxy");
+    }
+    (sim, client)
+}
+
+#[test]
+fn test_proxy_preserves_source() {
+    let (mut sim, client) = build(true);
+    let proxy_id = sim.lookup_id("proxy");
+    let server_id = sim.lookup_id("server");
+    for id in 0..3 {
+        client.borrow().ctx.emit(Request { id }, proxy_id, 1.);
+    }
+    sim.step_until_no_events();
+
+    // server replies directly to client, since the requests forwarded by proxy have client as source
+    let responses = client.borrow().responses.clone();
+    assert_eq!(
+        responses,
+        vec![(0, server_id, 3.), (1, server_id, 3.), (2, server_id, 3.)]
+    );
+}
+
+#[test]
+fn test_emit_as_self_is_allowed() {
+    let (mut sim, client) = build(false);
+    let ctx = sim.create_context("other");
+    let client_id = client.borrow().ctx.id();
+    ctx.emit_as(Response { id: 1 }, ctx.id(), client_id, 1.);
+    sim.step_until_no_events();
+    assert_eq!(client.borrow().responses, vec![(1, ctx.id(), 1.)]);
+}
+
+#[test]
+#[should_panic(expected = "Component proxy is not allowed to emit events on behalf of other components")]
+fn test_emit_as_not_allowed() {
+    let (mut sim, client) = build(false);
+    let proxy_id = sim.lookup_id("proxy");
+    client.borrow().ctx.emit(Request { id: 0 }, proxy_id, 1.);
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "Component proxy is not allowed to emit events on behalf of other components")]
+fn test_emit_ordered_as_not_allowed() {
+    let (mut sim, client) = build(false);
+    let server_id = sim.lookup_id("server");
+    let client_id = client.borrow().ctx.id();
+    let proxy_ctx = sim.create_context("proxy");
+    proxy_ctx.emit_ordered_as(Response { id: 0 }, server_id, client_id, 1.);
+}
@@ -0,0 +1,129 @@
+//! Tests of emitting events at absolute times.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventCancellationPolicy, EventHandler, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Sample {
+    value: u32,
+}
+
+struct Recorder {
+    samples: Vec<(f64, u32)>,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Sample { value } => {
+                self.samples.push((event.time, value));
+            }
+        })
+    }
+}
+
+#[test]
+fn test_trace_timestamps_are_exact() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    let recorder = Rc::new(RefCell::new(Recorder { samples: Vec::new() }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+
+    // 0.7 + (2.9 - 0.7) != 2.9 in floating-point arithmetic
+    let timestamps = [0.9, 1.1, 2.9, 3.3, 5.3];
+    sim.step_until_time(0.7);
+    for (i, time) in timestamps.iter().enumerate() {
+        if i % 2 == 0 {
+            ctx.emit_at(Sample { value: i as u32 }, recorder_id, *time);
+        } else {
+            ctx.emit_ordered_at(Sample { value: i as u32 }, recorder_id, *time);
+        }
+    }
+    sim.step_until_no_events();
+
+    let times: Vec<f64> = recorder.borrow().samples.iter().map(|(time, _)| *time).collect();
+    assert_eq!(times, timestamps);
+}
+
+#[test]
+fn test_time_scale_is_ignored() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    let _slowdown = ctx.scale_time(2.);
+    ctx.emit_self(Sample { value: 0 }, 1.);
+    ctx.emit_self_at(Sample { value: 1 }, 1.);
+    ctx.emit_ordered_self_at(Sample { value: 2 }, 1.5);
+
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![1., 1.5, 2.]);
+}
+
+#[test]
+fn test_emit_now_at_current_time() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    sim.step_until_time(5.);
+    ctx.emit_self_at(Sample { value: 0 }, 5.);
+    assert!(sim.step());
+    assert_eq!(sim.time(), 5.);
+}
+
+#[test]
+fn test_emit_as_at() {
+    let mut sim = Simulation::new(123);
+    let proxy_ctx = sim.create_context("proxy");
+    let client_ctx = sim.create_context("client");
+    let recorder = Rc::new(RefCell::new(Recorder { samples: Vec::new() }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+    sim.allow_emit_as("proxy");
+
+    proxy_ctx.emit_as_at(Sample { value: 1 }, client_ctx.id(), recorder_id, 2.);
+    proxy_ctx.emit_ordered_as_at(Sample { value: 2 }, client_ctx.id(), recorder_id, 3.);
+    let events = sim.dump_events();
+    assert!(events.iter().all(|e| e.src == client_ctx.id()));
+    sim.step_until_no_events();
+    assert_eq!(recorder.borrow().samples, vec![(2., 1), (3., 2)]);
+}
+
+#[test]
+fn test_emit_to_ref_at() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    let recorder = Rc::new(RefCell::new(Recorder { samples: Vec::new() }));
+    sim.add_handler("recorder", recorder.clone());
+    let recorder_ref = sim.component_ref("recorder");
+
+    ctx.emit_to_ref_at(Sample { value: 1 }, recorder_ref, 1.);
+    ctx.emit_to_ref_at(Sample { value: 2 }, recorder_ref, 3.);
+    sim.step_until_time(2.);
+    sim.remove_component("recorder", EventCancellationPolicy::None);
+    let new_recorder = Rc::new(RefCell::new(Recorder { samples: Vec::new() }));
+    sim.add_handler("recorder", new_recorder.clone());
+    sim.step_until_no_events();
+
+    assert_eq!(recorder.borrow().samples, vec![(1., 1)]);
+    // the event to removed component is not delivered to the new one
+    assert!(new_recorder.borrow().samples.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Event time 1.5 is earlier than the current time 2")]
+fn test_emit_at_past_time() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    sim.step_until_time(2.);
+    ctx.emit_self_at(Sample { value: 0 }, 1.5);
+}
+
+#[test]
+#[should_panic(expected = "Event order is broken!")]
+fn test_emit_ordered_at_before_last_ordered() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("source");
+    ctx.emit_ordered_self_at(Sample { value: 0 }, 2.);
+    ctx.emit_ordered_self_at(Sample { value: 1 }, 1.);
+}
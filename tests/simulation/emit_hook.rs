@@ -0,0 +1,141 @@
+//! Tests of context-level event emission hooks.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    tag: String,
+}
+
+#[derive(Clone, Serialize)]
+struct Annotated {
+    tag: String,
+    src: String,
+}
+
+fn build() -> (Simulation, SimulationContext, SimulationContext) {
+    let mut sim = Simulation::new(123);
+    let node1 = sim.create_context("node1");
+    let node2 = sim.create_context("node2");
+    (sim, node1, node2)
+}
+
+fn times(sim: &Simulation) -> Vec<f64> {
+    sim.dump_events().iter().map(|e| e.time).collect()
+}
+
+fn message(tag: &str) -> Message {
+    Message { tag: tag.to_owned() }
+}
+
+#[test]
+fn test_delay_adjustment() {
+    let (mut sim, node1, node2) = build();
+    node1.set_emit_hook(|_, event| event.delay += 0.5);
+    node1.emit(message("a"), node2.id(), 1.);
+    node1.emit_self(message("b"), 2.);
+    node1.emit_now(message("c"), node2.id());
+    node1.emit_ordered(message("d"), node2.id(), 3.);
+    node1.emit_at(message("e"), node2.id(), 4.);
+    // other contexts are not affected
+    node2.emit(message("f"), node1.id(), 1.);
+    assert_eq!(times(&sim), vec![0.5, 1., 1.5, 2.5, 3.5, 4.5]);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.5);
+}
+
+#[test]
+fn test_absolute_time_kept() {
+    let (mut sim, node1, node2) = build();
+    sim.step_until_time(0.1);
+    node1.set_emit_hook(|_, _| {});
+    node1.emit_at(message("a"), node2.id(), 0.3);
+    assert_eq!(times(&sim), vec![0.3]);
+}
+
+#[test]
+fn test_payload_replacement() {
+    let (sim, node1, node2) = build();
+    node1.set_emit_hook(|ctx, event| {
+        if let Some(message) = event.data.downcast_ref::<Message>() {
+            event.data = Box::new(Annotated {
+                tag: message.tag.clone(),
+                src: ctx.lookup_name(event.src()),
+            });
+        }
+    });
+    node1.emit(message("a"), node2.id(), 1.);
+    let events = sim.dump_events();
+    let annotated = events[0].data.downcast_ref::<Annotated>().unwrap();
+    assert_eq!(annotated.tag, "a");
+    assert_eq!(annotated.src, "node1");
+}
+
+#[test]
+fn test_observation() {
+    let (sim, node1, node2) = build();
+    let observed = Rc::new(RefCell::new(Vec::new()));
+    let observed_clone = observed.clone();
+    node1.set_emit_hook(move |_, event| {
+        observed_clone
+            .borrow_mut()
+            .push((event.src(), event.dst(), event.delay));
+    });
+    let first = node1.emit(message("a"), node2.id(), 1.);
+    node1.emit_after(message("b"), node2.id(), first, 2.);
+    {
+        let _scale = node1.scale_time(2.);
+        node1.emit_self(message("c"), 1.);
+    }
+    node1.clear_emit_hook();
+    node1.emit_self(message("d"), 1.);
+    assert_eq!(
+        *observed.borrow(),
+        vec![
+            (node1.id(), node2.id(), 1.),
+            (node1.id(), node2.id(), 2.),
+            (node1.id(), node1.id(), 2.)
+        ]
+    );
+    assert_eq!(sim.event_count(), 4);
+}
+
+#[test]
+fn test_emit_from_hook() {
+    let (sim, node1, node2) = build();
+    let mirror = node2.id();
+    // each event is duplicated to the mirror, the copies are not passed to the hook
+    node1.set_emit_hook(move |ctx, event| {
+        ctx.emit(Message { tag: "copy".to_owned() }, mirror, event.delay);
+    });
+    node1.emit_self(message("a"), 1.);
+    node1.emit_self(message("b"), 2.);
+    assert_eq!(sim.event_count(), 4);
+    let tags: Vec<_> = sim
+        .dump_events()
+        .iter()
+        .map(|e| e.data.downcast_ref::<Message>().unwrap().tag.clone())
+        .collect();
+    assert_eq!(tags, vec!["copy", "a", "copy", "b"]);
+}
+
+#[test]
+fn test_timers_not_hooked() {
+    let (sim, node1, _) = build();
+    node1.set_emit_hook(|_, event| event.delay += 10.);
+    node1.set_timer("timeout", 1.);
+    assert_eq!(times(&sim), vec![1.]);
+}
+
+#[test]
+#[should_panic(expected = "Event delay is negative")]
+fn test_negative_delay() {
+    let (_sim, node1, node2) = build();
+    node1.set_emit_hook(|_, event| event.delay -= 2.);
+    node1.emit(message("a"), node2.id(), 1.);
+}
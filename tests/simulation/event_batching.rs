@@ -0,0 +1,119 @@
+//! Tests of same-destination event batching.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, EventId, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    round: u32,
+}
+
+struct Node {
+    ctx: SimulationContext,
+    peers: Vec<Id>,
+    log: Rc<RefCell<Vec<(Id, EventId, f64)>>>,
+    batch_sizes: Vec<usize>,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        self.log.borrow_mut().push((self.ctx.id(), event.id, event.time));
+        cast!(match event.data {
+            Message { round } => {
+                if round < 5 && event.id.is_multiple_of(3) {
+                    for &peer in self.peers.iter() {
+                        self.ctx.emit(Message { round: round + 1 }, peer, 1.);
+                    }
+                    self.ctx.emit_self_now(Message { round: 5 });
+                }
+            }
+        })
+    }
+
+    fn on_batch(&mut self, events: Vec<Event>) {
+        self.batch_sizes.push(events.len());
+        for event in events {
+            self.on(event);
+        }
+    }
+}
+
+fn run(batching: bool) -> (Vec<(Id, EventId, f64)>, Vec<usize>) {
+    let mut sim = Simulation::new(123);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let node_count = 4;
+    let mut nodes = Vec::new();
+    for i in 0..node_count {
+        let name = format!("node-{}", i);
+        let node = Rc::new(RefCell::new(Node {
+            ctx: sim.create_context(&name),
+            peers: (0..node_count).collect(),
+            log: log.clone(),
+            batch_sizes: Vec::new(),
+        }));
+        sim.add_handler(&name, node.clone());
+        if batching {
+            sim.enable_event_batching(&name);
+        }
+        nodes.push(node);
+    }
+
+    let client = sim.create_context("client");
+    for i in 0..100 {
+        client.emit(Message { round: 0 }, i / 25, 1.);
+    }
+    sim.step_until_no_events();
+
+    let batch_sizes = nodes.iter().flat_map(|n| n.borrow().batch_sizes.clone()).collect();
+    let log = log.borrow().clone();
+    (log, batch_sizes)
+}
+
+#[test]
+fn test_batching_preserves_order() {
+    let (expected, batch_sizes) = run(false);
+    assert!(batch_sizes.is_empty());
+    let (actual, batch_sizes) = run(true);
+    assert_eq!(actual, expected);
+    assert_eq!(batch_sizes.iter().sum::<usize>(), expected.len());
+    assert!(batch_sizes.iter().any(|&size| size > 1));
+}
+
+#[test]
+fn test_batch_is_processed_in_single_step() {
+    let mut sim = Simulation::new(123);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let node = Rc::new(RefCell::new(Node {
+        ctx: sim.create_context("node"),
+        peers: Vec::new(),
+        log: log.clone(),
+        batch_sizes: Vec::new(),
+    }));
+    let node_id = sim.add_handler("node", node.clone());
+    sim.enable_event_batching("node");
+
+    let client = sim.create_context("client");
+    for _ in 0..10 {
+        client.emit(Message { round: 5 }, node_id, 1.);
+    }
+    let other = sim.create_context("other");
+    client.emit(Message { round: 5 }, other.id(), 1.);
+    for _ in 0..10 {
+        client.emit(Message { round: 5 }, node_id, 1.);
+    }
+    let canceled = client.emit(Message { round: 5 }, node_id, 1.);
+    client.cancel_event(canceled);
+    client.emit(Message { round: 5 }, node_id, 1.);
+
+    assert!(sim.step());
+    assert_eq!(node.borrow().batch_sizes, vec![10]);
+    // event for component without handler
+    assert!(sim.step());
+    assert!(sim.step());
+    assert_eq!(node.borrow().batch_sizes, vec![10, 11]);
+    assert!(!sim.step());
+}
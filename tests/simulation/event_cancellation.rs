@@ -28,6 +28,8 @@ fn prepare_test(comp1_name: &str, comp2_name: &str) -> Simulation {
     let comp2_id = sim.lookup_id(comp2_name);
 
     let ctx = sim.create_context("main");
+    // emitting events on behalf of other components requires the capability
+    sim.allow_emit_as("main");
     ctx.emit_as(TestEvent {}, comp1_id, comp2_id, 0.);
     ctx.emit_as(TestEvent {}, comp2_id, comp1_id, 0.);
     ctx.emit_as(TestEvent {}, comp2_id, comp2_id, 0.);
@@ -0,0 +1,185 @@
+//! Tests of coalescing of event bursts.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::queue_dump::QueueDumpOptions;
+use simcore::{Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Update {
+    value: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Command {
+    value: u32,
+}
+
+#[derive(Default)]
+struct Receiver {
+    // time of delivery and values of delivered events
+    deliveries: Vec<(f64, Vec<u32>)>,
+}
+
+impl Receiver {
+    fn value(event: &Event) -> u32 {
+        if let Some(update) = event.data.downcast_ref::<Update>() {
+            update.value
+        } else {
+            event.data.downcast_ref::<Command>().unwrap().value
+        }
+    }
+}
+
+impl EventHandler for Receiver {
+    fn on(&mut self, event: Event) {
+        self.deliveries.push((event.time, vec![Self::value(&event)]));
+    }
+
+    fn on_batch(&mut self, events: Vec<Event>) {
+        let time = events[0].time;
+        self.deliveries.push((time, events.iter().map(Self::value).collect()));
+    }
+}
+
+fn build() -> (Simulation, SimulationContext, Rc<RefCell<Receiver>>, Id) {
+    let mut sim = Simulation::new(123);
+    let receiver = Rc::new(RefCell::new(Receiver::default()));
+    let receiver_id = sim.add_handler("receiver", receiver.clone());
+    sim.enable_event_coalescing("receiver", 0.1);
+    let ctx = sim.create_context("sender");
+    (sim, ctx, receiver, receiver_id)
+}
+
+#[test]
+fn test_bursts_are_merged() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    for (value, time) in [(1, 1.), (2, 1.05), (3, 1.1), (4, 1.15), (5, 2.)] {
+        ctx.emit(Update { value }, receiver_id, time);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(
+        receiver.borrow().deliveries,
+        vec![(1., vec![1, 2, 3]), (1.15, vec![4]), (2., vec![5])]
+    );
+}
+
+#[test]
+fn test_events_emitted_out_of_order() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    ctx.emit(Update { value: 1 }, receiver_id, 1.05);
+    // earlier than the first event of the burst
+    ctx.emit(Update { value: 2 }, receiver_id, 1.);
+    ctx.emit(Update { value: 3 }, receiver_id, 1.02);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![2, 3]), (1.05, vec![1])]);
+}
+
+#[test]
+fn test_different_types_and_destinations_are_not_merged() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    let other = Rc::new(RefCell::new(Receiver::default()));
+    let other_id = sim.add_handler("other", other.clone());
+    ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    ctx.emit(Command { value: 2 }, receiver_id, 1.01);
+    ctx.emit(Update { value: 3 }, other_id, 1.02);
+    ctx.emit(Update { value: 4 }, other_id, 1.03);
+    ctx.emit(Update { value: 5 }, receiver_id, 1.04);
+    ctx.emit(Command { value: 6 }, receiver_id, 1.05);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![1, 5]), (1.01, vec![2, 6])]);
+    // coalescing is not enabled for other component
+    assert_eq!(other.borrow().deliveries, vec![(1.02, vec![3]), (1.03, vec![4])]);
+}
+
+#[test]
+fn test_delivered_burst_is_closed() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    sim.step();
+    // the first event is already delivered, so the event starts a new burst
+    ctx.emit(Update { value: 2 }, receiver_id, 0.05);
+    ctx.emit(Update { value: 3 }, receiver_id, 0.06);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![1]), (1.05, vec![2, 3])]);
+}
+
+#[test]
+fn test_cancel_merged_event() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    let merged = ctx.emit(Update { value: 2 }, receiver_id, 1.05);
+    ctx.emit(Update { value: 3 }, receiver_id, 1.06);
+    ctx.cancel_event(merged);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![1, 3])]);
+}
+
+#[test]
+fn test_cancel_first_event_of_burst() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    let first = ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    ctx.emit(Update { value: 2 }, receiver_id, 1.05);
+    ctx.emit(Update { value: 3 }, receiver_id, 1.06);
+    ctx.cancel_event(first);
+    ctx.emit(Update { value: 4 }, receiver_id, 1.051);
+    // the merged events are delivered separately when the canceled event is removed
+    sim.step_until_no_events();
+
+    assert_eq!(
+        receiver.borrow().deliveries,
+        vec![(1.05, vec![2]), (1.051, vec![4]), (1.06, vec![3])]
+    );
+}
+
+#[test]
+fn test_cancel_events_by_predicate() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    for (value, time) in [(1, 1.), (2, 1.01), (3, 1.02), (4, 1.03)] {
+        ctx.emit(Update { value }, receiver_id, time);
+    }
+    sim.cancel_events(|event| event.data.downcast_ref::<Update>().unwrap().value % 2 == 0);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![1, 3])]);
+}
+
+#[test]
+fn test_merged_events_are_dumped() {
+    let (sim, ctx, _receiver, receiver_id) = build();
+    ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    ctx.emit(Update { value: 2 }, receiver_id, 1.05);
+
+    let mut output = Vec::new();
+    sim.dump_queue(&mut output, &QueueDumpOptions::default()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("Pending events: 2 total"));
+}
+
+#[test]
+fn test_coalescing_with_batching() {
+    let (mut sim, ctx, receiver, receiver_id) = build();
+    sim.enable_event_batching("receiver");
+    ctx.emit(Update { value: 1 }, receiver_id, 1.);
+    ctx.emit(Command { value: 2 }, receiver_id, 1.);
+    ctx.emit(Update { value: 3 }, receiver_id, 1.05);
+    ctx.emit(Command { value: 4 }, receiver_id, 1.05);
+    sim.step_until_no_events();
+
+    assert_eq!(receiver.borrow().deliveries, vec![(1., vec![1, 3, 2, 4])]);
+}
+
+#[test]
+#[should_panic(expected = "Coalescing window must be non-negative")]
+fn test_negative_window() {
+    let (mut sim, _ctx, _receiver, _receiver_id) = build();
+    sim.enable_event_coalescing("receiver", -1.);
+}
@@ -0,0 +1,87 @@
+//! Tests of event handler generated via `event_handler` attribute.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{event_handler, Id, Simulation, SimulationContext, TypedEvent};
+
+#[derive(Clone, Serialize)]
+struct Ping {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Pong {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Unknown {}
+
+struct Node {
+    ctx: SimulationContext,
+    peer: Option<Id>,
+    log: Vec<(&'static str, u32, f64)>,
+}
+
+#[event_handler]
+impl Node {
+    fn on_ping(&mut self, event: TypedEvent<Ping>) {
+        self.record("ping", event.data.seq);
+        self.ctx.emit(Pong { seq: event.data.seq }, event.src, 1.);
+    }
+
+    fn on_pong(&mut self, event: TypedEvent<Pong>) {
+        self.record("pong", event.data.seq);
+        if event.data.seq < 2 {
+            self.ctx.emit(
+                Ping {
+                    seq: event.data.seq + 1,
+                },
+                self.peer.unwrap(),
+                1.,
+            );
+        }
+    }
+
+    // not a handler, left intact
+    fn record(&mut self, name: &'static str, seq: u32) {
+        self.log.push((name, seq, self.ctx.time()));
+    }
+}
+
+#[test]
+fn test_event_handler_attribute() {
+    let mut sim = Simulation::new(123);
+    let node1 = Rc::new(RefCell::new(Node {
+        ctx: sim.create_context("node1"),
+        peer: None,
+        log: Vec::new(),
+    }));
+    let node2 = Rc::new(RefCell::new(Node {
+        ctx: sim.create_context("node2"),
+        peer: None,
+        log: Vec::new(),
+    }));
+    let node1_id = sim.add_handler("node1", node1.clone());
+    let node2_id = sim.add_handler("node2", node2.clone());
+    node1.borrow_mut().peer = Some(node2_id);
+
+    node1.borrow().ctx.emit(Ping { seq: 0 }, node2_id, 1.);
+    // logged as unhandled
+    node1.borrow().ctx.emit(Unknown {}, node2_id, 1.);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        node1.borrow().log,
+        vec![("pong", 0, 2.), ("pong", 1, 4.), ("pong", 2, 6.)]
+    );
+    assert_eq!(
+        node2.borrow().log,
+        vec![("ping", 0, 1.), ("ping", 1, 3.), ("ping", 2, 5.)]
+    );
+    assert_eq!(node2.borrow().ctx.id(), node2_id);
+    assert_ne!(node1_id, node2_id);
+}
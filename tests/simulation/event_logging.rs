@@ -0,0 +1,209 @@
+//! Tests of event logging.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Once;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+
+use simcore::rate_limit::{OutputKind, OutputRateLimit};
+use simcore::{log_info, loggable_event, opaque_event, Simulation};
+
+// Logger capturing the records of the current thread, so that other tests running in parallel are not affected.
+struct CapturingLogger;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        CAPTURED.with(|captured| {
+            if let Some(records) = captured.borrow_mut().as_mut() {
+                records.push(record.args().to_string());
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+static INIT: Once = Once::new();
+
+fn capture_logs<F: FnOnce()>(f: F) -> Vec<String> {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    f();
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap())
+}
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u64,
+    path: String,
+    size: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct Start;
+
+loggable_event!(Request { id, path, size });
+loggable_event!(Start);
+
+fn run(register: bool) -> Vec<String> {
+    capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        if register {
+            sim.register_loggable_event::<Request>();
+            sim.register_loggable_event::<Start>();
+        }
+        let client = sim.create_context("client \"1\"");
+        let server = sim.create_context("server");
+        client.emit(Start, server.id(), 0.5);
+        client.emit(
+            Request {
+                id: 1,
+                path: "/index.html".to_owned(),
+                size: 0.1,
+            },
+            server.id(),
+            1.,
+        );
+        sim.step_until_no_events();
+    })
+    .into_iter()
+    .filter(|record| record.contains("EVENT"))
+    .collect()
+}
+
+#[test]
+fn test_loggable_events_are_logged_as_generic_events() {
+    let expected = run(false);
+    let actual = run(true);
+    assert_eq!(expected.len(), 2);
+    assert!(expected[1]
+        .ends_with(r#"{"type":"Request","data":{"id":1,"path":"/index.html","size":0.1},"src":"client \"1\""}"#));
+    assert_eq!(actual, expected);
+}
+
+#[derive(Clone)]
+struct Attach {
+    #[allow(dead_code)]
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+opaque_event!(Attach);
+
+#[test]
+fn test_opaque_event_is_logged_without_data() {
+    let records = capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        let client = sim.create_context("client");
+        let server = sim.create_context("server");
+        client.emit(
+            Attach {
+                buffer: Rc::new(RefCell::new(Vec::new())),
+            },
+            server.id(),
+            1.,
+        );
+        sim.step_until_no_events();
+    });
+    let events: Vec<_> = records.into_iter().filter(|record| record.contains("EVENT")).collect();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].ends_with(r#"{"type":"Attach","data":null,"src":"client"}"#));
+}
+
+#[test]
+fn test_run_metadata_is_logged_once() {
+    let records = capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        sim.set_run_label("experiment", "logging");
+        let ctx = sim.create_context("comp");
+        ctx.emit_self(Start, 1.);
+        ctx.emit_self(Start, 2.);
+        sim.step_until_no_events();
+        sim.step();
+    });
+    let metadata: Vec<_> = records
+        .into_iter()
+        .filter(|record| record.contains("Run metadata"))
+        .collect();
+    assert_eq!(metadata.len(), 1);
+    assert!(metadata[0].starts_with("[0.000"));
+    assert!(metadata[0].contains(r#""seed":123"#));
+    assert!(metadata[0].contains(r#""labels":{"experiment":"logging"}"#));
+}
+
+#[test]
+fn test_log_rate_limit() {
+    let mut suppressed = 0;
+    let records = capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        sim.set_output_rate_limit(Some(OutputRateLimit::new(1., 3)));
+        let client = sim.create_context("client");
+        let server = sim.create_context("server");
+        for i in 0..10 {
+            log_info!(client, "message {}", i);
+            client.emit(Start, server.id(), 0.5);
+        }
+        client.emit(Start, server.id(), 1.5);
+        sim.step_until_no_events();
+        log_info!(client, "done");
+        suppressed = sim.suppressed_output(OutputKind::Log);
+    });
+    let messages: Vec<_> = records.iter().filter(|record| record.contains("message")).collect();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[2].ends_with("message 2"));
+    let events: Vec<_> = records.iter().filter(|record| record.contains("EVENT")).collect();
+    assert_eq!(events.len(), 4);
+    assert!(events[3].starts_with("[1.500"));
+    assert!(records.last().unwrap().ends_with("done"));
+    assert_eq!(suppressed, 7 + 7);
+}
+
+#[test]
+fn test_component_log_rate_limit() {
+    let records = capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        let noisy = sim.create_context("noisy");
+        let quiet = sim.create_context("quiet");
+        sim.set_component_output_rate_limit("noisy", Some(OutputRateLimit::new(10., 1)));
+        for _ in 0..5 {
+            log_info!(noisy, "noisy message");
+            log_info!(quiet, "quiet message");
+        }
+    });
+    assert_eq!(
+        records.iter().filter(|record| record.contains("noisy message")).count(),
+        1
+    );
+    assert_eq!(
+        records.iter().filter(|record| record.contains("quiet message")).count(),
+        5
+    );
+}
+
+#[test]
+fn test_log_without_rate_limit_while_state_is_borrowed() {
+    let records = capture_logs(|| {
+        let mut sim = Simulation::new(123);
+        sim.enable_memory_trace();
+        let ctx = sim.create_context("comp");
+        ctx.emit_self(Start, 1.);
+        sim.step_until_no_events();
+        // the simulation state is borrowed while the trace is inspected
+        let trace = sim.trace();
+        log_info!(ctx, "processed {} events", trace.len());
+    });
+    assert!(records.last().unwrap().ends_with("processed 1 events"));
+}
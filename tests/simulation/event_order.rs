@@ -0,0 +1,111 @@
+//! Tests of processing order of events with equal time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, EventId, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Start {}
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+struct Recorder {
+    ctx: SimulationContext,
+    log: Vec<EventId>,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, event: Event) {
+        let id = event.id;
+        cast!(match event.data {
+            Start {} => {
+                self.log.push(id);
+                self.ctx.emit_self_now(Ping {});
+                self.ctx.emit_self(Ping {}, 0.);
+                self.ctx.emit_ordered_self_now(Ping {});
+                self.ctx.emit_self_now(Ping {});
+            }
+            Ping {} => {
+                self.log.push(id);
+            }
+        })
+    }
+}
+
+#[test]
+fn test_same_time_events_are_processed_in_creation_order() {
+    let mut sim = Simulation::new(123);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        ctx: sim.create_context("recorder"),
+        log: Vec::new(),
+    }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+
+    let source = sim.create_context("source");
+    // ids 0-3: events at time 1.0 created before the immediate events emitted at this time
+    source.emit(Start {}, recorder_id, 1.);
+    source.emit(Ping {}, recorder_id, 1.);
+    source.emit_ordered(Ping {}, recorder_id, 1.);
+    source.emit(Ping {}, recorder_id, 1.);
+    // ids 4-5: events at time 0.0
+    source.emit_now(Ping {}, recorder_id);
+    source.emit(Ping {}, recorder_id, 0.);
+
+    sim.step_until_no_events();
+    // ids 6-9 are emitted when processing Start event
+    assert_eq!(recorder.borrow().log, vec![4, 5, 0, 1, 2, 3, 6, 7, 8, 9]);
+}
+
+#[test]
+fn test_cancel_immediate_events() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let event1 = ctx.emit_self_now(Ping {});
+    ctx.emit_self_now(Ping {});
+    ctx.emit_self_now(Ping {});
+    ctx.cancel_event(event1);
+    sim.cancel_events(|e| e.id == 2);
+    let events = sim.dump_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, 1);
+}
+
+fn run_with_heap_arity(arity: usize) -> Vec<EventId> {
+    let mut sim = Simulation::new(123);
+    sim.set_event_heap_arity(arity);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        ctx: sim.create_context("recorder"),
+        log: Vec::new(),
+    }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+
+    let source = sim.create_context("source");
+    for i in 0..1000 {
+        // use a small set of distinct times to produce many events with equal time
+        let delay = sim.gen_range(0..50) as f64;
+        source.emit(Ping {}, recorder_id, delay);
+        if i % 100 == 0 {
+            source.emit(Start {}, recorder_id, delay);
+        }
+        if i == 500 {
+            // change arity when the heap is not empty
+            sim.set_event_heap_arity(arity + 1);
+        }
+    }
+    sim.step_until_no_events();
+    let log = recorder.borrow().log.clone();
+    log
+}
+
+#[test]
+fn test_heap_arity_does_not_affect_order() {
+    let expected = run_with_heap_arity(2);
+    assert_eq!(expected.len(), 1050);
+    for arity in [3, 4, 8] {
+        assert_eq!(run_with_heap_arity(arity), expected);
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests of event priorities controlling the processing order of events with equal time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::spill::SpillConfig;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Tagged {
+    tag: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Start {}
+
+struct Recorder {
+    ctx: SimulationContext,
+    log: Vec<(f64, u32)>,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Start {} => {
+                // zero-delay events with priorities are mixed with the immediate ones
+                self.ctx.emit_self_now(Tagged { tag: 10 });
+                self.ctx.emit_with_priority(Tagged { tag: 11 }, self.ctx.id(), 0., 1);
+                self.ctx.emit_self_now(Tagged { tag: 12 });
+                self.ctx.emit_with_priority(Tagged { tag: 13 }, self.ctx.id(), 0., -1);
+                self.ctx.emit_ordered_self_now(Tagged { tag: 14 });
+            }
+            Tagged { tag } => {
+                self.log.push((self.ctx.time(), tag));
+            }
+        })
+    }
+}
+
+fn build(sim: &mut Simulation) -> Rc<RefCell<Recorder>> {
+    let recorder = Rc::new(RefCell::new(Recorder {
+        ctx: sim.create_context("recorder"),
+        log: Vec::new(),
+    }));
+    sim.add_handler("recorder", recorder.clone());
+    recorder
+}
+
+#[test]
+fn test_equal_time_events_are_processed_by_priority() {
+    let mut sim = Simulation::new(123);
+    let recorder = build(&mut sim);
+    let src = sim.create_context("src");
+    let dst = recorder.borrow().ctx.id();
+
+    src.emit_with_priority(Tagged { tag: 0 }, dst, 1., 5);
+    src.emit(Tagged { tag: 1 }, dst, 1.);
+    src.emit_with_priority(Tagged { tag: 2 }, dst, 1., -5);
+    src.emit_with_priority(Tagged { tag: 3 }, dst, 1., 5);
+    src.emit_with_priority(Tagged { tag: 4 }, dst, 0.5, 100);
+    src.emit_with_priority(Tagged { tag: 5 }, dst, 2., -100);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        recorder.borrow().log,
+        vec![(0.5, 4), (1., 2), (1., 1), (1., 0), (1., 3), (2., 5)]
+    );
+}
+
+#[test]
+fn test_zero_delay_events_with_priority() {
+    let mut sim = Simulation::new(123);
+    let recorder = build(&mut sim);
+    let src = sim.create_context("src");
+    src.emit(Start {}, recorder.borrow().ctx.id(), 1.);
+    sim.step_until_no_events();
+
+    let tags: Vec<_> = recorder.borrow().log.iter().map(|(_, tag)| *tag).collect();
+    assert_eq!(tags, vec![13, 10, 12, 14, 11]);
+}
+
+#[test]
+fn test_priority_is_exposed_in_event() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(Tagged { tag: 0 }, 1.);
+    ctx.emit_with_priority(Tagged { tag: 1 }, ctx.id(), 1., -3);
+
+    let priorities: Vec<_> = sim.dump_events().iter().map(|e| e.priority).collect();
+    assert_eq!(priorities, vec![-3, 0]);
+}
+
+#[test]
+fn test_priorities_of_spilled_events() {
+    let run = |spill: bool| {
+        let mut sim = Simulation::new(123);
+        let recorder = build(&mut sim);
+        if spill {
+            sim.register_spillable_event::<Tagged>();
+            sim.enable_event_spilling(SpillConfig::new(
+                std::env::temp_dir().join("simcore-priority-spill"),
+                16,
+            ));
+        }
+        let src = sim.create_context("src");
+        let dst = recorder.borrow().ctx.id();
+        for tag in 0..200 {
+            let time = (tag % 10) as f64;
+            src.emit_with_priority(Tagged { tag }, dst, time, -(tag as i64 % 3));
+        }
+        sim.step_until_no_events();
+        let log = recorder.borrow().log.clone();
+        log
+    };
+    let log = run(true);
+    assert_eq!(log, run(false));
+    assert_eq!(&log[..3], &[(0., 20), (0., 50), (0., 80)]);
+}
+
+#[test]
+fn test_priorities_restored_from_checkpoint() {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Tagged>();
+    let recorder = build(&mut sim);
+    let ctx = &recorder.borrow().ctx;
+    ctx.emit_self(Tagged { tag: 0 }, 1.);
+    ctx.emit_with_priority(Tagged { tag: 1 }, ctx.id(), 1., -1);
+    let checkpoint = sim.checkpoint();
+
+    let mut resumed = Simulation::new(123);
+    resumed.register_checkpoint_event::<Tagged>();
+    let recorder = build(&mut resumed);
+    resumed.restore_checkpoint(&checkpoint);
+    resumed.step_until_no_events();
+    assert_eq!(recorder.borrow().log, vec![(1., 1), (1., 0)]);
+}
@@ -0,0 +1,152 @@
+//! Tests of spilling pending events to disk.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::spill::SpillConfig;
+use simcore::{cast, Event, EventHandler, EventId, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Spillable {
+    value: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct NonSpillable {
+    value: u64,
+}
+
+struct Recorder {
+    ctx: SimulationContext,
+    log: Vec<(EventId, f64, u64)>,
+}
+
+impl EventHandler for Recorder {
+    fn on(&mut self, event: Event) {
+        let id = event.id;
+        cast!(match event.data {
+            Spillable { value } => {
+                self.log.push((id, self.ctx.time(), value));
+                // emit new events while processing to mix them with spilled ones
+                if value % 7 == 0 {
+                    let delay = self.ctx.gen_range(0.0..50.0);
+                    self.ctx.emit_self(Spillable { value: value + 1 }, delay);
+                }
+            }
+            NonSpillable { value } => {
+                self.log.push((id, self.ctx.time(), value));
+            }
+        })
+    }
+}
+
+fn run(spill_dir: Option<&str>) -> Vec<(EventId, f64, u64)> {
+    let mut sim = Simulation::new(123);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        ctx: sim.create_context("recorder"),
+        log: Vec::new(),
+    }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+    if let Some(dir) = spill_dir {
+        sim.register_spillable_event::<Spillable>();
+        sim.enable_event_spilling(SpillConfig::new(std::env::temp_dir().join(dir), 64));
+    }
+
+    let source = sim.create_context("source");
+    let mut to_cancel = Vec::new();
+    for i in 0..2000 {
+        let delay = sim.gen_range(0.0..100.0);
+        let id = if i % 10 == 0 {
+            source.emit(NonSpillable { value: i }, recorder_id, delay)
+        } else {
+            source.emit(Spillable { value: i }, recorder_id, delay)
+        };
+        if i % 13 == 0 {
+            to_cancel.push(id);
+        }
+        source.emit_ordered(Spillable { value: 10000 + i }, recorder_id, i as f64 / 10.);
+    }
+    for id in to_cancel {
+        source.cancel_event(id);
+    }
+    sim.cancel_events(|e| e.id % 17 == 0);
+    sim.step_until_no_events();
+
+    let log = recorder.borrow().log.clone();
+    log
+}
+
+#[test]
+fn test_spilling_preserves_order() {
+    let expected = run(None);
+    let actual = run(Some("simcore-test-spilling-order"));
+    assert_eq!(actual.len(), expected.len());
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_dump_and_cancel_spilled_events() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.register_spillable_event::<Spillable>();
+    sim.enable_event_spilling(SpillConfig::new(
+        std::env::temp_dir().join("simcore-test-spilling-dump"),
+        10,
+    ));
+    for i in 0..100 {
+        ctx.emit_self(Spillable { value: i }, (100 - i) as f64);
+    }
+
+    let events = sim.dump_events();
+    assert_eq!(events.len(), 100);
+    assert!(events.windows(2).all(|w| w[0].time <= w[1].time));
+
+    let canceled = sim.cancel_and_get_events(|e| e.time > 50.);
+    assert_eq!(canceled.len(), 50);
+    assert_eq!(sim.dump_events().len(), 50);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 50.);
+}
+
+fn run_with_canceled_burst(spill_dir: Option<&str>) -> Simulation {
+    let mut sim = Simulation::new(123);
+    let recorder = Rc::new(RefCell::new(Recorder {
+        ctx: sim.create_context("recorder"),
+        log: Vec::new(),
+    }));
+    let recorder_id = sim.add_handler("recorder", recorder.clone());
+    sim.register_spillable_event::<Spillable>();
+    sim.register_checkpoint_event::<Spillable>();
+    sim.enable_event_coalescing("recorder", 0.01);
+    if let Some(dir) = spill_dir {
+        sim.enable_event_spilling(SpillConfig::new(std::env::temp_dir().join(dir), 4));
+    }
+
+    let source = sim.create_context("source");
+    let first = source.emit(Spillable { value: 1 }, recorder_id, 100.);
+    // merged into the burst of the first event
+    source.emit(Spillable { value: 2 }, recorder_id, 100.005);
+    source.emit_after(Spillable { value: 3 }, recorder_id, first, 1.);
+    source.cancel_event(first);
+    // the canceled event is spilled with the nearer events
+    for i in 0..10 {
+        source.emit(Spillable { value: 10 + i }, recorder_id, i as f64 + 1.);
+    }
+    sim.step_until_no_events();
+
+    let values: Vec<u64> = recorder.borrow().log.iter().map(|(_, _, value)| *value).collect();
+    // the recorder emits 15 after processing 14
+    assert_eq!(values, vec![10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 15, 2]);
+    sim
+}
+
+#[test]
+fn test_spilled_canceled_event_releases_coalesced_and_deferred_events() {
+    run_with_canceled_burst(None);
+    let sim = run_with_canceled_burst(Some("simcore-test-spilling-canceled-burst"));
+    // the deferred event is discarded with the canceled event, so the checkpoint can be saved
+    sim.checkpoint();
+}
@@ -0,0 +1,63 @@
+//! Tests of schema versions and migrations of event payloads.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use simcore::versioning::EventMigrations;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Request {
+    bytes: u32,
+    priority: u8,
+}
+
+fn migrations() -> EventMigrations {
+    let mut migrations = EventMigrations::new();
+    migrations
+        .set_version("Request", 2)
+        .add_migration("Request", 0, |data| json!({"bytes": data["size"]}))
+        .add_migration("Request", 1, |mut data| {
+            data["priority"] = json!(1);
+            data
+        });
+    migrations
+}
+
+#[test]
+fn test_migrations() {
+    let migrations = migrations();
+    assert_eq!(migrations.version("Request"), 2);
+    let expected = Request { bytes: 10, priority: 1 };
+    for (version, data) in [
+        (0, json!({"size": 10})),
+        (1, json!({"bytes": 10})),
+        (2, json!({"bytes": 10, "priority": 1})),
+    ] {
+        let data = migrations.migrate("Request", version, data);
+        assert_eq!(serde_json::from_value::<Request>(data).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_unversioned_type() {
+    let migrations = migrations();
+    assert_eq!(migrations.version("Response"), 0);
+    let data = json!({"ok": true});
+    assert_eq!(migrations.migrate("Response", 0, data.clone()), data);
+}
+
+#[test]
+#[should_panic(expected = "Migration of event type Request from version 1 is not added")]
+fn test_missing_migration() {
+    let mut migrations = EventMigrations::new();
+    migrations
+        .set_version("Request", 2)
+        .add_migration("Request", 0, |data| data);
+    migrations.migrate("Request", 0, json!({}));
+}
+
+#[test]
+#[should_panic(expected = "Recorded version 3 of event type Request is newer than the registered version 2")]
+fn test_newer_recorded_version() {
+    migrations().migrate("Request", 3, json!({}));
+}
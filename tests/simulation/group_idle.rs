@@ -0,0 +1,104 @@
+//! Tests of running the simulation until a group of components is idle.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::delay::DelayProfile;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Join {
+    hops: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Request {}
+
+// Forwards the join message to the peer until the hop count is exhausted.
+struct Node {
+    peer: Id,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Join { hops } => {
+                if hops > 0 {
+                    self.ctx.emit(Join { hops: hops - 1 }, self.peer, 1.);
+                }
+            }
+            Request {} => {}
+        })
+    }
+}
+
+fn build() -> (Simulation, SimulationContext) {
+    let mut sim = Simulation::new(123);
+    let node1 = sim.create_context("node1");
+    let node2 = sim.create_context("node2");
+    let (id1, id2) = (node1.id(), node2.id());
+    sim.add_handler("node1", Rc::new(RefCell::new(Node { peer: id2, ctx: node1 })));
+    sim.add_handler("node2", Rc::new(RefCell::new(Node { peer: id1, ctx: node2 })));
+    sim.set_component_group("node1", "bootstrap");
+    sim.set_component_group("node2", "bootstrap");
+    let client = sim.create_context("client");
+    sim.set_component_group("client", "load");
+    (sim, client)
+}
+
+#[test]
+fn test_group_idle() {
+    let (mut sim, client) = build();
+    let node1 = sim.lookup_id("node1");
+    client.emit_self(Request {}, 0.5);
+    client.emit_self(Request {}, 20.);
+    client.emit(Join { hops: 5 }, node1, 1.);
+    assert!(sim.step_until_group_idle("bootstrap"));
+    // the join is delivered at time 1 and forwarded 5 times
+    assert_eq!(sim.time(), 6.);
+    assert_eq!(sim.event_count(), 8);
+    // the idle group is not stepped further
+    assert!(sim.step_until_group_idle("bootstrap"));
+    assert_eq!(sim.time(), 6.);
+    assert!(sim.step_until_group_idle("load"));
+    assert_eq!(sim.time(), 20.);
+}
+
+#[test]
+fn test_events_from_group() {
+    let (mut sim, client) = build();
+    let node1 = sim.create_context("node1");
+    node1.emit(Request {}, client.id(), 3.);
+    client.emit_self(Request {}, 10.);
+    assert!(sim.step_until_group_idle("bootstrap"));
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_deferred_events() {
+    let (mut sim, client) = build();
+    let node2 = sim.lookup_id("node2");
+    let first = client.emit_self(Request {}, 2.);
+    client.emit_after(Join { hops: 0 }, node2, first, 1.);
+    assert!(sim.step_until_group_idle("bootstrap"));
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+fn test_empty_group() {
+    let (mut sim, client) = build();
+    client.emit_self(Request {}, 2.);
+    sim.set_group_delay("other", "load", DelayProfile::constant(1.));
+    assert!(sim.step_until_group_idle("other"));
+    assert_eq!(sim.time(), 0.);
+}
+
+#[test]
+#[should_panic(expected = "Component group unknown does not exist")]
+fn test_unknown_group() {
+    let (mut sim, _) = build();
+    sim.step_until_group_idle("unknown");
+}
@@ -0,0 +1,242 @@
+//! Tests of merging external input streams.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+
+use serde::Serialize;
+
+use simcore::input::{InputEvent, InputItem};
+use simcore::{Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    tag: String,
+}
+
+struct Receiver {
+    log: Vec<(f64, String, Id)>,
+}
+
+impl EventHandler for Receiver {
+    fn on(&mut self, event: Event) {
+        let message = event.data.downcast_ref::<Message>().unwrap();
+        self.log.push((event.time, message.tag.clone(), event.src));
+    }
+}
+
+struct Emitter {
+    ctx: SimulationContext,
+    dst: Id,
+}
+
+impl EventHandler for Emitter {
+    fn on(&mut self, _event: Event) {}
+}
+
+fn message(time: f64, dst: Id, tag: &str) -> InputItem {
+    InputItem::Event(InputEvent::new(time, dst, Message { tag: tag.to_string() }))
+}
+
+fn tags(receiver: &Rc<RefCell<Receiver>>) -> Vec<(f64, String)> {
+    receiver
+        .borrow()
+        .log
+        .iter()
+        .map(|(t, tag, _)| (*t, tag.clone()))
+        .collect()
+}
+
+fn setup() -> (Simulation, Rc<RefCell<Receiver>>, Id) {
+    let mut sim = Simulation::new(123);
+    let receiver = Rc::new(RefCell::new(Receiver { log: Vec::new() }));
+    let receiver_id = sim.add_handler("receiver", receiver.clone());
+    (sim, receiver, receiver_id)
+}
+
+#[test]
+fn test_inputs_are_merged_by_time_and_registration_order() {
+    let (mut sim, receiver, dst) = setup();
+    let a = sim.add_input(
+        "a",
+        vec![message(1., dst, "a1"), message(2., dst, "a2"), message(4., dst, "a4")],
+    );
+    let b = sim.add_input(
+        "b",
+        vec![message(2., dst, "b2"), message(3., dst, "b3"), message(4., dst, "b4")],
+    );
+    sim.step_until_no_events();
+
+    assert_eq!(
+        tags(&receiver),
+        vec![
+            (1., "a1".to_string()),
+            (2., "a2".to_string()),
+            (2., "b2".to_string()),
+            (3., "b3".to_string()),
+            (4., "a4".to_string()),
+            (4., "b4".to_string()),
+        ]
+    );
+    let sources: Vec<Id> = receiver.borrow().log.iter().map(|(_, _, src)| *src).collect();
+    assert_eq!(sources, vec![a, a, b, b, a, b]);
+    assert_eq!(sim.lookup_name(a), "a");
+    assert_eq!(sim.time(), 4.);
+}
+
+#[test]
+fn test_input_events_follow_pending_events_with_equal_time() {
+    let (mut sim, receiver, dst) = setup();
+    let emitter_ctx = sim.create_context("emitter");
+    let emitter = Emitter { ctx: emitter_ctx, dst };
+    emitter.ctx.emit(
+        Message {
+            tag: "pending".to_string(),
+        },
+        emitter.dst,
+        2.,
+    );
+    sim.add_handler("emitter", Rc::new(RefCell::new(emitter)));
+    sim.add_input("input", vec![message(2., dst, "input")]);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        tags(&receiver),
+        vec![(2., "pending".to_string()), (2., "input".to_string())]
+    );
+}
+
+#[test]
+fn test_runs_are_deterministic() {
+    let run = || {
+        let (mut sim, receiver, dst) = setup();
+        for name in ["x", "y", "z"] {
+            let items: Vec<InputItem> = (0..10)
+                .map(|i| message((i / 2) as f64, dst, &format!("{}{}", name, i)))
+                .collect();
+            sim.add_input(name, items);
+        }
+        sim.step_until_no_events();
+        tags(&receiver)
+    };
+    let first = run();
+    assert_eq!(first.len(), 30);
+    assert_eq!(first, run());
+}
+
+#[test]
+fn test_inputs_are_read_lazily() {
+    let (mut sim, receiver, dst) = setup();
+    let read = Rc::new(Cell::new(0));
+    let read_counter = read.clone();
+    sim.add_input(
+        "input",
+        (1..=5).map(move |i| {
+            read_counter.set(read_counter.get() + 1);
+            message(i as f64 * 10., dst, &i.to_string())
+        }),
+    );
+    assert_eq!(read.get(), 0);
+
+    // the next event is read to know whether it occurs before the time limit
+    sim.step_until_time(15.);
+    assert_eq!(tags(&receiver), vec![(10., "1".to_string())]);
+    assert_eq!(read.get(), 2);
+    assert_eq!(sim.time(), 15.);
+
+    // the event following the injected one is read to check for ties
+    assert!(sim.step());
+    assert_eq!(sim.time(), 20.);
+    assert_eq!(read.get(), 3);
+
+    sim.step_until_no_events();
+    assert_eq!(read.get(), 5);
+    assert_eq!(receiver.borrow().log.len(), 5);
+}
+
+#[test]
+fn test_watermarks_avoid_reading_further() {
+    let (mut sim, receiver, dst) = setup();
+    let read = Rc::new(Cell::new(0));
+    let read_counter = read.clone();
+    let items = vec![
+        message(1., dst, "1"),
+        InputItem::Watermark(10.),
+        message(12., dst, "12"),
+    ];
+    sim.add_input(
+        "input",
+        items
+            .into_iter()
+            .inspect(move |_| read_counter.set(read_counter.get() + 1)),
+    );
+
+    sim.step_until_time(8.);
+    assert_eq!(tags(&receiver), vec![(1., "1".to_string())]);
+    assert_eq!(read.get(), 2);
+
+    sim.step_until_time(11.);
+    assert_eq!(read.get(), 3);
+    assert_eq!(receiver.borrow().log.len(), 1);
+
+    sim.step_until_no_events();
+    assert_eq!(tags(&receiver).last(), Some(&(12., "12".to_string())));
+}
+
+#[test]
+fn test_step_until_time_reports_pending_inputs() {
+    let (mut sim, _receiver, dst) = setup();
+    sim.add_input("input", vec![message(1., dst, "1"), message(5., dst, "5")]);
+    assert!(sim.step_until_time(3.));
+    assert!(!sim.step_until_time(10.));
+}
+
+#[test]
+fn test_channel_input() {
+    let (mut sim, receiver, dst) = setup();
+    let (sender, items) = mpsc::sync_channel(1);
+    let producer = thread::spawn(move || {
+        for i in 1..=100 {
+            sender.send(i).unwrap();
+        }
+    });
+    sim.add_input(
+        "channel",
+        items.into_iter().map(move |i| message(i as f64, dst, &i.to_string())),
+    );
+    sim.add_input("trace", vec![message(50., dst, "trace")]);
+    sim.step_until_no_events();
+    producer.join().unwrap();
+
+    let log = tags(&receiver);
+    assert_eq!(log.len(), 101);
+    assert_eq!(log[49], (50., "50".to_string()));
+    assert_eq!(log[50], (50., "trace".to_string()));
+    assert_eq!(sim.time(), 100.);
+}
+
+#[test]
+#[should_panic(expected = "Input event time 1 is earlier than the previous event or watermark 2")]
+fn test_unordered_events_panic() {
+    let (mut sim, _receiver, dst) = setup();
+    sim.add_input("input", vec![message(2., dst, "2"), message(1., dst, "1")]);
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "Input event time 1 is earlier than the previous event or watermark 5")]
+fn test_event_before_watermark_panics() {
+    let (mut sim, _receiver, dst) = setup();
+    sim.add_input("input", vec![InputItem::Watermark(5.), message(1., dst, "1")]);
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "Input event time 1 is earlier than the previous event or watermark 3")]
+fn test_past_events_panic() {
+    let (mut sim, _receiver, dst) = setup();
+    sim.step_until_time(3.);
+    sim.add_input("input", vec![message(1., dst, "1")]);
+    sim.step_until_no_events();
+}
@@ -0,0 +1,131 @@
+//! Tests of emitting events over links with limited bandwidth.
+
+use serde::Serialize;
+
+use simcore::handler::EventCancellationPolicy;
+use simcore::link::Link;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Packet {
+    seq: u32,
+}
+
+fn event_times(sim: &Simulation) -> Vec<f64> {
+    sim.dump_events().iter().map(|e| e.time).collect()
+}
+
+#[test]
+fn test_independent_transmissions() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+
+    let link = Link::new(10., 1.);
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 20., &link);
+    src.emit_over_link(Packet { seq: 1 }, dst.id(), 10., &link);
+    src.emit_over_link(Packet { seq: 2 }, dst.id(), 0., &link);
+
+    assert_eq!(event_times(&sim), vec![1., 2., 3.]);
+    assert_eq!(src.link_busy_until(dst.id()), None);
+}
+
+#[test]
+fn test_serialized_transmissions() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+    let other = sim.create_context("other");
+
+    let link = Link::serialized(10., 1.);
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 20., &link);
+    src.emit_over_link(Packet { seq: 1 }, dst.id(), 10., &link);
+    // channels are separate for each destination and direction
+    src.emit_over_link(Packet { seq: 2 }, other.id(), 10., &link);
+    dst.emit_over_link(Packet { seq: 3 }, src.id(), 10., &link);
+
+    let mut times = event_times(&sim);
+    times.sort_by(f64::total_cmp);
+    assert_eq!(times, vec![2., 2., 3., 4.]);
+    assert_eq!(src.link_busy_until(dst.id()), Some(3.));
+    assert_eq!(src.link_busy_until(other.id()), Some(1.));
+    assert_eq!(dst.link_busy_until(src.id()), Some(1.));
+}
+
+#[test]
+fn test_serialized_channel_becomes_idle() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+
+    let link = Link::serialized(10., 0.5);
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 10., &link);
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 1.5);
+
+    // the previous transmission is finished, so the new one starts immediately
+    src.emit_over_link(Packet { seq: 1 }, dst.id(), 10., &link);
+    assert_eq!(event_times(&sim), vec![3.]);
+    assert_eq!(src.link_busy_until(dst.id()), Some(2.5));
+}
+
+#[test]
+fn test_independent_transmissions_do_not_occupy_channel() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 100., &Link::new(10., 0.));
+    src.emit_over_link(Packet { seq: 1 }, dst.id(), 10., &Link::serialized(10., 0.));
+
+    assert_eq!(event_times(&sim), vec![1., 10.]);
+}
+
+#[test]
+fn test_delay_is_not_scaled() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+
+    let _scale = src.scale_time(2.);
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 10., &Link::new(10., 1.));
+    assert_eq!(event_times(&sim), vec![2.]);
+}
+
+#[test]
+fn test_removed_component_releases_channels() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+
+    let link = Link::serialized(1., 0.);
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), 10., &link);
+    sim.remove_component("dst", EventCancellationPolicy::All);
+
+    // the identifier of removed component is reused by the new one
+    let new_dst = sim.create_context("new-dst");
+    assert_eq!(src.link_busy_until(new_dst.id()), None);
+    src.emit_over_link(Packet { seq: 1 }, new_dst.id(), 1., &link);
+    assert_eq!(event_times(&sim), vec![1.]);
+}
+
+#[test]
+#[should_panic(expected = "Link rate must be positive, got 0")]
+fn test_zero_rate() {
+    Link::new(0., 1.);
+}
+
+#[test]
+#[should_panic(expected = "Link latency must be non-negative, got -1")]
+fn test_negative_latency() {
+    Link::serialized(1., -1.);
+}
+
+#[test]
+#[should_panic(expected = "Payload size must be non-negative, got -1")]
+fn test_negative_size() {
+    let mut sim = Simulation::new(123);
+    let src = sim.create_context("src");
+    let dst = sim.create_context("dst");
+    src.emit_over_link(Packet { seq: 0 }, dst.id(), -1., &Link::new(1., 0.));
+}
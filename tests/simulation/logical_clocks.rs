@@ -0,0 +1,127 @@
+//! Tests of logical clocks.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::logical_clock::{LogicalClockKind, LogicalTime};
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Ping {
+    hops: u32,
+}
+
+struct Node {
+    peer: Id,
+    received: Vec<LogicalTime>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Ping { hops } => {
+                self.received.push(self.ctx.logical_time());
+                if hops > 0 {
+                    self.ctx.emit(Ping { hops: hops - 1 }, self.peer, 1.);
+                }
+            }
+        })
+    }
+}
+
+fn build(kind: LogicalClockKind) -> (Simulation, Rc<RefCell<Node>>, Rc<RefCell<Node>>) {
+    let mut sim = Simulation::new(123);
+    sim.enable_logical_clocks(kind);
+    sim.enable_memory_trace();
+    let a_ctx = sim.create_context("a");
+    let b_ctx = sim.create_context("b");
+    let a = Rc::new(RefCell::new(Node {
+        peer: b_ctx.id(),
+        received: Vec::new(),
+        ctx: a_ctx,
+    }));
+    let b = Rc::new(RefCell::new(Node {
+        peer: a.borrow().ctx.id(),
+        received: Vec::new(),
+        ctx: b_ctx,
+    }));
+    sim.add_handler("a", a.clone());
+    sim.add_handler("b", b.clone());
+    (sim, a, b)
+}
+
+#[test]
+fn test_lamport_clocks() {
+    let (mut sim, a, b) = build(LogicalClockKind::Lamport);
+    let b_id = sim.lookup_id("b");
+    a.borrow().ctx.emit(Ping { hops: 3 }, b_id, 1.);
+    // local events advance the clock too
+    a.borrow().ctx.emit_self(Ping { hops: 0 }, 0.5);
+    sim.step_until_no_events();
+
+    // clock is merged with the event timestamp and incremented on receive
+    let received = |node: &Rc<RefCell<Node>>| node.borrow().received.clone();
+    let lamport = |times: &[u64]| times.iter().map(|t| LogicalTime::Lamport(*t)).collect::<Vec<_>>();
+    assert_eq!(received(&a), lamport(&[3, 4, 8]));
+    assert_eq!(received(&b), lamport(&[2, 6]));
+
+    // timestamps of events in the trace grow along the causal chain
+    let trace = sim.trace();
+    let stamps: Vec<_> = trace
+        .query()
+        .dst(b_id)
+        .records()
+        .iter()
+        .map(|record| record.logical_time.clone().unwrap())
+        .collect();
+    assert_eq!(stamps, vec![LogicalTime::Lamport(1), LogicalTime::Lamport(5)]);
+}
+
+#[test]
+fn test_vector_clocks() {
+    let (mut sim, a, b) = build(LogicalClockKind::Vector);
+    let monitor = sim.create_context("monitor");
+    let (a_id, b_id) = (sim.lookup_id("a"), sim.lookup_id("b"));
+    let first = a.borrow().ctx.emit(Ping { hops: 1 }, b_id, 1.);
+    let independent = monitor.emit(Ping { hops: 0 }, a_id, 1.5);
+    sim.step_until_no_events();
+
+    assert_eq!(b.borrow().received, vec![LogicalTime::Vector(vec![1, 1])]);
+    assert_eq!(a.borrow().received.last().unwrap(), &LogicalTime::Vector(vec![3, 2, 1]));
+    assert_eq!(sim.logical_time(a_id), LogicalTime::Vector(vec![3, 2, 1]));
+    assert_eq!(monitor.logical_time(), LogicalTime::Vector(vec![0, 0, 1]));
+
+    let trace = sim.trace();
+    let first_time = trace.get(first).unwrap().logical_time.clone().unwrap();
+    let independent_time = trace.get(independent).unwrap().logical_time.clone().unwrap();
+    let reply_time = trace.query().src(b_id).records()[0].logical_time.clone().unwrap();
+    assert!(first_time.happened_before(&reply_time));
+    assert!(!reply_time.happened_before(&first_time));
+    assert!(first_time.is_concurrent(&independent_time));
+    assert!(reply_time.is_concurrent(&independent_time));
+}
+
+#[test]
+fn test_canceled_events_do_not_update_clocks() {
+    let (mut sim, a, b) = build(LogicalClockKind::Lamport);
+    let b_id = sim.lookup_id("b");
+    let event_id = a.borrow().ctx.emit(Ping { hops: 0 }, b_id, 1.);
+    assert_eq!(sim.event_logical_time(event_id), Some(LogicalTime::Lamport(1)));
+    a.borrow().ctx.cancel_event(event_id);
+    sim.step_until_no_events();
+
+    assert_eq!(sim.event_logical_time(event_id), None);
+    assert_eq!(sim.logical_time(b_id), LogicalTime::Lamport(0));
+    assert!(b.borrow().received.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Logical clocks are not enabled")]
+fn test_logical_clocks_disabled() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.logical_time();
+}
@@ -0,0 +1,144 @@
+//! Tests of in-memory trace of processed events.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Token {
+    hops: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Done {}
+
+struct Node {
+    ctx: SimulationContext,
+    next: Id,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Token { hops } => {
+                if hops > 0 {
+                    self.ctx.emit(Token { hops: hops - 1 }, self.next, 1.);
+                } else {
+                    self.ctx.emit_self_now(Done {});
+                }
+            }
+            Done {} => {}
+        })
+    }
+}
+
+#[test]
+fn test_trace_follows_causal_chains() {
+    let mut sim = Simulation::new(123);
+    let node_count = 3;
+    for i in 0..node_count {
+        let name = format!("node-{}", i);
+        let node = Node {
+            ctx: sim.create_context(&name),
+            next: (i + 1) % node_count,
+        };
+        sim.add_handler(&name, Rc::new(RefCell::new(node)));
+    }
+    let client = sim.create_context("client");
+    let first = client.emit(Token { hops: 5 }, 0, 0.);
+    let second = client.emit(Token { hops: 2 }, 1, 0.5);
+
+    // events processed before enabling the trace are not recorded
+    sim.step();
+    sim.enable_memory_trace();
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    // 5 hops + done of first token, 3 tokens + done of second token
+    assert_eq!(trace.len(), 5 + 1 + 3 + 1);
+    assert!(trace.get(first).is_none());
+    assert_eq!(trace.get(second).unwrap().parent, None);
+
+    // first event is not recorded, so its effects are disconnected from it
+    assert!(trace.effects(first).is_empty());
+    let second_effects = trace.effects(second);
+    assert_eq!(second_effects.len(), 3);
+    assert!(second_effects.windows(2).all(|w| w[0].time <= w[1].time));
+    let done = second_effects.last().unwrap();
+    assert!(done.data.is::<Done>());
+
+    let chain = trace.causes(done.id);
+    assert_eq!(chain.len(), 4);
+    assert_eq!(chain[0].id, second);
+    assert!(chain.windows(2).all(|w| w[1].parent == Some(w[0].id)));
+
+    assert_eq!(trace.query().event_type::<Done>().count(), 2);
+    assert_eq!(trace.query().dst(1).event_type::<Token>().count(), 3);
+    assert_eq!(trace.query().src(client.id()).count(), 1);
+    assert_eq!(trace.query().component(0).time_range(1., 3.).count(), 4);
+}
+
+#[test]
+fn test_events_emitted_outside_handlers_have_no_parent() {
+    let mut sim = Simulation::new(123);
+    let node = Node {
+        ctx: sim.create_context("node"),
+        next: 0,
+    };
+    sim.add_handler("node", Rc::new(RefCell::new(node)));
+    sim.enable_memory_trace();
+    let client = sim.create_context("client");
+
+    let first = client.emit(Token { hops: 0 }, 0, 1.);
+    sim.step();
+    // emitted after the step, so it is not caused by the first token
+    let second = client.emit(Token { hops: 0 }, 0, 1.);
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    assert_eq!(trace.get(second).unwrap().parent, None);
+    assert_eq!(trace.causes(second).len(), 1);
+    assert_eq!(trace.effects(first).len(), 1);
+    assert_eq!(trace.effects(second).len(), 1);
+}
+
+struct BatchNode {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for BatchNode {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Token { hops } => {
+                if hops > 0 {
+                    self.ctx.emit_self(Token { hops: hops - 1 }, 1.);
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn test_events_emitted_from_batch_are_attributed_to_first_event() {
+    let mut sim = Simulation::new(123);
+    let node = BatchNode {
+        ctx: sim.create_context("node"),
+    };
+    sim.add_handler("node", Rc::new(RefCell::new(node)));
+    sim.enable_event_batching("node");
+    sim.enable_memory_trace();
+    let client = sim.create_context("client");
+
+    let first = client.emit(Token { hops: 1 }, 0, 1.);
+    let second = client.emit(Token { hops: 1 }, 0, 1.);
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    assert_eq!(trace.len(), 4);
+    // both events are processed in a single invocation of the handler
+    assert_eq!(trace.effects(first).len(), 2);
+    assert!(trace.effects(second).is_empty());
+}
@@ -0,0 +1,314 @@
+//! Tests of metrics collected by components.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::handler::EventCancellationPolicy;
+use simcore::metrics::{MetricKind, MetricValue, TimeWeighted, TimeWeightedValue};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    size: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct Done {}
+
+struct Server {
+    ctx: SimulationContext,
+    queue: usize,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { size } => {
+                self.ctx.counter_inc("requests");
+                self.ctx.counter_add("bytes", size);
+                self.queue += 1;
+                self.ctx.gauge_set("queue", self.queue as f64);
+                self.ctx.emit_self(Done {}, size);
+                self.ctx.histogram_record("size", size);
+            }
+            Done {} => {
+                self.queue -= 1;
+                self.ctx.gauge_set("queue", self.queue as f64);
+            }
+        })
+    }
+}
+
+fn build() -> (Simulation, SimulationContext) {
+    let mut sim = Simulation::new(123);
+    sim.set_histogram_buckets("size", &[1., 2.]);
+    let client = sim.create_context("client");
+    for name in ["server1", "server2"] {
+        let server = Server {
+            ctx: sim.create_context(name),
+            queue: 0,
+        };
+        sim.add_handler(name, Rc::new(RefCell::new(server)));
+    }
+    (sim, client)
+}
+
+fn histogram(value: &MetricValue) -> &simcore::metrics::HistogramValue {
+    match value {
+        MetricValue::Histogram(histogram) => histogram,
+        _ => panic!("Metric is not a histogram"),
+    }
+}
+
+#[test]
+fn test_component_metrics() {
+    let (mut sim, client) = build();
+    let server1 = sim.lookup_id("server1");
+    let server2 = sim.lookup_id("server2");
+    client.emit(Request { size: 1. }, server1, 0.);
+    client.emit(Request { size: 3. }, server1, 0.);
+    client.emit(Request { size: 1.5 }, server2, 1.);
+    sim.step_until_no_events();
+
+    let report = sim.metrics();
+    assert_eq!(report.get("server1", "requests"), Some(&MetricValue::Counter(2.)));
+    assert_eq!(report.get("server1", "bytes"), Some(&MetricValue::Counter(4.)));
+    assert_eq!(report.get("server2", "requests"), Some(&MetricValue::Counter(1.)));
+    assert!(report.get("client", "requests").is_none());
+    assert_eq!(report.names(), vec!["bytes", "queue", "requests", "size"]);
+
+    match report.get("server1", "queue").unwrap() {
+        MetricValue::Gauge(gauge) => {
+            assert_eq!(gauge.value, 0.);
+            assert_eq!(gauge.min, 0.);
+            assert_eq!(gauge.max, 2.);
+            assert_eq!(gauge.updates, 4);
+        }
+        value => panic!("Unexpected metric {:?}", value),
+    }
+
+    let sizes = histogram(report.get("server1", "size").unwrap());
+    assert_eq!(sizes.count, 2);
+    assert_eq!(sizes.mean(), 2.);
+    assert_eq!(sizes.std_dev(), 1.);
+    assert_eq!(sizes.buckets, vec![1, 0, 1]);
+
+    let total = report.aggregate("size").unwrap();
+    assert_eq!(total.kind(), MetricKind::Histogram);
+    assert_eq!(histogram(&total).buckets, vec![1, 1, 1]);
+    assert_eq!(histogram(&total).min, 1.);
+    assert_eq!(histogram(&total).max, 3.);
+    assert_eq!(report.aggregate("requests"), Some(MetricValue::Counter(3.)));
+    assert!(report.aggregate("unknown").is_none());
+
+    let run_metrics = report.run_metrics();
+    assert_eq!(run_metrics["server1.bytes"], 4.);
+    assert_eq!(run_metrics["bytes"], 5.5);
+    assert_eq!(run_metrics["size"], 5.5 / 3.);
+    assert_eq!(run_metrics.len(), 12);
+
+    let output = report.to_string();
+    assert!(output.starts_with("component"));
+    assert_eq!(output.lines().count(), 9);
+    assert!(output.contains("server2    requests  counter    1"));
+}
+
+#[test]
+fn test_removed_component_metrics() {
+    let (mut sim, client) = build();
+    let server1 = sim.lookup_id("server1");
+    client.emit(Request { size: 1. }, server1, 0.);
+    sim.step_until_no_events();
+    sim.remove_component("server1", EventCancellationPolicy::None);
+
+    // the identifier is reused by the new component
+    let server3 = sim.create_context("server3");
+    assert_eq!(server3.id(), server1);
+    assert_eq!(server3.counter("requests"), 0.);
+    server3.counter_inc("requests");
+
+    let report = sim.metrics();
+    assert_eq!(report.get("server1", "requests"), Some(&MetricValue::Counter(1.)));
+    assert_eq!(report.get("server3", "requests"), Some(&MetricValue::Counter(1.)));
+    assert_eq!(report.aggregate("requests"), Some(MetricValue::Counter(2.)));
+}
+
+#[test]
+fn test_metrics_reset_after_warmup() {
+    let (mut sim, client) = build();
+    let server1 = sim.lookup_id("server1");
+    client.emit(Request { size: 1. }, server1, 0.);
+    client.emit(Request { size: 10. }, server1, 1.);
+    client.emit(Request { size: 2. }, server1, 5.);
+    sim.set_warmup_time(3.);
+    sim.step_until_no_events();
+
+    let report = sim.metrics();
+    assert_eq!(report.get("server1", "requests"), Some(&MetricValue::Counter(1.)));
+    let sizes = histogram(report.get("server1", "size").unwrap());
+    assert_eq!(sizes.count, 1);
+    assert_eq!(sizes.buckets, vec![0, 1, 0]);
+    match report.get("server1", "queue").unwrap() {
+        // the gauge keeps the value 1 at the end of warmup and is updated at times 5, 7 and 11
+        MetricValue::Gauge(gauge) => {
+            assert_eq!(gauge.value, 0.);
+            assert_eq!(gauge.max, 2.);
+            assert_eq!(gauge.updates, 3);
+        }
+        value => panic!("Unexpected metric {:?}", value),
+    }
+}
+
+#[test]
+#[should_panic(expected = "Metric requests of component server is a counter, not a gauge")]
+fn test_kind_mismatch() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    ctx.counter_inc("requests");
+    ctx.gauge_set("requests", 1.);
+}
+
+#[test]
+#[should_panic(expected = "Bucket bounds of histogram latency must be increasing")]
+fn test_unsorted_buckets() {
+    let mut sim = Simulation::new(123);
+    sim.set_histogram_buckets("latency", &[1., 1.]);
+}
+
+#[test]
+#[should_panic(expected = "Bucket bounds of histogram latency must be set before recording values")]
+fn test_late_buckets() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    ctx.histogram_record("latency", 1.);
+    sim.set_histogram_buckets("latency", &[1.]);
+}
+
+fn time_weighted(value: &MetricValue) -> &TimeWeightedValue {
+    match value {
+        MetricValue::TimeWeighted(time_weighted) => time_weighted,
+        _ => panic!("Metric is not time-weighted"),
+    }
+}
+
+// Server with a single processor, whose queue length and utilization are tracked over time.
+struct QueueServer {
+    ctx: SimulationContext,
+    queue: TimeWeighted<usize>,
+    busy: TimeWeighted<bool>,
+}
+
+impl EventHandler for QueueServer {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { size } => {
+                if self.busy.get() {
+                    self.queue.set(self.queue.get() + 1);
+                } else {
+                    self.busy.set(true);
+                    self.ctx.emit_self(Done {}, size);
+                }
+            }
+            Done {} => {
+                if self.queue.get() > 0 {
+                    self.queue.set(self.queue.get() - 1);
+                    self.ctx.emit_self(Done {}, 1.);
+                } else {
+                    self.busy.set(false);
+                }
+            }
+        })
+    }
+}
+
+fn build_queue_server(sim: &mut Simulation, name: &str) -> Rc<RefCell<QueueServer>> {
+    let ctx = sim.create_context(name);
+    let server = Rc::new(RefCell::new(QueueServer {
+        queue: TimeWeighted::new(&ctx, "queue", 0),
+        busy: TimeWeighted::new(&ctx, "busy", false),
+        ctx,
+    }));
+    sim.add_handler(name, server.clone());
+    server
+}
+
+#[test]
+fn test_time_weighted() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = build_queue_server(&mut sim, "server");
+    let server_id = sim.lookup_id("server");
+    // requests at times 1, 1 and 1 are served in [1, 2), [2, 3) and [3, 4)
+    for _ in 0..3 {
+        client.emit(Request { size: 1. }, server_id, 1.);
+    }
+    sim.step_until_time(8.);
+
+    let server = server.borrow();
+    assert_eq!(server.busy.mean(), 3. / 8.);
+    // queue length is 2 in [1, 2) and 1 in [2, 3)
+    assert_eq!(server.queue.mean(), 3. / 8.);
+    let stats = server.queue.stats();
+    assert_eq!((stats.min, stats.max, stats.value), (0., 2., 0.));
+    assert_eq!((stats.start_time, stats.last_time), (0., 8.));
+    assert_eq!(stats.updates, 5);
+
+    let report = sim.metrics();
+    assert_eq!(
+        time_weighted(report.get("server", "busy").unwrap()),
+        &server.busy.stats()
+    );
+    assert_eq!(report.get("server", "busy").unwrap().kind(), MetricKind::TimeWeighted);
+    assert_eq!(report.run_metrics()["server.busy"], 3. / 8.);
+    assert!(report
+        .to_string()
+        .contains("time-weighted  mean 0.375, last 0 (min 0, max 1)"));
+}
+
+#[test]
+fn test_time_weighted_aggregate() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    build_queue_server(&mut sim, "server1");
+    build_queue_server(&mut sim, "server2");
+    client.emit(Request { size: 2. }, sim.lookup_id("server1"), 0.);
+    client.emit(Request { size: 1. }, sim.lookup_id("server2"), 0.);
+    sim.step_until_time(4.);
+
+    // the aggregated utilization is the average number of busy servers
+    let busy = sim.metrics().aggregate("busy").unwrap();
+    assert_eq!(time_weighted(&busy).mean(), 3. / 4.);
+    assert_eq!(time_weighted(&busy).max, 1.);
+}
+
+#[test]
+fn test_time_weighted_after_warmup_and_removal() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    build_queue_server(&mut sim, "server");
+    let server_id = sim.lookup_id("server");
+    client.emit(Request { size: 4. }, server_id, 0.);
+    client.emit(Request { size: 1. }, server_id, 6.);
+    sim.set_warmup_time(2.);
+    sim.step_until_time(8.);
+    sim.remove_component("server", EventCancellationPolicy::None);
+    sim.step_until_time(10.);
+
+    // the server is busy in [2, 4) and [6, 7) after warmup and the averaging stops at removal
+    let report = sim.metrics();
+    let busy = time_weighted(report.get("server", "busy").unwrap());
+    assert_eq!((busy.start_time, busy.last_time), (2., 8.));
+    assert_eq!(busy.mean(), 3. / 6.);
+    assert_eq!(busy.updates, 3);
+}
+
+#[test]
+#[should_panic(expected = "Metric queue of component server is a counter, not a time-weighted")]
+fn test_time_weighted_kind_mismatch() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    ctx.counter_inc("queue");
+    TimeWeighted::new(&ctx, "queue", 0);
+}
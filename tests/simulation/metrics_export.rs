@@ -0,0 +1,176 @@
+//! Tests of metrics export.
+
+use std::path::PathBuf;
+
+use simcore::metrics::TimeWeighted;
+use simcore::metrics_export::{write_series, write_summary, ExportFormat, MetricsExportConfig};
+use simcore::Simulation;
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-metrics-{}-{}", name, std::process::id()))
+}
+
+fn build(seed: u64) -> Simulation {
+    let mut sim = Simulation::new(seed);
+    sim.enable_metric_series();
+    sim.set_run_label("servers", "2");
+    sim.set_run_label("policy", "round, robin");
+    let ctx = sim.create_context("server");
+    let mut queue = TimeWeighted::new(&ctx, "queue", 0);
+    ctx.counter_inc("requests");
+    sim.step_until_time(1.);
+    queue.set(2);
+    ctx.histogram_record("latency", 1.);
+    ctx.histogram_record("latency", 3.);
+    sim.step_until_time(2.);
+    ctx.gauge_set("load", 0.5);
+    sim
+}
+
+#[test]
+fn test_summary_csv() {
+    let sim = build(42);
+    let mut output = Vec::new();
+    write_summary(&sim.metrics(), &sim.run_metadata(), ExportFormat::Csv, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "seed,config_hash,policy,servers,component,metric,kind,value,count,sum,min,max,mean,std_dev",
+            "42,,\"round, robin\",2,server,latency,histogram,2,2,4,1,3,2,1",
+            "42,,\"round, robin\",2,server,load,gauge,0.5,1,,0.5,0.5,,",
+            "42,,\"round, robin\",2,server,queue,time-weighted,1,2,2,0,2,1,",
+            "42,,\"round, robin\",2,server,requests,counter,1,,,,,,",
+        ]
+    );
+}
+
+#[test]
+fn test_series_csv() {
+    let mut sim = build(42);
+    sim.set_run_config(&"config");
+    let hash = sim.run_metadata().config_hash.unwrap();
+    let mut output = Vec::new();
+    write_series(
+        &sim.metric_series(),
+        &sim.run_metadata(),
+        ExportFormat::Csv,
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let rows: Vec<String> = output
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.split_once(&format!("42,{},\"round, robin\",2,", hash))
+                .unwrap()
+                .1
+                .to_owned()
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            "server,latency,1,1",
+            "server,latency,1,3",
+            "server,load,2,0.5",
+            "server,queue,0,0",
+            "server,queue,1,2",
+            "server,requests,0,1",
+        ]
+    );
+}
+
+#[test]
+fn test_export_files() {
+    let dir = temp_dir("files");
+    let sim = build(1);
+    let paths = sim.export_metrics(&MetricsExportConfig::new(&dir));
+    assert_eq!(paths, vec![dir.join("summary.csv"), dir.join("series.csv")]);
+    let summary = std::fs::read_to_string(&paths[0]).unwrap();
+    assert_eq!(summary.lines().count(), 5);
+    let series = std::fs::read_to_string(&paths[1]).unwrap();
+    assert_eq!(series.lines().count(), 7);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_series_cleared_after_warmup() {
+    let mut sim = Simulation::new(123);
+    sim.enable_metric_series();
+    sim.set_warmup_time(5.);
+    let ctx = sim.create_context("server");
+    ctx.counter_inc("requests");
+    sim.step_until_time(6.);
+    ctx.counter_inc("requests");
+    assert_eq!(sim.metric_series()["server"]["requests"], vec![(6., 1.)]);
+}
+
+#[test]
+fn test_series_disabled() {
+    let dir = temp_dir("no-series");
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    ctx.counter_inc("requests");
+    assert!(sim.metric_series()["server"]["requests"].is_empty());
+    let paths = sim.export_metrics(&MetricsExportConfig::new(&dir));
+    assert_eq!(paths, vec![dir.join("summary.csv")]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_export_parquet() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    let dir = temp_dir("parquet");
+    let sim = build(7);
+    let mut config = MetricsExportConfig::new(&dir);
+    config.format = ExportFormat::Parquet;
+    config.prefix = "run_".to_owned();
+    let paths = sim.export_metrics(&config);
+    assert_eq!(
+        paths,
+        vec![dir.join("run_summary.parquet"), dir.join("run_series.parquet")]
+    );
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&paths[0]).unwrap()).unwrap();
+    let schema = reader.metadata().file_metadata().schema_descr();
+    let names: Vec<&str> = schema.columns().iter().map(|column| column.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "seed",
+            "config_hash",
+            "policy",
+            "servers",
+            "component",
+            "metric",
+            "kind",
+            "value",
+            "count",
+            "sum",
+            "min",
+            "max",
+            "mean",
+            "std_dev"
+        ]
+    );
+    let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(|row| row.unwrap()).collect();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0].get_long(0).unwrap(), 7);
+    assert!(rows[0].get_string(1).is_err());
+    assert_eq!(rows[0].get_string(2).unwrap(), "round, robin");
+    assert_eq!(rows[0].get_string(5).unwrap(), "latency");
+    assert_eq!(rows[0].get_double(7).unwrap(), 2.);
+    assert_eq!(rows[0].get_long(8).unwrap(), 2);
+    assert_eq!(rows[3].get_string(6).unwrap(), "counter");
+    assert!(rows[3].get_long(8).is_err());
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&paths[1]).unwrap()).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 6);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1 +1,73 @@
+mod analysis;
+mod arrival_generator;
+mod branching;
+mod breakpoints;
+mod checkpoint;
+mod chrome_trace;
+mod component_removal;
+#[cfg(feature = "zstd")]
+mod compression;
+mod continuous;
+mod contracts;
+mod cosim;
+mod default_delay;
+mod determinism;
+mod distributions;
+mod divergence;
+mod emit_after;
+mod emit_as;
+mod emit_at;
+mod emit_hook;
+mod event_batching;
 mod event_cancellation;
+mod event_coalescing;
+#[cfg(feature = "derive")]
+mod event_handler_attr;
+mod event_logging;
+mod event_order;
+mod event_priority;
+mod event_spilling;
+mod event_versions;
+mod group_idle;
+mod input_gateway;
+mod link;
+mod logical_clocks;
+mod memory_trace;
+mod metrics;
+mod metrics_export;
+mod named_timers;
+mod ordering_assumptions;
+#[cfg(feature = "thread")]
+mod parallel;
+mod producer_stats;
+mod queue_dump;
+mod random_choice;
+mod rate_limit;
+mod real_time;
+mod replay_mock;
+mod replications;
+mod resolution;
+mod resource_limits;
+mod routing;
+mod run_metadata;
+mod run_result;
+mod snapshot;
+mod startup;
+mod state_machine;
+mod step_back;
+mod step_observer;
+mod stop_conditions;
+mod sweep;
+mod time_advance;
+mod time_scale;
+mod time_tick;
+mod timeout_table;
+mod trace_diff;
+mod trace_file;
+mod trace_replay;
+mod trace_sampling;
+mod typed_time;
+mod waiting_queue;
+mod warmup;
+mod watchpoints;
+mod workload;
@@ -0,0 +1,173 @@
+//! Tests of named timers.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::timer::TimerFired;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Ack {
+    seq: u32,
+}
+
+// Sends messages one by one and retransmits the current message on timeout.
+struct Sender {
+    ctx: SimulationContext,
+    receiver: Id,
+    seq: u32,
+    count: u32,
+    log: Vec<(String, u32, f64)>,
+}
+
+impl Sender {
+    fn send(&mut self) {
+        self.log.push(("send".to_owned(), self.seq, self.ctx.time()));
+        self.ctx.emit(Message { seq: self.seq }, self.receiver, 1.);
+        self.ctx.set_timer("retransmit", 3.);
+    }
+}
+
+impl EventHandler for Sender {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Ack { seq } => {
+                if seq == self.seq {
+                    self.seq += 1;
+                    if self.seq < self.count {
+                        // replaces the pending timer
+                        self.send();
+                    } else {
+                        assert!(self.ctx.cancel_timer("retransmit"));
+                    }
+                }
+            }
+            TimerFired { name } => {
+                assert_eq!(name, "retransmit");
+                assert!(!self.ctx.has_timer("retransmit"));
+                self.send();
+            }
+        })
+    }
+}
+
+// Acknowledges messages, drops every second message received.
+struct Receiver {
+    ctx: SimulationContext,
+    received: u32,
+}
+
+impl EventHandler for Receiver {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Message { seq } => {
+                self.received += 1;
+                if self.received % 2 == 1 {
+                    self.ctx.emit(Ack { seq }, event.src, 1.);
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn test_timer_replacement_and_cancellation() {
+    let mut sim = Simulation::new(123);
+    let receiver_ctx = sim.create_context("receiver");
+    let receiver_id = receiver_ctx.id();
+    let sender = Rc::new(RefCell::new(Sender {
+        ctx: sim.create_context("sender"),
+        receiver: receiver_id,
+        seq: 0,
+        count: 3,
+        log: Vec::new(),
+    }));
+    sim.add_handler("sender", sender.clone());
+    sim.add_handler(
+        "receiver",
+        Rc::new(RefCell::new(Receiver {
+            ctx: receiver_ctx,
+            received: 0,
+        })),
+    );
+
+    sender.borrow_mut().send();
+    sim.step_until_no_events();
+
+    let sender = sender.borrow();
+    let log: Vec<_> = sender
+        .log
+        .iter()
+        .map(|(op, seq, time)| (op.as_str(), *seq, *time))
+        .collect();
+    assert_eq!(
+        log,
+        vec![
+            ("send", 0, 0.),
+            ("send", 1, 2.),
+            ("send", 1, 5.),
+            ("send", 2, 7.),
+            ("send", 2, 10.)
+        ]
+    );
+    // the last timer is cancelled
+    assert_eq!(sim.time(), 12.);
+    assert!(!sender.ctx.has_timer("retransmit"));
+}
+
+#[test]
+fn test_timer_names_are_local_to_component() {
+    let mut sim = Simulation::new(123);
+    let ctx1 = sim.create_context("comp1");
+    let ctx2 = sim.create_context("comp2");
+
+    ctx1.set_timer("timer", 1.);
+    ctx2.set_timer("timer", 2.);
+    assert!(ctx1.cancel_timer("timer"));
+    assert!(!ctx1.has_timer("timer"));
+    assert!(ctx2.has_timer("timer"));
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    assert!(!ctx2.has_timer("timer"));
+}
+
+// Records the names of fired timers.
+struct TimerLog {
+    fired: Vec<(String, f64)>,
+}
+
+impl EventHandler for TimerLog {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            TimerFired { name } => {
+                self.fired.push((name, event.time));
+            }
+        })
+    }
+}
+
+#[test]
+fn test_coalesced_timers() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let log = Rc::new(RefCell::new(TimerLog { fired: Vec::new() }));
+    sim.add_handler("comp", log.clone());
+    sim.enable_event_coalescing("comp", 1.);
+
+    ctx.set_timer("a", 1.);
+    ctx.set_timer("b", 1.5);
+    sim.step_until_no_events();
+    // both timers are delivered in one batch at time 1
+    assert_eq!(sim.time(), 1.);
+    assert_eq!(log.borrow().fired, vec![("a".to_owned(), 1.), ("b".to_owned(), 1.5)]);
+    assert!(!ctx.has_timer("a"));
+    assert!(!ctx.has_timer("b"));
+}
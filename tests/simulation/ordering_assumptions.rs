@@ -0,0 +1,150 @@
+//! Tests of checking event ordering assumptions.
+
+use serde::Serialize;
+
+use simcore::ordering::OrderingPolicy;
+use simcore::{EventCancellationPolicy, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Packet {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Ack {}
+
+#[test]
+fn test_fifo_preserved() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let receiver = sim.create_context("receiver");
+    sim.assume_fifo("sender", "receiver");
+
+    for seq in 0..10 {
+        sender.emit(Packet { seq }, receiver.id(), 1. + seq as f64 * 0.1);
+    }
+    // events with equal times are delivered in emission order
+    sender.emit(Packet { seq: 10 }, receiver.id(), 2.);
+    sender.emit(Ack {}, receiver.id(), 2.);
+    sim.step_until_no_events();
+
+    assert!(sim.take_ordering_violations().is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "FIFO ordering assumption is violated: event 0 (Packet) from sender to receiver delivered at 3 \
+                after event 1 (Ack) emitted later and delivered at 1"
+)]
+fn test_violation_panics() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let receiver = sim.create_context("receiver");
+    sim.assume_fifo("sender", "receiver");
+
+    sender.emit(Packet { seq: 0 }, receiver.id(), 3.);
+    sender.emit(Ack {}, receiver.id(), 1.);
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_randomized_delays_recorded() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    let receiver = sim.create_context("receiver");
+    sim.assume_fifo("sender", "receiver");
+    sim.set_ordering_policy(OrderingPolicy::Record);
+
+    let mut deliveries = Vec::new();
+    for seq in 0..100 {
+        let delivery = seq as f64 + sender.gen_range(0.0..5.0);
+        deliveries.push(delivery);
+        sender.emit(Packet { seq }, receiver.id(), delivery);
+    }
+    sim.step_until_no_events();
+
+    // the packet is overtaken if some packet sent later is delivered earlier
+    let expected = (0..deliveries.len())
+        .filter(|&i| deliveries[i + 1..].iter().any(|&d| d < deliveries[i]))
+        .count();
+
+    let violations = sim.take_ordering_violations();
+    assert!(expected > 0);
+    assert_eq!(violations.len(), expected);
+    for violation in &violations {
+        assert_eq!(violation.src, "sender");
+        assert_eq!(violation.dst, "receiver");
+        assert_eq!(violation.event_type, "Packet");
+        assert!(violation.event_id < violation.overtaking_event_id);
+        assert!(violation.time >= violation.overtaking_time);
+    }
+    // violations are taken
+    assert!(sim.take_ordering_violations().is_empty());
+}
+
+#[test]
+fn test_undeclared_pairs_not_checked() {
+    let mut sim = Simulation::new(123);
+    let a = sim.create_context("a");
+    let b = sim.create_context("b");
+    let c = sim.create_context("c");
+    sim.assume_fifo("a", "b");
+    sim.set_ordering_policy(OrderingPolicy::Record);
+
+    // reverse direction and other pairs
+    b.emit(Packet { seq: 0 }, a.id(), 5.);
+    b.emit(Packet { seq: 1 }, a.id(), 1.);
+    a.emit(Packet { seq: 0 }, c.id(), 5.);
+    a.emit(Packet { seq: 1 }, c.id(), 1.);
+    // different sources to the checked destination
+    c.emit(Packet { seq: 0 }, b.id(), 5.);
+    a.emit(Packet { seq: 1 }, b.id(), 1.);
+    sim.step_until_no_events();
+
+    assert!(sim.take_ordering_violations().is_empty());
+}
+
+#[test]
+fn test_fifo_to_destination() {
+    let mut sim = Simulation::new(123);
+    let a = sim.create_context("a");
+    let b = sim.create_context("b");
+    let server = sim.create_context("server");
+    sim.assume_fifo_to("server");
+    sim.set_ordering_policy(OrderingPolicy::Record);
+
+    // events from different sources can be reordered
+    a.emit(Packet { seq: 0 }, server.id(), 5.);
+    b.emit(Packet { seq: 0 }, server.id(), 1.);
+    // the source created after the declaration is also checked
+    let c = sim.create_context("c");
+    let first = c.emit(Packet { seq: 0 }, server.id(), 4.);
+    let second = c.emit(Packet { seq: 1 }, server.id(), 2.);
+    sim.step_until_no_events();
+
+    let violations = sim.take_ordering_violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].src, "c");
+    assert_eq!(violations[0].event_id, first);
+    assert_eq!(violations[0].time, 4.);
+    assert_eq!(violations[0].overtaking_event_id, second);
+    assert_eq!(violations[0].overtaking_time, 2.);
+}
+
+#[test]
+fn test_assumptions_removed_with_component() {
+    let mut sim = Simulation::new(123);
+    let sender = sim.create_context("sender");
+    sim.create_context("receiver");
+    sim.assume_fifo("sender", "receiver");
+    sim.set_ordering_policy(OrderingPolicy::Record);
+    sim.remove_component("receiver", EventCancellationPolicy::None);
+
+    // the identifier of removed component is reused
+    let other = sim.create_context("other");
+    sender.emit(Packet { seq: 0 }, other.id(), 5.);
+    sender.emit(Packet { seq: 1 }, other.id(), 1.);
+    sim.step_until_no_events();
+
+    assert!(sim.take_ordering_violations().is_empty());
+}
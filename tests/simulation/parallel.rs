@@ -0,0 +1,206 @@
+//! Tests of conservative parallel execution.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::parallel::ParallelSimulation;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Token {
+    id: u32,
+    hops: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Unregistered {}
+
+struct Node {
+    next: Id,
+    delay: f64,
+    jitter: f64,
+    received: Vec<(f64, u32, u32)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Token { id, hops } => {
+                self.received.push((self.ctx.time(), id, hops));
+                if hops < 20 {
+                    let delay = self.delay + self.ctx.gen_range(0.0..=self.jitter);
+                    self.ctx.emit(Token { id, hops: hops + 1 }, self.next, delay);
+                }
+            }
+        })
+    }
+}
+
+const NODES: usize = 6;
+
+fn add_node(sim: &mut Simulation, index: usize, jitter: f64) -> Rc<RefCell<Node>> {
+    let name = format!("node{}", index);
+    let ctx = sim.create_context(&name);
+    let next = sim.lookup_id(&format!("node{}", (index + 1) % NODES));
+    // distinct delays avoid simultaneous events whose order depends on partitioning
+    let delay = 1. + 0.013 * index as f64;
+    let node = Rc::new(RefCell::new(Node {
+        next,
+        delay,
+        jitter,
+        received: Vec::new(),
+        ctx,
+    }));
+    sim.add_handler(&name, node.clone());
+    // each node starts its own token
+    node.borrow().ctx.emit_self(
+        Token {
+            id: index as u32,
+            hops: 0,
+        },
+        0.1 * index as f64,
+    );
+    node
+}
+
+type Received = Vec<Vec<(f64, u32, u32)>>;
+
+fn run_parallel(partitions: usize, jitter: f64, time: f64) -> Received {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.register_event::<Token>();
+    let per_partition = NODES / partitions;
+    for partition in 0..partitions {
+        let indices: Vec<usize> = (partition * per_partition..(partition + 1) * per_partition).collect();
+        let names: Vec<String> = indices.iter().map(|index| format!("node{}", index)).collect();
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        sim.add_partition(&names, move |sim| {
+            // the contexts of remote nodes are already created as proxies
+            for index in indices.iter() {
+                sim.create_context(format!("node{}", index));
+            }
+            indices
+                .iter()
+                .map(|index| add_node(sim, *index, jitter))
+                .collect::<Vec<_>>()
+        });
+    }
+    sim.step_until_time(time);
+    assert_eq!(sim.time(), time);
+    let mut received = Vec::new();
+    for partition in 0..partitions {
+        received.extend(sim.execute(partition, |_, nodes| {
+            nodes
+                .iter()
+                .map(|node| node.borrow().received.clone())
+                .collect::<Vec<_>>()
+        }));
+    }
+    received
+}
+
+#[test]
+fn test_parallel_matches_sequential() {
+    let mut sim = Simulation::new(123);
+    for index in 0..NODES {
+        sim.create_context(format!("node{}", index));
+    }
+    let nodes: Vec<_> = (0..NODES).map(|index| add_node(&mut sim, index, 0.)).collect();
+    sim.step_until_time(15.);
+    let expected: Received = nodes.iter().map(|node| node.borrow().received.clone()).collect();
+
+    for partitions in [1, 2, 3] {
+        assert_eq!(run_parallel(partitions, 0., 15.), expected);
+    }
+}
+
+#[test]
+fn test_parallel_is_deterministic() {
+    let first = run_parallel(3, 0.5, 20.);
+    for _ in 0..3 {
+        assert_eq!(run_parallel(3, 0.5, 20.), first);
+    }
+}
+
+#[test]
+fn test_step_in_parts() {
+    let mut sim = ParallelSimulation::new(123, 0.5);
+    sim.register_event::<Token>();
+    let a = sim.add_partition(&["a"], |sim| {
+        let ctx = sim.create_context("a");
+        let b = sim.lookup_id("b");
+        ctx.emit(Token { id: 0, hops: 0 }, b, 0.5);
+        ctx.emit(Token { id: 1, hops: 0 }, b, 2.5);
+        ctx
+    });
+    let b = sim.add_partition(&["b"], |sim| sim.create_context("b"));
+    assert_eq!(sim.partitions(), 2);
+
+    sim.step_until_time(1.);
+    assert_eq!(sim.execute(a, |sim, _| sim.time()), 1.);
+    assert_eq!(sim.execute(b, |sim, _| sim.time()), 1.);
+    // the events received from other partitions are counted on arrival
+    assert_eq!(sim.execute(b, |sim, _| sim.event_count()), 1);
+    sim.step_until_time(2.);
+    assert_eq!(sim.execute(b, |sim, _| sim.event_count()), 1);
+    sim.step_until_time(3.);
+    assert_eq!(sim.execute(b, |sim, _| sim.event_count()), 2);
+    assert_eq!(sim.time(), 3.);
+}
+
+#[test]
+#[should_panic(expected = "Delay of event sent to remote component b is less than lookahead 1")]
+fn test_delay_below_lookahead() {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.register_event::<Token>();
+    sim.add_partition(&["a"], |sim| {
+        let ctx = sim.create_context("a");
+        let b = sim.lookup_id("b");
+        ctx.emit(Token { id: 0, hops: 0 }, b, 0.5);
+    });
+    sim.add_partition(&["b"], |sim| {
+        sim.create_context("b");
+    });
+    sim.step_until_time(10.);
+}
+
+#[test]
+#[should_panic(expected = "Type of event sent to remote component is not registered")]
+fn test_unregistered_event() {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.add_partition(&["a"], |sim| {
+        let ctx = sim.create_context("a");
+        let b = sim.lookup_id("b");
+        ctx.emit(Unregistered {}, b, 1.);
+    });
+    sim.add_partition(&["b"], |sim| {
+        sim.create_context("b");
+    });
+    sim.step_until_time(10.);
+}
+
+#[test]
+#[should_panic(expected = "Ordered and deferred events cannot be sent to remote component b")]
+fn test_ordered_event_to_remote() {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.register_event::<Token>();
+    sim.add_partition(&["a"], |sim| {
+        let ctx = sim.create_context("a");
+        let b = sim.lookup_id("b");
+        ctx.emit_ordered(Token { id: 0, hops: 0 }, b, 1.);
+    });
+    sim.add_partition(&["b"], |sim| {
+        sim.create_context("b");
+    });
+    sim.step_until_time(10.);
+}
+
+#[test]
+#[should_panic(expected = "Component a is already declared by another partition")]
+fn test_duplicate_component() {
+    let mut sim = ParallelSimulation::new(123, 1.);
+    sim.add_partition(&["a"], |_| {});
+    sim.add_partition(&["b", "a"], |_| {});
+}
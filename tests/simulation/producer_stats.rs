@@ -0,0 +1,119 @@
+//! Tests of event producer diagnostics.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::producers::ProducerStatsConfig;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Tick {}
+
+#[derive(Clone, Serialize)]
+struct Gossip {}
+
+// Emits one tick per time unit and starts gossiping after the specified time, doubling the gossip on each step.
+struct Node {
+    explode_at: f64,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Tick {} => {
+                self.ctx.emit_self(Tick {}, 1.);
+                if self.ctx.time() == self.explode_at {
+                    self.ctx.emit_self(Gossip {}, 0.25);
+                }
+            }
+            Gossip {} => {
+                self.ctx.emit_self(Gossip {}, 0.25);
+                self.ctx.emit_self(Gossip {}, 0.25);
+            }
+        })
+    }
+}
+
+fn build(config: ProducerStatsConfig) -> Simulation {
+    let mut sim = Simulation::new(123);
+    sim.enable_producer_stats(config);
+    for (name, explode_at) in [("stable", f64::INFINITY), ("faulty", 5.)] {
+        let ctx = sim.create_context(name);
+        ctx.emit_self(Tick {}, 0.);
+        sim.add_handler(name, Rc::new(RefCell::new(Node { explode_at, ctx })));
+    }
+    sim
+}
+
+#[test]
+fn test_explosion_source() {
+    let mut sim = build(ProducerStatsConfig::new(1.));
+    sim.step_until_time(4.5);
+    let report = sim.top_producers(10., 10);
+    assert_eq!(report.from, 0.);
+    assert_eq!(report.to, 5.);
+    // initial ticks and ticks emitted at times 0..=4
+    assert_eq!(report.total, 12);
+    assert_eq!(
+        report.components,
+        vec![("faulty".to_string(), 6), ("stable".to_string(), 6)]
+    );
+    assert_eq!(report.event_types, vec![("Tick".to_string(), 12)]);
+
+    sim.step_until_time(6.5);
+    let report = sim.top_producers(1., 1);
+    assert_eq!(report.from, 5.);
+    assert_eq!(report.to, 7.);
+    assert_eq!(report.components.len(), 1);
+    assert_eq!(report.components[0].0, "faulty");
+    assert_eq!(
+        report.sources,
+        // ticks of both components are emitted at times 5 and 6
+        vec![("faulty".to_string(), "Gossip".to_string(), report.total - 4)]
+    );
+    assert!(report
+        .to_string()
+        .starts_with("Top event producers in [5.000, 7.000): "));
+}
+
+#[test]
+fn test_window_between() {
+    let mut sim = build(ProducerStatsConfig::new(2.));
+    sim.step_until_time(6.);
+    // the window is extended to [2, 6)
+    let report = sim.top_producers_between(3., 5., 10);
+    assert_eq!((report.from, report.to), (2., 6.));
+    assert_eq!(report.event_types.len(), 2);
+    let ticks = report.event_types.iter().find(|(name, _)| name == "Tick").unwrap();
+    assert_eq!(ticks.1, 8);
+}
+
+#[test]
+fn test_history() {
+    let mut config = ProducerStatsConfig::new(1.);
+    config.history = Some(2.);
+    let mut sim = build(config);
+    sim.step_until_time(4.5);
+    let report = sim.top_producers_between(0., 10., 10);
+    // only the buckets [2, 3), [3, 4) and [4, 5) are kept
+    assert_eq!(report.total, 6);
+}
+
+#[test]
+fn test_stats_reset() {
+    let mut sim = build(ProducerStatsConfig::new(1.));
+    sim.step_until_time(3.5);
+    sim.enable_producer_stats(ProducerStatsConfig::new(1.));
+    assert_eq!(sim.top_producers(100., 10).total, 0);
+    assert!(sim.top_producers(100., 10).sources.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Producer stats are not enabled")]
+fn test_not_enabled() {
+    let sim = Simulation::new(123);
+    sim.top_producers(1., 10);
+}
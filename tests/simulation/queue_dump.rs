@@ -0,0 +1,101 @@
+//! Tests of pending event queue dump.
+
+use serde::Serialize;
+
+use simcore::queue_dump::QueueDumpOptions;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize)]
+struct Request {
+    body: String,
+}
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+fn dump(sim: &Simulation, options: &QueueDumpOptions) -> Vec<String> {
+    let mut output = Vec::new();
+    sim.dump_queue(&mut output, options).unwrap();
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| line.to_owned())
+        .collect()
+}
+
+fn build() -> Simulation {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let monitor = sim.create_context("monitor");
+    for i in 0..5 {
+        client.emit(
+            Request {
+                body: "x".repeat(10 * i),
+            },
+            server.id(),
+            i as f64,
+        );
+    }
+    monitor.emit(Ping {}, client.id(), 2.5);
+    let cancelled = monitor.emit(Ping {}, server.id(), 3.5);
+    monitor.cancel_event(cancelled);
+    sim
+}
+
+#[test]
+fn test_dump_all() {
+    let sim = build();
+    let lines = dump(&sim, &QueueDumpOptions::new());
+    assert_eq!(lines[0], "Pending events: 6 total, 6 matching, 6 shown");
+    assert_eq!(lines.len(), 7);
+    assert!(lines[1].trim_start().starts_with("0.000 #0"));
+    assert!(lines[1].ends_with("client -> server Request {\"body\":\"\"}"));
+    assert!(lines[4].ends_with("monitor -> client Ping {}"));
+    assert!(lines[6].trim_start().starts_with("4.000 #4"));
+}
+
+#[test]
+fn test_filters() {
+    let sim = build();
+    let server_id = sim.lookup_id("server");
+    let monitor_id = sim.lookup_id("monitor");
+
+    let options = QueueDumpOptions {
+        src: Some(monitor_id),
+        ..Default::default()
+    };
+    let lines = dump(&sim, &options);
+    assert_eq!(lines[0], "Pending events: 6 total, 1 matching, 1 shown");
+    assert!(lines[1].contains("Ping"));
+
+    let options = QueueDumpOptions {
+        dst: Some(server_id),
+        event_type: Some("Request".to_owned()),
+        until: Some(2.),
+        ..Default::default()
+    };
+    let lines = dump(&sim, &options);
+    assert_eq!(lines[0], "Pending events: 6 total, 3 matching, 3 shown");
+
+    let options = QueueDumpOptions {
+        event_type: Some("Response".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(dump(&sim, &options).len(), 1);
+}
+
+#[test]
+fn test_limit_and_truncation() {
+    let sim = build();
+    let options = QueueDumpOptions {
+        limit: Some(2),
+        max_payload_len: 12,
+        ..Default::default()
+    };
+    let lines = dump(&sim, &options);
+    assert_eq!(lines[0], "Pending events: 6 total, 6 matching, 2 shown");
+    assert!(lines[2].ends_with("Request {\"body\":\"xxx..."));
+    assert_eq!(lines[3], "... 4 more");
+    assert_eq!(lines.len(), 4);
+}
@@ -0,0 +1,98 @@
+//! Tests of random choice and shuffling helpers.
+
+use simcore::Simulation;
+
+const SAMPLES: usize = 20000;
+
+#[test]
+fn test_choose_is_uniform() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let items = [0, 1, 2, 3];
+    let mut counts = [0usize; 4];
+    for _ in 0..SAMPLES {
+        counts[*ctx.choose(&items)] += 1;
+    }
+    for count in counts {
+        assert!((count as f64 / SAMPLES as f64 - 0.25).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_choose_weighted_frequencies() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let items = ["a", "b", "c", "d"];
+    let weights = [1., 0., 3., 6.];
+    let mut counts = [0usize; 4];
+    for _ in 0..SAMPLES {
+        let item = ctx.choose_weighted(&items, &weights);
+        counts[items.iter().position(|x| x == item).unwrap()] += 1;
+    }
+    assert_eq!(counts[1], 0);
+    for (count, weight) in counts.iter().zip(weights) {
+        assert!((*count as f64 / SAMPLES as f64 - weight / 10.).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_shuffle_permutes_items() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let original: Vec<u32> = (0..50).collect();
+    let mut shuffled = original.clone();
+    ctx.shuffle(&mut shuffled);
+    assert_ne!(shuffled, original);
+    shuffled.sort();
+    assert_eq!(shuffled, original);
+
+    let mut empty: Vec<u32> = Vec::new();
+    ctx.shuffle(&mut empty);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_choices_are_reproducible() {
+    let run = |seed| {
+        let mut sim = Simulation::new(seed);
+        let ctx = sim.create_context("comp");
+        let items: Vec<u32> = (0..20).collect();
+        let mut shuffled = items.clone();
+        ctx.shuffle(&mut shuffled);
+        let chosen: Vec<u32> = (0..20)
+            .map(|i| *ctx.choose(&items) + *ctx.choose_weighted(&items, &[i as f64 + 1.; 20]))
+            .collect();
+        (shuffled, chosen)
+    };
+    assert_eq!(run(123), run(123));
+    assert_ne!(run(123), run(321));
+}
+
+#[test]
+#[should_panic(expected = "Cannot choose from empty slice")]
+fn test_choose_from_empty_slice() {
+    let mut sim = Simulation::new(123);
+    let items: [u32; 0] = [];
+    sim.create_context("comp").choose(&items);
+}
+
+#[test]
+#[should_panic(expected = "Numbers of items and weights must be equal, got 2 items and 3 weights")]
+fn test_choose_weighted_with_mismatched_weights() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").choose_weighted(&[1, 2], &[1., 1., 1.]);
+}
+
+#[test]
+#[should_panic(expected = "Invalid weights")]
+fn test_choose_weighted_with_zero_weights() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").choose_weighted(&[1, 2], &[0., 0.]);
+}
+
+#[test]
+#[should_panic(expected = "Invalid weights")]
+fn test_choose_weighted_with_negative_weight() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").choose_weighted(&[1, 2], &[1., -1.]);
+}
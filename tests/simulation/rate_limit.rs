@@ -0,0 +1,138 @@
+//! Tests of rate limiting of trace file output.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use simcore::rate_limit::{OutputKind, OutputRateLimit};
+use simcore::trace_file::{read_trace_file, TraceEventKind, TraceFileConfig, TraceFileRecord};
+use simcore::{EventCancellationPolicy, Simulation};
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+fn trace_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-rate-limit-{}-{}.jsonl", name, std::process::id()))
+}
+
+fn read_and_remove(path: &PathBuf) -> Vec<TraceFileRecord> {
+    let trace = read_trace_file(path);
+    std::fs::remove_file(path).unwrap();
+    trace.records
+}
+
+#[test]
+fn test_trace_file_windows() {
+    let path = trace_path("windows");
+    let mut sim = Simulation::new(123);
+    let mut config = TraceFileConfig::new(&path);
+    config.kinds = vec![TraceEventKind::Processed];
+    sim.enable_trace_file(config);
+    sim.set_output_rate_limit(Some(OutputRateLimit::new(2., 2)));
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    for time in [0.5, 1., 1.5, 1.9, 2., 2.1, 3.9, 4., 10.] {
+        client.emit(Ping {}, server.id(), time);
+    }
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+
+    let times: Vec<f64> = read_and_remove(&path).iter().map(|record| record.time).collect();
+    assert_eq!(times, vec![0.5, 1., 2., 2.1, 4., 10.]);
+    assert_eq!(sim.suppressed_output(OutputKind::TraceFile), 3);
+}
+
+#[test]
+fn test_trace_file_attribution() {
+    let path = trace_path("attribution");
+    let mut sim = Simulation::new(123);
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    sim.set_output_rate_limit(Some(OutputRateLimit::new(1., 2)));
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let ids: Vec<_> = (0..4).map(|_| client.emit(Ping {}, server.id(), 5.)).collect();
+    client.cancel_event(ids[0]);
+    client.cancel_event(ids[3]);
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+
+    let records = read_and_remove(&path);
+    let kinds: Vec<_> = records.iter().map(|record| (record.kind, record.id)).collect();
+    // the client writes 2 emissions in window [0, 1), so both cancellations attributed to it are dropped
+    assert_eq!(
+        kinds,
+        vec![
+            (TraceEventKind::Emitted, ids[0]),
+            (TraceEventKind::Emitted, ids[1]),
+            (TraceEventKind::Processed, ids[1]),
+            (TraceEventKind::Processed, ids[2]),
+        ]
+    );
+    assert_eq!(sim.suppressed_output(OutputKind::TraceFile), 4);
+}
+
+#[test]
+fn test_component_limits() {
+    let path = trace_path("components");
+    let mut sim = Simulation::new(123);
+    let mut config = TraceFileConfig::new(&path);
+    config.kinds = vec![TraceEventKind::Processed];
+    sim.enable_trace_file(config);
+    let client = sim.create_context("client");
+    let server = sim.create_context("server");
+    let monitor = sim.create_context("monitor");
+    sim.set_component_output_rate_limit("server", Some(OutputRateLimit::new(1., 1)));
+    for _ in 0..3 {
+        client.emit(Ping {}, server.id(), 1.);
+        client.emit(Ping {}, monitor.id(), 1.);
+    }
+    sim.step_until_no_events();
+
+    // removing the limit of all components does not affect the overridden limits
+    sim.set_output_rate_limit(None);
+    for _ in 0..3 {
+        client.emit(Ping {}, server.id(), 1.);
+        client.emit(Ping {}, monitor.id(), 1.);
+    }
+    sim.step_until_no_events();
+
+    // removed component loses its limit
+    sim.remove_component("server", EventCancellationPolicy::None);
+    let server = sim.create_context("server");
+    for _ in 0..3 {
+        client.emit(Ping {}, server.id(), 1.);
+    }
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+
+    let records = read_and_remove(&path);
+    assert_eq!(records.iter().filter(|record| record.dst == "monitor").count(), 6);
+    assert_eq!(records.iter().filter(|record| record.dst == "server").count(), 5);
+}
+
+#[test]
+fn test_determinism() {
+    let run = |name: &str| {
+        let path = trace_path(name);
+        let mut sim = Simulation::new(123);
+        sim.enable_trace_file(TraceFileConfig::new(&path));
+        sim.set_output_rate_limit(Some(OutputRateLimit::new(0.1, 5)));
+        let ctx = sim.create_context("comp");
+        for _ in 0..1000 {
+            ctx.emit_self(Ping {}, ctx.gen_range(0.0..10.0));
+        }
+        sim.step_until_no_events();
+        sim.disable_trace_file();
+        let ids: Vec<_> = read_and_remove(&path).iter().map(|record| record.id).collect();
+        (ids, sim.suppressed_output(OutputKind::TraceFile))
+    };
+    let first = run("determinism-1");
+    assert!(first.1 > 0);
+    assert_eq!(run("determinism-2"), first);
+}
+
+#[test]
+#[should_panic(expected = "Rate limit window must be positive, got 0")]
+fn test_invalid_window() {
+    OutputRateLimit::new(0., 1);
+}
@@ -0,0 +1,98 @@
+//! Tests of real-time pacing mode.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use simcore::breakpoint::Breakpoint;
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Tick {}
+
+// Records the wall-clock time of each tick and emits the next one until the limit is reached.
+struct Ticker {
+    limit: usize,
+    ticks: Vec<Instant>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Ticker {
+    fn on(&mut self, _event: Event) {
+        self.ticks.push(Instant::now());
+        if self.ticks.len() < self.limit {
+            self.ctx.emit_self(Tick {}, 0.01);
+        }
+    }
+}
+
+fn build(limit: usize) -> (Simulation, Rc<RefCell<Ticker>>) {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("ticker");
+    ctx.emit_self(Tick {}, 0.01);
+    let ticker = Rc::new(RefCell::new(Ticker {
+        limit,
+        ticks: Vec::new(),
+        ctx,
+    }));
+    sim.add_handler("ticker", ticker.clone());
+    (sim, ticker)
+}
+
+#[test]
+fn test_pacing() {
+    let (mut sim, ticker) = build(5);
+    let start = Instant::now();
+    sim.run_real_time(1.);
+    assert!((sim.time() - 0.05).abs() < 1e-9);
+    let ticks = &ticker.borrow().ticks;
+    assert_eq!(ticks.len(), 5);
+    for (i, tick) in ticks.iter().enumerate() {
+        assert!(tick.duration_since(start) >= Duration::from_millis(10 * (i as u64 + 1)));
+    }
+}
+
+#[test]
+fn test_speedup() {
+    let (mut sim, ticker) = build(10);
+    let start = Instant::now();
+    sim.run_real_time(5.);
+    let elapsed = start.elapsed();
+    assert_eq!(ticker.borrow().ticks.len(), 10);
+    // 0.1 seconds of simulation time at 5x speed
+    assert!(elapsed >= Duration::from_millis(20));
+}
+
+#[test]
+fn test_pacing_from_current_time() {
+    let (mut sim, ticker) = build(6);
+    sim.step_until_time(0.035);
+    assert_eq!(ticker.borrow().ticks.len(), 3);
+    // the pacing starts from the current simulation time, the remaining events are at 0.04, 0.05 and 0.06
+    let start = Instant::now();
+    sim.run_real_time(1.);
+    let elapsed = start.elapsed();
+    assert_eq!(ticker.borrow().ticks.len(), 6);
+    assert!(elapsed >= Duration::from_millis(25));
+}
+
+#[test]
+fn test_pause() {
+    let (mut sim, ticker) = build(5);
+    sim.add_breakpoint(Breakpoint::any().when(|event| event.time > 0.025));
+    sim.run_real_time(1.);
+    assert_eq!(ticker.borrow().ticks.len(), 2);
+    assert!(sim.take_breakpoint_hit().is_some());
+    sim.clear_breakpoints();
+    sim.run_real_time(1.);
+    assert_eq!(ticker.borrow().ticks.len(), 5);
+}
+
+#[test]
+#[should_panic(expected = "Real-time speedup factor must be positive, got 0")]
+fn test_invalid_speedup() {
+    let (mut sim, _ticker) = build(1);
+    sim.run_real_time(0.);
+}
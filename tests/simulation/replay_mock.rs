@@ -0,0 +1,199 @@
+//! Tests of replay-driven mock components.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::mock::{ReplayMock, ReplayMockConfig};
+use simcore::trace_file::TraceFileConfig;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Work {
+    id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Response {
+    id: u64,
+}
+
+// Processes each request in a self-scheduled work event and sends the response to the client.
+struct Server {
+    client: Id,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.ctx.emit_self(Work { id }, id as f64);
+            }
+            Work { id } => {
+                self.ctx.emit(Response { id: id * 10 }, self.client, 0.5);
+            }
+        })
+    }
+}
+
+struct Client {
+    responses: Vec<(f64, u64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Client {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Response { id } => {
+                self.responses.push((self.ctx.time(), id));
+            }
+        })
+    }
+}
+
+fn trace_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-mock-{}-{}.jsonl", name, std::process::id()))
+}
+
+fn add_client(sim: &mut Simulation) -> Rc<RefCell<Client>> {
+    let client = Rc::new(RefCell::new(Client {
+        responses: Vec::new(),
+        ctx: sim.create_context("client"),
+    }));
+    sim.add_handler("client", client.clone());
+    client
+}
+
+fn send(client: &Rc<RefCell<Client>>, id: u64, server: Id, delay: f64) {
+    client.borrow().ctx.emit(Request { id }, server, delay);
+}
+
+// Records the trace of the real server processing requests with ids 1 and 2 and returns the client responses.
+fn record(path: &Path) -> Vec<(f64, u64)> {
+    let mut sim = Simulation::new(123);
+    sim.enable_trace_file(TraceFileConfig::new(path));
+    let client = add_client(&mut sim);
+    let server_ctx = sim.create_context("server");
+    let server = Rc::new(RefCell::new(Server {
+        client: client.borrow().ctx.id(),
+        ctx: server_ctx,
+    }));
+    let server_id = sim.add_handler("server", server);
+    send(&client, 1, server_id, 1.);
+    send(&client, 2, server_id, 2.);
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    let responses = client.borrow().responses.clone();
+    responses
+}
+
+fn build_mock(config: ReplayMockConfig) -> (Simulation, Rc<RefCell<ReplayMock>>, Rc<RefCell<Client>>, Id) {
+    let mut sim = Simulation::new(123);
+    let client = add_client(&mut sim);
+    let mut mock = ReplayMock::new(config, sim.create_context("server"));
+    mock.register_event::<Work>().register_event::<Response>();
+    let mock = Rc::new(RefCell::new(mock));
+    let mock_id = sim.add_handler("server", mock.clone());
+    (sim, mock, client, mock_id)
+}
+
+#[test]
+fn test_replay() {
+    let path = trace_path("replay");
+    let recorded = record(&path);
+    assert_eq!(recorded, vec![(2.5, 10), (4.5, 20)]);
+
+    let (mut sim, mock, client, mock_id) = build_mock(ReplayMockConfig::new(&path, "server"));
+    assert_eq!(mock.borrow().remaining_steps(), 4);
+    send(&client, 1, mock_id, 1.);
+    send(&client, 2, mock_id, 2.);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(client.borrow().responses, recorded);
+    assert_eq!(mock.borrow().remaining_steps(), 0);
+}
+
+#[test]
+fn test_reordered_inputs() {
+    let path = trace_path("reordered");
+    record(&path);
+    let (mut sim, mock, client, mock_id) = build_mock(ReplayMockConfig::new(&path, "server"));
+    send(&client, 2, mock_id, 0.);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    // the step matching the payload is replayed with the recorded delays
+    assert_eq!(client.borrow().responses, vec![(2.5, 20)]);
+    assert_eq!(mock.borrow().remaining_steps(), 2);
+}
+
+#[test]
+fn test_without_payload_matching() {
+    let path = trace_path("payloads");
+    record(&path);
+    let mut config = ReplayMockConfig::new(&path, "server");
+    config.match_payloads = false;
+    let (mut sim, _mock, client, mock_id) = build_mock(config);
+    send(&client, 7, mock_id, 0.);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    // the first recorded step is replayed
+    assert_eq!(client.borrow().responses, vec![(1.5, 10)]);
+}
+
+#[test]
+fn test_source_matching() {
+    let path = trace_path("sources");
+    record(&path);
+    let mut config = ReplayMockConfig::new(&path, "server");
+    config.ignore_unmatched = true;
+    let (mut sim, mock, client, mock_id) = build_mock(config);
+    let other = sim.create_context("other");
+    other.emit(Request { id: 1 }, mock_id, 0.);
+    sim.step_until_no_events();
+    assert!(client.borrow().responses.is_empty());
+    assert_eq!(mock.borrow().remaining_steps(), 4);
+
+    let mut config = ReplayMockConfig::new(&path, "server");
+    config.match_sources = false;
+    let (mut sim, _mock, client, _mock_id) = build_mock(config);
+    let other = sim.create_context("other");
+    other.emit(Request { id: 1 }, mock_id, 0.);
+    sim.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(client.borrow().responses, vec![(1.5, 10)]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Replay mock server received event Request from client which does not match any recorded step"
+)]
+fn test_unmatched_event() {
+    let path = trace_path("unmatched");
+    record(&path);
+    let (mut sim, _mock, client, mock_id) = build_mock(ReplayMockConfig::new(&path, "server"));
+    std::fs::remove_file(&path).unwrap();
+    send(&client, 3, mock_id, 0.);
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "Event type Work from trace is not registered, see ReplayMock::register_event")]
+fn test_unregistered_event() {
+    let path = trace_path("unregistered");
+    record(&path);
+    let mut sim = Simulation::new(123);
+    let mock = ReplayMock::new(ReplayMockConfig::new(&path, "server"), sim.create_context("server"));
+    let mock_id = sim.add_handler("server", Rc::new(RefCell::new(mock)));
+    let client = sim.create_context("client");
+    client.emit(Request { id: 1 }, mock_id, 0.);
+    std::fs::remove_file(&path).unwrap();
+    sim.step_until_no_events();
+}
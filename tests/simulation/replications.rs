@@ -0,0 +1,138 @@
+//! Tests of replication runner.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::analysis::{compare_runs, RunMetrics};
+use simcore::experiment::Replications;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Job {}
+
+struct Worker {
+    ctx: SimulationContext,
+    jobs: u64,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job {} => {
+                self.jobs += 1;
+                if self.ctx.time() < 100. {
+                    let delay = self.ctx.sample_exponential(self.ctx.gen_range(0.5..2.));
+                    self.ctx.emit_self(Job {}, delay);
+                }
+            }
+        })
+    }
+}
+
+fn run_model(seed: u64) -> RunMetrics {
+    let mut sim = Simulation::new(seed);
+    let ctx = sim.create_context("worker");
+    ctx.emit_self_now(Job {});
+    let worker = Rc::new(RefCell::new(Worker { ctx, jobs: 0 }));
+    sim.add_handler("worker", worker.clone());
+    sim.step_until_no_events();
+    let mut metrics = RunMetrics::from([
+        ("jobs".to_owned(), worker.borrow().jobs as f64),
+        ("time".to_owned(), sim.time()),
+    ]);
+    if seed.is_multiple_of(2) {
+        metrics.insert("even".to_owned(), 1.);
+    }
+    metrics
+}
+
+#[test]
+fn test_replications() {
+    let report = Replications::new(5).with_base_seed(10).run(run_model);
+    let seeds: Vec<u64> = report.runs.iter().map(|run| run.seed).collect();
+    assert_eq!(seeds, vec![10, 11, 12, 13, 14]);
+    for run in &report.runs {
+        assert_eq!(run.metrics, run_model(run.seed));
+    }
+
+    let jobs: Vec<f64> = report.runs.iter().map(|run| run.metrics["jobs"]).collect();
+    let stats = report.get("jobs").unwrap();
+    assert_eq!(stats.count, 5);
+    assert_eq!(stats.mean, jobs.iter().sum::<f64>() / 5.);
+    assert!(stats.variance() > 0.);
+    assert_eq!(report.get("even").unwrap().count, 3);
+    assert!(report.get("unknown").is_none());
+    assert_eq!(report.metrics.keys().collect::<Vec<_>>(), vec!["even", "jobs", "time"]);
+}
+
+#[test]
+fn test_default_seeds() {
+    let replications = Replications::new(3);
+    assert_eq!(replications.seeds().collect::<Vec<_>>(), vec![0, 1, 2]);
+    let report = replications.run(|seed| RunMetrics::from([("seed".to_owned(), seed as f64)]));
+    let stats = report.get("seed").unwrap();
+    assert_eq!(stats.mean, 1.);
+    assert_eq!(stats.variance(), 1.);
+}
+
+#[test]
+fn test_report_display() {
+    let report = Replications::new(3).run(run_model);
+    let output = report.to_string();
+    assert!(output.starts_with("metric"));
+    assert_eq!(output.lines().count(), 4);
+    let single = Replications::new(1).run(run_model).to_string();
+    assert!(single.lines().nth(1).unwrap().trim_end().ends_with('-'));
+}
+
+#[test]
+fn test_compare_replications() {
+    let baseline = Replications::new(10).run(run_model);
+    let candidate = Replications::new(10).with_base_seed(100).run(|seed| {
+        let mut metrics = run_model(seed);
+        *metrics.get_mut("jobs").unwrap() *= 2.;
+        metrics
+    });
+    let report = compare_runs(&baseline.run_metrics(), &candidate.run_metrics());
+    assert!(report.get("jobs").unwrap().is_significant(0.01));
+}
+
+#[test]
+#[should_panic(expected = "Number of replications must be positive")]
+fn test_zero_replications() {
+    Replications::new(0);
+}
+
+#[test]
+#[should_panic(expected = "Replication failed")]
+fn test_failed_replication() {
+    Replications::new(3).run(|seed| {
+        assert!(seed != 1, "Replication failed");
+        RunMetrics::new()
+    });
+}
+
+#[cfg(feature = "thread")]
+#[test]
+fn test_parallel_replications() {
+    let sequential = Replications::new(20).with_base_seed(7).run(run_model);
+    for threads in [2, 4, 32] {
+        let parallel = Replications::new(20)
+            .with_base_seed(7)
+            .with_threads(threads)
+            .run(run_model);
+        assert_eq!(parallel, sequential);
+    }
+}
+
+#[cfg(feature = "thread")]
+#[test]
+#[should_panic(expected = "Replication failed")]
+fn test_failed_parallel_replication() {
+    Replications::new(8).with_threads(4).run(|seed| {
+        assert!(seed != 5, "Replication failed");
+        RunMetrics::new()
+    });
+}
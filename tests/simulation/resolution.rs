@@ -0,0 +1,258 @@
+//! Tests of multi-resolution components.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::resolution::{MultiResolution, Resolution};
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Timer {}
+
+struct Model {
+    ctx: SimulationContext,
+    // destination of forwarded requests
+    next: Option<Id>,
+    received: Vec<(u32, Id)>,
+    timers: u32,
+    translated: u32,
+}
+
+impl Model {
+    fn new(ctx: SimulationContext) -> Self {
+        Self {
+            ctx,
+            next: None,
+            received: Vec::new(),
+            timers: 0,
+            translated: 0,
+        }
+    }
+}
+
+impl EventHandler for Model {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.received.push((id, event.dst));
+                if let Some(next) = self.next {
+                    self.ctx.emit(Request { id }, next, 1.);
+                }
+            }
+            Timer {} => {
+                self.timers += 1;
+            }
+        })
+    }
+}
+
+fn add_model(sim: &mut Simulation, name: &str) -> Rc<RefCell<Model>> {
+    let model = Rc::new(RefCell::new(Model::new(sim.create_context(name))));
+    sim.add_handler(name, model.clone());
+    model
+}
+
+fn received_ids(model: &Rc<RefCell<Model>>) -> Vec<u32> {
+    model.borrow().received.iter().map(|(id, _)| *id).collect()
+}
+
+#[test]
+fn test_initial_routing() {
+    let mut sim = Simulation::new(123);
+    let detailed = add_model(&mut sim, "detailed");
+    let coarse = add_model(&mut sim, "coarse");
+    sim.add_multi_resolution("comp", MultiResolution::new("detailed", "coarse"));
+    assert_eq!(sim.resolution("comp"), Resolution::Detailed);
+
+    let client = sim.create_context("client");
+    client.emit(Request { id: 1 }, sim.lookup_id("detailed"), 1.);
+    client.emit(Request { id: 2 }, sim.lookup_id("coarse"), 2.);
+    sim.step_until_no_events();
+    assert_eq!(received_ids(&detailed), vec![1, 2]);
+    assert!(detailed
+        .borrow()
+        .received
+        .iter()
+        .all(|(_, dst)| *dst == sim.lookup_id("detailed")));
+    assert!(coarse.borrow().received.is_empty());
+}
+
+#[test]
+fn test_initial_coarse_resolution() {
+    let mut sim = Simulation::new(123);
+    let detailed = add_model(&mut sim, "detailed");
+    let coarse = add_model(&mut sim, "coarse");
+    sim.add_multi_resolution(
+        "comp",
+        MultiResolution::new("detailed", "coarse").with_initial_resolution(Resolution::Coarse),
+    );
+    let client = sim.create_context("client");
+    client.emit(Request { id: 1 }, sim.lookup_id("detailed"), 1.);
+    sim.step_until_no_events();
+    assert!(detailed.borrow().received.is_empty());
+    assert_eq!(coarse.borrow().received, vec![(1, sim.lookup_id("coarse"))]);
+}
+
+#[test]
+fn test_swap() {
+    let mut sim = Simulation::new(123);
+    let detailed = add_model(&mut sim, "detailed");
+    let coarse = add_model(&mut sim, "coarse");
+    sim.add_multi_resolution(
+        "comp",
+        MultiResolution::new("detailed", "coarse").with_translation(
+            detailed.clone(),
+            coarse.clone(),
+            |detailed, coarse| coarse.translated = detailed.received.len() as u32,
+            |coarse, detailed| detailed.translated = coarse.received.len() as u32,
+        ),
+    );
+    let detailed_id = sim.lookup_id("detailed");
+    let client = sim.create_context("client");
+    for id in 0..6 {
+        client.emit(Request { id }, detailed_id, id as f64);
+    }
+    // internal events of the detailed model
+    detailed.borrow().ctx.emit_self(Timer {}, 1.5);
+    detailed.borrow().ctx.emit_self(Timer {}, 3.5);
+
+    sim.step_until_time(2.5);
+    assert_eq!(received_ids(&detailed), vec![0, 1, 2]);
+    assert_eq!(detailed.borrow().timers, 1);
+
+    sim.set_resolution("comp", Resolution::Coarse);
+    assert_eq!(sim.resolution("comp"), Resolution::Coarse);
+    assert_eq!(coarse.borrow().translated, 3);
+    // the request emitted after the swap is delivered to the coarse model too
+    client.emit(Request { id: 10 }, detailed_id, 0.7);
+    sim.step_until_time(4.5);
+    assert_eq!(received_ids(&coarse), vec![3, 10, 4]);
+    assert_eq!(coarse.borrow().received[0].1, sim.lookup_id("coarse"));
+    // the pending timer of the detailed model is canceled
+    assert_eq!(detailed.borrow().timers, 1);
+    assert_eq!(coarse.borrow().timers, 0);
+
+    sim.set_resolution("comp", Resolution::Detailed);
+    assert_eq!(detailed.borrow().translated, 3);
+    // swapping to the active resolution does nothing
+    sim.set_resolution("comp", Resolution::Detailed);
+    sim.step_until_no_events();
+    assert_eq!(received_ids(&detailed), vec![0, 1, 2, 5]);
+    assert_eq!(received_ids(&coarse), vec![3, 10, 4]);
+    assert_eq!(detailed.borrow().timers, 1);
+}
+
+#[test]
+fn test_group() {
+    let mut sim = Simulation::new(123);
+    let frontend = add_model(&mut sim, "frontend");
+    let backend = add_model(&mut sim, "backend");
+    let frontend_coarse = add_model(&mut sim, "frontend-coarse");
+    let backend_coarse = add_model(&mut sim, "backend-coarse");
+    frontend.borrow_mut().next = Some(sim.lookup_id("backend"));
+    frontend_coarse.borrow_mut().next = Some(sim.lookup_id("backend"));
+    sim.add_multi_resolution(
+        "service",
+        MultiResolution::new("frontend", "frontend-coarse").with_models("backend", "backend-coarse"),
+    );
+    let client = sim.create_context("client");
+    let frontend_id = sim.lookup_id("frontend");
+    client.emit(Request { id: 1 }, frontend_id, 1.);
+    client.emit(Request { id: 2 }, frontend_id, 5.);
+
+    // the request forwarded between the detailed models is canceled on swap
+    sim.step_until_time(1.5);
+    assert_eq!(received_ids(&frontend), vec![1]);
+    sim.set_resolution("service", Resolution::Coarse);
+    sim.step_until_no_events();
+    assert!(backend.borrow().received.is_empty());
+    assert_eq!(received_ids(&frontend_coarse), vec![2]);
+    // the request forwarded to the detailed backend is delivered to the coarse backend
+    assert_eq!(received_ids(&backend_coarse), vec![2]);
+    assert_eq!(sim.time(), 6.);
+}
+
+#[test]
+fn test_outgoing_events_kept() {
+    // the events emitted by the deactivated model to other components are not canceled
+    let mut sim = Simulation::new(123);
+    let detailed = add_model(&mut sim, "detailed");
+    add_model(&mut sim, "coarse");
+    let sink = add_model(&mut sim, "sink");
+    detailed.borrow_mut().next = Some(sim.lookup_id("sink"));
+    sim.add_multi_resolution("comp", MultiResolution::new("detailed", "coarse"));
+    let client = sim.create_context("client");
+    client.emit(Request { id: 7 }, sim.lookup_id("detailed"), 1.);
+    sim.step();
+    sim.set_resolution("comp", Resolution::Coarse);
+    sim.step_until_no_events();
+    assert_eq!(received_ids(&sink), vec![7]);
+}
+
+#[test]
+fn test_removed_model() {
+    let mut sim = Simulation::new(123);
+    add_model(&mut sim, "detailed");
+    add_model(&mut sim, "coarse");
+    sim.add_multi_resolution("comp", MultiResolution::new("detailed", "coarse"));
+    let coarse_id = sim.lookup_id("coarse");
+    sim.remove_component("coarse", simcore::EventCancellationPolicy::None);
+    // the identifier of removed model is reused without redirection
+    let other = add_model(&mut sim, "other");
+    assert_eq!(sim.lookup_id("other"), coarse_id);
+    sim.create_context("client").emit(Request { id: 1 }, coarse_id, 1.);
+    sim.step_until_no_events();
+    assert_eq!(received_ids(&other), vec![1]);
+}
+
+#[test]
+#[should_panic(expected = "Multi-resolution component comp does not exist")]
+fn test_unknown_component() {
+    let mut sim = Simulation::new(123);
+    sim.set_resolution("comp", Resolution::Coarse);
+}
+
+#[test]
+#[should_panic(expected = "Multi-resolution component comp already exists")]
+fn test_duplicate_component() {
+    let mut sim = Simulation::new(123);
+    for name in ["a", "b", "c", "d"] {
+        add_model(&mut sim, name);
+    }
+    sim.add_multi_resolution("comp", MultiResolution::new("a", "b"));
+    sim.add_multi_resolution("comp", MultiResolution::new("c", "d"));
+}
+
+#[test]
+#[should_panic(expected = "Model b already belongs to multi-resolution component")]
+fn test_shared_model() {
+    let mut sim = Simulation::new(123);
+    for name in ["a", "b", "c"] {
+        add_model(&mut sim, name);
+    }
+    sim.add_multi_resolution("comp1", MultiResolution::new("a", "b"));
+    sim.add_multi_resolution("comp2", MultiResolution::new("c", "b"));
+}
+
+#[test]
+#[should_panic(expected = "Detailed and coarse models must be different components, got a")]
+fn test_same_models() {
+    MultiResolution::new("a", "a");
+}
+
+#[test]
+#[should_panic(expected = "Simulation with multi-resolution components cannot be branched")]
+fn test_branch() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("a");
+    sim.create_context("b");
+    sim.add_multi_resolution("comp", MultiResolution::new("a", "b"));
+    sim.branch();
+}
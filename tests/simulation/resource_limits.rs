@@ -0,0 +1,143 @@
+//! Tests of per-run resource limits.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::limits::{Limit, LimitAction, ResourceLimits};
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+// Emits the specified number of events on each event, the loop grows when fanout is greater than one.
+struct Looper {
+    fanout: usize,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Looper {
+    fn on(&mut self, _event: Event) {
+        for _ in 0..self.fanout {
+            self.ctx.emit_self(Ping {}, 1.);
+        }
+    }
+}
+
+fn build(fanout: usize) -> Simulation {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("loop");
+    ctx.emit_self(Ping {}, 0.);
+    sim.add_handler("loop", Rc::new(RefCell::new(Looper { fanout, ctx })));
+    let client = sim.create_context("client");
+    for i in 0..3 {
+        client.emit_self(Ping {}, i as f64);
+    }
+    sim
+}
+
+fn limits(action: LimitAction) -> ResourceLimits {
+    let mut limits = ResourceLimits::new();
+    limits.action = action;
+    limits
+}
+
+#[test]
+#[should_panic(expected = "Resource limit exceeded: pending events limit 50 at time")]
+fn test_abort_on_pending_events() {
+    let mut sim = build(2);
+    let mut limits = limits(LimitAction::Abort);
+    limits.max_pending_events = Some(50);
+    sim.set_resource_limits(limits);
+    sim.step_until_no_events();
+}
+
+#[test]
+#[should_panic(expected = "top event producers: loop (")]
+fn test_abort_reports_producers() {
+    let mut sim = build(2);
+    let mut limits = limits(LimitAction::Abort);
+    limits.max_pending_events = Some(50);
+    sim.set_resource_limits(limits);
+    sim.step_until_no_events();
+}
+
+#[test]
+fn test_pause_on_pending_events() {
+    let mut sim = build(2);
+    let mut limits = limits(LimitAction::Pause);
+    limits.max_pending_events = Some(50);
+    sim.set_resource_limits(limits);
+    sim.step_until_no_events();
+
+    let violation = sim.take_limit_violation().unwrap();
+    assert_eq!(violation.limit, Limit::PendingEvents(50));
+    assert_eq!(violation.pending_events, 51);
+    assert_eq!(violation.time, sim.time());
+    assert_eq!(violation.top_producers.len(), 2);
+    assert_eq!(violation.top_producers[0].0, "loop");
+    assert_eq!(violation.top_producers[1], ("client".to_string(), 3));
+    assert!(sim.take_limit_violation().is_none());
+
+    // the limits are removed after pausing, so the run can be resumed
+    assert!(sim.steps(100));
+    assert!(sim.take_limit_violation().is_none());
+}
+
+#[test]
+fn test_processed_events_limit() {
+    let mut sim = build(1);
+    let mut limits = limits(LimitAction::Pause);
+    limits.max_processed_events = Some(10);
+    sim.set_resource_limits(limits);
+    sim.step_until_time(100.);
+
+    let violation = sim.take_limit_violation().unwrap();
+    assert_eq!(violation.limit, Limit::ProcessedEvents(10));
+    assert_eq!(violation.processed_events, 11);
+    // 3 events of client and 8 events of loop at times 0..=7
+    assert_eq!(sim.time(), 7.);
+    assert_eq!(
+        violation.top_producers,
+        vec![("loop".to_string(), 9), ("client".to_string(), 3)]
+    );
+}
+
+#[test]
+fn test_time_limit() {
+    let mut sim = build(1);
+    let mut limits = limits(LimitAction::Pause);
+    limits.max_time = Some(5.5);
+    limits.top_producers = 1;
+    sim.set_resource_limits(limits);
+    assert!(sim.step_until_time(100.));
+
+    let violation = sim.take_limit_violation().unwrap();
+    assert_eq!(violation.limit, Limit::Time(5.5));
+    assert_eq!(violation.time, 6.);
+    assert_eq!(violation.top_producers.len(), 1);
+}
+
+#[test]
+fn test_limits_not_exceeded() {
+    let mut sim = build(1);
+    let mut limits = limits(LimitAction::Abort);
+    limits.max_pending_events = Some(10);
+    limits.max_processed_events = Some(100);
+    limits.max_time = Some(50.);
+    sim.set_resource_limits(limits);
+    sim.step_until_time(50.);
+    assert!(sim.take_limit_violation().is_none());
+}
+
+#[test]
+fn test_clear_limits() {
+    let mut sim = build(2);
+    let mut limits = limits(LimitAction::Abort);
+    limits.max_pending_events = Some(5);
+    sim.set_resource_limits(limits);
+    sim.clear_resource_limits();
+    sim.steps(100);
+    assert!(sim.take_limit_violation().is_none());
+}
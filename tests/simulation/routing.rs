@@ -0,0 +1,151 @@
+//! Tests of event routing.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::routing::Route;
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Control {}
+
+struct Inbox {
+    received: Vec<(u32, Id, f64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Inbox {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Message { seq } => {
+                self.received.push((seq, event.src, self.ctx.time()));
+            }
+            Control {} => {
+                self.received.push((u32::MAX, event.src, self.ctx.time()));
+            }
+        })
+    }
+}
+
+struct Firewall {
+    server_id: Id,
+    dropped: u32,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Firewall {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Message { seq } => {
+                if seq % 2 == 0 {
+                    self.ctx.emit(Message { seq }, self.server_id, 0.1);
+                } else {
+                    self.dropped += 1;
+                }
+            }
+        })
+    }
+}
+
+fn add_inbox(sim: &mut Simulation, name: &str) -> Rc<RefCell<Inbox>> {
+    let inbox = Rc::new(RefCell::new(Inbox {
+        received: Vec::new(),
+        ctx: sim.create_context(name),
+    }));
+    sim.add_handler(name, inbox.clone());
+    inbox
+}
+
+#[test]
+fn test_interposed_component() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = add_inbox(&mut sim, "server");
+    let server_id = sim.lookup_id("server");
+    let firewall = Rc::new(RefCell::new(Firewall {
+        server_id,
+        dropped: 0,
+        ctx: sim.create_context("firewall"),
+    }));
+    let firewall_id = sim.add_handler("firewall", firewall.clone());
+    sim.add_router(move |event| (event.dst == server_id && event.src != firewall_id).then(|| Route::to(firewall_id)));
+
+    for seq in 0..4 {
+        client.emit(Message { seq }, server_id, 1.);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(firewall.borrow().dropped, 2);
+    assert_eq!(
+        server.borrow().received,
+        vec![(0, firewall_id, 1.1), (2, firewall_id, 1.1)]
+    );
+}
+
+#[test]
+fn test_delay_by_event_type() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = add_inbox(&mut sim, "server");
+    let server_id = sim.lookup_id("server");
+    sim.add_router(|event| {
+        event
+            .as_typed::<Message>()
+            .map(|message| Route::to(event.dst).with_delay(message.data.seq as f64))
+    });
+
+    client.emit(Message { seq: 2 }, server_id, 0.);
+    client.emit(Control {}, server_id, 0.);
+    client.emit(Message { seq: 0 }, server_id, 0.);
+    client.emit(Message { seq: 1 }, server_id, 0.5);
+    sim.step_until_no_events();
+
+    let received: Vec<_> = server
+        .borrow()
+        .received
+        .iter()
+        .map(|(seq, _, time)| (*seq, *time))
+        .collect();
+    assert_eq!(received, vec![(u32::MAX, 0.), (0, 0.), (1, 1.5), (2, 2.)]);
+}
+
+#[test]
+fn test_ordered_events_keep_order() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    let server = add_inbox(&mut sim, "server");
+    let server_id = sim.lookup_id("server");
+    let backup = add_inbox(&mut sim, "backup");
+    let backup_id = sim.lookup_id("backup");
+    sim.add_router(move |event| {
+        let seq = event.as_typed::<Message>().unwrap().data.seq;
+        (seq == 0).then(|| Route::to(backup_id).with_delay(5.))
+    });
+
+    for seq in 0..3 {
+        client.emit_ordered(Message { seq }, server_id, 1.);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(backup.borrow().received, vec![(0, client.id(), 6.)]);
+    assert_eq!(
+        server.borrow().received,
+        vec![(1, client.id(), 6.), (2, client.id(), 6.)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Route delay is negative")]
+fn test_negative_route_delay() {
+    let mut sim = Simulation::new(123);
+    let client = sim.create_context("client");
+    sim.add_router(|event| Some(Route::to(event.dst).with_delay(-1.)));
+    client.emit_self(Control {}, 2.);
+}
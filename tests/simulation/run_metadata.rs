@@ -0,0 +1,85 @@
+//! Tests of run metadata.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::metadata::RunMetadata;
+use simcore::spill::SpillConfig;
+use simcore::Simulation;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Spillable {
+    value: u64,
+}
+
+#[derive(Serialize)]
+struct Config {
+    nodes: u32,
+    policy: String,
+}
+
+#[test]
+fn test_run_metadata() {
+    let mut sim = Simulation::new(123);
+    let metadata = sim.run_metadata();
+    assert_eq!(metadata.seed, 123);
+    assert_eq!(metadata.version, env!("CARGO_PKG_VERSION"));
+    assert!(metadata.labels.is_empty());
+    assert_eq!(metadata.config_hash, None);
+
+    sim.set_run_label("scenario", "a");
+    sim.set_run_label("scenario", "b");
+    sim.set_run_label("experiment", "test");
+    sim.set_run_config(&Config {
+        nodes: 4,
+        policy: "fifo".to_owned(),
+    });
+    let metadata = sim.run_metadata();
+    assert_eq!(
+        metadata.labels,
+        BTreeMap::from([
+            ("experiment".to_owned(), "test".to_owned()),
+            ("scenario".to_owned(), "b".to_owned())
+        ])
+    );
+    let hash = metadata.config_hash.clone().unwrap();
+    assert_eq!(hash.len(), 16);
+
+    sim.set_run_config(&Config {
+        nodes: 8,
+        policy: "fifo".to_owned(),
+    });
+    // the hash does not depend on platform, so it can be compared with hashes of other runs
+    assert_eq!(sim.run_metadata().config_hash.unwrap(), "aa265d7a0e8b928f");
+    assert_ne!(hash, "aa265d7a0e8b928f");
+
+    let parsed: RunMetadata = serde_json::from_str(&metadata.to_json()).unwrap();
+    assert_eq!(parsed, metadata);
+}
+
+#[test]
+fn test_spill_files_contain_run_metadata() {
+    let dir = std::env::temp_dir().join("simcore-test-run-metadata");
+    let mut sim = Simulation::new(123);
+    sim.set_run_label("experiment", "spilling");
+    let ctx = sim.create_context("comp");
+    sim.register_spillable_event::<Spillable>();
+    sim.enable_event_spilling(SpillConfig::new(&dir, 10));
+    for i in 0..100 {
+        ctx.emit_self(Spillable { value: i }, i as f64);
+    }
+
+    let mut file_count = 0;
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        let header: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        let metadata: RunMetadata = serde_json::from_value(header["metadata"].clone()).unwrap();
+        assert_eq!(metadata, sim.run_metadata());
+        file_count += 1;
+    }
+    assert!(file_count > 0);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 99.);
+}
@@ -0,0 +1,190 @@
+//! Tests of simulation run results.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use simcore::breakpoint::Breakpoint;
+use simcore::limits::{Limit, LimitAction, ResourceLimits};
+use simcore::run::TerminationReason;
+use simcore::snapshot::ComponentState;
+use simcore::stop::TimeReached;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Job {
+    fail: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct Tick {}
+
+struct Worker {
+    ctx: SimulationContext,
+    completed: u64,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job { fail } => {
+                assert!(!fail, "Job failed at {}", self.ctx.time());
+                self.completed += 1;
+            }
+            Tick {} => {
+                self.ctx.emit_self(Tick {}, 1.);
+            }
+        })
+    }
+}
+
+impl ComponentState for Worker {
+    fn state(&self) -> Value {
+        json!({ "completed": self.completed })
+    }
+}
+
+fn build() -> (Simulation, Rc<RefCell<Worker>>, SimulationContext) {
+    let mut sim = Simulation::new(123);
+    let worker = Rc::new(RefCell::new(Worker {
+        ctx: sim.create_context("worker"),
+        completed: 0,
+    }));
+    sim.add_handler("worker", worker.clone());
+    sim.register_state("worker", worker.clone());
+    let client = sim.create_context("client");
+    (sim, worker, client)
+}
+
+#[test]
+fn test_drained() {
+    let (mut sim, worker, client) = build();
+    for i in 1..=3 {
+        client.emit(Job { fail: false }, worker.borrow().ctx.id(), i as f64);
+    }
+    let result = sim.run();
+
+    assert_eq!(result.termination, TerminationReason::Drained);
+    assert!(!result.is_error());
+    assert_eq!(result.time, 3.);
+    assert_eq!(
+        (result.processed_events, result.emitted_events, result.pending_events),
+        (3, 0, 0)
+    );
+    assert_eq!(result.snapshot.get("worker"), Some(&json!({ "completed": 3 })));
+    assert!(result.failed_components.is_empty());
+    assert_eq!(sim.processed_event_count(), 3);
+}
+
+#[test]
+fn test_counters_are_per_run() {
+    let (mut sim, worker, _) = build();
+    worker.borrow().ctx.emit_self(Tick {}, 0.);
+    let result = sim.run_until_time(5.5);
+    assert_eq!(result.termination, TerminationReason::TimeReached);
+    assert_eq!(result.time, 5.5);
+    assert_eq!(
+        (result.processed_events, result.emitted_events, result.pending_events),
+        (6, 6, 1)
+    );
+
+    let result = sim.run_until(TimeReached(8.));
+    assert_eq!(result.termination, TerminationReason::StopCondition);
+    assert_eq!(result.time, 8.);
+    assert_eq!((result.processed_events, result.emitted_events), (3, 3));
+    assert_eq!(sim.processed_event_count(), 9);
+}
+
+#[test]
+fn test_time_reached_without_events() {
+    let (mut sim, _, _) = build();
+    let result = sim.run_until_time(10.);
+    assert_eq!(result.termination, TerminationReason::TimeReached);
+    assert_eq!(result.time, 10.);
+}
+
+#[test]
+fn test_handler_error() {
+    let (mut sim, worker, client) = build();
+    let worker_id = worker.borrow().ctx.id();
+    client.emit(Job { fail: false }, worker_id, 1.);
+    client.emit(Job { fail: true }, worker_id, 2.);
+    client.emit(Job { fail: false }, worker_id, 3.);
+    let result = sim.run();
+
+    assert!(result.is_error());
+    assert_eq!(
+        result.termination,
+        TerminationReason::Error("Job failed at 2".to_owned())
+    );
+    assert_eq!(result.failed_components, vec!["worker"]);
+    assert_eq!(result.time, 2.);
+    assert_eq!((result.processed_events, result.pending_events), (2, 1));
+    assert_eq!(result.snapshot.get("worker"), Some(&json!({ "completed": 1 })));
+}
+
+#[test]
+fn test_error_in_stop_condition_run() {
+    let (mut sim, worker, client) = build();
+    client.emit(Job { fail: true }, worker.borrow().ctx.id(), 1.);
+    let result = sim.run_until(TimeReached(5.));
+    assert_eq!(result.failed_components, vec!["worker"]);
+
+    // the stop condition of the failed run is discarded
+    client.emit(Job { fail: false }, worker.borrow().ctx.id(), 1.);
+    let result = sim.run();
+    assert_eq!(result.termination, TerminationReason::Drained);
+}
+
+#[test]
+fn test_budget() {
+    let (mut sim, worker, _) = build();
+    worker.borrow().ctx.emit_self(Tick {}, 0.);
+    sim.set_resource_limits(ResourceLimits {
+        max_processed_events: Some(4),
+        action: LimitAction::Pause,
+        ..ResourceLimits::new()
+    });
+    let result = sim.run();
+
+    let TerminationReason::Budget(violation) = result.termination else {
+        panic!("Unexpected termination reason {:?}", result.termination);
+    };
+    assert_eq!(violation.limit, Limit::ProcessedEvents(4));
+    assert_eq!(result.processed_events, 5);
+    // the reported violation is not taken from the simulation
+    assert_eq!(sim.take_limit_violation(), Some(violation));
+}
+
+#[test]
+fn test_budget_abort_is_error() {
+    let (mut sim, worker, _) = build();
+    worker.borrow().ctx.emit_self(Tick {}, 0.);
+    sim.set_resource_limits(ResourceLimits {
+        max_time: Some(3.5),
+        ..ResourceLimits::new()
+    });
+    let result = sim.run();
+
+    let TerminationReason::Error(message) = result.termination else {
+        panic!("Unexpected termination reason {:?}", result.termination);
+    };
+    assert!(message.starts_with("Resource limit exceeded"));
+    assert!(result.failed_components.is_empty());
+}
+
+#[test]
+fn test_paused() {
+    let (mut sim, worker, client) = build();
+    let worker_id = worker.borrow().ctx.id();
+    client.emit(Job { fail: false }, worker_id, 1.);
+    client.emit(Job { fail: true }, worker_id, 2.);
+    sim.add_breakpoint(Breakpoint::on_payload(|job: &Job| job.fail));
+    let result = sim.run();
+
+    assert_eq!(result.termination, TerminationReason::Paused);
+    assert_eq!((result.time, result.pending_events), (1., 1));
+    assert!(sim.take_breakpoint_hit().is_some());
+}
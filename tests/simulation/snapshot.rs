@@ -0,0 +1,144 @@
+//! Tests of component state snapshots.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use simcore::snapshot::ComponentState;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Transfer {
+    amount: i64,
+}
+
+struct Account {
+    balance: i64,
+    peer: Option<u32>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Account {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Transfer { amount } => {
+                self.balance += amount;
+                if let Some(peer) = self.peer {
+                    // pass half of the amount further
+                    self.balance -= amount / 2;
+                    self.ctx.emit(Transfer { amount: amount / 2 }, peer, 1.);
+                }
+            }
+        })
+    }
+}
+
+impl ComponentState for Account {
+    fn state(&self) -> Value {
+        json!({ "balance": self.balance })
+    }
+}
+
+fn build() -> (
+    Simulation,
+    SimulationContext,
+    Rc<RefCell<Account>>,
+    Rc<RefCell<Account>>,
+) {
+    let mut sim = Simulation::new(123);
+    let b = Rc::new(RefCell::new(Account {
+        balance: 0,
+        peer: None,
+        ctx: sim.create_context("b"),
+    }));
+    let b_id = sim.add_handler("b", b.clone());
+    let a = Rc::new(RefCell::new(Account {
+        balance: 0,
+        peer: Some(b_id),
+        ctx: sim.create_context("a"),
+    }));
+    sim.add_handler("a", a.clone());
+    let client = sim.create_context("client");
+    (sim, client, a, b)
+}
+
+#[test]
+fn test_snapshot_of_registered_components() {
+    let (mut sim, client, a, b) = build();
+    sim.register_state("a", a);
+    sim.register_state("b", b);
+    let a_id = sim.lookup_id("a");
+    client.emit(Transfer { amount: 100 }, a_id, 1.);
+
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.time, 0.);
+    assert_eq!(snapshot.event_count, 1);
+    assert_eq!(snapshot.get("a"), Some(&json!({"balance": 0})));
+
+    sim.step();
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.time, 1.);
+    assert_eq!(snapshot.get("a"), Some(&json!({"balance": 50})));
+    assert_eq!(snapshot.get("b"), Some(&json!({"balance": 0})));
+
+    sim.step();
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.time, 2.);
+    assert_eq!(snapshot.get("b"), Some(&json!({"balance": 50})));
+    // components are ordered by name
+    let names: Vec<&String> = snapshot.components.keys().collect();
+    assert_eq!(names, vec!["a", "b"]);
+    assert_eq!(
+        serde_json::to_value(&snapshot).unwrap(),
+        json!({
+            "time": 2.,
+            "event_count": 2,
+            "components": {"a": {"balance": 50}, "b": {"balance": 50}}
+        })
+    );
+}
+
+#[test]
+fn test_invariant_over_snapshots() {
+    let (mut sim, client, a, b) = build();
+    sim.register_state("a", a);
+    sim.register_state("b", b);
+    let a_id = sim.lookup_id("a");
+    client.emit(Transfer { amount: 100 }, a_id, 1.);
+    client.emit(Transfer { amount: 40 }, a_id, 1.5);
+
+    // the total balance grows as the transfers arrive, the amounts in transit are not included
+    let mut total_balances = Vec::new();
+    while sim.step() {
+        let snapshot = sim.snapshot();
+        let total: i64 = snapshot
+            .components
+            .values()
+            .map(|state| state["balance"].as_i64().unwrap())
+            .sum();
+        total_balances.push(total);
+    }
+    assert_eq!(total_balances, vec![50, 70, 120, 140]);
+}
+
+#[test]
+fn test_register_state_replaces_previous() {
+    let (mut sim, _client, a, b) = build();
+    a.borrow_mut().balance = 1;
+    b.borrow_mut().balance = 2;
+    sim.register_state("a", a);
+    sim.register_state("a", b);
+
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.components.len(), 1);
+    assert_eq!(snapshot.get("a"), Some(&json!({"balance": 2})));
+}
+
+#[test]
+#[should_panic]
+fn test_register_state_of_unknown_component() {
+    let (mut sim, _client, a, _b) = build();
+    sim.register_state("c", a);
+}
@@ -0,0 +1,164 @@
+//! Tests of component dependencies and startup ordering.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::startup::{Dependencies, StartableComponent};
+use simcore::{EventCancellationPolicy, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Ping {}
+
+struct Component {
+    started: Rc<RefCell<Vec<String>>>,
+    dependencies: Vec<Id>,
+    ctx: SimulationContext,
+}
+
+impl StartableComponent for Component {
+    fn on_start(&mut self, dependencies: &Dependencies) {
+        self.started.borrow_mut().push(self.ctx.name().to_owned());
+        self.dependencies = dependencies.ids();
+        self.ctx.emit_self(Ping {}, 1.);
+    }
+}
+
+fn add_component(
+    sim: &mut Simulation,
+    name: &str,
+    dependencies: &[&str],
+    started: &Rc<RefCell<Vec<String>>>,
+) -> Rc<RefCell<Component>> {
+    let component = Rc::new(RefCell::new(Component {
+        started: started.clone(),
+        dependencies: Vec::new(),
+        ctx: sim.create_context(name),
+    }));
+    sim.register_startable(name, component.clone(), dependencies);
+    component
+}
+
+#[test]
+fn test_topological_order() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    // app depends on cache and db, cache depends on db, logger is independent
+    let app = add_component(&mut sim, "app", &["cache", "db"], &started);
+    add_component(&mut sim, "logger", &[], &started);
+    add_component(&mut sim, "cache", &["db"], &started);
+    add_component(&mut sim, "db", &[], &started);
+    assert!(!sim.is_started());
+
+    sim.start();
+    assert!(sim.is_started());
+    assert_eq!(*started.borrow(), vec!["db", "cache", "app", "logger"]);
+    assert_eq!(
+        app.borrow().dependencies,
+        vec![sim.lookup_id("cache"), sim.lookup_id("db")]
+    );
+    assert_eq!(sim.dump_events().len(), 4);
+
+    // the hooks are called only once
+    sim.start();
+    sim.step_until_no_events();
+    assert_eq!(started.borrow().len(), 4);
+}
+
+#[test]
+fn test_dependencies_without_hooks() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    let client = add_component(&mut sim, "client", &["server"], &started);
+    let server_id = sim.create_context("server").id();
+
+    // the simulation is started before the first step, and the event emitted on start is pending
+    assert!(sim.step_until_time(0.5));
+    assert!(sim.is_started());
+    assert_eq!(client.borrow().dependencies, vec![server_id]);
+}
+
+#[test]
+fn test_register_after_start() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &[], &started);
+    sim.step_until_time(5.);
+    assert_eq!(*started.borrow(), vec!["a"]);
+
+    add_component(&mut sim, "b", &["a"], &started);
+    assert_eq!(*started.borrow(), vec!["a", "b"]);
+    assert_eq!(sim.dump_events()[0].time, 6.);
+}
+
+#[test]
+fn test_removed_component_is_not_started() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &[], &started);
+    add_component(&mut sim, "b", &[], &started);
+    sim.remove_component("a", EventCancellationPolicy::None);
+    sim.start();
+    assert_eq!(*started.borrow(), vec!["b"]);
+}
+
+#[test]
+fn test_branch_is_started() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &[], &started);
+    sim.start();
+    let mut branch = sim.branch();
+    assert!(branch.is_started());
+    branch.step_until_no_events();
+    assert_eq!(started.borrow().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Component dependencies form a cycle: b -> c -> d -> b")]
+fn test_cycle() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &["b"], &started);
+    add_component(&mut sim, "b", &["c"], &started);
+    add_component(&mut sim, "c", &["d"], &started);
+    add_component(&mut sim, "d", &["b"], &started);
+    sim.start();
+}
+
+#[test]
+#[should_panic(expected = "Component dependencies form a cycle: a -> a")]
+fn test_self_dependency() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &["a"], &started);
+    sim.start();
+}
+
+#[test]
+#[should_panic(expected = "Component a depends on unknown component b")]
+fn test_unknown_dependency() {
+    let mut sim = Simulation::new(123);
+    let started = Rc::new(RefCell::new(Vec::new()));
+    add_component(&mut sim, "a", &["b"], &started);
+    sim.step();
+}
+
+#[test]
+#[should_panic(expected = "Component c is not declared as dependency")]
+fn test_undeclared_dependency() {
+    struct Client {}
+
+    impl StartableComponent for Client {
+        fn on_start(&mut self, dependencies: &Dependencies) {
+            dependencies.id("c");
+        }
+    }
+
+    let mut sim = Simulation::new(123);
+    sim.create_context("a");
+    sim.create_context("b");
+    sim.register_startable("a", Rc::new(RefCell::new(Client {})), &["b"]);
+    sim.start();
+}
@@ -0,0 +1,115 @@
+//! Tests of timed state machine helper.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::state_machine::{StateMachine, Transition};
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum State {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+#[derive(Clone, Serialize)]
+struct Heartbeat {}
+
+#[derive(Clone, Serialize)]
+struct Votes {
+    count: u32,
+}
+
+struct Node {
+    ctx: SimulationContext,
+    fsm: StateMachine<State>,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        self.fsm.handle(&self.ctx, &event);
+    }
+}
+
+type Log = Rc<RefCell<Vec<(&'static str, State, State, f64)>>>;
+
+fn create_node(sim: &mut Simulation, log: &Log) -> Rc<RefCell<Node>> {
+    let ctx = sim.create_context("node");
+    let (enter_log, exit_log) = (log.clone(), log.clone());
+    let mut fsm = StateMachine::new("election", State::Follower)
+        // election timeout restarted by each heartbeat
+        .with_timed_transition(State::Follower, 5., State::Candidate)
+        .with_event_transition::<Heartbeat>(State::Follower, State::Follower)
+        .with_timed_transition(State::Candidate, 3., State::Candidate)
+        .with_guarded_event_transition::<Votes, _>(State::Candidate, State::Leader, |votes| votes.count >= 2)
+        .with_event_transition::<Heartbeat>(State::Candidate, State::Follower)
+        .with_enter_hook(State::Candidate, move |ctx, t: Transition<State>| {
+            enter_log.borrow_mut().push(("enter", t.from, t.to, ctx.time()))
+        })
+        .with_exit_hook(State::Candidate, move |ctx, t: Transition<State>| {
+            exit_log.borrow_mut().push(("exit", t.from, t.to, ctx.time()))
+        });
+    fsm.start(&ctx);
+    let node = Rc::new(RefCell::new(Node { ctx, fsm }));
+    sim.add_handler("node", node.clone());
+    node
+}
+
+#[test]
+fn test_timed_and_event_transitions() {
+    let mut sim = Simulation::new(123);
+    let log = Log::default();
+    let node = create_node(&mut sim, &log);
+    let node_id = node.borrow().ctx.id();
+    let peer = sim.create_context("peer");
+
+    peer.emit(Heartbeat {}, node_id, 3.);
+    // not enough votes
+    peer.emit(Votes { count: 1 }, node_id, 9.);
+    peer.emit(Votes { count: 2 }, node_id, 12.);
+    // ignored by leader
+    peer.emit(Heartbeat {}, node_id, 13.);
+    sim.step_until_no_events();
+
+    assert_eq!(node.borrow().fsm.state(), State::Leader);
+    assert_eq!(node.borrow().fsm.entered_at(), 12.);
+    assert!(!node.borrow().ctx.has_timer("election"));
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            ("enter", State::Follower, State::Candidate, 8.),
+            ("exit", State::Candidate, State::Candidate, 11.),
+            ("enter", State::Candidate, State::Candidate, 11.),
+            ("exit", State::Candidate, State::Leader, 12.),
+        ]
+    );
+}
+
+#[test]
+fn test_manual_transition_and_foreign_timers() {
+    let mut sim = Simulation::new(123);
+    let log = Log::default();
+    let node = create_node(&mut sim, &log);
+
+    {
+        let node = node.borrow();
+        // timers with other names do not affect the state machine
+        node.ctx.set_timer("other", 1.);
+    }
+    sim.step_until_time(2.);
+    assert_eq!(node.borrow().fsm.state(), State::Follower);
+
+    {
+        let mut node = node.borrow_mut();
+        let Node { ctx, fsm } = &mut *node;
+        let transition = fsm.transition(ctx, State::Leader);
+        assert_eq!(transition.from, State::Follower);
+    }
+    sim.step_until_no_events();
+    assert_eq!(node.borrow().fsm.state(), State::Leader);
+    assert!(log.borrow().is_empty());
+    assert_eq!(sim.time(), 2.);
+}
@@ -0,0 +1,215 @@
+//! Tests of stepping backwards via automatic checkpoints.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use simcore::breakpoint::Breakpoint;
+use simcore::observer::TimeAdvanceListener;
+use simcore::snapshot::ComponentState;
+use simcore::trace_file::{read_trace_file, TraceFileConfig};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Job {
+    size: u64,
+}
+
+struct Worker {
+    total: u64,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job { size } => {
+                self.total += size;
+                self.ctx.counter_inc("jobs");
+                let size = self.ctx.gen_range(1..100);
+                self.ctx.emit_self(Job { size }, self.ctx.gen_range(0.5..1.5));
+            }
+        })
+    }
+}
+
+impl ComponentState for Worker {
+    fn state(&self) -> Value {
+        json!(self.total)
+    }
+
+    fn restore_state(&mut self, state: Value) {
+        self.total = state.as_u64().unwrap();
+    }
+}
+
+fn build(interval: u64, max_checkpoints: usize) -> (Simulation, Rc<RefCell<Worker>>) {
+    let mut sim = Simulation::new(123);
+    sim.register_checkpoint_event::<Job>();
+    sim.enable_auto_checkpoints(interval, max_checkpoints);
+    let ctx = sim.create_context("worker");
+    ctx.emit_self(Job { size: 1 }, 1.);
+    let worker = Rc::new(RefCell::new(Worker { total: 0, ctx }));
+    sim.add_handler("worker", worker.clone());
+    sim.register_state("worker", worker.clone());
+    (sim, worker)
+}
+
+// Returns the time and worker state after each step.
+fn run(sim: &Simulation, worker: &Rc<RefCell<Worker>>, steps: usize) -> Vec<(f64, u64)> {
+    (0..steps)
+        .map(|_| {
+            sim.step();
+            (sim.time(), worker.borrow().total)
+        })
+        .collect()
+}
+
+#[test]
+fn test_step_back_reproduces_run() {
+    let (sim, worker) = build(4, 10);
+    let states = run(&sim, &worker, 30);
+    for events in [1, 3, 4, 5, 11, 29] {
+        assert!(sim.step_back(events));
+        let processed = 30 - events as usize;
+        assert_eq!(sim.processed_event_count(), processed as u64);
+        assert_eq!((sim.time(), worker.borrow().total), states[processed - 1]);
+        // the run continues in the same way
+        assert_eq!(run(&sim, &worker, events as usize), states[processed..]);
+    }
+}
+
+#[test]
+fn test_step_back_to_start() {
+    let (sim, worker) = build(5, 10);
+    run(&sim, &worker, 7);
+    assert!(sim.step_back(7));
+    assert_eq!(sim.processed_event_count(), 0);
+    assert_eq!((sim.time(), worker.borrow().total), (0., 0));
+    assert!(!sim.step_back(1));
+}
+
+#[test]
+fn test_old_checkpoints_are_discarded() {
+    let (sim, worker) = build(5, 2);
+    run(&sim, &worker, 23);
+    // the checkpoints after 15 and 20 events are kept
+    assert!(!sim.step_back(9));
+    assert_eq!(sim.processed_event_count(), 23);
+    assert!(sim.step_back(8));
+    assert_eq!(sim.processed_event_count(), 15);
+}
+
+#[test]
+fn test_breakpoints_are_not_checked_on_reexecution() {
+    let (mut sim, worker) = build(10, 10);
+    run(&sim, &worker, 15);
+    sim.add_breakpoint(Breakpoint::any());
+    assert!(sim.step_back(2));
+    assert_eq!(sim.processed_event_count(), 13);
+    assert!(sim.take_breakpoint_hit().is_none());
+}
+
+#[test]
+fn test_reexecution_across_warmup_end() {
+    // the only checkpoint is saved before the first event
+    let (mut sim, worker) = build(100, 10);
+    sim.set_warmup_time(5.);
+    let warmup_ends = Rc::new(Cell::new(0));
+    let counter = warmup_ends.clone();
+    sim.on_warmup_end(move |_| counter.set(counter.get() + 1));
+    let mut states = Vec::new();
+    for _ in 0..15 {
+        sim.step();
+        states.push((sim.time(), worker.borrow().total, sim.processed_event_count()));
+    }
+    let warmup_end_time = sim.warmup_end_time();
+    assert!(warmup_end_time.is_some());
+    assert_eq!(warmup_ends.get(), 1);
+
+    assert!(sim.step_back(3));
+    // the warmup period is ended again without calling the callbacks
+    assert_eq!(warmup_ends.get(), 1);
+    assert_eq!(sim.warmup_end_time(), warmup_end_time);
+    assert_eq!(
+        (sim.time(), worker.borrow().total, sim.processed_event_count()),
+        states[11]
+    );
+}
+
+// Returns the trace file records of the run with the specified number of steps followed by stepping back.
+fn record_trace(name: &str, steps: usize, back: u64) -> Vec<simcore::trace_file::TraceFileRecord> {
+    let path = std::env::temp_dir().join(format!("simcore-step-back-{}-{}.jsonl", name, std::process::id()));
+    let (mut sim, worker) = build(4, 10);
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    run(&sim, &worker, steps);
+    let jobs = worker.borrow().ctx.counter("jobs");
+    if back > 0 {
+        assert!(sim.step_back(back));
+    }
+    assert_eq!(worker.borrow().ctx.counter("jobs"), jobs);
+    sim.disable_trace_file();
+    let trace = read_trace_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    trace.records
+}
+
+#[test]
+fn test_reexecution_has_no_side_effects() {
+    let records = record_trace("no-side-effects", 15, 6);
+    assert_eq!(records, record_trace("reference", 15, 0));
+}
+
+#[derive(Default)]
+struct TimeRecorder {
+    advances: Vec<(f64, f64)>,
+    rewinds: Vec<(f64, f64)>,
+}
+
+impl TimeAdvanceListener for TimeRecorder {
+    fn on_time_advance(&mut self, old_time: f64, new_time: f64, _events: &[Event]) {
+        self.advances.push((old_time, new_time));
+    }
+
+    fn on_rewind(&mut self, old_time: f64, new_time: f64) {
+        self.rewinds.push((old_time, new_time));
+    }
+}
+
+#[test]
+fn test_time_advance_listener_across_step_back() {
+    let (mut sim, worker) = build(4, 10);
+    let recorder = Rc::new(RefCell::new(TimeRecorder::default()));
+    sim.add_time_advance_listener(recorder.clone());
+    let states = run(&sim, &worker, 15);
+    let advances = recorder.borrow().advances.clone();
+    let (_, last_reported) = *advances.last().unwrap();
+
+    assert!(sim.step_back(6));
+    assert_eq!(sim.time(), states[8].0);
+    // the re-executed events are not reported, instead the rewind is reported once
+    assert_eq!(recorder.borrow().advances, advances);
+    assert_eq!(recorder.borrow().rewinds, vec![(last_reported, sim.time())]);
+
+    // the next advance is reported from the time after stepping back
+    run(&sim, &worker, 6);
+    let recorder = recorder.borrow();
+    assert_eq!(recorder.advances.len(), advances.len() + 6);
+    assert_eq!(recorder.advances[advances.len()].0, states[8].0);
+    assert!(recorder
+        .advances
+        .windows(2)
+        .skip(advances.len())
+        .all(|w| w[0].1 == w[1].0));
+}
+
+#[test]
+#[should_panic(expected = "Automatic checkpoints are not enabled")]
+fn test_auto_checkpoints_not_enabled() {
+    let (mut sim, worker) = build(10, 10);
+    sim.disable_auto_checkpoints();
+    run(&sim, &worker, 5);
+    sim.step_back(1);
+}
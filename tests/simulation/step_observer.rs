@@ -0,0 +1,124 @@
+//! Tests of step observers.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::observer::{StepDelta, StepObserver};
+use simcore::{Event, EventHandler, EventId, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    fanout: u32,
+}
+
+struct Node {
+    ctx: SimulationContext,
+    batch_sizes: Vec<usize>,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        let message = event.data.downcast_ref::<Message>().unwrap();
+        for _ in 0..message.fanout {
+            self.ctx.emit_self(Message { fanout: 0 }, 1.);
+        }
+    }
+
+    fn on_batch(&mut self, events: Vec<Event>) {
+        self.batch_sizes.push(events.len());
+        for event in events {
+            self.on(event);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    before: Vec<(EventId, f64)>,
+    after: Vec<StepDelta>,
+}
+
+impl StepObserver for Recorder {
+    fn before_step(&mut self, event: &Event) {
+        assert_eq!(self.before.len(), self.after.len());
+        self.before.push((event.id, event.time));
+    }
+
+    fn after_step(&mut self, delta: &StepDelta) {
+        assert_eq!(self.before.len(), self.after.len() + 1);
+        self.after.push(delta.clone());
+    }
+}
+
+#[test]
+fn test_step_observers() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("node");
+    let client = sim.create_context("client");
+    let node = Rc::new(RefCell::new(Node {
+        ctx,
+        batch_sizes: Vec::new(),
+    }));
+    sim.add_handler("node", node.clone());
+    client.emit(Message { fanout: 3 }, node.borrow().ctx.id(), 1.);
+    // event without handler is observed too
+    client.emit(Message { fanout: 0 }, client.id(), 5.);
+
+    let first = Rc::new(RefCell::new(Recorder::default()));
+    let second = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_step_observer(first.clone());
+    sim.step();
+    sim.add_step_observer(second.clone());
+    sim.step_until_no_events();
+
+    let first = first.borrow();
+    assert_eq!(first.before, vec![(0, 1.), (2, 2.), (3, 2.), (4, 2.), (1, 5.)]);
+    let deltas: Vec<_> = first
+        .after
+        .iter()
+        .map(|delta| (delta.event_id, delta.time, delta.time_advance, delta.emitted_events))
+        .collect();
+    assert_eq!(
+        deltas,
+        vec![
+            (0, 1., 1., 3),
+            (2, 2., 1., 0),
+            (3, 2., 0., 0),
+            (4, 2., 0., 0),
+            (1, 5., 3., 0)
+        ]
+    );
+    // the observer added later sees only the following steps
+    assert_eq!(second.borrow().before, first.before[1..]);
+    assert_eq!(second.borrow().after, first.after[1..]);
+}
+
+#[test]
+fn test_batch_is_observed_as_single_step() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("node");
+    for _ in 0..3 {
+        ctx.emit_self(Message { fanout: 1 }, 1.);
+    }
+    let node = Rc::new(RefCell::new(Node {
+        ctx,
+        batch_sizes: Vec::new(),
+    }));
+    sim.add_handler("node", node.clone());
+    sim.enable_event_batching("node");
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_step_observer(recorder.clone());
+    sim.step_until_no_events();
+
+    assert_eq!(node.borrow().batch_sizes, vec![3, 3]);
+    assert_eq!(recorder.borrow().before, vec![(0, 1.), (3, 2.)]);
+    let emitted: Vec<_> = recorder
+        .borrow()
+        .after
+        .iter()
+        .map(|delta| delta.emitted_events)
+        .collect();
+    assert_eq!(emitted, vec![3, 0]);
+}
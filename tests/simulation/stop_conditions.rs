@@ -0,0 +1,204 @@
+//! Tests of stop conditions.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::stop::{ConfidenceWidth, EventCount, Metric, RelativePrecision, StopCondition, TimeReached};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Arrival {}
+
+#[derive(Clone, Serialize)]
+struct Departure {}
+
+// Server with uniformly distributed service time, whose arrivals are emitted in advance.
+struct Server {
+    ctx: SimulationContext,
+    service_time: Metric,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Arrival {} => {
+                let service_time = self.ctx.gen_range(1.0..5.0);
+                self.service_time.record(service_time);
+                self.ctx.emit_self(Departure {}, service_time);
+                self.ctx.emit_self(Arrival {}, 1.);
+            }
+            Departure {} => {}
+        })
+    }
+}
+
+fn setup(metric: &Metric) -> Simulation {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    ctx.emit_self(Arrival {}, 0.);
+    let server = Server {
+        ctx,
+        service_time: metric.clone(),
+    };
+    sim.add_handler("server", Rc::new(RefCell::new(server)));
+    sim
+}
+
+#[derive(Default)]
+struct Counter {
+    received: u32,
+}
+
+impl EventHandler for Counter {
+    fn on(&mut self, _event: Event) {
+        self.received += 1;
+    }
+
+    fn on_batch(&mut self, events: Vec<Event>) {
+        self.received += events.len() as u32;
+    }
+}
+
+#[test]
+fn test_event_count() {
+    let metric = Metric::new();
+    let mut sim = setup(&metric);
+    assert!(sim.step_until(EventCount::of::<Departure>(10)));
+    assert_eq!(metric.stats().unwrap().count, sim.event_count() as usize / 2);
+    assert!(sim.step_until(EventCount::of::<Arrival>(5)));
+
+    // the condition is checked before the first step
+    let time = sim.time();
+    assert!(sim.step_until(EventCount::of::<Arrival>(0)));
+    assert_eq!(sim.time(), time);
+}
+
+#[test]
+fn test_batched_and_coalesced_events_are_counted() {
+    for coalescing in [false, true] {
+        let mut sim = Simulation::new(123);
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        sim.add_handler("counter", counter.clone());
+        if coalescing {
+            sim.enable_event_coalescing("counter", 1.);
+        } else {
+            sim.enable_event_batching("counter");
+        }
+        let ctx = sim.create_context("sender");
+        let counter_id = sim.lookup_id("counter");
+        for _ in 0..5 {
+            ctx.emit(Arrival {}, counter_id, 1.);
+        }
+        ctx.emit(Arrival {}, counter_id, 3.);
+
+        assert!(sim.step_until(EventCount::of::<Arrival>(5)));
+        assert_eq!(counter.borrow().received, 5);
+        assert_eq!(sim.time(), 1.);
+    }
+}
+
+#[test]
+fn test_time_reached() {
+    let metric = Metric::new();
+    let mut sim = setup(&metric);
+    assert!(sim.step_until(TimeReached(10.)));
+    assert!(sim.time() >= 10. && sim.time() < 11.);
+
+    // no more events
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(Arrival {}, 1.);
+    assert!(!sim.step_until(TimeReached(10.)));
+    assert_eq!(sim.time(), 1.);
+}
+
+#[test]
+fn test_composite_conditions() {
+    let metric = Metric::new();
+    let mut sim = setup(&metric);
+    let condition = EventCount::of::<Arrival>(1000).or(TimeReached(20.));
+    assert!(sim.step_until(condition));
+    assert!(sim.time() >= 20. && sim.time() < 21.);
+
+    let condition = EventCount::of::<Arrival>(5).and(TimeReached(30.));
+    assert!(sim.step_until(condition));
+    assert!(sim.time() >= 30. && sim.time() < 31.);
+
+    let conditions: Vec<Box<dyn StopCondition>> =
+        vec![Box::new(TimeReached(40.)), Box::new(EventCount::of::<Arrival>(3))];
+    let mut conditions = conditions.into_iter();
+    let condition = conditions.next().unwrap().and(conditions.next().unwrap());
+    assert!(sim.step_until(condition));
+    assert!(sim.time() >= 40. && sim.time() < 41.);
+}
+
+#[test]
+fn test_relative_precision() {
+    let metric = Metric::new();
+    let mut sim = setup(&metric);
+    assert!(sim.step_until(RelativePrecision::new(&metric, 0.02, 0.95)));
+    let stats = metric.stats().unwrap();
+    let half_width = stats.confidence_half_width(0.95).unwrap();
+    assert!(half_width <= 0.02 * stats.mean);
+    assert!((stats.mean - 3.).abs() < 3. * 0.02 * 2.);
+
+    // higher confidence requires more observations
+    let more_confident = Metric::new();
+    let mut sim = setup(&more_confident);
+    assert!(sim.step_until(RelativePrecision::new(&more_confident, 0.02, 0.99)));
+    assert!(more_confident.stats().unwrap().count > stats.count);
+}
+
+#[test]
+fn test_min_observations() {
+    let metric = Metric::new();
+    let mut sim = setup(&metric);
+    // constant observations have zero variance
+    for _ in 0..5 {
+        metric.record(3.);
+    }
+    let condition = ConfidenceWidth::new(&metric, 100., 0.95).with_min_observations(20);
+    assert!(sim.step_until(condition));
+    assert_eq!(metric.stats().unwrap().count, 20);
+}
+
+#[test]
+fn test_confidence_width_with_batch_means() {
+    let metric = Metric::with_batch_size(10);
+    let mut sim = setup(&metric);
+    assert!(sim.step_until(ConfidenceWidth::new(&metric, 0.1, 0.9)));
+    let stats = metric.stats().unwrap();
+    assert!(stats.count >= 10);
+    assert!(2. * stats.confidence_half_width(0.9).unwrap() <= 0.1);
+    // batch means have smaller variance than the observations
+    assert!(stats.std_dev < 4. / 12f64.sqrt());
+}
+
+#[test]
+fn test_metric_batches() {
+    let metric = Metric::with_batch_size(2);
+    assert_eq!(metric.stats(), None);
+    metric.record(1.);
+    assert_eq!(metric.stats(), None);
+    for value in [5., 3., 5., 4., 6.] {
+        metric.record(value);
+    }
+    let stats = metric.stats().unwrap();
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.mean, 4.);
+    assert_eq!(stats.std_dev, 1.);
+}
+
+#[test]
+#[should_panic(expected = "Precision must be positive")]
+fn test_zero_precision_panics() {
+    RelativePrecision::new(&Metric::new(), 0., 0.95);
+}
+
+#[test]
+#[should_panic(expected = "Confidence level must be in the range (0, 1)")]
+fn test_invalid_confidence_panics() {
+    ConfidenceWidth::new(&Metric::new(), 1., 1.);
+}
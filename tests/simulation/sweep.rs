@@ -0,0 +1,256 @@
+//! Tests of parameter sweep runner.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::analysis::RunMetrics;
+use simcore::experiment::{ParamGrid, ParamValue, SweepParams, SweepRunner};
+use simcore::metadata::RunMetadata;
+use simcore::run::TerminationReason;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Job {}
+
+struct Worker {
+    ctx: SimulationContext,
+    rate: f64,
+    fail: bool,
+    jobs: u64,
+}
+
+impl EventHandler for Worker {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Job {} => {
+                self.jobs += 1;
+                assert!(!self.fail || self.jobs < 5, "Worker failed");
+                if self.ctx.time() < 50. {
+                    let delay = self.ctx.sample_exponential(self.rate);
+                    self.ctx.emit_self(Job {}, delay);
+                }
+            }
+        })
+    }
+}
+
+fn build(params: &SweepParams, seed: u64) -> Simulation {
+    let mut sim = Simulation::new(seed);
+    let ctx = sim.create_context("worker");
+    ctx.emit_self_now(Job {});
+    let worker = Worker {
+        ctx,
+        rate: params.f64("rate"),
+        fail: params.get("fail").is_some_and(|value| value.as_bool().unwrap()),
+        jobs: 0,
+    };
+    sim.add_handler("worker", Rc::new(RefCell::new(worker)));
+    sim
+}
+
+fn no_metrics(_sim: &Simulation, _result: &simcore::run::RunResult) -> RunMetrics {
+    RunMetrics::new()
+}
+
+#[test]
+fn test_grid_combinations() {
+    let grid = ParamGrid::new()
+        .with("rate", [1, 2])
+        .with("policy", ["fifo", "lifo", "random"])
+        .with("cache", [true]);
+    assert_eq!(grid.names(), vec!["rate", "policy", "cache"]);
+    let combinations = grid.combinations();
+    assert_eq!(combinations.len(), 6);
+    assert_eq!(combinations[0].to_string(), "rate=1, policy=fifo, cache=true");
+    assert_eq!(combinations[4].to_string(), "rate=2, policy=lifo, cache=true");
+    assert_eq!(combinations[4].i64("rate"), 2);
+    assert_eq!(combinations[4].f64("rate"), 2.);
+    assert_eq!(combinations[4].str("policy"), "lifo");
+    assert!(combinations[4].bool("cache"));
+    assert_eq!(combinations[4].get("policy"), Some(&ParamValue::Str("lifo".to_owned())));
+    assert!(combinations[4].get("unknown").is_none());
+
+    assert_eq!(ParamGrid::new().combinations(), vec![SweepParams::default()]);
+}
+
+#[test]
+#[should_panic(expected = "Parameter rate is already added")]
+fn test_duplicate_parameter() {
+    ParamGrid::new().with("rate", [1]).with("rate", [2]);
+}
+
+#[test]
+#[should_panic(expected = "Parameter rate has no values")]
+fn test_parameter_without_values() {
+    ParamGrid::new().with("rate", Vec::<f64>::new());
+}
+
+#[test]
+#[should_panic(expected = "Parameter policy is not a number")]
+fn test_wrong_parameter_type() {
+    let params = ParamGrid::new().with("policy", ["fifo"]).combinations();
+    params[0].f64("policy");
+}
+
+#[test]
+#[should_panic(expected = "Unknown parameter rate")]
+fn test_unknown_parameter() {
+    SweepParams::default().f64("rate");
+}
+
+#[test]
+fn test_sweep() {
+    let grid = ParamGrid::new().with("rate", [0.5, 1., 2.]);
+    let results = SweepRunner::new(grid)
+        .with_replications(4)
+        .with_base_seed(10)
+        .run(build, |_sim, result| {
+            RunMetrics::from([("jobs_per_time".to_owned(), result.processed_events as f64 / result.time)])
+        });
+    assert_eq!(results.param_names, vec!["rate"]);
+    assert_eq!(results.rows.len(), 12);
+    for (i, row) in results.rows.iter().enumerate() {
+        assert_eq!(row.combination, i / 4);
+        assert_eq!(row.replication, i % 4);
+        assert_eq!(row.seed, 10 + (i % 4) as u64);
+        assert_eq!(row.termination, TerminationReason::Drained);
+        // the initial job is emitted before the run
+        assert_eq!(row.metrics["processed_events"], row.metrics["emitted_events"] + 1.);
+        assert!(row.metrics["time"] >= 50.);
+    }
+    assert_eq!(
+        results.metric_names(),
+        vec!["emitted_events", "jobs_per_time", "processed_events", "time"]
+    );
+
+    let summary = results.summary();
+    assert_eq!(summary.len(), 3);
+    let rates: Vec<f64> = summary.iter().map(|(_, stats)| stats["jobs_per_time"].mean).collect();
+    assert!(rates[0] < rates[1] && rates[1] < rates[2]);
+    assert_eq!(summary[2].0.f64("rate"), 2.);
+    assert_eq!(summary[2].1["time"].count, 4);
+}
+
+#[test]
+fn test_common_seeds() {
+    // the combinations with the same parameters and seeds produce the same runs
+    let grid = ParamGrid::new().with("rate", [1., 1.]);
+    let results = SweepRunner::new(grid).with_replications(3).run(build, no_metrics);
+    for i in 0..3 {
+        assert_eq!(results.rows[i].metrics, results.rows[i + 3].metrics);
+    }
+    assert_ne!(results.rows[0].metrics, results.rows[1].metrics);
+}
+
+#[test]
+fn test_time_limit() {
+    let grid = ParamGrid::new().with("rate", [1.]);
+    let results = SweepRunner::new(grid).with_time_limit(20.).run(build, no_metrics);
+    assert_eq!(results.rows[0].termination, TerminationReason::TimeReached);
+    assert_eq!(results.rows[0].metrics["time"], 20.);
+}
+
+#[test]
+fn test_failed_runs() {
+    let grid = ParamGrid::new().with("rate", [1.]).with("fail", [false, true]);
+    let results = SweepRunner::new(grid).run(build, no_metrics);
+    assert_eq!(results.rows[0].termination, TerminationReason::Drained);
+    match &results.rows[1].termination {
+        TerminationReason::Error(message) => assert_eq!(message, "Worker failed"),
+        reason => panic!("Unexpected termination reason {:?}", reason),
+    }
+    assert_eq!(results.rows[1].metrics["processed_events"], 5.);
+}
+
+#[test]
+fn test_csv() {
+    let grid = ParamGrid::new()
+        .with("rate", [1.5])
+        .with("label", ["a,b", "say \"hi\""]);
+    let results = SweepRunner::new(grid).run(build, no_metrics);
+    let mut output = Vec::new();
+    results.write_csv(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().skip(1).collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0],
+        "rate,label,replication,seed,termination,emitted_events,processed_events,time"
+    );
+    let metrics = &results.rows[0].metrics;
+    assert_eq!(
+        lines[1],
+        format!(
+            "1.5,\"a,b\",0,0,drained,{},{},{}",
+            metrics["emitted_events"], metrics["processed_events"], metrics["time"]
+        )
+    );
+    assert!(lines[2].starts_with("1.5,\"say \"\"hi\"\"\",0,0,drained,"));
+}
+
+#[derive(Deserialize)]
+struct CsvHeader {
+    metadata: RunMetadata,
+}
+
+#[test]
+fn test_csv_metadata() {
+    let grid = ParamGrid::new().with("rate", [1., 2.]);
+    let results = SweepRunner::new(grid)
+        .with_base_seed(42)
+        .with_run_label("experiment", "rates")
+        .with_run_config(&("config", 1))
+        .run(build, no_metrics);
+    assert_eq!(results.metadata.seed, 42);
+    assert_eq!(results.metadata.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(results.metadata.labels["experiment"], "rates");
+    assert!(results.metadata.config_hash.is_some());
+    assert!(results.metadata.start_time > 0.);
+
+    let mut output = Vec::new();
+    results.write_csv(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let header = output.lines().next().unwrap().strip_prefix("# ").unwrap();
+    let header: CsvHeader = serde_json::from_str(header).unwrap();
+    assert_eq!(header.metadata, results.metadata);
+    assert!(output.lines().nth(1).unwrap().starts_with("rate,replication,seed,"));
+}
+
+#[test]
+fn test_display() {
+    let grid = ParamGrid::new().with("rate", [1., 2.]);
+    let results = SweepRunner::new(grid).with_replications(2).run(build, no_metrics);
+    let output = results.to_string();
+    assert_eq!(output.lines().count(), 5);
+    let header: Vec<&str> = output.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(
+        header,
+        vec![
+            "rate",
+            "replication",
+            "seed",
+            "termination",
+            "emitted_events",
+            "processed_events",
+            "time"
+        ]
+    );
+}
+
+#[cfg(feature = "thread")]
+#[test]
+fn test_parallel_sweep() {
+    let grid = ParamGrid::new().with("rate", [0.5, 1., 2.]).with("fail", [false, true]);
+    let sequential = SweepRunner::new(grid.clone())
+        .with_replications(3)
+        .run(build, no_metrics);
+    for threads in [2, 5, 64] {
+        let parallel = SweepRunner::new(grid.clone())
+            .with_replications(3)
+            .with_threads(threads)
+            .run(build, no_metrics);
+        assert_eq!(parallel.rows, sequential.rows);
+    }
+}
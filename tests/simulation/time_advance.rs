@@ -0,0 +1,155 @@
+//! Tests of time advance listeners.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::observer::TimeAdvanceListener;
+use simcore::{Event, EventHandler, EventId, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    fanout: u32,
+    delay: f64,
+}
+
+struct Node {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        let message = event.data.downcast_ref::<Message>().unwrap();
+        for _ in 0..message.fanout {
+            self.ctx.emit_self(Message { fanout: 0, delay: 0. }, message.delay);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    advances: Vec<(f64, f64, Vec<EventId>)>,
+}
+
+impl TimeAdvanceListener for Recorder {
+    fn on_time_advance(&mut self, old_time: f64, new_time: f64, events: &[Event]) {
+        self.advances
+            .push((old_time, new_time, events.iter().map(|event| event.id).collect()));
+    }
+}
+
+fn message(fanout: u32, delay: f64) -> Message {
+    Message { fanout, delay }
+}
+
+fn setup(sim: &mut Simulation) -> (Rc<RefCell<Node>>, Rc<RefCell<Recorder>>) {
+    let node = Rc::new(RefCell::new(Node {
+        ctx: sim.create_context("node"),
+    }));
+    sim.add_handler("node", node.clone());
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_time_advance_listener(recorder.clone());
+    (node, recorder)
+}
+
+#[test]
+fn test_same_time_events_are_batched() {
+    let mut sim = Simulation::new(123);
+    let (node, recorder) = setup(&mut sim);
+    {
+        let ctx = &node.borrow().ctx;
+        // the events emitted with zero delay are processed at the same time
+        ctx.emit_self(message(2, 0.), 1.);
+        ctx.emit_self(message(1, 1.5), 1.);
+        ctx.emit_self(message(0, 0.), 4.);
+    }
+    sim.step_until_no_events();
+
+    assert_eq!(
+        recorder.borrow().advances,
+        vec![(0., 1., vec![0, 1, 3, 4]), (1., 2.5, vec![5]), (2.5, 4., vec![2])]
+    );
+}
+
+#[test]
+fn test_advance_is_reported_after_step() {
+    let mut sim = Simulation::new(123);
+    let (node, recorder) = setup(&mut sim);
+    node.borrow().ctx.emit_self(message(0, 0.), 1.);
+    node.borrow().ctx.emit_self(message(0, 0.), 1.);
+    node.borrow().ctx.emit_self(message(0, 0.), 2.);
+
+    sim.step();
+    assert!(recorder.borrow().advances.is_empty());
+    sim.step();
+    assert_eq!(recorder.borrow().advances, vec![(0., 1., vec![0, 1])]);
+}
+
+#[test]
+fn test_advances_without_events() {
+    let mut sim = Simulation::new(123);
+    let (node, recorder) = setup(&mut sim);
+    node.borrow().ctx.emit_self(message(0, 0.), 2.);
+    sim.step_until_time(1.);
+    sim.step_until_time(3.);
+    // no advance when time does not change
+    sim.step_until_time(3.);
+
+    assert_eq!(
+        recorder.borrow().advances,
+        vec![(0., 1., vec![]), (1., 2., vec![0]), (2., 3., vec![])]
+    );
+}
+
+#[test]
+fn test_events_added_between_steps() {
+    let mut sim = Simulation::new(123);
+    let (node, recorder) = setup(&mut sim);
+    let id = node.borrow().ctx.emit_self(message(0, 0.), 1.);
+    node.borrow().ctx.emit_self(message(0, 0.), 1.);
+    node.borrow().ctx.emit_self(message(0, 0.), 3.);
+    sim.step();
+    // the pending event at the current time is canceled, so the advance is reported on the next step
+    sim.cancel_events(|event| event.id == id + 1);
+    sim.step();
+    // new event at the current time is reported as a separate advance
+    node.borrow().ctx.emit_self(message(0, 0.), 0.);
+    sim.step_until_no_events();
+
+    assert_eq!(
+        recorder.borrow().advances,
+        vec![(0., 1., vec![0]), (1., 3., vec![2]), (3., 3., vec![3])]
+    );
+}
+
+#[test]
+fn test_batched_events_are_reported() {
+    let mut sim = Simulation::new(123);
+    let (node, recorder) = setup(&mut sim);
+    sim.enable_event_batching("node");
+    for _ in 0..3 {
+        node.borrow().ctx.emit_self(message(0, 0.), 1.);
+    }
+    sim.step();
+
+    assert_eq!(recorder.borrow().advances, vec![(0., 1., vec![0, 1, 2])]);
+}
+
+#[test]
+fn test_listener_added_during_run() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(message(0, 0.), 1.);
+    ctx.emit_self(message(0, 0.), 2.);
+    sim.step();
+
+    let first = Rc::new(RefCell::new(Recorder::default()));
+    let second = Rc::new(RefCell::new(Recorder::default()));
+    sim.add_time_advance_listener(first.clone());
+    sim.add_time_advance_listener(second.clone());
+    sim.step_until_no_events();
+
+    assert_eq!(first.borrow().advances, vec![(1., 2., vec![1])]);
+    assert_eq!(second.borrow().advances, first.borrow().advances);
+}
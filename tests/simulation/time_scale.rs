@@ -0,0 +1,74 @@
+//! Tests of scoped time dilation.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Tick {}
+
+struct Host {
+    ctx: SimulationContext,
+    slowdown: f64,
+    ticks: Vec<f64>,
+}
+
+impl EventHandler for Host {
+    fn on(&mut self, _event: Event) {
+        self.ticks.push(self.ctx.time());
+        if self.ticks.len() < 4 {
+            let _scope = self.ctx.scale_time(self.slowdown);
+            self.ctx.emit_self(Tick {}, 1.);
+        }
+    }
+}
+
+#[test]
+fn test_scaled_emits_and_timers() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("host");
+    let other = sim.create_context("other");
+    sim.allow_emit_as("host");
+    {
+        let _scope = ctx.scale_time(4.);
+        ctx.emit(Tick {}, other.id(), 1.);
+        ctx.emit_ordered_self(Tick {}, 1.);
+        assert!(ctx.can_emit_ordered(1.));
+        assert!(!ctx.can_emit_ordered(0.5));
+        ctx.emit_as(Tick {}, other.id(), other.id(), 2.);
+        ctx.set_timer("timer", 0.5);
+        // other contexts are not affected
+        other.emit_self(Tick {}, 1.);
+    }
+    assert_eq!(ctx.time_scale(), 1.);
+    ctx.emit_self(Tick {}, 1.5);
+
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![1., 1.5, 2., 4., 4., 8.]);
+}
+
+#[test]
+fn test_scope_in_handler() {
+    let mut sim = Simulation::new(123);
+    let host = Rc::new(RefCell::new(Host {
+        ctx: sim.create_context("host"),
+        slowdown: 2.5,
+        ticks: Vec::new(),
+    }));
+    sim.add_handler("host", host.clone());
+    host.borrow().ctx.emit_self(Tick {}, 0.);
+    sim.step_until_no_events();
+    assert_eq!(host.borrow().ticks, vec![0., 2.5, 5., 7.5]);
+    assert_eq!(host.borrow().ctx.time_scale(), 1.);
+}
+
+#[test]
+#[should_panic(expected = "Time scale factor must be positive")]
+fn test_non_positive_factor() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("host");
+    let _scope = ctx.scale_time(0.);
+}
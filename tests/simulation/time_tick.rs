@@ -0,0 +1,166 @@
+//! Tests of discrete tick-based time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::tick::TickPolicy;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Message {
+    round: u32,
+}
+
+struct Node {
+    rounds: Vec<(u32, u64, f64)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Node {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Message { round } => {
+                self.rounds.push((round, self.ctx.current_tick(), self.ctx.time()));
+                if round < 10 {
+                    // delay of one tick computed with floating-point error
+                    self.ctx.emit_self(Message { round: round + 1 }, 0.3 - 0.2);
+                }
+            }
+        })
+    }
+}
+
+fn emit_times(policy: TickPolicy, delays: &[f64]) -> Vec<f64> {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.set_time_tick(0.25, policy);
+    for delay in delays {
+        ctx.emit_self(Message { round: 0 }, *delay);
+    }
+    sim.dump_events().iter().map(|e| e.time).collect()
+}
+
+#[test]
+fn test_round_policy() {
+    let times = emit_times(TickPolicy::Round, &[0.1, 0.2, 0.25, 0.6, 1.]);
+    assert_eq!(times, vec![0., 0.25, 0.25, 0.5, 1.]);
+}
+
+#[test]
+fn test_ceil_policy() {
+    let times = emit_times(TickPolicy::Ceil, &[0., 0.1, 0.25, 0.26, 1.]);
+    assert_eq!(times, vec![0., 0.25, 0.25, 0.5, 1.]);
+}
+
+#[test]
+fn test_strict_policy_accepts_aligned_times() {
+    let times = emit_times(TickPolicy::Strict, &[0., 0.5, 0.75, 0.1 + 0.2 - 0.05]);
+    assert_eq!(times, vec![0., 0.25, 0.5, 0.75]);
+}
+
+#[test]
+#[should_panic(expected = "Time 0.3 is not a multiple of time tick 0.25")]
+fn test_strict_policy_rejects_unaligned_times() {
+    emit_times(TickPolicy::Strict, &[0.3]);
+}
+
+#[test]
+fn test_rounds_do_not_accumulate_errors() {
+    let mut sim = Simulation::new(123);
+    let node = Rc::new(RefCell::new(Node {
+        rounds: Vec::new(),
+        ctx: sim.create_context("node"),
+    }));
+    sim.add_handler("node", node.clone());
+    sim.set_time_tick(0.1, TickPolicy::Strict);
+    node.borrow().ctx.emit_self(Message { round: 0 }, 0.);
+    sim.step_until_no_events();
+
+    let rounds = node.borrow().rounds.clone();
+    assert_eq!(rounds.len(), 11);
+    for (round, tick, time) in rounds {
+        assert_eq!(round as u64, tick);
+        assert_eq!(time, tick as f64 * 0.1);
+    }
+}
+
+#[test]
+fn test_ordered_events_are_snapped() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.set_time_tick(1., TickPolicy::Ceil);
+    ctx.emit_ordered_self(Message { round: 0 }, 0.5);
+    ctx.emit_ordered_self(Message { round: 1 }, 0.7);
+    ctx.emit_ordered_self(Message { round: 2 }, 1.2);
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![1., 1., 2.]);
+}
+
+#[test]
+#[should_panic(expected = "Time tick is not set")]
+fn test_current_tick_without_tick() {
+    let mut sim = Simulation::new(123);
+    sim.create_context("comp").current_tick();
+}
+
+#[test]
+fn test_tick_variants_are_exact() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    sim.set_time_tick(0.1, TickPolicy::Strict);
+    for round in 0..100 {
+        ctx.emit_self_ticks(Message { round }, 1);
+        sim.step();
+        assert_eq!(sim.current_tick(), round as u64 + 1);
+        assert_eq!(sim.time(), (round + 1) as f64 * 0.1);
+    }
+}
+
+#[test]
+fn test_emit_at_tick_and_ordered_ticks() {
+    let mut sim = Simulation::with_integer_ticks(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_at_tick(Message { round: 0 }, ctx.id(), 7);
+    ctx.emit_ordered_ticks(Message { round: 1 }, ctx.id(), 2);
+    ctx.emit_ordered_ticks(Message { round: 2 }, ctx.id(), 3);
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![2., 3., 7.]);
+
+    assert!(sim.step_until_tick(5));
+    assert_eq!(sim.current_tick(), 5);
+    ctx.emit_ticks(Message { round: 3 }, ctx.id(), 4);
+    assert!(!sim.step_until_tick(10));
+    assert_eq!(sim.current_tick(), 10);
+}
+
+#[test]
+fn test_tick_variants_ignore_time_scale() {
+    let mut sim = Simulation::with_integer_ticks(123);
+    let ctx = sim.create_context("comp");
+    let _guard = ctx.scale_time(2.5);
+    ctx.emit_self_ticks(Message { round: 0 }, 3);
+    ctx.set_timer_ticks("timer", 4);
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![3., 4.]);
+}
+
+#[test]
+fn test_set_timer_ticks_replaces_timer() {
+    let mut sim = Simulation::with_integer_ticks(123);
+    let ctx = sim.create_context("comp");
+    ctx.set_timer_ticks("timer", 5);
+    sim.step_until_tick(2);
+    ctx.set_timer_ticks("timer", 1);
+    let times: Vec<_> = sim.dump_events().iter().map(|e| e.time).collect();
+    assert_eq!(times, vec![3.]);
+}
+
+#[test]
+#[should_panic(expected = "Time tick is not set")]
+fn test_emit_ticks_without_tick() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self_ticks(Message { round: 0 }, 1);
+}
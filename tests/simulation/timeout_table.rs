@@ -0,0 +1,170 @@
+//! Tests of timeout tables.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use simcore::tick::TickPolicy;
+use simcore::timeout::{Timeout, TimeoutTable, TimeoutsExpired};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+struct Endpoint {
+    timeouts: TimeoutTable<u32>,
+    // Expired timeouts grouped by wake-up events.
+    batches: Vec<(f64, Vec<Timeout<u32>>)>,
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Endpoint {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            TimeoutsExpired { table } => {
+                assert_eq!(table, "connections");
+                let expired = self.timeouts.take_expired();
+                self.batches.push((self.ctx.time(), expired));
+            }
+        })
+    }
+}
+
+fn setup() -> (Simulation, Rc<RefCell<Endpoint>>) {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("endpoint");
+    let endpoint = Rc::new(RefCell::new(Endpoint {
+        timeouts: TimeoutTable::new(&ctx, "connections"),
+        batches: Vec::new(),
+        ctx,
+    }));
+    sim.add_handler("endpoint", endpoint.clone());
+    (sim, endpoint)
+}
+
+fn expired(endpoint: &Rc<RefCell<Endpoint>>) -> Vec<(f64, Vec<(u32, String)>)> {
+    endpoint
+        .borrow()
+        .batches
+        .iter()
+        .map(|(time, batch)| (*time, batch.iter().map(|t| (t.key, t.name.clone())).collect()))
+        .collect()
+}
+
+#[test]
+fn test_timeouts_with_equal_deadline_expire_in_single_event() {
+    let (mut sim, endpoint) = setup();
+    for conn in 0..100 {
+        endpoint.borrow_mut().timeouts.set(conn, "idle", 10.);
+        endpoint.borrow_mut().timeouts.set(conn, "retransmit", 3.);
+    }
+    assert_eq!(endpoint.borrow().timeouts.len(), 200);
+    assert_eq!(endpoint.borrow().timeouts.next_deadline(), Some(3.));
+    // the wake-up is rescheduled once for the earlier deadline
+    assert_eq!(sim.event_count(), 2);
+
+    sim.step_until_no_events();
+    let batches = expired(&endpoint);
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].0, 3.);
+    assert_eq!(batches[0].1.len(), 100);
+    assert!(batches[0].1.iter().all(|(_, name)| name == "retransmit"));
+    // expired timeouts are ordered by the time of setting
+    let keys: Vec<u32> = batches[1].1.iter().map(|(key, _)| *key).collect();
+    assert_eq!(keys, (0..100).collect::<Vec<_>>());
+    assert_eq!(batches[1].0, 10.);
+    assert!(endpoint.borrow().timeouts.is_empty());
+    assert_eq!(sim.event_count(), 3);
+}
+
+#[test]
+fn test_extension_does_not_create_events() {
+    let (mut sim, endpoint) = setup();
+    endpoint.borrow_mut().timeouts.set(1, "keepalive", 5.);
+    for i in 1..=40 {
+        sim.step_until_time(i as f64 * 0.1);
+        endpoint.borrow_mut().timeouts.set(1, "keepalive", 5.);
+    }
+    assert_eq!(endpoint.borrow().timeouts.deadline(&1, "keepalive"), Some(4. + 5.));
+    assert_eq!(sim.event_count(), 1);
+
+    sim.step_until_no_events();
+    // the first wake-up finds the extended timeout
+    assert_eq!(
+        expired(&endpoint),
+        vec![(5., vec![]), (9., vec![(1, "keepalive".to_owned())])]
+    );
+    assert_eq!(sim.event_count(), 2);
+}
+
+#[test]
+fn test_earlier_deadline_reschedules_wakeup() {
+    let (mut sim, endpoint) = setup();
+    endpoint.borrow_mut().timeouts.set(1, "idle", 10.);
+    endpoint.borrow_mut().timeouts.set(2, "retransmit", 2.);
+    assert_eq!(sim.event_count(), 2);
+    sim.step_until_no_events();
+    assert_eq!(
+        expired(&endpoint),
+        vec![
+            (2., vec![(2, "retransmit".to_owned())]),
+            (10., vec![(1, "idle".to_owned())])
+        ]
+    );
+}
+
+#[test]
+fn test_cancellation() {
+    let (mut sim, endpoint) = setup();
+    {
+        let mut endpoint = endpoint.borrow_mut();
+        endpoint.timeouts.set(1, "idle", 10.);
+        endpoint.timeouts.set(1, "retransmit", 2.);
+        endpoint.timeouts.set(2, "retransmit", 2.);
+        assert!(endpoint.timeouts.cancel(&2, "retransmit"));
+        assert!(!endpoint.timeouts.cancel(&2, "retransmit"));
+        assert!(!endpoint.timeouts.cancel(&3, "idle"));
+        assert_eq!(endpoint.timeouts.deadline(&2, "retransmit"), None);
+        assert_eq!(endpoint.timeouts.len(), 2);
+    }
+    sim.step_until_time(1.);
+    assert_eq!(endpoint.borrow_mut().timeouts.cancel_all(&1), 2);
+    assert_eq!(endpoint.borrow_mut().timeouts.cancel_all(&1), 0);
+    assert!(endpoint.borrow().timeouts.is_empty());
+
+    // the wake-up is canceled when the table becomes empty
+    assert!(!sim.step());
+    assert!(expired(&endpoint).is_empty());
+}
+
+#[test]
+fn test_zero_delay_timeout() {
+    let (mut sim, endpoint) = setup();
+    endpoint.borrow_mut().timeouts.set(7, "now", 0.);
+    sim.step_until_no_events();
+    assert_eq!(expired(&endpoint), vec![(0., vec![(7, "now".to_owned())])]);
+}
+
+#[test]
+fn test_timeouts_with_time_tick() {
+    for (policy, time) in [(TickPolicy::Round, 1.), (TickPolicy::Ceil, 2.)] {
+        let (mut sim, endpoint) = setup();
+        sim.set_time_tick(1., policy);
+        endpoint.borrow_mut().timeouts.set(1, "retransmit", 1.2);
+        sim.step_until_no_events();
+        assert_eq!(expired(&endpoint), vec![(time, vec![(1, "retransmit".to_owned())])]);
+    }
+}
+
+#[test]
+fn test_dropping_table_cancels_wakeup() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("endpoint");
+    let mut timeouts = TimeoutTable::new(&ctx, "requests");
+    timeouts.set("request", "response", 1.);
+    drop(timeouts);
+    assert!(!sim.step());
+}
+
+#[test]
+#[should_panic(expected = "Timeout delay must be non-negative")]
+fn test_negative_delay_panics() {
+    let (_sim, endpoint) = setup();
+    endpoint.borrow_mut().timeouts.set(1, "idle", -1.);
+}
@@ -0,0 +1,199 @@
+//! Tests of recorded trace comparison.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::json;
+
+use simcore::trace_diff::{diff_trace_files, diff_traces, TraceDiffConfig};
+use simcore::trace_file::{TraceEventKind, TraceFileConfig, TraceFileRecord};
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+fn record(id: u64, time: f64, value: u64) -> TraceFileRecord {
+    TraceFileRecord {
+        kind: TraceEventKind::Processed,
+        time,
+        id,
+        event_time: Some(time),
+        src: "client".to_string(),
+        dst: "server".to_string(),
+        type_name: "Request".to_string(),
+        data: Some(json!({"value": value, "stamp": id * 7})),
+    }
+}
+
+fn records(values: &[u64]) -> Vec<TraceFileRecord> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| record(i as u64, i as f64, *value))
+        .collect()
+}
+
+fn values(records: &[TraceFileRecord]) -> Vec<u64> {
+    records
+        .iter()
+        .map(|r| r.data.as_ref().unwrap()["value"].as_u64().unwrap())
+        .collect()
+}
+
+fn config(context: usize) -> TraceDiffConfig {
+    let mut config = TraceDiffConfig::new();
+    config.context = context;
+    config
+}
+
+#[test]
+fn test_equal_traces() {
+    let diff = diff_traces(records(&[1, 2, 3]), records(&[1, 2, 3]), &config(3));
+    assert!(diff.is_empty());
+    assert!(!diff.truncated);
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn test_changed_record() {
+    let diff = diff_traces(records(&[1, 2, 3, 4, 5]), records(&[1, 2, 9, 4, 5]), &config(1));
+    assert_eq!(diff.divergences.len(), 1);
+    let divergence = &diff.divergences[0];
+    assert_eq!((divergence.left_index, divergence.right_index), (2, 2));
+    assert_eq!(values(&divergence.left), vec![3]);
+    assert_eq!(values(&divergence.right), vec![9]);
+    assert_eq!(divergence.before.len(), 1);
+    assert_eq!(divergence.before[0].0.id, 1);
+    assert_eq!(divergence.after.len(), 1);
+    assert_eq!(divergence.after[0].1.id, 3);
+
+    let text = diff.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "@@ left 2, right 2 @@");
+    assert!(lines[1].starts_with("  {"));
+    assert!(lines[2].starts_with("- {") && lines[2].contains("\"value\":3"));
+    assert!(lines[3].starts_with("+ {") && lines[3].contains("\"value\":9"));
+}
+
+#[test]
+fn test_inserted_and_removed_records() {
+    // the comparison is not affected by the shifted identifiers and stamps
+    let mut config = config(2);
+    config.ignored_fields = vec![
+        "id".to_string(),
+        "time".to_string(),
+        "event_time".to_string(),
+        "data.stamp".to_string(),
+    ];
+    let diff = diff_traces(
+        records(&[1, 2, 3, 4, 5, 6, 7]),
+        records(&[1, 2, 8, 8, 3, 4, 6, 7]),
+        &config,
+    );
+    assert_eq!(diff.divergences.len(), 2);
+    let inserted = &diff.divergences[0];
+    assert_eq!((inserted.left_index, inserted.right_index), (2, 2));
+    assert!(inserted.left.is_empty());
+    assert_eq!(values(&inserted.right), vec![8, 8]);
+    assert_eq!(inserted.after.len(), 2);
+    let removed = &diff.divergences[1];
+    assert_eq!((removed.left_index, removed.right_index), (4, 6));
+    assert_eq!(values(&removed.left), vec![5]);
+    assert!(removed.right.is_empty());
+    // the context before is limited by the previous divergence
+    assert_eq!(removed.before.len(), 2);
+    assert_eq!(removed.after.len(), 2);
+}
+
+#[test]
+fn test_ignored_fields() {
+    let mut right = records(&[1, 2, 3]);
+    for record in right.iter_mut() {
+        record.data.as_mut().unwrap()["stamp"] = json!(0);
+        record.src = "other".to_string();
+    }
+    let diff = diff_traces(records(&[1, 2, 3]), right.clone(), &config(3));
+    // the traces are realigned at their ends
+    assert_eq!(diff.divergences.len(), 1);
+    assert_eq!(diff.divergences[0].left.len(), 3);
+    let mut config = config(3);
+    config.ignored_fields = vec![
+        "data.stamp".to_string(),
+        "src".to_string(),
+        "data.missing.field".to_string(),
+    ];
+    assert!(diff_traces(records(&[1, 2, 3]), right, &config).is_empty());
+}
+
+#[test]
+fn test_different_tail() {
+    let diff = diff_traces(records(&[1, 2]), records(&[1, 2, 3, 4]), &config(3));
+    assert_eq!(diff.divergences.len(), 1);
+    assert_eq!(values(&diff.divergences[0].right), vec![3, 4]);
+    assert!(diff.divergences[0].after.is_empty());
+}
+
+#[test]
+fn test_max_divergences() {
+    let mut config = config(0);
+    config.max_divergences = 2;
+    config.window = 1;
+    // the records are consumed lazily up to the third divergence
+    let right = records(&[9, 9, 9, 9, 9]).into_iter().take(3);
+    let diff = diff_traces(records(&[1, 2, 3, 4, 5]), right, &config);
+    assert!(diff.truncated);
+    assert_eq!(diff.divergences.len(), 2);
+    // without matching records in the window the records are compared one by one
+    assert_eq!(values(&diff.divergences[0].left), vec![1]);
+    assert_eq!(values(&diff.divergences[0].right), vec![9]);
+    assert!(diff.to_string().ends_with("(comparison stopped after 2 divergences)\n"));
+}
+
+#[derive(Clone, Serialize)]
+struct Tick {
+    jitter: f64,
+}
+
+struct Ticker {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Ticker {
+    fn on(&mut self, _event: Event) {
+        if self.ctx.time() < 10. {
+            let jitter = self.ctx.rand();
+            self.ctx.emit_self(Tick { jitter }, 1. + jitter);
+        }
+    }
+}
+
+fn record_trace(name: &str, seed: u64) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("simcore-diff-{}-{}.jsonl", name, std::process::id()));
+    let mut sim = Simulation::new(seed);
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    let ctx = sim.create_context("ticker");
+    ctx.emit_self(Tick { jitter: 0. }, 0.);
+    sim.add_handler("ticker", Rc::new(RefCell::new(Ticker { ctx })));
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    path
+}
+
+#[test]
+fn test_trace_files() {
+    let (first, second, other) = (
+        record_trace("first", 1),
+        record_trace("second", 1),
+        record_trace("other", 2),
+    );
+    assert!(diff_trace_files(&first, &second, &TraceDiffConfig::new()).is_empty());
+    let diff = diff_trace_files(&first, &other, &TraceDiffConfig::new());
+    for path in [first, second, other] {
+        std::fs::remove_file(path).unwrap();
+    }
+    // the initial tick is emitted and processed in the same way
+    let divergence = &diff.divergences[0];
+    assert_eq!((divergence.left_index, divergence.right_index), (2, 2));
+    assert_eq!(divergence.before.len(), 2);
+    assert_eq!(divergence.left[0].type_name, "Tick");
+}
@@ -0,0 +1,250 @@
+//! Tests of event trace recording to JSON Lines file.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Serialize;
+use serde_json::json;
+
+use simcore::trace_file::{read_trace_file, read_trace_file_range, TraceEventKind, TraceFileConfig, TraceFileRecord};
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Response {
+    id: u64,
+}
+
+struct Server {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Request { id } => {
+                self.ctx.emit(Response { id }, event.src, 0.5);
+            }
+        })
+    }
+}
+
+fn trace_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-trace-{}-{}.jsonl", name, std::process::id()))
+}
+
+fn build() -> (Simulation, SimulationContext, Id) {
+    let mut sim = Simulation::new(123);
+    let server_ctx = sim.create_context("server");
+    let server_id = sim.add_handler("server", Rc::new(RefCell::new(Server { ctx: server_ctx })));
+    let client = sim.create_context("client");
+    (sim, client, server_id)
+}
+
+fn record(config: TraceFileConfig, run: impl FnOnce(&mut Simulation, &SimulationContext, Id)) -> Vec<TraceFileRecord> {
+    let path = config.path.clone();
+    let (mut sim, client, server_id) = build();
+    sim.enable_trace_file(config);
+    run(&mut sim, &client, server_id);
+    sim.disable_trace_file();
+    let trace = read_trace_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(trace.metadata.seed, 123);
+    trace.records
+}
+
+fn summary(records: &[TraceFileRecord]) -> Vec<(TraceEventKind, u64, f64, &str)> {
+    records
+        .iter()
+        .map(|r| (r.kind, r.id, r.time, r.type_name.as_str()))
+        .collect()
+}
+
+#[test]
+fn test_all_events() {
+    let path = trace_path("all");
+    let records = record(TraceFileConfig::new(&path), |sim, client, server_id| {
+        client.emit(Request { id: 1 }, server_id, 1.);
+        let canceled = client.emit(Request { id: 2 }, server_id, 2.);
+        sim.step();
+        client.cancel_event(canceled);
+        sim.step_until_no_events();
+    });
+    assert_eq!(
+        summary(&records),
+        vec![
+            (TraceEventKind::Emitted, 0, 0., "Request"),
+            (TraceEventKind::Emitted, 1, 0., "Request"),
+            (TraceEventKind::Processed, 0, 1., "Request"),
+            (TraceEventKind::Emitted, 2, 1., "Response"),
+            (TraceEventKind::Canceled, 1, 1., "Request"),
+            (TraceEventKind::Processed, 2, 1.5, "Response"),
+        ]
+    );
+    let emitted = &records[3];
+    assert_eq!(emitted.event_time, Some(1.5));
+    assert_eq!(emitted.src, "server");
+    assert_eq!(emitted.dst, "client");
+    assert_eq!(emitted.data, Some(json!({"id": 1})));
+    let canceled = &records[4];
+    assert_eq!(canceled.event_time, Some(2.));
+    assert_eq!(canceled.data, None);
+}
+
+#[test]
+fn test_filters() {
+    let path = trace_path("filters");
+    let mut config = TraceFileConfig::new(&path);
+    config.kinds = vec![TraceEventKind::Processed];
+    config.event_types = Some(vec!["Response".to_string()]);
+    config.time_range = Some((2., 3.));
+    config.payloads = false;
+    let records = record(config, |sim, client, server_id| {
+        for i in 0..5 {
+            client.emit(Request { id: i }, server_id, i as f64);
+        }
+        sim.step_until_no_events();
+    });
+    assert_eq!(summary(&records), vec![(TraceEventKind::Processed, 7, 2.5, "Response")]);
+    assert!(records.iter().all(|r| r.data.is_none()));
+}
+
+#[test]
+fn test_component_filter() {
+    let path = trace_path("components");
+    let mut config = TraceFileConfig::new(&path);
+    config.components = Some(vec!["other".to_string()]);
+    let records = record(config, |sim, client, server_id| {
+        let other = sim.create_context("other");
+        client.emit(Request { id: 0 }, server_id, 1.);
+        other.emit(Request { id: 1 }, server_id, 1.);
+        sim.step_until_no_events();
+    });
+    assert_eq!(records.len(), 4);
+    assert!(records.iter().all(|r| r.src == "other" || r.dst == "other"));
+}
+
+#[test]
+fn test_canceled_events() {
+    let path = trace_path("canceled");
+    let mut config = TraceFileConfig::new(&path);
+    config.kinds = vec![TraceEventKind::Canceled];
+    config.event_types = Some(vec!["Request".to_string(), "TimerFired".to_string()]);
+    let records = record(config, |sim, client, server_id| {
+        let processed = client.emit(Request { id: 0 }, server_id, 1.);
+        client.emit(Request { id: 1 }, server_id, 5.);
+        client.emit(Request { id: 2 }, server_id, 6.);
+        client.set_timer("timeout", 3.);
+        sim.step();
+        // cancellation of processed events is not recorded
+        client.cancel_event(processed);
+        // replaced timer event is canceled
+        client.set_timer("timeout", 4.);
+        client.cancel_events(|event| event.time > 5.5);
+        // repeated cancellation is recorded once
+        client.cancel_events(|event| event.time > 5.5);
+    });
+    assert_eq!(
+        summary(&records),
+        vec![
+            (TraceEventKind::Canceled, 3, 1., "TimerFired"),
+            (TraceEventKind::Canceled, 2, 1., "Request"),
+        ]
+    );
+}
+
+#[test]
+fn test_deferred_event_time() {
+    let path = trace_path("deferred");
+    let records = record(TraceFileConfig::new(&path), |sim, client, server_id| {
+        let first = client.emit(Request { id: 0 }, server_id, 1.);
+        client.emit_after(Request { id: 1 }, server_id, first, 2.);
+        sim.step_until_no_events();
+    });
+    let emitted: Vec<_> = records
+        .iter()
+        .filter(|r| r.kind == TraceEventKind::Emitted && r.type_name == "Request")
+        .map(|r| r.event_time)
+        .collect();
+    assert_eq!(emitted, vec![Some(1.), None]);
+    let processed = records
+        .iter()
+        .find(|r| r.kind == TraceEventKind::Processed && r.id == 1)
+        .unwrap();
+    assert_eq!(processed.time, 3.);
+    assert_eq!(processed.event_time, Some(3.));
+}
+
+#[test]
+fn test_metadata_set_after_enabling() {
+    let path = trace_path("metadata");
+    let (mut sim, client, server_id) = build();
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    sim.set_run_label("scenario", "late");
+    client.emit(Request { id: 1 }, server_id, 1.);
+    sim.step_until_no_events();
+    sim.set_run_label("scenario", "ignored");
+    sim.disable_trace_file();
+    let trace = read_trace_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(trace.metadata.labels["scenario"], "late");
+    assert_eq!(trace.records.len(), 4);
+}
+
+// Records the requests sent at times 1, 2, ..., 10 and returns the path of the trace.
+fn record_requests(config: TraceFileConfig) -> PathBuf {
+    let path = config.path.clone();
+    let (mut sim, client, server_id) = build();
+    sim.enable_trace_file(config);
+    for id in 1..=10 {
+        client.emit(Request { id }, server_id, id as f64);
+    }
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    path
+}
+
+#[test]
+fn test_read_time_range() {
+    let path = record_requests(TraceFileConfig::new(trace_path("range")));
+    let all = read_trace_file(&path).records;
+    let range = read_trace_file_range(&path, 3., 5.5).records;
+    std::fs::remove_file(&path).unwrap();
+    let expected: Vec<_> = all.into_iter().filter(|r| r.time >= 3. && r.time <= 5.5).collect();
+    assert_eq!(range.len(), 9);
+    assert_eq!(range, expected);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compressed_trace() {
+    use simcore::trace_file::TraceFileFormat;
+
+    let plain_path = record_requests(TraceFileConfig::new(trace_path("plain")));
+    let mut config = TraceFileConfig::new(trace_path("compressed"));
+    config.format = TraceFileFormat::Zstd {
+        level: 0,
+        block_records: 4,
+    };
+    let compressed_path = record_requests(config);
+    let plain = read_trace_file(&plain_path).records;
+    let compressed = read_trace_file(&compressed_path).records;
+    let plain_range = read_trace_file_range(&plain_path, 4., 7.).records;
+    let compressed_range = read_trace_file_range(&compressed_path, 4., 7.).records;
+    let empty_range = read_trace_file_range(&compressed_path, 20., 30.).records;
+    let plain_size = std::fs::metadata(&plain_path).unwrap().len();
+    let compressed_size = std::fs::metadata(&compressed_path).unwrap().len();
+    std::fs::remove_file(&plain_path).unwrap();
+    std::fs::remove_file(&compressed_path).unwrap();
+
+    assert_eq!(compressed, plain);
+    assert_eq!(compressed_range, plain_range);
+    assert!(!compressed_range.is_empty());
+    assert!(empty_range.is_empty());
+    assert!(compressed_size < plain_size);
+}
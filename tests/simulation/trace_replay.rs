@@ -0,0 +1,258 @@
+//! Tests of trace replay.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use simcore::replay::{TraceReplay, TraceReplayConfig};
+use simcore::trace_file::{TraceEventKind, TraceFileConfig};
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Request {
+    id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Response {
+    id: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Ping {}
+
+struct Server {
+    ctx: SimulationContext,
+    log: Vec<(f64, String, u64)>,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        let src = self.ctx.lookup_name(event.src);
+        cast!(match event.data {
+            Request { id } => {
+                self.log.push((event.time, src, id));
+                self.ctx.emit(Response { id }, event.src, 0.5);
+            }
+            Ping {} => {
+                self.log.push((event.time, src, 0));
+            }
+        })
+    }
+}
+
+fn trace_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("simcore-replay-{}-{}.jsonl", name, std::process::id()))
+}
+
+fn add_server(sim: &mut Simulation) -> Rc<RefCell<Server>> {
+    let server = Rc::new(RefCell::new(Server {
+        ctx: sim.create_context("server"),
+        log: Vec::new(),
+    }));
+    sim.add_handler("server", server.clone());
+    server
+}
+
+// Records the run with requests of client and pings of monitor, returns the server log.
+fn record(config: TraceFileConfig) -> Vec<(f64, String, u64)> {
+    let mut sim = Simulation::new(123);
+    let server = add_server(&mut sim);
+    let server_id = sim.lookup_id("server");
+    let client = sim.create_context("client");
+    let monitor = sim.create_context("monitor");
+    sim.enable_trace_file(config);
+    for id in 1..=4 {
+        client.emit(Request { id }, server_id, id as f64 * 1.5);
+    }
+    monitor.emit(Ping {}, server_id, 2.);
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+    let log = server.borrow().log.clone();
+    log
+}
+
+fn replay(path: &Path, config: impl FnOnce(&mut TraceReplayConfig)) -> Vec<(f64, String, u64)> {
+    let mut sim = Simulation::new(123);
+    let server = add_server(&mut sim);
+    let mut replay_config = TraceReplayConfig::new(path);
+    config(&mut replay_config);
+    let mut replay = TraceReplay::new(replay_config);
+    replay.register_event::<Request>().register_event::<Ping>();
+    sim.add_trace_replay("replay", replay);
+    sim.step_until_no_events();
+    let log = server.borrow().log.clone();
+    log
+}
+
+#[test]
+fn test_replay_sources() {
+    let path = trace_path("sources");
+    let recorded = record(TraceFileConfig::new(&path));
+    let replayed = replay(&path, |config| {
+        config.sources = Some(vec!["client".to_string(), "monitor".to_string()]);
+    });
+    std::fs::remove_file(&path).unwrap();
+    // the responses of server are not replayed, and the replayed events come from the replay input
+    assert_eq!(replayed.len(), 5);
+    for ((time, _, id), (replayed_time, src, replayed_id)) in recorded.iter().zip(replayed.iter()) {
+        assert_eq!((time, id), (replayed_time, replayed_id));
+        assert_eq!(src, "replay");
+    }
+}
+
+#[test]
+fn test_replay_filters() {
+    let path = trace_path("filters");
+    record(TraceFileConfig::new(&path));
+    let replayed = replay(&path, |config| {
+        config.event_types = Some(vec!["Request".to_string()]);
+        config.time_range = Some((2., 5.));
+        config.time_offset = 10.;
+    });
+    std::fs::remove_file(&path).unwrap();
+    let replayed: Vec<_> = replayed.into_iter().map(|(time, _, id)| (time, id)).collect();
+    assert_eq!(replayed, vec![(13., 2), (14.5, 3)]);
+}
+
+// Replays the requests recorded with the specified version, registering them with version 2 and the migrations.
+fn replay_versioned(name: &str, recorded_version: Option<u32>, migrations: &[u32]) -> Vec<(f64, u64)> {
+    let path = trace_path(name);
+    let mut config = TraceFileConfig::new(&path);
+    if let Some(version) = recorded_version {
+        config.set_event_version::<Request>(version);
+    }
+    record(config);
+
+    let mut sim = Simulation::new(123);
+    let server = add_server(&mut sim);
+    let mut replay_config = TraceReplayConfig::new(&path);
+    replay_config.event_types = Some(vec!["Request".to_string()]);
+    let mut replay = TraceReplay::new(replay_config);
+    replay.register_event_version::<Request>(2);
+    for &from_version in migrations {
+        // version 1 multiplies the identifiers and version 2 increments them
+        let convert: fn(u64) -> u64 = if from_version == 0 { |id| id * 10 } else { |id| id + 1 };
+        replay.add_migration(
+            "Request",
+            from_version,
+            move |data| json!({"id": convert(data["id"].as_u64().unwrap())}),
+        );
+    }
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        sim.add_trace_replay("replay", replay);
+        sim.step_until_no_events();
+    }));
+    std::fs::remove_file(&path).unwrap();
+    if let Err(e) = result {
+        std::panic::resume_unwind(e);
+    }
+    let log = server.borrow().log.iter().map(|(time, _, id)| (*time, *id)).collect();
+    log
+}
+
+#[test]
+fn test_migrations() {
+    // the trace without versions has version 0, and the migrations are applied in the order of versions
+    let replayed = replay_versioned("migrations", None, &[1, 0]);
+    assert_eq!(replayed, vec![(1.5, 11), (3., 21), (4.5, 31), (6., 41)]);
+    let replayed = replay_versioned("migrations-v1", Some(1), &[0, 1]);
+    assert_eq!(replayed, vec![(1.5, 2), (3., 3), (4.5, 4), (6., 5)]);
+    let replayed = replay_versioned("migrations-v2", Some(2), &[]);
+    assert_eq!(replayed, vec![(1.5, 1), (3., 2), (4.5, 3), (6., 4)]);
+}
+
+#[test]
+#[should_panic(expected = "Migration of event type Request from version 1 is not added")]
+fn test_missing_migration() {
+    replay_versioned("missing-migration", Some(0), &[0]);
+}
+
+#[test]
+#[should_panic(expected = "Recorded version 3 of event type Request is newer than the registered version 2")]
+fn test_newer_recorded_version() {
+    replay_versioned("newer-version", Some(3), &[]);
+}
+
+#[test]
+#[should_panic(expected = "Event type Response from trace is not registered")]
+fn test_unregistered_type() {
+    let path = trace_path("unregistered");
+    record(TraceFileConfig::new(&path));
+    let result = std::panic::catch_unwind(|| replay(&path, |_| {}));
+    std::fs::remove_file(&path).unwrap();
+    std::panic::resume_unwind(result.unwrap_err());
+}
+
+#[test]
+#[should_panic(expected = "has no payload")]
+fn test_no_payloads() {
+    let path = trace_path("payloads");
+    let mut config = TraceFileConfig::new(&path);
+    config.kinds = vec![TraceEventKind::Processed];
+    config.payloads = false;
+    record(config);
+    let result = std::panic::catch_unwind(|| replay(&path, |_| {}));
+    std::fs::remove_file(&path).unwrap();
+    std::panic::resume_unwind(result.unwrap_err());
+}
+
+#[test]
+#[should_panic(expected = "Destination component server of replayed event does not exist")]
+fn test_missing_destination() {
+    let path = trace_path("destination");
+    record(TraceFileConfig::new(&path));
+    let mut sim = Simulation::new(123);
+    let mut replay = TraceReplay::new(TraceReplayConfig::new(&path));
+    replay.register_event::<Request>().register_event::<Ping>();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        sim.add_trace_replay("replay", replay);
+    }));
+    std::fs::remove_file(&path).unwrap();
+    std::panic::resume_unwind(result.unwrap_err());
+}
+
+#[test]
+fn test_replay_from_checkpoint() {
+    let path = trace_path("checkpoint");
+    record(TraceFileConfig::new(&path));
+    let build = || {
+        let mut sim = Simulation::new(123);
+        sim.register_checkpoint_event::<Request>();
+        sim.register_checkpoint_event::<Response>();
+        sim.register_checkpoint_event::<Ping>();
+        let server = add_server(&mut sim);
+        let mut config = TraceReplayConfig::new(&path);
+        config.sources = Some(vec!["client".to_string(), "monitor".to_string()]);
+        let mut replay = TraceReplay::new(config);
+        replay.register_event::<Request>().register_event::<Ping>();
+        sim.add_trace_replay("replay", replay);
+        (sim, server)
+    };
+
+    let (mut sim, server) = build();
+    sim.step_until_time(3.2);
+    let checkpoint = sim.checkpoint();
+    sim.step_until_no_events();
+
+    let (mut restored, restored_server) = build();
+    restored.restore_checkpoint(&checkpoint);
+    restored.step_until_no_events();
+    std::fs::remove_file(&path).unwrap();
+
+    // the events replayed before the checkpoint are skipped
+    let expected: Vec<_> = server
+        .borrow()
+        .log
+        .iter()
+        .filter(|(time, _, _)| *time > 3.2)
+        .cloned()
+        .collect();
+    assert_eq!(expected.len(), 2);
+    assert_eq!(restored_server.borrow().log, expected);
+    assert_eq!(restored.time(), sim.time());
+    assert_eq!(restored.event_count(), sim.event_count());
+}
@@ -0,0 +1,160 @@
+//! Tests of sampling events recorded in the in-memory trace.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::trace::TraceSampling;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Packet {
+    seq: u32,
+}
+
+#[derive(Clone, Serialize)]
+struct Heartbeat {}
+
+fn run(seed: u64, sampling: Option<TraceSampling>) -> (Vec<u64>, Vec<f64>) {
+    let mut sim = Simulation::new(seed);
+    sim.enable_memory_trace();
+    if let Some(sampling) = sampling {
+        sim.set_trace_sampling(sampling);
+    }
+    let ctx = sim.create_context("node");
+    let mut random = Vec::new();
+    for seq in 0..1000 {
+        ctx.emit_self(Packet { seq }, ctx.gen_range(0.0..100.0));
+    }
+    sim.step_until_no_events();
+    for _ in 0..10 {
+        random.push(sim.rand());
+    }
+    let ids = sim.trace().records().iter().map(|record| record.id).collect();
+    (ids, random)
+}
+
+#[test]
+fn test_probability_sampling_is_deterministic() {
+    let (ids, random) = run(123, Some(TraceSampling::Probability(0.2)));
+    assert!(ids.len() > 150 && ids.len() < 250, "{}", ids.len());
+    assert_eq!(run(123, Some(TraceSampling::Probability(0.2))).0, ids);
+    assert_ne!(run(456, Some(TraceSampling::Probability(0.2))).0, ids);
+
+    // sampling does not affect the random numbers generated by the simulation
+    let (all_ids, all_random) = run(123, None);
+    assert_eq!(all_ids.len(), 1000);
+    assert_eq!(random, all_random);
+    assert!(ids.iter().all(|id| all_ids.contains(id)));
+}
+
+#[test]
+fn test_extreme_probabilities() {
+    assert_eq!(run(123, Some(TraceSampling::Probability(1.))).0.len(), 1000);
+    assert_eq!(run(123, Some(TraceSampling::Probability(0.))).0.len(), 0);
+}
+
+#[test]
+fn test_sampling_by_event_type() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    sim.set_trace_sampling(TraceSampling::EveryNth(10));
+    sim.set_event_trace_sampling::<Heartbeat>(TraceSampling::Disabled);
+
+    let ctx = sim.create_context("node");
+    for seq in 0..25 {
+        ctx.emit_self(Packet { seq }, seq as f64);
+        ctx.emit_self(Heartbeat {}, seq as f64);
+    }
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    let packets: Vec<_> = trace
+        .records()
+        .iter()
+        .map(|record| record.data.downcast_ref::<Packet>().unwrap().seq)
+        .collect();
+    assert_eq!(packets, vec![0, 10, 20]);
+    assert_eq!(trace.query().event_type::<Heartbeat>().count(), 0);
+    assert_eq!(trace.skipped(), 47);
+}
+
+#[test]
+fn test_sampling_change_during_run() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    let ctx = sim.create_context("node");
+    for seq in 0..10 {
+        ctx.emit_self(Packet { seq }, seq as f64);
+    }
+
+    sim.step_until_time(4.5);
+    sim.set_trace_sampling(TraceSampling::Disabled);
+    sim.step_until_time(7.5);
+    sim.set_trace_sampling(TraceSampling::All);
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    let times: Vec<_> = trace.records().iter().map(|record| record.time).collect();
+    assert_eq!(times, vec![0., 1., 2., 3., 4., 8., 9.]);
+    assert_eq!(trace.skipped(), 3);
+}
+
+struct Monitor {
+    ctx: SimulationContext,
+}
+
+impl EventHandler for Monitor {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Heartbeat {} => {
+                self.ctx.emit_self(Packet { seq: 0 }, 1.);
+            }
+            Packet { .. } => {}
+        })
+    }
+}
+
+#[test]
+fn test_parents_of_skipped_events() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    sim.set_event_trace_sampling::<Heartbeat>(TraceSampling::Disabled);
+    let monitor = Monitor {
+        ctx: sim.create_context("node"),
+    };
+    let node_id = sim.add_handler("node", Rc::new(RefCell::new(monitor)));
+    let heartbeat = sim.create_context("client").emit(Heartbeat {}, node_id, 1.);
+    sim.step_until_no_events();
+
+    let trace = sim.trace();
+    assert!(trace.get(heartbeat).is_none());
+    // emitted while processing the skipped event
+    let packet = &trace.records()[0];
+    assert_eq!(packet.parent, Some(heartbeat));
+    assert_eq!(trace.causes(packet.id).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Sampling probability must be in [0, 1]")]
+fn test_invalid_probability() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    sim.set_trace_sampling(TraceSampling::Probability(1.5));
+}
+
+#[test]
+#[should_panic(expected = "Sampling interval must be positive")]
+fn test_zero_interval() {
+    let mut sim = Simulation::new(123);
+    sim.enable_memory_trace();
+    sim.set_event_trace_sampling::<Packet>(TraceSampling::EveryNth(0));
+}
+
+#[test]
+#[should_panic(expected = "Memory trace is not enabled")]
+fn test_sampling_without_trace() {
+    let mut sim = Simulation::new(123);
+    sim.set_trace_sampling(TraceSampling::EveryNth(2));
+}
@@ -0,0 +1,143 @@
+//! Tests of typed simulation time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use simcore::time::{SimDuration, SimTime};
+use simcore::timer::TimerFired;
+use simcore::{cast, Event, EventHandler, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Request {
+    deadline: SimTime,
+    timeout: SimDuration,
+}
+
+struct Server {
+    ctx: SimulationContext,
+    log: Vec<(String, SimTime)>,
+}
+
+impl EventHandler for Server {
+    fn on(&mut self, event: Event) {
+        let time = event.sim_time();
+        cast!(match event.data {
+            Request { deadline, timeout } => {
+                self.log.push(("request".to_owned(), time));
+                if timeout == SimDuration::ZERO {
+                    return;
+                }
+                self.ctx.set_timer_in("timeout", timeout);
+                self.ctx.emit_at_time(
+                    Request {
+                        deadline,
+                        timeout: SimDuration::ZERO,
+                    },
+                    self.ctx.id(),
+                    deadline,
+                );
+            }
+            TimerFired { name } => {
+                self.log.push((name, time));
+            }
+        })
+    }
+}
+
+#[test]
+fn test_unit_conversions() {
+    assert_eq!(SimDuration::from_millis(1500.), SimDuration::from_secs(1.5));
+    assert_eq!(SimDuration::from_micros(250.).as_secs(), 0.00025);
+    assert_eq!(SimDuration::from_nanos(1e6), SimDuration::from_millis(1.));
+    assert_eq!(SimDuration::from_secs(2.).as_millis(), 2000.);
+    assert_eq!(SimDuration::from_secs(2.).as_micros(), 2e6);
+    assert_eq!(SimDuration::from_secs(2.).as_nanos(), 2e9);
+    assert_eq!(
+        SimDuration::from(std::time::Duration::from_millis(20)),
+        SimDuration::from_millis(20.)
+    );
+}
+
+#[test]
+fn test_arithmetic() {
+    let mut time = SimTime::from_secs(3.);
+    time += SimDuration::from_secs(2.);
+    assert_eq!(time, SimTime::from_secs(5.));
+    time -= SimDuration::from_secs(1.);
+    assert_eq!(time - SimDuration::from_secs(4.), SimTime::ZERO);
+    assert_eq!(time.since(SimTime::from_secs(1.)), SimDuration::from_secs(3.));
+
+    let mut delay = SimDuration::from_secs(1.) + SimDuration::from_secs(2.);
+    delay -= SimDuration::from_secs(0.5);
+    assert_eq!(delay, SimDuration::from_secs(2.5));
+    assert_eq!(delay * 2., 2. * delay);
+    assert_eq!(delay / 5., SimDuration::from_secs(0.5));
+    assert_eq!(delay / SimDuration::from_secs(0.5), 5.);
+    assert_eq!(format!("{} {}", time, delay), "4 2.5");
+}
+
+#[test]
+#[should_panic(expected = "Simulation duration must be non-negative, got -1")]
+fn test_negative_duration() {
+    let _ = SimTime::from_secs(1.) - SimTime::from_secs(2.);
+}
+
+#[test]
+#[should_panic(expected = "Simulation time must be non-negative, got -0.5")]
+fn test_negative_time() {
+    let _ = SimTime::from_secs(1.) - SimDuration::from_secs(1.5);
+}
+
+#[test]
+fn test_serialization() {
+    let request = Request {
+        deadline: SimTime::from_secs(1.5),
+        timeout: SimDuration::from_millis(250.),
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(json, r#"{"deadline":1.5,"timeout":0.25}"#);
+    let decoded: Request = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.deadline, request.deadline);
+    assert_eq!(decoded.timeout, request.timeout);
+}
+
+#[test]
+fn test_typed_apis() {
+    let mut sim = Simulation::new(123);
+    let server = Rc::new(RefCell::new(Server {
+        ctx: sim.create_context("server"),
+        log: Vec::new(),
+    }));
+    sim.add_handler("server", server.clone());
+    let client = sim.create_context("client");
+    client.emit_in(
+        Request {
+            deadline: SimTime::from_secs(5.),
+            timeout: SimDuration::from_millis(500.),
+        },
+        server.borrow().ctx.id(),
+        SimDuration::from_secs(1.),
+    );
+
+    assert!(sim.step_for(SimDuration::from_secs(2.)));
+    assert_eq!(sim.now(), SimTime::from_secs(2.));
+    sim.step_until_no_events();
+    assert_eq!(client.now(), SimTime::from_secs(5.));
+
+    let log: Vec<_> = server
+        .borrow()
+        .log
+        .iter()
+        .map(|(name, time)| (name.clone(), time.as_secs()))
+        .collect();
+    assert_eq!(
+        log,
+        vec![
+            ("request".to_owned(), 1.),
+            ("timeout".to_owned(), 1.5),
+            ("request".to_owned(), 5.)
+        ]
+    );
+}
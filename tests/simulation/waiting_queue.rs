@@ -0,0 +1,100 @@
+//! Tests of waiting queues.
+
+use simcore::waiting_queue::{QueueDiscipline, QueueStats, WaitingQueue};
+use simcore::Simulation;
+
+fn advance(sim: &mut Simulation, delay: f64) {
+    sim.step_until_time(sim.time() + delay);
+}
+
+#[test]
+fn test_queue_disciplines() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+
+    let mut fifo = WaitingQueue::new(&ctx, "fifo", QueueDiscipline::Fifo);
+    let mut lifo = WaitingQueue::new(&ctx, "lifo", QueueDiscipline::Lifo);
+    let mut priority = WaitingQueue::new(&ctx, "priority", QueueDiscipline::Priority);
+    for (item, prio) in [(1, 2), (2, 1), (3, 2), (4, 0)] {
+        fifo.push(item);
+        lifo.push(item);
+        priority.push_with_priority(item, prio);
+    }
+    priority.push(5);
+
+    assert_eq!(fifo.peek(), Some(&1));
+    assert_eq!(lifo.iter().copied().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+    let drain = |queue: &mut WaitingQueue<i32>| std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>();
+    assert_eq!(drain(&mut fifo), vec![1, 2, 3, 4]);
+    assert_eq!(drain(&mut lifo), vec![4, 3, 2, 1]);
+    assert_eq!(drain(&mut priority), vec![4, 5, 2, 1, 3]);
+    assert!(fifo.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Priorities are supported only by priority queue")]
+fn test_priority_in_fifo_queue() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let mut queue = WaitingQueue::new(&ctx, "queue", QueueDiscipline::Fifo);
+    queue.push_with_priority(1, 1);
+}
+
+#[test]
+fn test_queue_stats() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let mut queue = WaitingQueue::new(&ctx, "queue", QueueDiscipline::Fifo);
+    assert_eq!(queue.stats(), QueueStats::default());
+
+    queue.push("a");
+    queue.push("b");
+    advance(&mut sim, 2.);
+    queue.push("c");
+    advance(&mut sim, 1.);
+    assert_eq!(queue.pop(), Some("a"));
+    advance(&mut sim, 3.);
+    // c reneges
+    assert_eq!(queue.remove_if(|item| *item == "c"), vec!["c"]);
+    advance(&mut sim, 4.);
+    assert_eq!(queue.pop(), Some("b"));
+    advance(&mut sim, 10.);
+
+    let stats = queue.stats();
+    // lengths: 2 for 2, 3 for 1, 2 for 3, 1 for 4, 0 for 10
+    assert_eq!(stats.mean_length, (4. + 3. + 6. + 4.) / 20.);
+    assert_eq!(stats.max_length, 3);
+    assert_eq!(stats.enqueued, 3);
+    assert_eq!(stats.dequeued, 3);
+    assert_eq!(stats.mean_wait_time, (3. + 4. + 10.) / 3.);
+    assert_eq!(stats.max_wait_time, 10.);
+}
+
+#[test]
+fn test_queue_stats_reset() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    let mut queue = WaitingQueue::new(&ctx, "queue", QueueDiscipline::Fifo);
+
+    queue.push("a");
+    queue.push("b");
+    queue.push("c");
+    advance(&mut sim, 2.);
+    assert_eq!(queue.pop(), Some("a"));
+    queue.reset_stats();
+    advance(&mut sim, 2.);
+    assert_eq!(queue.pop(), Some("b"));
+    advance(&mut sim, 2.);
+
+    assert_eq!(
+        queue.stats(),
+        QueueStats {
+            mean_length: 1.5,
+            max_length: 2,
+            enqueued: 0,
+            dequeued: 1,
+            mean_wait_time: 4.,
+            max_wait_time: 4.,
+        }
+    );
+}
@@ -0,0 +1,270 @@
+//! Tests of warmup detection.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::rate_limit::{OutputKind, OutputRateLimit};
+use simcore::trace_file::TraceFileConfig;
+use simcore::waiting_queue::{QueueDiscipline, WaitingQueue};
+use simcore::warmup::{mser, ResetStats, WarmupDetector, MSER_BATCH_SIZE};
+use simcore::{Event, EventHandler, Simulation, SimulationContext};
+
+// Series with exponential decay of initial bias and periodic noise.
+fn transient_series(len: usize, bias: f64, decay: f64) -> Vec<f64> {
+    (0..len)
+        .map(|i| 10. + bias * (-(i as f64) / decay).exp() + ((i * 7) % 11) as f64 / 10.)
+        .collect()
+}
+
+#[test]
+fn test_mser_detects_truncation_point() {
+    let values = transient_series(1000, 50., 20.);
+    let truncation = mser(&values, MSER_BATCH_SIZE).unwrap();
+    assert_eq!(truncation % MSER_BATCH_SIZE, 0);
+    assert!((50..=150).contains(&truncation), "truncation {}", truncation);
+
+    // larger initial bias requires longer truncation
+    let larger = mser(&transient_series(1000, 50., 60.), MSER_BATCH_SIZE).unwrap();
+    assert!(larger > truncation);
+}
+
+#[test]
+fn test_mser_without_bias() {
+    let values: Vec<f64> = (0..500).map(|i| ((i * 7) % 11) as f64).collect();
+    assert!(mser(&values, MSER_BATCH_SIZE).unwrap() < 50);
+    assert_eq!(mser(&[1.; 100], MSER_BATCH_SIZE), Some(0));
+}
+
+#[test]
+fn test_mser_without_steady_state() {
+    let values: Vec<f64> = (0..1000).map(|i| (i as f64).sqrt()).collect();
+    assert_eq!(mser(&values, MSER_BATCH_SIZE), None);
+    // less than two batches
+    assert_eq!(mser(&[1., 2., 3., 4., 5., 6.], MSER_BATCH_SIZE), None);
+    assert_eq!(mser(&[], MSER_BATCH_SIZE), None);
+}
+
+#[test]
+fn test_detector_uses_slowest_metric() {
+    let mut detector = WarmupDetector::new();
+    let fast = transient_series(1000, 50., 10.);
+    let slow = transient_series(1000, 50., 50.);
+    for i in 0..1000 {
+        detector.record("fast", i as f64, fast[i]);
+        detector.record("slow", i as f64, slow[i]);
+    }
+    assert_eq!(detector.metrics().collect::<Vec<_>>(), vec!["fast", "slow"]);
+
+    let fast_warmup = detector.metric_warmup("fast").unwrap();
+    let slow_warmup = detector.metric_warmup("slow").unwrap();
+    assert_eq!(fast_warmup.observations, 1000);
+    assert_eq!(fast_warmup.time, Some(fast_warmup.truncation.unwrap() as f64));
+    assert!(slow_warmup.time.unwrap() > fast_warmup.time.unwrap());
+    assert_eq!(detector.warmup_time(), slow_warmup.time);
+    assert!(detector.metric_warmup("unknown").is_none());
+}
+
+#[test]
+fn test_detector_requires_all_metrics_and_min_observations() {
+    let mut detector = WarmupDetector::new().with_min_observations(200).with_check_interval(10);
+    assert_eq!(detector.warmup_time(), None);
+    let values = transient_series(150, 50., 5.);
+    for (i, value) in values.iter().enumerate() {
+        detector.record("steady", i as f64, *value);
+        detector.record("growing", i as f64, i as f64);
+    }
+    assert_eq!(detector.metric_warmup("steady").unwrap().truncation, None);
+    assert_eq!(detector.warmup_time(), None);
+    assert!(!detector.is_steady());
+}
+
+#[test]
+fn test_steady_state_resets_statistics() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("server");
+    let queue = Rc::new(RefCell::new(WaitingQueue::new(&ctx, "queue", QueueDiscipline::Fifo)));
+
+    let mut detector = WarmupDetector::new().with_check_interval(50);
+    let reset_queue = queue.clone();
+    let resets = Rc::new(RefCell::new(Vec::new()));
+    let reset_times = resets.clone();
+    detector.on_steady_state(move |time| {
+        reset_queue.borrow_mut().reset_stats();
+        reset_times.borrow_mut().push(time);
+    });
+
+    // the queue fills up during the first 100 time units and then oscillates around 100 items
+    for i in 0..1000 {
+        sim.step_until_time(i as f64);
+        if i < 100 || i % 2 == 0 {
+            queue.borrow_mut().push(i);
+        } else {
+            queue.borrow_mut().pop();
+            queue.borrow_mut().pop();
+            queue.borrow_mut().push(i);
+        }
+        let len = queue.borrow().len() as f64;
+        detector.record("queue_length", sim.time(), len);
+    }
+
+    assert!(detector.is_steady());
+    let steady_time = detector.steady_time().unwrap();
+    assert!((95. ..=200.).contains(&steady_time), "steady time {}", steady_time);
+    // the callback is called once
+    assert_eq!(*resets.borrow(), vec![steady_time]);
+
+    let stats = queue.borrow_mut().stats();
+    assert!(stats.enqueued < 1000 - 100);
+    assert!(stats.max_length <= 101);
+    assert!(stats.mean_length >= 99. && stats.mean_length <= 101.);
+}
+
+#[test]
+#[should_panic(expected = "Observation time 1 is earlier than the previous observation time 2 of metric latency")]
+fn test_unordered_observations_panic() {
+    let mut detector = WarmupDetector::new();
+    detector.record("latency", 2., 1.);
+    detector.record("latency", 1., 1.);
+}
+
+#[derive(Clone, Serialize)]
+struct Tick {}
+
+struct Ticker {
+    ctx: SimulationContext,
+    ticks: Vec<f64>,
+}
+
+impl EventHandler for Ticker {
+    fn on(&mut self, event: Event) {
+        self.ticks.push(event.time);
+        self.ctx.emit_self(Tick {}, 1.);
+    }
+}
+
+impl ResetStats for Ticker {
+    fn reset_stats(&mut self) {
+        self.ticks.clear();
+    }
+}
+
+fn ticker_sim() -> (Simulation, Rc<RefCell<Ticker>>) {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("ticker");
+    ctx.emit_self(Tick {}, 0.);
+    let ticker = Rc::new(RefCell::new(Ticker { ctx, ticks: Vec::new() }));
+    sim.add_handler("ticker", ticker.clone());
+    (sim, ticker)
+}
+
+#[test]
+fn test_warmup_time_resets_statistics() {
+    let (mut sim, ticker) = ticker_sim();
+    let queue_ctx = sim.create_context("queue");
+    let queue = Rc::new(RefCell::new(WaitingQueue::new(
+        &queue_ctx,
+        "queue",
+        QueueDiscipline::Fifo,
+    )));
+    queue.borrow_mut().push(1);
+    sim.add_warmup_reset(ticker.clone());
+    sim.add_warmup_reset(queue.clone());
+    let end_times = Rc::new(RefCell::new(Vec::new()));
+    let end_times_ = end_times.clone();
+    sim.on_warmup_end(move |time| end_times_.borrow_mut().push(time));
+    sim.set_warmup_time(5.);
+    assert_eq!(sim.warmup_time(), Some(5.));
+
+    sim.step_until_time(4.5);
+    assert_eq!(sim.processed_event_count(), 5);
+    assert_eq!(sim.warmup_end_time(), None);
+
+    // the run result counts all events of the run
+    let result = sim.run_until_time(8.5);
+    assert_eq!(result.processed_events, 4);
+    assert_eq!(sim.warmup_end_time(), Some(5.));
+    assert_eq!(*end_times.borrow(), vec![5.]);
+    // the event at the warmup time is processed after the reset
+    assert_eq!(ticker.borrow().ticks, vec![5., 6., 7., 8.]);
+    assert_eq!(sim.processed_event_count(), 4);
+    assert_eq!(sim.event_count(), 4);
+    let stats = queue.borrow_mut().stats();
+    assert_eq!(stats.enqueued, 0);
+    assert_eq!(stats.mean_length, 1.);
+}
+
+#[test]
+fn test_warmup_ends_between_events() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("comp");
+    ctx.emit_self(Tick {}, 1.);
+    ctx.emit_self(Tick {}, 10.);
+    let end_times = Rc::new(RefCell::new(Vec::new()));
+    let end_times_ = end_times.clone();
+    sim.on_warmup_end(move |time| end_times_.borrow_mut().push(time));
+    sim.set_warmup_time(5.);
+
+    sim.step();
+    sim.step();
+    // the time is set to the warmup time before the reset
+    assert_eq!(*end_times.borrow(), vec![5.]);
+    assert_eq!(sim.time(), 10.);
+    assert_eq!(sim.processed_event_count(), 1);
+
+    // the warmup period ends if the simulation is advanced past it without events
+    let mut sim = Simulation::new(123);
+    sim.set_warmup_time(3.);
+    sim.step_until_time(2.);
+    assert_eq!(sim.warmup_end_time(), None);
+    sim.step_until_time(7.);
+    assert_eq!(sim.warmup_end_time(), Some(3.));
+    assert_eq!(sim.time(), 7.);
+}
+
+#[test]
+fn test_warmup_resets_suppressed_output() {
+    let (mut sim, _ticker) = ticker_sim();
+    let path = std::env::temp_dir().join(format!("simcore-warmup-{}.jsonl", std::process::id()));
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    sim.set_output_rate_limit(Some(OutputRateLimit::new(10., 4)));
+    sim.set_warmup_time(10.);
+    sim.step_until_time(9.5);
+    assert!(sim.suppressed_output(OutputKind::TraceFile) > 0);
+    sim.step_until_time(11.5);
+    sim.disable_trace_file();
+    std::fs::remove_file(&path).unwrap();
+    // the emitted and processed records at times 10 and 11 are within the limit
+    assert_eq!(sim.suppressed_output(OutputKind::TraceFile), 0);
+}
+
+#[test]
+fn test_manual_warmup_end() {
+    let (mut sim, ticker) = ticker_sim();
+    sim.add_warmup_reset(ticker.clone());
+    sim.step_until_time(3.5);
+    sim.end_warmup();
+    assert_eq!(sim.warmup_end_time(), Some(3.5));
+    assert_eq!(sim.warmup_time(), None);
+    assert!(ticker.borrow().ticks.is_empty());
+    sim.step_until_time(5.5);
+    assert_eq!(ticker.borrow().ticks, vec![4., 5.]);
+    assert_eq!(sim.processed_event_count(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Warmup period has already ended")]
+fn test_warmup_ended_twice() {
+    let mut sim = Simulation::new(123);
+    sim.end_warmup();
+    sim.set_warmup_time(10.);
+}
+
+#[test]
+#[should_panic(expected = "Warmup time 1 is earlier than the current time 2")]
+fn test_warmup_time_in_past() {
+    let mut sim = Simulation::new(123);
+    sim.step_until_time(2.);
+    sim.set_warmup_time(1.);
+}
@@ -0,0 +1,158 @@
+//! Tests of watchpoints on component state.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Add {
+    value: i64,
+}
+
+struct Counter {
+    value: i64,
+}
+
+impl EventHandler for Counter {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            Add { value } => {
+                self.value += value;
+            }
+        })
+    }
+}
+
+fn build(values: &[i64]) -> (Simulation, SimulationContext, Rc<RefCell<Counter>>, Id) {
+    let mut sim = Simulation::new(123);
+    let counter = Rc::new(RefCell::new(Counter { value: 0 }));
+    let counter_id = sim.add_handler("counter", counter.clone());
+    let ctx = sim.create_context("client");
+    for (i, value) in values.iter().enumerate() {
+        ctx.emit(Add { value: *value }, counter_id, (i + 1) as f64);
+    }
+    (sim, ctx, counter, counter_id)
+}
+
+#[test]
+fn test_pauses_step_until_no_events() {
+    let (mut sim, _ctx, counter, counter_id) = build(&[5, 10, -20, 30]);
+    let watchpoint = sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    assert_eq!(counter.borrow().value, 15);
+    let hits = sim.take_watchpoint_hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].watchpoint, watchpoint);
+    assert_eq!(hits[0].component, counter_id);
+    assert_eq!(hits[0].time, 2.);
+    assert!(sim.take_watchpoint_hits().is_empty());
+
+    // the condition becomes false and then true again
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+    assert_eq!(counter.borrow().value, 25);
+    assert_eq!(sim.take_watchpoint_hits().len(), 1);
+}
+
+#[test]
+fn test_triggers_only_on_transition_to_true() {
+    let (mut sim, _ctx, counter, _) = build(&[5, 10, 1, 1]);
+    sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    assert_eq!(sim.take_watchpoint_hits().len(), 1);
+    // the condition remains true, so the simulation is not paused again
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+    assert!(sim.take_watchpoint_hits().is_empty());
+}
+
+#[test]
+fn test_condition_true_at_registration() {
+    let (mut sim, _ctx, counter, _) = build(&[1, 1]);
+    counter.borrow_mut().value = 100;
+    sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    assert!(sim.take_watchpoint_hits().is_empty());
+}
+
+#[test]
+fn test_pauses_step_until_time() {
+    let (mut sim, _ctx, counter, _) = build(&[5, 10, 1, 1]);
+    sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    assert!(sim.step_until_time(10.));
+    // the clock is not moved to the requested time
+    assert_eq!(sim.time(), 2.);
+    assert_eq!(sim.take_watchpoint_hits().len(), 1);
+
+    assert!(!sim.step_until_time(10.));
+    assert_eq!(sim.time(), 10.);
+    assert_eq!(counter.borrow().value, 17);
+}
+
+#[test]
+fn test_pauses_steps() {
+    let (mut sim, _ctx, counter, _) = build(&[5, 10, 1, 1]);
+    sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    assert!(sim.steps(4));
+    assert_eq!(sim.time(), 2.);
+    assert_eq!(sim.take_watchpoint_hits().len(), 1);
+}
+
+#[test]
+fn test_ignores_events_to_other_components() {
+    let (mut sim, ctx, counter, _) = build(&[]);
+    let other = Rc::new(RefCell::new(Counter { value: 0 }));
+    let other_id = sim.add_handler("other", other.clone());
+    sim.add_watchpoint("counter", counter.clone(), |_| true);
+    // the watchpoint is evaluated only after events delivered to the watched component
+    counter.borrow_mut().value = 1;
+    ctx.emit(Add { value: 100 }, other_id, 1.);
+
+    sim.step_until_no_events();
+    assert!(sim.take_watchpoint_hits().is_empty());
+    assert_eq!(other.borrow().value, 100);
+}
+
+#[test]
+fn test_callback_does_not_pause() {
+    let (mut sim, _ctx, counter, _) = build(&[5, 10, -20, 30]);
+    let hits = Rc::new(RefCell::new(Vec::new()));
+    let hits_clone = hits.clone();
+    sim.add_watchpoint_callback(
+        "counter",
+        counter.clone(),
+        |c| c.value > 10,
+        move |hit| hits_clone.borrow_mut().push(hit.time),
+    );
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+    assert_eq!(*hits.borrow(), vec![2., 4.]);
+    assert!(sim.take_watchpoint_hits().is_empty());
+}
+
+#[test]
+fn test_remove_watchpoint() {
+    let (mut sim, _ctx, counter, _) = build(&[5, 10, -20, 30]);
+    let watchpoint = sim.add_watchpoint("counter", counter.clone(), |c| c.value > 10);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 2.);
+    sim.take_watchpoint_hits();
+    sim.remove_watchpoint(watchpoint);
+
+    sim.step_until_no_events();
+    assert_eq!(sim.time(), 4.);
+    assert!(sim.take_watchpoint_hits().is_empty());
+}
@@ -0,0 +1,293 @@
+//! Tests of workload recording and synthesis.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::generator::{ArrivalGenerator, TargetPolicy};
+use simcore::trace_file::{read_trace_file, TraceFileConfig};
+use simcore::workload::{FittedDistribution, Workload, WorkloadModel, WorkloadRecorder};
+use simcore::{Id, Simulation, SimulationContext};
+
+#[derive(Clone, Serialize)]
+struct Request {
+    size: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct Other {}
+
+fn sample(n: usize, seed: u64, f: impl Fn(&SimulationContext) -> f64) -> Vec<f64> {
+    let mut sim = Simulation::new(seed);
+    let ctx = sim.create_context("sampler");
+    (0..n).map(|_| f(&ctx)).collect()
+}
+
+fn generate(sim: &mut Simulation, server_id: Id, count: u64) {
+    let generator = ArrivalGenerator::new(sim.create_context("generator"), |ctx, _| Request {
+        size: ctx.sample_weibull(2., 1.5),
+    })
+    .with_exponential_interarrival(4.)
+    .with_max_arrivals(count)
+    .with_targets(TargetPolicy::Single(server_id));
+    let generator = Rc::new(RefCell::new(generator));
+    sim.add_handler("generator", generator.clone());
+    generator.borrow_mut().start();
+}
+
+#[test]
+fn test_workload_from_arrivals() {
+    let workload = Workload::from_arrivals(&[(1., Some(10.)), (1.5, Some(20.)), (3., Some(30.))]);
+    assert_eq!(workload.interarrivals, vec![0.5, 1.5]);
+    assert_eq!(workload.sizes, vec![10., 20., 30.]);
+    assert_eq!(workload.arrival_count(), 3);
+
+    let workload = Workload::from_arrivals(&[(1., None), (2., None)]);
+    assert!(workload.sizes.is_empty());
+    assert_eq!(workload.arrival_count(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Sizes must be specified for all arrivals")]
+fn test_workload_from_arrivals_partial_sizes() {
+    Workload::from_arrivals(&[(1., Some(10.)), (2., None)]);
+}
+
+#[test]
+fn test_recorder() {
+    let mut sim = Simulation::new(123);
+    let server_id = sim.create_context("server").id();
+    let other_id = sim.create_context("other").id();
+    let client = sim.create_context("client");
+    let recorder = Rc::new(RefCell::new(
+        WorkloadRecorder::new::<Request>()
+            .to(server_id)
+            .with_size(|request: &Request| request.size),
+    ));
+    sim.add_time_advance_listener(recorder.clone());
+
+    client.emit(Request { size: 1. }, server_id, 1.);
+    client.emit(Request { size: 2. }, server_id, 1.);
+    client.emit(Request { size: 3. }, other_id, 2.);
+    client.emit(Other {}, server_id, 3.);
+    client.emit(Request { size: 4. }, server_id, 4.5);
+    sim.step_until_no_events();
+
+    let workload = recorder.borrow().workload().clone();
+    assert_eq!(workload.interarrivals, vec![0., 3.5]);
+    assert_eq!(workload.sizes, vec![1., 2., 4.]);
+}
+
+#[test]
+#[should_panic(expected = "Size function must accept the recorded event type")]
+fn test_recorder_size_of_other_type() {
+    WorkloadRecorder::new::<Request>().with_size(|_: &Other| 1.);
+}
+
+#[test]
+fn test_fit_constant() {
+    assert_eq!(FittedDistribution::fit(&[2., 2., 2.]), FittedDistribution::Constant(2.));
+}
+
+#[test]
+fn test_fit_exponential() {
+    let values = sample(5000, 123, |ctx| ctx.sample_exponential(2.));
+    match FittedDistribution::fit(&values) {
+        FittedDistribution::Exponential { rate } => assert!((rate - 2.).abs() < 0.1),
+        dist => panic!("Unexpected distribution {:?}", dist),
+    }
+}
+
+#[test]
+fn test_fit_lognormal() {
+    let values = sample(5000, 123, |ctx| ctx.sample_lognormal(1., 0.5));
+    match FittedDistribution::fit(&values) {
+        FittedDistribution::LogNormal { mu, sigma } => {
+            assert!((mu - 1.).abs() < 0.05);
+            assert!((sigma - 0.5).abs() < 0.05);
+        }
+        dist => panic!("Unexpected distribution {:?}", dist),
+    }
+}
+
+#[test]
+fn test_fit_pareto() {
+    let values = sample(5000, 123, |ctx| ctx.sample_pareto(1., 3.));
+    match FittedDistribution::fit(&values) {
+        FittedDistribution::Pareto { scale, shape } => {
+            assert!((scale - 1.).abs() < 0.01);
+            assert!((shape - 3.).abs() < 0.2);
+        }
+        dist => panic!("Unexpected distribution {:?}", dist),
+    }
+}
+
+#[test]
+fn test_fit_weibull() {
+    let values = sample(5000, 123, |ctx| ctx.sample_weibull(2., 3.));
+    match FittedDistribution::fit(&values) {
+        FittedDistribution::Weibull { scale, shape } => {
+            assert!((scale - 2.).abs() < 0.1);
+            assert!((shape - 3.).abs() < 0.2);
+        }
+        dist => panic!("Unexpected distribution {:?}", dist),
+    }
+}
+
+#[test]
+fn test_fit_with_zeros() {
+    let values = vec![0., 1., 0.5, 0., 2.];
+    assert!(matches!(
+        FittedDistribution::fit(&values),
+        FittedDistribution::Exponential { .. }
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Cannot fit distribution to empty sample")]
+fn test_fit_empty() {
+    FittedDistribution::fit(&[]);
+}
+
+#[test]
+#[should_panic(expected = "Cannot fit distribution to sample with negative values")]
+fn test_fit_negative() {
+    FittedDistribution::fit(&[1., -1.]);
+}
+
+#[test]
+fn test_mean() {
+    assert_eq!(FittedDistribution::Constant(3.).mean(), 3.);
+    assert_eq!(FittedDistribution::Exponential { rate: 4. }.mean(), 0.25);
+    assert_eq!(FittedDistribution::Pareto { scale: 1., shape: 2. }.mean(), 2.);
+    assert_eq!(
+        FittedDistribution::Pareto { scale: 1., shape: 1. }.mean(),
+        f64::INFINITY
+    );
+    assert!((FittedDistribution::Weibull { scale: 2., shape: 1. }.mean() - 2.).abs() < 1e-9);
+    assert_eq!(FittedDistribution::Empirical(vec![1., 2., 6.]).mean(), 3.);
+}
+
+#[test]
+fn test_record_and_fit() {
+    let mut sim = Simulation::new(123);
+    let server_id = sim.create_context("server").id();
+    generate(&mut sim, server_id, 3000);
+    let recorder = Rc::new(RefCell::new(
+        WorkloadRecorder::new::<Request>().with_size(|request: &Request| request.size),
+    ));
+    sim.add_time_advance_listener(recorder.clone());
+    sim.step_until_no_events();
+
+    let workload = recorder.borrow().workload().clone();
+    assert_eq!(workload.arrival_count(), 3000);
+    let model = workload.fit();
+    assert!(matches!(model.interarrival, FittedDistribution::Exponential { .. }));
+    assert!(matches!(model.size, Some(FittedDistribution::Weibull { .. })));
+    assert!((model.mean_interarrival() - 0.25).abs() < 0.02);
+    assert!((model.mean_size() - 2. * 0.9027).abs() < 0.1);
+}
+
+#[test]
+fn test_workload_from_trace() {
+    let path = std::env::temp_dir().join(format!("simcore-workload-{}.jsonl", std::process::id()));
+    let mut sim = Simulation::new(123);
+    let server_id = sim.create_context("server").id();
+    generate(&mut sim, server_id, 100);
+    let recorder = Rc::new(RefCell::new(
+        WorkloadRecorder::new::<Request>().with_size(|request: &Request| request.size),
+    ));
+    sim.add_time_advance_listener(recorder.clone());
+    sim.enable_trace_file(TraceFileConfig::new(&path));
+    sim.step_until_no_events();
+    sim.disable_trace_file();
+
+    let trace = read_trace_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    let workload = Workload::from_trace(&trace, "Request", Some("server"), Some("/size"));
+    assert_eq!(&workload, recorder.borrow().workload());
+    assert!(Workload::from_trace(&trace, "Request", Some("generator"), None)
+        .interarrivals
+        .is_empty());
+}
+
+#[test]
+fn test_save_and_load() {
+    let path = std::env::temp_dir().join(format!("simcore-workload-{}.json", std::process::id()));
+    let workload = Workload::from_arrivals(&[(0., Some(1.)), (0.5, Some(2.)), (2., Some(3.))]);
+    workload.save(&path);
+    let loaded = Workload::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, workload);
+}
+
+#[test]
+fn test_empirical() {
+    let workload = Workload::from_arrivals(&[(0., Some(1.)), (1., Some(2.)), (3., Some(3.))]);
+    let model = workload.empirical();
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("sampler");
+    for _ in 0..100 {
+        assert!([1., 2.].contains(&model.sample_interarrival(&ctx)));
+        assert!([1., 2., 3.].contains(&model.sample_size(&ctx)));
+    }
+}
+
+#[test]
+fn test_scaling() {
+    let model = WorkloadModel::new(
+        FittedDistribution::Constant(2.),
+        Some(FittedDistribution::Constant(10.)),
+    )
+    .scale_rate(4.)
+    .scale_size(0.5);
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("sampler");
+    assert_eq!(model.sample_interarrival(&ctx), 0.5);
+    assert_eq!(model.sample_size(&ctx), 5.);
+    assert_eq!(model.mean_interarrival(), 0.5);
+    assert_eq!(model.mean_size(), 5.);
+}
+
+#[test]
+#[should_panic(expected = "Rate scale factor must be positive, got 0")]
+fn test_scale_rate_zero() {
+    WorkloadModel::new(FittedDistribution::Constant(1.), None).scale_rate(0.);
+}
+
+#[test]
+#[should_panic(expected = "Workload model has no size distribution")]
+fn test_sample_size_without_sizes() {
+    let mut sim = Simulation::new(123);
+    let ctx = sim.create_context("sampler");
+    WorkloadModel::new(FittedDistribution::Constant(1.), None).sample_size(&ctx);
+}
+
+#[test]
+#[should_panic(expected = "Workload must contain at least two arrivals")]
+fn test_fit_single_arrival() {
+    Workload::from_arrivals(&[(1., Some(1.))]).fit();
+}
+
+#[test]
+fn test_synthesize() {
+    let run = |seed: u64| {
+        let model = WorkloadModel::new(FittedDistribution::Exponential { rate: 2. }, None).scale_rate(5.);
+        let mut sim = Simulation::new(seed);
+        let server_id = sim.create_context("server").id();
+        let generator = ArrivalGenerator::new(sim.create_context("generator"), |_, _| Request { size: 1. })
+            .with_workload(model)
+            .with_stop_time(100.)
+            .with_targets(TargetPolicy::Single(server_id));
+        let generator = Rc::new(RefCell::new(generator));
+        sim.add_handler("generator", generator.clone());
+        generator.borrow_mut().start();
+        sim.step_until_no_events();
+        let count = generator.borrow().arrival_count();
+        count
+    };
+    let count = run(123);
+    assert!((count as f64 - 1000.).abs() < 100.);
+    assert_eq!(run(123), count);
+}